@@ -0,0 +1,551 @@
+//! Headless command-line interface, for CI pipelines and scripts.
+//!
+//! When the first argument is one of the commands below, the binary runs it
+//! against a data directory and exits instead of starting the GUI:
+//!
+//! ```text
+//! dependency-mapping-tool validate --data-path ./service-data --env prod --fail-on error
+//! dependency-mapping-tool export --data-path ./service-data --env prod --format mermaid
+//! dependency-mapping-tool stats --data-path ./service-data --env prod --format json
+//! dependency-mapping-tool manifest --data-path ./service-data --env prod --all --output manifests
+//! ```
+//!
+//! Commands call the same functions as the GUI commands (`run_validation`,
+//! `export_environment_graph`, `environment_counts`, `deployment_manifest`),
+//! so a data directory validates, exports and counts exactly the same from
+//! both. The exit code
+//! is `0` on success, `1` if validation exceeded the fail threshold and `2`
+//! for usage errors or data that couldn't be read.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use crate::commands::environments;
+use crate::commands::graph;
+use crate::commands::validation::{self, FailThreshold, ReportFormat};
+use crate::dates;
+use crate::error::AppError;
+use crate::export::GraphExportFormat;
+use crate::manifest::ManifestFormat;
+use crate::state::AppState;
+use crate::storage::encryption;
+
+/// Exit code for a successful command.
+const EXIT_OK: i32 = 0;
+
+/// Exit code for a validation that exceeded the fail threshold.
+const EXIT_FAILED: i32 = 1;
+
+/// Exit code for usage errors and data that couldn't be read.
+const EXIT_ERROR: i32 = 2;
+
+/// Help text printed by `help` and after usage errors.
+const USAGE: &str = "\
+Usage: dependency-mapping-tool <command> --data-path <path> --env <environment> [options]
+
+Commands:
+  validate    Validate an environment and print the report
+  export      Print the environment's dependency graph
+  stats       Print the environment's service and relationship counts
+  manifest    Print a service's deployment manifest (what must be healthy first)
+  help        Print this help
+
+Options:
+  --data-path <path>       Data directory or SQLite database (required)
+  --env <environment>      Environment to use (required)
+  --passphrase-env <var>   Environment variable holding the encryption passphrase
+  --output <file>          Write to a file instead of stdout
+  --format <format>        validate: text (default) or json
+                           export: json (default), dot or mermaid
+                           stats: text (default) or json
+                           manifest: yaml (default) or json
+
+Validation options:
+  --fail-on <severity>     error (default), warning or never
+  --max-errors <n>         Errors allowed before failing (overrides --fail-on)
+  --max-warnings <n>       Warnings allowed before failing (overrides --fail-on)
+
+Manifest options:
+  --service <id>           Service to deploy (required unless --all)
+  --all                    Write one manifest per service to the --output directory
+  --include-data-flow      Also follow reads_from and writes_to relationships
+";
+
+/// Names `run` runs as commands; any other first argument starts the GUI.
+const COMMANDS: &[&str] = &[
+    "validate", "export", "stats", "manifest", "help", "--help", "-h",
+];
+
+/// Options every command accepts.
+const COMMON_OPTIONS: &[&str] = &["data-path", "env", "passphrase-env", "output", "format"];
+
+/// Options only `validate` accepts.
+const VALIDATE_OPTIONS: &[&str] = &["fail-on", "max-errors", "max-warnings"];
+
+/// Options only `manifest` accepts.
+const MANIFEST_OPTIONS: &[&str] = &["service"];
+
+/// Options without a value only `manifest` accepts.
+const MANIFEST_FLAGS: &[&str] = &["all", "include-data-flow"];
+
+/// Returns true if the arguments name a command, so `run` won't start the GUI.
+///
+/// Lets `main` attach to the terminal before a command prints anything.
+///
+/// # Arguments
+///
+/// * `args` - The arguments after the program name
+pub fn is_command(args: &[String]) -> bool {
+    args.first()
+        .is_some_and(|command| COMMANDS.contains(&command.as_str()))
+}
+
+/// Runs a command-line command, if the arguments name one.
+///
+/// # Arguments
+///
+/// * `args` - The arguments after the program name
+///
+/// # Returns
+///
+/// * `Some(code)` - The exit code, if the first argument was a command
+/// * `None` - If there is no command, so the GUI should start (this includes
+///   arguments the operating system passes to GUI launches)
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first()?.as_str();
+    let result = match command {
+        "validate" => parse_options(&args[1..], VALIDATE_OPTIONS, &[]).and_then(|o| validate(&o)),
+        "export" => parse_options(&args[1..], &[], &[]).and_then(|o| export(&o)),
+        "stats" => parse_options(&args[1..], &[], &[]).and_then(|o| stats(&o)),
+        "manifest" => {
+            parse_options(&args[1..], MANIFEST_OPTIONS, MANIFEST_FLAGS).and_then(|o| manifest(&o))
+        }
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            Ok(EXIT_OK)
+        }
+        _ => return None,
+    };
+
+    Some(result.unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        if matches!(e, CliError::Usage(_)) {
+            eprint!("\n{}", USAGE);
+        }
+        EXIT_ERROR
+    }))
+}
+
+/// Why a command couldn't run.
+enum CliError {
+    /// The arguments were invalid.
+    Usage(String),
+    /// The command failed.
+    App(AppError),
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}", message),
+            CliError::App(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<AppError> for CliError {
+    fn from(e: AppError) -> Self {
+        CliError::App(e)
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(e: std::io::Error) -> Self {
+        CliError::App(AppError::Io(e))
+    }
+}
+
+/// Parsed `--name value` options and `--name` flags of a command.
+struct Options {
+    values: HashMap<String, String>,
+    flags: HashSet<String>,
+}
+
+impl Options {
+    /// Returns whether a flag was given.
+    fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    /// Returns an option's value, if it was given.
+    fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Returns an option's value, failing if it wasn't given.
+    fn require(&self, name: &str) -> Result<&str, CliError> {
+        self.get(name)
+            .ok_or_else(|| CliError::Usage(format!("Missing required option --{}", name)))
+    }
+
+    /// Parses an option's value as a count.
+    fn count(&self, name: &str) -> Result<Option<usize>, CliError> {
+        self.get(name)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    CliError::Usage(format!("--{} must be a number, got '{}'", name, value))
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Parses `--name value` and `--name=value` arguments, and `--name` flags.
+///
+/// Accepts the common options, `extra` and the `flags`; anything else is a
+/// usage error.
+fn parse_options(args: &[String], extra: &[&str], flags: &[&str]) -> Result<Options, CliError> {
+    let mut values = HashMap::new();
+    let mut given_flags = HashSet::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let Some(option) = arg.strip_prefix("--") else {
+            return Err(CliError::Usage(format!("Unexpected argument '{}'", arg)));
+        };
+        if flags.contains(&option) {
+            given_flags.insert(option.to_string());
+            continue;
+        }
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name, value.to_string()),
+            None => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| CliError::Usage(format!("Missing value for --{}", option)))?;
+                (option, value.clone())
+            }
+        };
+        if !COMMON_OPTIONS.contains(&name) && !extra.contains(&name) {
+            return Err(CliError::Usage(format!("Unknown option --{}", name)));
+        }
+        values.insert(name.to_string(), value);
+    }
+
+    Ok(Options {
+        values,
+        flags: given_flags,
+    })
+}
+
+/// Opens the data path and checks the environment exists.
+///
+/// Unlocks encrypted data with the passphrase from `--passphrase-env`.
+fn open_state(options: &Options) -> Result<(RwLock<AppState>, String), CliError> {
+    let data_path = PathBuf::from(options.require("data-path")?);
+    let environment = options.require("env")?.to_string();
+    if !data_path.exists() {
+        return Err(CliError::Usage(format!(
+            "Data path '{}' does not exist",
+            data_path.display()
+        )));
+    }
+
+    let mut state = AppState::new(data_path);
+    if let Some(variable) = options.get("passphrase-env") {
+        let passphrase = std::env::var(variable).map_err(|_| {
+            CliError::Usage(format!("Environment variable {} is not set", variable))
+        })?;
+        let key = encryption::unlock(&state.data_path, &passphrase)?;
+        state.set_encryption_key(Some(key));
+    }
+    if !state.storage.environment_exists(&environment)? {
+        return Err(AppError::EnvironmentNotFound(environment).into());
+    }
+
+    Ok((RwLock::new(state), environment))
+}
+
+/// Prints output to stdout, or writes it to `--output`.
+fn write_output(options: &Options, content: &str) -> Result<(), CliError> {
+    match options.get("output") {
+        Some(path) => fs::write(path, content)?,
+        None if content.ends_with('\n') => print!("{}", content),
+        None => println!("{}", content),
+    }
+    Ok(())
+}
+
+/// Runs `validate`: prints the validation report and fails past the threshold.
+fn validate(options: &Options) -> Result<i32, CliError> {
+    let format = match options.get("format").unwrap_or("text") {
+        "text" | "markdown" => ReportFormat::Markdown,
+        other => ReportFormat::parse(other)
+            .ok_or_else(|| CliError::Usage(format!("Unknown validate format '{}'", other)))?,
+    };
+
+    let mut threshold = match options.get("fail-on").unwrap_or("error") {
+        "error" => FailThreshold {
+            max_errors: Some(0),
+            max_warnings: None,
+        },
+        "warning" => FailThreshold {
+            max_errors: Some(0),
+            max_warnings: Some(0),
+        },
+        "never" => FailThreshold::default(),
+        other => {
+            return Err(CliError::Usage(format!(
+                "--fail-on must be error, warning or never, got '{}'",
+                other
+            )))
+        }
+    };
+    if let Some(max_errors) = options.count("max-errors")? {
+        threshold.max_errors = Some(max_errors);
+    }
+    if let Some(max_warnings) = options.count("max-warnings")? {
+        threshold.max_warnings = Some(max_warnings);
+    }
+
+    let (state, environment) = open_state(options)?;
+    let result = validation::run_validation(&state, &environment)?;
+    let flagged = validation::flagged_dependencies(&state, &environment)?;
+    let report = validation::render_validation_report(
+        &environment,
+        &result,
+        &flagged,
+        format,
+        Some(threshold),
+    )?;
+    write_output(options, &report)?;
+
+    Ok(if threshold.passed(&result) {
+        EXIT_OK
+    } else {
+        EXIT_FAILED
+    })
+}
+
+/// Runs `export`: prints the environment's graph.
+fn export(options: &Options) -> Result<i32, CliError> {
+    let format_name = options.get("format").unwrap_or("json");
+    let format = GraphExportFormat::parse(format_name)
+        .ok_or_else(|| CliError::Usage(format!("Unknown export format '{}'", format_name)))?;
+
+    let (state, environment) = open_state(options)?;
+    let today = dates::today();
+    let graph =
+        graph::export_environment_graph(&state, &environment, format, false, Some(&today), false)?;
+    write_output(options, &graph.content)?;
+
+    Ok(EXIT_OK)
+}
+
+/// Runs `manifest`: prints a service's deployment manifest, or with `--all`
+/// writes every service's to the `--output` directory.
+fn manifest(options: &Options) -> Result<i32, CliError> {
+    let format_name = options.get("format").unwrap_or("yaml");
+    let format = ManifestFormat::parse(format_name)
+        .ok_or_else(|| CliError::Usage(format!("Unknown manifest format '{}'", format_name)))?;
+    let include_data_flow = options.flag("include-data-flow");
+
+    if options.flag("all") {
+        if options.get("service").is_some() {
+            return Err(CliError::Usage(
+                "--service and --all can't be used together".to_string(),
+            ));
+        }
+        let output_dir = options.get("output").ok_or_else(|| {
+            CliError::Usage("--all needs an --output directory to write to".to_string())
+        })?;
+        let (state, environment) = open_state(options)?;
+        let written = graph::write_deployment_manifests(
+            &state,
+            &environment,
+            output_dir,
+            format,
+            include_data_flow,
+        )?;
+        println!("Wrote {} manifests to {}", written.len(), output_dir);
+        return Ok(EXIT_OK);
+    }
+
+    let service_id = options.require("service")?;
+    let (state, environment) = open_state(options)?;
+    let content =
+        graph::deployment_manifest(&state, &environment, service_id, format, include_data_flow)?;
+    write_output(options, &content)?;
+
+    Ok(EXIT_OK)
+}
+
+/// Runs `stats`: prints the environment's counts.
+fn stats(options: &Options) -> Result<i32, CliError> {
+    let format = options.get("format").unwrap_or("text");
+    if format != "text" && format != "json" {
+        return Err(CliError::Usage(format!(
+            "Unknown stats format '{}'",
+            format
+        )));
+    }
+
+    let (state, environment) = open_state(options)?;
+    let counts = environments::environment_counts(&state, &environment)?;
+
+    let content = if format == "json" {
+        serde_json::to_string_pretty(&counts).map_err(AppError::from)?
+    } else {
+        let mut statuses: Vec<_> = counts.status_counts.iter().collect();
+        statuses.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+        let mut text = format!(
+            "Environment: {}\nServices: {}\nRelationships: {}\n",
+            environment, counts.service_count, counts.relationship_count
+        );
+        for (status, count) in statuses {
+            text.push_str(&format!("  {}: {}\n", status.as_str(), count));
+        }
+        text
+    };
+    write_output(options, &content)?;
+
+    Ok(EXIT_OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{relationship, service, TestApp};
+    use tempfile::TempDir;
+
+    /// Runs a command against the app's data directory and returns its exit
+    /// code and what it wrote to the `--output` file.
+    fn run_in(app: &TestApp, command: &str, extra: &[&str]) -> (Option<i32>, String) {
+        let output_dir = TempDir::new().unwrap();
+        let output = output_dir.path().join("out");
+        let mut args: Vec<String> = vec![
+            command.to_string(),
+            "--data-path".to_string(),
+            app.data_path().display().to_string(),
+            "--env".to_string(),
+            "dev".to_string(),
+            format!("--output={}", output.display()),
+        ];
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        let code = run(&args);
+        (code, fs::read_to_string(output).unwrap_or_default())
+    }
+
+    fn orphaned_app() -> TestApp {
+        TestApp::with_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[
+                relationship("api-db", "api", "db"),
+                relationship("api-ghost", "api", "ghost"),
+            ],
+        )
+    }
+
+    #[test]
+    fn validate_exits_non_zero_past_the_threshold() {
+        let clean = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[relationship("api-db", "api", "db")],
+        );
+        assert_eq!(run_in(&clean, "validate", &[]).0, Some(EXIT_OK));
+
+        let app = orphaned_app();
+        assert_eq!(run_in(&app, "validate", &[]).0, Some(EXIT_FAILED));
+        assert_eq!(
+            run_in(&app, "validate", &["--fail-on", "never"]).0,
+            Some(EXIT_OK)
+        );
+        assert_eq!(
+            run_in(&app, "validate", &["--max-errors", "1"]).0,
+            Some(EXIT_OK)
+        );
+
+        let (code, report) = run_in(&app, "validate", &["--format", "json"]);
+        assert_eq!(code, Some(EXIT_FAILED));
+        let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert!(report.to_string().contains("api-ghost"), "{report}");
+    }
+
+    #[test]
+    fn validate_reports_match_the_gui_validation() {
+        let app = orphaned_app();
+        let (_, report) = run_in(&app, "validate", &[]);
+
+        let result = validation::run_validation(&app.state(), "dev").unwrap();
+        assert!(!result.issues.is_empty());
+        for issue in &result.issues {
+            assert!(report.contains(&issue.message), "{report}");
+        }
+    }
+
+    #[test]
+    fn export_and_stats_print_the_environment() {
+        let app = orphaned_app();
+
+        let (code, dot) = run_in(&app, "export", &["--format", "dot"]);
+        assert_eq!(code, Some(EXIT_OK));
+        assert!(dot.contains("digraph"), "{dot}");
+        assert!(dot.contains("api") && dot.contains("db"));
+        let (_, mermaid) = run_in(&app, "export", &["--format", "mermaid"]);
+        assert!(mermaid.starts_with("flowchart LR"), "{mermaid}");
+
+        let (code, stats) = run_in(&app, "stats", &["--format", "json"]);
+        assert_eq!(code, Some(EXIT_OK));
+        let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+        assert_eq!(stats["serviceCount"], 2);
+        assert_eq!(stats["relationshipCount"], 2);
+        let (_, text) = run_in(&app, "stats", &[]);
+        assert!(text.contains("Services: 2"), "{text}");
+    }
+
+    #[test]
+    fn bad_usage_and_missing_data_exit_with_an_error() {
+        let app = orphaned_app();
+
+        assert_eq!(
+            run_in(&app, "validate", &["--bogus", "1"]).0,
+            Some(EXIT_ERROR)
+        );
+        assert_eq!(
+            run_in(&app, "validate", &["--fail-on", "sometimes"]).0,
+            Some(EXIT_ERROR)
+        );
+        assert_eq!(
+            run_in(&app, "export", &["--format", "png"]).0,
+            Some(EXIT_ERROR)
+        );
+        let args = [
+            "stats",
+            "--data-path",
+            &app.data_path().display().to_string(),
+            "--env",
+            "prod",
+        ]
+        .map(str::to_string);
+        assert_eq!(run(&args), Some(EXIT_ERROR));
+        // Anything else starts the GUI
+        assert_eq!(run(&[]), None);
+        assert_eq!(run(&["--some-gui-flag".to_string()]), None);
+        assert!(!is_command(&[]));
+        assert!(!is_command(&["--some-gui-flag".to_string()]));
+    }
+
+    #[test]
+    fn every_command_is_recognized_before_it_runs() {
+        for command in COMMANDS {
+            let args = [command.to_string(), "--bogus".to_string()];
+            assert!(is_command(&args), "{command}");
+            assert!(run(&args).is_some(), "{command} started the GUI");
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Graph annotation commands for the Tauri application.
+//!
+//! Node positions and sticky notes of the graph view (see
+//! `models::NodeAnnotation`) are stored with the data instead of in the
+//! browser, so they are the same on every machine. Saves are merged node by
+//! node, so views editing different nodes don't overwrite each other.
+//! Annotations of deleted services are reported by validation as
+//! `orphaned_annotation` issues and pruned by `fix_validation_issues`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::error::AppError;
+use crate::git;
+use crate::models::NodeAnnotation;
+use crate::state::{read_state, write_state, AppState};
+use crate::storage;
+
+/// Retrieves the graph annotations of an environment.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(BTreeMap<String, NodeAnnotation>)` - Annotations by service ID
+/// * `Err(AppError::FileLoad)` - If the annotations file cannot be parsed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const annotations = await invoke('get_graph_annotations', { environment: 'prod' });
+/// ```
+#[tauri::command]
+pub fn get_graph_annotations(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<BTreeMap<String, NodeAnnotation>, AppError> {
+    let state = read_state(&state);
+    storage::load_annotations(&state.data_path, &environment)
+}
+
+/// Merges annotations into the stored ones.
+///
+/// Each given service's annotation replaces its stored one; a `null`
+/// annotation deletes it. Annotations of services that aren't given are
+/// kept.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+/// * `annotations` - Annotations (or `null` to delete) by service ID
+///
+/// # Returns
+///
+/// * `Ok(BTreeMap<String, NodeAnnotation>)` - All annotations after the merge
+/// * `Err(AppError::FileLoad)` - If the stored annotations cannot be parsed
+/// * `Err(AppError::Io)` - If the annotations file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `{environment}/.annotations.json` in the data directory
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend, after dragging a node and clearing another's note:
+/// await invoke('save_graph_annotations', {
+///     environment: 'prod',
+///     annotations: {
+///         'billing-api': { x: 120, y: 40, pinnedPosition: true, note: 'Moving to v2' },
+///         'legacy-cron': null
+///     }
+/// });
+/// ```
+#[tauri::command]
+pub fn save_graph_annotations<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    annotations: HashMap<String, Option<NodeAnnotation>>,
+) -> Result<BTreeMap<String, NodeAnnotation>, AppError> {
+    let state = write_state(&state);
+    let mut stored = storage::load_annotations(&state.data_path, &environment)?;
+
+    let mut changed = 0;
+    for (service_id, annotation) in annotations {
+        let is_change = match annotation {
+            Some(annotation) => stored.insert(service_id, annotation.clone()) != Some(annotation),
+            None => stored.remove(&service_id).is_some(),
+        };
+        if is_change {
+            changed += 1;
+        }
+    }
+    if changed == 0 {
+        return Ok(stored);
+    }
+
+    storage::save_annotations(&state.data_path, &environment, &stored)?;
+    git::auto_commit(
+        &app,
+        &state,
+        "save_graph_annotations",
+        format!("Update {} graph annotations in {}", changed, environment),
+        vec![storage::annotations::annotations_path(
+            &state.data_path,
+            &environment,
+        )?],
+    );
+
+    Ok(stored)
+}
@@ -0,0 +1,66 @@
+//! Audit log commands for the Tauri application.
+//!
+//! Mutating commands append to each environment's audit log as they go
+//! (see the `storage::audit` module for the format and rotation). This
+//! module lets the frontend page through it.
+
+use std::sync::RwLock;
+use tauri::State;
+
+use crate::error::AppError;
+use crate::state::{read_state, AppState};
+use crate::storage::audit::{self, AuditLogPage};
+
+/// Number of records returned when the caller doesn't pass a limit.
+const DEFAULT_AUDIT_PAGE_SIZE: usize = 100;
+
+/// Retrieves a page of an environment's audit log, newest first.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment whose log to read
+/// * `limit` - Maximum number of records to return (default 100)
+/// * `offset` - Number of matching records to skip, counting from the newest (default 0)
+/// * `filter_by_service` - Only return records that touched this service, including
+///   changes to its relationships
+///
+/// # Returns
+///
+/// * `Ok(AuditLogPage)` - The records and the total number matching the filter
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::Io)` - If the audit log exists but cannot be read
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const page = await invoke('get_audit_log', {
+///     environment: 'prod',
+///     limit: 50,
+///     offset: 0,
+///     filterByService: 'user-api'
+/// });
+/// for (const record of page.entries) {
+///     console.log(new Date(record.timestamp), record.user, record.command, record.after);
+/// }
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_audit_log(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    filter_by_service: Option<String>,
+) -> Result<AuditLogPage, AppError> {
+    // Hold the read lock so a concurrent append can't rotate the log mid-read
+    let state = read_state(&state);
+
+    audit::read_records(
+        &state.data_path,
+        &environment,
+        limit.unwrap_or(DEFAULT_AUDIT_PAGE_SIZE),
+        offset.unwrap_or(0),
+        filter_by_service.as_deref(),
+    )
+}
@@ -0,0 +1,991 @@
+//! Batch command for the Tauri application.
+//!
+//! Complex UI actions (e.g., renaming a service, which means saving the new
+//! service, deleting the old one and rewriting its relationships) are sent
+//! as one batch of operations. The batch is validated and applied to
+//! in-memory copies of the affected data first; files are only written once
+//! every operation has succeeded, so a failing operation leaves the disk
+//! untouched.
+//!
+//! The same split into computing a `Plan` and applying it backs the dry-run
+//! mode of the destructive commands.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Runtime};
+
+use crate::commands::relationships;
+use crate::descriptions;
+use crate::error::AppError;
+use crate::git;
+use crate::lifecycle;
+use crate::metadata_schema;
+use crate::models::{Relationship, RelationshipDescriptionSettings, Service, WebhookEvent};
+use crate::naming;
+use crate::state::diagnostics::CommandTimer;
+use crate::state::journal::{self, Change, Direction, JournalEntry};
+use crate::state::progress::Progress;
+use crate::state::{read_state, run_blocking, write_state, AppState};
+use crate::storage::{self, StorageBackend};
+use crate::webhooks;
+
+/// One operation of a batch.
+///
+/// Each variant behaves like the command of the same name.
+///
+/// # Variants
+///
+/// * `SaveService` - Create or update a service (see `save_service`)
+/// * `DeleteService` - Delete a service (see `delete_service`)
+/// * `SaveRelationship` - Create or update a relationship (see `save_relationship`)
+/// * `DeleteRelationship` - Delete a relationship (see `delete_relationship`)
+/// * `DeleteRelationshipsForService` - Delete every relationship of a service
+///   (see `delete_relationships_for_service`)
+///
+/// # Serialization
+///
+/// Operations are tagged with an `op` field holding the snake_case variant
+/// name; other fields are camelCase (e.g., `{ "op": "delete_service",
+/// "serviceId": "old-api" }`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum Operation {
+    SaveService {
+        service: Service,
+        #[serde(default)]
+        force: bool,
+    },
+    DeleteService {
+        service_id: String,
+    },
+    SaveRelationship {
+        relationship: Relationship,
+        #[serde(default)]
+        force: bool,
+    },
+    DeleteRelationship {
+        relationship_id: String,
+    },
+    DeleteRelationshipsForService {
+        service_id: String,
+    },
+}
+
+/// The outcome of a batch.
+///
+/// # Fields
+///
+/// * `saved_services` - Services that exist after the batch, with their new revisions
+/// * `deleted_services` - IDs of services the batch deleted
+/// * `saved_relationships` - Relationships the batch saved, with their new revisions
+/// * `deleted_relationships` - IDs of relationships the batch deleted
+/// * `dry_run` - True if the changes were only computed, not written
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult {
+    pub saved_services: Vec<Service>,
+    pub deleted_services: Vec<String>,
+    pub saved_relationships: Vec<Relationship>,
+    pub deleted_relationships: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// The changes a list of operations would make, computed without writing.
+///
+/// Destructive commands compute a plan first and then either return its
+/// result (dry run) or apply it, so a preview always matches what the real
+/// run does.
+///
+/// # Fields
+///
+/// * `changes` - The journal changes to write, with before and after states
+/// * `result` - What the changes save and delete
+pub struct Plan {
+    pub changes: Vec<Change>,
+    pub result: BatchResult,
+}
+
+impl Plan {
+    /// Computes the plan for a list of operations.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Plan)` - The changes (empty if the operations change nothing)
+    /// * `Err(AppError::BatchOperationFailed)` - If an operation failed, with its index
+    pub fn compute(
+        state: &AppState,
+        environment: &str,
+        operations: Vec<Operation>,
+    ) -> Result<Self, AppError> {
+        Self::compute_with_progress(state, environment, operations, &mut Progress::none())
+    }
+
+    /// Computes the plan, reporting each operation and stopping if cancelled.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Plan)` - The changes (empty if the operations change nothing)
+    /// * `Err(AppError::BatchOperationFailed)` - If an operation failed, with its index
+    /// * `Err(AppError::OperationCancelled)` - If the operation was cancelled
+    pub fn compute_with_progress(
+        state: &AppState,
+        environment: &str,
+        operations: Vec<Operation>,
+        progress: &mut Progress,
+    ) -> Result<Self, AppError> {
+        let mut batch = Batch::new(state, environment);
+        progress.phase("Applying operations", operations.len());
+        for (index, operation) in operations.into_iter().enumerate() {
+            progress.check_cancelled()?;
+            batch
+                .apply(operation)
+                .map_err(|e| AppError::BatchOperationFailed {
+                    index,
+                    source: Box::new(e),
+                })?;
+            progress.advance();
+        }
+
+        Ok(batch.into_plan())
+    }
+
+    /// Computes the plan for a single operation, returning its error unwrapped.
+    pub fn for_operation(
+        state: &AppState,
+        environment: &str,
+        operation: Operation,
+    ) -> Result<Self, AppError> {
+        let mut batch = Batch::new(state, environment);
+        batch.apply(operation)?;
+
+        Ok(batch.into_plan())
+    }
+
+    /// Writes the plan and records it like any other change.
+    ///
+    /// Does nothing if the plan has no changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - The application handle, used to queue git auto-commits
+    /// * `state` - The application state, locked for writing
+    /// * `environment` - The environment the plan was computed for
+    /// * `command` - The command applying the plan, for the audit log and git
+    /// * `description` - Describes the change in the undo history and git commit
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(BatchResult)` - The plan's result
+    /// * `Err(AppError::Io)` - If writing failed; files already written are restored
+    pub fn apply<R: Runtime>(
+        self,
+        app: &AppHandle<R>,
+        state: &mut AppState,
+        environment: &str,
+        command: &'static str,
+        description: String,
+    ) -> Result<BatchResult, AppError> {
+        self.apply_with_progress(
+            app,
+            state,
+            environment,
+            command,
+            description,
+            &mut Progress::none(),
+        )
+    }
+
+    /// Writes the plan like `apply`, reporting each file written.
+    pub fn apply_with_progress<R: Runtime>(
+        self,
+        app: &AppHandle<R>,
+        state: &mut AppState,
+        environment: &str,
+        command: &'static str,
+        description: String,
+        progress: &mut Progress,
+    ) -> Result<BatchResult, AppError> {
+        if !self.changes.is_empty() {
+            let entry = JournalEntry::new(description, self.changes);
+            apply_entry(app, state, environment, command, entry, progress)?;
+        }
+
+        Ok(self.result)
+    }
+}
+
+/// Applies a list of operations to an environment as a single unit.
+///
+/// Operations run in order against in-memory copies of the services they
+/// touch and of the relationships, and each one sees the effects of the
+/// ones before it (two saves of the same service need consecutive
+/// revisions). Only when every operation succeeded are the changed files
+/// written. If writing fails part way (e.g., the disk is full), the files
+/// already written are restored from their original contents.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment to change
+/// * `operations` - The operations to apply, in order
+/// * `dry_run` - If true, only compute what the batch would change (default: false)
+/// * `operation_id` - ID for the `operation:progress` events and `cancel_operation`
+///
+/// # Returns
+///
+/// * `Ok(BatchResult)` - What the batch saved and deleted (or would have)
+/// * `Err(AppError::BatchOperationFailed)` - If an operation failed; contains its
+///   index and the error the equivalent single command would have returned
+///   (including strict naming rule and metadata schema violations, and
+///   decommission dates, successors or effective dates the single commands
+///   would reject). Nothing was written.
+/// * `Err(AppError::OperationCancelled)` - If cancelled before writing; nothing was
+///   written
+/// * `Err(AppError::Io)` - If writing the results failed
+///
+/// # Side Effects
+///
+/// - Emits `operation:progress` events while applying operations and writing files
+///
+/// Unless `dry_run` is set:
+/// - Writes the changed service files and `relationships.json`
+/// - Updates the environment's caches from the final in-memory state
+/// - Records the whole batch as a single entry in the undo journal
+/// - Appends one audit log record per changed service and relationship
+/// - Commits all written files together to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: rename a service and move its relationship
+/// await invoke('execute_batch', {
+///     environment: 'dev',
+///     operations: [
+///         { op: 'save_service', service: { ...oldService, id: 'user-api', revision: 0 } },
+///         { op: 'delete_service', serviceId: 'users' },
+///         { op: 'save_relationship', relationship: { ...rel, source: 'user-api' } }
+///     ],
+///     dryRun: false,
+///     operationId: crypto.randomUUID()
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn execute_batch<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    operations: Vec<Operation>,
+    dry_run: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<BatchResult, AppError> {
+    storage::validate_environment_name(&environment)?;
+
+    run_blocking(app.clone(), move |state| {
+        let _timer = CommandTimer::start(state, "execute_batch");
+        let mut progress = Progress::start(&app, state, operation_id, "execute_batch");
+        let result = run_batch(
+            &app,
+            state,
+            &environment,
+            operations,
+            dry_run.unwrap_or(false),
+            &mut progress,
+        );
+        progress.finish(result)
+    })
+    .await
+}
+
+/// Checks and applies a batch, for `execute_batch`.
+///
+/// The strict rules are checked before the lock is taken, as they read the
+/// validation config and metadata schemas from disk.
+fn run_batch<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    operations: Vec<Operation>,
+    dry_run: bool,
+    progress: &mut Progress,
+) -> Result<BatchResult, AppError> {
+    let data_path = read_state(state).data_path.clone();
+    check_strict_rules(&data_path, &operations)?;
+
+    if dry_run {
+        let state = read_state(state);
+        let mut plan = Plan::compute_with_progress(&state, environment, operations, progress)?;
+        plan.result.dry_run = true;
+        return Ok(plan.result);
+    }
+
+    let mut state = write_state(state);
+    let plan = Plan::compute_with_progress(&state, environment, operations, progress)?;
+    let description = batch_description(&plan.result);
+
+    plan.apply_with_progress(
+        app,
+        &mut state,
+        environment,
+        "execute_batch",
+        description,
+        progress,
+    )
+}
+
+/// Applies `save_service`'s strict naming rules and metadata schemas to the
+/// batch's service saves.
+///
+/// # Returns
+///
+/// * `Ok(())` - If nothing is strict or every saved service conforms
+/// * `Err(AppError::BatchOperationFailed)` - With the index of the first rejected save
+/// * `Err(AppError::FileLoad)` - If the validation config isn't valid JSON
+fn check_strict_rules(data_path: &Path, operations: &[Operation]) -> Result<(), AppError> {
+    let config = storage::load_validation_config(data_path)?;
+    if !config.naming.strict && !config.strict_metadata_schemas {
+        return Ok(());
+    }
+
+    for (index, operation) in operations.iter().enumerate() {
+        if let Operation::SaveService { service, .. } = operation {
+            naming::enforce(&config, service)
+                .and_then(|()| metadata_schema::enforce(data_path, &config, service))
+                .map_err(|e| AppError::BatchOperationFailed {
+                    index,
+                    source: Box::new(e),
+                })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a journal entry's changes and records them.
+///
+/// Shared by every command that applies a set of changes at once. On a write
+/// failure the files already written are restored and the caches cleared.
+/// The writes are reported to `progress`, but not cancelled by it.
+///
+/// # Side Effects
+///
+/// - Writes the changed service files and `relationships.json`
+/// - Updates the environment's caches
+/// - Appends the changes to the audit log under `command`
+/// - Commits the written files to git when the `gitAutoCommit` setting is enabled
+/// - Records the entry in the undo journal
+pub fn apply_entry<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &mut AppState,
+    environment: &str,
+    command: &'static str,
+    entry: JournalEntry,
+    progress: &mut Progress,
+) -> Result<(), AppError> {
+    let storage = Arc::clone(&state.storage);
+    let written = write_changes(
+        storage.as_ref(),
+        &state.data_path,
+        environment,
+        &entry,
+        progress,
+    );
+
+    state.record_write(environment);
+
+    let paths = match written {
+        Ok(paths) => paths,
+        Err(e) => {
+            // Put back whatever was already written
+            if let Err(restore_error) = journal::apply(
+                storage.as_ref(),
+                &state.data_path,
+                environment,
+                &entry,
+                Direction::Undo,
+            ) {
+                eprintln!(
+                    "Warning: failed to restore environment {} after a failed write: {}",
+                    environment, restore_error
+                );
+            }
+            state.clear_environment_cache(environment);
+            return Err(e);
+        }
+    };
+
+    update_caches(state, environment, &entry.changes);
+    state.record_audit(
+        environment,
+        &journal::audit_records(command, &entry.changes, Direction::Redo),
+    );
+
+    let summary = format!("{} in {}", entry.description, environment);
+    git::auto_commit(app, state, command, summary.clone(), paths);
+    for change in &entry.changes {
+        if let Change::Service {
+            id,
+            before: Some(_),
+            after: None,
+        } = change
+        {
+            let summary = format!("Delete service {} in {}", id, environment);
+            webhooks::notify(
+                app,
+                state,
+                environment,
+                WebhookEvent::ServiceDeleted,
+                summary,
+            );
+        }
+    }
+    webhooks::notify(app, state, environment, WebhookEvent::DataChanged, summary);
+    state.record_undo(environment, entry);
+
+    Ok(())
+}
+
+/// A service touched by a batch (`None` = absent).
+struct TouchedService {
+    id: String,
+    /// The stored version.
+    before: Option<Service>,
+    /// The version after the operations applied so far.
+    after: Option<Service>,
+}
+
+/// In-memory copies of the data a batch touches.
+struct Batch<'a> {
+    storage: &'a dyn StorageBackend,
+    descriptions: &'a RelationshipDescriptionSettings,
+    environment: &'a str,
+    /// Touched services in the order they were first touched.
+    services: Vec<TouchedService>,
+    service_index: HashMap<String, usize>,
+    /// Stored and current relationships, loaded when first needed.
+    relationships: Option<(Vec<Relationship>, Vec<Relationship>)>,
+}
+
+impl<'a> Batch<'a> {
+    fn new(state: &'a AppState, environment: &'a str) -> Self {
+        Self {
+            storage: state.storage.as_ref(),
+            descriptions: &state.settings.relationship_descriptions,
+            environment,
+            services: Vec::new(),
+            service_index: HashMap::new(),
+            relationships: None,
+        }
+    }
+
+    /// Returns the current in-memory version of a service, loading it on first use.
+    fn service(&mut self, service_id: &str) -> Result<&mut Option<Service>, AppError> {
+        let index = match self.service_index.get(service_id) {
+            Some(&index) => index,
+            None => {
+                let stored = match self.storage.load_service(self.environment, service_id) {
+                    Ok(service) => Some(service),
+                    Err(AppError::ServiceNotFound(_)) => None,
+                    Err(e) => return Err(e),
+                };
+                self.services.push(TouchedService {
+                    id: service_id.to_string(),
+                    before: stored.clone(),
+                    after: stored,
+                });
+                self.service_index
+                    .insert(service_id.to_string(), self.services.len() - 1);
+                self.services.len() - 1
+            }
+        };
+
+        Ok(&mut self.services[index].after)
+    }
+
+    /// Returns true if a service exists after the operations applied so far.
+    ///
+    /// Unlike `service`, this doesn't mark the service as touched.
+    fn service_exists(&self, service_id: &str) -> Result<bool, AppError> {
+        match self.service_index.get(service_id) {
+            Some(&index) => Ok(self.services[index].after.is_some()),
+            None => self.storage.service_exists(self.environment, service_id),
+        }
+    }
+
+    /// Returns the display name of a service after the operations applied so
+    /// far, or its ID if it doesn't exist.
+    fn service_name(&self, service_id: &str) -> Result<String, AppError> {
+        let service = match self.service_index.get(service_id) {
+            Some(&index) => self.services[index].after.clone(),
+            None => match self.storage.load_service(self.environment, service_id) {
+                Ok(service) => Some(service),
+                Err(AppError::ServiceNotFound(_)) => None,
+                Err(e) => return Err(e),
+            },
+        };
+
+        Ok(service.map_or_else(|| service_id.to_string(), |service| service.name))
+    }
+
+    /// Returns the current in-memory relationships, loading them on first use.
+    fn relationships(&mut self) -> Result<&mut Vec<Relationship>, AppError> {
+        if self.relationships.is_none() {
+            let stored = self.storage.load_relationships(self.environment)?;
+            self.relationships = Some((stored.clone(), stored));
+        }
+
+        Ok(self
+            .relationships
+            .as_mut()
+            .map(|(_, current)| current)
+            .expect("relationships were just loaded"))
+    }
+
+    /// Validates one operation and applies it to the in-memory copies.
+    fn apply(&mut self, operation: Operation) -> Result<(), AppError> {
+        match operation {
+            Operation::SaveService { mut service, force } => {
+                storage::validate_service_id(&service.id)?;
+                lifecycle::enforce_with(&service, |successor_id| {
+                    self.service_exists(successor_id)
+                })?;
+
+                let current = self.service(&service.id)?;
+                let stored_revision = current.as_ref().map_or(0, |s| s.revision);
+                if !force {
+                    AppError::check_revision(&service.id, service.revision, stored_revision)?;
+                }
+                service.revision = stored_revision + 1;
+                *current = Some(service);
+            }
+            Operation::DeleteService { service_id } => {
+                storage::validate_service_id(&service_id)?;
+
+                let current = self.service(&service_id)?;
+                if current.take().is_none() {
+                    return Err(AppError::ServiceNotFound(service_id));
+                }
+            }
+            Operation::SaveRelationship {
+                mut relationship,
+                force,
+            } => {
+                storage::validate_service_id(&relationship.source)?;
+                storage::validate_service_id(&relationship.target)?;
+                relationships::check_effective_dates(&relationship)?;
+
+                let stored = self
+                    .relationships()?
+                    .iter()
+                    .find(|r| r.id == relationship.id)
+                    .cloned();
+                descriptions::describe(
+                    self.descriptions,
+                    &mut relationship,
+                    stored.as_ref(),
+                    |service_id| self.service_name(service_id),
+                )?;
+
+                let relationships = self.relationships()?;
+                let existing = relationships.iter().position(|r| r.id == relationship.id);
+
+                let stored_revision = existing.map_or(0, |idx| relationships[idx].revision);
+                if !force {
+                    AppError::check_revision(
+                        &relationship.id,
+                        relationship.revision,
+                        stored_revision,
+                    )?;
+                }
+                relationship.revision = stored_revision + 1;
+
+                match existing {
+                    Some(idx) => relationships[idx] = relationship,
+                    None => {
+                        let duplicate = relationships.iter().any(|r| {
+                            r.source == relationship.source
+                                && r.target == relationship.target
+                                && r.relationship_type == relationship.relationship_type
+                        });
+                        if duplicate {
+                            return Err(AppError::DuplicateRelationship(
+                                relationship.source,
+                                relationship.target,
+                            ));
+                        }
+                        relationships.push(relationship);
+                    }
+                }
+            }
+            Operation::DeleteRelationship { relationship_id } => {
+                let relationships = self.relationships()?;
+                let original_len = relationships.len();
+                relationships.retain(|r| r.id != relationship_id);
+                if relationships.len() == original_len {
+                    return Err(AppError::RelationshipNotFound(relationship_id));
+                }
+            }
+            Operation::DeleteRelationshipsForService { service_id } => {
+                let relationships = self.relationships()?;
+                relationships.retain(|r| r.source != service_id && r.target != service_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Turns the final in-memory state into a plan.
+    fn into_plan(self) -> Plan {
+        let mut changes = Vec::new();
+        let mut result = BatchResult {
+            saved_services: Vec::new(),
+            deleted_services: Vec::new(),
+            saved_relationships: Vec::new(),
+            deleted_relationships: Vec::new(),
+            dry_run: false,
+        };
+
+        for TouchedService { id, before, after } in self.services {
+            match &after {
+                Some(service) => result.saved_services.push(service.clone()),
+                None if before.is_some() => result.deleted_services.push(id.clone()),
+                // Created and deleted again within the batch
+                None => continue,
+            }
+            changes.push(Change::Service { id, before, after });
+        }
+
+        if let Some((before, after)) = self.relationships {
+            let before_by_id: HashMap<&str, &Relationship> =
+                before.iter().map(|r| (r.id.as_str(), r)).collect();
+            result.saved_relationships = after
+                .iter()
+                .filter(|r| before_by_id.get(r.id.as_str()) != Some(r))
+                .cloned()
+                .collect();
+            let after_ids: HashSet<&str> = after.iter().map(|r| r.id.as_str()).collect();
+            result.deleted_relationships = before
+                .iter()
+                .filter(|r| !after_ids.contains(r.id.as_str()))
+                .map(|r| r.id.clone())
+                .collect();
+
+            let changed =
+                !result.saved_relationships.is_empty() || !result.deleted_relationships.is_empty();
+            if changed {
+                changes.push(Change::Relationships { before, after });
+            }
+        }
+
+        Plan { changes, result }
+    }
+}
+
+/// Writes the after state of a batch's changes, returning the files written.
+fn write_changes(
+    storage: &dyn StorageBackend,
+    data_path: &Path,
+    environment: &str,
+    entry: &JournalEntry,
+    progress: &mut Progress,
+) -> Result<Vec<PathBuf>, AppError> {
+    let mut paths = Vec::new();
+
+    progress.phase("Writing files", entry.changes.len());
+    for change in &entry.changes {
+        paths.extend(write_change(storage, data_path, environment, change)?);
+        progress.advance();
+    }
+
+    Ok(paths)
+}
+
+/// Writes the after state of one change, returning the files written.
+fn write_change(
+    storage: &dyn StorageBackend,
+    data_path: &Path,
+    environment: &str,
+    change: &Change,
+) -> Result<Vec<PathBuf>, AppError> {
+    match change {
+        Change::Service { id, before, after } => {
+            match after {
+                Some(service) => storage.save_service(environment, service)?,
+                None if before.is_some() => storage.delete_service_file(environment, id)?,
+                None => return Ok(Vec::new()),
+            }
+            Ok(storage::service_file_path(data_path, environment, id)
+                .into_iter()
+                .collect())
+        }
+        Change::Relationships { before, after } => {
+            storage.save_relationships(environment, after)?;
+            Ok(storage::relationship_file_paths(
+                data_path,
+                environment,
+                storage::changed_relationship_sources(before, after),
+            ))
+        }
+        Change::Relationship { .. } => Ok(Vec::new()),
+    }
+}
+
+/// Brings the environment's caches in line with a batch that was just written.
+fn update_caches(state: &mut AppState, environment: &str, changes: &[Change]) {
+    for change in changes {
+        match change {
+            Change::Service { id, after, .. } => {
+                state.update_cached_service(environment, id, after.as_ref());
+            }
+            Change::Relationships { after, .. } => {
+                state.cache_relationships(environment, after.clone());
+            }
+            Change::Relationship { .. } => {}
+        }
+    }
+}
+
+/// Describes a batch for the undo history and git commit.
+fn batch_description(result: &BatchResult) -> String {
+    let services = result.saved_services.len() + result.deleted_services.len();
+    let relationships = result.saved_relationships.len() + result.deleted_relationships.len();
+    format!(
+        "Batch update of {} services and {} relationships",
+        services, relationships
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::cache;
+    use crate::test_support::{
+        block_on, relationship, service, snapshot_dir, without_dry_run, TestApp,
+    };
+
+    fn run(app: &TestApp, operations: Vec<Operation>) -> Result<BatchResult, AppError> {
+        run_with(app, operations, false)
+    }
+
+    fn run_with(
+        app: &TestApp,
+        operations: Vec<Operation>,
+        dry_run: bool,
+    ) -> Result<BatchResult, AppError> {
+        block_on(execute_batch(
+            app.handle(),
+            "dev".to_string(),
+            operations,
+            Some(dry_run),
+            None,
+        ))
+    }
+
+    fn save(service: Service) -> Operation {
+        Operation::SaveService {
+            service,
+            force: false,
+        }
+    }
+
+    fn succeeded_by(id: &str, successor_id: &str) -> Service {
+        let mut service = service(id);
+        service.successor_id = Some(successor_id.to_string());
+        service
+    }
+
+    fn assert_rejected_at(result: Result<BatchResult, AppError>, expected_index: usize) {
+        match result {
+            Err(AppError::BatchOperationFailed { index, source }) => {
+                assert_eq!(index, expected_index);
+                assert!(matches!(*source, AppError::ValidationError(_)), "{source}");
+            }
+            other => panic!("expected a rejected operation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn a_failing_third_operation_leaves_disk_and_caches_untouched() {
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[relationship("api-db", "api", "db")],
+        );
+        let cached = cache::services(&app.state(), "dev").unwrap().len();
+        let files = snapshot_dir(app.data_path());
+
+        let result = run(
+            &app,
+            vec![
+                save(service("cache")),
+                Operation::DeleteRelationship {
+                    relationship_id: "api-db".to_string(),
+                },
+                Operation::DeleteService {
+                    service_id: "missing".to_string(),
+                },
+                save(service("web")),
+            ],
+        );
+
+        match result {
+            Err(AppError::BatchOperationFailed { index, .. }) => assert_eq!(index, 2),
+            other => panic!("expected the third operation to fail, got {other:?}"),
+        }
+        assert_eq!(snapshot_dir(app.data_path()), files);
+        assert_eq!(cache::services(&app.state(), "dev").unwrap().len(), cached);
+        assert_eq!(cache::relationships(&app.state(), "dev").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn a_service_cannot_succeed_itself() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        let files = snapshot_dir(app.data_path());
+
+        let result = run(
+            &app,
+            vec![save(service("web")), save(succeeded_by("api", "api"))],
+        );
+
+        assert_rejected_at(result, 1);
+        assert_eq!(snapshot_dir(app.data_path()), files);
+    }
+
+    #[test]
+    fn a_missing_successor_is_rejected() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        let files = snapshot_dir(app.data_path());
+
+        let result = run(&app, vec![save(succeeded_by("api", "api-v2"))]);
+
+        assert_rejected_at(result, 0);
+        assert_eq!(snapshot_dir(app.data_path()), files);
+    }
+
+    #[test]
+    fn the_successor_may_be_created_by_the_same_batch() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+
+        let result = run(
+            &app,
+            vec![save(service("api-v2")), save(succeeded_by("api", "api-v2"))],
+        )
+        .unwrap();
+
+        assert_eq!(result.saved_services.len(), 2);
+    }
+
+    #[test]
+    fn a_successor_deleted_by_the_same_batch_is_rejected() {
+        let app = TestApp::with_environment("dev", &[service("api"), service("api-v2")], &[]);
+
+        let result = run(
+            &app,
+            vec![
+                Operation::DeleteService {
+                    service_id: "api-v2".to_string(),
+                },
+                save(succeeded_by("api", "api-v2")),
+            ],
+        );
+
+        assert_rejected_at(result, 1);
+    }
+
+    #[test]
+    fn effective_dates_ending_before_they_start_are_rejected() {
+        let app = TestApp::with_environment("dev", &[service("api"), service("db")], &[]);
+        let files = snapshot_dir(app.data_path());
+        let mut migration = relationship("rel-1", "api", "db");
+        migration.effective_from = Some("2025-07-01".to_string());
+        migration.effective_until = Some("2025-06-30".to_string());
+
+        let result = run(
+            &app,
+            vec![Operation::SaveRelationship {
+                relationship: migration,
+                force: false,
+            }],
+        );
+
+        assert_rejected_at(result, 0);
+        assert_eq!(snapshot_dir(app.data_path()), files);
+    }
+
+    #[test]
+    fn relationships_saved_by_a_batch_get_generated_descriptions() {
+        let mut payments = service("payments");
+        payments.name = "Payments".to_string();
+        let app = TestApp::with_environment("dev", &[payments], &[]);
+        write_state(&app.state())
+            .settings
+            .relationship_descriptions
+            .auto_generate = true;
+        let mut checkout = service("checkout");
+        checkout.name = "Checkout".to_string();
+
+        let result = run(
+            &app,
+            vec![
+                save(checkout),
+                Operation::SaveRelationship {
+                    relationship: relationship("rel-1", "checkout", "payments"),
+                    force: false,
+                },
+            ],
+        )
+        .unwrap();
+
+        let saved = &result.saved_relationships[0];
+        assert_eq!(
+            saved.description.as_deref(),
+            Some("Checkout depends on Payments")
+        );
+        assert!(descriptions::is_generated(saved));
+    }
+
+    #[test]
+    fn a_dry_run_writes_nothing_and_matches_the_real_run() {
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("web"), service("db")],
+            &[
+                relationship("api-db", "api", "db"),
+                relationship("web-api", "web", "api"),
+                relationship("web-db", "web", "db"),
+            ],
+        );
+        let mut renamed = service("api");
+        renamed.name = "API".to_string();
+        // Bulk updates and deletes of services and relationships together
+        let operations = vec![
+            save(service("cache")),
+            Operation::SaveService {
+                service: renamed,
+                force: true,
+            },
+            Operation::DeleteRelationship {
+                relationship_id: "web-api".to_string(),
+            },
+            Operation::DeleteService {
+                service_id: "db".to_string(),
+            },
+        ];
+        let files = snapshot_dir(app.data_path());
+
+        let preview = run_with(&app, operations.clone(), true).unwrap();
+
+        assert!(preview.dry_run);
+        assert_eq!(snapshot_dir(app.data_path()), files);
+        assert_eq!(preview.deleted_services, ["db"]);
+        assert!(preview
+            .deleted_relationships
+            .contains(&"web-api".to_string()));
+
+        let applied = run_with(&app, operations, false).unwrap();
+        assert!(!applied.dry_run);
+        assert_eq!(without_dry_run(&preview), without_dry_run(&applied));
+        assert_ne!(snapshot_dir(app.data_path()), files);
+    }
+}
@@ -0,0 +1,440 @@
+//! Cache commands for the Tauri application.
+//!
+//! This module lets the frontend force a reload of an environment, warm the
+//! caches ahead of the first query, drop cached data and inspect what is
+//! currently cached (see the `state::cache` module for how the caches are
+//! filled and kept fresh).
+
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::error::AppError;
+use crate::state::cache::{self, CacheStamp};
+use crate::state::{read_state, run_blocking, write_state, AppState};
+use crate::storage;
+
+/// What is cached for one environment.
+///
+/// # Fields
+///
+/// * `environment` - The environment name
+/// * `service_count` - Number of cached services, if services are cached
+/// * `relationship_count` - Number of cached relationships, if relationships are cached
+/// * `services_loaded_at` - When services were loaded, in milliseconds since the Unix epoch
+/// * `relationships_loaded_at` - When relationships were loaded, in the same unit
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedEnvironmentInfo {
+    pub environment: String,
+    pub service_count: Option<usize>,
+    pub relationship_count: Option<usize>,
+    pub services_loaded_at: Option<u64>,
+    pub relationships_loaded_at: Option<u64>,
+}
+
+/// A snapshot of the caches, for debugging.
+///
+/// # Fields
+///
+/// * `generation` - The cache generation, incremented on every write and invalidation
+/// * `environments` - The cached environments, sorted by name
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheInfo {
+    pub generation: u64,
+    pub environments: Vec<CachedEnvironmentInfo>,
+}
+
+/// Reloads an environment's services and relationships from storage.
+///
+/// Use this after editing data files outside the application when the
+/// automatic freshness checks can't see the change (for example an in-place
+/// edit of a service file while the data directory watcher isn't running).
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the caches
+/// * `environment` - The name of the environment to reload
+///
+/// # Returns
+///
+/// * `Ok(CachedEnvironmentInfo)` - What is cached for the environment after the reload
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::FileLoad)` - If a data file cannot be read or parsed
+/// * `Err(AppError::Io)` - If there's an error reading from the filesystem
+///
+/// # Side Effects
+///
+/// - Clears the environment's caches and fills them again from storage
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const info = await invoke('refresh_environment', { environment: 'dev' });
+/// console.log(`Reloaded ${info.serviceCount} services`);
+/// ```
+#[tauri::command]
+pub fn refresh_environment(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<CachedEnvironmentInfo, AppError> {
+    storage::validate_environment_name(&environment)?;
+
+    write_state(&state).clear_environment_cache(&environment);
+
+    preload(&state, &environment)
+}
+
+/// Loads an environment into the caches ahead of its first query.
+///
+/// Fire this right after `switch_environment` so the first screen of a big
+/// environment is served from memory. The data is loaded on a background
+/// thread without holding the state lock, which is only taken briefly to
+/// install the result, so other commands keep running meanwhile. Already
+/// cached, fresh data is not loaded again.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to reach the state from the loading thread
+/// * `environment` - The name of the environment to load
+///
+/// # Returns
+///
+/// * `Ok(CachedEnvironmentInfo)` - What is cached for the environment afterwards
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::FileLoad)` - If a data file cannot be read or parsed
+/// * `Err(AppError::Io)` - If there's an error reading from the filesystem
+///
+/// # Side Effects
+///
+/// - Fills the environment's caches from storage
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('switch_environment', { environment: 'prod' });
+/// await invoke('preload_environment', { environment: 'prod' });
+/// ```
+#[tauri::command]
+pub async fn preload_environment<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+) -> Result<CachedEnvironmentInfo, AppError> {
+    storage::validate_environment_name(&environment)?;
+
+    run_blocking(app, move |state| preload(state, &environment)).await
+}
+
+/// Fills an environment's caches, for `preload_environment` and `switch_environment`.
+pub fn preload(
+    state: &RwLock<AppState>,
+    environment: &str,
+) -> Result<CachedEnvironmentInfo, AppError> {
+    let services = cache::shared_services(state, environment)?;
+    let relationships = cache::shared_relationships(state, environment)?;
+
+    let state = read_state(state);
+    let mut info = environment_info(&state, environment);
+    // A write may have invalidated the fresh entries already; report what was loaded
+    info.service_count = Some(services.len());
+    info.relationship_count = Some(relationships.len());
+
+    Ok(info)
+}
+
+/// Drops cached data, for troubleshooting.
+///
+/// The next query of a cleared environment loads it from storage again.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the caches
+/// * `environment` - The environment to clear; `null` clears every environment
+///
+/// # Returns
+///
+/// * `Ok(CacheInfo)` - What is still cached afterwards
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+///
+/// # Side Effects
+///
+/// - Clears the caches of the environment, or all caches
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('clear_cache', { environment: 'dev' });
+/// await invoke('clear_cache', { environment: null });
+/// ```
+#[tauri::command]
+pub fn clear_cache(
+    state: State<'_, RwLock<AppState>>,
+    environment: Option<String>,
+) -> Result<CacheInfo, AppError> {
+    match environment {
+        Some(environment) => {
+            storage::validate_environment_name(&environment)?;
+            write_state(&state).clear_environment_cache(&environment);
+        }
+        None => write_state(&state).clear_cache(),
+    }
+
+    get_cache_info(state)
+}
+
+/// Describes the current contents of the caches.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the caches
+///
+/// # Returns
+///
+/// * `Ok(CacheInfo)` - The cached environments with entry counts and load times
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const info = await invoke('get_cache_info');
+/// for (const env of info.environments) {
+///     console.log(env.environment, env.serviceCount, new Date(env.servicesLoadedAt));
+/// }
+/// ```
+#[tauri::command]
+pub fn get_cache_info(state: State<'_, RwLock<AppState>>) -> Result<CacheInfo, AppError> {
+    Ok(cache_info(&read_state(&state)))
+}
+
+/// Describes the current contents of the caches, for `get_cache_info` and `get_diagnostics`.
+pub fn cache_info(state: &AppState) -> CacheInfo {
+    let names: BTreeSet<&String> = state
+        .services_cache
+        .keys()
+        .chain(state.relationships_cache.keys())
+        .collect();
+
+    CacheInfo {
+        generation: state.cache_generation,
+        environments: names
+            .into_iter()
+            .map(|environment| environment_info(state, environment))
+            .collect(),
+    }
+}
+
+/// Builds the cache description of one environment.
+fn environment_info(state: &AppState, environment: &str) -> CachedEnvironmentInfo {
+    CachedEnvironmentInfo {
+        environment: environment.to_string(),
+        service_count: state.services_cache.get(environment).map(|s| s.len()),
+        relationship_count: state.relationships_cache.get(environment).map(|r| r.len()),
+        services_loaded_at: state.services_stamps.get(environment).map(loaded_at_millis),
+        relationships_loaded_at: state
+            .relationships_stamps
+            .get(environment)
+            .map(loaded_at_millis),
+    }
+}
+
+/// Converts a stamp's load time to milliseconds since the Unix epoch.
+fn loaded_at_millis(stamp: &CacheStamp) -> u64 {
+    stamp
+        .loaded_at
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::environments::switch_environment;
+    use crate::commands::services::get_all_services;
+    use crate::storage::loader::relationships_file_path;
+    use crate::test_support::{block_on, relationship, service, SlowStorage, TestApp};
+    use std::fs::{self, File};
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    /// Moves a file's or directory's modification time into the future, as
+    /// an edit a little later than the cached load would.
+    fn touch_later(path: &Path) {
+        let later = SystemTime::now() + Duration::from_secs(2);
+        File::open(path).unwrap().set_modified(later).unwrap();
+    }
+
+    fn service_ids(app: &TestApp) -> Vec<String> {
+        let mut ids: Vec<String> = cache::services(&app.state(), "dev")
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    fn shop() -> TestApp {
+        TestApp::with_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[relationship("api-db", "api", "db")],
+        )
+    }
+
+    #[test]
+    fn a_service_file_added_behind_the_cache_is_picked_up() {
+        let app = shop();
+        assert_eq!(service_ids(&app), vec!["api", "db"]);
+
+        let services_dir = app.data_path().join("dev").join("services");
+        let copied = fs::read_to_string(services_dir.join("db.json"))
+            .unwrap()
+            .replace("\"db\"", "\"cache\"");
+        fs::write(services_dir.join("cache.json"), copied).unwrap();
+        touch_later(&services_dir);
+
+        assert_eq!(service_ids(&app), vec!["api", "cache", "db"]);
+    }
+
+    #[test]
+    fn relationships_edited_behind_the_cache_are_picked_up() {
+        let app = shop();
+        assert_eq!(cache::relationships(&app.state(), "dev").unwrap().len(), 1);
+
+        let path = relationships_file_path(app.data_path(), "dev").unwrap();
+        let edited = fs::read_to_string(&path)
+            .unwrap()
+            .replace("depends_on", "reads_from");
+        fs::write(&path, edited).unwrap();
+        touch_later(&path);
+
+        let relationships = cache::relationships(&app.state(), "dev").unwrap();
+        assert_eq!(relationships[0].relationship_type.as_str(), "reads_from");
+    }
+
+    #[test]
+    fn refreshing_picks_up_an_in_place_edit_the_stamps_miss() {
+        let app = shop();
+        assert_eq!(service_ids(&app), vec!["api", "db"]);
+        let services_dir = app.data_path().join("dev").join("services");
+        let path = services_dir.join("api.json");
+        let modified = fs::metadata(&services_dir).unwrap().modified().unwrap();
+        let edited = fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"name\": \"api\"", "\"name\": \"Public API\"");
+        fs::write(&path, edited).unwrap();
+        File::open(&services_dir)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let cached_name = |app: &TestApp| {
+            cache::services(&app.state(), "dev")
+                .unwrap()
+                .into_iter()
+                .find(|s| s.id == "api")
+                .unwrap()
+                .name
+        };
+        assert_eq!(cached_name(&app), "api");
+
+        let info = refresh_environment(app.state(), "dev".to_string()).unwrap();
+
+        assert_eq!(info.service_count, Some(2));
+        assert_eq!(info.relationship_count, Some(1));
+        assert_eq!(cached_name(&app), "Public API");
+    }
+
+    #[test]
+    fn cache_info_lists_the_cached_environments() {
+        let app = shop();
+        app.create_environment("prod", &[service("api")], &[]);
+        assert!(get_cache_info(app.state()).unwrap().environments.is_empty());
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        cache::services(&app.state(), "dev").unwrap();
+        cache::relationships(&app.state(), "dev").unwrap();
+        cache::services(&app.state(), "prod").unwrap();
+
+        let info = get_cache_info(app.state()).unwrap();
+        let environments: Vec<(&str, Option<usize>, Option<usize>)> = info
+            .environments
+            .iter()
+            .map(|e| {
+                (
+                    e.environment.as_str(),
+                    e.service_count,
+                    e.relationship_count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            environments,
+            vec![("dev", Some(2), Some(1)), ("prod", Some(1), None)]
+        );
+        let dev = &info.environments[0];
+        assert!(dev.services_loaded_at.unwrap() >= before);
+        assert!(dev.relationships_loaded_at.unwrap() >= before);
+        assert_eq!(info.environments[1].relationships_loaded_at, None);
+
+        let cleared = clear_cache(app.state(), Some("dev".to_string())).unwrap();
+        assert_eq!(cleared.environments.len(), 1);
+        assert!(cleared.generation > info.generation);
+    }
+
+    fn all_services(app: &TestApp) -> usize {
+        block_on(get_all_services(app.handle(), "dev".to_string()))
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn preloading_serves_the_next_query_from_the_cache() {
+        let app = shop();
+        let storage = SlowStorage::install(&app, Duration::ZERO, Duration::ZERO);
+
+        let info = block_on(preload_environment(app.handle(), "dev".to_string())).unwrap();
+        assert_eq!(info.service_count, Some(2));
+        assert_eq!(info.relationship_count, Some(1));
+        assert_eq!(
+            (storage.service_loads(), storage.relationship_loads()),
+            (1, 1)
+        );
+
+        assert_eq!(all_services(&app), 2);
+        assert_eq!(storage.service_loads(), 1);
+    }
+
+    #[test]
+    fn switching_with_preload_warms_the_cache_in_the_background() {
+        let app = shop();
+        let storage = SlowStorage::install(&app, Duration::ZERO, Duration::ZERO);
+
+        switch_environment(app.handle(), app.state(), "dev".to_string(), Some(true)).unwrap();
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while storage.relationship_loads() == 0
+            || !read_state(&app.state())
+                .relationships_cache
+                .contains_key("dev")
+        {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "the preload never finished"
+            );
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        assert_eq!(all_services(&app), 2);
+        assert_eq!(storage.service_loads(), 1);
+    }
+}
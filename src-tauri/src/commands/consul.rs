@@ -0,0 +1,260 @@
+//! Consul import commands for the Tauri application.
+//!
+//! Imports the services registered in a Consul registry into an
+//! environment, or refreshes the statuses of services already imported
+//! (see `consul` for how registrations are mapped).
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::consul::{self, RegisteredService, CONSUL_METADATA_KEY};
+use crate::error::AppError;
+use crate::merge::{MergeConflict, MergeStrategies, MergeStrategy, Merger};
+use crate::models::{ImportCounts, Service};
+use crate::state::progress::Progress;
+use crate::state::{write_state, AppState};
+use crate::storage;
+
+/// Import source the Consul import records its syncs under.
+pub const IMPORT_SOURCE: &str = "consul";
+
+/// How a full import merges a registration into the service it matches:
+/// Consul decides the status, its tags and metadata are merged in, and the
+/// fields edited in the app are kept.
+const IMPORT_STRATEGIES: MergeStrategies = MergeStrategies {
+    name: MergeStrategy::KeepExisting,
+    service_type: MergeStrategy::KeepExisting,
+    status: MergeStrategy::Overwrite,
+    details: MergeStrategy::KeepExisting,
+    tags: MergeStrategy::Merge,
+    metadata: MergeStrategy::Merge,
+};
+
+/// How syncing statuses merges: only the status is taken from Consul.
+const SYNC_STRATEGIES: MergeStrategies = MergeStrategies {
+    name: MergeStrategy::KeepExisting,
+    service_type: MergeStrategy::KeepExisting,
+    status: MergeStrategy::Overwrite,
+    details: MergeStrategy::KeepExisting,
+    tags: MergeStrategy::KeepExisting,
+    metadata: MergeStrategy::KeepExisting,
+};
+
+/// The result of a Consul import.
+///
+/// # Fields
+///
+/// * `services_found` - Number of services registered in Consul
+/// * `unmatched` - Consul names without a service in the environment; only filled
+///   when syncing statuses, since a full import creates them
+/// * `conflicts` - Fields where Consul and a stored service disagreed, with the side
+///   that was saved (see `merge`)
+/// * `result` - What the import saved
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsulImportReport {
+    pub services_found: usize,
+    pub unmatched: Vec<String>,
+    pub conflicts: Vec<MergeConflict>,
+    pub result: BatchResult,
+}
+
+/// Imports the services registered in Consul, or refreshes their statuses.
+///
+/// A Consul service matches the service it was imported as before (by the
+/// Consul name in its `consul` metadata) or else the service with its
+/// mapped ID. A full import creates unmatched services and updates matched
+/// ones with the Consul status, tags and metadata, keeping fields edited in
+/// the app; the report lists the fields where the two disagreed. With
+/// `syncStatusesOnly`, only the status of matched services is
+/// updated and nothing is created, e.g. for a refresh button. All changes
+/// are written as one batch (see `execute_batch`).
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the caches and data path
+/// * `environment` - The environment to import into
+/// * `base_url` - The Consul HTTP address, e.g. `http://consul.internal:8500`
+/// * `token` - The ACL token, if required; it is only sent to Consul, never stored
+/// * `sync_statuses_only` - If true, only update the status of existing services
+///   (default: false)
+/// * `operation_id` - ID for the `operation:progress` events and `cancel_operation`
+///
+/// # Returns
+///
+/// * `Ok(ConsulImportReport)` - What was found and changed
+/// * `Err(AppError::ValidationError)` - If the URL isn't an http(s) URL
+/// * `Err(AppError::RegistryAuthFailed)` - If the token is missing or invalid
+/// * `Err(AppError::RegistryTimeout)` - If Consul didn't answer in time
+/// * `Err(AppError::RegistryRequestFailed)` - If Consul couldn't be reached or
+///   answered with an error
+/// * `Err(AppError::BatchOperationFailed)` - If a change can't be made; nothing was written
+/// * `Err(AppError::OperationCancelled)` - If cancelled before writing; nothing was written
+///
+/// # Side Effects
+///
+/// - Emits `operation:progress` events while fetching, computing and writing
+/// - Writes the changed service files
+/// - Records the time and counts of the import in the environment's `environment.json`
+/// - Records the import as a single entry in the undo journal
+/// - Appends one audit log record per changed service
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: refresh statuses from the registry
+/// const report = await invoke('import_from_consul', {
+///     environment: 'prod',
+///     baseUrl: 'http://consul.internal:8500',
+///     token: consulToken,
+///     syncStatusesOnly: true,
+///     operationId: crypto.randomUUID()
+/// });
+/// console.log(`${report.result.savedServices.length} statuses changed`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_from_consul<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    base_url: String,
+    token: Option<String>,
+    sync_statuses_only: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<ConsulImportReport, AppError> {
+    storage::validate_environment_name(&environment)?;
+    let base_url = base_url.trim().to_string();
+    if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+        return Err(AppError::ValidationError(format!(
+            "Consul URL must start with http:// or https://, got '{}'",
+            base_url
+        )));
+    }
+
+    let mut progress = Progress::start(&app, &state, operation_id, "import_from_consul");
+    let result = import_registry(
+        &app,
+        &state,
+        &environment,
+        base_url,
+        token,
+        sync_statuses_only.unwrap_or(false),
+        &mut progress,
+    )
+    .await;
+    progress.finish(result)
+}
+
+/// Fetches the registered services and saves them, for `import_from_consul`.
+async fn import_registry<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    base_url: String,
+    token: Option<String>,
+    sync_statuses_only: bool,
+    progress: &mut Progress,
+) -> Result<ConsulImportReport, AppError> {
+    progress.phase("Fetching registered services", 1);
+    let registered = tauri::async_runtime::spawn_blocking(move || {
+        consul::fetch_services(&base_url, token.as_deref())
+    })
+    .await
+    .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))??;
+    progress.advance();
+    progress.check_cancelled()?;
+
+    let mut state = write_state(state);
+    let (operations, unmatched, conflicts) =
+        import_operations(&state, environment, &registered, sync_statuses_only)?;
+    let plan = Plan::compute_with_progress(&state, environment, operations, progress)?;
+    let description = if sync_statuses_only {
+        format!(
+            "Synced {} service statuses from Consul",
+            plan.result.saved_services.len()
+        )
+    } else {
+        format!("Imported {} services from Consul", registered.len())
+    };
+    let result = plan.apply_with_progress(
+        app,
+        &mut state,
+        environment,
+        "import_from_consul",
+        description,
+        progress,
+    )?;
+    let counts = ImportCounts {
+        services: registered.len(),
+        relationships: 0,
+    };
+    state.record_import(environment, IMPORT_SOURCE, counts);
+
+    Ok(ConsulImportReport {
+        services_found: registered.len(),
+        unmatched,
+        conflicts,
+        result,
+    })
+}
+
+/// The saves of an import, the Consul names without a service and the merge conflicts.
+type ImportOperations = (Vec<Operation>, Vec<String>, Vec<MergeConflict>);
+
+/// Builds the saves an import makes, and lists the Consul names without a
+/// service and the merge conflicts.
+fn import_operations(
+    state: &AppState,
+    environment: &str,
+    registered: &[RegisteredService],
+    sync_statuses_only: bool,
+) -> Result<ImportOperations, AppError> {
+    let existing = state.storage.load_services(environment)?;
+    let by_id: HashMap<&str, &Service> = existing.iter().map(|s| (s.id.as_str(), s)).collect();
+    let by_consul_name: HashMap<&str, &Service> = existing
+        .iter()
+        .filter_map(|s| consul_name(s).map(|name| (name, s)))
+        .collect();
+
+    let strategies = if sync_statuses_only {
+        SYNC_STRATEGIES
+    } else {
+        IMPORT_STRATEGIES
+    };
+    let mut merger = Merger::new(strategies);
+    let mut operations = Vec::new();
+    let mut unmatched = Vec::new();
+    for registration in registered {
+        let stored = by_consul_name
+            .get(registration.consul_name.as_str())
+            .or_else(|| by_id.get(registration.service.id.as_str()))
+            .copied();
+        if stored.is_none() && sync_statuses_only {
+            unmatched.push(registration.consul_name.clone());
+            continue;
+        }
+
+        if let Some(service) = merger.service(stored, registration.service.clone()) {
+            operations.push(Operation::SaveService {
+                service,
+                force: true,
+            });
+        }
+    }
+
+    Ok((operations, unmatched, merger.into_conflicts()))
+}
+
+/// Returns the Consul name a service was imported from, if it was imported.
+pub fn consul_name(service: &Service) -> Option<&str> {
+    service
+        .metadata
+        .get(CONSUL_METADATA_KEY)?
+        .get("service")?
+        .as_str()
+}
@@ -0,0 +1,620 @@
+//! Custom type registry commands for the Tauri application.
+//!
+//! The registries (see `models::CustomTypeDefinition`) list the custom
+//! service and relationship types a team has agreed on, with a label and
+//! color for display. Nothing stops a service from using an unregistered
+//! type; `validate_environment` flags it once the registry of its kind has
+//! entries, and `normalize_custom_types` rewrites stray spellings.
+//!
+//! The registries are configuration of the data path like saved views, so
+//! changing them isn't recorded in the undo journal or committed to git.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::error::AppError;
+use crate::models::{
+    CustomTypeDefinition, CustomTypeKind, Relationship, RelationshipType, Service, ServiceType,
+};
+use crate::state::{cache, read_state, run_blocking, write_state, AppState};
+use crate::storage;
+
+/// A custom type found in an environment's data.
+///
+/// # Fields
+///
+/// * `name` - The type as the services or relationships spell it
+/// * `count` - Number of services or relationships using it
+/// * `registered_as` - The registered type matching it regardless of case, if any
+/// * `newly_registered` - True if `discover_custom_types` registered it just now
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredCustomType {
+    pub name: String,
+    pub count: usize,
+    pub registered_as: Option<String>,
+    pub newly_registered: bool,
+}
+
+/// The custom types used in an environment, by kind.
+///
+/// # Fields
+///
+/// * `service_types` - Custom service types, sorted by name
+/// * `relationship_types` - Custom relationship types, sorted by name
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomTypeDiscovery {
+    pub service_types: Vec<DiscoveredCustomType>,
+    pub relationship_types: Vec<DiscoveredCustomType>,
+}
+
+/// Lists the registered custom types of one kind.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `kind` - `service` or `relationship`
+///
+/// # Returns
+///
+/// * `Ok(Vec<CustomTypeDefinition>)` - The registered types, sorted by name
+/// * `Err(AppError::FileLoad)` - If the registry file cannot be parsed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const types = await invoke('list_custom_types', { kind: 'service' });
+/// ```
+#[tauri::command]
+pub fn list_custom_types(
+    state: State<'_, RwLock<AppState>>,
+    kind: CustomTypeKind,
+) -> Result<Vec<CustomTypeDefinition>, AppError> {
+    let state = read_state(&state);
+    storage::load_custom_types(&state.data_path, kind)
+}
+
+/// Registers a custom type, replacing the entry with the same name.
+///
+/// A blank label defaults to the name.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `kind` - `service` or `relationship`
+/// * `definition` - The type to register
+///
+/// # Returns
+///
+/// * `Ok(CustomTypeDefinition)` - The registered type
+/// * `Err(AppError::ValidationError)` - If the name is empty, is a built-in type, or
+///   differs only in case from a registered type
+/// * `Err(AppError::FileLoad)` - If the registry file cannot be parsed
+/// * `Err(AppError::Io)` - If the registry file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `.types/{kind}_types.json` in the data directory (or
+///   `{database}.types/{kind}_types.json` next to a SQLite database)
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('add_custom_type', {
+///     kind: 'service',
+///     definition: { name: 'lambda', label: 'AWS Lambda', color: '#ff9900' }
+/// });
+/// ```
+#[tauri::command]
+pub fn add_custom_type(
+    state: State<'_, RwLock<AppState>>,
+    kind: CustomTypeKind,
+    definition: CustomTypeDefinition,
+) -> Result<CustomTypeDefinition, AppError> {
+    let state = write_state(&state);
+    let mut types = storage::load_custom_types(&state.data_path, kind)?;
+
+    let name = definition.name.trim().to_string();
+    check_type_name(kind, &name, &types, None)?;
+    let label = match definition.label.trim() {
+        "" => name.clone(),
+        label => label.to_string(),
+    };
+    let definition = CustomTypeDefinition {
+        name,
+        label,
+        ..definition
+    };
+
+    types.retain(|existing| existing.name != definition.name);
+    types.push(definition.clone());
+    storage::save_custom_types(&state.data_path, kind, &types)?;
+
+    Ok(definition)
+}
+
+/// Renames a registered custom type.
+///
+/// Only the registry changes; run `normalize_custom_types` with a mapping
+/// from the old name to the new one to move the services or relationships
+/// using it. Renaming to a different casing of the same name is allowed.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `kind` - `service` or `relationship`
+/// * `name` - The registered name
+/// * `new_name` - The name to register it under instead
+///
+/// # Returns
+///
+/// * `Ok(CustomTypeDefinition)` - The renamed type
+/// * `Err(AppError::CustomTypeNotFound)` - If no type of the kind has the name
+/// * `Err(AppError::ValidationError)` - If the new name is empty, is a built-in type, or
+///   is taken by another registered type regardless of case
+/// * `Err(AppError::Io)` - If the registry file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `.types/{kind}_types.json` in the data directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: rename the type, then move the services using it
+/// await invoke('rename_custom_type', {
+///     kind: 'service',
+///     name: 'fn',
+///     newName: 'lambda'
+/// });
+/// await invoke('normalize_custom_types', {
+///     environment: 'prod',
+///     mapping: { fn: 'lambda' }
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn rename_custom_type(
+    state: State<'_, RwLock<AppState>>,
+    kind: CustomTypeKind,
+    name: String,
+    new_name: String,
+) -> Result<CustomTypeDefinition, AppError> {
+    let state = write_state(&state);
+    let mut types = storage::load_custom_types(&state.data_path, kind)?;
+
+    let position = types
+        .iter()
+        .position(|existing| existing.name == name)
+        .ok_or(AppError::CustomTypeNotFound(name))?;
+    let new_name = new_name.trim().to_string();
+    check_type_name(kind, &new_name, &types, Some(types[position].name.as_str()))?;
+
+    let definition = &mut types[position];
+    if definition.label == definition.name {
+        definition.label = new_name.clone();
+    }
+    definition.name = new_name;
+    let renamed = definition.clone();
+
+    storage::save_custom_types(&state.data_path, kind, &types)?;
+
+    Ok(renamed)
+}
+
+/// Removes a custom type from the registry.
+///
+/// Services and relationships using the type keep it; once the registry of
+/// the kind is empty, validation stops checking custom types of that kind.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `kind` - `service` or `relationship`
+/// * `name` - The registered name
+///
+/// # Returns
+///
+/// * `Ok(())` - If the type was removed
+/// * `Err(AppError::CustomTypeNotFound)` - If no type of the kind has the name
+/// * `Err(AppError::Io)` - If the registry file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `.types/{kind}_types.json` in the data directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('delete_custom_type', { kind: 'relationship', name: 'replicates_to' });
+/// ```
+#[tauri::command]
+pub fn delete_custom_type(
+    state: State<'_, RwLock<AppState>>,
+    kind: CustomTypeKind,
+    name: String,
+) -> Result<(), AppError> {
+    let state = write_state(&state);
+    let mut types = storage::load_custom_types(&state.data_path, kind)?;
+
+    let count = types.len();
+    types.retain(|existing| existing.name != name);
+    if types.len() == count {
+        return Err(AppError::CustomTypeNotFound(name));
+    }
+
+    storage::save_custom_types(&state.data_path, kind, &types)
+}
+
+/// Lists the custom types an environment's services and relationships use.
+///
+/// With `register`, the unregistered types are added to the registries so
+/// an existing data set can be adopted in one step. Spellings that differ
+/// only in case are registered once, under the most used spelling.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `register` - If true, register the types that aren't yet (default: false)
+///
+/// # Returns
+///
+/// * `Ok(CustomTypeDiscovery)` - The custom types in use, with their registration
+/// * `Err(AppError::FileLoad)` - If the data or a registry file cannot be parsed
+/// * `Err(AppError::Io)` - If a registry file cannot be written
+///
+/// # Side Effects
+///
+/// With `register`, writes `.types/{kind}_types.json` in the data directory.
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: seed the registries from production
+/// const discovery = await invoke('discover_custom_types', {
+///     environment: 'prod',
+///     register: true
+/// });
+/// ```
+#[tauri::command]
+pub async fn discover_custom_types<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    register: Option<bool>,
+) -> Result<CustomTypeDiscovery, AppError> {
+    run_blocking(app, move |state| {
+        discover(state, &environment, register.unwrap_or(false))
+    })
+    .await
+}
+
+/// Finds and optionally registers the custom types, for `discover_custom_types`.
+fn discover(
+    state: &RwLock<AppState>,
+    environment: &str,
+    register: bool,
+) -> Result<CustomTypeDiscovery, AppError> {
+    let services = cache::shared_services(state, environment)?;
+    let relationships = cache::shared_relationships(state, environment)?;
+
+    let mut service_counts: BTreeMap<String, usize> = BTreeMap::new();
+    for service in &services {
+        if let ServiceType::Custom(name) = &service.service_type {
+            *service_counts.entry(name.clone()).or_insert(0) += 1;
+        }
+    }
+    let mut relationship_counts: BTreeMap<String, usize> = BTreeMap::new();
+    for relationship in relationships.iter() {
+        if let RelationshipType::Custom(name) = &relationship.relationship_type {
+            *relationship_counts.entry(name.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let discover_all = |data_path: &Path| -> Result<CustomTypeDiscovery, AppError> {
+        Ok(CustomTypeDiscovery {
+            service_types: discovered(
+                data_path,
+                CustomTypeKind::Service,
+                service_counts,
+                register,
+            )?,
+            relationship_types: discovered(
+                data_path,
+                CustomTypeKind::Relationship,
+                relationship_counts,
+                register,
+            )?,
+        })
+    };
+
+    // Registering holds the write lock so the registries don't change in between
+    if register {
+        discover_all(&write_state(state).data_path)
+    } else {
+        discover_all(&read_state(state).data_path)
+    }
+}
+
+/// Matches the custom types of one kind in use against its registry,
+/// registering the unregistered ones if `register` is set.
+fn discovered(
+    data_path: &Path,
+    kind: CustomTypeKind,
+    counts: BTreeMap<String, usize>,
+    register: bool,
+) -> Result<Vec<DiscoveredCustomType>, AppError> {
+    let mut types = storage::load_custom_types(data_path, kind)?;
+    let mut found: Vec<DiscoveredCustomType> = counts
+        .into_iter()
+        .map(|(name, count)| DiscoveredCustomType {
+            registered_as: registered_match(&name, &types).map(str::to_string),
+            name,
+            count,
+            newly_registered: false,
+        })
+        .collect();
+    if !register {
+        return Ok(found);
+    }
+
+    // Most used spelling of each unregistered name; the first one wins ties
+    let mut spellings: BTreeMap<String, usize> = BTreeMap::new();
+    for (position, entry) in found.iter().enumerate() {
+        if entry.registered_as.is_some() {
+            continue;
+        }
+        let best = spellings
+            .entry(entry.name.to_lowercase())
+            .or_insert(position);
+        if entry.count > found[*best].count {
+            *best = position;
+        }
+    }
+
+    let mut registered = false;
+    for position in spellings.into_values() {
+        let name = found[position].name.clone();
+        if check_type_name(kind, &name, &types, None).is_err() {
+            continue;
+        }
+        types.push(CustomTypeDefinition {
+            name: name.clone(),
+            label: name.clone(),
+            description: None,
+            color: None,
+        });
+        registered = true;
+        found[position].newly_registered = true;
+        for entry in found.iter_mut() {
+            if entry.registered_as.is_none() && entry.name.eq_ignore_ascii_case(&name) {
+                entry.registered_as = Some(name.clone());
+            }
+        }
+    }
+
+    if registered {
+        storage::save_custom_types(data_path, kind, &types)?;
+    }
+    Ok(found)
+}
+
+/// Rewrites custom service and relationship types to their registered spelling.
+///
+/// Without a mapping, each custom type that matches a registered type of
+/// its kind regardless of case is rewritten to the registered spelling
+/// (e.g. "Lambda" to "lambda"). A mapping replaces that and is applied to
+/// both kinds; mapping to a built-in name turns the custom type into the
+/// built-in one. All changes are saved as one batch (see `execute_batch`).
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment
+/// * `mapping` - Optional custom type names and the names to rewrite them to
+/// * `dry_run` - If true, only compute the changes (default: false)
+///
+/// # Returns
+///
+/// * `Ok(BatchResult)` - The rewritten services and relationships
+/// * `Err(AppError::ValidationError)` - If the mapping maps a type to an empty name
+/// * `Err(AppError::BatchOperationFailed)` - If a change can't be saved (e.g., a
+///   rewritten relationship duplicates an existing one); nothing was written
+/// * `Err(AppError::Io)` - If writing the data failed
+///
+/// # Side Effects
+///
+/// Unless `dry_run` is set:
+/// - Writes the changed service files and `relationships.json`
+/// - Records the rewrite as a single entry in the undo journal
+/// - Appends one audit log record per changed service or relationship
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: preview the rewrite to the registered spellings
+/// const preview = await invoke('normalize_custom_types', {
+///     environment: 'prod',
+///     dryRun: true
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn normalize_custom_types<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    mapping: Option<HashMap<String, String>>,
+    dry_run: Option<bool>,
+) -> Result<BatchResult, AppError> {
+    run_blocking(app.clone(), move |state| {
+        normalize(
+            &app,
+            state,
+            &environment,
+            mapping.as_ref(),
+            dry_run.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+/// Rewrites the custom types, for `normalize_custom_types`.
+fn normalize<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    mapping: Option<&HashMap<String, String>>,
+    dry_run: bool,
+) -> Result<BatchResult, AppError> {
+    if let Some(mapping) = mapping {
+        if let Some((name, _)) = mapping.iter().find(|(_, target)| target.trim().is_empty()) {
+            return Err(AppError::ValidationError(format!(
+                "Custom type '{}' cannot be mapped to an empty name",
+                name
+            )));
+        }
+    }
+
+    if dry_run {
+        let state = read_state(state);
+        let operations = normalize_operations(&state, environment, mapping)?;
+        let mut plan = Plan::compute(&state, environment, operations)?;
+        plan.result.dry_run = true;
+        return Ok(plan.result);
+    }
+
+    let mut state = write_state(state);
+    let operations = normalize_operations(&state, environment, mapping)?;
+    let plan = Plan::compute(&state, environment, operations)?;
+    let description = format!(
+        "Normalized the custom types of {} services and {} relationships",
+        plan.result.saved_services.len(),
+        plan.result.saved_relationships.len()
+    );
+
+    plan.apply(
+        app,
+        &mut state,
+        environment,
+        "normalize_custom_types",
+        description,
+    )
+}
+
+/// Builds the service and relationship saves that rewrite the custom types.
+fn normalize_operations(
+    state: &AppState,
+    environment: &str,
+    mapping: Option<&HashMap<String, String>>,
+) -> Result<Vec<Operation>, AppError> {
+    let service_types = storage::load_custom_types(&state.data_path, CustomTypeKind::Service)?;
+    let relationship_types =
+        storage::load_custom_types(&state.data_path, CustomTypeKind::Relationship)?;
+
+    let target = |name: &str, registry: &[CustomTypeDefinition]| -> Option<String> {
+        let target = match mapping {
+            Some(mapping) => mapping.get(name).map(|target| target.trim()),
+            None => registered_match(name, registry),
+        }?;
+        (target != name).then(|| target.to_string())
+    };
+
+    let mut operations = Vec::new();
+    for service in state.storage.load_services(environment)? {
+        if let ServiceType::Custom(name) = &service.service_type {
+            if let Some(target) = target(name, &service_types) {
+                operations.push(Operation::SaveService {
+                    service: Service {
+                        service_type: ServiceType::from_name(&target),
+                        ..service
+                    },
+                    force: false,
+                });
+            }
+        }
+    }
+    for relationship in state.storage.load_relationships(environment)? {
+        if let RelationshipType::Custom(name) = &relationship.relationship_type {
+            if let Some(target) = target(name, &relationship_types) {
+                operations.push(Operation::SaveRelationship {
+                    relationship: Relationship {
+                        relationship_type: RelationshipType::from_name(&target),
+                        ..relationship
+                    },
+                    force: false,
+                });
+            }
+        }
+    }
+
+    Ok(operations)
+}
+
+/// Returns the registered type matching a name regardless of case.
+fn registered_match<'a>(name: &str, registry: &'a [CustomTypeDefinition]) -> Option<&'a str> {
+    registry
+        .iter()
+        .find(|definition| definition.name == name)
+        .or_else(|| {
+            registry
+                .iter()
+                .find(|definition| definition.name.eq_ignore_ascii_case(name))
+        })
+        .map(|definition| definition.name.as_str())
+}
+
+/// Checks that a name can be registered as a custom type of a kind.
+///
+/// `renaming` is the registered name being renamed, which the new name may
+/// differ from only in case. Without it, the name may equal a registered
+/// name, whose entry is then replaced.
+fn check_type_name(
+    kind: CustomTypeKind,
+    name: &str,
+    registry: &[CustomTypeDefinition],
+    renaming: Option<&str>,
+) -> Result<(), AppError> {
+    if name.is_empty() {
+        return Err(AppError::ValidationError(
+            "Custom type name cannot be empty".to_string(),
+        ));
+    }
+
+    let lowercase = name.to_lowercase();
+    let built_in = match kind {
+        CustomTypeKind::Service => {
+            !matches!(ServiceType::from_name(&lowercase), ServiceType::Custom(_))
+        }
+        CustomTypeKind::Relationship => !matches!(
+            RelationshipType::from_name(&lowercase),
+            RelationshipType::Custom(_)
+        ),
+    };
+    if built_in {
+        return Err(AppError::ValidationError(format!(
+            "'{}' is a built-in {} type",
+            name,
+            kind.as_str()
+        )));
+    }
+
+    let own_name = renaming.unwrap_or(name);
+    let taken = registry
+        .iter()
+        .find(|existing| existing.name.eq_ignore_ascii_case(name) && existing.name != own_name);
+    match taken {
+        Some(existing) => Err(AppError::ValidationError(format!(
+            "'{}' is already registered as '{}'",
+            name, existing.name
+        ))),
+        None => Ok(()),
+    }
+}
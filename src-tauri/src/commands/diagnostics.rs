@@ -0,0 +1,134 @@
+//! Diagnostics commands for the Tauri application.
+//!
+//! Gives the frontend something to attach to a "it's slow" report: how
+//! often the instrumented commands were called and how long they took (see
+//! `state::diagnostics`), next to what is cached and where the data lives.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use tauri::State;
+
+use crate::commands::cache::{cache_info, CacheInfo};
+use crate::error::AppError;
+use crate::state::diagnostics::CommandStats;
+use crate::state::progress::RunningOperation;
+use crate::state::{read_state, AppState};
+use crate::storage::migrations::CURRENT_SCHEMA_VERSION;
+
+/// What the application is doing and how fast, for troubleshooting.
+///
+/// # Fields
+///
+/// * `app_version` - The application version
+/// * `schema_version` - The data file schema version the application writes
+/// * `data_path` - The data directory or SQLite database file
+/// * `workspace` - The active workspace, if any
+/// * `current_environment` - The currently selected environment
+/// * `cache` - What is cached, per environment
+/// * `commands` - Call statistics per instrumented command, by command name
+/// * `running_operations` - The long-running commands in progress
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    pub app_version: String,
+    pub schema_version: u32,
+    pub data_path: String,
+    pub workspace: Option<String>,
+    pub current_environment: String,
+    pub cache: CacheInfo,
+    pub commands: BTreeMap<String, CommandStats>,
+    pub running_operations: Vec<RunningOperation>,
+}
+
+/// Collects diagnostics about the running application.
+///
+/// Command statistics cover the calls since the application started or
+/// since the last `reset_diagnostics`.
+///
+/// # Arguments
+///
+/// * `state` - The application state
+///
+/// # Returns
+///
+/// * `Ok(Diagnostics)` - The diagnostics
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const diagnostics = await invoke('get_diagnostics');
+/// const slowest = Object.entries(diagnostics.commands)
+///     .sort(([, a], [, b]) => b.maxDurationMs - a.maxDurationMs)[0];
+/// ```
+#[tauri::command]
+pub fn get_diagnostics(state: State<'_, RwLock<AppState>>) -> Result<Diagnostics, AppError> {
+    let state = read_state(&state);
+
+    Ok(Diagnostics {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: CURRENT_SCHEMA_VERSION,
+        data_path: state.data_path.display().to_string(),
+        workspace: state.workspace.clone(),
+        current_environment: state.current_environment.clone(),
+        cache: cache_info(&state),
+        commands: state.command_timings.snapshot(),
+        running_operations: state.running_operations.list(),
+    })
+}
+
+/// Resets the command call statistics.
+///
+/// # Arguments
+///
+/// * `state` - The application state
+///
+/// # Side Effects
+///
+/// - Forgets the call counts and durations of every command
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('reset_diagnostics');
+/// ```
+#[tauri::command]
+pub fn reset_diagnostics(state: State<'_, RwLock<AppState>>) -> Result<(), AppError> {
+    read_state(&state).command_timings.reset();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::services::get_all_services;
+    use crate::test_support::{block_on, service, TestApp};
+
+    #[test]
+    fn command_calls_are_counted_until_reset() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+
+        for _ in 0..3 {
+            block_on(get_all_services(app.handle(), "dev".to_string())).unwrap();
+        }
+
+        let diagnostics = get_diagnostics(app.state()).unwrap();
+        let stats = diagnostics.commands["get_all_services"];
+        assert_eq!(stats.calls, 3);
+        assert!(stats.max_duration_ms >= stats.last_duration_ms);
+        assert!(stats.total_duration_ms >= stats.max_duration_ms);
+        assert_eq!(diagnostics.data_path, app.data_path().display().to_string());
+        assert_eq!(diagnostics.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(diagnostics.cache.environments[0].service_count, Some(1));
+
+        reset_diagnostics(app.state()).unwrap();
+        assert!(get_diagnostics(app.state()).unwrap().commands.is_empty());
+        block_on(get_all_services(app.handle(), "dev".to_string())).unwrap();
+        assert_eq!(
+            get_diagnostics(app.state()).unwrap().commands["get_all_services"].calls,
+            1
+        );
+    }
+}
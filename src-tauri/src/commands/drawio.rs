@@ -0,0 +1,410 @@
+//! draw.io import and export commands for the Tauri application.
+//!
+//! Exports a graph as a draw.io diagram and imports diagrams, whether
+//! exported by this tool or drawn by hand (see `drawio` for how cells are
+//! read). Imports can be repeated: vertices and edges are matched to what
+//! is stored and merged into it, and nothing missing from the diagram is
+//! deleted, since diagrams usually show only part of an environment.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime};
+
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::commands::graph::{self as graph_commands, GraphSelection};
+use crate::dates;
+use crate::drawio::{self, Diagram};
+use crate::error::AppError;
+use crate::merge::{MergeConflict, MergeStrategies, MergeStrategy, Merger};
+use crate::models::{
+    ImportCounts, Relationship, RelationshipOrigin, RelationshipType, ReviewStatus, Service,
+    ServiceStatus,
+};
+use crate::state::diagnostics::CommandTimer;
+use crate::state::{read_state, run_blocking, write_state, AppState};
+use crate::storage;
+
+/// Import source of the relationships the draw.io import creates.
+pub const IMPORT_SOURCE: &str = "drawio";
+
+/// How an import merges a vertex into the service it matches: the label
+/// is the name, and the type, status and everything else edited in the app
+/// is kept, since a diagram only hints at the type.
+const MERGE_STRATEGIES: MergeStrategies = MergeStrategies {
+    name: MergeStrategy::Overwrite,
+    service_type: MergeStrategy::KeepExisting,
+    status: MergeStrategy::KeepExisting,
+    details: MergeStrategy::KeepExisting,
+    tags: MergeStrategy::KeepExisting,
+    metadata: MergeStrategy::Merge,
+};
+
+/// A cell of the diagram that wasn't imported.
+///
+/// # Fields
+///
+/// * `cell_id` - The ID of the cell in the diagram
+/// * `label` - The text of the cell
+/// * `reason` - Why it was skipped
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedCell {
+    pub cell_id: String,
+    pub label: String,
+    pub reason: String,
+}
+
+/// The result of a draw.io import.
+///
+/// # Fields
+///
+/// * `services_found` - Number of vertices read as services
+/// * `relationships_found` - Number of edges read as relationships
+/// * `created_services` - IDs of the services the diagram added
+/// * `skipped` - Vertices and edges that weren't imported, with the reason
+/// * `conflicts` - Fields where the diagram and a stored service or relationship
+///   disagreed, with the side that was saved (see `merge`)
+/// * `result` - What the import saved
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrawioImportReport {
+    pub services_found: usize,
+    pub relationships_found: usize,
+    pub created_services: Vec<String>,
+    pub skipped: Vec<SkippedCell>,
+    pub conflicts: Vec<MergeConflict>,
+    pub result: BatchResult,
+}
+
+/// Exports an environment's graph, or the neighborhood of a service, as a
+/// draw.io diagram.
+///
+/// Vertices are laid out on a grid and shaped and colored by service type
+/// and status; edges are labeled with their relationship type (see
+/// `drawio::render`). Every cell carries the ID and type of its service or
+/// relationship, so importing the diagram again with `import_drawio`
+/// changes nothing.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to export on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `output_path` - The `.drawio` file to write; replaced if it exists
+/// * `options` - The part of the graph to draw (default: the whole environment)
+///
+/// # Returns
+///
+/// * `Ok(String)` - The written diagram
+/// * `Err(AppError::ValidationError)` - If `asOf` isn't a `YYYY-MM-DD` date
+/// * `Err(AppError::ServiceNotFound)` - If the center service doesn't exist
+/// * `Err(AppError::Io)` - If the data cannot be read or the file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the diagram to `output_path`
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('export_graph_drawio', {
+///     environment: 'prod',
+///     outputPath: '/tmp/prod.drawio',
+///     options: { excludeTags: ['deprecated'] }
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_graph_drawio<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    output_path: String,
+    options: Option<GraphSelection>,
+) -> Result<String, AppError> {
+    let selection = options.unwrap_or_default();
+    let as_of = dates::resolve_as_of(
+        selection.as_of.clone(),
+        Some(selection.ignore_effective_dates),
+    )?;
+    run_blocking(app, move |state| {
+        let _timer = CommandTimer::start(state, "export_graph_drawio");
+        let (services, relationships) =
+            graph_commands::select_graph(state, &environment, &selection, as_of)?;
+        let display = read_state(state).settings.display.clone();
+        let xml = drawio::render(&environment, &services, &relationships, &display);
+        fs::write(&output_path, &xml)?;
+        Ok(xml)
+    })
+    .await
+}
+
+/// Imports the services and relationships of a draw.io diagram.
+///
+/// A vertex is the service of its `serviceId` attribute; without one, the
+/// service named like its label, or else a new service with the label's
+/// slug as ID, its type guessed from the shape (a backend if the shape
+/// says nothing) and an unknown status. Stored services get the label as
+/// name and keep everything else. An edge is the relationship of its
+/// `relationshipId` attribute, or else the relationship with the same
+/// endpoints and type; its type is its `relationshipType` attribute or the
+/// relationship type its label names, communicates_with otherwise. New
+/// relationships get the origin `{ imported: "drawio" }`. Edges not
+/// connected to two services, edges from a service to itself and vertices
+/// without a usable label are skipped and reported. Nothing is deleted.
+/// All changes are written as one batch (see `execute_batch`).
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The environment to import into
+/// * `file_path` - The `.drawio` (or `.xml`) file, saved uncompressed
+///
+/// # Returns
+///
+/// * `Ok(DrawioImportReport)` - What was found, skipped and changed
+/// * `Err(AppError::FileLoad)` - If the file can't be read, is compressed or has
+///   no diagram cells
+/// * `Err(AppError::BatchOperationFailed)` - If a change can't be made (e.g., a
+///   `serviceId` isn't filename-safe); nothing was written
+///
+/// # Side Effects
+///
+/// - Writes the created and changed service files and `relationships.json`
+/// - Records the time and counts of the import in the environment's `environment.json`
+/// - Records the import as a single entry in the undo journal
+/// - Appends one audit log record per changed service and relationship
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('import_drawio', {
+///     environment: 'prod',
+///     filePath: '/home/me/architecture.drawio'
+/// });
+/// report.skipped.forEach(c => console.warn(`${c.label}: ${c.reason}`));
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_drawio<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    file_path: String,
+) -> Result<DrawioImportReport, AppError> {
+    storage::validate_environment_name(&environment)?;
+
+    run_blocking(app.clone(), move |state| {
+        import_diagram(&app, state, &environment, &file_path)
+    })
+    .await
+}
+
+/// Reads the diagram and saves its services and relationships, for `import_drawio`.
+fn import_diagram<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    file_path: &str,
+) -> Result<DrawioImportReport, AppError> {
+    let load_error = |reason: String| AppError::FileLoad {
+        path: file_path.to_string(),
+        reason,
+    };
+    let content = fs::read_to_string(file_path).map_err(|e| load_error(e.to_string()))?;
+    let diagram = drawio::parse(&content).map_err(load_error)?;
+
+    let mut state = write_state(state);
+    let services = state.storage.load_services(environment)?;
+    let relationships = state.storage.load_relationships(environment)?;
+    let mut merger = Merger::new(MERGE_STRATEGIES);
+    let mut skipped = Vec::new();
+
+    let (mut operations, service_ids, created_services) =
+        service_operations(&diagram, &services, &mut merger, &mut skipped);
+    let imported_services: HashSet<&String> = service_ids.values().collect();
+    let services_found = imported_services.len();
+    let relationship_operations = relationship_operations(
+        &diagram,
+        &relationships,
+        &service_ids,
+        &mut merger,
+        &mut skipped,
+    );
+    let relationships_found = relationship_operations.len();
+    operations.extend(relationship_operations.into_iter().flatten());
+
+    let plan = Plan::compute(&state, environment, operations)?;
+    let description = format!("Imported draw.io diagram {}", file_path);
+    let result = plan.apply(app, &mut state, environment, "import_drawio", description)?;
+    let counts = ImportCounts {
+        services: services_found,
+        relationships: relationships_found,
+    };
+    state.record_import(environment, IMPORT_SOURCE, counts);
+
+    Ok(DrawioImportReport {
+        services_found,
+        relationships_found,
+        created_services,
+        skipped,
+        conflicts: merger.into_conflicts(),
+        result,
+    })
+}
+
+/// Resolves each vertex to a service, merging it into the stored one.
+///
+/// Returns the saves of new and changed services, the service ID of each
+/// vertex's cell and the IDs of the new services.
+fn service_operations(
+    diagram: &Diagram,
+    services: &[Service],
+    merger: &mut Merger,
+    skipped: &mut Vec<SkippedCell>,
+) -> (Vec<Operation>, HashMap<String, String>, Vec<String>) {
+    let mut operations = Vec::new();
+    let mut service_ids = HashMap::new();
+    let mut created = Vec::new();
+    let mut seen = HashSet::new();
+
+    for node in &diagram.nodes {
+        let id = match &node.service_id {
+            Some(id) => id.clone(),
+            None => services
+                .iter()
+                .find(|s| s.name.trim().eq_ignore_ascii_case(&node.label))
+                .map(|s| s.id.clone())
+                .unwrap_or_else(|| storage::slugify(&node.label, storage::MAX_SLUG_LENGTH)),
+        };
+        if id.is_empty() {
+            skipped.push(SkippedCell {
+                cell_id: node.cell_id.clone(),
+                label: node.label.clone(),
+                reason: "The label has no letters or digits to make an ID of".to_string(),
+            });
+            continue;
+        }
+        service_ids.insert(node.cell_id.clone(), id.clone());
+        // draw.io copies of a vertex are the same service
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+
+        let stored = services.iter().find(|s| s.id == id);
+        let incoming = Service {
+            id: id.clone(),
+            name: node.label.clone(),
+            service_type: node.service_type.clone().unwrap_or_default(),
+            status: ServiceStatus::Unknown,
+            description: None,
+            version: None,
+            owner: None,
+            team: None,
+            tier: None,
+            lifecycle: None,
+            decommission_date: None,
+            successor_id: None,
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+            revision: 0,
+        };
+        if let Some(service) = merger.service(stored, incoming) {
+            if stored.is_none() {
+                created.push(id);
+            }
+            operations.push(Operation::SaveService {
+                service,
+                force: true,
+            });
+        }
+    }
+
+    (operations, service_ids, created)
+}
+
+/// Resolves each edge to a relationship, merging it into the stored one.
+///
+/// Returns one entry per imported edge: the save of the relationship, or
+/// `None` if the stored relationship is unchanged.
+fn relationship_operations(
+    diagram: &Diagram,
+    relationships: &[Relationship],
+    service_ids: &HashMap<String, String>,
+    merger: &mut Merger,
+    skipped: &mut Vec<SkippedCell>,
+) -> Vec<Option<Operation>> {
+    let mut operations = Vec::new();
+    let mut seen = HashSet::new();
+
+    for edge in &diagram.edges {
+        let service_of = |cell: &Option<String>| service_ids.get(cell.as_deref()?);
+        let (Some(source), Some(target)) = (service_of(&edge.source), service_of(&edge.target))
+        else {
+            skipped.push(SkippedCell {
+                cell_id: edge.cell_id.clone(),
+                label: edge.label.clone(),
+                reason: "The edge isn't connected to a service at both ends".to_string(),
+            });
+            continue;
+        };
+        if source == target {
+            skipped.push(SkippedCell {
+                cell_id: edge.cell_id.clone(),
+                label: edge.label.clone(),
+                reason: format!("The edge connects {} to itself", source),
+            });
+            continue;
+        }
+
+        let relationship_type = edge
+            .relationship_type
+            .clone()
+            .unwrap_or(RelationshipType::CommunicatesWith);
+        let stored = edge
+            .relationship_id
+            .as_ref()
+            .and_then(|id| relationships.iter().find(|r| r.id == *id))
+            .or_else(|| {
+                relationships.iter().find(|r| {
+                    r.source == *source
+                        && r.target == *target
+                        && r.relationship_type == relationship_type
+                })
+            });
+        let id = stored
+            .map(|r| r.id.clone())
+            .or_else(|| edge.relationship_id.clone())
+            .unwrap_or_else(storage::new_relationship_id);
+        // An edge drawn twice is one relationship
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+
+        let incoming = Relationship {
+            id,
+            source: source.clone(),
+            target: target.clone(),
+            relationship_type,
+            description: None,
+            metadata: None,
+            effective_from: None,
+            effective_until: None,
+            origin: RelationshipOrigin::Imported(IMPORT_SOURCE.to_string()),
+            review_status: ReviewStatus::Unreviewed,
+            review_note: None,
+            reviewed_by: None,
+            reviewed_at: None,
+            revision: 0,
+        };
+        operations.push(merger.relationship(stored, incoming).map(|relationship| {
+            Operation::SaveRelationship {
+                relationship,
+                force: false,
+            }
+        }));
+    }
+
+    operations
+}
@@ -0,0 +1,277 @@
+//! Drift checks of an environment against an export of an import source.
+//!
+//! Imports from Kubernetes and Consul run from time to time, and the
+//! source systems move on in between. `check_drift` compares a fresh
+//! export of the source with the environment without importing anything,
+//! e.g. for a "the map is 3 weeks stale and missing 4 services" banner. The
+//! export has the JSON shape the importer reads:
+//!
+//! - Kubernetes: a `List` of objects, e.g. the output of
+//!   `kubectl get deployments,statefulsets,services,ingresses -A -o json`
+//! - Consul: the catalog of tags by service name, as `/v1/catalog/services`
+//!   returns it
+//!
+//! Services and relationships are matched the way a re-import matches
+//! them, so what the report lists is what importing the export would
+//! create, tag `missing-from-cluster` or delete.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Runtime};
+
+use crate::commands::{consul as consul_commands, kubernetes as kubernetes_commands};
+use crate::consul;
+use crate::error::AppError;
+use crate::kubernetes;
+use crate::models::{Relationship, RelationshipType, Service};
+use crate::state::{cache, read_state, run_blocking, AppState};
+use crate::storage;
+
+/// An edge of the export without a relationship in the environment.
+///
+/// # Fields
+///
+/// * `source` / `target` - IDs of the services the edge connects
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftEdge {
+    pub source: String,
+    pub target: String,
+}
+
+/// How an environment differs from an export of an import source.
+///
+/// # Fields
+///
+/// * `source` - The import source the export was recognized as, e.g. `"kubernetes"`
+/// * `last_imported_at` - When the source last synced the environment, in
+///   milliseconds since the Unix epoch; `None` if no sync was recorded
+/// * `snapshot_services` - Number of services mapped from the export
+/// * `snapshot_relationships` - Number of edges mapped from the export
+/// * `missing_services` - IDs of the exported services the environment doesn't have
+/// * `extra_services` - IDs of services imported from the source that are no longer
+///   in the export
+/// * `missing_relationships` - Exported edges the environment doesn't have
+/// * `extra_relationships` - IDs of relationships imported from the source that the
+///   export no longer implies
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+    pub source: String,
+    pub last_imported_at: Option<u64>,
+    pub snapshot_services: usize,
+    pub snapshot_relationships: usize,
+    pub missing_services: Vec<String>,
+    pub extra_services: Vec<String>,
+    pub missing_relationships: Vec<DriftEdge>,
+    pub extra_relationships: Vec<String>,
+}
+
+/// Compares an environment with an export of an import source.
+///
+/// The source is recognized from the shape of the export (see the module
+/// documentation). For Kubernetes, only services and edges of the
+/// namespaces in the export count as extra, like a re-import of those
+/// namespaces; Consul has no edges. Nothing is written.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to compare on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `source_snapshot_path` - The exported JSON file
+///
+/// # Returns
+///
+/// * `Ok(DriftReport)` - What the environment lacks and has in excess
+/// * `Err(AppError::FileLoad)` - If the export cannot be read, isn't valid JSON or
+///   has neither shape
+/// * `Err(AppError::Io)` - If the environment's data cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const drift = await invoke('check_drift', {
+///     environment: 'prod',
+///     sourceSnapshotPath: '/tmp/prod-cluster.json'
+/// });
+/// console.log(`${drift.missingServices.length} services are missing from the map`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn check_drift<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    source_snapshot_path: String,
+) -> Result<DriftReport, AppError> {
+    run_blocking(app, move |state| {
+        drift(state, &environment, &source_snapshot_path)
+    })
+    .await
+}
+
+/// Reads the export and compares it, for `check_drift`.
+fn drift(
+    state: &RwLock<AppState>,
+    environment: &str,
+    snapshot_path: &str,
+) -> Result<DriftReport, AppError> {
+    storage::validate_environment_name(environment)?;
+    let load_error = |reason: String| AppError::FileLoad {
+        path: snapshot_path.to_string(),
+        reason,
+    };
+    let content = fs::read_to_string(snapshot_path).map_err(|e| load_error(e.to_string()))?;
+    let mut snapshot: Value =
+        serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))?;
+
+    let services = cache::shared_services(state, environment)?;
+    let relationships = cache::shared_relationships(state, environment)?;
+    // A Kubernetes List has a `kind`; a catalog only has arrays of tags
+    let mut report = if snapshot.get("kind").is_some_and(Value::is_string) {
+        let Some(Value::Array(items)) = snapshot.get_mut("items").map(Value::take) else {
+            return Err(load_error("The Kubernetes List has no items".to_string()));
+        };
+        kubernetes_drift(items, &services, &relationships).map_err(|e| load_error(e.to_string()))?
+    } else {
+        let catalog = serde_json::from_value(snapshot).map_err(|_| {
+            load_error(
+                "Expected a Kubernetes List or a Consul catalog of tags by service name"
+                    .to_string(),
+            )
+        })?;
+        consul_drift(catalog, &services)
+    };
+
+    let data_path = read_state(state).data_path.clone();
+    report.last_imported_at =
+        storage::environment_metadata::load_environment_metadata(&data_path, environment)?
+            .last_imports
+            .get(&report.source)
+            .copied();
+
+    Ok(report)
+}
+
+/// Compares the environment with the items of a Kubernetes `List`.
+fn kubernetes_drift(
+    items: Vec<Value>,
+    services: &[Arc<Service>],
+    relationships: &[Relationship],
+) -> Result<DriftReport, serde_json::Error> {
+    let namespaces: BTreeSet<String> = items
+        .iter()
+        .filter_map(|item| item.get("metadata")?.get("namespace")?.as_str())
+        .map(str::to_string)
+        .collect();
+    let topology = kubernetes::map_resources(&kubernetes::resources_from_list(items)?);
+
+    let namespace_of: HashMap<&str, &str> = services
+        .iter()
+        .filter_map(|s| Some((s.id.as_str(), kubernetes_commands::imported_namespace(s)?)))
+        .collect();
+    let in_snapshot = |id: &str| {
+        namespace_of
+            .get(id)
+            .is_some_and(|ns| namespaces.contains(*ns))
+    };
+
+    let ids: HashSet<&str> = services.iter().map(|s| s.id.as_str()).collect();
+    let mapped: HashSet<&str> = topology.services.iter().map(|s| s.id.as_str()).collect();
+    let missing_services = topology
+        .services
+        .iter()
+        .filter(|s| !ids.contains(s.id.as_str()))
+        .map(|s| s.id.clone())
+        .collect();
+    let mut extra_services: Vec<String> = services
+        .iter()
+        .filter(|s| !mapped.contains(s.id.as_str()) && in_snapshot(&s.id))
+        .map(|s| s.id.clone())
+        .collect();
+    extra_services.sort();
+
+    let existing: HashSet<(&str, &str)> = relationships
+        .iter()
+        .filter(|r| r.relationship_type == RelationshipType::CommunicatesWith)
+        .map(|r| (r.source.as_str(), r.target.as_str()))
+        .collect();
+    let missing_relationships = topology
+        .edges
+        .iter()
+        .filter(|edge| !existing.contains(&(edge.source.as_str(), edge.target.as_str())))
+        .map(|edge| DriftEdge {
+            source: edge.source.clone(),
+            target: edge.target.clone(),
+        })
+        .collect();
+
+    // Like a re-import, only edges from services of the exported namespaces
+    let inferred: HashSet<(&str, &str)> = topology
+        .edges
+        .iter()
+        .map(|edge| (edge.source.as_str(), edge.target.as_str()))
+        .collect();
+    let mut extra_relationships: Vec<String> = relationships
+        .iter()
+        .filter(|r| {
+            r.origin
+                .is_imported_from(kubernetes_commands::IMPORT_SOURCE)
+        })
+        .filter(|r| !inferred.contains(&(r.source.as_str(), r.target.as_str())))
+        .filter(|r| in_snapshot(&r.source))
+        .map(|r| r.id.clone())
+        .collect();
+    extra_relationships.sort();
+
+    Ok(DriftReport {
+        source: kubernetes_commands::IMPORT_SOURCE.to_string(),
+        last_imported_at: None,
+        snapshot_services: topology.services.len(),
+        snapshot_relationships: topology.edges.len(),
+        missing_services,
+        extra_services,
+        missing_relationships,
+        extra_relationships,
+    })
+}
+
+/// Compares the environment with a Consul catalog.
+fn consul_drift(catalog: BTreeMap<String, Vec<String>>, services: &[Arc<Service>]) -> DriftReport {
+    let registered = consul::services_from_catalog(catalog);
+
+    let ids: HashSet<&str> = services.iter().map(|s| s.id.as_str()).collect();
+    let imported_names: HashSet<&str> = services
+        .iter()
+        .filter_map(|s| consul_commands::consul_name(s))
+        .collect();
+    let mut missing_services: Vec<String> = registered
+        .iter()
+        .filter(|r| {
+            !imported_names.contains(r.consul_name.as_str()) && !ids.contains(r.service.id.as_str())
+        })
+        .map(|r| r.service.id.clone())
+        .collect();
+    missing_services.sort();
+
+    let names: HashSet<&str> = registered.iter().map(|r| r.consul_name.as_str()).collect();
+    let mut extra_services: Vec<String> = services
+        .iter()
+        .filter(|s| consul_commands::consul_name(s).is_some_and(|name| !names.contains(name)))
+        .map(|s| s.id.clone())
+        .collect();
+    extra_services.sort();
+
+    DriftReport {
+        source: consul_commands::IMPORT_SOURCE.to_string(),
+        last_imported_at: None,
+        snapshot_services: registered.len(),
+        snapshot_relationships: 0,
+        missing_services,
+        extra_services,
+        missing_relationships: Vec::new(),
+        extra_relationships: Vec::new(),
+    }
+}
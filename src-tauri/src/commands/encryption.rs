@@ -0,0 +1,226 @@
+//! Encryption commands for the Tauri application.
+//!
+//! This module lets the frontend unlock an encrypted data directory and
+//! convert environments between plaintext and encrypted storage (see the
+//! `storage::encryption` module for the file format).
+//!
+//! Encryption is opt-in: nothing is encrypted until a passphrase is set.
+//! While a passphrase is set, every file the application writes is
+//! encrypted; existing files are converted with `migrate_encryption`.
+
+use serde::Serialize;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::error::AppError;
+use crate::git;
+use crate::state::{read_state, run_blocking, write_state, AppState};
+use crate::storage::audit::AuditRecord;
+use crate::storage::{self, encryption};
+
+/// Sets the passphrase used to encrypt and decrypt the data directory.
+///
+/// Derives the encryption key with Argon2 and keeps it in memory for the
+/// rest of the session (or until the data path changes). The first
+/// passphrase set for a data directory becomes its passphrase; afterwards
+/// only the same passphrase is accepted.
+///
+/// # Arguments
+///
+/// * `state` - The application state to update
+/// * `passphrase` - The passphrase to derive the key from
+///
+/// # Returns
+///
+/// * `Ok(())` - If the key was derived and stored
+/// * `Err(AppError::ValidationError)` - If the passphrase is empty, or the data path is a
+///   SQLite database (which doesn't support encryption)
+/// * `Err(AppError::WrongPassphrase)` - If the data directory uses a different passphrase
+/// * `Err(AppError::Io)` - If the key file cannot be written
+///
+/// # Side Effects
+///
+/// - Creates `{data_path}/.encryption.json` (salt and key ID, never the key) on first use
+/// - Clears all caches so encrypted data is reloaded
+/// - All subsequent writes are encrypted
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('set_encryption_passphrase', { passphrase: 'correct horse battery staple' });
+/// ```
+#[tauri::command]
+pub fn set_encryption_passphrase(
+    state: State<'_, RwLock<AppState>>,
+    passphrase: String,
+) -> Result<(), AppError> {
+    let data_path = {
+        let state = read_state(&state);
+        match state.storage.as_file_system() {
+            Some(fs_backend) => fs_backend.data_path().to_path_buf(),
+            None => {
+                return Err(AppError::ValidationError(
+                    "Encryption is only available for directory data paths".to_string(),
+                ));
+            }
+        }
+    };
+
+    // Key derivation is deliberately slow, so don't hold the state lock for it
+    let key = encryption::unlock(&data_path, &passphrase)?;
+
+    let mut state = write_state(&state);
+    if state.data_path != data_path {
+        return Err(AppError::ValidationError(
+            "The data path changed while the passphrase was being checked".to_string(),
+        ));
+    }
+    state.set_encryption_key(Some(key));
+
+    Ok(())
+}
+
+/// Forgets the encryption passphrase for the rest of the session.
+///
+/// New writes are stored as plaintext again. Encrypted files stay encrypted
+/// and can no longer be read until the passphrase is set again; decrypt them
+/// first with `migrate_encryption` to turn encryption off for good.
+///
+/// # Arguments
+///
+/// * `state` - The application state to update
+///
+/// # Returns
+///
+/// * `Ok(())` - Always
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('migrate_encryption', { environment: 'dev', enable: false });
+/// await invoke('clear_encryption_passphrase');
+/// ```
+#[tauri::command]
+pub fn clear_encryption_passphrase(state: State<'_, RwLock<AppState>>) -> Result<(), AppError> {
+    let mut state = write_state(&state);
+    state.set_encryption_key(None);
+    Ok(())
+}
+
+/// The result of converting an environment's files.
+///
+/// # Fields
+///
+/// * `environment` - The environment that was converted
+/// * `enabled` - True if files were encrypted, false if they were decrypted
+/// * `files` - Paths of the files that were converted
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionMigrationReport {
+    pub environment: String,
+    pub enabled: bool,
+    pub files: Vec<String>,
+}
+
+/// Encrypts or decrypts an environment's existing files in place.
+///
+/// Requires the passphrase to be set in both directions. Files already in
+/// the requested form are skipped, so mixed environments are handled and
+/// the command can be re-run after an interruption.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment to convert
+/// * `enable` - True to encrypt plaintext files, false to decrypt encrypted files
+///
+/// # Returns
+///
+/// * `Ok(EncryptionMigrationReport)` - The files that were converted
+/// * `Err(AppError::EncryptionRequired)` - If no passphrase has been set
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory
+///   name, or the data path is a SQLite database
+/// * `Err(AppError::WrongPassphrase)` - If a file was encrypted with a different passphrase
+/// * `Err(AppError::FileLoad)` - If an encrypted file is corrupt
+/// * `Err(AppError::Io)` - If there's an error reading or writing files
+///
+/// # Side Effects
+///
+/// - Rewrites the environment's service files and `relationships.json` in place
+/// - Clears the environment's caches when anything was converted
+/// - Appends the conversion to the environment's audit log
+/// - Commits all converted files together to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('set_encryption_passphrase', { passphrase });
+/// const report = await invoke('migrate_encryption', { environment: 'prod', enable: true });
+/// console.log(`Encrypted ${report.files.length} files`);
+/// ```
+#[tauri::command]
+pub async fn migrate_encryption<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    enable: bool,
+) -> Result<EncryptionMigrationReport, AppError> {
+    run_blocking(app.clone(), move |state| {
+        convert_files(&app, state, environment, enable)
+    })
+    .await
+}
+
+/// Converts the environment's files, for `migrate_encryption`.
+fn convert_files<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: String,
+    enable: bool,
+) -> Result<EncryptionMigrationReport, AppError> {
+    let mut state = write_state(state);
+
+    let fs_backend = state.storage.as_file_system().cloned().ok_or_else(|| {
+        AppError::ValidationError(
+            "Encryption is only available for directory data paths".to_string(),
+        )
+    })?;
+    let key = fs_backend.encryption_key().ok_or_else(|| {
+        AppError::EncryptionRequired(fs_backend.data_path().display().to_string())
+    })?;
+
+    let converted =
+        storage::convert_environment_encryption(fs_backend.data_path(), &environment, key, enable)?;
+
+    if !converted.is_empty() {
+        state.record_write(&environment);
+        state.clear_environment_cache(&environment);
+
+        let action = if enable { "Encrypt" } else { "Decrypt" };
+        state.record_audit(
+            &environment,
+            &[
+                AuditRecord::new("migrate_encryption", Vec::new()).with_change(
+                    None,
+                    Some(format!("{}ed {} files", action, converted.len())),
+                ),
+            ],
+        );
+        git::auto_commit(
+            app,
+            &state,
+            "migrate_encryption",
+            format!("{} {} files in {}", action, converted.len(), environment),
+            converted.clone(),
+        );
+    }
+
+    Ok(EncryptionMigrationReport {
+        environment,
+        enabled: enable,
+        files: converted.iter().map(|p| p.display().to_string()).collect(),
+    })
+}
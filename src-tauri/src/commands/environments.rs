@@ -4,13 +4,26 @@
 //! (e.g., dev, staging, production). Each environment has its own isolated set of
 //! services and relationships stored in separate directories.
 
-use std::fs;
-use std::path::PathBuf;
-use std::sync::Mutex;
-use tauri::State;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Manager, Runtime, State};
 
+use crate::commands::{batch, settings};
 use crate::error::AppError;
-use crate::state::AppState;
+use crate::git;
+use crate::models::{RelationshipLayout, Service, ServiceStatus};
+use crate::sample::{self, SampleSpec, MAX_SAMPLE_SERVICES};
+use crate::state::diagnostics::CommandTimer;
+use crate::state::journal::{Change, JournalEntry};
+use crate::state::progress::Progress;
+use crate::state::{cache, read_state, run_blocking, write_state, AppState};
+use crate::storage::audit::AuditRecord;
+use crate::storage::report::{self, DataPathPreflight};
+use crate::storage::{self, migrations::FileMigration, migrations::CURRENT_SCHEMA_VERSION};
+use crate::watcher::DataWatcher;
+use crate::webhooks::WebhookSender;
 
 /// Lists all available environments in the data directory.
 ///
@@ -25,7 +38,6 @@ use crate::state::AppState;
 /// # Returns
 ///
 /// * `Ok(Vec<String>)` - A sorted list of environment names
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 /// * `Err(AppError::Io)` - If there's an error reading the data directory
 ///
 /// # Sorting Order
@@ -44,26 +56,14 @@ use crate::state::AppState;
 /// // Returns: ['dev', 'staging', 'prod', 'feature-branch']
 /// ```
 #[tauri::command]
-pub fn list_environments(state: State<'_, Mutex<AppState>>) -> Result<Vec<String>, AppError> {
-    let state = state.lock().map_err(|_| AppError::StateLock)?;
-
-    let mut environments = Vec::new();
-
-    if state.data_path.exists() {
-        for entry in fs::read_dir(&state.data_path)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_dir() {
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    // Skip hidden directories
-                    if !name.starts_with('.') {
-                        environments.push(name.to_string());
-                    }
-                }
-            }
-        }
-    }
+pub fn list_environments(state: State<'_, RwLock<AppState>>) -> Result<Vec<String>, AppError> {
+    let _timer = CommandTimer::start(&state, "list_environments");
+    sorted_environments(&read_state(&state))
+}
+
+/// Lists the environments in `list_environments`' order, for it and the HTTP API.
+pub fn sorted_environments(state: &AppState) -> Result<Vec<String>, AppError> {
+    let mut environments = state.storage.list_environments()?;
 
     // Sort environments in a sensible order
     environments.sort_by(|a, b| {
@@ -95,7 +95,6 @@ pub fn list_environments(state: State<'_, Mutex<AppState>>) -> Result<Vec<String
 /// # Returns
 ///
 /// * `Ok(String)` - The name of the current environment
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 ///
 /// # Examples
 ///
@@ -105,8 +104,8 @@ pub fn list_environments(state: State<'_, Mutex<AppState>>) -> Result<Vec<String
 /// console.log(`Currently viewing: ${currentEnv}`); // "dev"
 /// ```
 #[tauri::command]
-pub fn get_current_environment(state: State<'_, Mutex<AppState>>) -> Result<String, AppError> {
-    let state = state.lock().map_err(|_| AppError::StateLock)?;
+pub fn get_current_environment(state: State<'_, RwLock<AppState>>) -> Result<String, AppError> {
+    let state = read_state(&state);
     Ok(state.current_environment.clone())
 }
 
@@ -115,45 +114,67 @@ pub fn get_current_environment(state: State<'_, Mutex<AppState>>) -> Result<Stri
 /// Changes the current environment context for all subsequent operations.
 /// The target environment must already exist as a directory in the data path.
 /// This does NOT clear caches - cached data from other environments remains
-/// available for quick switching.
+/// available for quick switching. With `preload`, the new environment is
+/// loaded into the caches in the background (see `preload_environment`).
 ///
 /// # Arguments
 ///
+/// * `app` - The application handle, used to reach the state from the preloading thread
 /// * `state` - The application state to update
 /// * `environment` - The name of the environment to switch to
+/// * `preload` - If true, warm the environment's caches after switching (default: false)
 ///
 /// # Returns
 ///
 /// * `Ok(())` - If the environment was successfully switched
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
 /// * `Err(AppError::EnvironmentNotFound)` - If the specified environment doesn't exist
 ///
 /// # Side Effects
 ///
 /// - Updates the `current_environment` field in the application state
 /// - Does NOT clear the services or relationships cache
+/// - With `preload`, fills the environment's caches on a background thread
 ///
 /// # Examples
 ///
 /// ```typescript
 /// // From the frontend:
-/// await invoke('switch_environment', { environment: 'staging' });
+/// await invoke('switch_environment', { environment: 'staging', preload: true });
 /// // All subsequent service/relationship queries will use 'staging'
 /// ```
 #[tauri::command]
-pub fn switch_environment(
-    state: State<'_, Mutex<AppState>>,
+pub fn switch_environment<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
+    preload: Option<bool>,
 ) -> Result<(), AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
+    let _timer = CommandTimer::start(&state, "switch_environment");
+    storage::validate_environment_name(&environment)?;
 
-    // Verify environment exists
-    let env_path = state.data_path.join(&environment);
-    if !env_path.exists() {
-        return Err(AppError::EnvironmentNotFound(environment));
+    {
+        let mut state = write_state(&state);
+
+        // Verify environment exists
+        if !state.storage.environment_exists(&environment)? {
+            return Err(AppError::EnvironmentNotFound(environment));
+        }
+
+        state.current_environment = environment.clone();
     }
 
-    state.current_environment = environment;
+    if preload.unwrap_or(false) {
+        tauri::async_runtime::spawn_blocking(move || {
+            let state = app.state::<RwLock<AppState>>();
+            if let Err(e) = crate::commands::cache::preload(&state, &environment) {
+                eprintln!(
+                    "Warning: Failed to preload environment {}: {}",
+                    environment, e
+                );
+            }
+        });
+    }
 
     Ok(())
 }
@@ -172,16 +193,21 @@ pub fn switch_environment(
 /// # Returns
 ///
 /// * `Ok(())` - If the environment was successfully created
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
 /// * `Err(AppError::EnvironmentExists)` - If an environment with that name already exists
 /// * `Err(AppError::Io)` - If there's an error creating directories or files
 ///
+/// # Side Effects
+///
+/// - Appends the creation to the new environment's audit log
+///
 /// # Directory Structure Created
 ///
 /// ```text
 /// {data_path}/{environment}/
 /// ├── services/
-/// └── relationships.json
+/// ├── relationships.json
+/// └── .audit.jsonl
 /// ```
 ///
 /// # Examples
@@ -192,28 +218,21 @@ pub fn switch_environment(
 /// ```
 #[tauri::command]
 pub fn create_environment(
-    state: State<'_, Mutex<AppState>>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
 ) -> Result<(), AppError> {
-    let state = state.lock().map_err(|_| AppError::StateLock)?;
-
-    let env_path = state.data_path.join(&environment);
-
-    // Check if environment already exists
-    if env_path.exists() {
-        return Err(AppError::EnvironmentExists(environment));
-    }
+    storage::validate_environment_name(&environment)?;
 
-    // Create the environment directory
-    fs::create_dir_all(&env_path)?;
+    let mut state = write_state(&state);
 
-    // Create the services subdirectory
-    let services_path = env_path.join("services");
-    fs::create_dir_all(&services_path)?;
+    state.storage.create_environment(&environment)?;
 
-    // Create an empty relationships.json file
-    let relationships_path = env_path.join("relationships.json");
-    fs::write(&relationships_path, "[]")?;
+    state.record_write(&environment);
+    state.record_audit(
+        &environment,
+        &[AuditRecord::new("create_environment", Vec::new())
+            .with_change(None, Some(format!("Environment {}", environment)))],
+    );
 
     Ok(())
 }
@@ -222,23 +241,30 @@ pub fn create_environment(
 ///
 /// Changes the base directory where all environment folders are located.
 /// This clears all cached data since the cache would be invalid for the new
-/// location. The path must point to an existing directory.
+/// location. The path must point to an existing directory, or to a SQLite
+/// database file (`.db`, `.sqlite`, `.sqlite3`) in an existing directory to
+/// use the SQLite storage backend.
 ///
 /// # Arguments
 ///
+/// * `app` - The application handle, used to re-arm the data directory watcher
 /// * `state` - The application state to update
-/// * `path` - The absolute path to the new data directory
+/// * `path` - The absolute path to the new data directory or database file
 ///
 /// # Returns
 ///
 /// * `Ok(())` - If the data path was successfully updated
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 /// * `Err(AppError::InvalidPath)` - If the path doesn't exist or isn't a directory
+///   (for database files: if the containing directory doesn't exist)
 ///
 /// # Side Effects
 ///
 /// - Clears all cached services and relationships
-/// - Updates the `data_path` field in the application state
+/// - Updates the `data_path` field and storage backend in the application state
+/// - Moves the data directory watcher to the new path
+/// - Adds the path to the recently used data paths (see `get_recent_data_paths`)
+/// - If a workspace is active, saves the new path as that workspace's path
+/// - Records the validation error counts at the new path for webhooks
 ///
 /// # Directory Structure Expected
 ///
@@ -264,22 +290,1120 @@ pub fn create_environment(
 /// });
 /// ```
 #[tauri::command]
-pub fn set_data_path(state: State<'_, Mutex<AppState>>, path: String) -> Result<(), AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
+pub fn set_data_path<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    path: String,
+) -> Result<(), AppError> {
+    let _timer = CommandTimer::start(&state, "set_data_path");
+    let mut state = write_state(&state);
 
-    let path_buf = PathBuf::from(&path);
+    let path_buf = check_data_path(&path)?;
 
-    if !path_buf.exists() {
-        return Err(AppError::InvalidPath(path));
+    // Remember the path, as the active workspace's path if one is active
+    let mut settings = state.settings.clone();
+    settings.record_data_path(&path);
+    if let Some(name) = state.workspace.clone() {
+        if let Some(workspace) = settings.workspaces.iter_mut().find(|w| w.name == name) {
+            workspace.path = path.clone();
+        }
     }
+    settings::store_settings(&mut state, settings)?;
 
-    if !path_buf.is_dir() {
-        return Err(AppError::InvalidPath(format!("{} is not a directory", path)));
+    // Clears caches and opens the backend for the new location
+    state.set_data_path(path_buf);
+    watch_data_path(&app, &state.data_path);
+    drop(state);
+    if let Some(webhook_sender) = app.try_state::<WebhookSender>() {
+        webhook_sender.record_baselines();
     }
 
-    // Clear caches when changing data path
-    state.clear_cache();
-    state.data_path = path_buf;
-
     Ok(())
 }
+
+/// Inspects a candidate data directory without switching to it.
+///
+/// Reports which subdirectories look like environments (they have a
+/// `services/` directory or a `relationships.json`), how many service files
+/// they hold, and warnings for an empty or suspicious structure, such as an
+/// environment directory picked instead of the data directory. Use it
+/// before `set_data_path` to tell the user why a folder would show no data.
+///
+/// # Arguments
+///
+/// * `path` - The candidate data directory or SQLite database file
+///
+/// # Returns
+///
+/// * `Ok(DataPathPreflight)` - What the path contains; `usable` is false if
+///   `set_data_path` would reject it
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const preflight = await invoke('validate_data_path', { path: selectedFolder });
+/// if (preflight.warnings.length > 0) {
+///     showWarnings(preflight.warnings);
+/// }
+/// ```
+#[tauri::command]
+pub fn validate_data_path(path: String) -> Result<DataPathPreflight, AppError> {
+    Ok(report::preflight_data_path(Path::new(&path)))
+}
+
+/// Lists the recently used data paths that still exist.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` - Up to 10 paths, most recently set first
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const paths = await invoke('get_recent_data_paths');
+/// ```
+#[tauri::command]
+pub fn get_recent_data_paths(state: State<'_, RwLock<AppState>>) -> Result<Vec<String>, AppError> {
+    let state = read_state(&state);
+    Ok(state
+        .settings
+        .recent_data_paths
+        .iter()
+        .filter(|path| Path::new(path).exists())
+        .cloned()
+        .collect())
+}
+
+/// Checks that a path can be used as the data path.
+///
+/// The path must be an existing directory, or a SQLite database file whose
+/// directory exists (the file is created on first use).
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - The path
+/// * `Err(AppError::InvalidPath)` - If the path can't be used
+pub fn check_data_path(path: &str) -> Result<PathBuf, AppError> {
+    let path_buf = PathBuf::from(path);
+
+    if storage::is_sqlite_path(&path_buf) {
+        // The database file is created on first use, but its folder must exist
+        let parent_exists = path_buf.parent().map(|p| p.is_dir()).unwrap_or(false);
+        if !parent_exists {
+            return Err(AppError::InvalidPath(path.to_string()));
+        }
+    } else {
+        if !path_buf.exists() {
+            return Err(AppError::InvalidPath(path.to_string()));
+        }
+
+        if !path_buf.is_dir() {
+            return Err(AppError::InvalidPath(format!(
+                "{} is not a directory",
+                path
+            )));
+        }
+    }
+
+    Ok(path_buf)
+}
+
+/// Moves the data directory watcher to a new data path.
+///
+/// A failed re-arm is only logged, as it only costs external change detection.
+pub fn watch_data_path<R: Runtime>(app: &AppHandle<R>, data_path: &Path) {
+    if let Some(watcher) = app.try_state::<Mutex<DataWatcher>>() {
+        if let Ok(mut watcher) = watcher.lock() {
+            if let Err(e) = watcher.watch(data_path) {
+                eprintln!("Warning: failed to watch data directory: {}", e);
+            }
+        }
+    }
+}
+
+/// The result of migrating an environment's files to the current schema.
+///
+/// # Fields
+///
+/// * `environment` - The environment that was migrated
+/// * `target_version` - The schema version files were upgraded to
+/// * `files` - Each file that was (or would be) rewritten and what changed
+/// * `dry_run` - Whether the run only reported changes without applying them
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    pub environment: String,
+    pub target_version: u32,
+    pub files: Vec<FileMigration>,
+    pub dry_run: bool,
+}
+
+/// Rewrites an environment's data files at the latest schema version.
+///
+/// Loading already applies migrations in memory, so this command is only
+/// needed to persist the upgrade (e.g., before committing the data
+/// directory). Files that are already at the current version are skipped.
+/// With the SQLite backend there are no files to rewrite, so the report is
+/// always empty.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment to migrate
+/// * `dry_run` - When true, report what would change without writing anything
+///
+/// # Returns
+///
+/// * `Ok(MigrationReport)` - The files that were (or would be) migrated
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::FileLoad)` - If a file cannot be read, migrated, or parsed
+/// * `Err(AppError::Io)` - If there's an error writing files
+///
+/// # Side Effects
+///
+/// - Rewrites outdated service files and `relationships.json` in place
+/// - Clears the environment's caches when anything was written
+/// - Appends the migration to the environment's audit log
+/// - Commits all rewritten files together to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('migrate_environment', { environment: 'dev', dryRun: true });
+/// console.log(`${report.files.length} files need migration`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn migrate_environment<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    dry_run: bool,
+) -> Result<MigrationReport, AppError> {
+    run_blocking(app.clone(), move |state| {
+        migrate_files(&app, state, environment, dry_run)
+    })
+    .await
+}
+
+/// Rewrites the outdated files, for `migrate_environment`.
+fn migrate_files<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: String,
+    dry_run: bool,
+) -> Result<MigrationReport, AppError> {
+    let mut state = write_state(state);
+
+    let files = match state.storage.as_file_system() {
+        Some(fs_backend) => storage::migrate_environment_files(
+            fs_backend.data_path(),
+            &environment,
+            fs_backend.encryption_key(),
+            dry_run,
+        )?,
+        None => {
+            storage::validate_environment_name(&environment)?;
+            Vec::new()
+        }
+    };
+
+    if !dry_run && !files.is_empty() {
+        state.record_write(&environment);
+        state.clear_environment_cache(&environment);
+        state.record_audit(
+            &environment,
+            &[
+                AuditRecord::new("migrate_environment", Vec::new()).with_change(
+                    None,
+                    Some(format!(
+                        "Migrated {} files to schema version {}",
+                        files.len(),
+                        CURRENT_SCHEMA_VERSION
+                    )),
+                ),
+            ],
+        );
+
+        git::auto_commit(
+            app,
+            &state,
+            "migrate_environment",
+            format!(
+                "Migrate {} files in {} to schema version {}",
+                files.len(),
+                environment,
+                CURRENT_SCHEMA_VERSION
+            ),
+            files.iter().map(|f| PathBuf::from(&f.path)).collect(),
+        );
+    }
+
+    Ok(MigrationReport {
+        environment,
+        target_version: CURRENT_SCHEMA_VERSION,
+        files,
+        dry_run,
+    })
+}
+
+/// The result of normalizing an environment's data files.
+///
+/// # Fields
+///
+/// * `environment` - The environment that was normalized
+/// * `files` - The files that were (or would be) rewritten
+/// * `dry_run` - Whether the run only reported changes without applying them
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizationReport {
+    pub environment: String,
+    pub files: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// Rewrites an environment's data files in the canonical form.
+///
+/// Every save writes services and relationships the same way: fields in a
+/// fixed order, tags, metadata keys and relationships (by source, target
+/// and type) sorted, two-space indentation and a trailing newline. Files
+/// written by older versions or edited by hand are brought into that form,
+/// so git diffs of the data directory only show real changes; files that
+/// already are in it are left alone. With the SQLite backend there are no
+/// files to rewrite, so the report is always empty.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment to normalize
+/// * `dry_run` - When true, report which files would change without writing anything
+///
+/// # Returns
+///
+/// * `Ok(NormalizationReport)` - The files that were (or would be) rewritten
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::FileLoad)` - If a file cannot be read, migrated, or parsed
+/// * `Err(AppError::Io)` - If there's an error writing files
+///
+/// # Side Effects
+///
+/// - Rewrites the service and relationships files that aren't canonical, in place
+/// - Clears the environment's caches when anything was written
+/// - Appends the normalization to the environment's audit log
+/// - Commits all rewritten files together to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('normalize_environment_files', {
+///     environment: 'dev',
+///     dryRun: true
+/// });
+/// console.log(`${report.files.length} files would be rewritten`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn normalize_environment_files<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    dry_run: bool,
+) -> Result<NormalizationReport, AppError> {
+    run_blocking(app.clone(), move |state| {
+        normalize_files(&app, state, environment, dry_run)
+    })
+    .await
+}
+
+/// Rewrites the files that aren't canonical, for `normalize_environment_files`.
+fn normalize_files<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: String,
+    dry_run: bool,
+) -> Result<NormalizationReport, AppError> {
+    let mut state = write_state(state);
+
+    let paths = match state.storage.as_file_system() {
+        Some(fs_backend) => storage::normalize_environment_files(
+            fs_backend.data_path(),
+            &environment,
+            fs_backend.encryption_key(),
+            dry_run,
+        )?,
+        None => {
+            storage::validate_environment_name(&environment)?;
+            Vec::new()
+        }
+    };
+
+    if !dry_run && !paths.is_empty() {
+        state.record_write(&environment);
+        state.clear_environment_cache(&environment);
+        state.record_audit(
+            &environment,
+            &[AuditRecord::new("normalize_environment_files", Vec::new())
+                .with_change(None, Some(format!("Normalized {} files", paths.len())))],
+        );
+
+        git::auto_commit(
+            app,
+            &state,
+            "normalize_environment_files",
+            format!("Normalize {} files in {}", paths.len(), environment),
+            paths.clone(),
+        );
+    }
+
+    Ok(NormalizationReport {
+        environment,
+        files: paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect(),
+        dry_run,
+    })
+}
+
+/// The result of switching an environment's relationship storage layout.
+///
+/// # Fields
+///
+/// * `environment` - The environment that was migrated
+/// * `layout` - The layout the environment uses now
+/// * `files` - The files that were written or removed (empty if the
+///   environment already used the layout)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelationshipStorageMigration {
+    pub environment: String,
+    pub layout: RelationshipLayout,
+    pub files: Vec<String>,
+}
+
+/// Switches an environment between single-file and sharded relationship storage.
+///
+/// The sharded layout stores each source service's relationships in
+/// `relationships/{source_service_id}.json` instead of one
+/// `relationships.json`, so saving a relationship only rewrites the file of
+/// its source. The layout is recorded in the environment's
+/// `environment.json`; every command reads and writes both layouts the same
+/// way. The new files are complete before the old ones are removed, so an
+/// interrupted migration can simply be re-run. Sharding is only available
+/// for directory data paths.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment to migrate
+/// * `sharded` - True for one file per source service, false for a single `relationships.json`
+///
+/// # Returns
+///
+/// * `Ok(RelationshipStorageMigration)` - The layout now in use and the files that changed
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name,
+///   a source service ID can't be used as a file name, or the data path is a SQLite database
+/// * `Err(AppError::FileLoad)` - If the relationships or the environment metadata cannot be read
+/// * `Err(AppError::Io)` - If there's an error writing or removing files
+///
+/// # Side Effects
+///
+/// - Writes the relationships in the new layout, updates `environment.json` and
+///   removes the files of the old layout
+/// - Clears the environment's caches when anything changed
+/// - Appends the migration to the environment's audit log
+/// - Commits all changed files together to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const migration = await invoke('migrate_relationship_storage', {
+///     environment: 'prod',
+///     sharded: true
+/// });
+/// console.log(`${migration.files.length} files changed`);
+/// ```
+#[tauri::command]
+pub async fn migrate_relationship_storage<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    sharded: bool,
+) -> Result<RelationshipStorageMigration, AppError> {
+    let layout = if sharded {
+        RelationshipLayout::Sharded
+    } else {
+        RelationshipLayout::Single
+    };
+
+    run_blocking(app.clone(), move |state| {
+        switch_relationship_layout(&app, state, environment, layout)
+    })
+    .await
+}
+
+/// Moves the relationships to the new layout, for `migrate_relationship_storage`.
+fn switch_relationship_layout<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: String,
+    layout: RelationshipLayout,
+) -> Result<RelationshipStorageMigration, AppError> {
+    let mut state = write_state(state);
+
+    let paths = match state.storage.as_file_system() {
+        Some(fs_backend) => storage::migrate_relationship_layout(
+            fs_backend.data_path(),
+            &environment,
+            layout,
+            fs_backend.encryption_key(),
+        )?,
+        None if layout == RelationshipLayout::Sharded => {
+            return Err(AppError::ValidationError(
+                "Sharded relationship storage is only available for directory data paths"
+                    .to_string(),
+            ));
+        }
+        None => {
+            storage::validate_environment_name(&environment)?;
+            Vec::new()
+        }
+    };
+
+    if !paths.is_empty() {
+        let description = match layout {
+            RelationshipLayout::Sharded => "Sharded relationships by source service",
+            RelationshipLayout::Single => "Merged relationship shards into relationships.json",
+        };
+
+        state.record_write(&environment);
+        state.clear_environment_cache(&environment);
+        state.record_audit(
+            &environment,
+            &[AuditRecord::new("migrate_relationship_storage", Vec::new())
+                .with_change(None, Some(description.to_string()))],
+        );
+
+        git::auto_commit(
+            app,
+            &state,
+            "migrate_relationship_storage",
+            format!("{} in {}", description, environment),
+            paths.clone(),
+        );
+    }
+
+    Ok(RelationshipStorageMigration {
+        environment,
+        layout,
+        files: paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+    })
+}
+
+/// The result of importing a data directory into a SQLite database.
+///
+/// # Fields
+///
+/// * `db_path` - The database file that was written
+/// * `environments` - Names of the environments that were imported
+/// * `services_imported` - Total number of services imported
+/// * `relationships_imported` - Total number of relationships imported
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteMigrationReport {
+    pub db_path: String,
+    pub environments: Vec<String>,
+    pub services_imported: usize,
+    pub relationships_imported: usize,
+}
+
+/// Imports a JSON data directory into a SQLite database file.
+///
+/// Copies every environment's services and relationships into the database,
+/// replacing any data it already holds for the same environments. The source
+/// directory is left untouched; call `set_data_path` with the database path
+/// afterwards to switch to the SQLite backend.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to import on the blocking thread pool
+/// * `data_path` - The data directory to import
+/// * `db_path` - The database file to write (defaults to `{data_path}.db`)
+///
+/// # Returns
+///
+/// * `Ok(SqliteMigrationReport)` - What was imported and where
+/// * `Err(AppError::InvalidPath)` - If the data path isn't a directory, or the
+///   database path doesn't have a SQLite extension
+/// * `Err(AppError::FileLoad)` - If a source file cannot be read or parsed
+/// * `Err(AppError::Database)` - If writing the database fails
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('migrate_to_sqlite', {
+///     dataPath: '/Users/user/projects/my-app/service-data'
+/// });
+/// await invoke('set_data_path', { path: report.dbPath });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn migrate_to_sqlite<R: Runtime>(
+    app: AppHandle<R>,
+    data_path: String,
+    db_path: Option<String>,
+) -> Result<SqliteMigrationReport, AppError> {
+    run_blocking(app, move |_| import_into_sqlite(data_path, db_path)).await
+}
+
+/// Copies a data directory into a database file, for `migrate_to_sqlite`.
+fn import_into_sqlite(
+    data_path: String,
+    db_path: Option<String>,
+) -> Result<SqliteMigrationReport, AppError> {
+    let source_path = PathBuf::from(&data_path);
+    if !source_path.is_dir() {
+        return Err(AppError::InvalidPath(format!(
+            "{} is not a directory",
+            data_path
+        )));
+    }
+
+    let db_path = match db_path {
+        Some(db_path) => PathBuf::from(db_path),
+        None => source_path.with_extension("db"),
+    };
+    if !storage::is_sqlite_path(&db_path) {
+        return Err(AppError::InvalidPath(format!(
+            "{} is not a SQLite database path",
+            db_path.display()
+        )));
+    }
+
+    let source = storage::FileSystemBackend::new(source_path);
+    let counts = storage::SqliteBackend::new(db_path.clone()).import_directory(&source)?;
+
+    Ok(SqliteMigrationReport {
+        db_path: db_path.display().to_string(),
+        environments: counts.environments,
+        services_imported: counts.services,
+        relationships_imported: counts.relationships,
+    })
+}
+
+/// Entity counts for an environment.
+///
+/// # Fields
+///
+/// * `service_count` - Number of services
+/// * `relationship_count` - Number of relationships
+/// * `status_counts` - Number of services per status (statuses without services are omitted)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentCounts {
+    pub service_count: usize,
+    pub relationship_count: usize,
+    pub status_counts: HashMap<ServiceStatus, usize>,
+}
+
+/// Counts the services and relationships in an environment.
+///
+/// Meant for badges and summaries that don't need the data itself. Counts
+/// are computed from the caches without cloning any services or
+/// relationships; a cold cache is loaded first (and stays warm for the
+/// views that follow).
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment to count
+///
+/// # Returns
+///
+/// * `Ok(EnvironmentCounts)` - The counts
+/// * `Err(AppError::FileLoad)` - If a data file cannot be read or parsed
+/// * `Err(AppError::Io)` - If there's an error reading from the filesystem
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const counts = await invoke('get_counts', { environment: 'dev' });
+/// console.log(`${counts.serviceCount} services, ${counts.statusCounts.unhealthy ?? 0} unhealthy`);
+/// ```
+#[tauri::command]
+pub fn get_counts(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<EnvironmentCounts, AppError> {
+    let _timer = CommandTimer::start(&state, "get_counts");
+    environment_counts(&state, &environment)
+}
+
+/// Counts an environment's entities, for `get_counts` and the command-line `stats`.
+pub fn environment_counts(
+    state: &RwLock<AppState>,
+    environment: &str,
+) -> Result<EnvironmentCounts, AppError> {
+    let statuses = cache::map_services(state, environment, |s| s.status.clone())?;
+    let relationship_count = cache::with_relationships(state, environment, |r| r.len())?;
+
+    let service_count = statuses.len();
+    let mut status_counts = HashMap::new();
+    for status in statuses {
+        *status_counts.entry(status).or_insert(0) += 1;
+    }
+
+    Ok(EnvironmentCounts {
+        service_count,
+        relationship_count,
+        status_counts,
+    })
+}
+
+/// The result of generating a sample environment.
+///
+/// # Fields
+///
+/// * `environment` - The environment that was written
+/// * `services` - Number of services generated
+/// * `relationships` - Number of relationships generated
+/// * `replaced_services` - Number of previously existing services that were removed
+/// * `dry_run` - True if nothing was written
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleEnvironmentReport {
+    pub environment: String,
+    pub services: usize,
+    pub relationships: usize,
+    pub replaced_services: usize,
+    pub dry_run: bool,
+}
+
+/// Fills an environment with generated sample data.
+///
+/// Meant for demos and stress-testing the UI without real data. The data is
+/// generated from `spec` (see `SampleSpec`) and is reproducible: the same
+/// spec and seed always produce the same services and relationships. Data
+/// is written through the regular save paths, so the file layout is the
+/// same as for hand-made environments.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The environment to fill; created if it doesn't exist
+/// * `spec` - What to generate (all fields optional; defaults to 50 services)
+/// * `overwrite` - Replace the data of an environment that already has services
+///   or relationships (default false)
+/// * `dry_run` - If true, only report what would be generated and replaced (default false)
+///
+/// # Returns
+///
+/// * `Ok(SampleEnvironmentReport)` - What was generated (or would be)
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory
+///   name, the spec asks for too many services, or the environment is not empty
+///   and `overwrite` wasn't set
+/// * `Err(AppError::Io)` - If there's an error writing to the filesystem
+///
+/// # Side Effects
+///
+/// Unless `dry_run` is set:
+/// - Creates the environment if it doesn't exist
+/// - Writes one service file per generated service and replaces `relationships.json`
+/// - With `overwrite`, deletes existing services that the sample doesn't replace
+/// - Updates the environment's caches
+/// - Records the whole generation as a single entry in the undo journal
+/// - Appends one audit log record per changed service and relationship
+/// - Commits all written files together to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('generate_sample_environment', {
+///     environment: 'demo',
+///     spec: { serviceCount: 5000, seed: 7, teamCount: 12, includeCycles: true },
+///     overwrite: true
+/// });
+/// console.log(`Generated ${report.services} services`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn generate_sample_environment<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    spec: Option<SampleSpec>,
+    overwrite: Option<bool>,
+    dry_run: Option<bool>,
+) -> Result<SampleEnvironmentReport, AppError> {
+    run_blocking(app.clone(), move |state| {
+        write_sample_environment(&app, state, environment, spec, overwrite, dry_run)
+    })
+    .await
+}
+
+/// Generates and writes the sample, for `generate_sample_environment`.
+fn write_sample_environment<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: String,
+    spec: Option<SampleSpec>,
+    overwrite: Option<bool>,
+    dry_run: Option<bool>,
+) -> Result<SampleEnvironmentReport, AppError> {
+    storage::validate_environment_name(&environment)?;
+    let dry_run = dry_run.unwrap_or(false);
+
+    let spec = spec.unwrap_or_default();
+    if spec.service_count > MAX_SAMPLE_SERVICES {
+        return Err(AppError::ValidationError(format!(
+            "Sample environments are limited to {} services",
+            MAX_SAMPLE_SERVICES
+        )));
+    }
+
+    // Generating is pure computation, so do it before taking the lock
+    let sample = sample::generate(&spec);
+
+    let mut state = write_state(state);
+
+    let exists = state.storage.environment_exists(&environment)?;
+    let (existing_services, existing_relationships) = if exists {
+        (
+            state.storage.load_services(&environment)?,
+            state.storage.load_relationships(&environment)?,
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let is_empty = existing_services.is_empty() && existing_relationships.is_empty();
+    if !is_empty && !overwrite.unwrap_or(false) {
+        return Err(AppError::ValidationError(format!(
+            "Environment '{}' already contains data; pass overwrite to replace it",
+            environment
+        )));
+    }
+
+    let sample_ids: HashSet<&str> = sample.services.iter().map(|s| s.id.as_str()).collect();
+    let mut existing_by_id: HashMap<String, Service> = HashMap::new();
+    let mut changes = Vec::new();
+    let mut replaced_services = 0;
+
+    for service in existing_services {
+        if sample_ids.contains(service.id.as_str()) {
+            existing_by_id.insert(service.id.clone(), service);
+            continue;
+        }
+        replaced_services += 1;
+        changes.push(Change::Service {
+            id: service.id.clone(),
+            before: Some(service),
+            after: None,
+        });
+    }
+
+    for service in &sample.services {
+        let before = existing_by_id.remove(&service.id);
+        // Replaced services continue the existing revision sequence
+        let mut service = service.clone();
+        service.revision = before.as_ref().map_or(0, |b| b.revision + 1);
+
+        changes.push(Change::Service {
+            id: service.id.clone(),
+            before,
+            after: Some(service),
+        });
+    }
+
+    let revisions: HashMap<&str, u64> = existing_relationships
+        .iter()
+        .map(|r| (r.id.as_str(), r.revision))
+        .collect();
+    let mut relationships = sample.relationships.clone();
+    for relationship in &mut relationships {
+        if let Some(revision) = revisions.get(relationship.id.as_str()) {
+            relationship.revision = revision + 1;
+        }
+    }
+
+    changes.push(Change::Relationships {
+        before: existing_relationships,
+        after: relationships,
+    });
+
+    let report = SampleEnvironmentReport {
+        environment,
+        services: sample.services.len(),
+        relationships: sample.relationships.len(),
+        replaced_services,
+        dry_run,
+    };
+    if dry_run {
+        return Ok(report);
+    }
+
+    if !exists {
+        state.storage.create_environment(&report.environment)?;
+    }
+    batch::apply_entry(
+        app,
+        &mut state,
+        &report.environment,
+        "generate_sample_environment",
+        JournalEntry::new(
+            format!(
+                "Generated sample environment ({} services)",
+                report.services
+            ),
+            changes,
+        ),
+        &mut Progress::none(),
+    )?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::loader::relationships_file_path;
+    use crate::storage::{service_file_path, StorageBackend};
+    use crate::test_support::{
+        block_on, relationship, service, snapshot_dir, without_dry_run, TestApp,
+    };
+    use std::fs;
+
+    fn migrate(app: &TestApp, sharded: bool) -> RelationshipStorageMigration {
+        block_on(migrate_relationship_storage(
+            app.handle(),
+            "dev".to_string(),
+            sharded,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn relationship_storage_migration_round_trips() {
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("auth"), service("db")],
+            &[
+                relationship("api-auth", "api", "auth"),
+                relationship("api-db", "api", "db"),
+                relationship("auth-db", "auth", "db"),
+            ],
+        );
+        let dev = app.data_path().join("dev");
+        let single = fs::read(dev.join("relationships.json")).unwrap();
+
+        let migration = migrate(&app, true);
+        assert_eq!(migration.layout, RelationshipLayout::Sharded);
+        assert!(!dev.join("relationships.json").exists());
+        assert!(dev.join("relationships/api.json").exists());
+        assert!(dev.join("relationships/auth.json").exists());
+        assert!(!dev.join("relationships/db.json").exists());
+        assert!(migrate(&app, true).files.is_empty());
+
+        let migration = migrate(&app, false);
+        assert_eq!(migration.layout, RelationshipLayout::Single);
+        assert!(!dev.join("relationships").exists());
+        assert_eq!(fs::read(dev.join("relationships.json")).unwrap(), single);
+        assert!(migrate(&app, false).files.is_empty());
+    }
+
+    #[test]
+    fn migrating_an_environment_rewrites_legacy_files_at_the_current_version() {
+        let app = TestApp::with_environment("dev", &[service("api"), service("db")], &[]);
+        let env_dir = app.data_path().join("dev");
+        let relationships_path = relationships_file_path(app.data_path(), "dev").unwrap();
+        fs::write(
+            &relationships_path,
+            r#"[{ "id": "rel-1", "source": "api", "target": "db", "relationshipType": "depends_on" }]"#,
+        )
+        .unwrap();
+        fs::write(
+            env_dir.join("services").join("api.json"),
+            r#"{ "id": "api", "name": "API", "serviceType": "backend", "status": "healthy" }"#,
+        )
+        .unwrap();
+        let migrate = |dry_run| {
+            block_on(migrate_environment(
+                app.handle(),
+                "dev".to_string(),
+                dry_run,
+            ))
+            .unwrap()
+        };
+
+        // Old data loads before anything is rewritten
+        let state = app.state();
+        let loaded = read_state(&state)
+            .storage
+            .load_relationships("dev")
+            .unwrap();
+        assert_eq!(loaded.len(), 1);
+
+        let files = snapshot_dir(app.data_path());
+        let preview = migrate(true);
+        assert_eq!(snapshot_dir(app.data_path()), files);
+
+        let report = migrate(false);
+        assert_eq!(without_dry_run(&preview), without_dry_run(&report));
+        let mut migrated: Vec<&str> = report
+            .files
+            .iter()
+            .map(|f| f.path.rsplit(['/', '\\']).next().unwrap())
+            .collect();
+        migrated.sort();
+        assert_eq!(migrated, vec!["api.json", "relationships.json"]);
+        assert!(report
+            .files
+            .iter()
+            .all(|f| f.from_version == 1 && f.to_version == CURRENT_SCHEMA_VERSION));
+
+        let stored: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&relationships_path).unwrap()).unwrap();
+        assert_eq!(stored["schemaVersion"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(stored["relationships"][0]["id"], "rel-1");
+        assert_eq!(
+            read_state(&state)
+                .storage
+                .load_relationships("dev")
+                .unwrap(),
+            loaded
+        );
+
+        assert!(migrate(false).files.is_empty());
+    }
+
+    #[test]
+    fn migrating_to_sqlite_copies_every_environment() {
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[relationship("api-db", "api", "db")],
+        );
+        app.create_environment("prod", &[service("api")], &[]);
+        let db_path = app.data_path().join("data.db");
+
+        let report = block_on(migrate_to_sqlite(
+            app.handle(),
+            app.data_path().display().to_string(),
+            Some(db_path.display().to_string()),
+        ))
+        .unwrap();
+
+        assert_eq!(report.db_path, db_path.display().to_string());
+        let mut environments = report.environments.clone();
+        environments.sort();
+        assert_eq!(environments, vec!["dev", "prod"]);
+        assert_eq!(report.services_imported, 3);
+        assert_eq!(report.relationships_imported, 1);
+
+        let files = storage::FileSystemBackend::new(app.data_path().to_path_buf());
+        let database = storage::SqliteBackend::new(db_path.clone());
+        for environment in ["dev", "prod"] {
+            let ids = |backend: &dyn StorageBackend| {
+                let mut ids: Vec<String> = backend
+                    .load_services(environment)
+                    .unwrap()
+                    .into_iter()
+                    .map(|s| s.id)
+                    .collect();
+                ids.sort();
+                ids
+            };
+            assert_eq!(ids(&database), ids(&files));
+            assert_eq!(
+                database.load_relationships(environment).unwrap(),
+                files.load_relationships(environment).unwrap()
+            );
+        }
+    }
+
+    fn normalize(app: &TestApp, dry_run: bool) -> NormalizationReport {
+        block_on(normalize_environment_files(
+            app.handle(),
+            "dev".to_string(),
+            dry_run,
+        ))
+        .unwrap()
+    }
+
+    /// Writes JSON with four-space indentation and trailing whitespace, as
+    /// an editor set up differently might.
+    fn write_messy(path: &std::path::Path, value: &serde_json::Value) {
+        let mut out = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+        serde::Serialize::serialize(value, &mut serializer).unwrap();
+        let messy = String::from_utf8(out).unwrap().replace('\n', "  \n");
+        fs::write(path, messy).unwrap();
+    }
+
+    #[test]
+    fn normalizing_twice_changes_nothing() {
+        let mut api = service("api");
+        api.tags = vec!["alpha".to_string(), "zeta".to_string()];
+        let app = TestApp::with_environment(
+            "dev",
+            &[api, service("db"), service("web")],
+            &[
+                relationship("web-api", "web", "api"),
+                relationship("api-db", "api", "db"),
+            ],
+        );
+        // Files saved by the app already are canonical
+        assert!(normalize(&app, true).files.is_empty());
+
+        let api_path = service_file_path(app.data_path(), "dev", "api").unwrap();
+        let mut value: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&api_path).unwrap()).unwrap();
+        value["tags"] = serde_json::json!(["zeta", "alpha"]);
+        value["metadata"] = serde_json::json!({ "zone": "eu", "app": "shop" });
+        write_messy(&api_path, &value);
+        let relationships_path = relationships_file_path(app.data_path(), "dev").unwrap();
+        let mut value: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&relationships_path).unwrap()).unwrap();
+        value["relationships"].as_array_mut().unwrap().reverse();
+        write_messy(&relationships_path, &value);
+        let messy = snapshot_dir(app.data_path());
+
+        let preview = normalize(&app, true);
+        assert_eq!(snapshot_dir(app.data_path()), messy);
+        let mut expected = vec![
+            api_path.display().to_string(),
+            relationships_path.display().to_string(),
+        ];
+        expected.sort();
+        let mut files = preview.files.clone();
+        files.sort();
+        assert_eq!(files, expected);
+
+        let applied = normalize(&app, false);
+        assert_eq!(without_dry_run(&preview), without_dry_run(&applied));
+        let normalized = snapshot_dir(app.data_path());
+
+        assert!(normalize(&app, false).files.is_empty());
+        assert_eq!(snapshot_dir(app.data_path()), normalized);
+
+        let api = fs::read_to_string(&api_path).unwrap();
+        assert!(api.ends_with("}\n") && !api.contains(" \n"));
+        assert!(api.contains("\n  \"id\": \"api\""), "{api}");
+        assert!(api.find("\"alpha\"") < api.find("\"zeta\""));
+        assert!(api.find("\"app\"") < api.find("\"zone\""));
+        let relationships = fs::read_to_string(&relationships_path).unwrap();
+        assert!(relationships.find("api-db") < relationships.find("web-api"));
+    }
+}
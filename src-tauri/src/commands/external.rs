@@ -0,0 +1,279 @@
+//! External dependency inventory commands.
+//!
+//! Security reviews need every third-party system the services touch and
+//! who talks to it. These commands list the external services of an
+//! environment with the services that have relationships to them, grouped
+//! by relationship type and team, and export the list as CSV. Validation
+//! flags the services modeled as internal that are tagged third-party (see
+//! `is_third_party`).
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::dates;
+use crate::error::AppError;
+use crate::export::csv_row;
+use crate::models::{RelationshipType, Service, ServiceType};
+use crate::state::{cache, run_blocking, AppState};
+
+/// The tag marking a service as a third-party system.
+pub const THIRD_PARTY_TAG: &str = "third-party";
+
+/// Columns of an exported external dependency inventory, in order.
+pub const CSV_COLUMNS: &[&str] = &[
+    "externalServiceId",
+    "externalServiceName",
+    "dependentCount",
+    "dependentServiceId",
+    "dependentServiceName",
+    "relationshipType",
+    "team",
+];
+
+/// How external dependencies are ordered.
+///
+/// # Variants
+///
+/// * `Id` - By external service ID (the default)
+/// * `DependentCount` - Most dependent services first, then by ID
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalDependencyOrder {
+    #[default]
+    Id,
+    DependentCount,
+}
+
+/// Services of one team using an external service in one way.
+///
+/// # Fields
+///
+/// * `relationship_type` - How they use it
+/// * `team` - Their team; `None` for services without one
+/// * `service_ids` - IDs of the services, sorted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependentGroup {
+    pub relationship_type: RelationshipType,
+    pub team: Option<String>,
+    pub service_ids: Vec<String>,
+}
+
+/// An external service and the services that use it.
+///
+/// # Fields
+///
+/// * `service_id` - The external service's ID
+/// * `service_name` - The external service's display name
+/// * `dependent_count` - Number of distinct services with a relationship to it
+/// * `dependents` - The services, grouped by relationship type and then team
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalDependency {
+    pub service_id: String,
+    pub service_name: String,
+    pub dependent_count: usize,
+    pub dependents: Vec<DependentGroup>,
+}
+
+/// Lists the external services of an environment and who uses them.
+///
+/// Every service of type `external` is listed, including the ones nothing
+/// uses yet. Its dependents are the sources of the relationships in effect
+/// today that target it, whatever their type.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache
+/// * `environment` - The name of the environment
+/// * `order` - "id" or "dependent_count" (default: "id")
+///
+/// # Returns
+///
+/// * `Ok(Vec<ExternalDependency>)` - The external services with their dependents
+/// * `Err(AppError)` - If the data cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: the most used third parties first
+/// const inventory = await invoke('get_external_dependencies', {
+///     environment: 'prod',
+///     order: 'dependent_count'
+/// });
+/// ```
+#[tauri::command]
+pub fn get_external_dependencies(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    order: Option<ExternalDependencyOrder>,
+) -> Result<Vec<ExternalDependency>, AppError> {
+    external_dependencies(&state, &environment, order.unwrap_or_default())
+}
+
+/// Writes the external dependency inventory of an environment as CSV.
+///
+/// The file has a header row with the `CSV_COLUMNS` and one row per
+/// dependent service and relationship type, ordered like
+/// `get_external_dependencies`. An external service nothing uses gets one
+/// row with the dependent columns empty.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to export on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `output_path` - The file to write; replaced if it exists
+/// * `order` - "id" or "dependent_count" (default: "id")
+///
+/// # Returns
+///
+/// * `Ok(Vec<ExternalDependency>)` - The exported inventory
+/// * `Err(AppError::Io)` - If the data cannot be read or the file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the inventory to `output_path`
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('export_external_dependencies', {
+///     environment: 'prod',
+///     outputPath: '/tmp/prod-third-parties.csv'
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_external_dependencies<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    output_path: String,
+    order: Option<ExternalDependencyOrder>,
+) -> Result<Vec<ExternalDependency>, AppError> {
+    run_blocking(app, move |state| {
+        write_external_dependencies(state, &environment, &output_path, order.unwrap_or_default())
+    })
+    .await
+}
+
+/// Builds the inventory and writes it as CSV, for `export_external_dependencies`.
+fn write_external_dependencies(
+    state: &RwLock<AppState>,
+    environment: &str,
+    output_path: &str,
+    order: ExternalDependencyOrder,
+) -> Result<Vec<ExternalDependency>, AppError> {
+    let inventory = external_dependencies(state, environment, order)?;
+    let services = cache::shared_services(state, environment)?;
+    let names: HashMap<&str, &str> = services
+        .iter()
+        .map(|s| (s.id.as_str(), s.name.as_str()))
+        .collect();
+
+    let mut csv = csv_row(CSV_COLUMNS.iter().copied());
+    for external in &inventory {
+        let count = external.dependent_count.to_string();
+        let head = [
+            external.service_id.as_str(),
+            external.service_name.as_str(),
+            count.as_str(),
+        ];
+        if external.dependents.is_empty() {
+            csv.push_str(&csv_row(head.into_iter().chain(["", "", "", ""])));
+        }
+        for group in &external.dependents {
+            for id in &group.service_ids {
+                let dependent = [
+                    id.as_str(),
+                    names.get(id.as_str()).copied().unwrap_or(""),
+                    group.relationship_type.as_str(),
+                    group.team.as_deref().unwrap_or(""),
+                ];
+                csv.push_str(&csv_row(head.into_iter().chain(dependent)));
+            }
+        }
+    }
+    fs::write(output_path, csv)?;
+
+    Ok(inventory)
+}
+
+/// Builds the external dependency inventory of an environment.
+fn external_dependencies(
+    state: &RwLock<AppState>,
+    environment: &str,
+    order: ExternalDependencyOrder,
+) -> Result<Vec<ExternalDependency>, AppError> {
+    let services = cache::shared_services(state, environment)?;
+    let relationships = cache::shared_relationships(state, environment)?;
+    let services_by_id: HashMap<&str, &Service> = services
+        .iter()
+        .map(|s| (s.id.as_str(), s.as_ref()))
+        .collect();
+
+    // Dependents of each external service by (relationship type, team)
+    type Groups<'a> = BTreeMap<(&'a str, Option<&'a str>), (RelationshipType, BTreeSet<&'a str>)>;
+    let mut usage: BTreeMap<&str, Groups> = services
+        .iter()
+        .filter(|s| s.service_type == ServiceType::External)
+        .map(|s| (s.id.as_str(), Groups::new()))
+        .collect();
+    let today = dates::today();
+    for relationship in relationships.iter().filter(|r| r.is_effective_on(&today)) {
+        let Some(groups) = usage.get_mut(relationship.target.as_str()) else {
+            continue;
+        };
+        let team = services_by_id
+            .get(relationship.source.as_str())
+            .and_then(|source| source.team.as_deref())
+            .map(str::trim)
+            .filter(|team| !team.is_empty());
+        groups
+            .entry((relationship.relationship_type.as_str(), team))
+            .or_insert_with(|| (relationship.relationship_type.clone(), BTreeSet::new()))
+            .1
+            .insert(relationship.source.as_str());
+    }
+
+    let mut inventory: Vec<ExternalDependency> = usage
+        .into_iter()
+        .map(|(service_id, groups)| {
+            let dependent_count = groups
+                .values()
+                .flat_map(|(_, ids)| ids)
+                .collect::<BTreeSet<_>>()
+                .len();
+            ExternalDependency {
+                service_id: service_id.to_string(),
+                service_name: services_by_id[service_id].name.clone(),
+                dependent_count,
+                dependents: groups
+                    .into_iter()
+                    .map(|((_, team), (relationship_type, ids))| DependentGroup {
+                        relationship_type,
+                        team: team.map(str::to_string),
+                        service_ids: ids.into_iter().map(str::to_string).collect(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    if order == ExternalDependencyOrder::DependentCount {
+        // Stable, so ties stay in ID order
+        inventory.sort_by_key(|dependency| Reverse(dependency.dependent_count));
+    }
+
+    Ok(inventory)
+}
+
+/// Returns true if a service is tagged as a third-party system.
+pub fn is_third_party(service: &Service) -> bool {
+    service
+        .tags
+        .iter()
+        .any(|tag| tag.eq_ignore_ascii_case(THIRD_PARTY_TAG))
+}
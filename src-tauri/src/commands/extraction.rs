@@ -0,0 +1,315 @@
+//! Subgraph extraction commands for the Tauri application.
+//!
+//! When a domain outgrows its environment, `extract_to_environment` copies
+//! (or moves) the services a selector matches into a new environment of
+//! their own, along with the relationships between them. Relationships that
+//! cross the boundary can't come along, as one end stays behind; they are
+//! reported so the user can decide what to do with them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime};
+
+use crate::commands::batch::{self, Operation, Plan};
+use crate::error::AppError;
+use crate::models::{Relationship, Service, ServiceType};
+use crate::state::journal::{Change, JournalEntry};
+use crate::state::progress::Progress;
+use crate::state::{run_blocking, write_state, AppState};
+use crate::storage::{self, audit::AuditRecord};
+
+/// The services an extraction takes.
+///
+/// A service is selected if its ID is listed, or if it matches every given
+/// criterion out of `tags`, `teams` and `service_types`; a criterion matches
+/// when the service has any of its values.
+///
+/// # Fields
+///
+/// * `service_ids` - Services to take regardless of the other criteria
+/// * `tags` - Take services with any of these tags
+/// * `teams` - Take services of any of these teams
+/// * `service_types` - Take services of any of these types
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServiceSelector {
+    pub service_ids: Vec<String>,
+    pub tags: Vec<String>,
+    pub teams: Vec<String>,
+    pub service_types: Vec<ServiceType>,
+}
+
+impl ServiceSelector {
+    /// Returns true if the selector has no criteria and no IDs.
+    pub fn is_empty(&self) -> bool {
+        self.service_ids.is_empty()
+            && self.tags.is_empty()
+            && self.teams.is_empty()
+            && self.service_types.is_empty()
+    }
+
+    /// Returns true if a service matches the criteria, leaving `service_ids` aside.
+    fn matches_criteria(&self, service: &Service) -> bool {
+        if self.tags.is_empty() && self.teams.is_empty() && self.service_types.is_empty() {
+            return false;
+        }
+
+        (self.tags.is_empty() || service.tags.iter().any(|tag| self.tags.contains(tag)))
+            && (self.teams.is_empty()
+                || service
+                    .team
+                    .as_ref()
+                    .is_some_and(|team| self.teams.contains(team)))
+            && (self.service_types.is_empty() || self.service_types.contains(&service.service_type))
+    }
+}
+
+/// What `extract_to_environment` copied or moved.
+///
+/// # Fields
+///
+/// * `source_environment` - The environment the services came from
+/// * `target_environment` - The environment created for them
+/// * `services` - IDs of the services taken, sorted
+/// * `relationships` - IDs of the relationships between them, taken along
+/// * `boundary_relationships` - Relationships between a taken service and one left
+///   behind; they aren't copied, and after a move they are orphaned in the source
+/// * `moved` - True if the services and relationships were removed from the source
+/// * `dry_run` - True if nothing was written
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionReport {
+    pub source_environment: String,
+    pub target_environment: String,
+    pub services: Vec<String>,
+    pub relationships: Vec<String>,
+    pub boundary_relationships: Vec<Relationship>,
+    pub moved: bool,
+    pub dry_run: bool,
+}
+
+/// Copies or moves part of an environment's graph into a new environment.
+///
+/// The services the selector matches are copied as they are, with the
+/// relationships whose both ends are among them. With `move_services`, the
+/// copied services and relationships are then deleted from the source;
+/// relationships between them and the services left behind stay in the
+/// source, orphaned, and are reported in `boundaryRelationships` either way.
+/// The new environment and the source each get one entry in their undo
+/// journal, so undoing the move in the source restores the services there
+/// without removing the copies.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `source_environment` - The environment to take the services from
+/// * `target_environment` - The environment to create; must not exist
+/// * `selector` - Which services to take (see `ServiceSelector`)
+/// * `move_services` - If true, delete the taken services and relationships from
+///   the source (default: false)
+/// * `dry_run` - If true, only report what would be taken (default: false)
+///
+/// # Returns
+///
+/// * `Ok(ExtractionReport)` - What was taken (or would be), and the boundary relationships
+/// * `Err(AppError::ValidationError)` - If an environment name is not a safe directory
+///   name, both are the same, or the selector is empty or matches no services
+/// * `Err(AppError::EnvironmentExists)` - If the target environment already exists
+/// * `Err(AppError::ServiceNotFound)` - If a listed service doesn't exist in the source
+/// * `Err(AppError::Io)` - If the data cannot be read or written; if writing the
+///   copies failed, the target environment was created but left empty
+///
+/// # Side Effects
+///
+/// Unless `dry_run` is set:
+/// - Creates the target environment and writes the copied service files and its
+///   `relationships.json`
+/// - With `move_services`, deletes the taken service files from the source and
+///   rewrites its `relationships.json`
+/// - Updates the caches of both environments
+/// - Records one entry in the undo journal of each environment written
+/// - Appends the creation and one record per copied or deleted service and
+///   relationship to the audit logs
+/// - Commits the changes to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: see what moving the payments domain would sever
+/// const preview = await invoke('extract_to_environment', {
+///     sourceEnvironment: 'prod',
+///     targetEnvironment: 'payments',
+///     selector: { teams: ['Payments'], serviceIds: ['ledger-db'] },
+///     moveServices: true,
+///     dryRun: true
+/// });
+/// console.log(`${preview.boundaryRelationships.length} relationships would be orphaned`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn extract_to_environment<R: Runtime>(
+    app: AppHandle<R>,
+    source_environment: String,
+    target_environment: String,
+    selector: ServiceSelector,
+    move_services: Option<bool>,
+    dry_run: Option<bool>,
+) -> Result<ExtractionReport, AppError> {
+    storage::validate_environment_name(&source_environment)?;
+    storage::validate_environment_name(&target_environment)?;
+    if source_environment == target_environment {
+        return Err(AppError::ValidationError(
+            "The target environment must differ from the source".to_string(),
+        ));
+    }
+    if selector.is_empty() {
+        return Err(AppError::ValidationError(
+            "The selector needs service IDs, tags, teams or service types".to_string(),
+        ));
+    }
+
+    run_blocking(app.clone(), move |state| {
+        let report = ExtractionReport {
+            source_environment,
+            target_environment,
+            services: Vec::new(),
+            relationships: Vec::new(),
+            boundary_relationships: Vec::new(),
+            moved: move_services.unwrap_or(false),
+            dry_run: dry_run.unwrap_or(false),
+        };
+        extract(&app, state, &selector, report)
+    })
+    .await
+}
+
+/// Selects, copies and optionally deletes the services, for
+/// `extract_to_environment`, filling in the report.
+fn extract<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    selector: &ServiceSelector,
+    mut report: ExtractionReport,
+) -> Result<ExtractionReport, AppError> {
+    let source = report.source_environment.clone();
+    let target = report.target_environment.clone();
+    let mut state = write_state(state);
+    if state.storage.environment_exists(&target)? {
+        return Err(AppError::EnvironmentExists(target));
+    }
+
+    let all_services = state.storage.load_services(&source)?;
+    let known: HashSet<&str> = all_services.iter().map(|s| s.id.as_str()).collect();
+    if let Some(missing) = selector
+        .service_ids
+        .iter()
+        .find(|id| !known.contains(id.as_str()))
+    {
+        return Err(AppError::ServiceNotFound(missing.clone()));
+    }
+    let mut services: Vec<Service> = all_services
+        .iter()
+        .filter(|s| selector.service_ids.contains(&s.id) || selector.matches_criteria(s))
+        .cloned()
+        .collect();
+    if services.is_empty() {
+        return Err(AppError::ValidationError(format!(
+            "The selector matches no services in '{}'",
+            source
+        )));
+    }
+    services.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let selected: HashSet<&str> = services.iter().map(|s| s.id.as_str()).collect();
+    let mut relationships = Vec::new();
+    for relationship in state.storage.load_relationships(&source)? {
+        match (
+            selected.contains(relationship.source.as_str()),
+            selected.contains(relationship.target.as_str()),
+        ) {
+            (true, true) => relationships.push(relationship),
+            (false, false) => {}
+            _ => report.boundary_relationships.push(relationship),
+        }
+    }
+    report.services = services.iter().map(|s| s.id.clone()).collect();
+    report.relationships = relationships.iter().map(|r| r.id.clone()).collect();
+
+    // Computed before anything is written, so a failing deletion writes nothing
+    let removal = if report.moved {
+        let operations = report
+            .services
+            .iter()
+            .map(|id| Operation::DeleteService {
+                service_id: id.clone(),
+            })
+            .chain(
+                report
+                    .relationships
+                    .iter()
+                    .map(|id| Operation::DeleteRelationship {
+                        relationship_id: id.clone(),
+                    }),
+            )
+            .collect();
+        Some(Plan::compute(&state, &source, operations)?)
+    } else {
+        None
+    };
+    if report.dry_run {
+        return Ok(report);
+    }
+
+    state.storage.create_environment(&target)?;
+    state.clear_environment_cache(&target);
+    state.record_write(&target);
+    state.record_audit(
+        &target,
+        &[AuditRecord::new("extract_to_environment", Vec::new())
+            .with_change(None, Some(format!("Environment {}", target)))],
+    );
+
+    let mut changes: Vec<Change> = services
+        .into_iter()
+        .map(|service| Change::Service {
+            id: service.id.clone(),
+            before: None,
+            after: Some(service),
+        })
+        .collect();
+    changes.push(Change::Relationships {
+        before: Vec::new(),
+        after: relationships,
+    });
+    let description = format!(
+        "Extracted {} services from environment {}",
+        report.services.len(),
+        source
+    );
+    batch::apply_entry(
+        app,
+        &mut state,
+        &target,
+        "extract_to_environment",
+        JournalEntry::new(description, changes),
+        &mut Progress::none(),
+    )?;
+
+    if let Some(removal) = removal {
+        let description = format!(
+            "Moved {} services to environment {}",
+            report.services.len(),
+            target
+        );
+        removal.apply(
+            app,
+            &mut state,
+            &source,
+            "extract_to_environment",
+            description,
+        )?;
+    }
+
+    Ok(report)
+}
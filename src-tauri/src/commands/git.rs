@@ -0,0 +1,59 @@
+//! Git integration commands for the Tauri application.
+//!
+//! This module lets the frontend show whether an environment's data has
+//! uncommitted changes. Auto-committing itself happens in the write commands
+//! (see the `git` module).
+
+use std::sync::RwLock;
+use tauri::State;
+
+use crate::error::AppError;
+use crate::git::{self, GitStatus};
+use crate::state::{read_state, AppState};
+use crate::storage;
+
+/// Retrieves the git status of an environment's files.
+///
+/// Reports whether the data directory is inside a git repository, the
+/// current branch, and which of the environment's files are modified or
+/// untracked. Returns `isRepository: false` (and nothing dirty) when there
+/// is no repository or the SQLite storage backend is in use.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment to inspect
+///
+/// # Returns
+///
+/// * `Ok(GitStatus)` - The environment's git status
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::Git)` - If the repository exists but cannot be read
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const status = await invoke('get_git_status', { environment: 'dev' });
+/// if (status.dirty) {
+///     console.log(`${status.changedFiles.length} uncommitted files on ${status.branch}`);
+/// }
+/// ```
+#[tauri::command]
+pub fn get_git_status(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<GitStatus, AppError> {
+    storage::validate_environment_name(&environment)?;
+
+    let data_path = {
+        let state = read_state(&state);
+        match state.storage.as_file_system() {
+            Some(fs_backend) => fs_backend.data_path().to_path_buf(),
+            None => return Ok(GitStatus::default()),
+        }
+    };
+
+    // Computed without holding the state lock; status can be slow on big repositories
+    Ok(git::environment_status(&data_path, &environment)?)
+}
@@ -0,0 +1,314 @@
+//! GitHub enrichment commands for the Tauri application.
+//!
+//! Fills in facts about a service's repository (see `github`) from the
+//! `repository` URL in its metadata. The facts are stored under the
+//! `github` metadata key; the token used to read them never is.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::error::AppError;
+use crate::github::{self, Repository, RepositoryFacts};
+use crate::models::{Service, ServiceStatus};
+use crate::state::{read_state, write_state, AppState};
+use crate::storage;
+
+/// Metadata key holding the repository URL of a service.
+const REPOSITORY_METADATA_KEY: &str = "repository";
+
+/// Metadata key holding the facts read from GitHub.
+pub const GITHUB_METADATA_KEY: &str = "github";
+
+/// What happened to one service during an enrichment.
+///
+/// # Variants
+///
+/// * `Enriched` - The facts were read and saved
+/// * `Unchanged` - The facts were read and matched the stored ones
+/// * `NoRepository` - The service has no recognizable GitHub repository URL
+/// * `Failed` - Reading the facts failed; see the message
+/// * `NotAttempted` - Skipped because the rate limit was used up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EnrichmentOutcome {
+    Enriched,
+    Unchanged,
+    NoRepository,
+    Failed,
+    NotAttempted,
+}
+
+/// The enrichment of one service.
+///
+/// # Fields
+///
+/// * `service_id` - The service
+/// * `repository` - The repository as `owner/name`, if the URL was recognized
+/// * `outcome` - What happened
+/// * `message` - Why the enrichment failed, for failed services
+/// * `deprecated` - Whether the service was marked deprecated because its
+///   repository is archived
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceEnrichment {
+    pub service_id: String,
+    pub repository: Option<String>,
+    pub outcome: EnrichmentOutcome,
+    pub message: Option<String>,
+    pub deprecated: bool,
+}
+
+/// The result of a GitHub enrichment.
+///
+/// # Fields
+///
+/// * `services` - One entry per requested service, in request order
+/// * `rate_limited` - Why the enrichment stopped early, if the rate limit ran out
+/// * `result` - What the enrichment saved
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubEnrichmentReport {
+    pub services: Vec<ServiceEnrichment>,
+    pub rate_limited: Option<String>,
+    pub result: BatchResult,
+}
+
+/// Enriches services with facts about their GitHub repositories.
+///
+/// For each service with a GitHub URL in its `repository` metadata, reads
+/// the repository's description, primary language, archived flag and last
+/// push date, and stores them in its `github` metadata. A service that
+/// fails (e.g. a private repository) is reported and the others continue.
+/// When GitHub reports the rate limit as used up, no further requests are
+/// sent and the remaining services are reported as not attempted. All
+/// changes are written as one batch (see `execute_batch`).
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the caches and data path
+/// * `environment` - The environment of the services
+/// * `service_ids` - The services to enrich; empty enriches every service
+/// * `token` - A GitHub token, if any; it is only sent to GitHub, never stored
+/// * `deprecate_archived` - If true, mark services with an archived repository
+///   as deprecated (default: false)
+///
+/// # Returns
+///
+/// * `Ok(GitHubEnrichmentReport)` - What happened to each service
+/// * `Err(AppError::ServiceNotFound)` - If a requested service doesn't exist
+/// * `Err(AppError::BatchOperationFailed)` - If a change can't be made; nothing was written
+///
+/// # Side Effects
+///
+/// - Writes the changed service files
+/// - Records the enrichment as a single entry in the undo journal
+/// - Appends one audit log record per changed service
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('enrich_from_github', {
+///     environment: 'prod',
+///     serviceIds: [],
+///     token: githubToken,
+///     deprecateArchived: true
+/// });
+/// const failed = report.services.filter(s => s.outcome === 'failed');
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn enrich_from_github<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    service_ids: Vec<String>,
+    token: Option<String>,
+    deprecate_archived: Option<bool>,
+) -> Result<GitHubEnrichmentReport, AppError> {
+    storage::validate_environment_name(&environment)?;
+    let targets = {
+        let state = read_state(&state);
+        let services = state.storage.load_services(&environment)?;
+        select_targets(&services, &service_ids)?
+    };
+
+    let Fetched {
+        entries: mut services,
+        fetched,
+        rate_limited,
+    } = tauri::async_runtime::spawn_blocking(move || fetch_all(targets, token.as_deref()))
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?;
+
+    let deprecate_archived = deprecate_archived.unwrap_or(false);
+    let mut state = write_state(&state);
+    let stored = state.storage.load_services(&environment)?;
+    let mut operations = Vec::new();
+    for (index, facts) in fetched {
+        let entry = &mut services[index];
+        let Some(service) = stored.iter().find(|s| s.id == entry.service_id) else {
+            entry.outcome = EnrichmentOutcome::Failed;
+            entry.message = Some("The service was deleted during the enrichment".to_string());
+            continue;
+        };
+
+        let repository = entry.repository.as_deref().unwrap_or_default();
+        let enriched = enrich_service(service, repository, &facts, deprecate_archived);
+        entry.deprecated = enriched.status != service.status;
+        if serde_json::to_value(&enriched).ok() == serde_json::to_value(service).ok() {
+            entry.outcome = EnrichmentOutcome::Unchanged;
+            continue;
+        }
+        entry.outcome = EnrichmentOutcome::Enriched;
+        operations.push(Operation::SaveService {
+            service: enriched,
+            force: true,
+        });
+    }
+
+    let plan = Plan::compute(&state, &environment, operations)?;
+    let description = format!(
+        "Enriched {} services from GitHub",
+        plan.result.saved_services.len()
+    );
+    let result = plan.apply(
+        &app,
+        &mut state,
+        &environment,
+        "enrich_from_github",
+        description,
+    )?;
+
+    Ok(GitHubEnrichmentReport {
+        services,
+        rate_limited,
+        result,
+    })
+}
+
+/// Resolves the requested services to their repositories.
+fn select_targets(
+    services: &[Service],
+    service_ids: &[String],
+) -> Result<Vec<(String, Option<Repository>)>, AppError> {
+    let selected: Vec<&Service> = if service_ids.is_empty() {
+        services.iter().collect()
+    } else {
+        let mut seen = HashSet::new();
+        service_ids
+            .iter()
+            .filter(|id| seen.insert(id.as_str()))
+            .map(|id| {
+                services
+                    .iter()
+                    .find(|s| &s.id == id)
+                    .ok_or_else(|| AppError::ServiceNotFound(id.clone()))
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    Ok(selected
+        .into_iter()
+        .map(|service| {
+            let repository = service
+                .metadata
+                .get(REPOSITORY_METADATA_KEY)
+                .and_then(|url| url.as_str())
+                .and_then(github::parse_repository_url);
+            (service.id.clone(), repository)
+        })
+        .collect())
+}
+
+/// The requests sent for an enrichment.
+///
+/// # Fields
+///
+/// * `entries` - One report entry per target
+/// * `fetched` - The facts read, with the index of their entry
+/// * `rate_limited` - Why the requests stopped early, if they did
+struct Fetched {
+    entries: Vec<ServiceEnrichment>,
+    fetched: Vec<(usize, RepositoryFacts)>,
+    rate_limited: Option<String>,
+}
+
+/// Reads the facts of every target until the rate limit runs out.
+fn fetch_all(targets: Vec<(String, Option<Repository>)>, token: Option<&str>) -> Fetched {
+    let mut entries = Vec::new();
+    let mut fetched = Vec::new();
+    let mut rate_limited: Option<String> = None;
+
+    for (index, (service_id, repository)) in targets.into_iter().enumerate() {
+        let mut entry = ServiceEnrichment {
+            service_id,
+            repository: repository.as_ref().map(Repository::to_string),
+            outcome: EnrichmentOutcome::NoRepository,
+            message: None,
+            deprecated: false,
+        };
+
+        if let Some(repository) = repository {
+            if rate_limited.is_some() {
+                entry.outcome = EnrichmentOutcome::NotAttempted;
+            } else {
+                match github::fetch_repository(&repository, token) {
+                    Ok(response) => {
+                        fetched.push((index, response.facts));
+                        if response.remaining == Some(0) {
+                            rate_limited = Some(
+                                AppError::GitHubRateLimited(github::describe_reset(response.reset))
+                                    .to_string(),
+                            );
+                        }
+                    }
+                    Err(e @ AppError::GitHubRateLimited(_)) => {
+                        entry.outcome = EnrichmentOutcome::NotAttempted;
+                        rate_limited = Some(e.to_string());
+                    }
+                    Err(e) => {
+                        entry.outcome = EnrichmentOutcome::Failed;
+                        entry.message = Some(e.to_string());
+                    }
+                }
+            }
+        }
+        entries.push(entry);
+    }
+
+    Fetched {
+        entries,
+        fetched,
+        rate_limited,
+    }
+}
+
+/// Stores the facts of a repository in a service's `github` metadata.
+fn enrich_service(
+    service: &Service,
+    repository: &str,
+    facts: &RepositoryFacts,
+    deprecate_archived: bool,
+) -> Service {
+    let mut enriched = service.clone();
+    enriched.metadata.insert(
+        GITHUB_METADATA_KEY.to_string(),
+        serde_json::json!({
+            "repository": repository,
+            "description": facts.description,
+            "language": facts.language,
+            "archived": facts.archived,
+            "pushedAt": facts.pushed_at,
+        }),
+    );
+    if facts.archived && deprecate_archived {
+        enriched.status = ServiceStatus::Deprecated;
+    }
+
+    enriched
+}
@@ -2,16 +2,40 @@
 //!
 //! This module provides functionality for building and traversing the service
 //! dependency graph. It uses a breadth-first search (BFS) algorithm to discover
-//! connected services up to a specified depth from a center service.
+//! connected services up to a specified depth from a center service, and
+//! exposes the effective status (see `health`), criticality (see
+//! `criticality`) and layering (see `layers`) of services and suggestions
+//! for breaking dependency cycles (see `cycles`), and collapses the graph
+//! into groups of services. The traversal is shared with saved views (see
+//! `views`), which can also filter it by relationship type and direction.
+//! The whole graph can be exported as JSON, DOT or Mermaid, or as the tables
+//! of Grafana's node graph panel (see `export`), and the prerequisites of
+//! services as deployment manifests (see `manifest`).
 
-use serde::Serialize;
-use std::collections::HashSet;
-use std::sync::Mutex;
-use tauri::State;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Runtime, State};
 
+use crate::criticality;
+use crate::cycles::{self, CycleBreakReport};
+use crate::dates;
+use crate::edges::{self, AggregatedEdge};
 use crate::error::AppError;
-use crate::models::{Relationship, Service};
-use crate::state::AppState;
+use crate::export::{self, GrafanaExportFormat, GraphExportFormat};
+use crate::health::{self, EffectiveStatus};
+use crate::layers::{self, DependencyLayers};
+use crate::manifest::{self, ManifestFormat};
+use crate::models::{
+    NodeAnnotation, Relationship, RelationshipType, Service, ServiceStatus, ServiceType,
+    TraversalDirection,
+};
+use crate::redaction;
+use crate::state::diagnostics::CommandTimer;
+use crate::state::progress::Progress;
+use crate::state::relationship_index::RelationshipIndex;
+use crate::state::{cache, read_state, run_blocking, AppState};
 use crate::storage;
 
 /// Represents the graph data for visualization centered on a specific service.
@@ -25,6 +49,20 @@ use crate::storage;
 /// * `center_service` - The service that is the focal point of the graph view
 /// * `connected_services` - Services connected to the center service within the specified depth
 /// * `relationships` - All relationships between the center service and connected services
+/// * `effective_statuses` - Effective status per returned service ID, if requested
+/// * `criticality_scores` - Criticality score (0-1) per returned service ID, if requested
+/// * `group_memberships` - IDs of the groups each returned service belongs to, if
+///   requested; services without groups are left out
+/// * `annotations` - Graph annotations of the returned services, if requested;
+///   services without annotations are left out
+/// * `aggregated_edges` - The relationships collapsed into one edge per source and
+///   target (see `edges`), if requested; `relationships` still lists them all
+/// * `center_excluded` - Whether the center service matches an exclusion; it is
+///   returned and traversed from anyway
+/// * `truncated` - Whether `max_nodes` or `max_edges` left services or relationships out
+/// * `omitted_neighbor_counts` - How many neighbors of each returned service were left
+///   out by the caps, for "+N more" badges (see `expand_graph_node`); services with
+///   none omitted are left out
 ///
 /// # Serialization
 ///
@@ -32,12 +70,34 @@ use crate::storage;
 /// - `center_service` → `centerService`
 /// - `connected_services` → `connectedServices`
 /// - `relationships` → `relationships`
+/// - `effective_statuses` → `effectiveStatuses` (omitted unless requested)
+/// - `criticality_scores` → `criticalityScores` (omitted unless requested)
+/// - `group_memberships` → `groupMemberships` (omitted unless requested)
+/// - `annotations` → `annotations` (omitted unless requested)
+/// - `aggregated_edges` → `aggregatedEdges` (omitted unless requested)
+/// - `center_excluded` → `centerExcluded`
+/// - `truncated` → `truncated`
+/// - `omitted_neighbor_counts` → `omittedNeighborCounts` (omitted when empty)
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GraphData {
     pub center_service: Service,
     pub connected_services: Vec<Service>,
     pub relationships: Vec<Relationship>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_statuses: Option<HashMap<String, ServiceStatus>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub criticality_scores: Option<HashMap<String, f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_memberships: Option<HashMap<String, Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<HashMap<String, NodeAnnotation>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregated_edges: Option<Vec<AggregatedEdge>>,
+    pub center_excluded: bool,
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub omitted_neighbor_counts: BTreeMap<String, usize>,
 }
 
 /// Retrieves the dependency graph centered on a specific service.
@@ -56,26 +116,96 @@ pub struct GraphData {
 ///    - Track visited services to avoid cycles
 /// 3. Collect all unique relationships between discovered services
 ///
+/// # Exclusions
+///
+/// Services with an excluded status, type or tag, or an excluded ID, are neither
+/// returned nor traversed through: a service only reachable through an
+/// excluded one is left out too. Relationships touching excluded services
+/// are dropped. The center service is always included, with
+/// `centerExcluded` set if it matches an exclusion.
+///
+/// # Effective Dates
+///
+/// Only relationships in effect on `as_of` (default: today) are followed
+/// and returned, so edges that have expired or not started yet are left
+/// out (see `Relationship::is_effective_on`). `ignore_effective_dates`
+/// follows every relationship. The effective status and criticality are
+/// computed from every relationship either way.
+///
+/// # Size Limits
+///
+/// With `max_nodes` or `max_edges`, the traversal stops adding services once
+/// the graph holds that many services (the center included) or relationships.
+/// The result is then marked `truncated`, and `omittedNeighborCounts` says
+/// how many neighbors of each boundary service were left out. Which services
+/// are kept is deterministic: each level is expanded in service ID order and
+/// each service's neighbors are added in ID order, so repeating a query over
+/// unchanged data returns the same graph.
+///
+/// # Preferences
+///
+/// `depth`, `direction`, `exclude_statuses`, `exclude_service_types` and
+/// `aggregate_edges` default to the saved graph preferences (see
+/// `get_graph_preferences`) when omitted. A parameter that is passed always
+/// wins, so `excludeStatuses: []` shows every status whatever the
+/// preferences exclude.
+///
 /// # Arguments
 ///
-/// * `state` - The application state containing the cache and data path
+/// * `state` - The application state containing the cache, data path and settings
 /// * `environment` - The name of the environment to query
 /// * `center_service_id` - The ID of the service to center the graph on
-/// * `depth` - Optional maximum traversal depth (default: 1). Higher values
-///   discover more distant dependencies but may result in larger graphs.
+/// * `depth` - Optional maximum traversal depth (default: the preferred depth, 1
+///   unless changed). Higher values discover more distant dependencies but may
+///   result in larger graphs.
+/// * `direction` - Which way relationships are followed (default: the preferred
+///   direction, both unless changed)
+/// * `include_effective_status` - If true, also return each node's effective status
+///   (default: false). It is computed over the whole environment, following
+///   depends_on relationships only, so it reflects dependencies outside the graph.
+/// * `include_criticality` - If true, also return each node's criticality score with
+///   the default damping and iterations (default: false), e.g. to size nodes by it.
+///   Like the effective status, it is computed over the whole environment.
+/// * `include_groups` - If true, also return the groups each node belongs to
+///   (default: false)
+/// * `include_annotations` - If true, also return each node's stored position and
+///   notes (see `get_graph_annotations`; default: false)
+/// * `max_nodes` - Optional maximum number of services in the graph, the center included
+/// * `max_edges` - Optional maximum number of relationships in the graph
+/// * `exclude_statuses` - Optional statuses of services to leave out, e.g. deprecated
+///   (default: the preferred ones)
+/// * `exclude_tags` - Optional tags of services to leave out (any of them excludes)
+/// * `exclude_service_ids` - Optional IDs of services to leave out
+/// * `exclude_service_types` - Optional types of services to leave out (default: the
+///   preferred ones)
+/// * `aggregate_edges` - If true, also return the relationships collapsed into one
+///   edge per source and target, with their count, types and IDs (default: the
+///   preference, false unless changed)
+/// * `as_of` - Optional `YYYY-MM-DD` date relationships must be in effect on
+///   (default: today)
+/// * `ignore_effective_dates` - If true, follow every relationship whatever its
+///   effective dates (default: false)
 ///
 /// # Returns
 ///
 /// * `Ok(GraphData)` - The graph data containing center service, connected
 ///   services, and relationships
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
-/// * `Err(AppError::EnvironmentNotFound)` - If the environment doesn't exist in cache
 /// * `Err(AppError::ServiceNotFound)` - If the center service doesn't exist
+/// * `Err(AppError::ValidationError)` - If `as_of` isn't a `YYYY-MM-DD` date
 /// * `Err(AppError::Io)` - If there's an error reading from the filesystem
+/// * `Err(AppError::FileLoad)` - If groups or annotations were requested and their
+///   file cannot be parsed
+///
+/// # Side Effects
+///
+/// - Records the center service as recently viewed (see `get_recent_services`)
 ///
 /// # Performance
 ///
+/// - Follows the cached per-service relationship index (see
+///   `state::relationship_index`) instead of scanning every relationship per service
 /// - Uses HashSet for O(1) lookup of visited services and seen relationships
+/// - Clones only the relationships in the result, once, after the traversal
 /// - Caches services and relationships to minimize disk I/O
 /// - Time complexity: O(V + E) where V is vertices and E is edges within depth
 ///
@@ -95,109 +225,2690 @@ pub struct GraphData {
 ///     centerServiceId: 'api-gateway',
 ///     depth: 2
 /// });
+///
+/// // Color nodes by their effective status:
+/// const graphWithHealth = await invoke('get_service_graph', {
+///     environment: 'dev',
+///     centerServiceId: 'api-gateway',
+///     includeEffectiveStatus: true,
+///     includeCriticality: true,
+///     includeGroups: true,
+///     includeAnnotations: true
+/// });
+///
+/// // Keep a busy gateway's graph renderable:
+/// const capped = await invoke('get_service_graph', {
+///     environment: 'prod',
+///     centerServiceId: 'api-gateway',
+///     depth: 2,
+///     maxNodes: 200,
+///     maxEdges: 500
+/// });
+/// if (capped.truncated) {
+///     showMoreBadges(capped.omittedNeighborCounts);
+/// }
+///
+/// // Hide deprecated and test-only services:
+/// const clean = await invoke('get_service_graph', {
+///     environment: 'prod',
+///     centerServiceId: 'api-gateway',
+///     depth: 2,
+///     excludeStatuses: ['deprecated'],
+///     excludeTags: ['test-only']
+/// });
+///
+/// // Draw one edge with a badge per pair of services:
+/// const aggregated = await invoke('get_service_graph', {
+///     environment: 'prod',
+///     centerServiceId: 'api-gateway',
+///     aggregateEdges: true
+/// });
+///
+/// // The graph once the database migration is over:
+/// const migrated = await invoke('get_service_graph', {
+///     environment: 'prod',
+///     centerServiceId: 'orders-api',
+///     asOf: '2025-07-01'
+/// });
+///
+/// // Only the dependents, without external services, whatever the preferences:
+/// const dependents = await invoke('get_service_graph', {
+///     environment: 'prod',
+///     centerServiceId: 'ledger-db',
+///     direction: 'upstream',
+///     excludeServiceTypes: ['external']
+/// });
 /// ```
 #[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
 pub fn get_service_graph(
-    state: State<'_, Mutex<AppState>>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
     center_service_id: String,
     depth: Option<u32>,
+    direction: Option<TraversalDirection>,
+    include_effective_status: Option<bool>,
+    include_criticality: Option<bool>,
+    include_groups: Option<bool>,
+    include_annotations: Option<bool>,
+    max_nodes: Option<usize>,
+    max_edges: Option<usize>,
+    exclude_statuses: Option<Vec<ServiceStatus>>,
+    exclude_tags: Option<Vec<String>>,
+    exclude_service_ids: Option<Vec<String>>,
+    exclude_service_types: Option<Vec<ServiceType>>,
+    aggregate_edges: Option<bool>,
+    as_of: Option<String>,
+    ignore_effective_dates: Option<bool>,
 ) -> Result<GraphData, AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
-    let depth = depth.unwrap_or(1);
+    let _timer = CommandTimer::start(&state, "get_service_graph");
+    let preferences = read_state(&state).settings.graph_preferences.clone();
+    let mut exclude_service_ids = exclude_service_ids.unwrap_or_default();
+    let exclude_service_types = exclude_service_types.unwrap_or(preferences.exclude_service_types);
+    if !exclude_service_types.is_empty() {
+        let services = cache::shared_services(&state, &environment)?;
+        exclude_service_ids.extend(
+            services
+                .iter()
+                .filter(|s| exclude_service_types.contains(&s.service_type))
+                .map(|s| s.id.clone()),
+        );
+    }
+    let query = GraphQuery {
+        depth: depth.unwrap_or(preferences.depth),
+        relationship_types: None,
+        direction: direction.unwrap_or(preferences.direction),
+        include_effective_status: include_effective_status.unwrap_or(false),
+        include_criticality: include_criticality.unwrap_or(false),
+        include_groups: include_groups.unwrap_or(false),
+        include_annotations: include_annotations.unwrap_or(false),
+        max_nodes,
+        max_edges,
+        exclude_statuses: exclude_statuses.unwrap_or(preferences.exclude_statuses),
+        exclude_tags: exclude_tags.unwrap_or_default(),
+        exclude_service_ids,
+        aggregate_edges: aggregate_edges.unwrap_or(preferences.aggregate_edges),
+        as_of: dates::resolve_as_of(as_of, ignore_effective_dates)?,
+    };
 
-    // Load services if not cached
-    if !state.services_cache.contains_key(&environment) {
-        let services = storage::load_services(&state.data_path, &environment)?;
-        let services_map: std::collections::HashMap<String, Service> = services
-            .iter()
-            .map(|s| (s.id.clone(), s.clone()))
-            .collect();
-        state.services_cache.insert(environment.clone(), services_map);
-    }
-
-    // Load relationships if not cached
-    if !state.relationships_cache.contains_key(&environment) {
-        let relationships = storage::load_relationships(&state.data_path, &environment)?;
-        state
-            .relationships_cache
-            .insert(environment.clone(), relationships);
-    }
-
-    let services_map = state
-        .services_cache
-        .get(&environment)
-        .ok_or_else(|| AppError::EnvironmentNotFound(environment.clone()))?;
-
-    let all_relationships = state
-        .relationships_cache
-        .get(&environment)
-        .ok_or_else(|| AppError::EnvironmentNotFound(environment.clone()))?;
-
-    // Get center service
-    let center_service = services_map
-        .get(&center_service_id)
-        .ok_or_else(|| AppError::ServiceNotFound(center_service_id.clone()))?
-        .clone();
-
-    // Find connected services up to the specified depth
-    let mut visited: HashSet<String> = HashSet::new();
-    let mut current_level: HashSet<String> = HashSet::new();
-    current_level.insert(center_service_id.clone());
-    visited.insert(center_service_id.clone());
-
-    let mut connected_service_ids: HashSet<String> = HashSet::new();
-    let mut relevant_relationships: Vec<Relationship> = Vec::new();
-    let mut seen_relationship_ids: HashSet<String> = HashSet::new();
-
-    for _ in 0..depth {
-        let mut next_level: HashSet<String> = HashSet::new();
-
-        for service_id in &current_level {
-            // Find relationships where this service is source or target
-            for rel in all_relationships {
-                let connected_id = if rel.source == *service_id {
-                    Some(&rel.target)
-                } else if rel.target == *service_id {
-                    Some(&rel.source)
-                } else {
-                    None
-                };
-
-                if let Some(connected_id) = connected_id {
-                    // Add relationship if not already seen (O(1) lookup instead of O(n))
-                    if !seen_relationship_ids.contains(&rel.id) {
-                        // Only include if both services exist
-                        if services_map.contains_key(&rel.source)
-                            && services_map.contains_key(&rel.target)
-                        {
-                            seen_relationship_ids.insert(rel.id.clone());
-                            relevant_relationships.push(rel.clone());
-                        }
+    let graph = build_service_graph(&state, &environment, &[center_service_id], &query)?;
+    let (recent, data_path) = {
+        let state = read_state(&state);
+        (Arc::clone(&state.recent_services), state.data_path.clone())
+    };
+    recent.record(&data_path, &environment, &graph.center_service.id);
+
+    Ok(graph)
+}
+
+/// How `build_service_graph` traverses and annotates the graph.
+///
+/// # Fields
+///
+/// * `depth` - Maximum traversal depth
+/// * `relationship_types` - Relationship types to follow; all of them if `None`
+/// * `direction` - Which way relationships are followed
+/// * `include_effective_status` / `include_criticality` / `include_groups` /
+///   `include_annotations` - What to add to the nodes, as for `get_service_graph`
+/// * `max_nodes` / `max_edges` - Size limits of the graph, as for `get_service_graph`
+/// * `exclude_statuses` / `exclude_tags` / `exclude_service_ids` - Services to leave
+///   out and not traverse through, as for `get_service_graph`
+/// * `aggregate_edges` - Whether to also return the aggregated edges
+/// * `as_of` - Date relationships must be in effect on to be followed; all of them
+///   if `None`
+#[derive(Debug, Clone)]
+pub struct GraphQuery {
+    pub depth: u32,
+    pub relationship_types: Option<Vec<RelationshipType>>,
+    pub direction: TraversalDirection,
+    pub include_effective_status: bool,
+    pub include_criticality: bool,
+    pub include_groups: bool,
+    pub include_annotations: bool,
+    pub max_nodes: Option<usize>,
+    pub max_edges: Option<usize>,
+    pub exclude_statuses: Vec<ServiceStatus>,
+    pub exclude_tags: Vec<String>,
+    pub exclude_service_ids: Vec<String>,
+    pub aggregate_edges: bool,
+    pub as_of: Option<String>,
+}
+
+impl GraphQuery {
+    /// Returns whether the query leaves a service out of the graph.
+    pub fn excludes(&self, service: &Service) -> bool {
+        self.exclude_service_ids.contains(&service.id)
+            || has_excluded_status_or_tag(service, &self.exclude_statuses, &self.exclude_tags)
+    }
+}
+
+/// Returns whether a service has one of the given statuses or tags.
+fn has_excluded_status_or_tag(
+    service: &Service,
+    exclude_statuses: &[ServiceStatus],
+    exclude_tags: &[String],
+) -> bool {
+    exclude_statuses.contains(&service.status)
+        || service.tags.iter().any(|tag| exclude_tags.contains(tag))
+}
+
+/// Builds the graph around one or more center services.
+///
+/// The traversal behind `get_service_graph`. The first center becomes the
+/// `center_service`; any others are returned among the connected services.
+/// Centers are always included, even if they match an exclusion.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment to query
+/// * `center_ids` - The services to start from
+/// * `query` - How to traverse and annotate the graph
+///
+/// # Returns
+///
+/// * `Ok(GraphData)` - The services within the depth and the relationships between them
+/// * `Err(AppError::ServiceNotFound)` - If a center service doesn't exist
+/// * `Err(AppError::ValidationError)` - If no center service is given
+/// * `Err(AppError::FileLoad)` - If groups or annotations were requested and their
+///   file cannot be parsed
+pub fn build_service_graph(
+    state: &RwLock<AppState>,
+    environment: &str,
+    center_ids: &[String],
+    query: &GraphQuery,
+) -> Result<GraphData, AppError> {
+    // Borrow the shared cached data; the lock isn't held while the graph is built
+    let services = cache::shared_services(state, environment)?;
+    let (all_relationships, relationship_index) =
+        cache::shared_indexed_relationships(state, environment)?;
+
+    build_graph_from(
+        state,
+        environment,
+        &services,
+        &all_relationships,
+        &relationship_index,
+        center_ids,
+        query,
+    )
+}
+
+/// Builds the graph like `build_service_graph`, from given services and
+/// relationships instead of the cached ones.
+///
+/// `simulate_graph` passes the data as a change would leave it. Groups and
+/// annotations, if requested, are still read from the environment.
+///
+/// # Arguments
+///
+/// * `state` - The application state, for the data path
+/// * `environment` - The name of the environment the groups and annotations are read from
+/// * `services` - All services
+/// * `all_relationships` - All relationships, in stored order
+/// * `relationship_index` - The index of `all_relationships` by service
+/// * `center_ids` - The services to start from
+/// * `query` - How to traverse and annotate the graph
+pub fn build_graph_from(
+    state: &RwLock<AppState>,
+    environment: &str,
+    services: &[Arc<Service>],
+    all_relationships: &[Relationship],
+    relationship_index: &RelationshipIndex,
+    center_ids: &[String],
+    query: &GraphQuery,
+) -> Result<GraphData, AppError> {
+    let mut services_map: HashMap<&str, &Service> = services
+        .iter()
+        .map(|s| (s.id.as_str(), s.as_ref()))
+        .collect();
+
+    // Get center services
+    let Some(center_service_id) = center_ids.first() else {
+        return Err(AppError::ValidationError(
+            "At least one center service is required".to_string(),
+        ));
+    };
+    if let Some(missing) = center_ids
+        .iter()
+        .find(|id| !services_map.contains_key(id.as_str()))
+    {
+        return Err(AppError::ServiceNotFound(missing.clone()));
+    }
+    let center_service = services_map[center_service_id.as_str()].clone();
+    let center_excluded = query.excludes(&center_service);
+
+    // Excluded services are unknown to the traversal, so it can't pass through them
+    services_map.retain(|id, service| {
+        !query.excludes(service) || center_ids.iter().any(|center| center == id)
+    });
+
+    // Find connected services up to the specified depth. Services and
+    // relationships are tracked by reference into the cached data; only the
+    // relationships that end up in the graph are cloned, once, at the end.
+    let mut visited: HashSet<&str> = HashSet::from([center_service_id.as_str()]);
+    let mut connected_service_ids: Vec<&str> = Vec::new();
+    for id in &center_ids[1..] {
+        if visited.insert(id) {
+            connected_service_ids.push(id);
+        }
+    }
+    let mut current_level: Vec<&str> = visited.iter().copied().collect();
+
+    let mut relevant_positions: Vec<usize> = Vec::new();
+    let mut seen_positions: HashSet<usize> = HashSet::new();
+    let mut omitted_neighbor_counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut truncated = false;
+
+    let max_nodes = query.max_nodes.unwrap_or(usize::MAX);
+    let max_edges = query.max_edges.unwrap_or(usize::MAX);
+
+    for _ in 0..query.depth {
+        let mut next_level: Vec<&str> = Vec::new();
+
+        // Expand in ID order so the caps always keep the same services
+        current_level.sort_unstable();
+        for &service_id in &current_level {
+            let neighbors = neighbors_of(
+                all_relationships,
+                relationship_index,
+                &services_map,
+                service_id,
+                query.relationship_types.as_deref(),
+                query.direction,
+                query.as_of.as_deref(),
+            );
+
+            let mut omitted = 0;
+            for (connected_id, positions) in neighbors {
+                if !visited.contains(connected_id) {
+                    if visited.len() >= max_nodes || relevant_positions.len() >= max_edges {
+                        omitted += 1;
+                        continue;
                     }
+                    visited.insert(connected_id);
+                    next_level.push(connected_id);
+                    connected_service_ids.push(connected_id);
+                }
 
-                    // Add to next level if not visited
-                    if !visited.contains(connected_id) {
-                        next_level.insert(connected_id.clone());
-                        visited.insert(connected_id.clone());
-                        connected_service_ids.insert(connected_id.clone());
+                for position in positions {
+                    if seen_positions.contains(&position) {
+                        continue;
+                    }
+                    if relevant_positions.len() >= max_edges {
+                        truncated = true;
+                        break;
                     }
+                    seen_positions.insert(position);
+                    relevant_positions.push(position);
                 }
             }
+
+            if omitted > 0 {
+                truncated = true;
+                omitted_neighbor_counts.insert(service_id.to_string(), omitted);
+            }
         }
 
+        if next_level.is_empty() {
+            break;
+        }
         current_level = next_level;
     }
 
+    // Relationships in stored order, as without the caps
+    relevant_positions.sort_unstable();
+    let relevant_relationships: Vec<Relationship> = relevant_positions
+        .iter()
+        .map(|&position| all_relationships[position].clone())
+        .collect();
+    let aggregated_edges = query
+        .aggregate_edges
+        .then(|| edges::aggregate_edges(&relevant_relationships));
+
     // Get the connected services
     let connected_services: Vec<Service> = connected_service_ids
         .iter()
-        .filter_map(|id| services_map.get(id).cloned())
+        .filter_map(|id| services_map.get(id).map(|s| (*s).clone()))
         .collect();
 
+    let in_graph = |id: &str| visited.contains(id);
+
+    let effective_statuses = query.include_effective_status.then(|| {
+        health::effective_statuses(services, all_relationships, false)
+            .into_iter()
+            .filter(|status| in_graph(&status.service_id))
+            .map(|status| (status.service_id, status.effective_status))
+            .collect()
+    });
+
+    let criticality_scores = if query.include_criticality {
+        let scores = criticality::criticality_scores(
+            services,
+            all_relationships,
+            criticality::DEFAULT_DAMPING,
+            criticality::DEFAULT_ITERATIONS,
+        )?;
+        Some(
+            services
+                .iter()
+                .zip(scores)
+                .filter(|(service, _)| in_graph(&service.id))
+                .map(|(service, score)| (service.id.clone(), score))
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let group_memberships = if query.include_groups {
+        let groups = storage::load_groups(&read_state(state).data_path, environment)?;
+        let mut memberships: HashMap<String, Vec<String>> = HashMap::new();
+        for group in groups {
+            for member_id in group.member_ids {
+                if in_graph(&member_id) {
+                    memberships
+                        .entry(member_id)
+                        .or_default()
+                        .push(group.id.clone());
+                }
+            }
+        }
+        Some(memberships)
+    } else {
+        None
+    };
+
+    let annotations = if query.include_annotations {
+        let annotations = storage::load_annotations(&read_state(state).data_path, environment)?;
+        Some(
+            annotations
+                .into_iter()
+                .filter(|(service_id, _)| in_graph(service_id))
+                .collect(),
+        )
+    } else {
+        None
+    };
+
     Ok(GraphData {
         center_service,
         connected_services,
         relationships: relevant_relationships,
+        effective_statuses,
+        criticality_scores,
+        group_memberships,
+        annotations,
+        aggregated_edges,
+        center_excluded,
+        truncated,
+        omitted_neighbor_counts,
+    })
+}
+
+/// Groups the relationships a traversal follows from a service by the
+/// service at their other end.
+///
+/// Only relationships of the given types (all if `None`) that lead the
+/// given way, are in effect on `as_of` (if given) and whose other end
+/// exists are included. Neighbors are sorted by ID and their relationships
+/// listed in stored order.
+fn neighbors_of<'a>(
+    relationships: &'a [Relationship],
+    relationship_index: &RelationshipIndex,
+    services_map: &HashMap<&str, &Service>,
+    service_id: &str,
+    relationship_types: Option<&[RelationshipType]>,
+    direction: TraversalDirection,
+    as_of: Option<&str>,
+) -> BTreeMap<&'a str, Vec<usize>> {
+    let downstream = direction != TraversalDirection::Upstream;
+    let upstream = direction != TraversalDirection::Downstream;
+
+    let mut neighbors: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for &position in relationship_index.positions(service_id) {
+        let Some(rel) = relationships.get(position) else {
+            continue;
+        };
+        if relationship_types.is_some_and(|types| !types.contains(&rel.relationship_type)) {
+            continue;
+        }
+        if as_of.is_some_and(|date| !rel.is_effective_on(date)) {
+            continue;
+        }
+
+        let connected_id = if downstream && rel.source == service_id {
+            rel.target.as_str()
+        } else if upstream && rel.target == service_id {
+            rel.source.as_str()
+        } else {
+            continue;
+        };
+        if services_map.contains_key(connected_id) {
+            neighbors.entry(connected_id).or_default().push(position);
+        }
+    }
+    neighbors
+}
+
+/// The neighbors of one service that a capped graph left out.
+///
+/// # Fields
+///
+/// * `service_id` - ID of the expanded service
+/// * `services` - Its neighbors that were not excluded, sorted by ID
+/// * `relationships` - The relationships between it and those neighbors, in stored order
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNodeExpansion {
+    pub service_id: String,
+    pub services: Vec<Service>,
+    pub relationships: Vec<Relationship>,
+}
+
+/// Fetches the neighbors of one service omitted from a capped graph.
+///
+/// Backs the "+N more" badges of a truncated `get_service_graph` result:
+/// pass the services already shown as `exclude_ids` to get only the
+/// missing neighbors and the relationships connecting them to the service.
+/// Relationships to services already shown are not repeated.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `service_id` - The service to expand
+/// * `exclude_ids` - Services to leave out, typically those already in the graph
+/// * `relationship_types` - Relationship types to follow; all of them if absent
+/// * `direction` - Which way relationships are followed (default: both)
+/// * `exclude_statuses` / `exclude_tags` - Optional statuses and tags of services to
+///   leave out, as for `get_service_graph`
+/// * `as_of` / `ignore_effective_dates` - Which relationships to follow by their
+///   effective dates, as for `get_service_graph` (default: those in effect today)
+///
+/// # Returns
+///
+/// * `Ok(GraphNodeExpansion)` - The neighbors and their relationships to the service
+/// * `Err(AppError::ServiceNotFound)` - If the service doesn't exist
+/// * `Err(AppError::ValidationError)` - If `as_of` isn't a `YYYY-MM-DD` date
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend - expand a "+37 more" badge:
+/// const expansion = await invoke('expand_graph_node', {
+///     environment: 'prod',
+///     serviceId: 'payments-api',
+///     excludeIds: shownServiceIds
+/// });
+/// addToGraph(expansion.services, expansion.relationships);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub fn expand_graph_node(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    service_id: String,
+    exclude_ids: Vec<String>,
+    relationship_types: Option<Vec<RelationshipType>>,
+    direction: Option<TraversalDirection>,
+    exclude_statuses: Option<Vec<ServiceStatus>>,
+    exclude_tags: Option<Vec<String>>,
+    as_of: Option<String>,
+    ignore_effective_dates: Option<bool>,
+) -> Result<GraphNodeExpansion, AppError> {
+    let _timer = CommandTimer::start(&state, "expand_graph_node");
+    let as_of = dates::resolve_as_of(as_of, ignore_effective_dates)?;
+    let services = cache::shared_services(&state, &environment)?;
+    if !services.iter().any(|s| s.id == service_id) {
+        return Err(AppError::ServiceNotFound(service_id));
+    }
+    let exclude_statuses = exclude_statuses.unwrap_or_default();
+    let exclude_tags = exclude_tags.unwrap_or_default();
+    let services_map: HashMap<&str, &Service> = services
+        .iter()
+        .filter(|s| {
+            s.id == service_id || !has_excluded_status_or_tag(s, &exclude_statuses, &exclude_tags)
+        })
+        .map(|s| (s.id.as_str(), s.as_ref()))
+        .collect();
+    let (relationships, relationship_index) =
+        cache::shared_indexed_relationships(&state, &environment)?;
+
+    let excluded: HashSet<&str> = exclude_ids.iter().map(String::as_str).collect();
+    let neighbors = neighbors_of(
+        &relationships,
+        &relationship_index,
+        &services_map,
+        &service_id,
+        relationship_types.as_deref(),
+        direction.unwrap_or_default(),
+        as_of.as_deref(),
+    );
+
+    let mut expanded_services = Vec::new();
+    let mut positions = Vec::new();
+    for (connected_id, connected_positions) in neighbors {
+        if excluded.contains(connected_id) {
+            continue;
+        }
+        if connected_id != service_id {
+            expanded_services.push(services_map[connected_id].clone());
+        }
+        positions.extend(connected_positions);
+    }
+    positions.sort_unstable();
+
+    Ok(GraphNodeExpansion {
+        service_id,
+        services: expanded_services,
+        relationships: positions
+            .into_iter()
+            .map(|position| relationships[position].clone())
+            .collect(),
     })
 }
+
+/// Computes the effective status of every service in an environment.
+///
+/// A service is only as healthy as its dependencies: one whose transitive
+/// dependency is degraded or unhealthy is effectively degraded, unless its
+/// own status is already worse. Unknown and deprecated services don't
+/// propagate. Dependency cycles are handled; every service is visited at
+/// most once per status level.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment to query
+/// * `include_data_flow` - If true, reads_from and writes_to relationships count as
+///   dependencies as well as depends_on (default: false)
+///
+/// # Returns
+///
+/// * `Ok(Vec<EffectiveStatus>)` - One entry per service, sorted by ID, with the
+///   shortest path to its worst dependency
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const statuses = await invoke('get_effective_status', { environment: 'prod' });
+/// for (const s of statuses.filter(s => s.effectiveStatus !== s.ownStatus)) {
+///     console.log(`${s.serviceId} is impaired via ${s.worstDependencyPath.join(' -> ')}`);
+/// }
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_effective_status(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    include_data_flow: Option<bool>,
+) -> Result<Vec<EffectiveStatus>, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    let mut statuses = health::effective_statuses(
+        &services,
+        &relationships,
+        include_data_flow.unwrap_or(false),
+    );
+    statuses.sort_by(|a, b| a.service_id.cmp(&b.service_id));
+
+    Ok(statuses)
+}
+
+/// A service and how critical it is to the rest of the environment.
+///
+/// # Fields
+///
+/// * `service_id` - ID of the service
+/// * `service_name` - Name of the service
+/// * `score` - PageRank over the dependencies, normalized so the most critical
+///   service scores 1.0
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CriticalityScore {
+    pub service_id: String,
+    pub service_name: String,
+    pub score: f64,
+}
+
+/// Ranks the services of an environment by how much depends on them.
+///
+/// Runs PageRank over the depends_on relationships with rank flowing from
+/// each service to its dependencies, so services that much of the graph
+/// transitively relies on score highest, not just those with the most direct
+/// dependents. Iteration stops early once the scores converge.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `damping` - Probability of following a dependency rather than jumping to a
+///   random service, between 0 and 1 (default: 0.85)
+/// * `iterations` - Maximum number of iterations, at most 1000 (default: 30)
+///
+/// # Returns
+///
+/// * `Ok(Vec<CriticalityScore>)` - Every service, most critical first (ties by ID)
+/// * `Err(AppError::ValidationError)` - If `damping` or `iterations` is out of range
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const ranking = await invoke('get_criticality_ranking', { environment: 'prod' });
+/// console.log(`Most critical: ${ranking[0].serviceName}`);
+/// ```
+#[tauri::command]
+pub fn get_criticality_ranking(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    damping: Option<f64>,
+    iterations: Option<u32>,
+) -> Result<Vec<CriticalityScore>, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    let scores = criticality::criticality_scores(
+        &services,
+        &relationships,
+        damping.unwrap_or(criticality::DEFAULT_DAMPING),
+        iterations.unwrap_or(criticality::DEFAULT_ITERATIONS),
+    )?;
+
+    let mut ranking: Vec<CriticalityScore> = services
+        .iter()
+        .zip(scores)
+        .map(|(service, score)| CriticalityScore {
+            service_id: service.id.clone(),
+            service_name: service.name.clone(),
+            score,
+        })
+        .collect();
+    ranking.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.service_id.cmp(&b.service_id))
+    });
+
+    Ok(ranking)
+}
+
+/// Assigns the services of an environment to layers for a layered view.
+///
+/// Layer 0 holds the services nothing depends on; every other service is
+/// placed one layer past the furthest service depending on it, following
+/// depends_on relationships (the length of the longest path reaching it).
+/// The services of a dependency cycle share one layer and are also listed
+/// as a cycle group. The result is deterministic, so it can be used to
+/// position nodes.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `seed_by_type` - If true, force frontends into the first layer and databases
+///   into the last (default: false)
+///
+/// # Returns
+///
+/// * `Ok(DependencyLayers)` - Service IDs per layer and the cycle groups
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const { layers, cycles } = await invoke('get_dependency_layers', {
+///     environment: 'prod',
+///     seedByType: true
+/// });
+/// layers.forEach((ids, x) => ids.forEach((id, y) => position(id, x, y)));
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_dependency_layers(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    seed_by_type: Option<bool>,
+) -> Result<DependencyLayers, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    Ok(layers::dependency_layers(
+        &services,
+        &relationships,
+        seed_by_type.unwrap_or(false),
+    ))
+}
+
+/// A service related to only one of the compared services.
+///
+/// # Fields
+///
+/// * `service_id` - ID of the related service
+/// * `relationship_types` - Types of the relationships connecting it, sorted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedService {
+    pub service_id: String,
+    pub relationship_types: Vec<RelationshipType>,
+}
+
+/// A service related to both compared services.
+///
+/// # Fields
+///
+/// * `service_id` - ID of the related service
+/// * `relationship_types_a` - Types of the relationships connecting it to service A
+/// * `relationship_types_b` - Types of the relationships connecting it to service B
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedRelatedService {
+    pub service_id: String,
+    pub relationship_types_a: Vec<RelationshipType>,
+    pub relationship_types_b: Vec<RelationshipType>,
+}
+
+/// How the related services of two services overlap.
+///
+/// # Fields
+///
+/// * `only_a` - Services related only to service A, sorted by ID
+/// * `only_b` - Services related only to service B, sorted by ID
+/// * `shared` - Services related to both, sorted by ID
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedServicesComparison {
+    pub only_a: Vec<RelatedService>,
+    pub only_b: Vec<RelatedService>,
+    pub shared: Vec<SharedRelatedService>,
+}
+
+/// The dependencies and dependents two services have in common and apart.
+///
+/// # Fields
+///
+/// * `service_a` / `service_b` - IDs of the compared services
+/// * `dependencies` - Targets of the services' relationships
+/// * `dependents` - Sources of relationships to the services
+/// * `transitive_dependencies` - Everything reachable along relationships, if requested
+/// * `transitive_dependents` - Everything reaching the services, if requested
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyComparison {
+    pub service_a: String,
+    pub service_b: String,
+    pub dependencies: RelatedServicesComparison,
+    pub dependents: RelatedServicesComparison,
+    pub transitive_dependencies: Option<RelatedServicesComparison>,
+    pub transitive_dependents: Option<RelatedServicesComparison>,
+}
+
+/// Compares the dependencies and dependents of two services.
+///
+/// Relationships of every type count, and each related service lists the
+/// relationship types involved, so a service both read from and written to
+/// shows up as shared with different types. For the transitive sets, the
+/// types are those of the relationships by which the service is reached.
+/// Relationships to missing services are ignored.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `service_a` - ID of the first service
+/// * `service_b` - ID of the second service
+/// * `include_transitive` - If true, also compare everything reachable in either
+///   direction (default: false)
+///
+/// # Returns
+///
+/// * `Ok(DependencyComparison)` - The overlap of direct (and transitive) relations
+/// * `Err(AppError::ServiceNotFound)` - Naming the first of the services that doesn't exist
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const comparison = await invoke('compare_service_dependencies', {
+///     environment: 'prod',
+///     serviceA: 'user-api',
+///     serviceB: 'user-service'
+/// });
+/// console.log(`${comparison.dependencies.shared.length} shared dependencies`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn compare_service_dependencies(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    service_a: String,
+    service_b: String,
+    include_transitive: Option<bool>,
+) -> Result<DependencyComparison, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    let service_ids: HashSet<&str> = services.iter().map(|s| s.id.as_str()).collect();
+    for id in [&service_a, &service_b] {
+        if !service_ids.contains(id.as_str()) {
+            return Err(AppError::ServiceNotFound(id.clone()));
+        }
+    }
+
+    // (neighbor, relationship type) per service, in both directions
+    let mut outgoing: HashMap<&str, Vec<(&str, &RelationshipType)>> = HashMap::new();
+    let mut incoming: HashMap<&str, Vec<(&str, &RelationshipType)>> = HashMap::new();
+    for rel in relationships.iter() {
+        if service_ids.contains(rel.source.as_str()) && service_ids.contains(rel.target.as_str()) {
+            let rel_type = &rel.relationship_type;
+            outgoing
+                .entry(&rel.source)
+                .or_default()
+                .push((&rel.target, rel_type));
+            incoming
+                .entry(&rel.target)
+                .or_default()
+                .push((&rel.source, rel_type));
+        }
+    }
+
+    let compare = |edges: &HashMap<&str, Vec<(&str, &RelationshipType)>>, transitive: bool| {
+        compare_related(
+            related_services(edges, &service_a, transitive),
+            related_services(edges, &service_b, transitive),
+        )
+    };
+    let transitive = include_transitive.unwrap_or(false);
+
+    Ok(DependencyComparison {
+        dependencies: compare(&outgoing, false),
+        dependents: compare(&incoming, false),
+        transitive_dependencies: transitive.then(|| compare(&outgoing, true)),
+        transitive_dependents: transitive.then(|| compare(&incoming, true)),
+        service_a,
+        service_b,
+    })
+}
+
+/// Collects the services a service is related to along the given edges.
+///
+/// # Returns
+///
+/// The related services (never the service itself) with the relationship types
+/// by which they are reached, sorted and deduplicated
+fn related_services<'a>(
+    edges: &HashMap<&str, Vec<(&'a str, &'a RelationshipType)>>,
+    start: &str,
+    transitive: bool,
+) -> BTreeMap<&'a str, Vec<RelationshipType>> {
+    let mut related: BTreeMap<&str, Vec<RelationshipType>> = BTreeMap::new();
+    let mut queue = VecDeque::from([start]);
+    let mut visited: HashSet<&str> = HashSet::from([start]);
+
+    while let Some(current) = queue.pop_front() {
+        for &(neighbor, rel_type) in edges.get(current).into_iter().flatten() {
+            if neighbor == start {
+                continue;
+            }
+            let types = related.entry(neighbor).or_default();
+            if !types.contains(rel_type) {
+                types.push(rel_type.clone());
+            }
+            if transitive && visited.insert(neighbor) {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    for types in related.values_mut() {
+        types.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    }
+    related
+}
+
+/// Splits the related services of two services into those only one has and shared ones.
+fn compare_related(
+    a: BTreeMap<&str, Vec<RelationshipType>>,
+    mut b: BTreeMap<&str, Vec<RelationshipType>>,
+) -> RelatedServicesComparison {
+    let mut only_a = Vec::new();
+    let mut shared = Vec::new();
+    for (service_id, relationship_types_a) in a {
+        match b.remove(service_id) {
+            Some(relationship_types_b) => shared.push(SharedRelatedService {
+                service_id: service_id.to_string(),
+                relationship_types_a,
+                relationship_types_b,
+            }),
+            None => only_a.push(RelatedService {
+                service_id: service_id.to_string(),
+                relationship_types: relationship_types_a,
+            }),
+        }
+    }
+
+    let only_b = b
+        .into_iter()
+        .map(|(service_id, relationship_types)| RelatedService {
+            service_id: service_id.to_string(),
+            relationship_types,
+        })
+        .collect();
+
+    RelatedServicesComparison {
+        only_a,
+        only_b,
+        shared,
+    }
+}
+
+/// Whether one service can reach another.
+///
+/// # Fields
+///
+/// * `reachable` - True if a path exists
+/// * `distance` - Number of relationships on the shortest path (0 from a service
+///   to itself); absent if unreachable
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reachability {
+    pub reachable: bool,
+    pub distance: Option<u32>,
+}
+
+/// A pair of services to check reachability between.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReachabilityQuery {
+    pub from_id: String,
+    pub to_id: String,
+}
+
+/// Checks whether one service can reach another along relationships.
+///
+/// Runs a breadth-first search that stops as soon as the target is found,
+/// without collecting the path.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `from_id` - ID of the service to start from
+/// * `to_id` - ID of the service to reach
+/// * `relationship_types` - Relationship types to follow (default: depends_on only)
+/// * `directed` - If false, relationships are also followed from target to source
+///   (default: true)
+///
+/// # Returns
+///
+/// * `Ok(Reachability)` - Whether and how far `to_id` is reachable
+/// * `Err(AppError::ServiceNotFound)` - If either service doesn't exist
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const { reachable, distance } = await invoke('is_reachable', {
+///     environment: 'prod',
+///     fromId: 'web-frontend',
+///     toId: 'users-db'
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn is_reachable(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    from_id: String,
+    to_id: String,
+    relationship_types: Option<Vec<RelationshipType>>,
+    directed: Option<bool>,
+) -> Result<Reachability, AppError> {
+    let query = ReachabilityQuery { from_id, to_id };
+    let mut results = are_reachable(
+        state,
+        environment,
+        vec![query],
+        relationship_types,
+        directed,
+    )?;
+
+    Ok(results.remove(0))
+}
+
+/// Checks reachability for many pairs of services at once.
+///
+/// The graph is built once for all pairs, and pairs starting at the same
+/// service share one breadth-first search, which stops once all of their
+/// targets are found.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `pairs` - The services to check, each as `{ fromId, toId }`
+/// * `relationship_types` - Relationship types to follow (default: depends_on only)
+/// * `directed` - If false, relationships are also followed from target to source
+///   (default: true)
+///
+/// # Returns
+///
+/// * `Ok(Vec<Reachability>)` - One result per pair, in order
+/// * `Err(AppError::ServiceNotFound)` - Naming the first service of the pairs that
+///   doesn't exist
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const results = await invoke('are_reachable', {
+///     environment: 'prod',
+///     pairs: [
+///         { fromId: 'web-frontend', toId: 'users-db' },
+///         { fromId: 'billing-api', toId: 'users-db' }
+///     ]
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn are_reachable(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    pairs: Vec<ReachabilityQuery>,
+    relationship_types: Option<Vec<RelationshipType>>,
+    directed: Option<bool>,
+) -> Result<Vec<Reachability>, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    let relationship_types =
+        relationship_types.unwrap_or_else(|| vec![RelationshipType::DependsOn]);
+    let directed = directed.unwrap_or(true);
+
+    let index: HashMap<&str, usize> = services
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.id.as_str(), i))
+        .collect();
+    let position = |id: &str| {
+        index
+            .get(id)
+            .copied()
+            .ok_or_else(|| AppError::ServiceNotFound(id.to_string()))
+    };
+
+    let mut edges: Vec<Vec<usize>> = vec![Vec::new(); services.len()];
+    for rel in relationships.iter() {
+        if !relationship_types.contains(&rel.relationship_type) {
+            continue;
+        }
+        if let (Some(&source), Some(&target)) = (
+            index.get(rel.source.as_str()),
+            index.get(rel.target.as_str()),
+        ) {
+            edges[source].push(target);
+            if !directed {
+                edges[target].push(source);
+            }
+        }
+    }
+
+    // Group the targets by start so each start is searched once
+    let mut targets_by_start: HashMap<usize, HashSet<usize>> = HashMap::new();
+    let mut queries = Vec::with_capacity(pairs.len());
+    for pair in &pairs {
+        let (from, to) = (position(&pair.from_id)?, position(&pair.to_id)?);
+        targets_by_start.entry(from).or_default().insert(to);
+        queries.push((from, to));
+    }
+
+    let distances: HashMap<usize, HashMap<usize, u32>> = targets_by_start
+        .into_iter()
+        .map(|(start, targets)| (start, distances_to(&edges, start, targets)))
+        .collect();
+
+    Ok(queries
+        .into_iter()
+        .map(|(from, to)| {
+            let distance = distances
+                .get(&from)
+                .and_then(|found| found.get(&to))
+                .copied();
+            Reachability {
+                reachable: distance.is_some(),
+                distance,
+            }
+        })
+        .collect())
+}
+
+/// Searches breadth-first from a service until all targets are found.
+///
+/// # Returns
+///
+/// The distance to each reachable target
+fn distances_to(
+    edges: &[Vec<usize>],
+    start: usize,
+    mut targets: HashSet<usize>,
+) -> HashMap<usize, u32> {
+    let mut found = HashMap::new();
+    let mut distance: Vec<Option<u32>> = vec![None; edges.len()];
+    let mut queue = VecDeque::from([start]);
+    distance[start] = Some(0);
+
+    while let Some(current) = queue.pop_front() {
+        let hops = distance[current].unwrap_or(0);
+        if targets.remove(&current) {
+            found.insert(current, hops);
+            if targets.is_empty() {
+                break;
+            }
+        }
+        for &next in &edges[current] {
+            if distance[next].is_none() {
+                distance[next] = Some(hops + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    found
+}
+
+/// Suggests relationships to remove to break the dependency cycles of an environment.
+///
+/// Cycles are formed by depends_on and communicates_with relationships. The
+/// relationship on the most cycles is suggested first, preferring
+/// communicates_with over depends_on on ties, until removing all suggestions
+/// would leave no cycle. This is a greedy approximation; fewer removals may
+/// be possible.
+///
+/// The command only suggests; remove the relationships with
+/// `delete_relationship` or, to do it as one undoable change, `execute_batch`.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(CycleBreakReport)` - The cyclic groups and the suggested removals, each with
+///   the cycle it breaks and why it was picked
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const { suggestions } = await invoke('suggest_cycle_breaks', { environment: 'prod' });
+/// await invoke('execute_batch', {
+///     environment: 'prod',
+///     operations: suggestions.flatMap(s => s.relationshipIds.map(relationshipId => ({
+///         op: 'delete_relationship',
+///         relationshipId
+///     })))
+/// });
+/// ```
+#[tauri::command]
+pub fn suggest_cycle_breaks(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<CycleBreakReport, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    Ok(cycles::suggest_cycle_breaks(&services, &relationships))
+}
+
+/// ID of the group node holding services without the grouping attribute.
+pub const UNGROUPED_ID: &str = "ungrouped";
+
+/// What to collapse services into groups by.
+///
+/// Deserialized as `"team"`, `"serviceType"` or `{ "tagPrefix": "domain:" }`.
+/// With a tag prefix, a service belongs to the group named after the rest of
+/// its first tag (alphabetically) starting with the prefix, e.g. "payments"
+/// for `domain:payments`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GroupBy {
+    Team,
+    ServiceType,
+    TagPrefix(String),
+}
+
+impl GroupBy {
+    /// Returns the name of the group a service belongs to, if any.
+    fn group_of(&self, service: &Service) -> Option<String> {
+        match self {
+            GroupBy::Team => service
+                .team
+                .as_deref()
+                .map(str::trim)
+                .filter(|team| !team.is_empty())
+                .map(str::to_string),
+            GroupBy::ServiceType => Some(service.service_type.as_str().to_string()),
+            GroupBy::TagPrefix(prefix) => service
+                .tags
+                .iter()
+                .filter_map(|tag| tag.strip_prefix(prefix.as_str()))
+                .filter(|rest| !rest.is_empty())
+                .min()
+                .map(str::to_string),
+        }
+    }
+}
+
+/// A group of services shown as one node.
+///
+/// # Fields
+///
+/// * `id` - `group:{name}`, or `ungrouped` for services without the attribute
+/// * `name` - The team, service type or tag remainder; "Ungrouped" for the rest
+/// * `status` - The worst status among the members
+/// * `member_count` - Number of services in the group
+/// * `member_ids` - IDs of the services in the group, sorted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupNode {
+    pub id: String,
+    pub name: String,
+    pub status: ServiceStatus,
+    pub member_count: usize,
+    pub member_ids: Vec<String>,
+}
+
+/// The relationships from members of one group to members of another.
+///
+/// # Fields
+///
+/// * `id` - `{source}->{target}`
+/// * `source` / `target` - IDs of the group nodes
+/// * `count` - Number of relationships collapsed into this edge
+/// * `relationship_types` - The types among them, sorted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupRelationship {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub count: usize,
+    pub relationship_types: Vec<RelationshipType>,
+}
+
+/// The graph collapsed into groups, shaped like `GraphData`.
+///
+/// # Fields
+///
+/// * `groups` - The group nodes, sorted by name with the ungrouped node last
+/// * `relationships` - The aggregated relationships between groups, sorted by source
+///   and target
+/// * `intra_group_relationship_count` - Relationships within a group, which are dropped
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupedGraphData {
+    pub groups: Vec<GroupNode>,
+    pub relationships: Vec<GroupRelationship>,
+    pub intra_group_relationship_count: usize,
+}
+
+/// Ranks statuses from best to worst, for picking a group's worst member.
+fn status_severity(status: &ServiceStatus) -> u8 {
+    match status {
+        ServiceStatus::Healthy => 0,
+        ServiceStatus::Deprecated => 1,
+        ServiceStatus::Unknown => 2,
+        ServiceStatus::Degraded => 3,
+        ServiceStatus::Unhealthy => 4,
+    }
+}
+
+/// Collapses the graph of an environment into groups of services.
+///
+/// Each group becomes one node whose status is that of its worst member
+/// (unhealthy, then degraded, unknown, deprecated and healthy). Relationships
+/// between members of different groups become one weighted edge per pair of
+/// groups. Relationships within a group are dropped but counted; those
+/// involving missing services are ignored, as are those not in effect on
+/// `as_of` (see `get_service_graph`).
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `group_by` - `"team"`, `"serviceType"` or `{ "tagPrefix": "..." }`
+/// * `as_of` - Optional `YYYY-MM-DD` date relationships must be in effect on
+///   (default: today)
+/// * `ignore_effective_dates` - If true, count every relationship whatever its
+///   effective dates (default: false)
+///
+/// # Returns
+///
+/// * `Ok(GroupedGraphData)` - The group nodes and aggregated relationships
+/// * `Err(AppError::ValidationError)` - If `as_of` isn't a `YYYY-MM-DD` date
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend - one node per team:
+/// const teams = await invoke('get_grouped_graph', { environment: 'prod', groupBy: 'team' });
+///
+/// // One node per domain tag (e.g., "domain:payments"):
+/// const domains = await invoke('get_grouped_graph', {
+///     environment: 'prod',
+///     groupBy: { tagPrefix: 'domain:' }
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_grouped_graph(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    group_by: GroupBy,
+    as_of: Option<String>,
+    ignore_effective_dates: Option<bool>,
+) -> Result<GroupedGraphData, AppError> {
+    let _timer = CommandTimer::start(&state, "get_grouped_graph");
+    let as_of = dates::resolve_as_of(as_of, ignore_effective_dates)?;
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    // Group node ID per service ID
+    let mut group_ids: HashMap<&str, String> = HashMap::new();
+    let mut groups: BTreeMap<String, GroupNode> = BTreeMap::new();
+    for service in &services {
+        let (id, name) = match group_by.group_of(service) {
+            Some(name) => (format!("group:{}", name), name),
+            None => (UNGROUPED_ID.to_string(), "Ungrouped".to_string()),
+        };
+        let group = groups.entry(id.clone()).or_insert_with(|| GroupNode {
+            id: id.clone(),
+            name,
+            status: service.status.clone(),
+            member_count: 0,
+            member_ids: Vec::new(),
+        });
+        if status_severity(&service.status) > status_severity(&group.status) {
+            group.status = service.status.clone();
+        }
+        group.member_count += 1;
+        group.member_ids.push(service.id.clone());
+        group_ids.insert(&service.id, id);
+    }
+
+    let mut edges: BTreeMap<(&str, &str), GroupRelationship> = BTreeMap::new();
+    let mut intra_group_relationship_count = 0;
+    for rel in relationships.iter() {
+        if as_of
+            .as_deref()
+            .is_some_and(|date| !rel.is_effective_on(date))
+        {
+            continue;
+        }
+        let (Some(source), Some(target)) = (
+            group_ids.get(rel.source.as_str()),
+            group_ids.get(rel.target.as_str()),
+        ) else {
+            continue;
+        };
+        if source == target {
+            intra_group_relationship_count += 1;
+            continue;
+        }
+
+        let edge = edges
+            .entry((source, target))
+            .or_insert_with(|| GroupRelationship {
+                id: format!("{}->{}", source, target),
+                source: source.clone(),
+                target: target.clone(),
+                count: 0,
+                relationship_types: Vec::new(),
+            });
+        edge.count += 1;
+        if !edge.relationship_types.contains(&rel.relationship_type) {
+            edge.relationship_types.push(rel.relationship_type.clone());
+        }
+    }
+
+    let mut groups: Vec<GroupNode> = groups.into_values().collect();
+    for group in &mut groups {
+        group.member_ids.sort();
+    }
+    groups.sort_by(|a, b| {
+        (a.id == UNGROUPED_ID)
+            .cmp(&(b.id == UNGROUPED_ID))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut relationships: Vec<GroupRelationship> = edges.into_values().collect();
+    for edge in &mut relationships {
+        edge.relationship_types
+            .sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    }
+
+    Ok(GroupedGraphData {
+        groups,
+        relationships,
+        intra_group_relationship_count,
+    })
+}
+
+/// An exported graph.
+///
+/// # Fields
+///
+/// * `content` - The rendered graph
+/// * `redacted_values` - How many values were masked or stripped (0 without `redact`)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphExport {
+    pub content: String,
+    pub redacted_values: usize,
+}
+
+/// Exports an environment's whole dependency graph.
+///
+/// Every service becomes a node and every relationship in effect on
+/// `as_of` (see `get_service_graph`) an edge, sorted by ID so exports of
+/// unchanged data are identical. With `redact`, the metadata keys and
+/// service fields of the redaction config in the settings are masked or
+/// stripped first (see `redaction`); DOT and Mermaid only draw IDs, names,
+/// types and statuses, so only JSON exports have anything to redact.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to export on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `format` - "json", "dot" (Graphviz) or "mermaid"
+/// * `output_path` - The file to write; replaced if it exists. If absent, nothing is written
+/// * `operation_id` - ID for the `operation:progress` events and `cancel_operation`
+/// * `aggregate_edges` - If true, DOT and Mermaid draw the relationships between two
+///   services as one edge labeled with their types and count (default: false)
+/// * `as_of` - Optional `YYYY-MM-DD` date relationships must be in effect on
+///   (default: today)
+/// * `ignore_effective_dates` - If true, export every relationship whatever its
+///   effective dates (default: false)
+/// * `redact` - If true, hide the values the redaction config lists (default: false)
+///
+/// # Returns
+///
+/// * `Ok(GraphExport)` - The rendered graph and how many values were redacted
+/// * `Err(AppError::ValidationError)` - If `as_of` isn't a `YYYY-MM-DD` date, or a
+///   redaction pattern is invalid
+/// * `Err(AppError::Io)` - If the data cannot be read or the file cannot be written
+/// * `Err(AppError::OperationCancelled)` - If cancelled before the file was written
+///
+/// # Side Effects
+///
+/// - Writes the graph to `output_path`, if given
+/// - Emits `operation:progress` events while rendering and writing the graph
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const { content } = await invoke('export_graph', { environment: 'prod', format: 'mermaid' });
+/// await invoke('export_graph', {
+///     environment: 'prod',
+///     format: 'dot',
+///     outputPath: '/tmp/prod.dot'
+/// });
+///
+/// // For a vendor, without the sensitive metadata:
+/// const { redactedValues } = await invoke('export_graph', {
+///     environment: 'prod',
+///     format: 'json',
+///     outputPath: '/tmp/prod.json',
+///     redact: true
+/// });
+///
+/// // One arrow per pair of services:
+/// const dot = await invoke('export_graph', {
+///     environment: 'prod',
+///     format: 'dot',
+///     aggregateEdges: true
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_graph<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    format: GraphExportFormat,
+    output_path: Option<String>,
+    operation_id: Option<String>,
+    aggregate_edges: Option<bool>,
+    as_of: Option<String>,
+    ignore_effective_dates: Option<bool>,
+    redact: Option<bool>,
+) -> Result<GraphExport, AppError> {
+    let as_of = dates::resolve_as_of(as_of, ignore_effective_dates)?;
+    run_blocking(app.clone(), move |state| {
+        let _timer = CommandTimer::start(state, "export_graph");
+        let mut progress = Progress::start(&app, state, operation_id, "export_graph");
+        let render = || {
+            export_environment_graph(
+                state,
+                &environment,
+                format,
+                aggregate_edges.unwrap_or(false),
+                as_of.as_deref(),
+                redact.unwrap_or(false),
+            )
+        };
+        let result = write_graph(render, output_path, &mut progress);
+        progress.finish(result)
+    })
+    .await
+}
+
+/// Renders the graph with `render` and writes it, for `export_graph`.
+fn write_graph(
+    render: impl FnOnce() -> Result<GraphExport, AppError>,
+    output_path: Option<String>,
+    progress: &mut Progress,
+) -> Result<GraphExport, AppError> {
+    progress.phase("Rendering graph", 1);
+    let export = render()?;
+    progress.advance();
+
+    if let Some(output_path) = output_path {
+        progress.check_cancelled()?;
+        progress.phase("Writing file", 1);
+        fs::write(output_path, &export.content)?;
+        progress.advance();
+    }
+
+    Ok(export)
+}
+
+/// Exports an environment's graph for Grafana's node graph panel.
+///
+/// Writes the panel's `nodes` table (`id`, `title`, `subTitle`, `mainStat`,
+/// the `arc__healthy`, `arc__degraded` and `arc__unhealthy` status arcs and
+/// `icon`) and `edges` table (`id`, `source`, `target`, `mainStat`, `color`,
+/// `strokeDasharray`), either as `nodes.csv` and `edges.csv` or as one
+/// `nodegraph.json`. Icons, colors and dashes come from the display config.
+/// Only the relationships in effect on `as_of` become edges (see
+/// `get_service_graph`).
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to export on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `output_dir` - The directory to write to; created if needed, files in it are replaced
+/// * `format` - "csv" or "json" (default: "csv")
+/// * `as_of` - Optional `YYYY-MM-DD` date relationships must be in effect on
+///   (default: today)
+/// * `ignore_effective_dates` - If true, export every relationship whatever its
+///   effective dates (default: false)
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` - The paths of the written files
+/// * `Err(AppError::ValidationError)` - If `as_of` isn't a `YYYY-MM-DD` date
+/// * `Err(AppError::Io)` - If the data cannot be read or the files cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the files to `output_dir`
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const files = await invoke('export_grafana_nodegraph', {
+///     environment: 'prod',
+///     outputDir: '/srv/grafana/nodegraph'
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_grafana_nodegraph<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    output_dir: String,
+    format: Option<GrafanaExportFormat>,
+    as_of: Option<String>,
+    ignore_effective_dates: Option<bool>,
+) -> Result<Vec<String>, AppError> {
+    let as_of = dates::resolve_as_of(as_of, ignore_effective_dates)?;
+    run_blocking(app, move |state| {
+        write_grafana_nodegraph(state, &environment, &output_dir, format, as_of.as_deref())
+    })
+    .await
+}
+
+/// Writes the node graph files, for `export_grafana_nodegraph`.
+fn write_grafana_nodegraph(
+    state: &RwLock<AppState>,
+    environment: &str,
+    output_dir: &str,
+    format: Option<GrafanaExportFormat>,
+    as_of: Option<&str>,
+) -> Result<Vec<String>, AppError> {
+    let services = cache::shared_services(state, environment)?;
+    let relationships = effective_relationships(state, environment, as_of)?;
+    let display = read_state(state).settings.display.clone();
+    let graph = export::grafana_nodegraph(&services, &relationships, &display);
+
+    let output_dir = std::path::Path::new(output_dir);
+    fs::create_dir_all(output_dir)?;
+    let files = match format.unwrap_or_default() {
+        GrafanaExportFormat::Csv => vec![
+            ("nodes.csv", graph.nodes_csv()),
+            ("edges.csv", graph.edges_csv()),
+        ],
+        GrafanaExportFormat::Json => {
+            vec![("nodegraph.json", serde_json::to_string_pretty(&graph)?)]
+        }
+    };
+
+    let mut written = Vec::new();
+    for (name, content) in files {
+        let path = output_dir.join(name);
+        fs::write(&path, content)?;
+        written.push(path.to_string_lossy().into_owned());
+    }
+
+    Ok(written)
+}
+
+/// Which part of an environment's graph an export draws.
+///
+/// # Fields
+///
+/// * `center_service_id` - Service to draw the neighborhood of; the whole
+///   environment if `None`
+/// * `depth` - How far from the center to traverse (default: 1)
+/// * `service_types` - Service types to draw; all of them if `None`
+/// * `relationship_types` - Relationship types to draw and follow; all of them if `None`
+/// * `exclude_statuses` / `exclude_tags` / `exclude_service_ids` - Services to leave
+///   out, as for `get_service_graph`
+/// * `as_of` / `ignore_effective_dates` - Which relationships are in effect, as for
+///   `get_service_graph`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GraphSelection {
+    pub center_service_id: Option<String>,
+    pub depth: Option<u32>,
+    pub service_types: Option<Vec<ServiceType>>,
+    pub relationship_types: Option<Vec<RelationshipType>>,
+    pub exclude_statuses: Vec<ServiceStatus>,
+    pub exclude_tags: Vec<String>,
+    pub exclude_service_ids: Vec<String>,
+    pub as_of: Option<String>,
+    pub ignore_effective_dates: bool,
+}
+
+/// What `export_graph_d2` draws.
+///
+/// # Fields
+///
+/// * `selection` - The part of the graph to draw (see `GraphSelection`)
+/// * `group_by_team` - Whether to draw the services of each team in a container
+/// * `output_path` - The file to write; replaced if it exists. If absent, nothing is
+///   written
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct D2ExportOptions {
+    #[serde(flatten)]
+    pub selection: GraphSelection,
+    pub group_by_team: bool,
+    pub output_path: Option<String>,
+}
+
+/// Exports an environment's graph, or the neighborhood of a service, as a
+/// D2 diagram.
+///
+/// Databases are drawn as cylinders and queues as queues, each edge is
+/// labeled with its relationship type, and the colors and line styles of
+/// the display config become D2 classes (see `export::render_d2`). Services
+/// left out by the options are not drawn and not traversed through, and
+/// neither are the relationships touching them; endpoints of relationships
+/// that don't exist are drawn labeled by their ID.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to export on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `options` - What to draw and where to write it (default: the whole environment,
+///   not grouped, not written)
+///
+/// # Returns
+///
+/// * `Ok(String)` - The D2 source
+/// * `Err(AppError::ValidationError)` - If `asOf` isn't a `YYYY-MM-DD` date
+/// * `Err(AppError::ServiceNotFound)` - If the center service doesn't exist
+/// * `Err(AppError::Io)` - If the data cannot be read or the file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the diagram to `outputPath`, if given
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: the checkout flow, two hops out, by team
+/// const d2 = await invoke('export_graph_d2', {
+///     environment: 'prod',
+///     options: {
+///         centerServiceId: 'checkout',
+///         depth: 2,
+///         groupByTeam: true,
+///         excludeTags: ['deprecated']
+///     }
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_graph_d2<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    options: Option<D2ExportOptions>,
+) -> Result<String, AppError> {
+    let options = options.unwrap_or_default();
+    let selection = &options.selection;
+    let as_of = dates::resolve_as_of(
+        selection.as_of.clone(),
+        Some(selection.ignore_effective_dates),
+    )?;
+    run_blocking(app, move |state| {
+        let _timer = CommandTimer::start(state, "export_graph_d2");
+        let (services, relationships) =
+            select_graph(state, &environment, &options.selection, as_of)?;
+        let display = read_state(state).settings.display.clone();
+        let d2 = export::render_d2(&services, &relationships, options.group_by_team, &display);
+        if let Some(output_path) = &options.output_path {
+            fs::write(output_path, &d2)?;
+        }
+        Ok(d2)
+    })
+    .await
+}
+
+/// Returns the services and relationships a selection draws, for the D2
+/// and draw.io exports.
+///
+/// Services the selection leaves out are not traversed through, and the
+/// relationships touching them are left out too.
+///
+/// # Arguments
+///
+/// * `state` - The application state
+/// * `environment` - The name of the environment
+/// * `selection` - What to draw
+/// * `as_of` - The date relationships must be in effect on, resolved from the
+///   selection (see `dates::resolve_as_of`)
+///
+/// # Returns
+///
+/// * `Ok((services, relationships))` - What to draw
+/// * `Err(AppError::ServiceNotFound)` - If the center service doesn't exist
+/// * `Err(AppError::Io)` - If the data cannot be read
+pub fn select_graph(
+    state: &RwLock<AppState>,
+    environment: &str,
+    selection: &GraphSelection,
+    as_of: Option<String>,
+) -> Result<(Vec<Service>, Vec<Relationship>), AppError> {
+    let services = cache::shared_services(state, environment)?;
+    let mut exclude_service_ids = selection.exclude_service_ids.clone();
+    if let Some(service_types) = &selection.service_types {
+        exclude_service_ids.extend(
+            services
+                .iter()
+                .filter(|s| !service_types.contains(&s.service_type))
+                .map(|s| s.id.clone()),
+        );
+    }
+    let query = GraphQuery {
+        depth: selection.depth.unwrap_or(1),
+        relationship_types: selection.relationship_types.clone(),
+        direction: TraversalDirection::Both,
+        include_effective_status: false,
+        include_criticality: false,
+        include_groups: false,
+        include_annotations: false,
+        max_nodes: None,
+        max_edges: None,
+        exclude_statuses: selection.exclude_statuses.clone(),
+        exclude_tags: selection.exclude_tags.clone(),
+        exclude_service_ids,
+        aggregate_edges: false,
+        as_of,
+    };
+
+    if let Some(center) = &selection.center_service_id {
+        let graph = build_service_graph(state, environment, std::slice::from_ref(center), &query)?;
+        let mut drawn = graph.connected_services;
+        if !graph.center_excluded {
+            drawn.push(graph.center_service);
+        }
+        return Ok((drawn, graph.relationships));
+    }
+
+    let (drawn, excluded): (Vec<&Arc<Service>>, Vec<&Arc<Service>>) =
+        services.iter().partition(|s| !query.excludes(s));
+    let excluded: HashSet<&str> = excluded.iter().map(|s| s.id.as_str()).collect();
+    let relationships = effective_relationships(state, environment, query.as_of.as_deref())?
+        .into_iter()
+        .filter(|r| {
+            query
+                .relationship_types
+                .as_ref()
+                .is_none_or(|types| types.contains(&r.relationship_type))
+        })
+        .filter(|r| !excluded.contains(r.source.as_str()) && !excluded.contains(r.target.as_str()))
+        .collect();
+
+    Ok((
+        drawn.into_iter().map(|s| (**s).clone()).collect(),
+        relationships,
+    ))
+}
+
+/// Renders an environment's graph, for `export_graph` and the command-line `export`.
+///
+/// Colors come from the display config in the settings; the command line
+/// has no settings, so it exports with the built-in palette. Only the
+/// relationships in effect on `as_of` are exported, all of them if `None`.
+/// With `redact`, JSON exports are rendered from redacted data.
+pub fn export_environment_graph(
+    state: &RwLock<AppState>,
+    environment: &str,
+    format: GraphExportFormat,
+    aggregate_edges: bool,
+    as_of: Option<&str>,
+    redact: bool,
+) -> Result<GraphExport, AppError> {
+    let services = cache::shared_services(state, environment)?;
+    let relationships = effective_relationships(state, environment, as_of)?;
+    let (display, redaction_config) = {
+        let state = read_state(state);
+        (
+            state.settings.display.clone(),
+            state.settings.redaction.clone(),
+        )
+    };
+
+    // DOT and Mermaid draw nothing the redaction config can list
+    if redact && format == GraphExportFormat::Json {
+        let redacted = redaction::redact(&services, &relationships, &redaction_config)?;
+        let content = export::render_graph(
+            environment,
+            &redacted.services,
+            &redacted.relationships,
+            format,
+            aggregate_edges,
+            &display,
+        )?;
+        return Ok(GraphExport {
+            content,
+            redacted_values: redacted.redacted_values,
+        });
+    }
+
+    let content = export::render_graph(
+        environment,
+        &services,
+        &relationships,
+        format,
+        aggregate_edges,
+        &display,
+    )?;
+    Ok(GraphExport {
+        content,
+        redacted_values: 0,
+    })
+}
+
+/// Returns the cached relationships of an environment in effect on `as_of`,
+/// all of them if `None`.
+fn effective_relationships(
+    state: &RwLock<AppState>,
+    environment: &str,
+    as_of: Option<&str>,
+) -> Result<Vec<Relationship>, AppError> {
+    let relationships = cache::shared_relationships(state, environment)?;
+    Ok(relationships
+        .iter()
+        .filter(|r| as_of.is_none_or(|date| r.is_effective_on(date)))
+        .cloned()
+        .collect())
+}
+
+/// Exports the deployment manifest of a service: what must be healthy first.
+///
+/// The manifest lists the service's prerequisites, the services it reaches
+/// by following depends_on relationships (and, with `include_data_flow`,
+/// reads_from and writes_to), each after its own prerequisites, so a deploy
+/// pipeline can check or deploy them in that order. For each prerequisite it
+/// gives the ID, name, type, tier and whether it is external, and it lists
+/// the service's own relationships with their metadata (ports, pool sizes
+/// and the like). Only the relationships in effect today are followed.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to export on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `service_id` - The service to deploy
+/// * `output_path` - Optional file to write the manifest to
+/// * `format` - "yaml" or "json" (default: "yaml")
+/// * `include_data_flow` - If true, also follow reads_from and writes_to
+///   relationships (default: false)
+///
+/// # Returns
+///
+/// * `Ok(String)` - The manifest
+/// * `Err(AppError::ServiceNotFound)` - If the service doesn't exist
+/// * `Err(AppError::ValidationError)` - If prerequisites depend on each other in a
+///   cycle (the message names it), or a relationship points at a missing service
+/// * `Err(AppError::Io)` - If the data cannot be read or the file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the manifest to `output_path`, if given
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const manifest = await invoke('export_deployment_manifest', {
+///     environment: 'prod',
+///     serviceId: 'billing-api',
+///     outputPath: '/srv/deploy/billing-api.yaml'
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_deployment_manifest<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    service_id: String,
+    output_path: Option<String>,
+    format: Option<ManifestFormat>,
+    include_data_flow: Option<bool>,
+) -> Result<String, AppError> {
+    run_blocking(app, move |state| {
+        let content = deployment_manifest(
+            state,
+            &environment,
+            &service_id,
+            format.unwrap_or_default(),
+            include_data_flow.unwrap_or(false),
+        )?;
+        if let Some(output_path) = output_path {
+            fs::write(output_path, &content)?;
+        }
+        Ok(content)
+    })
+    .await
+}
+
+/// Exports the deployment manifest of every service of an environment.
+///
+/// Writes one `<service-id>.yaml` (or `.json`) per service to `output_dir`,
+/// each as `export_deployment_manifest` would export it. Every manifest is
+/// built before any is written, so a cycle fails the export without
+/// leaving a partial set behind.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to export on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `output_dir` - The directory to write to; created if needed, files in it are replaced
+/// * `format` - "yaml" or "json" (default: "yaml")
+/// * `include_data_flow` - If true, also follow reads_from and writes_to
+///   relationships (default: false)
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` - The paths of the written files, ordered by service ID
+/// * `Err(AppError::ValidationError)` - If the prerequisites of a service depend on
+///   each other in a cycle, or a relationship points at a missing service
+/// * `Err(AppError::Io)` - If the data cannot be read or the files cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the files to `output_dir`
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const files = await invoke('export_deployment_manifests', {
+///     environment: 'prod',
+///     outputDir: '/srv/deploy/manifests',
+///     format: 'json'
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_deployment_manifests<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    output_dir: String,
+    format: Option<ManifestFormat>,
+    include_data_flow: Option<bool>,
+) -> Result<Vec<String>, AppError> {
+    run_blocking(app, move |state| {
+        write_deployment_manifests(
+            state,
+            &environment,
+            &output_dir,
+            format.unwrap_or_default(),
+            include_data_flow.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+/// Renders the deployment manifest of a service, for
+/// `export_deployment_manifest` and the command-line `manifest`.
+pub fn deployment_manifest(
+    state: &RwLock<AppState>,
+    environment: &str,
+    service_id: &str,
+    format: ManifestFormat,
+    include_data_flow: bool,
+) -> Result<String, AppError> {
+    let services = cache::shared_services(state, environment)?;
+    let relationships = effective_relationships(state, environment, Some(&dates::today()))?;
+    manifest::build(
+        environment,
+        &services,
+        &relationships,
+        service_id,
+        include_data_flow,
+    )?
+    .render(format)
+}
+
+/// Writes the deployment manifest of every service, for
+/// `export_deployment_manifests` and the command-line `manifest --all`.
+pub fn write_deployment_manifests(
+    state: &RwLock<AppState>,
+    environment: &str,
+    output_dir: &str,
+    format: ManifestFormat,
+    include_data_flow: bool,
+) -> Result<Vec<String>, AppError> {
+    let mut services = cache::shared_services(state, environment)?;
+    services.sort_by(|a, b| a.id.cmp(&b.id));
+    let relationships = effective_relationships(state, environment, Some(&dates::today()))?;
+
+    let manifests = services
+        .iter()
+        .map(|service| {
+            let manifest = manifest::build(
+                environment,
+                &services,
+                &relationships,
+                &service.id,
+                include_data_flow,
+            )?;
+            Ok((&service.id, manifest.render(format)?))
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    let output_dir = std::path::Path::new(output_dir);
+    fs::create_dir_all(output_dir)?;
+    let mut written = Vec::new();
+    for (service_id, content) in manifests {
+        let path = output_dir.join(format!("{}.{}", service_id, format.extension()));
+        fs::write(&path, content)?;
+        written.push(path.to_string_lossy().into_owned());
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::settings::{get_graph_preferences, save_graph_preferences};
+    use crate::models::GraphPreferences;
+    use crate::state::write_state;
+    use crate::test_support::{relationship, service, TestApp};
+    use std::time::{Duration, Instant};
+
+    /// The optional parameters of `get_service_graph` a test sets.
+    #[derive(Default)]
+    struct Params {
+        depth: Option<u32>,
+        exclude_statuses: Option<Vec<ServiceStatus>>,
+        exclude_tags: Option<Vec<String>>,
+        exclude_service_ids: Option<Vec<String>>,
+        aggregate_edges: Option<bool>,
+        as_of: Option<&'static str>,
+    }
+
+    fn graph(app: &TestApp, center: &str, params: Params) -> GraphData {
+        get_service_graph(
+            app.state(),
+            "dev".to_string(),
+            center.to_string(),
+            params.depth,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            params.exclude_statuses,
+            params.exclude_tags,
+            params.exclude_service_ids,
+            None,
+            params.aggregate_edges,
+            params.as_of.map(str::to_string),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn service_ids(graph: &GraphData) -> Vec<&str> {
+        let mut ids: Vec<&str> = graph
+            .connected_services
+            .iter()
+            .map(|s| s.id.as_str())
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn relationship_ids(graph: &GraphData) -> Vec<&str> {
+        let mut ids: Vec<&str> = graph.relationships.iter().map(|r| r.id.as_str()).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn as_of_selects_the_relationships_in_effect() {
+        let mut old_db = relationship("old-db", "api", "db-v1");
+        old_db.effective_until = Some("2025-06-30".to_string());
+        let mut new_db = relationship("new-db", "api", "db-v2");
+        new_db.effective_from = Some("2025-04-01".to_string());
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("db-v1"), service("db-v2")],
+            &[old_db, new_db],
+        );
+        let on = |date| {
+            let params = Params {
+                as_of: Some(date),
+                ..Params::default()
+            };
+            graph(&app, "api", params)
+        };
+
+        assert_eq!(relationship_ids(&on("2025-03-01")), ["old-db"]);
+        assert_eq!(relationship_ids(&on("2025-05-01")), ["new-db", "old-db"]);
+        assert_eq!(relationship_ids(&on("2025-07-01")), ["new-db"]);
+    }
+
+    /// api → proxy → db, plus api → cache → search, with proxy matching
+    /// every kind of exclusion.
+    fn proxied_app() -> TestApp {
+        let mut proxy = service("proxy");
+        proxy.status = ServiceStatus::Deprecated;
+        proxy.tags = vec!["test-only".to_string()];
+        TestApp::with_environment(
+            "dev",
+            &[
+                service("api"),
+                proxy,
+                service("db"),
+                service("cache"),
+                service("search"),
+            ],
+            &[
+                relationship("api-proxy", "api", "proxy"),
+                relationship("proxy-db", "proxy", "db"),
+                relationship("api-cache", "api", "cache"),
+                relationship("cache-search", "cache", "search"),
+            ],
+        )
+    }
+
+    #[test]
+    fn excluded_services_are_not_traversed_through() {
+        let app = proxied_app();
+        let exclusions = [
+            Params {
+                exclude_service_ids: Some(vec!["proxy".to_string()]),
+                ..Params::default()
+            },
+            Params {
+                exclude_statuses: Some(vec![ServiceStatus::Deprecated]),
+                ..Params::default()
+            },
+            Params {
+                exclude_tags: Some(vec!["test-only".to_string()]),
+                ..Params::default()
+            },
+        ];
+
+        for params in exclusions {
+            let graph = graph(
+                &app,
+                "api",
+                Params {
+                    depth: Some(3),
+                    ..params
+                },
+            );
+
+            // db is only reachable through proxy, so it must not leak in
+            assert_eq!(service_ids(&graph), ["cache", "search"]);
+            assert_eq!(relationship_ids(&graph), ["api-cache", "cache-search"]);
+            assert!(!graph.center_excluded);
+        }
+    }
+
+    #[test]
+    fn a_service_reachable_around_an_excluded_one_stays() {
+        let app = proxied_app();
+        crate::commands::relationships::save_relationship(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            relationship("search-db", "search", "db"),
+            None,
+        )
+        .unwrap();
+        let params = Params {
+            depth: Some(3),
+            exclude_service_ids: Some(vec!["proxy".to_string()]),
+            ..Params::default()
+        };
+
+        let graph = graph(&app, "api", params);
+
+        assert_eq!(service_ids(&graph), ["cache", "db", "search"]);
+        assert!(!relationship_ids(&graph).contains(&"proxy-db"));
+    }
+
+    #[test]
+    fn an_excluded_center_is_kept_and_flagged() {
+        let app = proxied_app();
+        let params = Params {
+            depth: Some(3),
+            exclude_statuses: Some(vec![ServiceStatus::Deprecated]),
+            ..Params::default()
+        };
+
+        let graph = graph(&app, "proxy", params);
+
+        assert_eq!(graph.center_service.id, "proxy");
+        assert!(graph.center_excluded);
+        assert_eq!(service_ids(&graph), ["api", "cache", "db", "search"]);
+    }
+
+    #[test]
+    fn aggregated_edges_cover_the_returned_relationships() {
+        let mut calls = relationship("api-db-calls", "api", "db");
+        calls.relationship_type = RelationshipType::CommunicatesWith;
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[
+                relationship("api-db", "api", "db"),
+                calls,
+                relationship("db-api", "db", "api"),
+            ],
+        );
+
+        let plain = graph(&app, "api", Params::default());
+        assert!(plain.aggregated_edges.is_none());
+
+        let params = Params {
+            aggregate_edges: Some(true),
+            ..Params::default()
+        };
+        let graph = graph(&app, "api", params);
+        let mut edges = graph.aggregated_edges.unwrap();
+        edges.sort_by(|a, b| a.id.cmp(&b.id));
+
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0].id, "api->db");
+        assert_eq!(edges[0].count, 2);
+        assert_eq!(
+            edges[0].relationship_types,
+            [
+                RelationshipType::CommunicatesWith,
+                RelationshipType::DependsOn
+            ]
+        );
+        assert_eq!(edges[1].id, "db->api");
+        assert_eq!(edges[1].relationship_ids, ["db-api"]);
+        // The relationships themselves are still returned
+        assert_eq!(graph.relationships.len(), 3);
+    }
+
+    #[test]
+    fn depth_three_over_twenty_thousand_edges_is_fast() {
+        const SERVICES: usize = 2_000;
+        let services: Vec<Service> = (0..SERVICES)
+            .map(|n| service(&format!("svc-{n}")))
+            .collect();
+        // Ten outgoing edges per service, spread so depth 3 reaches most of the graph
+        let relationships: Vec<Relationship> = (0..SERVICES * 10)
+            .map(|n| {
+                let source = n / 10;
+                let target = (source * 37 + (n % 10) * 101 + 1) % SERVICES;
+                relationship(
+                    &format!("rel-{n}"),
+                    &format!("svc-{source}"),
+                    &format!("svc-{target}"),
+                )
+            })
+            .collect();
+        let app = TestApp::with_environment("dev", &services, &relationships);
+        let params = || Params {
+            depth: Some(3),
+            ..Params::default()
+        };
+        // The first call loads and indexes the environment
+        graph(&app, "svc-0", params());
+
+        let started = Instant::now();
+        let result = graph(&app, "svc-0", params());
+        let elapsed = started.elapsed();
+
+        assert!(result.connected_services.len() > 1_000);
+        assert!(result.relationships.len() > 4_000);
+        // Tens of milliseconds even unoptimized, with room for slow machines;
+        // scanning every edge per visited service at each level took seconds
+        assert!(elapsed < Duration::from_millis(250), "took {elapsed:?}");
+    }
+
+    #[test]
+    fn criticality_ranks_the_shared_dependency_first() {
+        let app = TestApp::with_environment(
+            "dev",
+            &[
+                service("web"),
+                service("mobile"),
+                service("api"),
+                service("db"),
+            ],
+            &[
+                relationship("web-api", "web", "api"),
+                relationship("mobile-api", "mobile", "api"),
+                relationship("api-db", "api", "db"),
+            ],
+        );
+
+        let ranking = get_criticality_ranking(app.state(), "dev".to_string(), None, None).unwrap();
+
+        let ids: Vec<&str> = ranking.iter().map(|s| s.service_id.as_str()).collect();
+        // Ties are broken by ID
+        assert_eq!(ids, ["db", "api", "mobile", "web"]);
+        assert_eq!(ranking[0].score, 1.0);
+    }
+
+    fn typed(id: &str, source: &str, target: &str, rel_type: RelationshipType) -> Relationship {
+        let mut rel = relationship(id, source, target);
+        rel.relationship_type = rel_type;
+        rel
+    }
+
+    fn compare(app: &TestApp, a: &str, b: &str) -> Result<DependencyComparison, AppError> {
+        compare_service_dependencies(
+            app.state(),
+            "dev".to_string(),
+            a.to_string(),
+            b.to_string(),
+            Some(true),
+        )
+    }
+
+    fn ids(related: &[RelatedService]) -> Vec<&str> {
+        related.iter().map(|r| r.service_id.as_str()).collect()
+    }
+
+    #[test]
+    fn overlapping_services_share_dependencies_with_their_types() {
+        let app = TestApp::with_environment(
+            "dev",
+            &[
+                "user-api",
+                "user-service",
+                "redis",
+                "db",
+                "auth",
+                "disk",
+                "web",
+            ]
+            .map(service),
+            &[
+                typed("a-redis", "user-api", "redis", RelationshipType::ReadsFrom),
+                typed(
+                    "b-redis",
+                    "user-service",
+                    "redis",
+                    RelationshipType::WritesTo,
+                ),
+                relationship("a-db", "user-api", "db"),
+                relationship("b-db", "user-service", "db"),
+                relationship("a-auth", "user-api", "auth"),
+                relationship("db-disk", "db", "disk"),
+                relationship("web-a", "web", "user-api"),
+                relationship("a-missing", "user-api", "missing"),
+            ],
+        );
+
+        let comparison = compare(&app, "user-api", "user-service").unwrap();
+
+        let dependencies = &comparison.dependencies;
+        assert_eq!(ids(&dependencies.only_a), ["auth"]);
+        assert!(dependencies.only_b.is_empty());
+        let shared: Vec<_> = dependencies
+            .shared
+            .iter()
+            .map(|s| {
+                (
+                    s.service_id.as_str(),
+                    s.relationship_types_a.clone(),
+                    s.relationship_types_b.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            shared,
+            [
+                (
+                    "db",
+                    vec![RelationshipType::DependsOn],
+                    vec![RelationshipType::DependsOn]
+                ),
+                (
+                    "redis",
+                    vec![RelationshipType::ReadsFrom],
+                    vec![RelationshipType::WritesTo]
+                ),
+            ]
+        );
+        assert_eq!(ids(&comparison.dependents.only_a), ["web"]);
+        assert!(comparison.dependents.shared.is_empty());
+
+        // disk is only reached through the shared db
+        let transitive = comparison.transitive_dependencies.unwrap();
+        assert_eq!(ids(&transitive.only_a), ["auth"]);
+        let shared: Vec<&str> = transitive
+            .shared
+            .iter()
+            .map(|s| s.service_id.as_str())
+            .collect();
+        assert_eq!(shared, ["db", "disk", "redis"]);
+    }
+
+    #[test]
+    fn disjoint_services_share_nothing() {
+        let app = TestApp::with_environment(
+            "dev",
+            &["a", "b", "x", "y", "z"].map(service),
+            &[
+                relationship("a-x", "a", "x"),
+                relationship("x-y", "x", "y"),
+                relationship("b-z", "b", "z"),
+                relationship("z-a", "z", "a"),
+            ],
+        );
+
+        let comparison = compare(&app, "a", "b").unwrap();
+
+        assert_eq!(ids(&comparison.dependencies.only_a), ["x"]);
+        assert_eq!(ids(&comparison.dependencies.only_b), ["z"]);
+        assert!(comparison.dependencies.shared.is_empty());
+        assert_eq!(ids(&comparison.dependents.only_a), ["z"]);
+        assert!(comparison.dependents.only_b.is_empty());
+        // b reaches a's dependencies through z, but never itself
+        let transitive = comparison.transitive_dependencies.unwrap();
+        assert_eq!(ids(&transitive.only_b), ["a", "z"]);
+        let shared: Vec<&str> = transitive
+            .shared
+            .iter()
+            .map(|s| s.service_id.as_str())
+            .collect();
+        assert_eq!(shared, ["x", "y"]);
+    }
+
+    #[test]
+    fn comparing_a_missing_service_names_it() {
+        let app = TestApp::with_environment("dev", &[service("a")], &[]);
+
+        for (a, b, missing) in [("a", "nope", "nope"), ("gone", "a", "gone")] {
+            match compare(&app, a, b) {
+                Err(AppError::ServiceNotFound(id)) => assert_eq!(id, missing),
+                other => panic!("expected ServiceNotFound, got {other:?}"),
+            }
+        }
+    }
+
+    /// a → b → c → d, with c deprecated.
+    fn chain_app() -> TestApp {
+        let mut c = service("c");
+        c.status = ServiceStatus::Deprecated;
+        TestApp::with_environment(
+            "dev",
+            &[service("a"), service("b"), c, service("d")],
+            &[
+                relationship("a-b", "a", "b"),
+                relationship("b-c", "b", "c"),
+                relationship("c-d", "c", "d"),
+            ],
+        )
+    }
+
+    fn save_preferences(app: &TestApp, preferences: serde_json::Value) -> GraphPreferences {
+        let preferences = serde_json::from_value(preferences).unwrap();
+        save_graph_preferences(app.state(), preferences).unwrap()
+    }
+
+    #[test]
+    fn omitted_parameters_use_the_graph_preferences() {
+        let app = chain_app();
+        assert_eq!(service_ids(&graph(&app, "a", Params::default())), ["b"]);
+
+        save_preferences(
+            &app,
+            serde_json::json!({ "depth": 3, "direction": "both", "aggregateEdges": true }),
+        );
+        let preferred = graph(&app, "a", Params::default());
+        assert_eq!(service_ids(&preferred), ["b", "c", "d"]);
+        assert!(preferred.aggregated_edges.is_some());
+
+        save_preferences(
+            &app,
+            serde_json::json!({ "depth": 3, "excludeStatuses": ["deprecated"] }),
+        );
+        assert_eq!(service_ids(&graph(&app, "a", Params::default())), ["b"]);
+    }
+
+    #[test]
+    fn explicit_parameters_override_the_graph_preferences() {
+        let app = chain_app();
+        save_preferences(
+            &app,
+            serde_json::json!({
+                "depth": 3,
+                "excludeStatuses": ["deprecated"],
+                "aggregateEdges": true
+            }),
+        );
+
+        let shallow = graph(
+            &app,
+            "a",
+            Params {
+                depth: Some(1),
+                aggregate_edges: Some(false),
+                ..Params::default()
+            },
+        );
+        assert_eq!(service_ids(&shallow), ["b"]);
+        assert!(shallow.aggregated_edges.is_none());
+
+        // An explicitly empty list also wins over the preferred one
+        let everything = graph(
+            &app,
+            "a",
+            Params {
+                exclude_statuses: Some(Vec::new()),
+                ..Params::default()
+            },
+        );
+        assert_eq!(service_ids(&everything), ["b", "c", "d"]);
+
+        let deeper = graph(
+            &app,
+            "b",
+            Params {
+                depth: Some(2),
+                exclude_statuses: Some(Vec::new()),
+                ..Params::default()
+            },
+        );
+        assert_eq!(service_ids(&deeper), ["a", "c", "d"]);
+    }
+
+    #[test]
+    fn invalid_stored_preferences_fall_back_to_the_defaults() {
+        let app = chain_app();
+        let saved = save_preferences(
+            &app,
+            serde_json::json!({
+                "depth": 0,
+                "direction": "sideways",
+                "excludeStatuses": ["retired", "deprecated"],
+                "aggregateEdges": "yes"
+            }),
+        );
+        assert_eq!(
+            saved,
+            GraphPreferences {
+                exclude_statuses: vec![ServiceStatus::Deprecated],
+                ..GraphPreferences::default()
+            }
+        );
+
+        // The same goes for a settings file edited by hand
+        let settings_path = app.data_path().join("settings.json");
+        std::fs::write(
+            &settings_path,
+            r#"{ "graphPreferences": { "depth": -2, "excludeStatuses": "all" } }"#,
+        )
+        .unwrap();
+        write_state(&app.state()).load_settings(settings_path);
+        let loaded = get_graph_preferences(app.state());
+        assert_eq!(loaded, GraphPreferences::default());
+        assert_eq!(service_ids(&graph(&app, "a", Params::default())), ["b"]);
+    }
+}
@@ -0,0 +1,500 @@
+//! Service group management commands for the Tauri application.
+//!
+//! Groups (see `models::Group`) are explicit domains services belong to,
+//! stored per environment in `groups.json`. A service can be in any number
+//! of groups. Deleting a service doesn't remove it from its groups, just as
+//! it doesn't delete its relationships; validation reports the leftover
+//! memberships as `orphaned_group_member` issues instead.
+
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::error::AppError;
+use crate::git;
+use crate::models::Group;
+use crate::state::{read_state, write_state, AppState};
+use crate::storage;
+
+/// Lists the groups of an environment.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(Vec<Group>)` - The groups, sorted by name
+/// * `Err(AppError::FileLoad)` - If the groups file cannot be parsed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const groups = await invoke('list_groups', { environment: 'prod' });
+/// ```
+#[tauri::command]
+pub fn list_groups(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<Vec<Group>, AppError> {
+    let state = read_state(&state);
+    let mut groups = storage::load_groups(&state.data_path, &environment)?;
+    groups.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+
+    Ok(groups)
+}
+
+/// Creates or replaces a group.
+///
+/// Duplicate member IDs are dropped. Every member must be an existing
+/// service, so groups can't be created with orphaned memberships.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+/// * `group` - The group to save; replaces the group with the same ID
+///
+/// # Returns
+///
+/// * `Ok(Group)` - The saved group
+/// * `Err(AppError::ValidationError)` - If the ID or name is empty, or a member
+///   doesn't exist
+/// * `Err(AppError::Io)` - If the groups file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `{environment}/groups.json` in the data directory
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('save_group', {
+///     environment: 'prod',
+///     group: { id: 'payments', name: 'Payments', memberIds: ['billing-api'] }
+/// });
+/// ```
+#[tauri::command]
+pub fn save_group<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    mut group: Group,
+) -> Result<Group, AppError> {
+    if group.id.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Group ID cannot be empty".to_string(),
+        ));
+    }
+    if group.name.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Group name cannot be empty".to_string(),
+        ));
+    }
+
+    let state = write_state(&state);
+    group.member_ids = existing_members(&state, &environment, group.member_ids, &[])?;
+
+    let mut groups = storage::load_groups(&state.data_path, &environment)?;
+    match groups.iter_mut().find(|g| g.id == group.id) {
+        Some(existing) => *existing = group.clone(),
+        None => groups.push(group.clone()),
+    }
+
+    write_groups(
+        &app,
+        &state,
+        &environment,
+        &groups,
+        "save_group",
+        format!("Update group {} in {}", group.id, environment),
+    )?;
+
+    Ok(group)
+}
+
+/// Deletes a group; its member services are not affected.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+/// * `group_id` - The ID of the group to delete
+///
+/// # Returns
+///
+/// * `Ok(())` - If the group was deleted
+/// * `Err(AppError::GroupNotFound)` - If no group has the ID
+/// * `Err(AppError::Io)` - If the groups file cannot be written
+///
+/// # Side Effects
+///
+/// - Rewrites `{environment}/groups.json` in the data directory
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('delete_group', { environment: 'prod', groupId: 'payments' });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn delete_group<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    group_id: String,
+) -> Result<(), AppError> {
+    let state = write_state(&state);
+    let mut groups = storage::load_groups(&state.data_path, &environment)?;
+
+    let original_len = groups.len();
+    groups.retain(|g| g.id != group_id);
+    if groups.len() == original_len {
+        return Err(AppError::GroupNotFound(group_id));
+    }
+
+    write_groups(
+        &app,
+        &state,
+        &environment,
+        &groups,
+        "delete_group",
+        format!("Delete group {} in {}", group_id, environment),
+    )
+}
+
+/// Adds services to a group.
+///
+/// Services already in the group are skipped.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+/// * `group_id` - The ID of the group
+/// * `service_ids` - The services to add
+///
+/// # Returns
+///
+/// * `Ok(Group)` - The updated group
+/// * `Err(AppError::GroupNotFound)` - If no group has the ID
+/// * `Err(AppError::ValidationError)` - If a service doesn't exist
+/// * `Err(AppError::Io)` - If the groups file cannot be written
+///
+/// # Side Effects
+///
+/// - Rewrites `{environment}/groups.json` in the data directory
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('add_group_members', {
+///     environment: 'prod',
+///     groupId: 'payments',
+///     serviceIds: ['payments-db']
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn add_group_members<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    group_id: String,
+    service_ids: Vec<String>,
+) -> Result<Group, AppError> {
+    let state = write_state(&state);
+    let mut groups = storage::load_groups(&state.data_path, &environment)?;
+    let group = groups
+        .iter_mut()
+        .find(|g| g.id == group_id)
+        .ok_or_else(|| AppError::GroupNotFound(group_id.clone()))?;
+
+    let added = existing_members(&state, &environment, service_ids, &group.member_ids)?;
+    group.member_ids.extend(added);
+    let group = group.clone();
+
+    write_groups(
+        &app,
+        &state,
+        &environment,
+        &groups,
+        "add_group_members",
+        format!("Add members to group {} in {}", group_id, environment),
+    )?;
+
+    Ok(group)
+}
+
+/// Removes services from a group.
+///
+/// Services that aren't in the group are ignored, so this also cleans up
+/// memberships of services that no longer exist.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+/// * `group_id` - The ID of the group
+/// * `service_ids` - The services to remove
+///
+/// # Returns
+///
+/// * `Ok(Group)` - The updated group
+/// * `Err(AppError::GroupNotFound)` - If no group has the ID
+/// * `Err(AppError::Io)` - If the groups file cannot be written
+///
+/// # Side Effects
+///
+/// - Rewrites `{environment}/groups.json` in the data directory
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('remove_group_members', {
+///     environment: 'prod',
+///     groupId: 'payments',
+///     serviceIds: ['legacy-billing']
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn remove_group_members<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    group_id: String,
+    service_ids: Vec<String>,
+) -> Result<Group, AppError> {
+    let state = write_state(&state);
+    let mut groups = storage::load_groups(&state.data_path, &environment)?;
+    let group = groups
+        .iter_mut()
+        .find(|g| g.id == group_id)
+        .ok_or_else(|| AppError::GroupNotFound(group_id.clone()))?;
+
+    group.member_ids.retain(|id| !service_ids.contains(id));
+    let group = group.clone();
+
+    write_groups(
+        &app,
+        &state,
+        &environment,
+        &groups,
+        "remove_group_members",
+        format!("Remove members from group {} in {}", group_id, environment),
+    )?;
+
+    Ok(group)
+}
+
+/// Deduplicates service IDs, drops those already present and checks the rest exist.
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` - The new member IDs, in their original order
+/// * `Err(AppError::ValidationError)` - Listing the services that don't exist
+fn existing_members(
+    state: &AppState,
+    environment: &str,
+    service_ids: Vec<String>,
+    present: &[String],
+) -> Result<Vec<String>, AppError> {
+    let mut members: Vec<String> = Vec::new();
+    let mut missing = Vec::new();
+    for id in service_ids {
+        if present.contains(&id) || members.contains(&id) {
+            continue;
+        }
+        if !state.storage.service_exists(environment, &id)? {
+            missing.push(id);
+            continue;
+        }
+        members.push(id);
+    }
+
+    if missing.is_empty() {
+        Ok(members)
+    } else {
+        Err(AppError::ValidationError(format!(
+            "Services do not exist in environment '{}': {}",
+            environment,
+            missing.join(", ")
+        )))
+    }
+}
+
+/// Writes the groups of an environment and queues the git auto-commit.
+fn write_groups<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    environment: &str,
+    groups: &[Group],
+    command: &'static str,
+    summary: String,
+) -> Result<(), AppError> {
+    storage::save_groups(&state.data_path, environment, groups)?;
+
+    git::auto_commit(
+        app,
+        state,
+        command,
+        summary,
+        vec![storage::groups::groups_path(&state.data_path, environment)?],
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::services::delete_service;
+    use crate::commands::validation::run_validation;
+    use crate::models::IssueType;
+    use crate::test_support::{service, TestApp};
+
+    fn group(id: &str, member_ids: &[&str]) -> Group {
+        Group {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: None,
+            owner: None,
+            member_ids: member_ids.iter().map(|id| id.to_string()).collect(),
+        }
+    }
+
+    fn save(app: &TestApp, group: Group) -> Result<Group, AppError> {
+        save_group(app.handle(), app.state(), "dev".to_string(), group)
+    }
+
+    fn members(app: &TestApp, group_id: &str) -> Vec<String> {
+        list_groups(app.state(), "dev".to_string())
+            .unwrap()
+            .into_iter()
+            .find(|g| g.id == group_id)
+            .map(|g| g.member_ids)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn groups_are_saved_edited_and_deleted() {
+        let app = TestApp::with_environment("dev", &["api", "db", "web"].map(service), &[]);
+
+        let saved = save(&app, group("payments", &["api", "db", "api"])).unwrap();
+        assert_eq!(saved.member_ids, ["api", "db"]);
+        // A service can be in several groups
+        save(&app, group("identity", &["api"])).unwrap();
+
+        add_group_members(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            "payments".to_string(),
+            vec!["web".to_string()],
+        )
+        .unwrap();
+        remove_group_members(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            "payments".to_string(),
+            vec!["api".to_string()],
+        )
+        .unwrap();
+        assert_eq!(members(&app, "payments"), ["db", "web"]);
+        assert_eq!(members(&app, "identity"), ["api"]);
+
+        delete_group(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            "identity".to_string(),
+        )
+        .unwrap();
+        let ids: Vec<String> = list_groups(app.state(), "dev".to_string())
+            .unwrap()
+            .into_iter()
+            .map(|g| g.id)
+            .collect();
+        assert_eq!(ids, ["payments"]);
+        assert!(matches!(
+            delete_group(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                "identity".to_string()
+            ),
+            Err(AppError::GroupNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn members_must_exist() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+
+        assert!(matches!(
+            save(&app, group("payments", &["api", "ghost"])),
+            Err(AppError::ValidationError(_))
+        ));
+        save(&app, group("payments", &["api"])).unwrap();
+        assert!(add_group_members(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            "payments".to_string(),
+            vec!["ghost".to_string()],
+        )
+        .is_err());
+        assert_eq!(members(&app, "payments"), ["api"]);
+    }
+
+    #[test]
+    fn deleting_a_member_service_is_flagged_by_validation() {
+        let app = TestApp::with_environment("dev", &[service("api"), service("db")], &[]);
+        save(&app, group("payments", &["api", "db"])).unwrap();
+
+        delete_service(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            "db".to_string(),
+        )
+        .unwrap();
+
+        // The membership is kept, and reported until it is removed
+        assert_eq!(members(&app, "payments"), ["api", "db"]);
+        let orphaned: Vec<_> = run_validation(&app.state(), "dev")
+            .unwrap()
+            .issues
+            .into_iter()
+            .filter(|issue| issue.issue_type == IssueType::OrphanedGroupMember)
+            .collect();
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!(orphaned[0].affected_ids, ["payments", "db"]);
+
+        remove_group_members(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            "payments".to_string(),
+            vec!["db".to_string()],
+        )
+        .unwrap();
+        assert!(!run_validation(&app.state(), "dev")
+            .unwrap()
+            .issues
+            .iter()
+            .any(|issue| issue.issue_type == IssueType::OrphanedGroupMember));
+    }
+}
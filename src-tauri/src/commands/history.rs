@@ -0,0 +1,216 @@
+//! Undo/redo commands for the Tauri application.
+//!
+//! Mutating commands record what they changed in a per-environment journal
+//! (see the `state::journal` module). These commands walk that journal back
+//! and forth and describe it for the UI. The journal lives in memory only.
+
+use serde::Serialize;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::error::AppError;
+use crate::git;
+use crate::models::WebhookEvent;
+use crate::state::journal::{self, Direction, JournalEntryInfo};
+use crate::state::{read_state, write_state, AppState};
+use crate::webhooks;
+
+/// The undo and redo stacks of an environment.
+///
+/// # Fields
+///
+/// * `undo` - Entries that can be undone, most recent first
+/// * `redo` - Entries that can be redone, next redo first
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoHistory {
+    pub undo: Vec<JournalEntryInfo>,
+    pub redo: Vec<JournalEntryInfo>,
+}
+
+/// Undoes the most recent change in an environment.
+///
+/// Writes back the state from before the change through the normal storage
+/// operations. Changes made by one command (including bulk operations) are
+/// undone together.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the journal and data path
+/// * `environment` - The name of the environment to undo in
+///
+/// # Returns
+///
+/// * `Ok(Some(JournalEntryInfo))` - The change that was undone
+/// * `Ok(None)` - If there is nothing to undo
+/// * `Err(AppError::Io)` - If there's an error writing to the filesystem; the
+///   entry stays on the undo stack
+///
+/// # Side Effects
+///
+/// - Rewrites the affected service files and `relationships.json`
+/// - Clears the environment's caches
+/// - Moves the entry to the redo stack
+/// - Appends the reverted changes to the environment's audit log
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const undone = await invoke('undo', { environment: 'dev' });
+/// if (undone) {
+///     showToast(`Undid: ${undone.description}`);
+/// }
+/// ```
+#[tauri::command]
+pub fn undo<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<Option<JournalEntryInfo>, AppError> {
+    apply_journal_entry(&app, &state, &environment, Direction::Undo)
+}
+
+/// Redoes the most recently undone change in an environment.
+///
+/// Redo is only possible until a new change is made; any mutation clears
+/// the redo stack.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the journal and data path
+/// * `environment` - The name of the environment to redo in
+///
+/// # Returns
+///
+/// * `Ok(Some(JournalEntryInfo))` - The change that was redone
+/// * `Ok(None)` - If there is nothing to redo
+/// * `Err(AppError::Io)` - If there's an error writing to the filesystem; the
+///   entry stays on the redo stack
+///
+/// # Side Effects
+///
+/// - Rewrites the affected service files and `relationships.json`
+/// - Clears the environment's caches
+/// - Moves the entry back to the undo stack
+/// - Appends the reapplied changes to the environment's audit log
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('redo', { environment: 'dev' });
+/// ```
+#[tauri::command]
+pub fn redo<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<Option<JournalEntryInfo>, AppError> {
+    apply_journal_entry(&app, &state, &environment, Direction::Redo)
+}
+
+/// Retrieves the undo and redo stacks of an environment.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the journal
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(UndoHistory)` - The stacks (both empty if nothing was changed this session)
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const history = await invoke('get_undo_history', { environment: 'dev' });
+/// undoButton.title = history.undo[0]?.description ?? 'Nothing to undo';
+/// ```
+#[tauri::command]
+pub fn get_undo_history(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<UndoHistory, AppError> {
+    let state = read_state(&state);
+
+    Ok(match state.journals.get(&environment) {
+        Some(journal) => UndoHistory {
+            undo: journal.undo_history(),
+            redo: journal.redo_history(),
+        },
+        None => UndoHistory {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        },
+    })
+}
+
+/// Applies the next journal entry in a direction and files it on the opposite stack.
+fn apply_journal_entry<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    direction: Direction,
+) -> Result<Option<JournalEntryInfo>, AppError> {
+    let mut state = write_state(state);
+
+    let entry = match state
+        .journals
+        .get_mut(environment)
+        .and_then(|journal| journal.take(direction))
+    {
+        Some(entry) => entry,
+        None => return Ok(None),
+    };
+
+    let result = journal::apply(
+        state.storage.as_ref(),
+        &state.data_path,
+        environment,
+        &entry,
+        direction,
+    );
+
+    // Even a partially applied entry may have changed files
+    state.record_write(environment);
+    state.clear_environment_cache(environment);
+
+    let paths = match result {
+        Ok(paths) => paths,
+        Err(e) => {
+            state
+                .journals
+                .entry(environment.to_string())
+                .or_default()
+                .restore(direction, entry);
+            return Err(e);
+        }
+    };
+
+    let (command, verb) = match direction {
+        Direction::Undo => ("undo", "Undo"),
+        Direction::Redo => ("redo", "Redo"),
+    };
+    state.record_audit(
+        environment,
+        &journal::audit_records(command, &entry.changes, direction),
+    );
+    let summary = format!("{}: {}", verb, entry.description);
+    git::auto_commit(app, &state, command, summary.clone(), paths);
+    webhooks::notify(app, &state, environment, WebhookEvent::DataChanged, summary);
+
+    let info = entry.info();
+    state
+        .journals
+        .entry(environment.to_string())
+        .or_default()
+        .applied(direction, entry);
+
+    Ok(Some(info))
+}
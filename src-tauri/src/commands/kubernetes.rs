@@ -0,0 +1,492 @@
+//! Kubernetes import commands for the Tauri application.
+//!
+//! Imports the live topology of a cluster into an environment (see
+//! `kubernetes` for how resources are mapped). Imports can be repeated:
+//! services are updated in place and services of the imported namespaces
+//! that are no longer in the cluster are tagged `missing-from-cluster`
+//! instead of being deleted, so nothing documented by hand is lost. Only
+//! edges imported from Kubernetes before (see `RelationshipOrigin`) are
+//! removed when the cluster no longer implies them.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::error::AppError;
+use crate::kubernetes::{self, MappedTopology, KUBERNETES_METADATA_KEY};
+use crate::merge::{MergeConflict, MergeStrategies, MergeStrategy, Merger};
+use crate::models::{
+    ImportCounts, Relationship, RelationshipOrigin, RelationshipType, ReviewStatus, Service,
+};
+use crate::state::progress::Progress;
+use crate::state::{read_state, write_state, AppState};
+use crate::storage;
+
+/// Tag of imported services that have disappeared from the cluster.
+pub const MISSING_FROM_CLUSTER_TAG: &str = "missing-from-cluster";
+
+/// Import source of the relationships the Kubernetes import creates.
+pub const IMPORT_SOURCE: &str = "kubernetes";
+
+/// How long listing the cluster's resources may take in total.
+const IMPORT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How an import merges a mapped service into the service imported before:
+/// the cluster decides the name and status, its version, team, tags and
+/// metadata are merged in, and the type and other fields edited in the app
+/// are kept.
+const MERGE_STRATEGIES: MergeStrategies = MergeStrategies {
+    name: MergeStrategy::Overwrite,
+    service_type: MergeStrategy::KeepExisting,
+    status: MergeStrategy::Overwrite,
+    details: MergeStrategy::Merge,
+    tags: MergeStrategy::Merge,
+    metadata: MergeStrategy::Merge,
+};
+
+/// The result of a Kubernetes import.
+///
+/// # Fields
+///
+/// * `services_found` - Number of services mapped from the cluster
+/// * `edges_found` - Number of communicates_with edges inferred from the cluster
+/// * `missing_from_cluster` - IDs of previously imported services that are gone,
+///   newly tagged or already tagged
+/// * `conflicts` - Fields where the cluster and a stored service disagreed, with the
+///   side that was saved (see `merge`)
+/// * `result` - What the import saved (or would save, for a dry run)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KubernetesImportReport {
+    pub services_found: usize,
+    pub edges_found: usize,
+    pub missing_from_cluster: Vec<String>,
+    pub conflicts: Vec<MergeConflict>,
+    pub result: BatchResult,
+}
+
+/// Imports Deployments, StatefulSets, Services and Ingresses from a cluster.
+///
+/// New services are created; services imported before are updated with
+/// the cluster's name and status, and the version, team, tags and metadata
+/// it has, while keeping any other fields edited in the app; the report
+/// lists the fields where the two disagreed. Inferred edges that don't exist yet are
+/// created as communicates_with relationships with the origin
+/// `{ imported: "kubernetes" }`; edges imported before from a service of the
+/// imported namespaces that the cluster no longer implies are deleted.
+/// Manual edges are never changed. All changes are written as one batch
+/// (see `execute_batch`), so a single undo reverts the import.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the caches and data path
+/// * `environment` - The environment to import into
+/// * `kubeconfig_path` - The kubeconfig to use; `null` uses `KUBECONFIG` or `~/.kube/config`
+/// * `namespaces` - The namespaces to import
+/// * `dry_run` - If true, only compute what the import would change (default: false)
+/// * `operation_id` - ID for the `operation:progress` events and `cancel_operation`
+///
+/// # Returns
+///
+/// * `Ok(KubernetesImportReport)` - What was found and changed
+/// * `Err(AppError::ValidationError)` - If no namespace was given
+/// * `Err(AppError::InvalidPath)` - If the kubeconfig cannot be read
+/// * `Err(AppError::ClusterAuthFailed)` - If the cluster rejected the credentials
+/// * `Err(AppError::ClusterRequestFailed)` - If the cluster couldn't be reached in time
+/// * `Err(AppError::BatchOperationFailed)` - If a change can't be made; nothing was written
+/// * `Err(AppError::OperationCancelled)` - If cancelled before writing; nothing was written
+///
+/// # Side Effects
+///
+/// - Emits `operation:progress` events while fetching, computing and writing
+///
+/// Unless `dry_run` is set:
+/// - Writes the changed service files and `relationships.json`
+/// - Records the time and counts of the import in the environment's `environment.json`
+/// - Records the import as a single entry in the undo journal
+/// - Appends one audit log record per changed service and relationship
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('import_from_kubernetes_cluster', {
+///     environment: 'prod',
+///     kubeconfigPath: '/home/me/.kube/prod.yaml',
+///     namespaces: ['payments', 'checkout'],
+///     dryRun: true,
+///     operationId: crypto.randomUUID()
+/// });
+/// console.log(`${report.missingFromCluster.length} services are gone from the cluster`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_from_kubernetes_cluster<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    kubeconfig_path: Option<String>,
+    namespaces: Vec<String>,
+    dry_run: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<KubernetesImportReport, AppError> {
+    storage::validate_environment_name(&environment)?;
+    if namespaces.is_empty() {
+        return Err(AppError::ValidationError(
+            "Choose at least one namespace to import".to_string(),
+        ));
+    }
+
+    let mut progress =
+        Progress::start(&app, &state, operation_id, "import_from_kubernetes_cluster");
+    let result = import_cluster(
+        &app,
+        &state,
+        &environment,
+        kubeconfig_path.as_deref(),
+        &namespaces,
+        dry_run.unwrap_or(false),
+        &mut progress,
+    )
+    .await;
+    progress.finish(result)
+}
+
+/// Fetches the cluster's resources and saves them, for `import_from_kubernetes_cluster`.
+async fn import_cluster<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    kubeconfig_path: Option<&str>,
+    namespaces: &[String],
+    dry_run: bool,
+    progress: &mut Progress,
+) -> Result<KubernetesImportReport, AppError> {
+    progress.phase("Fetching cluster resources", 1);
+    let fetch = kubernetes::fetch_resources(kubeconfig_path.map(Path::new), namespaces);
+    let resources = tokio::time::timeout(IMPORT_TIMEOUT, fetch)
+        .await
+        .map_err(|_| {
+            AppError::ClusterRequestFailed(format!(
+                "The cluster did not answer within {} seconds",
+                IMPORT_TIMEOUT.as_secs()
+            ))
+        })??;
+    progress.advance();
+    progress.check_cancelled()?;
+    let topology = kubernetes::map_resources(&resources);
+
+    if dry_run {
+        let state = read_state(state);
+        let (operations, missing, conflicts) =
+            import_operations(&state, environment, namespaces, &topology)?;
+        let mut plan = Plan::compute_with_progress(&state, environment, operations, progress)?;
+        plan.result.dry_run = true;
+        return Ok(report(&topology, missing, conflicts, plan.result));
+    }
+
+    let mut state = write_state(state);
+    let (operations, missing, conflicts) =
+        import_operations(&state, environment, namespaces, &topology)?;
+    let plan = Plan::compute_with_progress(&state, environment, operations, progress)?;
+    let description = format!(
+        "Imported {} services from Kubernetes namespaces {}",
+        topology.services.len(),
+        namespaces.join(", ")
+    );
+    let result = plan.apply_with_progress(
+        app,
+        &mut state,
+        environment,
+        "import_from_kubernetes_cluster",
+        description,
+        progress,
+    )?;
+    let counts = ImportCounts {
+        services: topology.services.len(),
+        relationships: topology.edges.len(),
+    };
+    state.record_import(environment, IMPORT_SOURCE, counts);
+
+    Ok(report(&topology, missing, conflicts, result))
+}
+
+/// Builds the report of an import.
+fn report(
+    topology: &MappedTopology,
+    missing_from_cluster: Vec<String>,
+    conflicts: Vec<MergeConflict>,
+    result: BatchResult,
+) -> KubernetesImportReport {
+    KubernetesImportReport {
+        services_found: topology.services.len(),
+        edges_found: topology.edges.len(),
+        missing_from_cluster,
+        conflicts,
+        result,
+    }
+}
+
+/// The saves of an import, the services gone from the cluster and the merge conflicts.
+type ImportOperations = (Vec<Operation>, Vec<String>, Vec<MergeConflict>);
+
+/// Builds the saves an import makes, and lists the services gone from the
+/// cluster and the merge conflicts.
+fn import_operations(
+    state: &AppState,
+    environment: &str,
+    namespaces: &[String],
+    topology: &MappedTopology,
+) -> Result<ImportOperations, AppError> {
+    let existing: HashMap<String, Service> = state
+        .storage
+        .load_services(environment)?
+        .into_iter()
+        .map(|s| (s.id.clone(), s))
+        .collect();
+    let mut merger = Merger::new(MERGE_STRATEGIES);
+    let mut operations = Vec::new();
+
+    for imported in &topology.services {
+        // A service back in the cluster loses `missing-from-cluster`
+        let mut stored = existing.get(&imported.id).cloned();
+        let mut unflagged = false;
+        if let Some(stored) = &mut stored {
+            let tag_count = stored.tags.len();
+            stored.tags.retain(|tag| tag != MISSING_FROM_CLUSTER_TAG);
+            unflagged = stored.tags.len() != tag_count;
+        }
+        let merged = merger.service(stored.as_ref(), imported.clone());
+        if let Some(service) = merged.or(stored.filter(|_| unflagged)) {
+            operations.push(Operation::SaveService {
+                service,
+                force: true,
+            });
+        }
+    }
+
+    let imported_ids: HashSet<&str> = topology.services.iter().map(|s| s.id.as_str()).collect();
+    let mut missing: Vec<&Service> = existing
+        .values()
+        .filter(|s| !imported_ids.contains(s.id.as_str()))
+        .filter(|s| imported_namespace(s).is_some_and(|ns| namespaces.iter().any(|n| n == ns)))
+        .collect();
+    missing.sort_by(|a, b| a.id.cmp(&b.id));
+    for service in &missing {
+        if !service
+            .tags
+            .iter()
+            .any(|tag| tag == MISSING_FROM_CLUSTER_TAG)
+        {
+            let mut flagged = (*service).clone();
+            flagged.tags.push(MISSING_FROM_CLUSTER_TAG.to_string());
+            operations.push(Operation::SaveService {
+                service: flagged,
+                force: true,
+            });
+        }
+    }
+
+    let relationships = state.storage.load_relationships(environment)?;
+    let inferred: HashSet<(&str, &str)> = topology
+        .edges
+        .iter()
+        .map(|edge| (edge.source.as_str(), edge.target.as_str()))
+        .collect();
+    for relationship in &relationships {
+        let from_imported_namespace = existing
+            .get(&relationship.source)
+            .and_then(imported_namespace)
+            .is_some_and(|ns| namespaces.iter().any(|n| n == ns));
+        if relationship.origin.is_imported_from(IMPORT_SOURCE)
+            && from_imported_namespace
+            && !inferred.contains(&(relationship.source.as_str(), relationship.target.as_str()))
+        {
+            operations.push(Operation::DeleteRelationship {
+                relationship_id: relationship.id.clone(),
+            });
+        }
+    }
+
+    let mut taken_ids: HashSet<String> = relationships.iter().map(|r| r.id.clone()).collect();
+    for edge in &topology.edges {
+        let exists = relationships.iter().any(|r| {
+            r.source == edge.source
+                && r.target == edge.target
+                && r.relationship_type == RelationshipType::CommunicatesWith
+        });
+        if exists {
+            continue;
+        }
+
+        let base_id = format!("rel-{}-to-{}", edge.source, edge.target);
+        let mut id = base_id.clone();
+        let mut suffix = 2;
+        while taken_ids.contains(&id) {
+            id = format!("{}-{}", base_id, suffix);
+            suffix += 1;
+        }
+        taken_ids.insert(id.clone());
+
+        operations.push(Operation::SaveRelationship {
+            relationship: Relationship {
+                id,
+                source: edge.source.clone(),
+                target: edge.target.clone(),
+                relationship_type: RelationshipType::CommunicatesWith,
+                description: Some(edge.reason.clone()),
+                metadata: None,
+                effective_from: None,
+                effective_until: None,
+                origin: RelationshipOrigin::Imported(IMPORT_SOURCE.to_string()),
+                review_status: ReviewStatus::Unreviewed,
+                review_note: None,
+                reviewed_by: None,
+                reviewed_at: None,
+                revision: 0,
+            },
+            force: false,
+        });
+    }
+
+    let missing_ids = missing.iter().map(|s| s.id.clone()).collect();
+    Ok((operations, missing_ids, merger.into_conflicts()))
+}
+
+/// Returns the namespace a service was imported from, if it was imported.
+pub fn imported_namespace(service: &Service) -> Option<&str> {
+    service
+        .metadata
+        .get(KUBERNETES_METADATA_KEY)?
+        .get("namespace")?
+        .as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{block_on, service, snapshot_dir, without_dry_run, TestApp};
+    use serde_json::{json, Value};
+    use std::fs;
+    use std::thread;
+    use tiny_http::{Header, Response, Server};
+
+    fn deployment(name: &str, env: Value) -> Value {
+        json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": { "name": name, "namespace": "shop" },
+            "spec": {
+                "selector": { "matchLabels": { "app": name } },
+                "template": {
+                    "metadata": { "labels": { "app": name } },
+                    "spec": {
+                        "containers": [{ "name": name, "image": format!("shop/{name}:1.0"), "env": env }]
+                    }
+                }
+            }
+        })
+    }
+
+    /// The list the fake API server answers a request for `path` with.
+    fn list_for(path: &str) -> Value {
+        let (kind, items) = if path.ends_with("/deployments") {
+            let env = json!([{ "name": "API_URL", "value": "http://api:8080" }]);
+            (
+                "DeploymentList",
+                vec![deployment("web", env), deployment("api", json!([]))],
+            )
+        } else if path.ends_with("/statefulsets") {
+            ("StatefulSetList", Vec::new())
+        } else if path.ends_with("/services") {
+            let api = json!({
+                "apiVersion": "v1",
+                "kind": "Service",
+                "metadata": { "name": "api", "namespace": "shop" },
+                "spec": { "selector": { "app": "api" } }
+            });
+            ("ServiceList", vec![api])
+        } else {
+            ("IngressList", Vec::new())
+        };
+        json!({ "apiVersion": "v1", "kind": kind, "metadata": {}, "items": items })
+    }
+
+    /// Serves the shop namespace on a local port and returns a kubeconfig for it.
+    fn fake_cluster(dir: &Path) -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let path = request.url().split('?').next().unwrap_or_default();
+                let body = list_for(path).to_string();
+                let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+                let _ = request.respond(Response::from_string(body).with_header(content_type));
+            }
+        });
+
+        let kubeconfig = dir.join("kubeconfig.yaml");
+        let config = format!(
+            "apiVersion: v1
+kind: Config
+clusters:
+- name: test
+  cluster:
+    server: http://127.0.0.1:{port}
+contexts:
+- name: test
+  context:
+    cluster: test
+    user: test
+current-context: test
+users:
+- name: test
+  user:
+    token: test
+"
+        );
+        fs::write(&kubeconfig, config).unwrap();
+        kubeconfig.display().to_string()
+    }
+
+    #[test]
+    fn a_dry_run_import_writes_nothing_and_matches_the_real_run() {
+        let app = TestApp::with_environment("dev", &[service("legacy")], &[]);
+        let config_dir = tempfile::TempDir::new().unwrap();
+        let kubeconfig = fake_cluster(config_dir.path());
+        let import = |dry_run| {
+            block_on(import_from_kubernetes_cluster(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                Some(kubeconfig.clone()),
+                vec!["shop".to_string()],
+                Some(dry_run),
+                None,
+            ))
+            .unwrap()
+        };
+        let files = snapshot_dir(app.data_path());
+
+        let preview = import(true);
+
+        assert!(preview.result.dry_run);
+        assert_eq!(snapshot_dir(app.data_path()), files);
+        assert_eq!(preview.services_found, 2);
+        assert_eq!(preview.edges_found, 1);
+        assert_eq!(preview.result.saved_services.len(), 2);
+        assert_eq!(preview.result.saved_relationships.len(), 1);
+
+        let applied = import(false);
+        assert_eq!(
+            without_dry_run(&preview.result),
+            without_dry_run(&applied.result)
+        );
+        assert!(app.data_path().join("dev/services/shop-web.json").exists());
+    }
+}
@@ -0,0 +1,624 @@
+//! Metadata key catalog commands for the Tauri application.
+//!
+//! Metadata is free-form, so the same fact ends up under several keys
+//! (`protocol` and `proto`) or with several value types (a port as `8080`
+//! and as `"8080"`). The reports list every top-level key with how often
+//! and with which value types it is used, and the rename commands move a
+//! key to its agreed spelling across all services or relationships.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime};
+
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::error::AppError;
+use crate::state::{cache, read_state, run_blocking, write_state, AppState};
+
+/// Number of distinct example values a report lists per key.
+pub const MAX_METADATA_EXAMPLES: usize = 3;
+
+/// Number of conflicting IDs a refused rename lists in its error.
+const MAX_LISTED_CONFLICTS: usize = 5;
+
+/// The JSON type of a metadata value.
+///
+/// # Variants
+///
+/// * `String` - A string
+/// * `Number` - An integer or floating point number
+/// * `Bool` - `true` or `false`
+/// * `Object` - A nested object
+/// * `Array` - A list
+/// * `Null` - `null`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataValueType {
+    String,
+    Number,
+    Bool,
+    Object,
+    Array,
+    Null,
+}
+
+impl MetadataValueType {
+    /// Returns the type of a value.
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::String(_) => Self::String,
+            Value::Number(_) => Self::Number,
+            Value::Bool(_) => Self::Bool,
+            Value::Object(_) => Self::Object,
+            Value::Array(_) => Self::Array,
+            Value::Null => Self::Null,
+        }
+    }
+}
+
+/// How one metadata key is used.
+///
+/// # Fields
+///
+/// * `key` - The key
+/// * `count` - Number of items that have it
+/// * `value_types` - Number of items per type of their value; more than one
+///   type means the key is used inconsistently
+/// * `examples` - Up to `MAX_METADATA_EXAMPLES` distinct values, the most used first
+/// * `similar_keys` - Other keys that differ only in case or separators
+///   (e.g. `health_url` for `healthUrl`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataKeyUsage {
+    pub key: String,
+    pub count: usize,
+    pub value_types: BTreeMap<MetadataValueType, usize>,
+    pub examples: Vec<Value>,
+    pub similar_keys: Vec<String>,
+}
+
+/// The metadata keys used by an environment's services or relationships.
+///
+/// # Fields
+///
+/// * `item_count` - Number of services or relationships
+/// * `items_with_metadata` - Number of them with at least one metadata key
+/// * `keys` - Every key used, the most used first and then by key
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataReport {
+    pub item_count: usize,
+    pub items_with_metadata: usize,
+    pub keys: Vec<MetadataKeyUsage>,
+}
+
+/// What a rename does with an item that already has the new key.
+///
+/// # Variants
+///
+/// * `KeepExisting` - The value under the new key is kept and the old key dropped
+/// * `Overwrite` - The value under the old key replaces it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyMergePolicy {
+    KeepExisting,
+    Overwrite,
+}
+
+/// The result of renaming a metadata key.
+///
+/// # Fields
+///
+/// * `renamed` - IDs of the items whose key was renamed
+/// * `merged` - IDs of the items among them that had both keys, merged by the policy
+/// * `result` - What was saved (or, in a dry run, would be)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataKeyRename {
+    pub renamed: Vec<String>,
+    pub merged: Vec<String>,
+    pub result: BatchResult,
+}
+
+/// Lists the metadata keys an environment's relationships use.
+///
+/// Only top-level keys are listed; a nested object counts as one value of
+/// type `object`.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(MetadataReport)` - Every key with its counts, value types and examples
+/// * `Err(AppError::FileLoad)` - If the relationships cannot be parsed
+/// * `Err(AppError::Io)` - If the relationships cannot be read
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: find the keys used with more than one value type
+/// const report = await invoke('get_relationship_metadata_report', { environment: 'prod' });
+/// const mixed = report.keys.filter(k => Object.keys(k.valueTypes).length > 1);
+/// ```
+#[tauri::command]
+pub async fn get_relationship_metadata_report<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+) -> Result<MetadataReport, AppError> {
+    run_blocking(app, move |state| {
+        let relationships = cache::shared_relationships(state, &environment)?;
+        Ok(metadata_report(
+            relationships.iter().map(|r| r.metadata.as_ref()),
+        ))
+    })
+    .await
+}
+
+/// Lists the metadata keys an environment's services use.
+///
+/// Like `get_relationship_metadata_report`, for services.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(MetadataReport)` - Every key with its counts, value types and examples
+/// * `Err(AppError::FileLoad)` - If a service file cannot be parsed
+/// * `Err(AppError::Io)` - If the services cannot be read
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('get_service_metadata_report', { environment: 'prod' });
+/// ```
+#[tauri::command]
+pub async fn get_service_metadata_report<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+) -> Result<MetadataReport, AppError> {
+    run_blocking(app, move |state| {
+        let services = cache::shared_services(state, &environment)?;
+        Ok(metadata_report(services.iter().map(|s| Some(&s.metadata))))
+    })
+    .await
+}
+
+/// Renames a metadata key on every relationship that has it.
+///
+/// A relationship that has both keys is refused unless `merge` says which
+/// value to keep, so no value is lost silently. All renames are saved as one
+/// batch (see `execute_batch`), so the change is undone as a unit.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment
+/// * `old_key` - The key to rename
+/// * `new_key` - The key to rename it to
+/// * `merge` - What to do with relationships that have both keys (default: refuse)
+/// * `dry_run` - If true, only compute the renames (default: false)
+///
+/// # Returns
+///
+/// * `Ok(MetadataKeyRename)` - The renamed relationships
+/// * `Err(AppError::ValidationError)` - If a key is empty, both keys are the same, or
+///   relationships have both keys and no `merge` was given; nothing was written
+/// * `Err(AppError::BatchOperationFailed)` - If a relationship can't be saved;
+///   nothing was written
+/// * `Err(AppError::Io)` - If the relationships cannot be read or written
+///
+/// # Side Effects
+///
+/// Unless `dry_run` is set and if any relationship has the key:
+/// - Updates the relationships JSON file once
+/// - Records the rename as a single entry in the undo journal
+/// - Appends one audit log record per renamed relationship
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: move `proto` to `protocol`, keeping `protocol` where both exist
+/// const rename = await invoke('rename_relationship_metadata_key', {
+///     environment: 'prod',
+///     oldKey: 'proto',
+///     newKey: 'protocol',
+///     merge: 'keep_existing',
+///     dryRun: true
+/// });
+/// console.log(`${rename.renamed.length} relationships would change`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn rename_relationship_metadata_key<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    old_key: String,
+    new_key: String,
+    merge: Option<KeyMergePolicy>,
+    dry_run: Option<bool>,
+) -> Result<MetadataKeyRename, AppError> {
+    let rename = KeyRename::new(old_key, new_key, merge)?;
+    run_blocking(app.clone(), move |state| {
+        rename.run(
+            &app,
+            state,
+            &environment,
+            RenamedItems::Relationships,
+            dry_run.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+/// Renames a metadata key on every service that has it.
+///
+/// Like `rename_relationship_metadata_key`, for services.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment
+/// * `old_key` - The key to rename
+/// * `new_key` - The key to rename it to
+/// * `merge` - What to do with services that have both keys (default: refuse)
+/// * `dry_run` - If true, only compute the renames (default: false)
+///
+/// # Returns
+///
+/// * `Ok(MetadataKeyRename)` - The renamed services
+/// * `Err(AppError::ValidationError)` - If a key is empty, both keys are the same, or
+///   services have both keys and no `merge` was given; nothing was written
+/// * `Err(AppError::BatchOperationFailed)` - If a service can't be saved; nothing
+///   was written
+/// * `Err(AppError::Io)` - If the services cannot be read or written
+///
+/// # Side Effects
+///
+/// Unless `dry_run` is set and if any service has the key:
+/// - Writes the renamed service files
+/// - Records the rename as a single entry in the undo journal
+/// - Appends one audit log record per renamed service
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('rename_service_metadata_key', {
+///     environment: 'prod',
+///     oldKey: 'health_url',
+///     newKey: 'healthUrl'
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn rename_service_metadata_key<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    old_key: String,
+    new_key: String,
+    merge: Option<KeyMergePolicy>,
+    dry_run: Option<bool>,
+) -> Result<MetadataKeyRename, AppError> {
+    let rename = KeyRename::new(old_key, new_key, merge)?;
+    run_blocking(app.clone(), move |state| {
+        rename.run(
+            &app,
+            state,
+            &environment,
+            RenamedItems::Services,
+            dry_run.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+/// Builds the report of the metadata of a set of items.
+///
+/// # Arguments
+///
+/// * `items` - The metadata of each item; `None` for an item without any
+pub fn metadata_report<'a>(
+    items: impl IntoIterator<Item = Option<&'a HashMap<String, Value>>>,
+) -> MetadataReport {
+    let mut item_count = 0;
+    let mut items_with_metadata = 0;
+    let mut usages: BTreeMap<&str, KeyTally> = BTreeMap::new();
+    for metadata in items {
+        item_count += 1;
+        let Some(metadata) = metadata.filter(|m| !m.is_empty()) else {
+            continue;
+        };
+        items_with_metadata += 1;
+        for (key, value) in metadata {
+            let usage = usages.entry(key.as_str()).or_insert_with(|| KeyTally {
+                count: 0,
+                value_types: BTreeMap::new(),
+                values: Vec::new(),
+            });
+            usage.count += 1;
+            *usage
+                .value_types
+                .entry(MetadataValueType::of(value))
+                .or_insert(0) += 1;
+            match usage.values.iter_mut().find(|(seen, _)| seen == value) {
+                Some((_, count)) => *count += 1,
+                None => usage.values.push((value.clone(), 1)),
+            }
+        }
+    }
+
+    let mut spellings: HashMap<String, Vec<&str>> = HashMap::new();
+    for key in usages.keys() {
+        spellings.entry(key_spelling(key)).or_default().push(key);
+    }
+
+    let mut keys: Vec<MetadataKeyUsage> = usages
+        .into_iter()
+        .map(|(key, mut usage)| {
+            usage.values.sort_by_key(|(_, count)| Reverse(*count));
+            MetadataKeyUsage {
+                key: key.to_string(),
+                count: usage.count,
+                value_types: usage.value_types,
+                examples: usage
+                    .values
+                    .into_iter()
+                    .take(MAX_METADATA_EXAMPLES)
+                    .map(|(value, _)| value)
+                    .collect(),
+                similar_keys: spellings[&key_spelling(key)]
+                    .iter()
+                    .filter(|other| **other != key)
+                    .map(|other| other.to_string())
+                    .collect(),
+            }
+        })
+        .collect();
+    keys.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+
+    MetadataReport {
+        item_count,
+        items_with_metadata,
+        keys,
+    }
+}
+
+/// The uses of one key counted so far, for `metadata_report`.
+struct KeyTally {
+    count: usize,
+    value_types: BTreeMap<MetadataValueType, usize>,
+    // Distinct values with their counts, in the order first seen
+    values: Vec<(Value, usize)>,
+}
+
+/// Returns a key without case and separators, so `health_url`, `health-url`
+/// and `healthUrl` compare equal.
+fn key_spelling(key: &str) -> String {
+    key.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The items a `KeyRename` rewrites.
+#[derive(Debug, Clone, Copy)]
+enum RenamedItems {
+    Services,
+    Relationships,
+}
+
+impl RenamedItems {
+    /// The plural name of the items, for messages.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Services => "services",
+            Self::Relationships => "relationships",
+        }
+    }
+}
+
+/// A rename of a metadata key, for `rename_relationship_metadata_key` and
+/// `rename_service_metadata_key`.
+///
+/// # Fields
+///
+/// * `old_key` - The key renamed
+/// * `new_key` - The key it is renamed to
+/// * `merge` - What to do with items that have both keys, if anything
+struct KeyRename {
+    old_key: String,
+    new_key: String,
+    merge: Option<KeyMergePolicy>,
+}
+
+/// What renaming the key of one item's metadata did.
+enum Renamed {
+    /// The item doesn't have the old key.
+    Unchanged,
+    /// The old key was moved to the new one.
+    Moved,
+    /// The item had both keys and they were merged by the policy.
+    Merged,
+    /// The item has both keys and there is no policy.
+    Conflict,
+}
+
+/// The saves of a rename, with the IDs of the renamed and the merged items.
+type RenameOperations = (Vec<Operation>, Vec<String>, Vec<String>);
+
+impl KeyRename {
+    /// Checks the keys of a rename.
+    fn new(
+        old_key: String,
+        new_key: String,
+        merge: Option<KeyMergePolicy>,
+    ) -> Result<Self, AppError> {
+        let (old_key, new_key) = (old_key.trim().to_string(), new_key.trim().to_string());
+        if old_key.is_empty() || new_key.is_empty() {
+            return Err(AppError::ValidationError(
+                "Metadata keys cannot be empty".to_string(),
+            ));
+        }
+        if old_key == new_key {
+            return Err(AppError::ValidationError(format!(
+                "Metadata key '{}' cannot be renamed to itself",
+                old_key
+            )));
+        }
+
+        Ok(Self {
+            old_key,
+            new_key,
+            merge,
+        })
+    }
+
+    /// Computes the renames and, unless it is a dry run, saves them as one batch.
+    fn run<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        state: &RwLock<AppState>,
+        environment: &str,
+        items: RenamedItems,
+        dry_run: bool,
+    ) -> Result<MetadataKeyRename, AppError> {
+        if dry_run {
+            let state = read_state(state);
+            let (operations, renamed, merged) = self.operations(&state, environment, items)?;
+            let mut plan = Plan::compute(&state, environment, operations)?;
+            plan.result.dry_run = true;
+            return Ok(MetadataKeyRename {
+                renamed,
+                merged,
+                result: plan.result,
+            });
+        }
+
+        let mut state = write_state(state);
+        let (operations, renamed, merged) = self.operations(&state, environment, items)?;
+        let plan = Plan::compute(&state, environment, operations)?;
+        let description = format!(
+            "Renamed metadata key '{}' to '{}' on {} {}",
+            self.old_key,
+            self.new_key,
+            renamed.len(),
+            items.name()
+        );
+        let command = match items {
+            RenamedItems::Services => "rename_service_metadata_key",
+            RenamedItems::Relationships => "rename_relationship_metadata_key",
+        };
+        let result = plan.apply(app, &mut state, environment, command, description)?;
+
+        Ok(MetadataKeyRename {
+            renamed,
+            merged,
+            result,
+        })
+    }
+
+    /// Builds the saves of the renamed items, with the IDs of the renamed and
+    /// the merged ones.
+    ///
+    /// # Returns
+    ///
+    /// * `Err(AppError::ValidationError)` - If items have both keys and there is no policy
+    fn operations(
+        &self,
+        state: &AppState,
+        environment: &str,
+        items: RenamedItems,
+    ) -> Result<RenameOperations, AppError> {
+        let mut operations = Vec::new();
+        let (mut renamed, mut merged, mut conflicts) = (Vec::new(), Vec::new(), Vec::new());
+        let mut record = |id: &str, outcome: &Renamed| match outcome {
+            Renamed::Unchanged => false,
+            Renamed::Conflict => {
+                conflicts.push(id.to_string());
+                false
+            }
+            Renamed::Moved | Renamed::Merged => {
+                if matches!(outcome, Renamed::Merged) {
+                    merged.push(id.to_string());
+                }
+                renamed.push(id.to_string());
+                true
+            }
+        };
+
+        match items {
+            RenamedItems::Services => {
+                for mut service in state.storage.load_services(environment)? {
+                    let outcome = self.rename(&mut service.metadata);
+                    if record(&service.id, &outcome) {
+                        operations.push(Operation::SaveService {
+                            service,
+                            force: false,
+                        });
+                    }
+                }
+            }
+            RenamedItems::Relationships => {
+                for mut relationship in state.storage.load_relationships(environment)? {
+                    let Some(metadata) = relationship.metadata.as_mut() else {
+                        continue;
+                    };
+                    let outcome = self.rename(metadata);
+                    if record(&relationship.id, &outcome) {
+                        operations.push(Operation::SaveRelationship {
+                            relationship,
+                            force: false,
+                        });
+                    }
+                }
+            }
+        }
+
+        if !conflicts.is_empty() {
+            let mut listed = conflicts[..conflicts.len().min(MAX_LISTED_CONFLICTS)].join(", ");
+            if conflicts.len() > MAX_LISTED_CONFLICTS {
+                listed.push_str(", ...");
+            }
+            return Err(AppError::ValidationError(format!(
+                "{} {} have both '{}' and '{}' ({}); pass a merge policy to rename them",
+                conflicts.len(),
+                items.name(),
+                self.old_key,
+                self.new_key,
+                listed
+            )));
+        }
+
+        Ok((operations, renamed, merged))
+    }
+
+    /// Renames the key in one item's metadata.
+    fn rename(&self, metadata: &mut HashMap<String, Value>) -> Renamed {
+        if !metadata.contains_key(&self.old_key) {
+            return Renamed::Unchanged;
+        }
+        let outcome = match (metadata.contains_key(&self.new_key), self.merge) {
+            (false, _) => Renamed::Moved,
+            (true, Some(_)) => Renamed::Merged,
+            (true, None) => return Renamed::Conflict,
+        };
+
+        if let Some(value) = metadata.remove(&self.old_key) {
+            if matches!(outcome, Renamed::Moved) || self.merge == Some(KeyMergePolicy::Overwrite) {
+                metadata.insert(self.new_key.clone(), value);
+            }
+        }
+        outcome
+    }
+}
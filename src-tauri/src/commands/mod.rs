@@ -1,5 +1,37 @@
+pub mod annotations;
+pub mod audit;
+pub mod batch;
+pub mod cache;
+pub mod consul;
+pub mod custom_types;
+pub mod diagnostics;
+pub mod drawio;
+pub mod drift;
+pub mod encryption;
 pub mod environments;
+pub mod external;
+pub mod extraction;
+pub mod git;
+pub mod github;
 pub mod graph;
+pub mod groups;
+pub mod history;
+pub mod kubernetes;
+pub mod metadata;
+pub mod nginx;
+pub mod operations;
+pub mod ownership;
+pub mod placeholders;
+pub mod probes;
+pub mod reconciliation;
 pub mod relationships;
+pub mod scheduler;
 pub mod services;
+pub mod settings;
+pub mod simulation;
+pub mod snapshots;
+pub mod storage;
+pub mod teams;
 pub mod validation;
+pub mod views;
+pub mod workspaces;
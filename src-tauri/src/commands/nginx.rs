@@ -0,0 +1,484 @@
+//! nginx config import commands for the Tauri application.
+//!
+//! Turns the routes of a gateway's nginx config (see `nginx`) into
+//! communicates_with relationships from the gateway to the services it
+//! proxies to. Imports can be repeated: edges imported before are updated
+//! with the locations routed to them instead of being duplicated, and
+//! removed once the config no longer routes to their target. Edges with
+//! another origin (see `RelationshipOrigin`) are left as they are.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime};
+
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::error::AppError;
+use crate::merge::{MergeConflict, MergeStrategies, MergeStrategy, Merger};
+use crate::models::{
+    ImportCounts, Relationship, RelationshipOrigin, RelationshipType, ReviewStatus, Service,
+    ServiceStatus, ServiceType,
+};
+use crate::nginx::{self, ProxyRoute, SkippedDirective};
+use crate::state::progress::Progress;
+use crate::state::{run_blocking, write_state, AppState};
+use crate::storage;
+
+/// Relationship metadata key holding the nginx locations routed over an edge.
+pub const NGINX_METADATA_KEY: &str = "nginx";
+
+/// Import source of the relationships the nginx import creates.
+pub const IMPORT_SOURCE: &str = "nginx";
+
+/// How an import merges into what is stored: a service an external
+/// service would replace and the fields of imported edges edited in the app
+/// are kept, and the routed locations are merged into an edge's metadata.
+const MERGE_STRATEGIES: MergeStrategies = MergeStrategies {
+    name: MergeStrategy::KeepExisting,
+    service_type: MergeStrategy::KeepExisting,
+    status: MergeStrategy::KeepExisting,
+    details: MergeStrategy::KeepExisting,
+    tags: MergeStrategy::KeepExisting,
+    metadata: MergeStrategy::Merge,
+};
+
+/// The locations routed to each target service ID.
+type RoutedLocations = BTreeMap<String, BTreeSet<String>>;
+
+/// The result of an nginx config import.
+///
+/// # Fields
+///
+/// * `routes_found` - Number of `proxy_pass` routes read from the config
+/// * `created_services` - IDs of the external services created for unknown upstreams
+/// * `skipped` - Directives that were skipped, with the reason
+/// * `conflicts` - Fields where the config and a stored service or edge disagreed,
+///   with the side that was saved (see `merge`)
+/// * `result` - What the import saved
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NginxImportReport {
+    pub routes_found: usize,
+    pub created_services: Vec<String>,
+    pub skipped: Vec<SkippedDirective>,
+    pub conflicts: Vec<MergeConflict>,
+    pub result: BatchResult,
+}
+
+/// Imports a gateway's routing edges from its nginx config.
+///
+/// Each `proxy_pass` host (or upstream name) is matched against the IDs and
+/// names of the environment's services, also trying its first DNS label
+/// (`billing.internal` matches `billing`) and the servers of an upstream.
+/// Unmatched hosts become external services, unless a service with the
+/// external service's ID exists, which is kept as it is. The gateway gets one
+/// communicates_with relationship per target, with the routed locations in
+/// its `nginx` metadata and the origin `{ imported: "nginx" }`. Imported
+/// edges of the gateway whose target is no longer routed to are deleted;
+/// a manual edge to a target is kept as it is instead of getting an
+/// imported one. Edited descriptions of imported edges are kept; the report
+/// lists them with anything else the config disagreed with. All changes
+/// are written as one batch (see `execute_batch`).
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The environment to import into
+/// * `config_path` - The main nginx config file
+/// * `gateway_service_id` - The service the config belongs to
+/// * `operation_id` - ID for the `operation:progress` events and `cancel_operation`
+///
+/// # Returns
+///
+/// * `Ok(NginxImportReport)` - What was found, skipped and changed
+/// * `Err(AppError::FileLoad)` - If the config can't be read
+/// * `Err(AppError::ServiceNotFound)` - If the gateway service doesn't exist
+/// * `Err(AppError::BatchOperationFailed)` - If a change can't be made; nothing was written
+/// * `Err(AppError::OperationCancelled)` - If cancelled before writing; nothing was written
+///
+/// # Side Effects
+///
+/// - Emits `operation:progress` events while computing and writing the changes
+/// - Writes the created service files and `relationships.json`
+/// - Records the time and counts of the import in the environment's `environment.json`
+/// - Records the import as a single entry in the undo journal
+/// - Appends one audit log record per changed service and relationship
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('import_nginx_config', {
+///     environment: 'prod',
+///     configPath: '/etc/nginx/nginx.conf',
+///     gatewayServiceId: 'edge-gateway',
+///     operationId: crypto.randomUUID()
+/// });
+/// report.skipped.forEach(s => console.warn(`${s.file}:${s.line} ${s.reason}`));
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_nginx_config<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    config_path: String,
+    gateway_service_id: String,
+    operation_id: Option<String>,
+) -> Result<NginxImportReport, AppError> {
+    storage::validate_environment_name(&environment)?;
+
+    run_blocking(app.clone(), move |state| {
+        let mut progress = Progress::start(&app, state, operation_id, "import_nginx_config");
+        let result = import_routes(
+            &app,
+            state,
+            &environment,
+            &config_path,
+            &gateway_service_id,
+            &mut progress,
+        );
+        progress.finish(result)
+    })
+    .await
+}
+
+/// Reads the config and saves its routes, for `import_nginx_config`.
+fn import_routes<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    config_path: &str,
+    gateway_service_id: &str,
+    progress: &mut Progress,
+) -> Result<NginxImportReport, AppError> {
+    progress.phase("Reading config", 1);
+    let config = nginx::parse_config(Path::new(config_path))?;
+    progress.advance();
+
+    let mut state = write_state(state);
+    let services = state.storage.load_services(environment)?;
+    if !services.iter().any(|s| s.id == gateway_service_id) {
+        return Err(AppError::ServiceNotFound(gateway_service_id.to_string()));
+    }
+
+    let mut merger = Merger::new(MERGE_STRATEGIES);
+    let (mut operations, targets, created_services) =
+        target_services(&services, &config.routes, &mut merger);
+    // One edge from the gateway to each routed service
+    let counts = ImportCounts {
+        services: targets.len(),
+        relationships: targets.len(),
+    };
+    operations.extend(edge_operations(
+        &state,
+        environment,
+        gateway_service_id,
+        targets,
+        &mut merger,
+    )?);
+
+    let plan = Plan::compute_with_progress(&state, environment, operations, progress)?;
+    let description = format!(
+        "Imported {} nginx routes of {}",
+        config.routes.len(),
+        gateway_service_id
+    );
+    let result = plan.apply_with_progress(
+        app,
+        &mut state,
+        environment,
+        "import_nginx_config",
+        description,
+        progress,
+    )?;
+    state.record_import(environment, IMPORT_SOURCE, counts);
+
+    Ok(NginxImportReport {
+        routes_found: config.routes.len(),
+        created_services,
+        skipped: config.skipped,
+        conflicts: merger.into_conflicts(),
+        result,
+    })
+}
+
+/// Resolves each route to a service, creating external services for unknown hosts.
+///
+/// Returns the saves of the created services, the locations routed to each
+/// target service ID and the IDs of the created services.
+fn target_services(
+    services: &[Service],
+    routes: &[ProxyRoute],
+    merger: &mut Merger,
+) -> (Vec<Operation>, RoutedLocations, Vec<String>) {
+    let mut operations = Vec::new();
+    let mut targets = RoutedLocations::new();
+    let mut created = Vec::new();
+
+    for route in routes {
+        let matched = std::iter::once(&route.host)
+            .chain(&route.upstream_servers)
+            .find_map(|host| match_service(services, host));
+        let target_id = match matched {
+            Some(service) => service.id.clone(),
+            None => {
+                let id = stub_id(&route.host);
+                if !targets.contains_key(&id) {
+                    let stored = services.iter().find(|service| service.id == id);
+                    let stub = stub_service(&id, route);
+                    if let Some(service) = merger.service(stored, stub) {
+                        created.push(id.clone());
+                        operations.push(Operation::SaveService {
+                            service,
+                            force: true,
+                        });
+                    }
+                }
+                id
+            }
+        };
+        targets
+            .entry(target_id)
+            .or_default()
+            .insert(route.location.clone());
+    }
+
+    (operations, targets, created)
+}
+
+/// Finds the service a host names, by ID or name, then by its first DNS label.
+fn match_service<'a>(services: &'a [Service], host: &str) -> Option<&'a Service> {
+    let host = host.to_lowercase();
+    let first_label = host.split('.').next().unwrap_or_default().to_string();
+    [host, first_label].into_iter().find_map(|candidate| {
+        services.iter().find(|service| {
+            service.id.to_lowercase() == candidate || service.name.to_lowercase() == candidate
+        })
+    })
+}
+
+/// Returns the ID of the external service created for an unknown host.
+fn stub_id(host: &str) -> String {
+    storage::slugify(host, storage::MAX_SLUG_LENGTH)
+}
+
+/// Builds the external service of the unknown host of a route.
+fn stub_service(id: &str, route: &ProxyRoute) -> Service {
+    Service {
+        id: id.to_string(),
+        name: route.host.clone(),
+        service_type: ServiceType::External,
+        status: ServiceStatus::Unknown,
+        description: Some(format!(
+            "Proxied to by nginx config {} (line {})",
+            route.file, route.line
+        )),
+        version: None,
+        owner: None,
+        team: None,
+        tier: None,
+        lifecycle: None,
+        decommission_date: None,
+        successor_id: None,
+        tags: Vec::new(),
+        metadata: HashMap::new(),
+        revision: 0,
+    }
+}
+
+/// Builds the saves and deletions of the gateway's edges, merging locations
+/// into the edges imported before.
+fn edge_operations(
+    state: &AppState,
+    environment: &str,
+    gateway_id: &str,
+    targets: RoutedLocations,
+    merger: &mut Merger,
+) -> Result<Vec<Operation>, AppError> {
+    let relationships = state.storage.load_relationships(environment)?;
+    let mut taken_ids: HashSet<String> = relationships.iter().map(|r| r.id.clone()).collect();
+    let mut operations = Vec::new();
+
+    for relationship in &relationships {
+        if relationship.source == gateway_id
+            && relationship.origin.is_imported_from(IMPORT_SOURCE)
+            && !targets.contains_key(&relationship.target)
+        {
+            operations.push(Operation::DeleteRelationship {
+                relationship_id: relationship.id.clone(),
+            });
+        }
+    }
+
+    for (target, mut locations) in targets {
+        if target == gateway_id {
+            continue;
+        }
+
+        let routes_to_target = |r: &Relationship| {
+            r.source == gateway_id
+                && r.target == target
+                && r.relationship_type == RelationshipType::CommunicatesWith
+        };
+        let imported = relationships
+            .iter()
+            .find(|r| routes_to_target(r) && r.origin.is_imported_from(IMPORT_SOURCE));
+        let id = match imported {
+            Some(existing) => {
+                locations.extend(stored_locations(existing));
+                existing.id.clone()
+            }
+            // Leave the target's manual edge alone rather than add an imported one
+            None if relationships.iter().any(routes_to_target) => continue,
+            None => {
+                let base_id = format!("rel-{}-to-{}", gateway_id, target);
+                let mut id = base_id.clone();
+                let mut suffix = 2;
+                while taken_ids.contains(&id) {
+                    id = format!("{}-{}", base_id, suffix);
+                    suffix += 1;
+                }
+                taken_ids.insert(id.clone());
+                id
+            }
+        };
+
+        let edge = Relationship {
+            id,
+            source: gateway_id.to_string(),
+            target,
+            relationship_type: RelationshipType::CommunicatesWith,
+            description: Some("Routed by the nginx config".to_string()),
+            metadata: Some(HashMap::from([(
+                NGINX_METADATA_KEY.to_string(),
+                locations_metadata(&locations),
+            )])),
+            effective_from: None,
+            effective_until: None,
+            origin: RelationshipOrigin::Imported(IMPORT_SOURCE.to_string()),
+            review_status: ReviewStatus::Unreviewed,
+            review_note: None,
+            reviewed_by: None,
+            reviewed_at: None,
+            revision: 0,
+        };
+        if let Some(relationship) = merger.relationship(imported, edge) {
+            operations.push(Operation::SaveRelationship {
+                relationship,
+                force: false,
+            });
+        }
+    }
+
+    Ok(operations)
+}
+
+/// Returns the locations a previous import stored on an edge.
+fn stored_locations(relationship: &Relationship) -> Vec<String> {
+    relationship
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get(NGINX_METADATA_KEY))
+        .and_then(|nginx| nginx.get("locations"))
+        .and_then(|locations| locations.as_array())
+        .map(|locations| {
+            locations
+                .iter()
+                .filter_map(|l| l.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Builds the `nginx` metadata of an edge.
+fn locations_metadata(locations: &BTreeSet<String>) -> serde_json::Value {
+    serde_json::json!({ "locations": locations })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::ConflictWinner;
+    use crate::test_support::{block_on, service, snapshot_dir, TestApp};
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const CONFIG: &str = "http {
+    server {
+        location /billing {
+            proxy_pass http://billing.internal:8080;
+        }
+    }
+}
+";
+
+    fn import(test_app: &TestApp, config_path: &Path) -> NginxImportReport {
+        block_on(import_nginx_config(
+            test_app.handle(),
+            "prod".to_string(),
+            config_path.display().to_string(),
+            "edge".to_string(),
+            None,
+        ))
+        .unwrap()
+    }
+
+    /// The files under one directory of the environment.
+    fn files_under(test_app: &TestApp, dir: &str) -> BTreeMap<PathBuf, Vec<u8>> {
+        snapshot_dir(&test_app.data_path().join("prod").join(dir))
+    }
+
+    #[test]
+    fn a_stored_service_is_kept_and_reimports_leave_the_data_untouched() {
+        let mut billing = service("billing-internal");
+        billing.name = "Billing service".to_string();
+        billing.description = Some("Edited in the app".to_string());
+        let test_app = TestApp::with_environment("prod", &[service("edge"), billing], &[]);
+        let config_path = test_app.data_path().join("nginx.conf");
+        fs::write(&config_path, CONFIG).unwrap();
+        let services_before = files_under(&test_app, "services");
+
+        let report = import(&test_app, &config_path);
+
+        assert!(report.created_services.is_empty());
+        assert_eq!(files_under(&test_app, "services"), services_before);
+        let kept: Vec<(&str, &str, ConflictWinner)> = report
+            .conflicts
+            .iter()
+            .map(|c| (c.item_id.as_str(), c.field.as_str(), c.winner))
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                ("billing-internal", "name", ConflictWinner::Existing),
+                ("billing-internal", "serviceType", ConflictWinner::Existing),
+                ("billing-internal", "status", ConflictWinner::Existing),
+                ("billing-internal", "description", ConflictWinner::Existing),
+            ]
+        );
+        let relationships = test_app
+            .state()
+            .read()
+            .unwrap()
+            .storage
+            .load_relationships("prod")
+            .unwrap();
+        assert_eq!(relationships.len(), 1);
+        assert_eq!(relationships[0].target, "billing-internal");
+
+        let relationships_before =
+            fs::read(test_app.data_path().join("prod").join("relationships.json")).unwrap();
+        let report = import(&test_app, &config_path);
+
+        assert!(report.result.saved_services.is_empty());
+        assert!(report.result.saved_relationships.is_empty());
+        assert_eq!(files_under(&test_app, "services"), services_before);
+        assert_eq!(
+            fs::read(test_app.data_path().join("prod").join("relationships.json")).unwrap(),
+            relationships_before
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Long-running operation commands for the Tauri application.
+//!
+//! Commands that report `operation:progress` events (see
+//! `state::progress`) are registered under the ID they were started with
+//! while they run, so they can be listed and cancelled.
+
+use std::sync::Arc;
+use tauri::State;
+
+use crate::error::AppError;
+use crate::state::progress::{RunningOperation, RunningOperations};
+
+/// Lists the long-running operations in progress.
+///
+/// # Arguments
+///
+/// * `operations` - The running operations, managed apart from the state lock
+///
+/// # Returns
+///
+/// * `Ok(Vec<RunningOperation>)` - The running operations, oldest first
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: cancel everything before switching workspaces
+/// const running = await invoke('list_running_operations');
+/// for (const op of running) {
+///     await invoke('cancel_operation', { operationId: op.operationId });
+/// }
+/// ```
+#[tauri::command]
+pub fn list_running_operations(
+    operations: State<'_, Arc<RunningOperations>>,
+) -> Result<Vec<RunningOperation>, AppError> {
+    Ok(operations.list())
+}
+
+/// Cancels a running operation.
+///
+/// The operation stops at its next cancellation check, between two of its
+/// items, and its command returns `AppError::OperationCancelled` with the
+/// phase it was in and how many of the phase's items were done; its final
+/// `operation:progress` event has the outcome `cancelled`. Nothing has been
+/// written at that point: an operation that has already started writing
+/// files is not interrupted and finishes normally.
+///
+/// # Arguments
+///
+/// * `operations` - The running operations, managed apart from the state lock
+/// * `operation_id` - The ID the operation was started with
+///
+/// # Returns
+///
+/// * `Ok(true)` - If the operation was running and has been asked to stop
+/// * `Ok(false)` - If no operation with that ID is running (e.g. it already finished)
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const operationId = crypto.randomUUID();
+/// const unlisten = await listen('operation:progress', ({ payload }) => {
+///     if (payload.operationId === operationId) {
+///         setProgress(payload.done / payload.total);
+///     }
+/// });
+/// const batch = invoke('execute_batch', { environment: 'prod', operations, operationId });
+/// cancelButton.onclick = () => invoke('cancel_operation', { operationId });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn cancel_operation(
+    operations: State<'_, Arc<RunningOperations>>,
+    operation_id: String,
+) -> Result<bool, AppError> {
+    Ok(operations.cancel(&operation_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::batch::{execute_batch, Operation};
+    use crate::state::cache;
+    use crate::test_support::{block_on, service, snapshot_dir, SlowStorage, TestApp};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use tauri::Manager;
+
+    #[test]
+    fn a_cancelled_batch_stops_part_way_and_writes_nothing() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        let files = snapshot_dir(app.data_path());
+        // Each new service is looked up in storage first, which takes 20ms
+        SlowStorage::install(&app, Duration::from_millis(20), Duration::ZERO);
+        let total = 50;
+        let operations: Vec<Operation> = (0..total)
+            .map(|index| Operation::SaveService {
+                service: service(&format!("service-{index}")),
+                force: false,
+            })
+            .collect();
+
+        let handle = app.handle();
+        let batch = thread::spawn(move || {
+            block_on(execute_batch(
+                handle,
+                "dev".to_string(),
+                operations,
+                None,
+                Some("bulk".to_string()),
+            ))
+        });
+        let started = Instant::now();
+        while list_running_operations(app.app.state()).unwrap().is_empty() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "the batch never started"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+        thread::sleep(Duration::from_millis(100));
+
+        let running = list_running_operations(app.app.state()).unwrap();
+        assert_eq!(running[0].operation_id, "bulk");
+        assert_eq!(running[0].command, "execute_batch");
+        assert!(cancel_operation(app.app.state(), "bulk".to_string()).unwrap());
+
+        match batch.join().unwrap() {
+            Err(AppError::OperationCancelled {
+                operation_id,
+                done,
+                total: of,
+                ..
+            }) => {
+                assert_eq!(operation_id, "bulk");
+                assert_eq!(of, total);
+                assert!(
+                    done > 0 && done < total,
+                    "cancelled after {done} of {total}"
+                );
+            }
+            other => panic!("expected the batch to be cancelled, got {other:?}"),
+        }
+        assert_eq!(snapshot_dir(app.data_path()), files);
+        assert_eq!(cache::services(&app.state(), "dev").unwrap().len(), 1);
+        assert!(list_running_operations(app.app.state()).unwrap().is_empty());
+        assert!(!cancel_operation(app.app.state(), "bulk".to_string()).unwrap());
+    }
+}
@@ -0,0 +1,403 @@
+//! Ownership coverage commands.
+//!
+//! When a service breaks, its owner and team say who to page. These commands
+//! report the services nobody owns, how services are spread across teams,
+//! owners whose services belong to several teams, and services owned by
+//! people who have left (the validation config's `departedOwners`). The
+//! report can be exported as CSV for follow-ups outside the application.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::error::AppError;
+use crate::export::csv_row;
+use crate::models::{Service, ServiceType};
+use crate::redaction;
+use crate::state::{cache, read_state, run_blocking, AppState};
+use crate::storage;
+
+/// Columns of an exported ownership report, in order.
+pub const CSV_COLUMNS: &[&str] = &[
+    "category",
+    "serviceId",
+    "serviceName",
+    "serviceType",
+    "owner",
+    "team",
+    "detail",
+];
+
+/// Index of the first column of `CSV_COLUMNS` a redacted export masks values in
+/// (`owner`, `team` and `detail`); IDs, names and types are never redacted.
+const REDACTED_COLUMNS_START: usize = 4;
+
+/// Services of one type with neither an owner nor a team.
+///
+/// # Fields
+///
+/// * `service_type` - The service type as serialized (e.g., "database")
+/// * `service_ids` - IDs of the unowned services, sorted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnownedServices {
+    pub service_type: String,
+    pub service_ids: Vec<String>,
+}
+
+/// A team and how many services it is responsible for.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamServiceCount {
+    pub team: String,
+    pub service_count: usize,
+}
+
+/// An owner whose services belong to more than one team.
+///
+/// # Fields
+///
+/// * `owner` - The owner as recorded on the services
+/// * `teams` - The teams of the owned services, sorted
+/// * `service_ids` - IDs of the owned services, sorted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossTeamOwner {
+    pub owner: String,
+    pub teams: Vec<String>,
+    pub service_ids: Vec<String>,
+}
+
+/// A service owned by someone on the departed list.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepartedOwnerService {
+    pub service_id: String,
+    pub service_name: String,
+    pub owner: String,
+    pub team: Option<String>,
+}
+
+/// Ownership coverage of an environment.
+///
+/// # Fields
+///
+/// * `service_count` - Number of services in the environment
+/// * `unowned_count` - Number of services with neither an owner nor a team
+/// * `unowned` - The unowned services, grouped by type (most first)
+/// * `teams` - Every team with its number of services (most first)
+/// * `cross_team_owners` - Owners whose services belong to several teams
+/// * `departed_owner_services` - Services owned by someone on the departed list
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnershipReport {
+    pub service_count: usize,
+    pub unowned_count: usize,
+    pub unowned: Vec<UnownedServices>,
+    pub teams: Vec<TeamServiceCount>,
+    pub cross_team_owners: Vec<CrossTeamOwner>,
+    pub departed_owner_services: Vec<DepartedOwnerService>,
+}
+
+/// An exported ownership report.
+///
+/// # Fields
+///
+/// * `report` - The exported report, unredacted
+/// * `redacted_values` - How many values were masked in the file (0 without `redact`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnershipExport {
+    pub report: OwnershipReport,
+    pub redacted_values: usize,
+}
+
+/// Reports the ownership coverage of an environment.
+///
+/// Blank owners and teams count as missing. Owners are compared as written;
+/// departed owners are matched case-insensitively against the validation
+/// config's `departedOwners` list.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(OwnershipReport)` - The ownership coverage
+/// * `Err(AppError::FileLoad)` - If the validation config isn't valid JSON
+/// * `Err(AppError)` - If the services cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('get_ownership_report', { environment: 'prod' });
+/// console.log(`${report.unownedCount} of ${report.serviceCount} services have no owner`);
+/// ```
+#[tauri::command]
+pub fn get_ownership_report(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<OwnershipReport, AppError> {
+    ownership_report(&state, &environment)
+}
+
+/// Writes the ownership report of an environment as CSV.
+///
+/// The file has a header row with the `CSV_COLUMNS` and one row per finding,
+/// where `category` is one of:
+/// - `unowned` - A service with neither an owner nor a team
+/// - `cross_team_owner` - A service of an owner who owns services in several
+///   teams; `detail` lists the teams
+/// - `departed_owner` - A service owned by someone who has left
+/// - `team` - A team, with its number of services as `detail` (no service columns)
+///
+/// With `redact`, values the redaction config of the settings lists (see
+/// `redaction`) are masked as `***` in the `owner`, `team` and `detail`
+/// columns, stripped ones too.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to export on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `output_path` - The file to write; replaced if it exists
+/// * `redact` - If true, mask the values the redaction config lists (default: false)
+///
+/// # Returns
+///
+/// * `Ok(OwnershipExport)` - The exported report and how many values were redacted
+/// * `Err(AppError::Io)` - If the report cannot be written
+/// * `Err(AppError::FileLoad)` - If the validation config isn't valid JSON
+/// * `Err(AppError::ValidationError)` - If a redaction pattern is invalid
+///
+/// # Side Effects
+///
+/// - Writes the report to `output_path`
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('export_ownership_report', {
+///     environment: 'prod',
+///     outputPath: '/tmp/prod-ownership.csv',
+///     redact: true
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_ownership_report<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    output_path: String,
+    redact: Option<bool>,
+) -> Result<OwnershipExport, AppError> {
+    run_blocking(app, move |state| {
+        write_ownership_report(state, &environment, &output_path, redact.unwrap_or(false))
+    })
+    .await
+}
+
+/// Builds the ownership report and writes it as CSV, for `export_ownership_report`.
+fn write_ownership_report(
+    state: &RwLock<AppState>,
+    environment: &str,
+    output_path: &str,
+    redact: bool,
+) -> Result<OwnershipExport, AppError> {
+    let report = ownership_report(state, environment)?;
+    let services = cache::shared_services(state, environment)?;
+    let services_map: HashMap<&str, &Service> = services
+        .iter()
+        .map(|s| (s.id.as_str(), s.as_ref()))
+        .collect();
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut push_service = |category: &str, id: &str, detail: &str| {
+        let Some(service) = services_map.get(id) else {
+            return;
+        };
+        rows.push(
+            [
+                category,
+                service.id.as_str(),
+                service.name.as_str(),
+                service.service_type.as_str(),
+                service.owner.as_deref().unwrap_or(""),
+                service.team.as_deref().unwrap_or(""),
+                detail,
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        );
+    };
+
+    for group in &report.unowned {
+        for id in &group.service_ids {
+            push_service("unowned", id, "");
+        }
+    }
+    for owner in &report.cross_team_owners {
+        let detail = format!("Owns services in teams: {}", owner.teams.join(", "));
+        for id in &owner.service_ids {
+            push_service("cross_team_owner", id, &detail);
+        }
+    }
+    for departed in &report.departed_owner_services {
+        push_service("departed_owner", &departed.service_id, "Owner has left");
+    }
+    for team in &report.teams {
+        let count = team.service_count.to_string();
+        rows.push(
+            ["team", "", "", "", "", team.team.as_str(), count.as_str()]
+                .map(str::to_string)
+                .to_vec(),
+        );
+    }
+
+    let mut redacted_values = 0;
+    if redact {
+        let config = read_state(state).settings.redaction.clone();
+        let redacted = redaction::redact(&services, &[], &config)?;
+        let cells = rows
+            .iter_mut()
+            .flat_map(|row| &mut row[REDACTED_COLUMNS_START..]);
+        for cell in cells {
+            let (masked, count) = redacted.mask_in_text(cell);
+            *cell = masked;
+            redacted_values += count;
+        }
+    }
+
+    let mut csv = csv_row(CSV_COLUMNS.iter().copied());
+    for row in &rows {
+        csv.push_str(&csv_row(row.iter().map(String::as_str)));
+    }
+    fs::write(output_path, csv)?;
+
+    Ok(OwnershipExport {
+        report,
+        redacted_values,
+    })
+}
+
+/// Builds the ownership report of an environment.
+fn ownership_report(
+    state: &RwLock<AppState>,
+    environment: &str,
+) -> Result<OwnershipReport, AppError> {
+    let config = {
+        let state = read_state(state);
+        storage::load_validation_config(&state.data_path)?
+    };
+    let departed: HashSet<String> = config
+        .departed_owners
+        .iter()
+        .map(|owner| owner.trim().to_lowercase())
+        .collect();
+
+    let mut services = cache::shared_services(state, environment)?;
+    services.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut unowned: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    let mut teams: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut owners: BTreeMap<&str, (BTreeSet<&str>, Vec<String>)> = BTreeMap::new();
+    let mut departed_owner_services = Vec::new();
+
+    for service in &services {
+        let owner = non_blank(&service.owner);
+        let team = non_blank(&service.team);
+
+        if owner.is_none() && team.is_none() {
+            unowned
+                .entry(service.service_type.as_str())
+                .or_default()
+                .push(service.id.clone());
+        }
+        if let Some(team) = team {
+            *teams.entry(team).or_default() += 1;
+        }
+        if let Some(owner) = owner {
+            let (owner_teams, owned) = owners.entry(owner).or_default();
+            owner_teams.extend(team);
+            owned.push(service.id.clone());
+
+            if departed.contains(&owner.to_lowercase()) {
+                departed_owner_services.push(DepartedOwnerService {
+                    service_id: service.id.clone(),
+                    service_name: service.name.clone(),
+                    owner: owner.to_string(),
+                    team: team.map(str::to_string),
+                });
+            }
+        }
+    }
+
+    let unowned_count = unowned.values().map(Vec::len).sum();
+    let mut unowned: Vec<UnownedServices> = unowned
+        .into_iter()
+        .map(|(service_type, service_ids)| UnownedServices {
+            service_type: service_type.to_string(),
+            service_ids,
+        })
+        .collect();
+    // Stable, so ties stay in type order
+    unowned.sort_by_key(|u| Reverse(u.service_ids.len()));
+
+    let mut teams: Vec<TeamServiceCount> = teams
+        .into_iter()
+        .map(|(team, service_count)| TeamServiceCount {
+            team: team.to_string(),
+            service_count,
+        })
+        .collect();
+    teams.sort_by_key(|t| Reverse(t.service_count));
+
+    let cross_team_owners = owners
+        .into_iter()
+        .filter(|(_, (owner_teams, _))| owner_teams.len() > 1)
+        .map(|(owner, (owner_teams, service_ids))| CrossTeamOwner {
+            owner: owner.to_string(),
+            teams: owner_teams.into_iter().map(str::to_string).collect(),
+            service_ids,
+        })
+        .collect();
+
+    Ok(OwnershipReport {
+        service_count: services.len(),
+        unowned_count,
+        unowned,
+        teams,
+        cross_team_owners,
+        departed_owner_services,
+    })
+}
+
+/// Returns true if a service has neither an owner nor a team.
+pub fn is_unowned(service: &Service) -> bool {
+    non_blank(&service.owner).is_none() && non_blank(&service.team).is_none()
+}
+
+/// Returns true if a service is critical enough that it must have an owner:
+/// tier-1 services and databases.
+pub fn requires_owner(service: &Service) -> bool {
+    service.tier == Some(1) || service.service_type == ServiceType::Database
+}
+
+/// Describes why a service requires an owner, for messages.
+pub fn criticality(service: &Service) -> &'static str {
+    if service.tier == Some(1) {
+        "Tier-1"
+    } else {
+        "Database"
+    }
+}
+
+/// Returns the trimmed value, or `None` if it is missing or blank.
+fn non_blank(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
@@ -0,0 +1,420 @@
+//! Placeholder services for the missing ends of relationships.
+//!
+//! An orphaned relationship usually points at a service that exists but was
+//! never added to the map. `create_placeholder_services` adds a stub for
+//! each such ID, tagged `placeholder` with an unknown status, so the edge
+//! is kept and the stub can be filled in later. The validation panel passes
+//! the `affectedIds` of an orphaned relationship issue straight through:
+//! they hold the relationship's ID next to the missing service's, and IDs of
+//! relationships are skipped. IDs that no relationship points from or at are
+//! rejected, so a typo doesn't turn into a service.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime};
+
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::error::AppError;
+use crate::models::{RelationshipType, Service, ServiceStatus, ServiceType};
+use crate::state::{run_blocking, write_state, AppState};
+
+/// The tag every placeholder service gets, here and in the
+/// `createPlaceholderServices` fix of `fix_validation_issues`.
+pub const PLACEHOLDER_TAG: &str = "placeholder";
+
+/// What placeholder services are created with.
+///
+/// # Fields
+///
+/// * `service_type` - The type of every placeholder; without one, the type is
+///   inferred from the relationships pointing at it (see `infer_service_type`)
+/// * `team` - The team of every placeholder
+/// * `owner` - The owner of every placeholder
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlaceholderDefaults {
+    pub service_type: Option<ServiceType>,
+    pub team: Option<String>,
+    pub owner: Option<String>,
+}
+
+/// The result of creating placeholder services.
+///
+/// # Fields
+///
+/// * `created` - IDs of the placeholders created
+/// * `already_existed` - Requested IDs that already were services; they were left as they are
+/// * `skipped_relationship_ids` - Requested IDs that are relationships and no
+///   relationship points at; they are skipped rather than rejected, since an
+///   orphan issue's affected IDs include them
+/// * `result` - What was saved
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceholderResult {
+    pub created: Vec<String>,
+    pub already_existed: Vec<String>,
+    pub skipped_relationship_ids: Vec<String>,
+    pub result: BatchResult,
+}
+
+/// Creates placeholder services for IDs that relationships point at.
+///
+/// Each placeholder is named after its ID, has an unknown status and the
+/// `placeholder` tag, and takes its type from `defaults` or else from the
+/// relationships targeting it: a reads_from or writes_to target becomes a
+/// database, a publishes or subscribes target a queue, anything else a
+/// backend. Existing services are never overwritten, and only IDs that a
+/// relationship points from or at get a placeholder. All placeholders are
+/// written as one batch (see `execute_batch`), so a single undo removes
+/// them.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment
+/// * `service_ids` - The IDs to create; duplicates and relationship IDs are skipped
+/// * `defaults` - The type, team and owner to create them with
+///
+/// # Returns
+///
+/// * `Ok(PlaceholderResult)` - Which IDs were created and which already existed
+/// * `Err(AppError::ValidationError)` - If an ID is neither a service, a relationship
+///   nor an end of one; nothing was written
+/// * `Err(AppError::BatchOperationFailed)` - If a placeholder can't be created (e.g.,
+///   its ID isn't filename-safe); nothing was written
+/// * `Err(AppError::Io)` - If the data cannot be read or written
+///
+/// # Side Effects
+///
+/// - Writes one file per created placeholder
+/// - Records the creation as a single entry in the undo journal
+/// - Appends one audit log record per created placeholder
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: the "create missing service" button of an orphan issue
+/// const result = await invoke('create_placeholder_services', {
+///     environment: 'prod',
+///     serviceIds: issue.affectedIds,
+///     defaults: { team: 'Platform' }
+/// });
+/// console.log(`Created ${result.created.join(', ')}`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn create_placeholder_services<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    service_ids: Vec<String>,
+    defaults: Option<PlaceholderDefaults>,
+) -> Result<PlaceholderResult, AppError> {
+    run_blocking(app.clone(), move |state| {
+        create_placeholders(
+            &app,
+            state,
+            &environment,
+            &service_ids,
+            &defaults.unwrap_or_default(),
+        )
+    })
+    .await
+}
+
+/// Creates the placeholders, for `create_placeholder_services`.
+fn create_placeholders<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    service_ids: &[String],
+    defaults: &PlaceholderDefaults,
+) -> Result<PlaceholderResult, AppError> {
+    let mut state = write_state(state);
+    let existing: HashSet<String> = state
+        .storage
+        .load_services(environment)?
+        .into_iter()
+        .map(|s| s.id)
+        .collect();
+    let relationships = state.storage.load_relationships(environment)?;
+    let relationship_ids: HashSet<&str> = relationships.iter().map(|r| r.id.as_str()).collect();
+    let mut referenced: HashSet<&str> = HashSet::new();
+    let mut incoming: HashMap<&str, Vec<&RelationshipType>> = HashMap::new();
+    for relationship in &relationships {
+        referenced.insert(relationship.source.as_str());
+        referenced.insert(relationship.target.as_str());
+        incoming
+            .entry(relationship.target.as_str())
+            .or_default()
+            .push(&relationship.relationship_type);
+    }
+
+    let mut created = Vec::new();
+    let mut already_existed = Vec::new();
+    let mut skipped_relationship_ids = Vec::new();
+    let mut unreferenced = Vec::new();
+    let mut operations = Vec::new();
+    let mut seen = HashSet::new();
+    for id in service_ids.iter().map(|id| id.trim()) {
+        if id.is_empty() || !seen.insert(id) {
+            continue;
+        }
+        if existing.contains(id) {
+            already_existed.push(id.to_string());
+            continue;
+        }
+        if !referenced.contains(id) {
+            if relationship_ids.contains(id) {
+                skipped_relationship_ids.push(id.to_string());
+            } else {
+                unreferenced.push(id);
+            }
+            continue;
+        }
+
+        let service_type = defaults.service_type.clone().unwrap_or_else(|| {
+            let types = incoming.get(id).into_iter().flatten().copied();
+            infer_service_type(types).unwrap_or_default()
+        });
+        operations.push(Operation::SaveService {
+            service: placeholder_service(id, service_type, defaults),
+            force: false,
+        });
+        created.push(id.to_string());
+    }
+    if !unreferenced.is_empty() {
+        return Err(AppError::ValidationError(format!(
+            "No relationship points at {}",
+            unreferenced.join(", ")
+        )));
+    }
+
+    let plan = Plan::compute(&state, environment, operations)?;
+    let description = format!("Created {} placeholder services", created.len());
+    let result = plan.apply(
+        app,
+        &mut state,
+        environment,
+        "create_placeholder_services",
+        description,
+    )?;
+
+    Ok(PlaceholderResult {
+        created,
+        already_existed,
+        skipped_relationship_ids,
+        result,
+    })
+}
+
+/// Infers the type of a missing service from the types of the relationships
+/// targeting it.
+///
+/// reads_from and writes_to targets are databases, publishes and subscribes
+/// targets are queues. When both kinds point at the service, the kind with
+/// more relationships wins, and a database on a tie.
+///
+/// # Returns
+///
+/// The inferred type; `None` if no relationship says anything about it.
+pub fn infer_service_type<'a>(
+    relationship_types: impl IntoIterator<Item = &'a RelationshipType>,
+) -> Option<ServiceType> {
+    let (mut database, mut queue) = (0, 0);
+    for relationship_type in relationship_types {
+        match relationship_type {
+            RelationshipType::ReadsFrom | RelationshipType::WritesTo => database += 1,
+            RelationshipType::Publishes | RelationshipType::Subscribes => queue += 1,
+            _ => {}
+        }
+    }
+
+    match (database, queue) {
+        (0, 0) => None,
+        (database, queue) if database >= queue => Some(ServiceType::Database),
+        _ => Some(ServiceType::Queue),
+    }
+}
+
+/// Builds a stub service standing in for a missing relationship endpoint.
+pub fn placeholder_service(
+    id: &str,
+    service_type: ServiceType,
+    defaults: &PlaceholderDefaults,
+) -> Service {
+    Service {
+        id: id.to_string(),
+        name: id.to_string(),
+        service_type,
+        status: ServiceStatus::Unknown,
+        description: Some("Placeholder for a service relationships point at".to_string()),
+        version: None,
+        owner: defaults.owner.clone(),
+        team: defaults.team.clone(),
+        tier: None,
+        lifecycle: None,
+        decommission_date: None,
+        successor_id: None,
+        tags: vec![PLACEHOLDER_TAG.to_string()],
+        metadata: HashMap::new(),
+        revision: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Relationship;
+    use crate::state::{cache, read_state};
+    use crate::test_support::{block_on, relationship, service, snapshot_dir, TestApp};
+
+    fn typed(
+        id: &str,
+        source: &str,
+        target: &str,
+        relationship_type: RelationshipType,
+    ) -> Relationship {
+        let mut relationship = relationship(id, source, target);
+        relationship.relationship_type = relationship_type;
+        relationship
+    }
+
+    /// api depends on auth, reads from orders-db and publishes to events; only
+    /// api exists.
+    fn orphaned_app() -> TestApp {
+        TestApp::with_environment(
+            "dev",
+            &[service("api")],
+            &[
+                relationship("api-auth", "api", "auth"),
+                typed(
+                    "api-orders",
+                    "api",
+                    "orders-db",
+                    RelationshipType::ReadsFrom,
+                ),
+                typed("api-events", "api", "events", RelationshipType::Publishes),
+            ],
+        )
+    }
+
+    fn create(
+        app: &TestApp,
+        ids: &[&str],
+        defaults: Option<PlaceholderDefaults>,
+    ) -> Result<PlaceholderResult, AppError> {
+        let ids = ids.iter().map(|id| id.to_string()).collect();
+        block_on(create_placeholder_services(
+            app.handle(),
+            "dev".to_string(),
+            ids,
+            defaults,
+        ))
+    }
+
+    #[test]
+    fn service_types_are_inferred_from_the_relationships_pointing_at_them() {
+        use RelationshipType::*;
+        assert_eq!(
+            infer_service_type(&[ReadsFrom]),
+            Some(ServiceType::Database)
+        );
+        assert_eq!(infer_service_type(&[WritesTo]), Some(ServiceType::Database));
+        assert_eq!(infer_service_type(&[Publishes]), Some(ServiceType::Queue));
+        assert_eq!(infer_service_type(&[Subscribes]), Some(ServiceType::Queue));
+        assert_eq!(
+            infer_service_type(&[Publishes, ReadsFrom, Subscribes]),
+            Some(ServiceType::Queue)
+        );
+        assert_eq!(
+            infer_service_type(&[Publishes, WritesTo]),
+            Some(ServiceType::Database)
+        );
+        assert_eq!(infer_service_type(&[DependsOn, CommunicatesWith]), None);
+        assert_eq!(infer_service_type(&[]), None);
+    }
+
+    #[test]
+    fn placeholders_are_tagged_unknown_stubs_with_inferred_types() {
+        let app = orphaned_app();
+
+        let result = create(&app, &["auth", "orders-db", "events"], None).unwrap();
+
+        assert_eq!(result.created, ["auth", "orders-db", "events"]);
+        assert!(result.already_existed.is_empty());
+        let stored = |id: &str| cache::service(&app.state(), "dev", id).unwrap();
+        for id in ["auth", "orders-db", "events"] {
+            let placeholder = stored(id);
+            assert_eq!(placeholder.name, id);
+            assert_eq!(placeholder.status, ServiceStatus::Unknown);
+            assert_eq!(placeholder.tags, [PLACEHOLDER_TAG]);
+            assert_eq!(placeholder.team, None);
+            assert_eq!(placeholder.revision, 1);
+        }
+        assert_eq!(stored("auth").service_type, ServiceType::Backend);
+        assert_eq!(stored("orders-db").service_type, ServiceType::Database);
+        assert_eq!(stored("events").service_type, ServiceType::Queue);
+
+        // One batch, so one undo removes them all
+        let state = app.state();
+        let history = read_state(&state).journals["dev"].undo_history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].change_count, 3);
+    }
+
+    #[test]
+    fn defaults_override_the_inferred_type_and_set_team_and_owner() {
+        let app = orphaned_app();
+        let defaults = PlaceholderDefaults {
+            service_type: Some(ServiceType::External),
+            team: Some("Platform".to_string()),
+            owner: Some("alice".to_string()),
+        };
+
+        create(&app, &["orders-db"], Some(defaults)).unwrap();
+
+        let placeholder = cache::service(&app.state(), "dev", "orders-db").unwrap();
+        assert_eq!(placeholder.service_type, ServiceType::External);
+        assert_eq!(placeholder.team.as_deref(), Some("Platform"));
+        assert_eq!(placeholder.owner.as_deref(), Some("alice"));
+        assert_eq!(placeholder.tags, [PLACEHOLDER_TAG]);
+    }
+
+    #[test]
+    fn existing_services_are_left_alone_and_relationship_ids_skipped() {
+        let app = orphaned_app();
+        let original = cache::service(&app.state(), "dev", "api").unwrap();
+
+        // An orphan issue's affected IDs, twice, next to an existing service
+        let result = create(&app, &["api-auth", "auth", " auth ", "api"], None).unwrap();
+
+        assert_eq!(result.created, ["auth"]);
+        assert_eq!(result.already_existed, ["api"]);
+        assert_eq!(result.skipped_relationship_ids, ["api-auth"]);
+        let api = cache::service(&app.state(), "dev", "api").unwrap();
+        assert_eq!(
+            serde_json::to_value(api).unwrap(),
+            serde_json::to_value(original).unwrap()
+        );
+        assert!(!cache::service_exists(&app.state(), "dev", "api-auth").unwrap());
+    }
+
+    #[test]
+    fn ids_no_relationship_refers_to_are_rejected() {
+        let app = orphaned_app();
+        let before = snapshot_dir(app.data_path());
+
+        let result = create(&app, &["auth", "typo", "also-typo"], None);
+
+        match result {
+            Err(AppError::ValidationError(message)) => {
+                assert!(message.contains("typo, also-typo"), "{message}");
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+        assert_eq!(snapshot_dir(app.data_path()), before);
+        assert!(!cache::service_exists(&app.state(), "dev", "auth").unwrap());
+    }
+}
@@ -0,0 +1,361 @@
+//! Health probe commands for the Tauri application.
+//!
+//! Services whose metadata holds a health endpoint URL (under the key of
+//! the `healthProbe.urlKey` setting, `healthUrl` by default) can have
+//! their status taken from it (see `probes`). Probes only run when
+//! `probe_service_health` is invoked; nothing probes in the background.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, RwLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::commands::batch::{Operation, Plan};
+use crate::error::AppError;
+use crate::models::{HealthProbeSettings, ServiceStatus};
+use crate::probes::{self, ProbeResult};
+use crate::state::progress::Progress;
+use crate::state::{cache, read_state, run_blocking, write_state, AppState};
+use crate::storage::{self, audit::now_millis};
+
+/// Service metadata key holding the outcome of the last applied probe.
+pub const HEALTH_PROBE_METADATA_KEY: &str = "healthProbe";
+
+/// Name of the event emitted with the outcome of each probe.
+pub const HEALTH_PROBE_EVENT: &str = "health-probe-result";
+
+/// The outcome of probing one service.
+///
+/// # Fields
+///
+/// * `service_id` - The probed service
+/// * `url` - The health endpoint; `None` if the service has none
+/// * `previous_status` - The status before the probe; `None` if the service doesn't exist
+/// * `status` - The status the answer maps to; `None` if the service wasn't probed
+/// * `http_status` - The status code of the answer, if there was one
+/// * `latency_ms` - How long the request took, in milliseconds
+/// * `error` - Why the service wasn't probed or isn't healthy
+/// * `changed` - Whether the probe changes the status; it was saved if the probe
+///   was applied
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeOutcome {
+    pub service_id: String,
+    pub url: Option<String>,
+    pub previous_status: Option<ServiceStatus>,
+    pub status: Option<ServiceStatus>,
+    pub http_status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    pub changed: bool,
+}
+
+/// A service to probe.
+struct ProbeTarget {
+    service_id: String,
+    url: String,
+    status: ServiceStatus,
+}
+
+/// Probes the health endpoints of services and optionally updates their statuses.
+///
+/// Each service is probed with a GET request to the URL in its metadata
+/// (see `probes` for how answers map to statuses), with at most the
+/// `healthProbe.concurrency` setting's number of requests at a time, each
+/// limited to `healthProbe.timeoutMs`. A failed probe makes the service
+/// unhealthy but never fails the command. With `apply`, every probed
+/// service gets the status its answer maps to, except deprecated services,
+/// which keep theirs, and its `healthProbe` metadata records when it was
+/// probed, the status code and the latency; all of it is written as one
+/// batch (see `execute_batch`). Without `apply`, nothing is written.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool, to
+///   emit events and to queue git auto-commits
+/// * `environment` - The name of the environment
+/// * `service_ids` - The services to probe; every service with a health endpoint
+///   if `None`
+/// * `apply` - If true, save the statuses and probe metadata (default: false)
+/// * `operation_id` - ID for the `operation:progress` events and `cancel_operation`
+///
+/// # Returns
+///
+/// * `Ok(Vec<ProbeOutcome>)` - One outcome per service, in the order of `service_ids`,
+///   or by ID; requested services without a health endpoint or that don't exist
+///   get an outcome with an `error`
+/// * `Err(AppError::BatchOperationFailed)` - If the statuses can't be saved; nothing
+///   was written
+/// * `Err(AppError::OperationCancelled)` - If cancelled before writing; nothing was
+///   written
+/// * `Err(AppError::Io)` - If the data cannot be read or written
+///
+/// # Side Effects
+///
+/// - Sends one GET request per probed service
+/// - Emits a `health-probe-result` event with the outcome of each probe as it
+///   arrives, and `operation:progress` events
+/// - With `apply`, writes the probed service files, records the probe as a single
+///   entry in the undo journal, appends one audit log record per probed service
+///   and commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: see what the endpoints say, then apply it
+/// const outcomes = await invoke('probe_service_health', { environment: 'prod' });
+/// outcomes.filter(o => o.changed).forEach(o => console.log(o.serviceId, o.status));
+/// await invoke('probe_service_health', { environment: 'prod', apply: true });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn probe_service_health<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    service_ids: Option<Vec<String>>,
+    apply: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<Vec<ProbeOutcome>, AppError> {
+    storage::validate_environment_name(&environment)?;
+
+    run_blocking(app.clone(), move |state| {
+        let mut progress = Progress::start(&app, state, operation_id, "probe_service_health");
+        let result = probe_services(
+            &app,
+            state,
+            &environment,
+            service_ids,
+            apply.unwrap_or(false),
+            &mut progress,
+        );
+        progress.finish(result)
+    })
+    .await
+}
+
+/// Probes the services and applies the results, for `probe_service_health`.
+fn probe_services<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    service_ids: Option<Vec<String>>,
+    apply: bool,
+    progress: &mut Progress,
+) -> Result<Vec<ProbeOutcome>, AppError> {
+    let settings = read_state(state).settings.health_probe.clone();
+    let services = cache::shared_services(state, environment)?;
+    let health_url = |metadata: &HashMap<String, Value>| {
+        metadata
+            .get(&settings.url_key)
+            .and_then(Value::as_str)
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+    };
+
+    // Outcomes of the requested services that can't be probed, by position
+    let mut unprobed: Vec<(usize, ProbeOutcome)> = Vec::new();
+    let mut targets = Vec::new();
+    match service_ids {
+        Some(service_ids) => {
+            for (position, id) in service_ids.into_iter().enumerate() {
+                let Some(service) = services.iter().find(|s| s.id == id) else {
+                    let error = format!("Service '{}' not found", id);
+                    unprobed.push((position, not_probed(id, None, None, error)));
+                    continue;
+                };
+                let status = Some(service.status.clone());
+                match health_url(&service.metadata) {
+                    Some(url) if probes::is_probe_url(&url) => targets.push(ProbeTarget {
+                        service_id: id,
+                        url,
+                        status: service.status.clone(),
+                    }),
+                    Some(url) => {
+                        let error = format!("'{}' isn't an http or https URL", url);
+                        unprobed.push((position, not_probed(id, Some(url), status, error)));
+                    }
+                    None => {
+                        let error = format!("The service has no '{}' metadata", settings.url_key);
+                        unprobed.push((position, not_probed(id, None, status, error)));
+                    }
+                }
+            }
+        }
+        None => {
+            for service in services.iter() {
+                if let Some(url) = health_url(&service.metadata).filter(|u| probes::is_probe_url(u))
+                {
+                    targets.push(ProbeTarget {
+                        service_id: service.id.clone(),
+                        url,
+                        status: service.status.clone(),
+                    });
+                }
+            }
+            targets.sort_by(|a, b| a.service_id.cmp(&b.service_id));
+        }
+    }
+
+    let results = probe_all(app, &targets, &settings, progress)?;
+    if apply {
+        apply_results(app, state, environment, &targets, &results, progress)?;
+    }
+
+    let mut outcomes: Vec<ProbeOutcome> = targets
+        .iter()
+        .zip(&results)
+        .map(|(target, result)| outcome(target, result))
+        .collect();
+    for (position, outcome) in unprobed {
+        outcomes.insert(position, outcome);
+    }
+
+    Ok(outcomes)
+}
+
+/// Probes every target, at most `settings.concurrency` at a time.
+///
+/// Returns the results in the order of `targets`.
+fn probe_all<R: Runtime>(
+    app: &AppHandle<R>,
+    targets: &[ProbeTarget],
+    settings: &HealthProbeSettings,
+    progress: &mut Progress,
+) -> Result<Vec<ProbeResult>, AppError> {
+    progress.phase("Probing services", targets.len());
+    let timeout = Duration::from_millis(settings.timeout_ms);
+    let workers = settings.concurrency.clamp(1, targets.len().max(1));
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let mut results: Vec<Option<ProbeResult>> = (0..targets.len()).map(|_| None).collect();
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let (next, stop) = (&next, &stop);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(target) = targets.get(index) else {
+                        break;
+                    };
+                    let result = probes::probe(&target.url, timeout);
+                    if sender.send((index, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for (index, result) in receiver {
+            if let Err(e) = app.emit(HEALTH_PROBE_EVENT, outcome(&targets[index], &result)) {
+                eprintln!(
+                    "Warning: failed to emit {} event: {}",
+                    HEALTH_PROBE_EVENT, e
+                );
+            }
+            results[index] = Some(result);
+            progress.advance();
+            // Requests already sent finish, but no new ones start
+            if progress.check_cancelled().is_err() {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+    });
+
+    progress.check_cancelled()?;
+    Ok(results.into_iter().flatten().collect())
+}
+
+/// Saves the statuses and probe metadata of the probed services.
+fn apply_results<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    targets: &[ProbeTarget],
+    results: &[ProbeResult],
+    progress: &mut Progress,
+) -> Result<(), AppError> {
+    let mut state = write_state(state);
+    let mut services: HashMap<String, _> = state
+        .storage
+        .load_services(environment)?
+        .into_iter()
+        .map(|s| (s.id.clone(), s))
+        .collect();
+    let probed_at = now_millis();
+
+    let mut operations = Vec::new();
+    for (target, result) in targets.iter().zip(results) {
+        // Deleted while it was probed
+        let Some(mut service) = services.remove(&target.service_id) else {
+            continue;
+        };
+        if service.status != ServiceStatus::Deprecated {
+            service.status = result.status.clone();
+        }
+        service.metadata.insert(
+            HEALTH_PROBE_METADATA_KEY.to_string(),
+            json!({
+                "probedAt": probed_at,
+                "httpStatus": result.http_status,
+                "latencyMs": result.latency_ms,
+            }),
+        );
+        operations.push(Operation::SaveService {
+            service,
+            force: false,
+        });
+    }
+
+    let plan = Plan::compute_with_progress(&state, environment, operations, progress)?;
+    let description = format!("Probed the health of {} services", targets.len());
+    plan.apply_with_progress(
+        app,
+        &mut state,
+        environment,
+        "probe_service_health",
+        description,
+        progress,
+    )?;
+
+    Ok(())
+}
+
+/// Builds the outcome of a requested service that wasn't probed.
+fn not_probed(
+    service_id: String,
+    url: Option<String>,
+    previous_status: Option<ServiceStatus>,
+    error: String,
+) -> ProbeOutcome {
+    ProbeOutcome {
+        service_id,
+        url,
+        previous_status,
+        status: None,
+        http_status: None,
+        latency_ms: None,
+        error: Some(error),
+        changed: false,
+    }
+}
+
+/// Builds the outcome of a probe.
+fn outcome(target: &ProbeTarget, result: &ProbeResult) -> ProbeOutcome {
+    ProbeOutcome {
+        service_id: target.service_id.clone(),
+        url: Some(target.url.clone()),
+        previous_status: Some(target.status.clone()),
+        status: Some(result.status.clone()),
+        http_status: result.http_status,
+        latency_ms: Some(result.latency_ms),
+        error: result.error.clone(),
+        changed: target.status != result.status && target.status != ServiceStatus::Deprecated,
+    }
+}
@@ -0,0 +1,385 @@
+//! Reconciliation of declared and actual dependencies.
+//!
+//! Service templates record the dependencies a team intends in the
+//! `declaredDependencies` metadata key (a list of service IDs or names).
+//! These commands compare that list with the service's outgoing depends_on
+//! relationships and create the missing ones on request.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime};
+
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::error::AppError;
+use crate::models::{Relationship, RelationshipOrigin, RelationshipType, ReviewStatus, Service};
+use crate::state::{cache, read_state, run_blocking, write_state, AppState};
+
+/// Metadata key holding a service's declared dependencies.
+pub const DECLARED_DEPENDENCIES_KEY: &str = "declaredDependencies";
+
+/// A declared dependency without a matching depends_on relationship.
+///
+/// # Fields
+///
+/// * `source_id` - The service declaring the dependency
+/// * `target_id` - The service it declares, resolved to an ID
+/// * `declared_as` - The entry as written in the metadata (an ID or a name)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingDependency {
+    pub source_id: String,
+    pub target_id: String,
+    #[serde(default)]
+    pub declared_as: String,
+}
+
+/// A depends_on relationship its source service doesn't declare.
+///
+/// # Fields
+///
+/// * `relationship_id` - ID of the relationship
+/// * `source_id` - The depending service
+/// * `target_id` - The undeclared dependency
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndeclaredDependency {
+    pub relationship_id: String,
+    pub source_id: String,
+    pub target_id: String,
+}
+
+/// A declared dependency that doesn't resolve to exactly one service.
+///
+/// # Fields
+///
+/// * `source_id` - The service declaring the dependency
+/// * `declared_as` - The entry as written in the metadata
+/// * `reason` - Why it couldn't be resolved
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnresolvedDependency {
+    pub source_id: String,
+    pub declared_as: String,
+    pub reason: String,
+}
+
+/// The result of comparing declared with actual dependencies.
+///
+/// # Fields
+///
+/// * `services_checked` - Number of services with declared dependencies
+/// * `missing` - Declared dependencies without a relationship; pass the accepted
+///   ones to `apply_reconciliation` to create them
+/// * `undeclared` - Relationships of checked services that aren't declared (informational)
+/// * `unresolved` - Declared entries that match no service or several
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciliationReport {
+    pub services_checked: usize,
+    pub missing: Vec<MissingDependency>,
+    pub undeclared: Vec<UndeclaredDependency>,
+    pub unresolved: Vec<UnresolvedDependency>,
+}
+
+/// Compares each service's declared dependencies with its actual ones.
+///
+/// Only services with a `declaredDependencies` metadata key are checked;
+/// their outgoing depends_on relationships are the actual dependencies.
+/// Each declared entry is matched against service IDs first and then,
+/// case-insensitively, against service names. Entries matching no service,
+/// matching several by name, or a key that isn't a list of strings are
+/// reported as unresolved.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to load on the blocking thread pool
+/// * `environment` - The name of the environment to reconcile
+///
+/// # Returns
+///
+/// * `Ok(ReconciliationReport)` - The differences, sorted by source service
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('reconcile_declared_dependencies', { environment: 'prod' });
+/// for (const m of report.missing) {
+///     console.log(`${m.sourceId} declares ${m.targetId} but doesn't depend on it`);
+/// }
+/// ```
+#[tauri::command]
+pub async fn reconcile_declared_dependencies<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+) -> Result<ReconciliationReport, AppError> {
+    run_blocking(app, move |state| {
+        let mut services = cache::services(state, &environment)?;
+        let relationships = cache::shared_relationships(state, &environment)?;
+        services.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(reconcile(&services, &relationships))
+    })
+    .await
+}
+
+/// Builds the reconciliation report for sorted services.
+fn reconcile(services: &[Service], relationships: &[Relationship]) -> ReconciliationReport {
+    let ids: HashSet<&str> = services.iter().map(|s| s.id.as_str()).collect();
+    let mut ids_by_name: HashMap<String, Vec<&str>> = HashMap::new();
+    for service in services {
+        ids_by_name
+            .entry(service.name.to_lowercase())
+            .or_default()
+            .push(&service.id);
+    }
+
+    let mut actual: HashMap<&str, Vec<&Relationship>> = HashMap::new();
+    for relationship in relationships {
+        if relationship.relationship_type == RelationshipType::DependsOn {
+            actual
+                .entry(&relationship.source)
+                .or_default()
+                .push(relationship);
+        }
+    }
+
+    let mut report = ReconciliationReport::default();
+
+    for service in services {
+        let Some(value) = service.metadata.get(DECLARED_DEPENDENCIES_KEY) else {
+            continue;
+        };
+        report.services_checked += 1;
+
+        let Some(entries) = value.as_array().and_then(|entries| {
+            entries
+                .iter()
+                .map(|entry| entry.as_str())
+                .collect::<Option<Vec<&str>>>()
+        }) else {
+            report.unresolved.push(UnresolvedDependency {
+                source_id: service.id.clone(),
+                declared_as: value.to_string(),
+                reason: format!(
+                    "{} is not a list of service IDs or names",
+                    DECLARED_DEPENDENCIES_KEY
+                ),
+            });
+            continue;
+        };
+
+        // Resolve the declared entries to service IDs
+        let mut declared: BTreeSet<&str> = BTreeSet::new();
+        for entry in entries {
+            let target = if ids.contains(entry) {
+                entry
+            } else {
+                match ids_by_name.get(&entry.to_lowercase()).map(Vec::as_slice) {
+                    Some([id]) => *id,
+                    Some(matches) => {
+                        report.unresolved.push(UnresolvedDependency {
+                            source_id: service.id.clone(),
+                            declared_as: entry.to_string(),
+                            reason: format!(
+                                "Several services have this name: {}",
+                                matches.join(", ")
+                            ),
+                        });
+                        continue;
+                    }
+                    None => {
+                        report.unresolved.push(UnresolvedDependency {
+                            source_id: service.id.clone(),
+                            declared_as: entry.to_string(),
+                            reason: "No service has this ID or name".to_string(),
+                        });
+                        continue;
+                    }
+                }
+            };
+
+            if target != service.id
+                && declared.insert(target)
+                && !depends_on(&actual, service, target)
+            {
+                report.missing.push(MissingDependency {
+                    source_id: service.id.clone(),
+                    target_id: target.to_string(),
+                    declared_as: entry.to_string(),
+                });
+            }
+        }
+
+        for relationship in actual.get(service.id.as_str()).into_iter().flatten() {
+            if !declared.contains(relationship.target.as_str()) {
+                report.undeclared.push(UndeclaredDependency {
+                    relationship_id: relationship.id.clone(),
+                    source_id: relationship.source.clone(),
+                    target_id: relationship.target.clone(),
+                });
+            }
+        }
+    }
+
+    report
+}
+
+/// Returns true if the service has a depends_on relationship to the target.
+fn depends_on(actual: &HashMap<&str, Vec<&Relationship>>, service: &Service, target: &str) -> bool {
+    actual
+        .get(service.id.as_str())
+        .is_some_and(|relationships| relationships.iter().any(|r| r.target == target))
+}
+
+/// Creates the accepted missing dependencies as depends_on relationships.
+///
+/// All relationships are created as one batch (see `execute_batch`), so
+/// either all of them are or none, and a single undo removes them. New
+/// relationships get the ID `rel-{source}-to-{target}`, numbered if taken.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment
+/// * `accepted` - Missing dependencies from `reconcile_declared_dependencies` to create
+/// * `dry_run` - If true, only compute the relationships that would be created (default: false)
+///
+/// # Returns
+///
+/// * `Ok(BatchResult)` - The created relationships in `savedRelationships`
+/// * `Err(AppError::ValidationError)` - If a source or target service doesn't exist
+/// * `Err(AppError::BatchOperationFailed)` - If a relationship can't be created (e.g.,
+///   it already exists); nothing was written
+/// * `Err(AppError::Io)` - If writing the relationships failed
+///
+/// # Side Effects
+///
+/// Unless `dry_run` is set:
+/// - Writes `relationships.json`
+/// - Records the creation as a single entry in the undo journal
+/// - Appends one audit log record per created relationship
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: create everything the report found missing
+/// const report = await invoke('reconcile_declared_dependencies', { environment: 'prod' });
+/// await invoke('apply_reconciliation', { environment: 'prod', accepted: report.missing });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn apply_reconciliation<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    accepted: Vec<MissingDependency>,
+    dry_run: Option<bool>,
+) -> Result<BatchResult, AppError> {
+    run_blocking(app.clone(), move |state| {
+        create_dependencies(
+            &app,
+            state,
+            &environment,
+            &accepted,
+            dry_run.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+/// Creates the accepted missing dependencies, for `apply_reconciliation`.
+fn create_dependencies<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    accepted: &[MissingDependency],
+    dry_run: bool,
+) -> Result<BatchResult, AppError> {
+    if dry_run {
+        let state = read_state(state);
+        let operations = reconciliation_operations(&state, environment, accepted)?;
+        let mut plan = Plan::compute(&state, environment, operations)?;
+        plan.result.dry_run = true;
+        return Ok(plan.result);
+    }
+
+    let mut state = write_state(state);
+    let operations = reconciliation_operations(&state, environment, accepted)?;
+    let plan = Plan::compute(&state, environment, operations)?;
+    let description = format!(
+        "Created {} declared dependencies",
+        plan.result.saved_relationships.len()
+    );
+
+    plan.apply(
+        app,
+        &mut state,
+        environment,
+        "apply_reconciliation",
+        description,
+    )
+}
+
+/// Builds the relationship saves for the accepted missing dependencies.
+fn reconciliation_operations(
+    state: &AppState,
+    environment: &str,
+    accepted: &[MissingDependency],
+) -> Result<Vec<Operation>, AppError> {
+    let mut taken_ids: HashSet<String> = state
+        .storage
+        .load_relationships(environment)?
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut operations = Vec::new();
+    for dependency in accepted {
+        if !seen.insert((&dependency.source_id, &dependency.target_id)) {
+            continue;
+        }
+        for id in [&dependency.source_id, &dependency.target_id] {
+            if !state.storage.service_exists(environment, id)? {
+                return Err(AppError::ValidationError(format!(
+                    "Service '{}' does not exist in environment '{}'",
+                    id, environment
+                )));
+            }
+        }
+
+        let base_id = format!("rel-{}-to-{}", dependency.source_id, dependency.target_id);
+        let mut id = base_id.clone();
+        let mut suffix = 2;
+        while taken_ids.contains(&id) {
+            id = format!("{}-{}", base_id, suffix);
+            suffix += 1;
+        }
+        taken_ids.insert(id.clone());
+
+        operations.push(Operation::SaveRelationship {
+            relationship: Relationship {
+                id,
+                source: dependency.source_id.clone(),
+                target: dependency.target_id.clone(),
+                relationship_type: RelationshipType::DependsOn,
+                description: Some(format!("Declared in {}", DECLARED_DEPENDENCIES_KEY)),
+                metadata: None,
+                effective_from: None,
+                effective_until: None,
+                origin: RelationshipOrigin::Manual,
+                review_status: ReviewStatus::Unreviewed,
+                review_note: None,
+                reviewed_by: None,
+                reviewed_at: None,
+                revision: 0,
+            },
+            force: false,
+        });
+    }
+
+    Ok(operations)
+}
@@ -4,13 +4,26 @@
 //! between services within different environments. Relationships define how services
 //! connect to and depend on each other.
 
-use std::sync::Mutex;
-use tauri::State;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
 
+use crate::commands::batch::{BatchResult, Operation, Plan};
+use crate::dates;
+use crate::descriptions;
 use crate::error::AppError;
-use crate::models::Relationship;
-use crate::state::AppState;
-use crate::storage::loader;
+use crate::git;
+use crate::models::{
+    Relationship, RelationshipOrigin, RelationshipType, ReviewStatus, WebhookEvent,
+};
+use crate::state::diagnostics::CommandTimer;
+use crate::state::journal::{Change, JournalEntry};
+use crate::state::relationship_index::RelationshipIndex;
+use crate::state::{cache, read_state, run_blocking, write_state, AppState};
+use crate::storage;
+use crate::storage::audit::{now_millis, AuditRecord};
+use crate::webhooks;
 
 /// Retrieves all relationships for a specified environment.
 ///
@@ -18,15 +31,28 @@ use crate::storage::loader;
 /// If not cached, it loads the relationships from disk and populates the cache for
 /// future requests.
 ///
+/// Only the relationships in effect on `as_of` (default: today) are
+/// returned, unless `ignore_effective_dates` is set (see
+/// `Relationship::is_effective_on`). With `origin`, only the relationships
+/// with that origin are returned, e.g. only the hand-curated ones, and with
+/// `review_status` only those in that state of the architecture review.
+///
 /// # Arguments
 ///
-/// * `state` - The application state containing the cache and data path
+/// * `app` - The application handle, used to load on the blocking thread pool
 /// * `environment` - The name of the environment to retrieve relationships from
+/// * `as_of` - Optional `YYYY-MM-DD` date the relationships must be in effect on
+/// * `ignore_effective_dates` - If true, return every relationship whatever its
+///   effective dates (default: false)
+/// * `origin` - Optional origin the relationships must have, `"manual"` or
+///   `{ imported: source }`
+/// * `review_status` - Optional review status the relationships must have,
+///   `"unreviewed"`, `"approved"` or `"flagged"`
 ///
 /// # Returns
 ///
 /// * `Ok(Vec<Relationship>)` - A vector containing all relationships in the environment
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
+/// * `Err(AppError::ValidationError)` - If `as_of` isn't a `YYYY-MM-DD` date
 /// * `Err(AppError::Io)` - If there's an error reading from the filesystem
 ///
 /// # Examples
@@ -34,28 +60,50 @@ use crate::storage::loader;
 /// ```typescript
 /// // From the frontend:
 /// const relationships = await invoke('get_all_relationships', { environment: 'dev' });
+///
+/// // The relationships after the migration:
+/// const after = await invoke('get_all_relationships', {
+///     environment: 'prod',
+///     asOf: '2025-07-01'
+/// });
+///
+/// // The edges the Kubernetes import created:
+/// const imported = await invoke('get_all_relationships', {
+///     environment: 'prod',
+///     origin: { imported: 'kubernetes' }
+/// });
+///
+/// // The dependencies the architecture board questioned:
+/// const flagged = await invoke('get_all_relationships', {
+///     environment: 'prod',
+///     reviewStatus: 'flagged'
+/// });
 /// ```
-#[tauri::command]
-pub fn get_all_relationships(
-    state: State<'_, Mutex<AppState>>,
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_all_relationships<R: Runtime>(
+    app: AppHandle<R>,
     environment: String,
+    as_of: Option<String>,
+    ignore_effective_dates: Option<bool>,
+    origin: Option<RelationshipOrigin>,
+    review_status: Option<ReviewStatus>,
 ) -> Result<Vec<Relationship>, AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
-
-    // Check cache first
-    if let Some(relationships) = state.relationships_cache.get(&environment) {
-        return Ok(relationships.clone());
-    }
-
-    // Load from disk
-    let relationships = loader::load_relationships(&state.data_path, &environment)?;
-
-    // Update cache
-    state
-        .relationships_cache
-        .insert(environment, relationships.clone());
-
-    Ok(relationships)
+    let as_of = dates::resolve_as_of(as_of, ignore_effective_dates)?;
+    run_blocking(app, move |state| {
+        let _timer = CommandTimer::start(state, "get_all_relationships");
+        let mut relationships = cache::relationships(state, &environment)?;
+        if let Some(as_of) = as_of {
+            relationships.retain(|r| r.is_effective_on(&as_of));
+        }
+        if let Some(origin) = origin {
+            relationships.retain(|r| r.origin == origin);
+        }
+        if let Some(review_status) = review_status {
+            relationships.retain(|r| r.review_status == review_status);
+        }
+        Ok(relationships)
+    })
+    .await
 }
 
 /// Retrieves all relationships involving a specific service.
@@ -63,6 +111,8 @@ pub fn get_all_relationships(
 /// Returns relationships where the specified service is either the source
 /// (depends on other services) or the target (other services depend on it).
 /// This is useful for displaying a service's dependency graph in the UI.
+/// The relationships are looked up in the cache's per-service index, so
+/// only the service's own relationships are visited and cloned.
 ///
 /// # Arguments
 ///
@@ -73,7 +123,6 @@ pub fn get_all_relationships(
 /// # Returns
 ///
 /// * `Ok(Vec<Relationship>)` - A vector of relationships involving the service (may be empty)
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 /// * `Err(AppError::Io)` - If there's an error reading from the filesystem
 ///
 /// # Examples
@@ -88,31 +137,106 @@ pub fn get_all_relationships(
 /// ```
 #[tauri::command(rename_all = "camelCase")]
 pub fn get_relationships_for_service(
-    state: State<'_, Mutex<AppState>>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
     service_id: String,
 ) -> Result<Vec<Relationship>, AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
+    let _timer = CommandTimer::start(&state, "get_relationships_for_service");
+    cache::relationships_for_service(&state, &environment, &service_id)
+}
 
-    // Check cache first
-    let relationships = if let Some(cached) = state.relationships_cache.get(&environment) {
-        cached.clone()
-    } else {
-        // Load from disk
-        let loaded = loader::load_relationships(&state.data_path, &environment)?;
-        // Update cache
-        state
-            .relationships_cache
-            .insert(environment, loaded.clone());
-        loaded
-    };
+/// Relationships a service is the target and the source of.
+///
+/// # Fields
+///
+/// * `incoming` - Relationships targeting the service
+/// * `outgoing` - Relationships from the service
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EdgeCounts {
+    pub incoming: usize,
+    pub outgoing: usize,
+}
 
-    let filtered: Vec<Relationship> = relationships
-        .into_iter()
-        .filter(|r| r.source == service_id || r.target == service_id)
-        .collect();
+/// Relationship counts of a service, for the badges of list rows.
+///
+/// # Fields
+///
+/// * `incoming` - Relationships targeting the service
+/// * `outgoing` - Relationships from the service
+/// * `by_type` - The same counts per relationship type, keyed by the type's
+///   snake_case name; types the service has no relationships of are left out
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelationshipCounts {
+    pub incoming: usize,
+    pub outgoing: usize,
+    pub by_type: BTreeMap<String, EdgeCounts>,
+}
 
-    Ok(filtered)
+/// Counts the incoming and outgoing relationships of every service.
+///
+/// All cached relationships are counted in one pass, whatever their
+/// effective dates, including those whose other end doesn't exist: they
+/// hint at orphans. A relationship from a service to itself counts as both.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to count on the blocking thread pool
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(HashMap<String, RelationshipCounts>)` - Counts by service ID; services
+///   without relationships are left out, and IDs of missing services that
+///   relationships point at are included
+/// * `Err(AppError::Io)` - If there's an error reading from the filesystem
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const counts = await invoke('get_relationship_counts', { environment: 'prod' });
+/// // { 'api-gateway': { incoming: 12, outgoing: 7,
+/// //     byType: { depends_on: { incoming: 10, outgoing: 5 }, ... } }, ... }
+/// ```
+#[tauri::command]
+pub async fn get_relationship_counts<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+) -> Result<HashMap<String, RelationshipCounts>, AppError> {
+    run_blocking(app, move |state| {
+        let _timer = CommandTimer::start(state, "get_relationship_counts");
+        let relationships = cache::shared_relationships(state, &environment)?;
+        Ok(count_relationships(&relationships))
+    })
+    .await
+}
+
+/// Counts the relationships of every service in one pass, for
+/// `get_relationship_counts` and `get_service_summaries`.
+pub fn count_relationships(relationships: &[Relationship]) -> HashMap<String, RelationshipCounts> {
+    let mut counts: HashMap<String, RelationshipCounts> = HashMap::new();
+    for relationship in relationships {
+        let relationship_type = relationship.relationship_type.as_str();
+
+        let source = counts.entry(relationship.source.clone()).or_default();
+        source.outgoing += 1;
+        source
+            .by_type
+            .entry(relationship_type.to_string())
+            .or_default()
+            .outgoing += 1;
+
+        let target = counts.entry(relationship.target.clone()).or_default();
+        target.incoming += 1;
+        target
+            .by_type
+            .entry(relationship_type.to_string())
+            .or_default()
+            .incoming += 1;
+    }
+    counts
 }
 
 /// Saves a relationship to the specified environment (create or update).
@@ -124,14 +248,18 @@ pub fn get_relationships_for_service(
 ///
 /// # Arguments
 ///
+/// * `app` - The application handle, used to queue git auto-commits
 /// * `state` - The application state containing the cache and data path
 /// * `environment` - The name of the environment to save the relationship to
 /// * `relationship` - The complete relationship object to save
+/// * `force` - Save even if the revision doesn't match (default false)
 ///
 /// # Returns
 ///
-/// * `Ok(())` - If the relationship was successfully saved
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
+/// * `Ok(Relationship)` - The saved relationship with its new revision
+/// * `Err(AppError::ValidationError)` - If the source or target is not a valid service ID,
+///   or the effective dates aren't valid
+/// * `Err(AppError::Conflict)` - If the relationship was changed since `relationship.revision`
 /// * `Err(AppError::DuplicateRelationship)` - If a relationship with the same source,
 ///   target, and type already exists (for new relationships only)
 /// * `Err(AppError::Io)` - If there's an error writing to the filesystem
@@ -139,42 +267,79 @@ pub fn get_relationships_for_service(
 /// # Side Effects
 ///
 /// - Updates the relationships JSON file at `{data_path}/{environment}/relationships.json`
-/// - Invalidates the relationships cache to ensure consistency
+/// - Stores the updated relationships in the relationships cache
+/// - Records the change in the environment's undo journal
+/// - Appends the change to the environment's audit log
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
 ///
 /// # Validation
 ///
 /// - Prevents duplicate relationships (same source + target + type)
+/// - Rejects source/target IDs that aren't valid service IDs (e.g., path traversal)
+/// - Rejects effective dates that aren't `YYYY-MM-DD` dates, or an
+///   `effectiveUntil` before the `effectiveFrom`
+/// - Keeps the `origin` as sent; a relationship saved without one is manual
+/// - Gives a relationship without a description a generated one when the
+///   `relationshipDescriptions.autoGenerate` setting is on (see `descriptions`);
+///   a generated description that was edited by hand is kept as a written one
 /// - Does NOT validate that source and target services exist
+/// - Rejects saves based on an outdated `revision` (the stored one, or 0 for a
+///   new relationship) unless `force` is set; the saved relationship gets the
+///   next revision
 ///
 /// # Examples
 ///
 /// ```typescript
 /// // From the frontend:
-/// await invoke('save_relationship', {
+/// const saved = await invoke('save_relationship', {
 ///     environment: 'dev',
 ///     relationship: {
 ///         id: 'rel-123',
 ///         source: 'api-gateway',
 ///         target: 'user-service',
 ///         relationshipType: 'depends_on',
-///         description: 'API Gateway routes to User Service'
+///         description: 'API Gateway routes to User Service',
+///         revision: 0
 ///     }
 /// });
 /// ```
 #[tauri::command]
-pub fn save_relationship(
-    state: State<'_, Mutex<AppState>>,
+pub fn save_relationship<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
-    relationship: Relationship,
-) -> Result<(), AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
+    mut relationship: Relationship,
+    force: Option<bool>,
+) -> Result<Relationship, AppError> {
+    let _timer = CommandTimer::start(&state, "save_relationship");
+    storage::validate_service_id(&relationship.source)?;
+    storage::validate_service_id(&relationship.target)?;
+    check_effective_dates(&relationship)?;
 
-    let mut relationships = loader::load_relationships(&state.data_path, &environment)?;
+    let relationship_id = relationship.id.clone();
+    let mut state = write_state(&state);
+
+    // Taken before the write, while it can still be compared with storage
+    let previous_index = cache::fresh_relationship_index(&state, &environment);
+    let mut relationships = state.storage.load_relationships(&environment)?;
+    let in_stored_order = is_in_stored_order(&relationships);
 
     // Check if relationship already exists (by ID)
-    if let Some(idx) = relationships.iter().position(|r| r.id == relationship.id) {
+    let existing = relationships.iter().position(|r| r.id == relationship.id);
+
+    let stored_revision = existing.map_or(0, |idx| relationships[idx].revision);
+    if !force.unwrap_or(false) {
+        AppError::check_revision(&relationship_id, relationship.revision, stored_revision)?;
+    }
+    let stored = existing.map(|idx| &relationships[idx]);
+    describe(&state, &environment, &mut relationship, stored)?;
+    relationship.revision = stored_revision + 1;
+    let after = relationship.clone();
+
+    let (index, before) = if let Some(idx) = existing {
         // Update existing
-        relationships[idx] = relationship;
+        let before = std::mem::replace(&mut relationships[idx], relationship);
+        (idx, Some(before))
     } else {
         // Check for duplicate source/target/type combination
         let duplicate = relationships.iter().any(|r| {
@@ -193,16 +358,663 @@ pub fn save_relationship(
 
         // Add new
         relationships.push(relationship);
-    }
+        (relationships.len() - 1, None)
+    };
+
+    // Sorted like storage keeps them, so the cached copy matches the next load
+    relationships.sort_by(storage::relationship_order);
+    let position = relationships
+        .binary_search_by(|r| storage::relationship_order(r, &after))
+        .unwrap_or_else(|slot| slot);
+
+    state
+        .storage
+        .save_relationships(&environment, &relationships)?;
+    state.record_write(&environment);
+    let sources = before.iter().chain([&after]).map(|r| r.source.as_str());
+    let changed_paths = storage::relationship_file_paths(&state.data_path, &environment, sources);
+    let relationship_index = RelationshipIndex::after_change(
+        previous_index.as_deref().filter(|_| in_stored_order),
+        &relationships,
+        before.as_ref().map(|before| (index, before)),
+        Some((position, &after)),
+    );
+
+    state.record_audit(
+        &environment,
+        &[AuditRecord::relationship(
+            "save_relationship",
+            before.as_ref(),
+            Some(&after),
+        )],
+    );
+
+    let description = match before {
+        Some(_) => format!("Updated relationship {}", after.label()),
+        None => format!("Created relationship {}", after.label()),
+    };
+    state.record_undo(
+        &environment,
+        JournalEntry::new(
+            description,
+            vec![Change::Relationship {
+                id: relationship_id.clone(),
+                index: position,
+                before,
+                after: Some(after.clone()),
+            }],
+        ),
+    );
 
-    loader::save_relationships(&state.data_path, &environment, &relationships)?;
+    let summary = format!("Update relationship {} in {}", relationship_id, environment);
+    git::auto_commit(
+        &app,
+        &state,
+        "save_relationship",
+        summary.clone(),
+        changed_paths,
+    );
+    webhooks::notify(
+        &app,
+        &state,
+        &environment,
+        WebhookEvent::DataChanged,
+        summary,
+    );
 
-    // Invalidate cache to ensure consistency
-    state.relationships_cache.remove(&environment);
+    // Keep the cache warm with the vector that was just written
+    state.cache_indexed_relationships(&environment, relationships, relationship_index);
+
+    Ok(after)
+}
+
+/// Returns true if relationships are in the order storage keeps them.
+///
+/// Only then does the cached index, built from the same order, still fit
+/// them. Files that were edited by hand may be in any order.
+fn is_in_stored_order(relationships: &[Relationship]) -> bool {
+    relationships.is_sorted_by(|a, b| storage::relationship_order(a, b).is_le())
+}
+
+/// Rejects effective dates that aren't `YYYY-MM-DD` dates or that end
+/// before they start, for `save_relationship` and batches.
+pub fn check_effective_dates(relationship: &Relationship) -> Result<(), AppError> {
+    for date in [&relationship.effective_from, &relationship.effective_until]
+        .into_iter()
+        .flatten()
+    {
+        if !dates::is_valid_date(date) {
+            return Err(AppError::ValidationError(format!(
+                "Effective date '{}' of relationship '{}' must be a date like 2025-09-30",
+                date, relationship.id
+            )));
+        }
+    }
+
+    if let (Some(from), Some(until)) = (&relationship.effective_from, &relationship.effective_until)
+    {
+        if from > until {
+            return Err(AppError::ValidationError(format!(
+                "Relationship '{}' cannot end ({}) before it starts ({})",
+                relationship.id, until, from
+            )));
+        }
+    }
 
     Ok(())
 }
 
+/// Applies the `relationshipDescriptions` settings to a relationship being
+/// saved, for `save_relationship` (see `descriptions::describe`).
+fn describe(
+    state: &AppState,
+    environment: &str,
+    relationship: &mut Relationship,
+    stored: Option<&Relationship>,
+) -> Result<(), AppError> {
+    let settings = &state.settings.relationship_descriptions;
+    descriptions::describe(settings, relationship, stored, |service_id| {
+        match state.storage.load_service(environment, service_id) {
+            Ok(service) => Ok(service.name),
+            Err(AppError::ServiceNotFound(_)) => Ok(service_id.to_string()),
+            Err(e) => Err(e),
+        }
+    })
+}
+
+/// Generates the descriptions of the relationships of an environment.
+///
+/// Relationships without a description get one rendered from the template
+/// of the `relationshipDescriptions` setting (see `descriptions`), whether
+/// or not `autoGenerate` is on, and generated descriptions are rendered
+/// again, e.g. after a service was renamed. Descriptions people wrote are
+/// only replaced with `overwrite_existing`, after which they count as
+/// generated too. Relationships whose description wouldn't change are left
+/// alone. All changes are saved as one batch (see `execute_batch`).
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment
+/// * `overwrite_existing` - If true, also replace written descriptions (default: false)
+/// * `dry_run` - If true, only compute the changes (default: false)
+///
+/// # Returns
+///
+/// * `Ok(BatchResult)` - The relationships with new descriptions
+/// * `Err(AppError::Io)` - If there's an error reading or writing the data
+///
+/// # Side Effects
+///
+/// Unless `dry_run` is set and if any description changed:
+/// - Updates the relationships JSON file once
+/// - Records the backfill as a single entry in the undo journal
+/// - Appends one audit log record per changed relationship
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: preview the descriptions missing in prod
+/// const preview = await invoke('backfill_relationship_descriptions', {
+///     environment: 'prod',
+///     dryRun: true
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn backfill_relationship_descriptions<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    overwrite_existing: Option<bool>,
+    dry_run: Option<bool>,
+) -> Result<BatchResult, AppError> {
+    run_blocking(app.clone(), move |state| {
+        backfill_descriptions(
+            &app,
+            state,
+            &environment,
+            overwrite_existing.unwrap_or(false),
+            dry_run.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+/// Generates the descriptions, for `backfill_relationship_descriptions`.
+fn backfill_descriptions<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &RwLock<AppState>,
+    environment: &str,
+    overwrite_existing: bool,
+    dry_run: bool,
+) -> Result<BatchResult, AppError> {
+    if dry_run {
+        let state = read_state(state);
+        let operations = backfill_operations(&state, environment, overwrite_existing)?;
+        let mut plan = Plan::compute(&state, environment, operations)?;
+        plan.result.dry_run = true;
+        return Ok(plan.result);
+    }
+
+    let mut state = write_state(state);
+    let operations = backfill_operations(&state, environment, overwrite_existing)?;
+    let plan = Plan::compute(&state, environment, operations)?;
+    let description = format!(
+        "Generated the descriptions of {} relationships",
+        plan.result.saved_relationships.len()
+    );
+
+    plan.apply(
+        app,
+        &mut state,
+        environment,
+        "backfill_relationship_descriptions",
+        description,
+    )
+}
+
+/// Builds the relationship saves that set the generated descriptions.
+fn backfill_operations(
+    state: &AppState,
+    environment: &str,
+    overwrite_existing: bool,
+) -> Result<Vec<Operation>, AppError> {
+    let template = &state.settings.relationship_descriptions.template;
+    let services = state.storage.load_services(environment)?;
+    let names: HashMap<&str, &str> = services
+        .iter()
+        .map(|s| (s.id.as_str(), s.name.as_str()))
+        .collect();
+
+    let mut operations = Vec::new();
+    for mut relationship in state.storage.load_relationships(environment)? {
+        let generated = descriptions::is_generated(&relationship);
+        if descriptions::has_description(&relationship) && !generated && !overwrite_existing {
+            continue;
+        }
+        let source_name = names.get(relationship.source.as_str()).copied();
+        let target_name = names.get(relationship.target.as_str()).copied();
+        let description = descriptions::render(
+            template,
+            &relationship,
+            source_name.unwrap_or(&relationship.source),
+            target_name.unwrap_or(&relationship.target),
+        );
+        if description.is_empty()
+            || generated && relationship.description.as_deref() == Some(description.as_str())
+        {
+            continue;
+        }
+        descriptions::set_generated(&mut relationship, description);
+        operations.push(Operation::SaveRelationship {
+            relationship,
+            force: false,
+        });
+    }
+
+    Ok(operations)
+}
+
+/// Records the architecture review of a relationship.
+///
+/// Only the review fields change, so the relationship doesn't have to be
+/// sent again: the status, note and reviewer replace those of the previous
+/// review, and `reviewedAt` becomes now (or is cleared when the status goes
+/// back to unreviewed). The save is forced, so a review never conflicts
+/// with an edit made meanwhile.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment containing the relationship
+/// * `relationship_id` - The ID of the relationship reviewed
+/// * `status` - "unreviewed", "approved" or "flagged"
+/// * `note` - Optional note, e.g. why the dependency was flagged
+/// * `reviewer` - Optional name or email of the reviewer
+///
+/// # Returns
+///
+/// * `Ok(Relationship)` - The relationship with its review and new revision
+/// * `Err(AppError::RelationshipNotFound)` - If no relationship exists with the given ID
+/// * `Err(AppError::Io)` - If there's an error reading or writing the relationships
+///
+/// # Side Effects
+///
+/// - Updates the relationships JSON file at `{data_path}/{environment}/relationships.json`
+/// - Records the change in the environment's undo journal
+/// - Appends the change to the environment's audit log
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('set_relationship_review', {
+///     environment: 'prod',
+///     relationshipId: 'rel-checkout-to-legacy-billing',
+///     status: 'flagged',
+///     note: 'Should go through the billing API',
+///     reviewer: 'arch-board@example.com'
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_relationship_review<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    relationship_id: String,
+    status: ReviewStatus,
+    note: Option<String>,
+    reviewer: Option<String>,
+) -> Result<Relationship, AppError> {
+    let mut state = write_state(&state);
+    let Some(mut relationship) = state
+        .storage
+        .load_relationships(&environment)?
+        .into_iter()
+        .find(|r| r.id == relationship_id)
+    else {
+        return Err(AppError::RelationshipNotFound(relationship_id));
+    };
+
+    relationship.review_status = status;
+    relationship.review_note = note;
+    relationship.reviewed_by = reviewer;
+    relationship.reviewed_at = (status != ReviewStatus::Unreviewed).then(now_millis);
+
+    let operations = vec![Operation::SaveRelationship {
+        relationship,
+        force: true,
+    }];
+    let plan = Plan::compute(&state, &environment, operations)?;
+    let description = format!(
+        "Marked relationship {} {}",
+        relationship_id,
+        status.as_str()
+    );
+    let mut result = plan.apply(
+        &app,
+        &mut state,
+        &environment,
+        "set_relationship_review",
+        description,
+    )?;
+
+    result
+        .saved_relationships
+        .pop()
+        .ok_or(AppError::RelationshipNotFound(relationship_id))
+}
+
+/// What `retarget_relationships` or `resource_relationships` rewrote.
+///
+/// # Fields
+///
+/// * `relationships` - The rewritten relationships, with their new revisions
+///   (or, in a dry run, as they would be saved)
+/// * `skipped_duplicates` - Relationships left alone because the rewrite would
+///   duplicate another relationship
+/// * `dry_run` - True if nothing was written
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewriteResult {
+    pub relationships: Vec<Relationship>,
+    pub skipped_duplicates: Vec<SkippedDuplicate>,
+    pub dry_run: bool,
+}
+
+/// A relationship a rewrite left alone.
+///
+/// # Fields
+///
+/// * `relationship_id` - The relationship left alone
+/// * `duplicate_of` - The relationship with the source, target and type it would have had
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedDuplicate {
+    pub relationship_id: String,
+    pub duplicate_of: String,
+}
+
+/// Points every matching relationship to another target.
+///
+/// Meant for replacing a shared dependency, e.g. moving the services of a
+/// database migration from `postgres-9` to `postgres-14` at once. Every
+/// relationship targeting `old_target_id` is rewritten, or only those of
+/// the given types and from the given sources. A relationship whose rewrite
+/// would have the source, target and type of another relationship (one
+/// already targeting the new service, or another one rewritten) is left
+/// alone and reported. All rewrites are saved as one batch (see
+/// `execute_batch`), so the relationships are written once and the change
+/// is undone as a unit.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `old_target_id` - The service the relationships target now
+/// * `new_target_id` - The service they should target; must exist
+/// * `relationship_types` - Optional types to rewrite (default: all)
+/// * `source_ids` - Optional sources whose relationships to rewrite (default: all)
+/// * `dry_run` - If true, only compute the rewrites (default: false)
+///
+/// # Returns
+///
+/// * `Ok(RewriteResult)` - The rewritten relationships and the skipped duplicates
+/// * `Err(AppError::ServiceNotFound)` - If the new target doesn't exist
+/// * `Err(AppError::ValidationError)` - If the new target is the old one or isn't a
+///   valid service ID
+/// * `Err(AppError::Io)` - If there's an error reading or writing the relationships
+///
+/// # Side Effects
+///
+/// Unless `dry_run` is set and if any relationship was rewritten:
+/// - Updates the relationships JSON file once
+/// - Records the rewrite as a single entry in the undo journal
+/// - Appends one audit log record per rewritten relationship
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: preview the database migration
+/// const preview = await invoke('retarget_relationships', {
+///     environment: 'prod',
+///     oldTargetId: 'postgres-9',
+///     newTargetId: 'postgres-14',
+///     relationshipTypes: ['depends_on', 'reads_from', 'writes_to'],
+///     dryRun: true
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub fn retarget_relationships<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    old_target_id: String,
+    new_target_id: String,
+    relationship_types: Option<Vec<RelationshipType>>,
+    source_ids: Option<Vec<String>>,
+    dry_run: Option<bool>,
+) -> Result<RewriteResult, AppError> {
+    let rewrite = EndpointRewrite {
+        endpoint: Endpoint::Target,
+        old_id: old_target_id,
+        new_id: new_target_id,
+        relationship_types,
+        counterpart_ids: source_ids,
+    };
+    rewrite.run(&app, &state, &environment, dry_run.unwrap_or(false))
+}
+
+/// Moves every matching relationship to another source.
+///
+/// The counterpart of `retarget_relationships`, e.g. for handing the
+/// dependencies of a service over to its replacement: every relationship
+/// from `old_source_id` is rewritten, or only those of the given types and
+/// to the given targets, with duplicates skipped the same way.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `old_source_id` - The service the relationships start at now
+/// * `new_source_id` - The service they should start at; must exist
+/// * `relationship_types` - Optional types to rewrite (default: all)
+/// * `target_ids` - Optional targets whose relationships to rewrite (default: all)
+/// * `dry_run` - If true, only compute the rewrites (default: false)
+///
+/// # Returns
+///
+/// * `Ok(RewriteResult)` - The rewritten relationships and the skipped duplicates
+/// * `Err(AppError::ServiceNotFound)` - If the new source doesn't exist
+/// * `Err(AppError::ValidationError)` - If the new source is the old one or isn't a
+///   valid service ID
+/// * `Err(AppError::Io)` - If there's an error reading or writing the relationships
+///
+/// # Side Effects
+///
+/// Like `retarget_relationships`.
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const result = await invoke('resource_relationships', {
+///     environment: 'prod',
+///     oldSourceId: 'billing-v1',
+///     newSourceId: 'billing-v2'
+/// });
+/// console.log(`Skipped ${result.skippedDuplicates.length} duplicates`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub fn resource_relationships<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    old_source_id: String,
+    new_source_id: String,
+    relationship_types: Option<Vec<RelationshipType>>,
+    target_ids: Option<Vec<String>>,
+    dry_run: Option<bool>,
+) -> Result<RewriteResult, AppError> {
+    let rewrite = EndpointRewrite {
+        endpoint: Endpoint::Source,
+        old_id: old_source_id,
+        new_id: new_source_id,
+        relationship_types,
+        counterpart_ids: target_ids,
+    };
+    rewrite.run(&app, &state, &environment, dry_run.unwrap_or(false))
+}
+
+/// The end of a relationship an `EndpointRewrite` moves.
+#[derive(Debug, Clone, Copy)]
+enum Endpoint {
+    Source,
+    Target,
+}
+
+/// A rewrite of one end of relationships, for `retarget_relationships` and
+/// `resource_relationships`.
+///
+/// # Fields
+///
+/// * `endpoint` - The end moved
+/// * `old_id` - The service that end is at now
+/// * `new_id` - The service it is moved to
+/// * `relationship_types` - The types rewritten, if not all
+/// * `counterpart_ids` - The services at the other end whose relationships are
+///   rewritten, if not all
+struct EndpointRewrite {
+    endpoint: Endpoint,
+    old_id: String,
+    new_id: String,
+    relationship_types: Option<Vec<RelationshipType>>,
+    counterpart_ids: Option<Vec<String>>,
+}
+
+impl EndpointRewrite {
+    /// Computes the rewrites and, unless it is a dry run, saves them as one batch.
+    fn run<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        state: &RwLock<AppState>,
+        environment: &str,
+        dry_run: bool,
+    ) -> Result<RewriteResult, AppError> {
+        storage::validate_service_id(&self.new_id)?;
+        if self.new_id == self.old_id {
+            return Err(AppError::ValidationError(format!(
+                "Relationships cannot be moved from '{}' to itself",
+                self.old_id
+            )));
+        }
+
+        if dry_run {
+            let state = read_state(state);
+            let (plan, skipped_duplicates) = self.plan(&state, environment)?;
+            return Ok(RewriteResult {
+                relationships: plan.result.saved_relationships,
+                skipped_duplicates,
+                dry_run: true,
+            });
+        }
+
+        let mut state = write_state(state);
+        let (plan, skipped_duplicates) = self.plan(&state, environment)?;
+        let (command, end) = match self.endpoint {
+            Endpoint::Source => ("resource_relationships", "source"),
+            Endpoint::Target => ("retarget_relationships", "target"),
+        };
+        let description = format!(
+            "Moved the {} of {} relationships from {} to {}",
+            end,
+            plan.result.saved_relationships.len(),
+            self.old_id,
+            self.new_id
+        );
+        let result = plan.apply(app, &mut state, environment, command, description)?;
+
+        Ok(RewriteResult {
+            relationships: result.saved_relationships,
+            skipped_duplicates,
+            dry_run: false,
+        })
+    }
+
+    /// Plans the saves of the rewritten relationships, skipping duplicates.
+    fn plan(
+        &self,
+        state: &AppState,
+        environment: &str,
+    ) -> Result<(Plan, Vec<SkippedDuplicate>), AppError> {
+        if !state.storage.service_exists(environment, &self.new_id)? {
+            return Err(AppError::ServiceNotFound(self.new_id.clone()));
+        }
+
+        let relationships = state.storage.load_relationships(environment)?;
+        let (matching, kept): (Vec<&Relationship>, Vec<&Relationship>) =
+            relationships.iter().partition(|r| self.matches(r));
+
+        let mut rewritten: Vec<Relationship> = Vec::new();
+        let mut skipped_duplicates = Vec::new();
+        for relationship in matching {
+            let mut moved = relationship.clone();
+            match self.endpoint {
+                Endpoint::Source => moved.source = self.new_id.clone(),
+                Endpoint::Target => moved.target = self.new_id.clone(),
+            }
+            let duplicate = kept.iter().copied().chain(&rewritten).find(|r| {
+                r.source == moved.source
+                    && r.target == moved.target
+                    && r.relationship_type == moved.relationship_type
+            });
+            match duplicate {
+                Some(duplicate) => skipped_duplicates.push(SkippedDuplicate {
+                    relationship_id: relationship.id.clone(),
+                    duplicate_of: duplicate.id.clone(),
+                }),
+                None => rewritten.push(moved),
+            }
+        }
+
+        let operations = rewritten
+            .into_iter()
+            .map(|relationship| Operation::SaveRelationship {
+                relationship,
+                force: false,
+            })
+            .collect();
+        let plan = Plan::compute(state, environment, operations)?;
+
+        Ok((plan, skipped_duplicates))
+    }
+
+    /// Returns true if a relationship is one to rewrite.
+    fn matches(&self, relationship: &Relationship) -> bool {
+        let (moved, counterpart) = match self.endpoint {
+            Endpoint::Source => (&relationship.source, &relationship.target),
+            Endpoint::Target => (&relationship.target, &relationship.source),
+        };
+        *moved == self.old_id
+            && self
+                .relationship_types
+                .as_ref()
+                .is_none_or(|types| types.contains(&relationship.relationship_type))
+            && self
+                .counterpart_ids
+                .as_ref()
+                .is_none_or(|ids| ids.contains(counterpart))
+    }
+}
+
 /// Deletes a single relationship by its unique identifier.
 ///
 /// This command removes a specific relationship from the environment.
@@ -211,6 +1023,7 @@ pub fn save_relationship(
 ///
 /// # Arguments
 ///
+/// * `app` - The application handle, used to queue git auto-commits
 /// * `state` - The application state containing the cache and data path
 /// * `environment` - The name of the environment containing the relationship
 /// * `relationship_id` - The unique identifier of the relationship to delete
@@ -218,14 +1031,16 @@ pub fn save_relationship(
 /// # Returns
 ///
 /// * `Ok(())` - If the relationship was successfully deleted
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 /// * `Err(AppError::RelationshipNotFound)` - If no relationship exists with the given ID
 /// * `Err(AppError::Io)` - If there's an error writing to the filesystem
 ///
 /// # Side Effects
 ///
 /// - Updates the relationships JSON file
-/// - Invalidates the relationships cache
+/// - Stores the updated relationships in the relationships cache
+/// - Records the change in the environment's undo journal
+/// - Appends the change to the environment's audit log
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
 ///
 /// # Examples
 ///
@@ -237,26 +1052,84 @@ pub fn save_relationship(
 /// });
 /// ```
 #[tauri::command(rename_all = "camelCase")]
-pub fn delete_relationship(
-    state: State<'_, Mutex<AppState>>,
+pub fn delete_relationship<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
     relationship_id: String,
 ) -> Result<(), AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
+    let _timer = CommandTimer::start(&state, "delete_relationship");
+    let mut state = write_state(&state);
 
-    let mut relationships = loader::load_relationships(&state.data_path, &environment)?;
-    let original_len = relationships.len();
+    // Taken before the write, while it can still be compared with storage
+    let previous_index = cache::fresh_relationship_index(&state, &environment);
+    let mut relationships = state.storage.load_relationships(&environment)?;
+    let in_stored_order = is_in_stored_order(&relationships);
 
-    relationships.retain(|r| r.id != relationship_id);
+    let index = match relationships.iter().position(|r| r.id == relationship_id) {
+        Some(index) => index,
+        None => return Err(AppError::RelationshipNotFound(relationship_id)),
+    };
+    let removed = relationships[index].clone();
 
-    if relationships.len() == original_len {
-        return Err(AppError::RelationshipNotFound(relationship_id));
+    relationships.retain(|r| r.id != relationship_id);
+    if !in_stored_order {
+        relationships.sort_by(storage::relationship_order);
     }
 
-    loader::save_relationships(&state.data_path, &environment, &relationships)?;
+    state
+        .storage
+        .save_relationships(&environment, &relationships)?;
+    state.record_write(&environment);
+    let changed_paths =
+        storage::relationship_file_paths(&state.data_path, &environment, [removed.source.as_str()]);
+    let relationship_index = RelationshipIndex::after_change(
+        previous_index.as_deref().filter(|_| in_stored_order),
+        &relationships,
+        Some((index, &removed)),
+        None,
+    );
+
+    state.record_audit(
+        &environment,
+        &[AuditRecord::relationship(
+            "delete_relationship",
+            Some(&removed),
+            None,
+        )],
+    );
 
-    // Invalidate cache to ensure consistency
-    state.relationships_cache.remove(&environment);
+    state.record_undo(
+        &environment,
+        JournalEntry::new(
+            format!("Deleted relationship {}", removed.label()),
+            vec![Change::Relationship {
+                id: relationship_id.clone(),
+                index,
+                before: Some(removed),
+                after: None,
+            }],
+        ),
+    );
+
+    let summary = format!("Delete relationship {} in {}", relationship_id, environment);
+    git::auto_commit(
+        &app,
+        &state,
+        "delete_relationship",
+        summary.clone(),
+        changed_paths,
+    );
+    webhooks::notify(
+        &app,
+        &state,
+        &environment,
+        WebhookEvent::DataChanged,
+        summary,
+    );
+
+    // Keep the cache warm with the vector that was just written
+    state.cache_indexed_relationships(&environment, relationships, relationship_index);
 
     Ok(())
 }
@@ -265,10 +1138,12 @@ pub fn delete_relationship(
 ///
 /// This command removes all relationships where the specified service appears
 /// as either the source or target. This is typically called when deleting a
-/// service to clean up orphaned relationships.
+/// service to clean up orphaned relationships. Use
+/// `delete_relationships_for_service_preview` to see what would be deleted first.
 ///
 /// # Arguments
 ///
+/// * `app` - The application handle, used to queue git auto-commits
 /// * `state` - The application state containing the cache and data path
 /// * `environment` - The name of the environment to clean up
 /// * `service_id` - The unique identifier of the service whose relationships should be removed
@@ -276,18 +1151,23 @@ pub fn delete_relationship(
 /// # Returns
 ///
 /// * `Ok(usize)` - The number of relationships that were deleted
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 /// * `Err(AppError::Io)` - If there's an error writing to the filesystem
 ///
 /// # Side Effects
 ///
+/// If any relationships were deleted:
 /// - Updates the relationships JSON file
-/// - Invalidates the relationships cache
+/// - Stores the updated relationships in the relationships cache
+/// - Records the change in the environment's undo journal
+/// - Appends the change to the environment's audit log
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
 ///
 /// # Note
 ///
-/// This function does not fail if no relationships are found - it simply returns 0.
-/// This allows for safe cleanup even when a service has no relationships.
+/// This function does not fail if no relationships are found - it simply returns 0
+/// without writing anything. This allows for safe cleanup even when a service has
+/// no relationships. When the environment's relationships are cached, the
+/// per-service index tells that without reading the relationships file.
 ///
 /// # Examples
 ///
@@ -300,24 +1180,658 @@ pub fn delete_relationship(
 /// console.log(`Deleted ${deletedCount} relationships`);
 /// ```
 #[tauri::command(rename_all = "camelCase")]
-pub fn delete_relationships_for_service(
-    state: State<'_, Mutex<AppState>>,
+pub fn delete_relationships_for_service<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
     service_id: String,
 ) -> Result<usize, AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
+    let mut state = write_state(&state);
 
-    let mut relationships = loader::load_relationships(&state.data_path, &environment)?;
-    let original_len = relationships.len();
+    // The cached index answers "nothing to delete" without reading the relationships
+    let has_relationships = cache::fresh_relationship_index(&state, &environment)
+        .is_none_or(|index| !index.positions(&service_id).is_empty());
+    if !has_relationships {
+        return Ok(0);
+    }
 
-    relationships.retain(|r| r.source != service_id && r.target != service_id);
+    let plan = Plan::for_operation(
+        &state,
+        &environment,
+        Operation::DeleteRelationshipsForService {
+            service_id: service_id.clone(),
+        },
+    )?;
+    let deleted_count = plan.result.deleted_relationships.len();
 
-    let deleted_count = original_len - relationships.len();
+    // Undone as a single unit
+    plan.apply(
+        &app,
+        &mut state,
+        &environment,
+        "delete_relationships_for_service",
+        format!(
+            "Deleted {} relationships of service {}",
+            deleted_count, service_id
+        ),
+    )?;
+
+    Ok(deleted_count)
+}
 
-    loader::save_relationships(&state.data_path, &environment, &relationships)?;
+/// Previews which relationships `delete_relationships_for_service` would delete.
+///
+/// Computes the same changes as the real command without writing anything or
+/// touching the caches.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+/// * `service_id` - The service whose relationships would be removed
+///
+/// # Returns
+///
+/// * `Ok(BatchResult)` - The IDs of the relationships that would be deleted in
+///   `deletedRelationships`, with `dryRun` set
+/// * `Err(AppError::Io)` - If the relationships file cannot be read
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const preview = await invoke('delete_relationships_for_service_preview', {
+///     environment: 'dev',
+///     serviceId: 'deprecated-service'
+/// });
+/// confirm(`Delete ${preview.deletedRelationships.length} relationships?`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn delete_relationships_for_service_preview(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    service_id: String,
+) -> Result<BatchResult, AppError> {
+    let state = read_state(&state);
 
-    // Invalidate cache to ensure consistency
-    state.relationships_cache.remove(&environment);
+    let mut plan = Plan::for_operation(
+        &state,
+        &environment,
+        Operation::DeleteRelationshipsForService { service_id },
+    )?;
+    plan.result.dry_run = true;
 
-    Ok(deleted_count)
+    Ok(plan.result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::environments::migrate_relationship_storage;
+    use crate::models::{RelationshipLayout, Service};
+    use crate::test_support::{
+        block_on, relationship, service, snapshot_dir, SlowStorage, TestApp,
+    };
+    use std::fs;
+    use std::time::Duration;
+
+    fn named(id: &str, name: &str) -> Service {
+        let mut service = service(id);
+        service.name = name.to_string();
+        service
+    }
+
+    fn stored_relationships(app: &TestApp) -> Vec<Relationship> {
+        read_state(&app.state())
+            .storage
+            .load_relationships("dev")
+            .unwrap()
+    }
+
+    /// Runs a test once per relationship layout, against an environment of
+    /// four services where `db` is the target of relationships from three
+    /// other sources.
+    fn on_both_layouts(test: impl Fn(&TestApp, RelationshipLayout)) {
+        for layout in [RelationshipLayout::Single, RelationshipLayout::Sharded] {
+            let app = TestApp::with_environment(
+                "dev",
+                &[
+                    service("api"),
+                    service("auth"),
+                    service("cache"),
+                    service("db"),
+                ],
+                &[
+                    relationship("api-auth", "api", "auth"),
+                    relationship("api-db", "api", "db"),
+                    relationship("auth-db", "auth", "db"),
+                    relationship("cache-db", "cache", "db"),
+                    relationship("db-cache", "db", "cache"),
+                ],
+            );
+            let sharded = layout == RelationshipLayout::Sharded;
+            block_on(migrate_relationship_storage(
+                app.handle(),
+                "dev".to_string(),
+                sharded,
+            ))
+            .unwrap();
+
+            test(&app, layout);
+        }
+    }
+
+    /// Returns the relationship files of dev, relative to its directory.
+    fn relationship_files(app: &TestApp) -> Vec<String> {
+        let dir = app.data_path().join("dev");
+        let mut files = Vec::new();
+        if dir.join("relationships.json").exists() {
+            files.push("relationships.json".to_string());
+        }
+        if let Ok(entries) = fs::read_dir(dir.join("relationships")) {
+            for entry in entries {
+                let name = entry.unwrap().file_name().into_string().unwrap();
+                files.push(format!("relationships/{name}"));
+            }
+        }
+        files.sort();
+        files
+    }
+
+    fn stored_ids(app: &TestApp) -> Vec<String> {
+        let mut ids: Vec<String> = stored_relationships(app)
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn migrating_stores_one_file_per_source() {
+        on_both_layouts(|app, layout| {
+            let expected = match layout {
+                RelationshipLayout::Single => vec!["relationships.json"],
+                RelationshipLayout::Sharded => vec![
+                    "relationships/api.json",
+                    "relationships/auth.json",
+                    "relationships/cache.json",
+                    "relationships/db.json",
+                ],
+            };
+            assert_eq!(relationship_files(app), expected);
+            assert_eq!(
+                stored_ids(app),
+                ["api-auth", "api-db", "auth-db", "cache-db", "db-cache"]
+            );
+        });
+    }
+
+    #[test]
+    fn save_relationship_adds_and_updates() {
+        on_both_layouts(|app, layout| {
+            let added = save_relationship(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                relationship("auth-cache", "auth", "cache"),
+                None,
+            )
+            .unwrap();
+            assert_eq!(added.revision, 1);
+
+            let mut updated = stored_relationships(app)
+                .into_iter()
+                .find(|r| r.id == "api-auth")
+                .unwrap();
+            updated.description = Some("Checks tokens".to_string());
+            save_relationship(app.handle(), app.state(), "dev".to_string(), updated, None).unwrap();
+
+            // Caches are dropped so the files are read back
+            write_state(&app.state()).clear_environment_cache("dev");
+            let relationships = stored_relationships(app);
+            let stored = |id: &str| relationships.iter().find(|r| r.id == id).unwrap();
+            assert_eq!(stored("auth-cache").target, "cache");
+            assert_eq!(
+                stored("api-auth").description.as_deref(),
+                Some("Checks tokens")
+            );
+            assert_eq!(relationships.len(), 6, "{layout:?}");
+
+            let duplicate = save_relationship(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                relationship("api-auth-again", "api", "auth"),
+                None,
+            );
+            assert!(matches!(
+                duplicate,
+                Err(AppError::DuplicateRelationship(_, _))
+            ));
+        });
+    }
+
+    #[test]
+    fn delete_relationship_removes_only_that_relationship() {
+        on_both_layouts(|app, _| {
+            delete_relationship(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                "auth-db".to_string(),
+            )
+            .unwrap();
+
+            write_state(&app.state()).clear_environment_cache("dev");
+            assert_eq!(
+                stored_ids(app),
+                ["api-auth", "api-db", "cache-db", "db-cache"]
+            );
+
+            let missing = delete_relationship(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                "auth-db".to_string(),
+            );
+            assert!(matches!(missing, Err(AppError::RelationshipNotFound(_))));
+        });
+    }
+
+    #[test]
+    fn delete_relationships_for_service_reaches_every_shard() {
+        on_both_layouts(|app, layout| {
+            let deleted = delete_relationships_for_service(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                "db".to_string(),
+            )
+            .unwrap();
+
+            // db is the source of one relationship and the target of three,
+            // each stored with a different source
+            assert_eq!(deleted, 4);
+            write_state(&app.state()).clear_environment_cache("dev");
+            assert_eq!(stored_ids(app), ["api-auth"]);
+
+            let expected = match layout {
+                RelationshipLayout::Single => vec!["relationships.json"],
+                RelationshipLayout::Sharded => vec!["relationships/api.json"],
+            };
+            assert_eq!(relationship_files(app), expected);
+        });
+    }
+
+    #[test]
+    fn reads_agree_on_both_layouts() {
+        on_both_layouts(|app, _| {
+            let all = block_on(get_all_relationships(
+                app.handle(),
+                "dev".to_string(),
+                None,
+                None,
+                None,
+                None,
+            ))
+            .unwrap();
+            assert_eq!(all.len(), 5);
+
+            let mut for_db: Vec<String> =
+                get_relationships_for_service(app.state(), "dev".to_string(), "db".to_string())
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| r.id)
+                    .collect();
+            for_db.sort();
+            assert_eq!(for_db, ["api-db", "auth-db", "cache-db", "db-cache"]);
+
+            let counts =
+                block_on(get_relationship_counts(app.handle(), "dev".to_string())).unwrap();
+            assert_eq!(counts["db"].incoming, 3);
+            assert_eq!(counts["db"].outgoing, 1);
+        });
+    }
+
+    #[test]
+    fn retarget_relationships_rewrites_across_shards() {
+        on_both_layouts(|app, _| {
+            let result = retarget_relationships(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                "db".to_string(),
+                "cache".to_string(),
+                None,
+                Some(vec!["api".to_string(), "auth".to_string()]),
+                None,
+            )
+            .unwrap();
+            assert_eq!(result.relationships.len(), 2);
+
+            write_state(&app.state()).clear_environment_cache("dev");
+            let relationships = stored_relationships(app);
+            let target = |id: &str| {
+                let r = relationships.iter().find(|r| r.id == id).unwrap();
+                r.target.clone()
+            };
+            assert_eq!(target("api-db"), "cache");
+            assert_eq!(target("auth-db"), "cache");
+            assert_eq!(target("cache-db"), "db");
+        });
+    }
+
+    #[test]
+    fn backfill_refreshes_generated_descriptions_after_a_rename() {
+        let mut generated = relationship("rel-1", "checkout", "payments");
+        descriptions::set_generated(&mut generated, "Checkout depends on Payments".to_string());
+        let mut written = relationship("rel-2", "checkout", "ledger");
+        written.description = Some("Books each order".to_string());
+        let app = TestApp::with_environment(
+            "dev",
+            &[
+                named("checkout", "Checkout"),
+                named("payments", "Payment Gateway"),
+                named("ledger", "Ledger"),
+            ],
+            &[generated, written],
+        );
+
+        let result = block_on(backfill_relationship_descriptions(
+            app.handle(),
+            "dev".to_string(),
+            None,
+            None,
+        ))
+        .unwrap();
+
+        assert_eq!(result.saved_relationships.len(), 1);
+        let relationships = stored_relationships(&app);
+        let stored = |id: &str| relationships.iter().find(|r| r.id == id).unwrap();
+        assert_eq!(
+            stored("rel-1").description.as_deref(),
+            Some("Checkout depends on Payment Gateway")
+        );
+        assert!(descriptions::is_generated(stored("rel-1")));
+        assert_eq!(
+            stored("rel-2").description.as_deref(),
+            Some("Books each order")
+        );
+    }
+
+    #[test]
+    fn the_delete_for_service_preview_writes_nothing() {
+        on_both_layouts(|app, _| {
+            let files = snapshot_dir(app.data_path());
+
+            let preview = delete_relationships_for_service_preview(
+                app.state(),
+                "dev".to_string(),
+                "db".to_string(),
+            )
+            .unwrap();
+
+            assert!(preview.dry_run);
+            assert_eq!(preview.deleted_relationships.len(), 4);
+            assert_eq!(snapshot_dir(app.data_path()), files);
+            assert_eq!(stored_relationships(app).len(), 5);
+
+            // The real run deletes exactly what the preview listed
+            let deleted = delete_relationships_for_service(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                "db".to_string(),
+            )
+            .unwrap();
+            assert_eq!(deleted, preview.deleted_relationships.len());
+            write_state(&app.state()).clear_environment_cache("dev");
+            let remaining = stored_ids(app);
+            assert!(preview
+                .deleted_relationships
+                .iter()
+                .all(|id| !remaining.contains(id)));
+            assert_eq!(remaining, ["api-auth"]);
+        });
+    }
+
+    #[test]
+    fn the_cached_index_follows_saves_and_deletes() {
+        let service_ids: Vec<String> = (0..40).map(|n| format!("svc-{n}")).collect();
+        let services: Vec<Service> = service_ids.iter().map(|id| service(id)).collect();
+        let relationships: Vec<Relationship> = (0..400)
+            .map(|n| {
+                let source = &service_ids[n % 40];
+                let target = &service_ids[(n * 7 + 3) % 40];
+                relationship(&format!("rel-{n}"), source, target)
+            })
+            .collect();
+        let app = TestApp::with_environment("dev", &services, &relationships);
+        let for_service = |id: &str| {
+            let mut ids: Vec<String> =
+                get_relationships_for_service(app.state(), "dev".to_string(), id.to_string())
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| r.id)
+                    .collect();
+            ids.sort();
+            ids
+        };
+        // Warm the cache, so the changes below patch the index
+        for_service("svc-0");
+
+        for n in 0..40 {
+            if n % 2 == 0 {
+                let source = &service_ids[(n * 3) % 40];
+                let target = &service_ids[(n * 11 + 1) % 40];
+                let mut created = relationship(&format!("new-{n}"), source, target);
+                created.relationship_type = RelationshipType::Publishes;
+                save_relationship(app.handle(), app.state(), "dev".to_string(), created, None)
+                    .unwrap();
+            } else {
+                let id = format!("rel-{}", n * 5);
+                delete_relationship(app.handle(), app.state(), "dev".to_string(), id).unwrap();
+            }
+        }
+
+        let stored = stored_relationships(&app);
+        for id in &service_ids {
+            let mut expected: Vec<String> = stored
+                .iter()
+                .filter(|r| r.source == *id || r.target == *id)
+                .map(|r| r.id.clone())
+                .collect();
+            expected.sort();
+            assert_eq!(for_service(id), expected, "{id}");
+        }
+    }
+
+    #[test]
+    fn writes_leave_the_cache_warm() {
+        on_both_layouts(|app, layout| {
+            let all_ids = || {
+                let mut ids: Vec<String> = block_on(get_all_relationships(
+                    app.handle(),
+                    "dev".to_string(),
+                    None,
+                    None,
+                    None,
+                    None,
+                ))
+                .unwrap()
+                .into_iter()
+                .map(|r| r.id)
+                .collect();
+                ids.sort();
+                ids
+            };
+            all_ids();
+            let storage = SlowStorage::install(app, Duration::ZERO, Duration::ZERO);
+            // Writes read the stored relationships; the reads after them must not
+            let ids_from_cache = || {
+                let loads = storage.relationship_loads();
+                let ids = all_ids();
+                assert_eq!(storage.relationship_loads(), loads, "{layout:?}");
+                ids
+            };
+
+            let saved = save_relationship(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                relationship("api-cache", "api", "cache"),
+                None,
+            )
+            .unwrap();
+            assert_eq!(
+                ids_from_cache(),
+                vec![
+                    "api-auth",
+                    "api-cache",
+                    "api-db",
+                    "auth-db",
+                    "cache-db",
+                    "db-cache"
+                ],
+                "{layout:?}"
+            );
+
+            delete_relationship(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                saved.id.clone(),
+            )
+            .unwrap();
+            assert_eq!(
+                ids_from_cache(),
+                vec!["api-auth", "api-db", "auth-db", "cache-db", "db-cache"]
+            );
+
+            delete_relationships_for_service(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                "db".to_string(),
+            )
+            .unwrap();
+            assert_eq!(ids_from_cache(), vec!["api-auth"]);
+
+            assert_eq!(stored_ids(app), vec!["api-auth"]);
+        });
+    }
+
+    #[test]
+    fn relationship_counts_include_missing_endpoints() {
+        let mut reads = relationship("api-cache", "api", "cache");
+        reads.relationship_type = RelationshipType::ReadsFrom;
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("cache"), service("db")],
+            &[
+                relationship("api-db", "api", "db"),
+                reads,
+                relationship("api-ghost", "api", "ghost"),
+                relationship("phantom-db", "phantom", "db"),
+                relationship("db-db", "db", "db"),
+            ],
+        );
+
+        let counts = block_on(get_relationship_counts(app.handle(), "dev".to_string())).unwrap();
+        let mut ids: Vec<&str> = counts.keys().map(String::as_str).collect();
+        ids.sort();
+        assert_eq!(ids, ["api", "cache", "db", "ghost", "phantom"]);
+
+        let api = &counts["api"];
+        assert_eq!((api.incoming, api.outgoing), (0, 3));
+        assert_eq!(api.by_type["depends_on"].outgoing, 2);
+        assert_eq!(api.by_type["reads_from"].outgoing, 1);
+        assert_eq!(api.by_type.len(), 2);
+
+        // db-db counts as both
+        let db = &counts["db"];
+        assert_eq!((db.incoming, db.outgoing), (3, 1));
+        assert_eq!((counts["ghost"].incoming, counts["ghost"].outgoing), (1, 0));
+        assert_eq!(
+            (counts["phantom"].incoming, counts["phantom"].outgoing),
+            (0, 1)
+        );
+
+        let value = serde_json::to_value(&counts["cache"]).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "incoming": 1,
+                "outgoing": 0,
+                "byType": { "reads_from": { "incoming": 1, "outgoing": 0 } }
+            })
+        );
+
+        // Summaries count the edge to the missing service too
+        let summaries = block_on(crate::commands::services::get_service_summaries(
+            app.handle(),
+            "dev".into(),
+        ))
+        .unwrap();
+        let api = summaries.iter().find(|s| s.id == "api").unwrap();
+        let api = serde_json::to_value(api).unwrap();
+        assert_eq!(api["outgoingCount"], 3);
+        assert_eq!(api["incomingCount"], 0);
+    }
+
+    /// `services` services, each depending on the next five (wrapping).
+    fn large_graph(services: usize) -> Vec<Relationship> {
+        (0..services)
+            .flat_map(|index| {
+                (1..=5).map(move |offset| {
+                    relationship(
+                        &format!("rel-{index}-{offset}"),
+                        &format!("service-{index}"),
+                        &format!("service-{}", (index + offset) % services),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn fastest_count(relationships: &[Relationship]) -> Duration {
+        (0..3)
+            .map(|_| {
+                let started = std::time::Instant::now();
+                std::hint::black_box(count_relationships(relationships));
+                started.elapsed()
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn relationship_counts_take_one_pass_over_a_large_environment() {
+        let relationships = large_graph(5_000);
+        let app = TestApp::with_environment("dev", &[], &relationships);
+        let storage = SlowStorage::install(&app, Duration::ZERO, Duration::ZERO);
+
+        let counts = block_on(get_relationship_counts(app.handle(), "dev".to_string())).unwrap();
+        assert_eq!(counts.len(), 5_000);
+        assert!(counts
+            .values()
+            .all(|counts| counts.incoming == 5 && counts.outgoing == 5));
+
+        // Only the cached relationships are read, once; services aren't
+        // loaded to be scanned one by one
+        assert_eq!(storage.relationship_loads(), 1);
+        assert_eq!(storage.service_loads(), 0);
+        block_on(get_relationship_counts(app.handle(), "dev".to_string())).unwrap();
+        assert_eq!(storage.relationship_loads(), 1);
+
+        // Four times the services take about four times as long; scanning
+        // the relationships once per service would take sixteen
+        let small = fastest_count(&large_graph(2_000));
+        let large = fastest_count(&large_graph(8_000));
+        assert!(
+            large < small * 10,
+            "2000 services took {small:?}, 8000 took {large:?}"
+        );
+    }
 }
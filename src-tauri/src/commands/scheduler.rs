@@ -0,0 +1,79 @@
+//! Scheduled export commands for the Tauri application.
+//!
+//! Scheduled exports are configured through the `scheduledExport` settings
+//! and run in the background (see `scheduler`). These commands run them on
+//! demand and show what past runs did.
+
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::error::AppError;
+use crate::scheduler::{self, RunTrigger, ScheduledRun, Scheduler};
+use crate::state::{read_state, AppState};
+
+/// Retrieves the recent scheduled and manual export runs.
+///
+/// The history is kept in memory, so it starts empty each time the app starts.
+///
+/// # Arguments
+///
+/// * `scheduler` - The scheduler holding the history
+///
+/// # Returns
+///
+/// * `Ok(Vec<ScheduledRun>)` - One entry per exported environment, most recent first
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const runs = await invoke('get_scheduled_task_history');
+/// const failed = runs.filter(run => run.outcome === 'failed');
+/// ```
+#[tauri::command]
+pub fn get_scheduled_task_history(
+    scheduler: State<'_, Scheduler>,
+) -> Result<Vec<ScheduledRun>, AppError> {
+    Ok(scheduler.history())
+}
+
+/// Runs the configured scheduled export now.
+///
+/// Runs even when scheduled exports are disabled, so the configuration can
+/// be tried before it is enabled. The schedule itself is unaffected.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to record the runs and emit events
+/// * `state` - The application state containing the settings
+///
+/// # Returns
+///
+/// * `Ok(Vec<ScheduledRun>)` - One run per configured environment; failed
+///   environments are reported in the runs, not as an error
+/// * `Err(AppError::ValidationError)` - If the settings can't be run (e.g., no
+///   environment is configured)
+///
+/// # Side Effects
+///
+/// - Writes a snapshot or export file per environment and prunes older ones
+/// - Emits `scheduled-export-finished` per environment
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const runs = await invoke('run_scheduled_export_now');
+/// console.log(runs.map(run => `${run.environment}: ${run.outcome}`));
+/// ```
+#[tauri::command]
+pub fn run_scheduled_export_now<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+) -> Result<Vec<ScheduledRun>, AppError> {
+    let mut settings = read_state(&state).settings.scheduled_export.clone();
+    settings.enabled = true;
+    scheduler::validate_settings(&settings)?;
+
+    Ok(scheduler::run_exports(&app, &settings, RunTrigger::Manual))
+}
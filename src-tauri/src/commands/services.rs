@@ -4,13 +4,24 @@
 //! environments. All commands utilize a caching layer to minimize disk I/O and
 //! improve performance on repeated queries.
 
-use std::sync::Mutex;
-use tauri::State;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Runtime, State};
 
+use crate::commands::relationships::count_relationships;
 use crate::error::AppError;
-use crate::models::Service;
-use crate::state::AppState;
+use crate::git;
+use crate::lifecycle;
+use crate::metadata_schema;
+use crate::models::{Service, ServiceSummary, WebhookEvent};
+use crate::naming;
+use crate::state::diagnostics::CommandTimer;
+use crate::state::journal::{self, Change, JournalEntry};
+use crate::state::quick_open::MatchRank;
+use crate::state::{cache, read_state, run_blocking, write_state, AppState};
 use crate::storage;
+use crate::storage::audit::{self, AuditRecord};
+use crate::webhooks;
 
 /// Retrieves all services for a specified environment.
 ///
@@ -20,13 +31,12 @@ use crate::storage;
 ///
 /// # Arguments
 ///
-/// * `state` - The application state containing the cache and data path
+/// * `app` - The application handle, used to load on the blocking thread pool
 /// * `environment` - The name of the environment to retrieve services from (e.g., "dev", "staging", "prod")
 ///
 /// # Returns
 ///
 /// * `Ok(Vec<Service>)` - A vector containing all services in the environment
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 /// * `Err(AppError::Io)` - If there's an error reading from the filesystem
 ///
 /// # Examples
@@ -36,35 +46,70 @@ use crate::storage;
 /// const services = await invoke('get_all_services', { environment: 'dev' });
 /// ```
 #[tauri::command]
-pub fn get_all_services(
-    state: State<'_, Mutex<AppState>>,
+pub async fn get_all_services<R: Runtime>(
+    app: AppHandle<R>,
     environment: String,
 ) -> Result<Vec<Service>, AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
-
-    // Check cache first
-    if let Some(services_map) = state.services_cache.get(&environment) {
-        return Ok(services_map.values().cloned().collect());
-    }
-
-    // Load from disk
-    let services = storage::load_services(&state.data_path, &environment)?;
-
-    // Update cache
-    let services_map: std::collections::HashMap<String, Service> = services
-        .iter()
-        .map(|s| (s.id.clone(), s.clone()))
-        .collect();
-    state.services_cache.insert(environment, services_map);
+    run_blocking(app, move |state| {
+        let _timer = CommandTimer::start(state, "get_all_services");
+        cache::services(state, &environment)
+    })
+    .await
+}
 
-    Ok(services)
+/// Retrieves lightweight summaries of all services in an environment.
+///
+/// Intended for list views: each summary carries the ID, name, type, status,
+/// team, tag count, whether there is a description, and the numbers of
+/// incoming and outgoing relationships (see `get_relationship_counts`), but
+/// no description text or metadata. Summaries are built straight from the
+/// services cache without cloning whole services.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to load on the blocking thread pool
+/// * `environment` - The name of the environment to retrieve summaries from
+///
+/// # Returns
+///
+/// * `Ok(Vec<ServiceSummary>)` - One summary per service in the environment
+/// * `Err(AppError::Io)` - If there's an error reading from the filesystem
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const summaries = await invoke('get_service_summaries', { environment: 'dev' });
+/// // [{ id: 'api-gateway', name: 'API Gateway', serviceType: 'gateway', status: 'healthy',
+/// //    team: 'Platform', tagCount: 3, hasDescription: true, incomingCount: 12,
+/// //    outgoingCount: 7 }, ...]
+/// ```
+#[tauri::command]
+pub async fn get_service_summaries<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+) -> Result<Vec<ServiceSummary>, AppError> {
+    run_blocking(app, move |state| {
+        let _timer = CommandTimer::start(state, "get_service_summaries");
+        let relationships = cache::shared_relationships(state, &environment)?;
+        let counts = count_relationships(&relationships);
+        cache::map_services(state, &environment, |service| {
+            let counts = counts.get(&service.id);
+            ServiceSummary {
+                incoming_count: Some(counts.map_or(0, |c| c.incoming)),
+                outgoing_count: Some(counts.map_or(0, |c| c.outgoing)),
+                ..ServiceSummary::from(service)
+            }
+        })
+    })
+    .await
 }
 
 /// Retrieves a single service by its unique identifier.
 ///
-/// This command looks up a service first in the cache, then falls back to loading
-/// from disk if not found. When loaded from disk, the service is added to the cache
-/// for future requests.
+/// This command looks up a service in the cache if the environment is cached,
+/// and otherwise loads just that service from disk. A service loaded on its own
+/// is not cached; the cache only ever holds complete environments.
 ///
 /// # Arguments
 ///
@@ -75,10 +120,13 @@ pub fn get_all_services(
 /// # Returns
 ///
 /// * `Ok(Service)` - The requested service if found
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 /// * `Err(AppError::ServiceNotFound)` - If no service exists with the given ID
 /// * `Err(AppError::Io)` - If there's an error reading from the filesystem
 ///
+/// # Side Effects
+///
+/// - Records the service as recently viewed (see `get_recent_services`)
+///
 /// # Examples
 ///
 /// ```typescript
@@ -90,48 +138,149 @@ pub fn get_all_services(
 /// ```
 #[tauri::command(rename_all = "camelCase")]
 pub fn get_service_by_id(
-    state: State<'_, Mutex<AppState>>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
     service_id: String,
 ) -> Result<Service, AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
+    let _timer = CommandTimer::start(&state, "get_service_by_id");
+    let service = cache::service(&state, &environment, &service_id)?;
+    let (recent, data_path) = {
+        let state = read_state(&state);
+        (Arc::clone(&state.recent_services), state.data_path.clone())
+    };
+    recent.record(&data_path, &environment, &service_id);
 
-    // Check cache first
-    if let Some(services_map) = state.services_cache.get(&environment) {
-        if let Some(service) = services_map.get(&service_id) {
-            return Ok(service.clone());
-        }
+    Ok(service)
+}
+
+/// Checks whether a service exists.
+///
+/// Unlike `get_service_by_id`, a missing service is not an error. When the
+/// environment isn't cached, only the service file's existence is checked,
+/// so a file that fails to parse still counts as existing.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment to look in
+/// * `service_id` - The unique identifier of the service
+///
+/// # Returns
+///
+/// * `Ok(bool)` - Whether the service exists
+/// * `Err(AppError::ValidationError)` - If the environment name or service ID is not path-safe
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const taken = await invoke('service_exists', { environment: 'dev', serviceId: 'new-api' });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn service_exists(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    service_id: String,
+) -> Result<bool, AppError> {
+    cache::service_exists(&state, &environment, &service_id)
+}
+
+/// Kinds of IDs `generate_id` generates.
+///
+/// # Variants
+///
+/// * `Service` - A slug of a name, unique in the environment
+/// * `Relationship` - A random UUID
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdKind {
+    Service,
+    Relationship,
+}
+
+/// Generates an ID for a new service or relationship.
+///
+/// Service IDs are slugs of the hint (see `storage::slugify`): accents are
+/// folded to ASCII, everything is lowercased and runs of other characters
+/// become dashes, e.g. "Zürich Payments API" becomes `zurich-payments-api`.
+/// A hint with nothing to keep (empty, or only emoji) gives `service`. If a
+/// service of the environment already has the ID, `-2`, `-3` and so on is
+/// appended until it is free. Relationship IDs are version 4 UUIDs and
+/// ignore the hint. Either way the ID is safe to use as a filename.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The environment the ID must be free in
+/// * `kind` - "service" or "relationship"
+/// * `hint` - The name to derive a service ID from
+///
+/// # Returns
+///
+/// * `Ok(String)` - The new ID
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const id = await invoke('generate_id', {
+///     environment: 'dev',
+///     kind: 'service',
+///     hint: 'Payments API'
+/// });
+/// // "payments-api", or "payments-api-2" if that is taken
+/// ```
+#[tauri::command]
+pub fn generate_id(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    kind: IdKind,
+    hint: Option<String>,
+) -> Result<String, AppError> {
+    storage::validate_environment_name(&environment)?;
+    if let IdKind::Relationship = kind {
+        return Ok(storage::new_relationship_id());
     }
 
-    // Load from disk
-    let service = storage::load_service(&state.data_path, &environment, &service_id)?;
+    let base = storage::slugify(
+        hint.as_deref().unwrap_or_default(),
+        storage::MAX_SLUG_LENGTH,
+    );
+    let base = if base.is_empty() {
+        "service".to_string()
+    } else {
+        base
+    };
 
-    // Update cache
-    state
-        .services_cache
-        .entry(environment)
-        .or_default()
-        .insert(service_id, service.clone());
+    let mut id = base.clone();
+    let mut n = 1;
+    while cache::service_exists(&state, &environment, &id)? {
+        n += 1;
+        let suffix = format!("-{}", n);
+        let stem = &base[..base.len().min(storage::MAX_SLUG_LENGTH - suffix.len())];
+        id = format!("{}{}", stem.trim_end_matches('-'), suffix);
+    }
 
-    Ok(service)
+    Ok(id)
 }
 
 /// Searches for services matching a query string within an environment.
 ///
 /// Performs a case-insensitive search across service properties including
-/// name, ID, description, and tags. Uses the `Service::matches_search` method
-/// to determine matches.
+/// name, ID, description, tier, and tags. Uses the `Service::matches_search`
+/// method to determine matches.
 ///
 /// # Arguments
 ///
 /// * `state` - The application state containing the cache and data path
 /// * `environment` - The name of the environment to search within
 /// * `query` - The search query string to match against service properties
+/// * `tier` - Only return services of this tier (default: any tier, or none)
 ///
 /// # Returns
 ///
 /// * `Ok(Vec<Service>)` - A vector of services matching the search query (may be empty)
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 /// * `Err(AppError::Io)` - If there's an error reading from the filesystem
 ///
 /// # Examples
@@ -143,95 +292,260 @@ pub fn get_service_by_id(
 ///     query: 'api'
 /// });
 /// // Returns all services with "api" in their name, description, or tags
+///
+/// // Only the tier-1 services
+/// const critical = await invoke('search_services', { environment: 'dev', query: '', tier: 1 });
 /// ```
 #[tauri::command]
 pub fn search_services(
-    state: State<'_, Mutex<AppState>>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
     query: String,
+    tier: Option<u8>,
 ) -> Result<Vec<Service>, AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
-
-    // Check cache first
-    let services = if let Some(services_map) = state.services_cache.get(&environment) {
-        services_map.values().cloned().collect()
-    } else {
-        // Load from disk
-        let loaded = storage::load_services(&state.data_path, &environment)?;
-        // Update cache
-        let services_map: std::collections::HashMap<String, Service> = loaded
-            .iter()
-            .map(|s| (s.id.clone(), s.clone()))
-            .collect();
-        state.services_cache.insert(environment, services_map);
-        loaded
-    };
+    let _timer = CommandTimer::start(&state, "search_services");
+    let services = cache::services(&state, &environment)?;
 
     let results: Vec<Service> = services
         .into_iter()
+        .filter(|s| tier.is_none() || s.tier == tier)
         .filter(|s| s.matches_search(&query))
         .collect();
 
     Ok(results)
 }
 
+/// How many services `quick_open` returns by default.
+pub const DEFAULT_QUICK_OPEN_LIMIT: usize = 20;
+
+/// A service matching a quick-open prefix.
+///
+/// # Fields
+///
+/// * `service` - The service's summary, serialized inline
+/// * `rank` - How it matches: "exact_id", "id_prefix", "name_prefix" or "word_prefix"
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickOpenMatch {
+    #[serde(flatten)]
+    pub service: ServiceSummary,
+    pub rank: MatchRank,
+}
+
+/// Finds services by the prefix of their ID or name, for the quick switcher.
+///
+/// Unlike `search_services`, which scans every service for a substring,
+/// this looks the prefix up in a sorted index of the cached services that
+/// is built when the cache is populated and patched by every save and
+/// delete, so it keeps up with typing in environments with thousands of
+/// services. Services are ranked by how they match: the prefix being the
+/// whole ID, then the ID starting with it, then the name, then a later
+/// word of the ID or name (after a space, `-`, `_`, `.`, `/` or `:`);
+/// each service is listed once with its best match, and ties are ordered
+/// by ID.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache
+/// * `environment` - The name of the environment to search
+/// * `prefix` - What was typed, compared ignoring case; empty lists services by ID
+/// * `limit` - The most services to return (default: 20)
+///
+/// # Returns
+///
+/// * `Ok(Vec<QuickOpenMatch>)` - The matching services, best match first
+/// * `Err(AppError::Io)` - If there's an error reading from the filesystem
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend, on every keystroke:
+/// const matches = await invoke('quick_open', {
+///     environment: 'prod',
+///     prefix: 'pay',
+///     limit: 10
+/// });
+/// // payments-api (id_prefix), then "Checkout Payments" (word_prefix)
+/// ```
+#[tauri::command]
+pub fn quick_open(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    prefix: String,
+    limit: Option<usize>,
+) -> Result<Vec<QuickOpenMatch>, AppError> {
+    let _timer = CommandTimer::start(&state, "quick_open");
+    let limit = limit.unwrap_or(DEFAULT_QUICK_OPEN_LIMIT);
+    let matches = cache::quick_open(&state, &environment, &prefix, limit)?;
+
+    Ok(matches
+        .into_iter()
+        .map(|(service, rank)| QuickOpenMatch { service, rank })
+        .collect())
+}
+
 /// Saves a service to the specified environment (create or update).
 ///
 /// This command persists a service to disk and updates the in-memory cache.
 /// If a service with the same ID already exists, it will be overwritten.
 /// For new services, ensure the service ID is unique within the environment.
 ///
+/// # Revisions
+///
+/// The service's `revision` must match the stored one (0 for a new service),
+/// otherwise someone else saved it since it was read and the save is
+/// rejected. The saved service gets the next revision; use the returned
+/// service for further edits. `force` skips the check (for imports). A
+/// stored file that can't be read has no revision to compare, so the save
+/// fails with the load error; with `force` the file is overwritten, and the
+/// save isn't recorded in the undo journal.
+///
+/// # Conventions
+///
+/// When the validation config's naming rules are `strict` (see
+/// `save_validation_config`), a service that breaks them is rejected, even
+/// with `force`. The same goes for metadata that fails the JSON Schema of
+/// the service's type when `strictMetadataSchemas` is set (see
+/// `save_metadata_schema`).
+///
+/// A `successorId` must name another existing service and a
+/// `decommissionDate` must be a `YYYY-MM-DD` date; these are checked even
+/// with `force`.
+///
 /// # Arguments
 ///
+/// * `app` - The application handle, used to queue git auto-commits
 /// * `state` - The application state containing the cache and data path
 /// * `environment` - The name of the environment to save the service to
 /// * `service` - The complete service object to save
+/// * `force` - Save even if the revision doesn't match (default false)
 ///
 /// # Returns
 ///
-/// * `Ok(())` - If the service was successfully saved
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
-/// * `Err(AppError::Io)` - If there's an error writing to the filesystem
+/// * `Ok(Service)` - The saved service with its new revision
+/// * `Err(AppError::Conflict)` - If the service was changed since `service.revision`
+/// * `Err(AppError::ValidationError)` - If strict naming rules or metadata schemas reject
+///   the service, or its successor or decommission date isn't valid
+/// * `Err(AppError::FileLoad)` - If the validation config or metadata schema isn't valid
+///   JSON, or the stored service can't be parsed and `force` isn't set
+/// * `Err(AppError::Io)` - If there's an error reading or writing the filesystem
 ///
 /// # Side Effects
 ///
 /// - Creates or updates a JSON file at `{data_path}/{environment}/services/{service.id}.json`
 /// - Updates the in-memory services cache
+/// - Records the change in the environment's undo journal
+/// - Appends the change to the environment's audit log
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
 ///
 /// # Examples
 ///
 /// ```typescript
 /// // From the frontend:
-/// await invoke('save_service', {
+/// const saved = await invoke('save_service', {
 ///     environment: 'dev',
 ///     service: {
 ///         id: 'new-service',
 ///         name: 'New Service',
 ///         serviceType: 'api',
 ///         description: 'A new API service',
-///         tags: ['api', 'backend']
+///         tags: ['api', 'backend'],
+///         revision: 0
 ///     }
 /// });
+/// console.log(`Saved revision ${saved.revision}`);
 /// ```
 #[tauri::command]
-pub fn save_service(
-    state: State<'_, Mutex<AppState>>,
+pub fn save_service<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
-    service: Service,
-) -> Result<(), AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
+    mut service: Service,
+    force: Option<bool>,
+) -> Result<Service, AppError> {
+    let _timer = CommandTimer::start(&state, "save_service");
+    let mut state = write_state(&state);
+
+    let config = storage::load_validation_config(&state.data_path)?;
+    naming::enforce(&config, &service)?;
+    metadata_schema::enforce(&state.data_path, &config, &service)?;
+    lifecycle::enforce(state.storage.as_ref(), &environment, &service)?;
+
+    let force = force.unwrap_or(false);
+    let before = if force {
+        // A forced save may replace a file that doesn't load, unjournaled
+        journal::previous_service(state.storage.as_ref(), &environment, &service.id)
+    } else {
+        let stored = match state.storage.load_service(&environment, &service.id) {
+            Ok(stored) => Some(stored),
+            Err(AppError::ServiceNotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let stored_revision = stored.as_ref().map_or(0, |stored| stored.revision);
+        AppError::check_revision(&service.id, service.revision, stored_revision)?;
+        Some(stored)
+    };
+
+    let stored_revision = match &before {
+        Some(Some(stored)) => Some(stored.revision),
+        Some(None) => Some(0),
+        None => None,
+    };
+    service.revision = stored_revision.unwrap_or(service.revision) + 1;
 
     // Save to disk
-    storage::save_service(&state.data_path, &environment, &service)?;
+    state.storage.save_service(&environment, &service)?;
+    state.record_write(&environment);
 
-    // Update cache
-    state
-        .services_cache
-        .entry(environment)
-        .or_default()
-        .insert(service.id.clone(), service);
+    let previous = before.as_ref().and_then(Option::as_ref);
+    state.record_audit(
+        &environment,
+        &[AuditRecord::service(
+            "save_service",
+            previous,
+            Some(&service),
+        )],
+    );
 
-    Ok(())
+    if let Some(before) = before {
+        let description = match before {
+            Some(_) => format!("Updated service {}", service.id),
+            None => format!("Created service {}", service.id),
+        };
+        state.record_undo(
+            &environment,
+            JournalEntry::new(
+                description,
+                vec![Change::Service {
+                    id: service.id.clone(),
+                    before,
+                    after: Some(service.clone()),
+                }],
+            ),
+        );
+    }
+
+    let summary = format!("Update service {} in {}", service.id, environment);
+    git::auto_commit(
+        &app,
+        &state,
+        "save_service",
+        summary.clone(),
+        storage::service_file_path(&state.data_path, &environment, &service.id)
+            .into_iter()
+            .collect(),
+    );
+    webhooks::notify(
+        &app,
+        &state,
+        &environment,
+        WebhookEvent::DataChanged,
+        summary,
+    );
+
+    state.update_cached_service(&environment, &service.id, Some(&service));
+
+    Ok(service)
 }
 
 /// Deletes a service from the specified environment.
@@ -239,10 +553,12 @@ pub fn save_service(
 /// This command removes the service file from disk and removes the service
 /// from the in-memory cache. Note that this does NOT automatically delete
 /// relationships involving this service - use `delete_relationships_for_service`
-/// separately if needed.
+/// separately if needed. Nor is the service removed from its groups; validation
+/// reports those memberships as `orphaned_group_member` issues.
 ///
 /// # Arguments
 ///
+/// * `app` - The application handle, used to queue git auto-commits
 /// * `state` - The application state containing the cache and data path
 /// * `environment` - The name of the environment containing the service
 /// * `service_id` - The unique identifier of the service to delete
@@ -250,7 +566,6 @@ pub fn save_service(
 /// # Returns
 ///
 /// * `Ok(())` - If the service was successfully deleted
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
 /// * `Err(AppError::ServiceNotFound)` - If no service exists with the given ID
 /// * `Err(AppError::Io)` - If there's an error deleting from the filesystem
 ///
@@ -258,11 +573,14 @@ pub fn save_service(
 ///
 /// - Deletes the JSON file at `{data_path}/{environment}/services/{service_id}.json`
 /// - Removes the service from the in-memory cache
+/// - Records the deletion in the environment's undo journal
+/// - Appends the deletion to the environment's audit log
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
 ///
 /// # Warning
 ///
-/// This operation is irreversible. Consider warning users before deletion
-/// and handling orphaned relationships.
+/// The deletion can only be reverted with `undo` during the same session.
+/// Consider warning users before deletion and handling orphaned relationships.
 ///
 /// # Examples
 ///
@@ -274,20 +592,457 @@ pub fn save_service(
 /// });
 /// ```
 #[tauri::command(rename_all = "camelCase")]
-pub fn delete_service(
-    state: State<'_, Mutex<AppState>>,
+pub fn delete_service<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
     service_id: String,
 ) -> Result<(), AppError> {
-    let mut state = state.lock().map_err(|_| AppError::StateLock)?;
+    let _timer = CommandTimer::start(&state, "delete_service");
+    let mut state = write_state(&state);
+
+    let before = journal::previous_service(state.storage.as_ref(), &environment, &service_id);
 
     // Delete from disk
-    storage::delete_service_file(&state.data_path, &environment, &service_id)?;
+    state
+        .storage
+        .delete_service_file(&environment, &service_id)?;
+    state.record_write(&environment);
+
+    let previous = before.as_ref().and_then(Option::as_ref);
+    state.record_audit(
+        &environment,
+        &[AuditRecord::new("delete_service", vec![service_id.clone()])
+            .with_change(previous.map(audit::service_summary), None)],
+    );
 
-    // Update cache
-    if let Some(services_map) = state.services_cache.get_mut(&environment) {
-        services_map.remove(&service_id);
+    if let Some(before @ Some(_)) = before {
+        state.record_undo(
+            &environment,
+            JournalEntry::new(
+                format!("Deleted service {}", service_id),
+                vec![Change::Service {
+                    id: service_id.clone(),
+                    before,
+                    after: None,
+                }],
+            ),
+        );
     }
 
+    let summary = format!("Delete service {} in {}", service_id, environment);
+    git::auto_commit(
+        &app,
+        &state,
+        "delete_service",
+        summary.clone(),
+        storage::service_file_path(&state.data_path, &environment, &service_id)
+            .into_iter()
+            .collect(),
+    );
+    webhooks::notify(
+        &app,
+        &state,
+        &environment,
+        WebhookEvent::DataChanged,
+        summary.clone(),
+    );
+    webhooks::notify(
+        &app,
+        &state,
+        &environment,
+        WebhookEvent::ServiceDeleted,
+        summary,
+    );
+
+    state.update_cached_service(&environment, &service_id, None);
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::environments::create_environment;
+    use crate::commands::relationships::save_relationship;
+    use crate::storage::service_file_path;
+    use crate::test_support::{
+        block_on, relationship, service, snapshot_dir, SlowStorage, TestApp,
+    };
+    use std::fs;
+    use std::time::Duration;
+    use tauri::Manager;
+
+    #[test]
+    fn conflicting_edits_keep_the_first_save() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+
+        // Two windows read the same revision
+        let mut first = get_service_by_id(app.state(), "dev".into(), "api".into()).unwrap();
+        let mut second = first.clone();
+        assert_eq!(first.revision, 0);
+
+        first.description = Some("First edit".to_string());
+        let saved = save_service(app.handle(), app.state(), "dev".into(), first, None).unwrap();
+        assert_eq!(saved.revision, 1);
+
+        second.description = Some("Second edit".to_string());
+        let conflict = save_service(
+            app.handle(),
+            app.state(),
+            "dev".into(),
+            second.clone(),
+            None,
+        );
+        assert!(matches!(
+            conflict,
+            Err(AppError::Conflict {
+                expected_revision: 0,
+                actual_revision: 1,
+                ..
+            })
+        ));
+        let stored = get_service_by_id(app.state(), "dev".into(), "api".into()).unwrap();
+        assert_eq!(stored.description.as_deref(), Some("First edit"));
+
+        // Redone on top of the first edit, the second one goes through
+        second.revision = stored.revision;
+        let saved = save_service(app.handle(), app.state(), "dev".into(), second, None).unwrap();
+        assert_eq!(saved.revision, 2);
+        assert_eq!(saved.description.as_deref(), Some("Second edit"));
+    }
+
+    #[test]
+    fn new_services_start_at_revision_one() {
+        let app = TestApp::with_environment("dev", &[], &[]);
+
+        let saved = save_service(
+            app.handle(),
+            app.state(),
+            "dev".into(),
+            service("api"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(saved.revision, 1);
+
+        let stale = save_service(
+            app.handle(),
+            app.state(),
+            "dev".into(),
+            service("api"),
+            None,
+        );
+        assert!(matches!(stale, Err(AppError::Conflict { .. })));
+    }
+
+    #[test]
+    fn unreadable_stored_service_fails_unless_forced() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        let path = service_file_path(app.data_path(), "dev", "api").unwrap();
+        fs::write(&path, "{ not json").unwrap();
+
+        let result = save_service(
+            app.handle(),
+            app.state(),
+            "dev".into(),
+            service("api"),
+            None,
+        );
+        assert!(matches!(result, Err(AppError::FileLoad { .. })));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+
+        let forced = save_service(
+            app.handle(),
+            app.state(),
+            "dev".into(),
+            service("api"),
+            Some(true),
+        )
+        .unwrap();
+        assert_eq!(forced.revision, 1);
+        assert_ne!(fs::read_to_string(&path).unwrap(), "{ not json");
+    }
+
+    #[test]
+    fn ids_and_environments_cannot_escape_the_data_directory() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        app.create_environment("prod", &[service("api-gateway")], &[]);
+        let data_path = app.data_path();
+        // A sibling of the data directory that only an escaping write could create
+        let outside = format!(
+            "{}-escaped",
+            data_path.file_name().unwrap().to_string_lossy()
+        );
+        let files = snapshot_dir(data_path);
+        let is_rejected =
+            |result: Result<(), AppError>| matches!(result, Err(AppError::ValidationError(_)));
+
+        let escaping_service = service(&format!("../../{}/api", outside));
+        assert!(is_rejected(
+            save_service(
+                app.handle(),
+                app.state(),
+                "dev".into(),
+                escaping_service,
+                Some(true)
+            )
+            .map(drop)
+        ));
+        assert!(is_rejected(
+            save_service(
+                app.handle(),
+                app.state(),
+                format!("../{}", outside),
+                service("api"),
+                Some(true)
+            )
+            .map(drop)
+        ));
+        assert!(is_rejected(delete_service(
+            app.handle(),
+            app.state(),
+            "dev".into(),
+            "../../prod/services/api-gateway".into()
+        )));
+        assert!(is_rejected(delete_service(
+            app.handle(),
+            app.state(),
+            "../prod".into(),
+            "api-gateway".into()
+        )));
+        assert!(is_rejected(
+            get_service_by_id(
+                app.state(),
+                "dev".into(),
+                "../../prod/services/api-gateway".into()
+            )
+            .map(drop)
+        ));
+        assert!(is_rejected(
+            save_relationship(
+                app.handle(),
+                app.state(),
+                "dev".into(),
+                relationship("rel-1", "api", &format!("../../{}/db", outside)),
+                None
+            )
+            .map(drop)
+        ));
+        assert!(is_rejected(create_environment(
+            app.state(),
+            format!("../{}", outside)
+        )));
+
+        assert_eq!(snapshot_dir(data_path), files);
+        assert!(!data_path.parent().unwrap().join(&outside).exists());
+    }
+
+    #[test]
+    fn commands_work_the_same_on_both_backends() {
+        for app in [TestApp::new(), TestApp::with_sqlite()] {
+            app.create_environment("dev", &[service("api"), service("db")], &[]);
+            let mut web = service("web");
+            web.tags = vec!["public".to_string()];
+
+            save_service(app.handle(), app.state(), "dev".into(), web, None).unwrap();
+            save_relationship(
+                app.handle(),
+                app.state(),
+                "dev".into(),
+                relationship("web-api", "web", "api"),
+                None,
+            )
+            .unwrap();
+            delete_service(app.handle(), app.state(), "dev".into(), "db".into()).unwrap();
+
+            let stored = get_service_by_id(app.state(), "dev".into(), "web".into()).unwrap();
+            assert_eq!(stored.revision, 1);
+            assert_eq!(stored.tags, vec!["public"]);
+            let mut ids: Vec<String> = crate::state::cache::services(&app.state(), "dev")
+                .unwrap()
+                .into_iter()
+                .map(|s| s.id)
+                .collect();
+            ids.sort();
+            assert_eq!(ids, vec!["api", "web"]);
+            let relationships = crate::state::cache::relationships(&app.state(), "dev").unwrap();
+            let edges: Vec<(&str, &str)> = relationships
+                .iter()
+                .map(|r| (r.source.as_str(), r.target.as_str()))
+                .collect();
+            assert_eq!(edges, vec![("web", "api")]);
+        }
+    }
+
+    #[test]
+    fn summaries_are_a_small_fraction_of_the_full_services() {
+        let services: Vec<Service> = (0..200)
+            .map(|index| {
+                let mut service = service(&format!("service-{index:03}"));
+                service.team = Some("Platform".to_string());
+                service.tags = vec!["public".to_string(), "tier-1".to_string()];
+                service.description = Some("Handles requests. ".repeat(40));
+                for key in 0..20 {
+                    service.metadata.insert(
+                        format!("annotation-{key}"),
+                        serde_json::json!({ "value": "x".repeat(100), "source": "kubernetes" }),
+                    );
+                }
+                service
+            })
+            .collect();
+        let app = TestApp::with_environment(
+            "dev",
+            &services,
+            &[relationship("rel-1", "service-000", "service-001")],
+        );
+
+        let full = block_on(get_all_services(app.handle(), "dev".into())).unwrap();
+        let summaries = block_on(get_service_summaries(app.handle(), "dev".into())).unwrap();
+
+        let full_size = serde_json::to_vec(&full).unwrap().len();
+        let summary_size = serde_json::to_vec(&summaries).unwrap().len();
+        assert!(
+            summary_size * 20 < full_size,
+            "summaries take {summary_size} bytes, the services {full_size}"
+        );
+
+        let first = summaries.iter().find(|s| s.id == "service-000").unwrap();
+        let summary = serde_json::to_value(first).unwrap();
+        assert_eq!(summary["team"], "Platform");
+        assert_eq!(summary["tagCount"], 2);
+        assert_eq!(summary["hasDescription"], true);
+        assert_eq!(summary["outgoingCount"], 1);
+        assert_eq!(summary["incomingCount"], 0);
+        assert!(summary.get("metadata").is_none());
+        assert!(summary.get("description").is_none());
+    }
+
+    fn new_service_id(app: &TestApp, hint: &str) -> String {
+        generate_id(
+            app.state(),
+            "dev".to_string(),
+            IdKind::Service,
+            Some(hint.to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn generated_service_ids_are_valid_and_unique_in_the_environment() {
+        let long = "x".repeat(200);
+        let app = TestApp::with_environment(
+            "dev",
+            &[
+                service("payments-api"),
+                service("payments-api-2"),
+                service("service"),
+                service(&long[..storage::MAX_SLUG_LENGTH]),
+            ],
+            &[],
+        );
+
+        assert_eq!(new_service_id(&app, "Payments API"), "payments-api-3");
+        assert_eq!(new_service_id(&app, "🙂🚀"), "service-2");
+        assert_eq!(new_service_id(&app, ""), "service-2");
+        assert_eq!(new_service_id(&app, "Zürich"), "zurich");
+        let id = new_service_id(&app, &long);
+        assert_eq!(id.len(), storage::MAX_SLUG_LENGTH);
+        assert!(id.ends_with("x-2"));
+
+        for hint in ["../../etc", "🙂", "名前", "a/b\\c", &long] {
+            let id = new_service_id(&app, hint);
+            assert!(
+                storage::validate_service_id(&id).is_ok(),
+                "{:?} -> {:?}",
+                hint,
+                id
+            );
+            assert!(!cache::service_exists(&app.state(), "dev", &id).unwrap());
+        }
+    }
+
+    fn quick_open_ids(app: &TestApp, prefix: &str) -> Vec<(String, MatchRank)> {
+        quick_open(app.state(), "dev".into(), prefix.into(), None)
+            .unwrap()
+            .into_iter()
+            .map(|found| (found.service.id, found.rank))
+            .collect()
+    }
+
+    #[test]
+    fn quick_open_follows_saves_and_deletes_without_reloading() {
+        let app = TestApp::with_environment("dev", &[service("api"), service("db")], &[]);
+        let storage = SlowStorage::install(&app, Duration::ZERO, Duration::ZERO);
+        assert_eq!(
+            quick_open_ids(&app, "api"),
+            vec![("api".to_string(), MatchRank::ExactId)]
+        );
+        let loads = storage.service_loads();
+
+        // A new service
+        let billing = Service {
+            name: "Billing Engine".to_string(),
+            ..service("billing")
+        };
+        save_service(app.handle(), app.state(), "dev".into(), billing, None).unwrap();
+        assert_eq!(
+            quick_open_ids(&app, "engine"),
+            vec![("billing".to_string(), MatchRank::WordPrefix)]
+        );
+
+        // A renamed service
+        let mut api = get_service_by_id(app.state(), "dev".into(), "api".into()).unwrap();
+        api.name = "Gateway".to_string();
+        save_service(app.handle(), app.state(), "dev".into(), api, None).unwrap();
+        assert_eq!(
+            quick_open_ids(&app, "gate"),
+            vec![("api".to_string(), MatchRank::NamePrefix)]
+        );
+        assert_eq!(
+            quick_open_ids(&app, "api"),
+            vec![("api".to_string(), MatchRank::ExactId)]
+        );
+
+        // A deleted service
+        delete_service(app.handle(), app.state(), "dev".into(), "billing".into()).unwrap();
+        assert!(quick_open_ids(&app, "bill").is_empty());
+        assert!(quick_open_ids(&app, "engine").is_empty());
+        assert_eq!(
+            quick_open_ids(&app, ""),
+            vec![
+                ("api".to_string(), MatchRank::IdPrefix),
+                ("db".to_string(), MatchRank::IdPrefix),
+            ]
+        );
+
+        assert_eq!(storage.service_loads(), loads);
+    }
+
+    #[test]
+    fn reads_record_views_without_the_write_lock() {
+        let app = TestApp::with_environment("dev", &[service("api"), service("db")], &[]);
+        get_service_by_id(app.state(), "dev".into(), "db".into()).unwrap();
+
+        // Another reader holds the lock; a writer would have to wait for it
+        let state = app.state();
+        let reading = read_state(&state);
+        let handle = app.handle();
+        let (done, finished) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let state = handle.state::<RwLock<AppState>>();
+            let service = get_service_by_id(state, "dev".into(), "api".into());
+            done.send(service.map(|s| s.id)).unwrap();
+        });
+        let viewed = finished.recv_timeout(Duration::from_secs(5));
+        drop(reading);
+
+        assert_eq!(viewed.unwrap().unwrap(), "api");
+        let state = app.state();
+        let state = read_state(&state);
+        let recent = state.recent_services.get(&state.data_path, "dev");
+        let recent: Vec<&str> = recent.iter().map(|r| r.service_id.as_str()).collect();
+        assert_eq!(recent, ["api", "db"]);
+    }
+}
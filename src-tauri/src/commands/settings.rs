@@ -0,0 +1,648 @@
+//! Settings commands for the Tauri application.
+//!
+//! This module exposes the persisted user settings (see `models::Settings`)
+//! to the frontend, along with the pinned and recently viewed services the
+//! quick-switcher offers, and the webhook notifications are configured here
+//! (see `webhooks`). Services are recorded as recently viewed by
+//! `get_service_by_id` and `get_service_graph`.
+
+use std::collections::HashSet;
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::error::AppError;
+use crate::http_api::HttpApi;
+use crate::models::{
+    data_path_key, DisplayConfig, GraphPreferences, RecentService, ServiceSummary, Settings,
+    WebhookConfig, WebhookEvent,
+};
+use crate::redaction;
+use crate::scheduler::{self, Scheduler};
+use crate::state::{cache, read_state, write_state, AppState};
+use crate::storage;
+use crate::storage::audit::now_millis;
+use crate::webhooks::{self, WebhookPayload, WebhookSender};
+
+/// Retrieves the current application settings.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+///
+/// # Returns
+///
+/// * `Ok(Settings)` - The current settings
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const settings = await invoke('get_settings');
+/// console.log(settings.gitAutoCommit); // false
+/// ```
+#[tauri::command]
+pub fn get_settings(state: State<'_, RwLock<AppState>>) -> Result<Settings, AppError> {
+    let state = read_state(&state);
+    Ok(state.settings.clone())
+}
+
+/// Replaces the application settings and persists them.
+///
+/// The embedded HTTP API (see `http_api`) is started, restarted or stopped
+/// to match the new `httpApi` settings before they are saved, so settings
+/// the server can't run with are never stored. Enabled `scheduledExport`
+/// settings and the patterns of the `redaction` config are checked the
+/// same way.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to reach the HTTP API server and scheduler
+/// * `state` - The application state to update
+/// * `settings` - The complete new settings
+///
+/// # Returns
+///
+/// * `Ok(())` - If the settings were saved
+/// * `Err(AppError::ValidationError)` - If the HTTP API is enabled without a token,
+///   scheduled exports are enabled with an invalid schedule, target or environment,
+///   or a redaction pattern is invalid
+/// * `Err(AppError::Io)` - If the HTTP API port cannot be bound or the settings file
+///   cannot be written
+///
+/// # Side Effects
+///
+/// - Starts, restarts or stops the HTTP API server
+/// - Writes the settings file in the application config directory
+/// - Updates the settings in the application state (only if the write succeeded)
+/// - Records new webhook baselines if the webhook changed (see `set_webhook_config`)
+/// - Reschedules scheduled exports if their settings changed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const settings = await invoke('get_settings');
+/// await invoke('update_settings', { settings: { ...settings, gitAutoCommit: true } });
+/// ```
+#[tauri::command]
+pub fn update_settings<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    settings: Settings,
+) -> Result<(), AppError> {
+    scheduler::validate_settings(&settings.scheduled_export)?;
+    redaction::validate_config(&settings.redaction)?;
+    if let Some(http_api) = app.try_state::<Mutex<HttpApi>>() {
+        if let Ok(mut http_api) = http_api.lock() {
+            http_api.apply(&app, &settings.http_api)?;
+        }
+    }
+
+    let (webhook_changed, schedule_changed) = {
+        let mut state = write_state(&state);
+        let webhook_changed = state.settings.webhook != settings.webhook;
+        let schedule_changed = state.settings.scheduled_export != settings.scheduled_export;
+        store_settings(&mut state, settings)?;
+        (webhook_changed, schedule_changed)
+    };
+    if webhook_changed {
+        if let Some(webhook_sender) = app.try_state::<WebhookSender>() {
+            webhook_sender.record_baselines();
+        }
+    }
+    if schedule_changed {
+        if let Some(scheduler) = app.try_state::<Scheduler>() {
+            scheduler.reschedule();
+        }
+    }
+
+    Ok(())
+}
+
+/// Turns read-only mode on or off and persists the choice.
+///
+/// While it is on, every command that changes data (saves, deletes, batch
+/// operations, imports, fixes, environment creation and so on) fails with
+/// `AppError::ReadOnlyMode` without touching disk; reads, queries and
+/// exports keep working (see `read_only`).
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+/// * `enabled` - Whether read-only mode is on
+///
+/// # Returns
+///
+/// * `Ok(())` - If the setting was saved
+/// * `Err(AppError::Io)` - If the settings file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the settings file in the application config directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('set_read_only', { enabled: true });
+/// const settings = await invoke('get_settings');
+/// console.log(settings.readOnly); // true
+/// ```
+#[tauri::command]
+pub fn set_read_only(state: State<'_, RwLock<AppState>>, enabled: bool) -> Result<(), AppError> {
+    let mut state = write_state(&state);
+    let mut settings = state.settings.clone();
+    settings.read_only = enabled;
+    store_settings(&mut state, settings)
+}
+
+/// Pins a service to the top of the quick-switcher.
+///
+/// Pinning an already pinned service keeps its position. Pins are kept per
+/// data path, so the same-named environment of another workspace has pins
+/// of its own.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+/// * `environment` - The name of the environment containing the service
+/// * `service_id` - The service to pin
+///
+/// # Returns
+///
+/// * `Ok(())` - If the service is pinned
+/// * `Err(AppError::ServiceNotFound)` - If no service exists with the given ID
+/// * `Err(AppError::Io)` - If the settings file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the settings file in the application config directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('pin_service', { environment: 'prod', serviceId: 'billing-api' });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn pin_service(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    service_id: String,
+) -> Result<(), AppError> {
+    cache::service(&state, &environment, &service_id)?;
+
+    let mut state = write_state(&state);
+    let mut settings = state.settings.clone();
+    let pinned = settings
+        .pinned_services
+        .entry(data_path_key(&state.data_path))
+        .or_default()
+        .entry(environment)
+        .or_default();
+    if pinned.contains(&service_id) {
+        return Ok(());
+    }
+    pinned.push(service_id);
+
+    store_settings(&mut state, settings)
+}
+
+/// Unpins a service.
+///
+/// Unpinning a service that isn't pinned does nothing, so services that no
+/// longer exist can be unpinned as well.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+/// * `environment` - The name of the environment containing the service
+/// * `service_id` - The service to unpin
+///
+/// # Returns
+///
+/// * `Ok(())` - If the service is no longer pinned
+/// * `Err(AppError::Io)` - If the settings file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the settings file in the application config directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('unpin_service', { environment: 'prod', serviceId: 'billing-api' });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn unpin_service(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    service_id: String,
+) -> Result<(), AppError> {
+    let mut state = write_state(&state);
+    let mut settings = state.settings.clone();
+    let data_path = data_path_key(&state.data_path);
+    let Some(environments) = settings.pinned_services.get_mut(&data_path) else {
+        return Ok(());
+    };
+    let Some(pinned) = environments.get_mut(&environment) else {
+        return Ok(());
+    };
+    if !pinned.contains(&service_id) {
+        return Ok(());
+    }
+    pinned.retain(|id| *id != service_id);
+    if pinned.is_empty() {
+        environments.remove(&environment);
+    }
+    if environments.is_empty() {
+        settings.pinned_services.remove(&data_path);
+    }
+
+    store_settings(&mut state, settings)
+}
+
+/// Retrieves the pinned services of an environment.
+///
+/// Pinned services that have since been deleted are left out, but stay
+/// pinned in case they come back.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings and cache
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(Vec<ServiceSummary>)` - The pinned services, in the order they were pinned
+/// * `Err(AppError)` - If the services cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const pinned = await invoke('get_pinned_services', { environment: 'prod' });
+/// ```
+#[tauri::command]
+pub fn get_pinned_services(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<Vec<ServiceSummary>, AppError> {
+    let pinned = {
+        let state = read_state(&state);
+        state
+            .settings
+            .pinned(&state.data_path, &environment)
+            .to_vec()
+    };
+    if pinned.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let services = cache::shared_services(&state, &environment)?;
+    Ok(pinned
+        .iter()
+        .filter_map(|id| services.iter().find(|s| s.id == *id))
+        .map(|service| ServiceSummary::from(service.as_ref()))
+        .collect())
+}
+
+/// Retrieves the recently viewed services of an environment.
+///
+/// Services that have since been deleted are left out. Like pins, views are
+/// kept per data path (see `pin_service`).
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the recent views and cache
+/// * `environment` - The name of the environment
+/// * `limit` - Maximum number of services to return (default: all that are kept,
+///   up to 50)
+///
+/// # Returns
+///
+/// * `Ok(Vec<RecentService>)` - The services with when they were last viewed,
+///   most recent first
+/// * `Err(AppError)` - If the services cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const recent = await invoke('get_recent_services', { environment: 'prod', limit: 10 });
+/// ```
+#[tauri::command]
+pub fn get_recent_services(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    limit: Option<usize>,
+) -> Result<Vec<RecentService>, AppError> {
+    let recent = {
+        let state = read_state(&state);
+        state.recent_services.get(&state.data_path, &environment)
+    };
+    if recent.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let services = cache::shared_services(&state, &environment)?;
+    let existing: HashSet<&str> = services.iter().map(|s| s.id.as_str()).collect();
+    Ok(recent
+        .into_iter()
+        .filter(|r| existing.contains(r.service_id.as_str()))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+/// Retrieves the webhook configuration.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+///
+/// # Returns
+///
+/// * `Ok(Some(WebhookConfig))` - The configured webhook
+/// * `Ok(None)` - If no webhook is configured
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const webhook = await invoke('get_webhook_config');
+/// ```
+#[tauri::command]
+pub fn get_webhook_config(
+    state: State<'_, RwLock<AppState>>,
+) -> Result<Option<WebhookConfig>, AppError> {
+    Ok(read_state(&state).settings.webhook.clone())
+}
+
+/// Configures where webhook notifications are sent, or turns them off.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to reach the webhook thread
+/// * `state` - The application state containing the settings
+/// * `config` - The webhook to send to; `null` removes it
+///
+/// # Returns
+///
+/// * `Ok(())` - If the configuration was saved
+/// * `Err(AppError::ValidationError)` - If the URL isn't an http(s) URL
+/// * `Err(AppError::Io)` - If the settings file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the settings file in the application config directory
+/// - Records the current validation error counts in the background, as the
+///   baseline for `validationErrorsIncreased`
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('set_webhook_config', {
+///     config: {
+///         url: 'https://hooks.slack.com/services/T000/B000/XXXX',
+///         secret: 'shared-secret',
+///         events: ['validationErrorsIncreased', 'serviceDeleted']
+///     }
+/// });
+/// ```
+#[tauri::command]
+pub fn set_webhook_config<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    config: Option<WebhookConfig>,
+) -> Result<(), AppError> {
+    if let Some(config) = &config {
+        let url = config.url.trim();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(AppError::ValidationError(format!(
+                "Webhook URL must start with http:// or https://, got '{}'",
+                config.url
+            )));
+        }
+    }
+
+    {
+        let mut state = write_state(&state);
+        let mut settings = state.settings.clone();
+        settings.webhook = config;
+        store_settings(&mut state, settings)?;
+    }
+
+    if let Some(webhook_sender) = app.try_state::<WebhookSender>() {
+        webhook_sender.record_baselines();
+    }
+
+    Ok(())
+}
+
+/// Sends a test notification to the configured webhook.
+///
+/// Unlike other notifications, the test is sent right away, once, and a
+/// failure is returned, so the user can check the URL and receiver.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+///
+/// # Returns
+///
+/// * `Ok(())` - If the receiver accepted the notification
+/// * `Err(AppError::ValidationError)` - If no webhook is configured
+/// * `Err(AppError::WebhookFailed)` - If the request failed or got an error status
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('test_webhook');
+/// ```
+#[tauri::command]
+pub fn test_webhook(state: State<'_, RwLock<AppState>>) -> Result<(), AppError> {
+    let (config, environment) = {
+        let state = read_state(&state);
+        let config = state
+            .settings
+            .webhook
+            .clone()
+            .ok_or_else(|| AppError::ValidationError("No webhook is configured".to_string()))?;
+        (config, state.current_environment.clone())
+    };
+
+    let payload = WebhookPayload {
+        environment,
+        event: WebhookEvent::Test,
+        summary: "Test notification from the Dependency Mapping Tool".to_string(),
+        timestamp: now_millis(),
+    };
+    webhooks::send(&config, &payload)
+}
+
+/// Retrieves the colors, icons and line styles graphs are drawn with.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+///
+/// # Returns
+///
+/// * `Ok(DisplayConfig)` - The saved display config, or the built-in palette if none
+///   was saved
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const display = await invoke('get_display_config');
+/// console.log(display.serviceTypes.database.color); // "#DD6B20"
+/// ```
+#[tauri::command]
+pub fn get_display_config(state: State<'_, RwLock<AppState>>) -> Result<DisplayConfig, AppError> {
+    Ok(read_state(&state).settings.display.clone())
+}
+
+/// Replaces the display config the graph view and the exporters use.
+///
+/// Types and statuses the config leaves out are drawn with the built-in
+/// defaults.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+/// * `config` - The complete new display config
+///
+/// # Returns
+///
+/// * `Ok(())` - If the config was saved
+/// * `Err(AppError::ValidationError)` - If a color isn't a hex color or a color name
+/// * `Err(AppError::Io)` - If the settings file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the settings file in the application config directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const display = await invoke('get_display_config');
+/// display.serviceTypes.lambda = { color: '#FF9900', icon: 'bolt' };
+/// await invoke('save_display_config', { config: display });
+/// ```
+#[tauri::command]
+pub fn save_display_config(
+    state: State<'_, RwLock<AppState>>,
+    config: DisplayConfig,
+) -> Result<(), AppError> {
+    let colors = config.colors();
+    if let Some((key, color)) = colors.iter().find(|(_, color)| !is_valid_color(color)) {
+        return Err(AppError::ValidationError(format!(
+            "Color of '{}' must be a hex color like #3182CE or a color name, got '{}'",
+            key, color
+        )));
+    }
+
+    let mut state = write_state(&state);
+    let mut settings = state.settings.clone();
+    settings.display = config;
+    store_settings(&mut state, settings)
+}
+
+/// Retrieves the defaults `get_service_graph` uses for omitted parameters.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+///
+/// # Returns
+///
+/// The saved graph preferences, or the built-in defaults if none were saved
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: preselect the depth picker
+/// const preferences = await invoke('get_graph_preferences');
+/// setDepth(preferences.depth);
+/// ```
+#[tauri::command]
+pub fn get_graph_preferences(state: State<'_, RwLock<AppState>>) -> GraphPreferences {
+    read_state(&state).settings.graph_preferences.clone()
+}
+
+/// Replaces the defaults `get_service_graph` uses for omitted parameters.
+///
+/// The preferences apply to every environment. Invalid values, like a depth
+/// of 0 or an unknown status, are replaced by the defaults as they are when
+/// the settings are loaded (see `GraphPreferences`), so the returned
+/// preferences show what was stored.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+/// * `preferences` - The complete new graph preferences
+///
+/// # Returns
+///
+/// * `Ok(GraphPreferences)` - The preferences as saved
+/// * `Err(AppError::Io)` - If the settings file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes the settings file in the application config directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: open graphs two levels deep, without deprecated services
+/// await invoke('save_graph_preferences', {
+///     preferences: {
+///         depth: 2,
+///         direction: 'both',
+///         excludeStatuses: ['deprecated'],
+///         excludeServiceTypes: [],
+///         aggregateEdges: false
+///     }
+/// });
+/// ```
+#[tauri::command]
+pub fn save_graph_preferences(
+    state: State<'_, RwLock<AppState>>,
+    preferences: GraphPreferences,
+) -> Result<GraphPreferences, AppError> {
+    let mut state = write_state(&state);
+    let mut settings = state.settings.clone();
+    settings.graph_preferences = preferences;
+    store_settings(&mut state, settings)?;
+
+    Ok(state.settings.graph_preferences.clone())
+}
+
+/// Returns true if a color can be used in every export format: `#` with 3,
+/// 4, 6 or 8 hex digits, or a color name like `teal`.
+fn is_valid_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => {
+            matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic()),
+    }
+}
+
+/// Persists settings and installs them in the application state.
+///
+/// The state is only updated if the write succeeded.
+pub fn store_settings(state: &mut AppState, settings: Settings) -> Result<(), AppError> {
+    if let Some(settings_path) = &state.settings_path {
+        storage::save_settings(settings_path, &settings)?;
+    }
+
+    state.settings = settings;
+
+    Ok(())
+}
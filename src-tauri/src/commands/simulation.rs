@@ -0,0 +1,370 @@
+//! What-if simulation of the dependency graph.
+//!
+//! `simulate_graph` applies hypothetical changes (a service removed, edges
+//! added) to a copy of an environment's cached data and returns the graph
+//! as the changes would leave it, with the orphaned relationships and
+//! dependency cycles they would introduce. Nothing is written and the
+//! caches are left untouched, so a proposal can be reviewed before anyone
+//! acts on it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use tauri::State;
+
+use crate::commands::graph::{self, GraphData, GraphQuery};
+use crate::commands::validation::{self, ValidationIssue, ValidationResult};
+use crate::error::AppError;
+use crate::models::{Relationship, Service, TraversalDirection};
+use crate::state::diagnostics::CommandTimer;
+use crate::state::relationship_index::RelationshipIndex;
+use crate::state::{cache, read_state, AppState};
+use crate::storage;
+
+/// One hypothetical change of a simulation.
+///
+/// # Variants
+///
+/// * `AddService` - Add a service, or replace the one with the same ID
+/// * `RemoveService` - Remove a service; its relationships are kept, so they
+///   show up as orphaned
+/// * `AddRelationship` - Add a relationship, or replace the one with the same ID
+/// * `RemoveRelationship` - Remove a relationship
+///
+/// # Serialization
+///
+/// Tagged like the operations of a batch (see `batch::Operation`), e.g.
+/// `{ "op": "remove_service", "serviceId": "legacy-auth" }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum HypotheticalChange {
+    AddService { service: Service },
+    RemoveService { service_id: String },
+    AddRelationship { relationship: Relationship },
+    RemoveRelationship { relationship_id: String },
+}
+
+/// An environment's data as hypothetical changes would leave it.
+///
+/// # Fields
+///
+/// * `services` - Every service after the changes
+/// * `relationships` - Every relationship after the changes, in stored order
+///   with the added ones last
+/// * `graph` - The graph around the center service, if one was given
+/// * `validation` - The orphaned relationship and dependency cycle checks of the
+///   changed data
+/// * `new_issues` - The issues in `validation` the current data doesn't have
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphSimulation {
+    pub services: Vec<Service>,
+    pub relationships: Vec<Relationship>,
+    pub graph: Option<GraphData>,
+    pub validation: ValidationResult,
+    pub new_issues: Vec<ValidationIssue>,
+}
+
+/// Previews the graph after hypothetical changes, without writing anything.
+///
+/// The changes are applied in order to copies of the cached services and
+/// relationships. The result holds the whole changed graph and, with a
+/// center service, the graph around it as `get_service_graph` would build
+/// it from the changed data (following every relationship, whatever its
+/// effective dates). The orphaned relationship and dependency cycle checks
+/// of `validate_environment` are re-run on the changed data, and the issues
+/// the current data doesn't have are listed separately, e.g. the
+/// relationships a removed service would leave dangling.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `changes` - The hypothetical changes, applied in order
+/// * `center_service_id` - Optional service to center the graph on
+/// * `depth` - Optional traversal depth around the center (default: 1)
+///
+/// # Returns
+///
+/// * `Ok(GraphSimulation)` - The changed data, its graph and its new issues
+/// * `Err(AppError::BatchOperationFailed)` - If a change can't be applied (e.g., it
+///   removes a service that doesn't exist), with its index
+/// * `Err(AppError::ServiceNotFound)` - If the center service doesn't exist after
+///   the changes
+/// * `Err(AppError::FileLoad)` - If the validation config or suppressions can't be
+///   parsed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: what if legacy-auth went away and the gateway used sso?
+/// const simulation = await invoke('simulate_graph', {
+///     environment: 'prod',
+///     changes: [
+///         { op: 'remove_service', serviceId: 'legacy-auth' },
+///         {
+///             op: 'add_relationship',
+///             relationship: {
+///                 id: 'rel-gateway-sso',
+///                 source: 'api-gateway',
+///                 target: 'sso',
+///                 relationshipType: 'authenticates_via'
+///             }
+///         }
+///     ],
+///     centerServiceId: 'api-gateway'
+/// });
+/// console.log(`${simulation.newIssues.length} new problems`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn simulate_graph(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    changes: Vec<HypotheticalChange>,
+    center_service_id: Option<String>,
+    depth: Option<u32>,
+) -> Result<GraphSimulation, AppError> {
+    let _timer = CommandTimer::start(&state, "simulate_graph");
+    let data_path = read_state(&state).data_path.clone();
+    let config = storage::load_validation_config(&data_path)?;
+    let suppressions = storage::load_suppressions(&data_path, &environment)?;
+
+    // Copies of the cached data; the cache itself is left alone
+    let mut services = cache::shared_services(&state, &environment)?;
+    let mut relationships = cache::relationships(&state, &environment)?;
+    let before = validation::check_structure(&services, &relationships, &config, &suppressions);
+
+    for (index, change) in changes.into_iter().enumerate() {
+        apply_change(&mut services, &mut relationships, change).map_err(|e| {
+            AppError::BatchOperationFailed {
+                index,
+                source: Box::new(e),
+            }
+        })?;
+    }
+
+    let after = validation::check_structure(&services, &relationships, &config, &suppressions);
+    let known: HashSet<&str> = before
+        .issues
+        .iter()
+        .chain(&before.suppressed_issues)
+        .map(|issue| issue.fingerprint.as_str())
+        .collect();
+    let new_issues = after
+        .issues
+        .iter()
+        .filter(|issue| !known.contains(issue.fingerprint.as_str()))
+        .cloned()
+        .collect();
+
+    let graph = match center_service_id {
+        Some(center_service_id) => {
+            let query = GraphQuery {
+                depth: depth.unwrap_or(1),
+                relationship_types: None,
+                direction: TraversalDirection::Both,
+                include_effective_status: false,
+                include_criticality: false,
+                include_groups: false,
+                include_annotations: false,
+                max_nodes: None,
+                max_edges: None,
+                exclude_statuses: Vec::new(),
+                exclude_tags: Vec::new(),
+                exclude_service_ids: Vec::new(),
+                aggregate_edges: false,
+                as_of: None,
+            };
+            let relationship_index = RelationshipIndex::build(&relationships);
+            Some(graph::build_graph_from(
+                &state,
+                &environment,
+                &services,
+                &relationships,
+                &relationship_index,
+                &[center_service_id],
+                &query,
+            )?)
+        }
+        None => None,
+    };
+
+    Ok(GraphSimulation {
+        services: services.iter().map(|s| s.as_ref().clone()).collect(),
+        relationships,
+        graph,
+        validation: after,
+        new_issues,
+    })
+}
+
+/// Applies one hypothetical change to the copies of the data.
+fn apply_change(
+    services: &mut Vec<Arc<Service>>,
+    relationships: &mut Vec<Relationship>,
+    change: HypotheticalChange,
+) -> Result<(), AppError> {
+    match change {
+        HypotheticalChange::AddService { service } => {
+            storage::validate_service_id(&service.id)?;
+            match services.iter_mut().find(|s| s.id == service.id) {
+                Some(existing) => *existing = Arc::new(service),
+                None => services.push(Arc::new(service)),
+            }
+        }
+        HypotheticalChange::RemoveService { service_id } => {
+            let Some(position) = services.iter().position(|s| s.id == service_id) else {
+                return Err(AppError::ServiceNotFound(service_id));
+            };
+            services.remove(position);
+        }
+        HypotheticalChange::AddRelationship { relationship } => {
+            storage::validate_service_id(&relationship.source)?;
+            storage::validate_service_id(&relationship.target)?;
+            match relationships.iter_mut().find(|r| r.id == relationship.id) {
+                Some(existing) => *existing = relationship,
+                None => relationships.push(relationship),
+            }
+        }
+        HypotheticalChange::RemoveRelationship { relationship_id } => {
+            let Some(position) = relationships.iter().position(|r| r.id == relationship_id) else {
+                return Err(AppError::RelationshipNotFound(relationship_id));
+            };
+            relationships.remove(position);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::IssueType;
+    use crate::test_support::{relationship, service, snapshot_dir, TestApp};
+
+    fn shop() -> TestApp {
+        TestApp::with_environment(
+            "dev",
+            &[service("web"), service("api"), service("db")],
+            &[
+                relationship("web-api", "web", "api"),
+                relationship("api-db", "api", "db"),
+            ],
+        )
+    }
+
+    /// The cached data of the environment, serialized for comparison.
+    fn cached(app: &TestApp) -> serde_json::Value {
+        let mut services = cache::services(&app.state(), "dev").unwrap();
+        services.sort_by(|a, b| a.id.cmp(&b.id));
+        serde_json::json!({
+            "services": services,
+            "relationships": cache::relationships(&app.state(), "dev").unwrap(),
+        })
+    }
+
+    fn simulate(
+        app: &TestApp,
+        changes: Vec<HypotheticalChange>,
+        center: Option<&str>,
+    ) -> Result<GraphSimulation, AppError> {
+        simulate_graph(
+            app.state(),
+            "dev".to_string(),
+            changes,
+            center.map(str::to_string),
+            None,
+        )
+    }
+
+    fn issues_of(issues: &[ValidationIssue], issue_type: IssueType) -> Vec<Vec<String>> {
+        issues
+            .iter()
+            .filter(|issue| issue.issue_type == issue_type)
+            .map(|issue| issue.affected_ids.clone())
+            .collect()
+    }
+
+    #[test]
+    fn a_removal_surfaces_the_relationships_it_would_orphan() {
+        let app = shop();
+        let cached_before = cached(&app);
+        let files = snapshot_dir(app.data_path());
+
+        let simulation = simulate(
+            &app,
+            vec![HypotheticalChange::RemoveService {
+                service_id: "db".to_string(),
+            }],
+            None,
+        )
+        .unwrap();
+
+        let ids: Vec<&str> = simulation.services.iter().map(|s| s.id.as_str()).collect();
+        assert!(!ids.contains(&"db"));
+        assert_eq!(simulation.relationships.len(), 2);
+        let orphaned = issues_of(&simulation.new_issues, IssueType::OrphanedRelationship);
+        assert_eq!(orphaned.len(), 1);
+        assert!(orphaned[0].contains(&"api-db".to_string()));
+        assert!(simulation
+            .new_issues
+            .iter()
+            .all(|issue| !issue.affected_ids.contains(&"web-api".to_string())));
+
+        assert_eq!(cached(&app), cached_before);
+        assert_eq!(snapshot_dir(app.data_path()), files);
+    }
+
+    #[test]
+    fn an_added_edge_closing_a_loop_is_a_new_cycle_in_the_centered_graph() {
+        let app = shop();
+        let files = snapshot_dir(app.data_path());
+
+        let simulation = simulate(
+            &app,
+            vec![HypotheticalChange::AddRelationship {
+                relationship: relationship("db-web", "db", "web"),
+            }],
+            Some("db"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            issues_of(&simulation.new_issues, IssueType::CircularDependency).len(),
+            1
+        );
+        let graph = simulation.graph.unwrap();
+        assert_eq!(graph.center_service.id, "db");
+        let mut edges: Vec<&str> = graph.relationships.iter().map(|r| r.id.as_str()).collect();
+        edges.sort();
+        assert_eq!(edges, vec!["api-db", "db-web"]);
+        assert_eq!(snapshot_dir(app.data_path()), files);
+    }
+
+    #[test]
+    fn a_change_that_cannot_be_applied_names_its_index() {
+        let app = shop();
+        let cached_before = cached(&app);
+
+        let error = simulate(
+            &app,
+            vec![
+                HypotheticalChange::RemoveRelationship {
+                    relationship_id: "web-api".to_string(),
+                },
+                HypotheticalChange::RemoveService {
+                    service_id: "gone".to_string(),
+                },
+            ],
+            None,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AppError::BatchOperationFailed { index: 1, .. }
+        ));
+        assert_eq!(cached(&app), cached_before);
+    }
+}
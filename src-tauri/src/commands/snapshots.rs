@@ -0,0 +1,232 @@
+//! Environment snapshot commands for the Tauri application.
+//!
+//! A snapshot (see `models::Snapshot`) freezes an environment's services and
+//! relationships under a label, for example before an architecture review,
+//! so the environment can later be compared against it with `diff_snapshot`.
+//! Snapshots are stored outside the data the app loads, so they never show
+//! up as services or environments.
+
+use std::sync::RwLock;
+use tauri::State;
+
+use crate::diff::{self, EnvironmentDiff};
+use crate::error::AppError;
+use crate::models::{Snapshot, SnapshotInfo};
+use crate::state::{cache, read_state, write_state, AppState};
+use crate::storage;
+use crate::storage::audit::now_millis;
+
+/// Maximum number of label characters used in a snapshot ID.
+const MAX_SLUG_LENGTH: usize = 40;
+
+/// Takes a snapshot of an environment's current services and relationships.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `label` - A human-readable label, e.g. `"Before Q4 review"`
+///
+/// # Returns
+///
+/// * `Ok(SnapshotInfo)` - The new snapshot's ID, label, time and entity counts
+/// * `Err(AppError::ValidationError)` - If the label is empty or the environment name
+///   can't be used as a path
+/// * `Err(AppError::Io)` - If the snapshot cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `{environment}/.snapshots/{id}.json` in the data directory (or
+///   `{database}.snapshots/{environment}/{id}.json` next to a SQLite database),
+///   encrypted when a passphrase is set
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const snapshot = await invoke('create_snapshot', {
+///     environment: 'prod',
+///     label: 'Before Q4 review'
+/// });
+/// ```
+#[tauri::command]
+pub fn create_snapshot(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    label: String,
+) -> Result<SnapshotInfo, AppError> {
+    let label = label.trim().to_string();
+    if label.is_empty() {
+        return Err(AppError::ValidationError(
+            "Snapshot label cannot be empty".to_string(),
+        ));
+    }
+    storage::validate_environment_name(&environment)?;
+
+    let mut services: Vec<_> = cache::shared_services(&state, &environment)?
+        .iter()
+        .map(|s| s.as_ref().clone())
+        .collect();
+    services.sort_by(|a, b| a.id.cmp(&b.id));
+    let relationships = cache::shared_relationships(&state, &environment)?.to_vec();
+
+    let created_at = now_millis();
+    let snapshot = Snapshot {
+        id: format!("{}-{}", created_at, slugify(&label)),
+        label,
+        created_at,
+        services,
+        relationships,
+    };
+
+    let state = write_state(&state);
+    storage::save_snapshot(
+        &state.data_path,
+        &environment,
+        &snapshot,
+        state.encryption_key.as_ref(),
+    )?;
+
+    Ok(SnapshotInfo::from(&snapshot))
+}
+
+/// Lists the snapshots of an environment.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(Vec<SnapshotInfo>)` - The snapshots, newest first
+/// * `Err(AppError::Io)` - If the snapshot directory cannot be read
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const snapshots = await invoke('list_snapshots', { environment: 'prod' });
+/// ```
+#[tauri::command]
+pub fn list_snapshots(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<Vec<SnapshotInfo>, AppError> {
+    let state = read_state(&state);
+    let snapshots = storage::load_snapshots(
+        &state.data_path,
+        &environment,
+        state.encryption_key.as_ref(),
+    )?;
+
+    Ok(snapshots.iter().map(SnapshotInfo::from).collect())
+}
+
+/// Compares a snapshot with the environment's current data.
+///
+/// Services and relationships are matched by ID. Entities that exist now
+/// but not in the snapshot are added, those only in the snapshot are
+/// removed, and those in both that differ (other than in their revision)
+/// are changed, with the names of the differing fields.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+/// * `snapshot_id` - The ID of the snapshot to compare against
+///
+/// # Returns
+///
+/// * `Ok(EnvironmentDiff)` - What changed since the snapshot was taken
+/// * `Err(AppError::SnapshotNotFound)` - If the environment has no snapshot with the ID
+/// * `Err(AppError::FileLoad)` - If the snapshot cannot be read or parsed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const diff = await invoke('diff_snapshot', {
+///     environment: 'prod',
+///     snapshotId: '1767225600000-before-q4-review'
+/// });
+/// console.log(`${diff.services.removed.length} services removed since the review`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn diff_snapshot(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    snapshot_id: String,
+) -> Result<EnvironmentDiff, AppError> {
+    let snapshot = {
+        let state = read_state(&state);
+        storage::load_snapshot(
+            &state.data_path,
+            &environment,
+            &snapshot_id,
+            state.encryption_key.as_ref(),
+        )?
+        .ok_or(AppError::SnapshotNotFound(snapshot_id))?
+    };
+
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    Ok(diff::diff_environments(
+        &snapshot.services,
+        &snapshot.relationships,
+        &services,
+        &relationships,
+    ))
+}
+
+/// Deletes a snapshot.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+/// * `snapshot_id` - The ID of the snapshot
+///
+/// # Returns
+///
+/// * `Ok(())` - If the snapshot was deleted
+/// * `Err(AppError::SnapshotNotFound)` - If the environment has no snapshot with the ID
+/// * `Err(AppError::Io)` - If the snapshot cannot be deleted
+///
+/// # Side Effects
+///
+/// - Deletes `{environment}/.snapshots/{id}.json` in the data directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('delete_snapshot', {
+///     environment: 'prod',
+///     snapshotId: '1767225600000-before-q4-review'
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn delete_snapshot(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    snapshot_id: String,
+) -> Result<(), AppError> {
+    let state = write_state(&state);
+    if storage::delete_snapshot(&state.data_path, &environment, &snapshot_id)? {
+        Ok(())
+    } else {
+        Err(AppError::SnapshotNotFound(snapshot_id))
+    }
+}
+
+/// Turns a label into the lowercase, dash-separated part of a snapshot ID.
+fn slugify(label: &str) -> String {
+    let slug = storage::slugify(label, MAX_SLUG_LENGTH);
+    if slug.is_empty() {
+        "snapshot".to_string()
+    } else {
+        slug
+    }
+}
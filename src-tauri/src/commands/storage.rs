@@ -0,0 +1,121 @@
+//! Storage maintenance commands for the Tauri application.
+//!
+//! This module reports on the data directory itself (disk usage, stale and
+//! leftover files) rather than on the services and relationships in it.
+
+use serde::Serialize;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime};
+
+use crate::error::AppError;
+use crate::state::{read_state, run_blocking, AppState};
+use crate::storage::report::{self, EnvironmentStorageReport};
+
+/// Default age in days after which data files are reported as stale.
+const DEFAULT_STALE_DAYS: u64 = 180;
+
+/// Disk usage and file health across one or more environments.
+///
+/// # Fields
+///
+/// * `data_path` - The data directory that was scanned
+/// * `stale_after_days` - The staleness threshold that was applied
+/// * `service_file_count` - Total service files across the reported environments
+/// * `total_bytes` - Total size of the reported environments
+/// * `orphaned_bytes` - Total size of orphaned artifacts
+/// * `environments` - The per-environment reports
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    pub data_path: String,
+    pub stale_after_days: u64,
+    pub service_file_count: usize,
+    pub total_bytes: u64,
+    pub orphaned_bytes: u64,
+    pub environments: Vec<EnvironmentStorageReport>,
+}
+
+/// Reports disk usage and file health for the data directory.
+///
+/// Lists, per environment, the number of service files, total size, the
+/// largest files, data files that haven't changed in a long time, leftover
+/// artifacts (temporary files, backups, history directories), and whether
+/// `relationships.json` loads. Files are only stat'ed, not parsed, apart
+/// from `relationships.json`.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to scan on the blocking thread pool
+/// * `environment` - The environment to report on; all environments when omitted
+/// * `stale_days` - Age in days after which data files count as stale (default 180)
+///
+/// # Returns
+///
+/// * `Ok(StorageReport)` - The report, with totals over the reported environments
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory
+///   name, or the data path is a SQLite database
+/// * `Err(AppError::Io)` - If a directory cannot be listed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('get_storage_report', { staleDays: 365 });
+/// for (const env of report.environments) {
+///     console.log(`${env.environment}: ${env.totalBytes} bytes, ${env.staleFiles.length} stale`);
+/// }
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_storage_report<R: Runtime>(
+    app: AppHandle<R>,
+    environment: Option<String>,
+    stale_days: Option<u64>,
+) -> Result<StorageReport, AppError> {
+    run_blocking(app, move |state| {
+        build_storage_report(state, environment, stale_days)
+    })
+    .await
+}
+
+/// Scans the environments, for `get_storage_report`.
+fn build_storage_report(
+    state: &RwLock<AppState>,
+    environment: Option<String>,
+    stale_days: Option<u64>,
+) -> Result<StorageReport, AppError> {
+    let (fs_backend, environments) = {
+        let state = read_state(state);
+        let fs_backend = state.storage.as_file_system().cloned().ok_or_else(|| {
+            AppError::ValidationError(
+                "Storage reports are only available for directory data paths".to_string(),
+            )
+        })?;
+        let environments = match environment {
+            Some(environment) => vec![environment],
+            None => state.storage.list_environments()?,
+        };
+        (fs_backend, environments)
+    };
+
+    // Scanning can take a while on large directories; the lock is released
+    let stale_after_days = stale_days.unwrap_or(DEFAULT_STALE_DAYS);
+    let mut reports = Vec::new();
+    for environment in &environments {
+        reports.push(report::environment_report(
+            fs_backend.data_path(),
+            environment,
+            fs_backend.encryption_key(),
+            stale_after_days,
+        )?);
+    }
+    reports.sort_by(|a, b| a.environment.cmp(&b.environment));
+
+    Ok(StorageReport {
+        data_path: fs_backend.data_path().display().to_string(),
+        stale_after_days,
+        service_file_count: reports.iter().map(|r| r.service_file_count).sum(),
+        total_bytes: reports.iter().map(|r| r.total_bytes).sum(),
+        orphaned_bytes: reports.iter().map(|r| r.orphaned_bytes).sum(),
+        environments: reports,
+    })
+}
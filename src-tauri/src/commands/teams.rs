@@ -0,0 +1,687 @@
+//! Team-scoped views of an environment.
+//!
+//! Team leads only care about their slice of the map. `get_team_overview`
+//! returns the services of one team, the relationships among them, the
+//! edges to and from other teams and the validation issues concerning the
+//! team, and `validate_environment` can group its issues the same way (see
+//! `group_issues`). When a team is disbanded, `transfer_ownership` hands
+//! its services over to another team.
+//!
+//! Teams are matched by the `team` field of the services, ignoring case and
+//! surrounding whitespace, and are named as the first of their services
+//! spells them. Services without a team belong to `UNASSIGNED_TEAM`.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime};
+
+use crate::commands::batch::{Operation, Plan};
+use crate::commands::validation::{self, ValidationIssue};
+use crate::dates;
+use crate::error::AppError;
+use crate::models::{IssueSeverity, Relationship, Service};
+use crate::state::{cache, read_state, run_blocking, write_state, AppState};
+
+/// The team of the services without one.
+pub const UNASSIGNED_TEAM: &str = "unassigned";
+
+/// The metadata key of the notes `transfer_ownership` appends to each service.
+pub const TRANSFERS_KEY: &str = "ownershipTransfers";
+
+/// The relationships between a team and one other team.
+///
+/// # Fields
+///
+/// * `team` - The other team
+/// * `relationships` - The relationships, ordered like the stored ones
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamEdges {
+    pub team: String,
+    pub relationships: Vec<Relationship>,
+}
+
+/// The validation issues concerning one team.
+///
+/// # Fields
+///
+/// * `team` - The team
+/// * `issues` - The issues, ordered like those of the validation result
+/// * `error_count` / `warning_count` / `info_count` - The issues by severity
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamIssues {
+    pub team: String,
+    pub issues: Vec<ValidationIssue>,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub info_count: usize,
+}
+
+/// One team's slice of an environment.
+///
+/// # Fields
+///
+/// * `team` - The team
+/// * `services` - The team's services
+/// * `relationships` - The relationships among the team's services
+/// * `outbound` - Relationships from the team's services to other teams, grouped by
+///   the other team (sorted)
+/// * `inbound` - Relationships from other teams to the team's services, grouped the
+///   same way
+/// * `issues` - The validation issues concerning the team (see `group_issues`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamOverview {
+    pub team: String,
+    pub services: Vec<Service>,
+    pub relationships: Vec<Relationship>,
+    pub outbound: Vec<TeamEdges>,
+    pub inbound: Vec<TeamEdges>,
+    pub issues: TeamIssues,
+}
+
+/// The outcome of `transfer_ownership`.
+///
+/// # Fields
+///
+/// * `transferred_service_ids` - The services moved to the new team, sorted
+/// * `cross_team_relationships` - Relationships in effect today between a moved
+///   service and a service of another team, as the transfer leaves them
+/// * `dry_run` - True if nothing was written
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnershipTransfer {
+    pub transferred_service_ids: Vec<String>,
+    pub cross_team_relationships: Vec<Relationship>,
+    pub dry_run: bool,
+}
+
+/// Returns one team's services, relationships and validation issues.
+///
+/// Only the relationships in effect today are included. Relationships to
+/// services that don't exist are left out of the edges; validation reports
+/// them as orphaned.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool
+/// * `environment` - The name of the environment
+/// * `team` - The team, in any case; "unassigned" for the services without a team
+///
+/// # Returns
+///
+/// * `Ok(TeamOverview)` - The team's slice of the environment
+/// * `Err(AppError::ValidationError)` - If no service belongs to the team
+/// * `Err(AppError::Io)` - If there's an error reading the data files
+/// * `Err(AppError::FileLoad)` - If the validation config or suppressions file isn't
+///   valid JSON
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const overview = await invoke('get_team_overview', {
+///     environment: 'prod',
+///     team: 'payments'
+/// });
+/// for (const edges of overview.inbound) {
+///     console.log(`${edges.team} uses us ${edges.relationships.length} times`);
+/// }
+/// ```
+#[tauri::command]
+pub async fn get_team_overview<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    team: String,
+) -> Result<TeamOverview, AppError> {
+    run_blocking(app, move |state| team_overview(state, &environment, &team)).await
+}
+
+/// Builds the overview of a team, for `get_team_overview`.
+fn team_overview(
+    state: &RwLock<AppState>,
+    environment: &str,
+    team: &str,
+) -> Result<TeamOverview, AppError> {
+    let validation = validation::run_validation(state, environment)?;
+    let services = cache::shared_services(state, environment)?;
+    let relationships = cache::shared_relationships(state, environment)?;
+    let teams = Teams::of(&services);
+
+    let key = team_key(Some(team));
+    let Some(name) = teams.names.get(&key) else {
+        return Err(AppError::ValidationError(format!(
+            "No service belongs to team '{}'",
+            team.trim()
+        )));
+    };
+
+    let today = dates::today();
+    let mut internal = Vec::new();
+    let mut outbound: BTreeMap<&str, Vec<Relationship>> = BTreeMap::new();
+    let mut inbound: BTreeMap<&str, Vec<Relationship>> = BTreeMap::new();
+    for relationship in relationships.iter().filter(|r| r.is_effective_on(&today)) {
+        let (Some(source), Some(target)) = (
+            teams.of_service(&relationship.source),
+            teams.of_service(&relationship.target),
+        ) else {
+            continue;
+        };
+        match (source == key, target == key) {
+            (true, true) => internal.push(relationship.clone()),
+            (true, false) => outbound
+                .entry(target)
+                .or_default()
+                .push(relationship.clone()),
+            (false, true) => inbound
+                .entry(source)
+                .or_default()
+                .push(relationship.clone()),
+            (false, false) => {}
+        }
+    }
+    let edges = |groups: BTreeMap<&str, Vec<Relationship>>| -> Vec<TeamEdges> {
+        groups
+            .into_iter()
+            .map(|(other, relationships)| TeamEdges {
+                team: teams.names[other].clone(),
+                relationships,
+            })
+            .collect()
+    };
+
+    let issues = group_issues(&services, &relationships, &validation.issues)
+        .into_iter()
+        .find(|issues| team_key(Some(issues.team.as_str())) == key)
+        .unwrap_or_else(|| team_issues(name.clone(), Vec::new()));
+
+    Ok(TeamOverview {
+        team: name.clone(),
+        services: services
+            .iter()
+            .filter(|s| team_key(s.team.as_deref()) == key)
+            .map(|s| s.as_ref().clone())
+            .collect(),
+        relationships: internal,
+        outbound: edges(outbound),
+        inbound: edges(inbound),
+        issues,
+    })
+}
+
+/// Moves every service of one team to another, e.g. when a team is disbanded.
+///
+/// Each service of `from_team` (matched ignoring case, "unassigned" for the
+/// services without a team) gets `to_team` as its team and, if given,
+/// `new_owner` as its owner. A note with the date and the previous team
+/// and owner is appended to the `ownershipTransfers` metadata list of each
+/// service. All services are saved as one batch (see `execute_batch`), so
+/// the transfer is undone as one step and recorded in the history.
+///
+/// The result lists the relationships that cross the new team boundary,
+/// whose other ends' owners the new team should talk to.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to run on the blocking thread pool and
+///   to queue git auto-commits
+/// * `environment` - The name of the environment
+/// * `from_team` - The team whose services move
+/// * `to_team` - The team they move to
+/// * `new_owner` - Optional new owner of every moved service
+/// * `dry_run` - If true, only compute the transfer (default: false)
+///
+/// # Returns
+///
+/// * `Ok(OwnershipTransfer)` - The moved services and the cross-team relationships;
+///   empty if no service belongs to `from_team`
+/// * `Err(AppError::ValidationError)` - If `to_team` is blank or the same team as
+///   `from_team`
+/// * `Err(AppError::Io)` - If there's an error reading or writing the data
+///
+/// # Side Effects
+///
+/// Unless `dry_run` is set and if any service moved:
+/// - Writes the moved service files
+/// - Records the transfer as a single entry in the undo journal
+/// - Appends one audit log record per moved service
+/// - Commits the change to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: preview handing legacy-platform over to core-platform
+/// const preview = await invoke('transfer_ownership', {
+///     environment: 'prod',
+///     fromTeam: 'legacy-platform',
+///     toTeam: 'core-platform',
+///     newOwner: 'jane@example.com',
+///     dryRun: true
+/// });
+/// console.log(`${preview.crossTeamRelationships.length} edges to discuss`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn transfer_ownership<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    from_team: String,
+    to_team: String,
+    new_owner: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<OwnershipTransfer, AppError> {
+    run_blocking(app.clone(), move |state| {
+        let transfer = Transfer {
+            from_team: &from_team,
+            to_team: to_team.trim(),
+            new_owner: new_owner
+                .as_deref()
+                .map(str::trim)
+                .filter(|o| !o.is_empty()),
+        };
+        transfer.run(&app, state, &environment, dry_run.unwrap_or(false))
+    })
+    .await
+}
+
+/// A handover of a team's services, for `transfer_ownership`.
+///
+/// # Fields
+///
+/// * `from_team` - The team whose services move
+/// * `to_team` - The team they move to, trimmed
+/// * `new_owner` - Their new owner, if it changes
+struct Transfer<'a> {
+    from_team: &'a str,
+    to_team: &'a str,
+    new_owner: Option<&'a str>,
+}
+
+impl Transfer<'_> {
+    /// Computes the transfer and, unless it is a dry run, saves it as one batch.
+    fn run<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        state: &RwLock<AppState>,
+        environment: &str,
+        dry_run: bool,
+    ) -> Result<OwnershipTransfer, AppError> {
+        if self.to_team.is_empty() {
+            return Err(AppError::ValidationError(
+                "Services cannot be transferred to a blank team".to_string(),
+            ));
+        }
+        if team_key(Some(self.from_team)) == team_key(Some(self.to_team)) {
+            return Err(AppError::ValidationError(format!(
+                "Services of team '{}' cannot be transferred to the same team",
+                self.from_team.trim()
+            )));
+        }
+
+        if dry_run {
+            let state = read_state(state);
+            let (plan, mut transfer) = self.plan(&state, environment)?;
+            transfer.transferred_service_ids = saved_ids(&plan);
+            transfer.dry_run = true;
+            return Ok(transfer);
+        }
+
+        let mut state = write_state(state);
+        let (plan, mut transfer) = self.plan(&state, environment)?;
+        transfer.transferred_service_ids = saved_ids(&plan);
+        let description = format!(
+            "Transferred {} services from team {} to {}",
+            transfer.transferred_service_ids.len(),
+            self.from_team.trim(),
+            self.to_team
+        );
+        plan.apply(
+            app,
+            &mut state,
+            environment,
+            "transfer_ownership",
+            description,
+        )?;
+
+        Ok(transfer)
+    }
+
+    /// Plans the saves of the moved services and finds the relationships
+    /// crossing the new team boundary.
+    fn plan(
+        &self,
+        state: &AppState,
+        environment: &str,
+    ) -> Result<(Plan, OwnershipTransfer), AppError> {
+        let from_key = team_key(Some(self.from_team));
+        let to_key = team_key(Some(self.to_team));
+        let today = dates::today();
+
+        let mut services = state.storage.load_services(environment)?;
+        let mut operations = Vec::new();
+        let mut moved = HashSet::new();
+        for service in services
+            .iter_mut()
+            .filter(|s| team_key(s.team.as_deref()) == from_key)
+        {
+            let mut note = json!({ "date": today, "previousTeam": service.team });
+            if let Some(owner) = self.new_owner {
+                note["previousOwner"] = json!(service.owner);
+                service.owner = Some(owner.to_string());
+            }
+            match service.metadata.get_mut(TRANSFERS_KEY) {
+                Some(Value::Array(notes)) => notes.push(note),
+                Some(other) => *other = Value::Array(vec![other.take(), note]),
+                None => {
+                    service
+                        .metadata
+                        .insert(TRANSFERS_KEY.to_string(), Value::Array(vec![note]));
+                }
+            }
+            service.team = Some(self.to_team.to_string());
+
+            moved.insert(service.id.clone());
+            operations.push(Operation::SaveService {
+                service: service.clone(),
+                force: false,
+            });
+        }
+        let plan = Plan::compute(state, environment, operations)?;
+
+        // Team keys as the transfer leaves them
+        let teams = Teams::of(&services);
+        let crosses = |moved_end: &str, other_end: &str| {
+            moved.contains(moved_end)
+                && !moved.contains(other_end)
+                && teams
+                    .of_service(other_end)
+                    .is_some_and(|team| team != to_key)
+        };
+        let cross_team_relationships = state
+            .storage
+            .load_relationships(environment)?
+            .into_iter()
+            .filter(|r| r.is_effective_on(&today))
+            .filter(|r| crosses(&r.source, &r.target) || crosses(&r.target, &r.source))
+            .collect();
+
+        Ok((
+            plan,
+            OwnershipTransfer {
+                transferred_service_ids: Vec::new(),
+                cross_team_relationships,
+                dry_run: false,
+            },
+        ))
+    }
+}
+
+/// Returns the sorted IDs of the services a plan saves.
+fn saved_ids(plan: &Plan) -> Vec<String> {
+    let mut ids: Vec<String> = plan
+        .result
+        .saved_services
+        .iter()
+        .map(|s| s.id.clone())
+        .collect();
+    ids.sort();
+    ids
+}
+
+/// Groups validation issues by the teams they concern.
+///
+/// An issue concerns the teams of the services among its affected IDs and
+/// of both ends of the relationships among them, so an issue about a
+/// cross-team edge is listed under both teams. Issues concerning no
+/// service, e.g. about a file, aren't listed under any team.
+///
+/// # Arguments
+///
+/// * `services` - The services of the environment
+/// * `relationships` - Its relationships
+/// * `issues` - The issues to group
+///
+/// # Returns
+///
+/// The teams with issues, sorted by their lowercased names; the issues of
+/// the services without a team are listed under "unassigned".
+pub fn group_issues<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    issues: &[ValidationIssue],
+) -> Vec<TeamIssues> {
+    let teams = Teams::of(services);
+    let ends: HashMap<&str, [&str; 2]> = relationships
+        .iter()
+        .map(|r| (r.id.as_str(), [r.source.as_str(), r.target.as_str()]))
+        .collect();
+
+    let mut grouped: BTreeMap<&str, Vec<ValidationIssue>> = BTreeMap::new();
+    for issue in issues {
+        let mut issue_teams = BTreeSet::new();
+        for id in &issue.affected_ids {
+            issue_teams.extend(teams.of_service(id));
+            for end in ends.get(id.as_str()).into_iter().flatten() {
+                issue_teams.extend(teams.of_service(end));
+            }
+        }
+        for team in issue_teams {
+            grouped.entry(team).or_default().push(issue.clone());
+        }
+    }
+
+    grouped
+        .into_iter()
+        .map(|(key, issues)| team_issues(teams.names[key].clone(), issues))
+        .collect()
+}
+
+/// Counts the issues of a team by severity.
+fn team_issues(team: String, issues: Vec<ValidationIssue>) -> TeamIssues {
+    let count = |severity: IssueSeverity| issues.iter().filter(|i| i.severity == severity).count();
+    TeamIssues {
+        team,
+        error_count: count(IssueSeverity::Error),
+        warning_count: count(IssueSeverity::Warning),
+        info_count: count(IssueSeverity::Info),
+        issues,
+    }
+}
+
+/// Returns the key a team is matched by: trimmed and lowercased, or
+/// `UNASSIGNED_TEAM` for a missing or blank team.
+fn team_key(team: Option<&str>) -> String {
+    match team.map(str::trim) {
+        Some(team) if !team.is_empty() => team.to_lowercase(),
+        _ => UNASSIGNED_TEAM.to_string(),
+    }
+}
+
+/// The teams of the services of an environment.
+///
+/// # Fields
+///
+/// * `names` - The name of each team by its key
+/// * `services` - The key of each service's team by service ID
+struct Teams<'a> {
+    names: HashMap<String, String>,
+    services: HashMap<&'a str, String>,
+}
+
+impl<'a> Teams<'a> {
+    /// Collects the teams of services.
+    fn of<S: Borrow<Service>>(services: &'a [S]) -> Self {
+        let mut teams = Teams {
+            names: HashMap::new(),
+            services: HashMap::new(),
+        };
+        for service in services {
+            let service = service.borrow();
+            let key = team_key(service.team.as_deref());
+            let name = match &service.team {
+                Some(team) if key != UNASSIGNED_TEAM => team.trim(),
+                _ => UNASSIGNED_TEAM,
+            };
+            teams
+                .names
+                .entry(key.clone())
+                .or_insert_with(|| name.to_string());
+            teams.services.insert(service.id.as_str(), key);
+        }
+        teams
+    }
+
+    /// Returns the key of a service's team, if the service exists.
+    fn of_service(&self, service_id: &str) -> Option<&str> {
+        self.services.get(service_id).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::validation::validate_environment;
+    use crate::models::IssueType;
+    use crate::test_support::{block_on, relationship, service, TestApp};
+
+    fn on_team(id: &str, team: Option<&str>) -> Service {
+        let mut service = service(id);
+        service.team = team.map(str::to_string);
+        service
+    }
+
+    /// Payments, Identity and Logistics, one unassigned service and edges
+    /// between all of them.
+    fn three_teams() -> TestApp {
+        TestApp::with_environment(
+            "dev",
+            &[
+                on_team("billing", Some("Payments")),
+                on_team("ledger", Some(" PAYMENTS ")),
+                on_team("auth", Some("Identity")),
+                on_team("users", Some("identity")),
+                on_team("shipping", Some("Logistics")),
+                on_team("legacy", None),
+            ],
+            &[
+                relationship("billing-ledger", "billing", "ledger"),
+                relationship("billing-auth", "billing", "auth"),
+                relationship("ledger-users", "ledger", "users"),
+                relationship("shipping-billing", "shipping", "billing"),
+                relationship("auth-ledger", "auth", "ledger"),
+                relationship("legacy-ledger", "legacy", "ledger"),
+                relationship("shipping-auth", "shipping", "auth"),
+                relationship("billing-ghost", "billing", "ghost"),
+            ],
+        )
+    }
+
+    fn overview(app: &TestApp, team: &str) -> TeamOverview {
+        block_on(get_team_overview(
+            app.handle(),
+            "dev".to_string(),
+            team.to_string(),
+        ))
+        .unwrap()
+    }
+
+    fn grouped(edges: &[TeamEdges]) -> Vec<(String, Vec<&str>)> {
+        edges
+            .iter()
+            .map(|e| {
+                let mut ids: Vec<&str> = e.relationships.iter().map(|r| r.id.as_str()).collect();
+                ids.sort_unstable();
+                (e.team.to_lowercase(), ids)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cross_team_edges_are_grouped_by_the_other_team() {
+        let app = three_teams();
+
+        let payments = overview(&app, "payments");
+
+        assert_eq!(payments.team.trim().to_lowercase(), "payments");
+        let mut ids: Vec<&str> = payments.services.iter().map(|s| s.id.as_str()).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, ["billing", "ledger"]);
+        let internal: Vec<&str> = payments
+            .relationships
+            .iter()
+            .map(|r| r.id.as_str())
+            .collect();
+        assert_eq!(internal, ["billing-ledger"]);
+        assert_eq!(
+            grouped(&payments.outbound),
+            [("identity".to_string(), vec!["billing-auth", "ledger-users"])]
+        );
+        assert_eq!(
+            grouped(&payments.inbound),
+            [
+                ("identity".to_string(), vec!["auth-ledger"]),
+                ("logistics".to_string(), vec!["shipping-billing"]),
+                ("unassigned".to_string(), vec!["legacy-ledger"]),
+            ]
+        );
+
+        let identity = overview(&app, "IDENTITY");
+        assert_eq!(
+            grouped(&identity.outbound),
+            [("payments".to_string(), vec!["auth-ledger"])]
+        );
+        assert_eq!(
+            grouped(&identity.inbound),
+            [
+                ("logistics".to_string(), vec!["shipping-auth"]),
+                ("payments".to_string(), vec!["billing-auth", "ledger-users"]),
+            ]
+        );
+
+        let unassigned = overview(&app, UNASSIGNED_TEAM);
+        assert_eq!(unassigned.services[0].id, "legacy");
+        assert!(unassigned.inbound.is_empty());
+        assert!(matches!(
+            block_on(get_team_overview(
+                app.handle(),
+                "dev".to_string(),
+                "nobody".to_string()
+            )),
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn issues_are_listed_under_the_teams_they_concern() {
+        let app = three_teams();
+        let orphaned = |issues: &TeamIssues| {
+            issues
+                .issues
+                .iter()
+                .any(|i| i.issue_type == IssueType::OrphanedRelationship)
+        };
+
+        let payments = overview(&app, "Payments");
+        assert!(orphaned(&payments.issues));
+        assert!(payments.issues.error_count >= 1);
+        assert!(!orphaned(&overview(&app, "identity").issues));
+
+        let result = block_on(validate_environment(
+            app.handle(),
+            "dev".to_string(),
+            None,
+            Some(true),
+        ))
+        .unwrap();
+        let by_team = result.issues_by_team.unwrap();
+        let with_orphans: Vec<String> = by_team
+            .iter()
+            .filter(|issues| orphaned(issues))
+            .map(|issues| issues.team.trim().to_lowercase())
+            .collect();
+        assert_eq!(with_orphans, ["payments"]);
+    }
+}
@@ -2,297 +2,3293 @@
 //!
 //! This module provides comprehensive data integrity validation for environments,
 //! detecting issues such as orphaned relationships, circular dependencies,
-//! duplicate IDs, and missing required fields.
+//! duplicate IDs, missing required fields, and service files whose names don't
+//! match the IDs they contain.
 
-use serde::Serialize;
-use std::collections::{HashMap, HashSet};
-use std::sync::Mutex;
-use tauri::State;
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Runtime, State};
 
+use crate::commands::placeholders::{placeholder_service, PlaceholderDefaults};
+use crate::commands::teams::{self, TeamIssues};
+use crate::commands::{batch, external, ownership};
+use crate::dates;
 use crate::error::AppError;
-use crate::models::{RelationshipType, Service};
-use crate::state::AppState;
-use crate::storage::loader;
+use crate::git;
+use crate::lifecycle;
+use crate::metadata_schema::{self, MetadataSchemas};
+use crate::models::{
+    CustomTypeDefinition, CustomTypeKind, Group, IssueSeverity, IssueType, NodeAnnotation,
+    Relationship, RelationshipOrigin, RelationshipType, ReviewStatus, Service, ServiceLifecycle,
+    ServiceStatus, ServiceType, Suppression, ValidationConfig,
+};
+use crate::naming::NamingChecker;
+use crate::ports;
+use crate::redaction;
+use crate::similarity;
+use crate::state::diagnostics::CommandTimer;
+use crate::state::journal::{Change, JournalEntry};
+use crate::state::progress::Progress;
+use crate::state::{cache, read_state, run_blocking, write_state, AppState};
+use crate::storage::audit::{self, AuditRecord};
+use crate::storage::{self, loader, FileSystemBackend, StorageBackend};
 
-/// Severity levels for validation issues.
+/// Represents a single validation issue found in the environment data.
+///
+/// Contains all information needed to display the issue in the UI and
+/// help the user resolve it.
+///
+/// # Fields
+///
+/// * `severity` - How critical the issue is (error, warning, info)
+/// * `issue_type` - The category of the issue
+/// * `message` - Human-readable description of the problem
+/// * `affected_ids` - IDs of services/relationships involved
+/// * `suggestion` - Optional recommendation for fixing the issue
+/// * `fingerprint` - Stable identifier of the issue across runs, used to suppress
+///   it (see `issue_fingerprint`); set by `ValidationResult::from_issues`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub severity: IssueSeverity,
+    pub issue_type: IssueType,
+    pub message: String,
+    pub affected_ids: Vec<String>,
+    pub suggestion: Option<String>,
+    pub fingerprint: String,
+}
+
+/// The complete result of validating an environment.
+///
+/// Contains all discovered issues along with summary counts for quick
+/// assessment of the environment's health.
+///
+/// # Fields
+///
+/// * `issues` - All validation issues found, except suppressed ones
+/// * `error_count` - Number of critical errors
+/// * `warning_count` - Number of warnings
+/// * `info_count` - Number of informational notices
+/// * `suppressed_issues` - Issues matching an active suppression; not counted
+/// * `issues_by_team` - The issues grouped by the teams they concern (see
+///   `teams::group_issues`), only if asked for
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationResult {
+    pub issues: Vec<ValidationIssue>,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub info_count: usize,
+    pub suppressed_issues: Vec<ValidationIssue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issues_by_team: Option<Vec<TeamIssues>>,
+}
+
+/// Validates the entire environment for data integrity issues.
+///
+/// Performs comprehensive validation of all services and relationships in
+/// the specified environment. This includes checking for:
+///
+/// 1. **Duplicate Service IDs** (Error) - Multiple services with the same ID
+/// 2. **Missing Required Fields** (Error) - Services without id or name
+/// 3. **Orphaned Relationships** (Error) - Relationships referencing non-existent services
+/// 4. **Invalid Relationship Types** (Warning) - Unknown relationship types
+/// 5. **Circular Dependencies** (Warning) - Dependency cycles in "depends_on" relationships
+/// 6. **Unreachable Services** (Info) - Services with no relationships
+/// 7. **Filename Mismatches** (Error) - Service files not named `{id}.json`
+/// 8. **Legacy File Formats** (Info) - relationships.json stored as a bare array
+/// 9. **Naming Conventions** (Warning) - Service IDs, names or tags that don't match
+///    the config's patterns
+/// 10. **Missing Type Requirements** (Warning) - Services without the tags or metadata
+///     keys the config requires for their type
+/// 11. **Possible Duplicates** (Warning) - Pairs of services with near-identical names
+///     (see `find_possible_duplicates`), at most 200 pairs
+/// 12. **Reversed Data Flows** (Warning) - reads_from/writes_to starting at a database or
+///     cache (the source is the reader or writer)
+/// 13. **Reversed Dependencies** (Warning) - A database depending on a frontend
+/// 14. **Unlikely Auth Providers** (Warning) - authenticates_via targeting a service that
+///     isn't external and has no "auth" or "identity" tag
+/// 15. **Missing Queues** (Warning) - publishes/subscribes between two non-queue services
+///
+/// 16. **Deprecated Dependencies** (Warning) - Deprecated services that relationships
+///     still point at, listing the services pointing at them
+/// 17. **Unhealthy Dependencies** (Info) - Unhealthy services that others depend_on
+/// 18. **Metadata Schema Violations** (Warning) - Metadata failing the JSON Schema for the
+///     service's type (see `save_metadata_schema`), one issue per failed property
+/// 19. **Tier Violations** (Warning) - depends_on from a service to one of a less critical
+///     (higher numbered) tier
+/// 20. **Unclassified Tiers** (Info) - A single issue counting the services without a
+///     tier, only if some services have one
+/// 21. **Past Decommission Dates** (Warning) - Services whose decommission date has
+///     passed but whose lifecycle isn't `decommissioned`
+/// 22. **Decommissioned Dependencies** (Warning) - Decommissioned services that
+///     relationships still point at, suggesting their successor
+/// 23. **Expired Relationships** (Info) - Relationships whose `effectiveUntil` is more
+///     than 90 days past, as cleanup candidates
+///
+/// Checks 12-15 are heuristics based on the conventions of the built-in
+/// relationship types; teams with other conventions can disable each of them.
+///
+/// The data path's validation config (see `get_validation_config`) can
+/// disable any of these checks, in which case they aren't run at all, and
+/// override the severity each one reports with. Issues suppressed with
+/// `suppress_validation_issue` are listed separately in `suppressedIssues`
+/// and not counted, until their suppression expires.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to validate on the blocking thread pool
+/// * `environment` - The name of the environment to validate
+/// * `operation_id` - ID for the `operation:progress` events and `cancel_operation`
+/// * `group_issues_by_team` - If true, also group the issues by team in `issuesByTeam`
+///   (default: false)
+///
+/// # Returns
+///
+/// * `Ok(ValidationResult)` - The validation results with all issues and counts
+/// * `Err(AppError::OperationCancelled)` - If the validation was cancelled
+/// * `Err(AppError::Io)` - If there's an error reading the data files
+/// * `Err(AppError::FileLoad)` - If the validation config or suppressions file isn't
+///   valid JSON
+/// * `Err(AppError::ValidationError)` - If a naming pattern in the config or a metadata
+///   schema is invalid
+///
+/// # Algorithm Details
+///
+/// - Circular dependency detection uses DFS (Depth-First Search) on "depends_on" relationships
+/// - Duplicate cycle detection normalizes cycles for comparison
+/// - All checks are performed in a single pass where possible for efficiency
+///
+/// # Side Effects
+///
+/// - Emits `operation:progress` events while loading and checking the data
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const result = await invoke('validate_environment', { environment: 'dev' });
+/// console.log(`Found ${result.errorCount} errors, ${result.warningCount} warnings`);
+///
+/// for (const issue of result.issues) {
+///     console.log(`[${issue.severity}] ${issue.message}`);
+///     if (issue.suggestion) {
+///         console.log(`  Suggestion: ${issue.suggestion}`);
+///     }
+/// }
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn validate_environment<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    operation_id: Option<String>,
+    group_issues_by_team: Option<bool>,
+) -> Result<ValidationResult, AppError> {
+    run_blocking(app.clone(), move |state| {
+        let _timer = CommandTimer::start(state, "validate_environment");
+        let mut progress = Progress::start(&app, state, operation_id, "validate_environment");
+        let group_by_team = group_issues_by_team.unwrap_or(false);
+        let result =
+            run_validation_with_progress(state, &environment, group_by_team, &mut progress);
+        progress.finish(result)
+    })
+    .await
+}
+
+/// Runs `validate_environment`'s checks.
+///
+/// Shared by the command, the report export and the command-line
+/// `validate`, so all of them report the same issues. The state lock is
+/// only held to look up the data path; the files are read without it.
+pub fn run_validation(
+    state: &RwLock<AppState>,
+    environment: &str,
+) -> Result<ValidationResult, AppError> {
+    run_validation_with_progress(state, environment, false, &mut Progress::none())
+}
+
+/// Runs the checks like `run_validation`, reporting each loading step and
+/// stopping between them if cancelled, and grouping the issues by team if
+/// asked to.
+fn run_validation_with_progress(
+    state: &RwLock<AppState>,
+    environment: &str,
+    group_by_team: bool,
+    progress: &mut Progress,
+) -> Result<ValidationResult, AppError> {
+    let (data_path, backend) = {
+        let state = read_state(state);
+        (state.data_path.clone(), Arc::clone(&state.storage))
+    };
+    // Loading the config, services, relationships, and groups and annotations
+    progress.phase("Loading data", 4);
+    let config = storage::load_validation_config(&data_path)?;
+    let suppressions = storage::load_suppressions(&data_path, environment)?;
+    progress.advance();
+    progress.check_cancelled()?;
+
+    let mut issues = Vec::new();
+    let services = load_services_checked(backend.as_ref(), environment, &config, &mut issues)?;
+    progress.advance();
+    progress.check_cancelled()?;
+    let relationships = backend.load_relationships(environment)?;
+    progress.advance();
+    progress.check_cancelled()?;
+    let groups = storage::load_groups(&data_path, environment)?;
+    let annotations = storage::load_annotations(&data_path, environment)?;
+    progress.advance();
+    progress.check_cancelled()?;
+
+    progress.phase("Checking", 1);
+    issues.extend(check_data(
+        &services,
+        &relationships,
+        &groups,
+        &annotations,
+        &config,
+        &data_path,
+        progress,
+    )?);
+    check_import_freshness(
+        &data_path,
+        environment,
+        &relationships,
+        &config,
+        &mut issues,
+    )?;
+    progress.advance();
+
+    let mut result = ValidationResult::from_issues(issues, &config, &suppressions);
+    if group_by_team {
+        let grouped = teams::group_issues(&services, &relationships, &result.issues);
+        result.issues_by_team = Some(grouped);
+    }
+    Ok(result)
+}
+
+/// Checks a relationship before it is saved, for inline feedback in the editor.
+///
+/// Nothing is written. The draft is checked against the environment as it
+/// is now, with the checks `save_relationship` and `validate_environment`
+/// would apply to it:
+///
+/// 1. **Orphaned Relationships** (Error) - An endpoint isn't an existing service,
+///    named by which end it is
+/// 2. **Duplicate Relationships** (Error) - Another relationship has the same source,
+///    target and type; `save_relationship` would refuse it
+/// 3. **Self References** (Warning) - The source is the target
+/// 4. **Direction Heuristics** (Warning) - The checks 12-15 of `validate_environment`
+/// 5. **Circular Dependencies** (Warning) - A depends_on draft would close a dependency
+///    cycle; the shortest cycle it closes is reported
+///
+/// The draft may be an edit of an existing relationship, which is then left
+/// out of the duplicate and cycle checks. The data path's validation config
+/// disables checks and overrides severities as for `validate_environment`;
+/// suppressions don't apply.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to check on the blocking thread pool
+/// * `environment` - The name of the environment the relationship is for
+/// * `relationship` - The relationship as filled in so far
+///
+/// # Returns
+///
+/// * `Ok(ValidationResult)` - The issues the draft has; no errors means
+///   `save_relationship` accepts it
+/// * `Err(AppError::Io)` - If there's an error reading the data files
+/// * `Err(AppError::FileLoad)` - If the validation config isn't valid JSON
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: block the save button on errors, show warnings inline
+/// const check = await invoke('validate_relationship_draft', {
+///     environment: 'prod',
+///     relationship: {
+///         id: 'rel-1',
+///         source: 'orders',
+///         target: 'billing',
+///         relationshipType: 'depends_on'
+///     }
+/// });
+/// setCanSave(check.errorCount === 0);
+/// ```
+#[tauri::command]
+pub async fn validate_relationship_draft<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    relationship: Relationship,
+) -> Result<ValidationResult, AppError> {
+    run_blocking(app, move |state| {
+        let data_path = read_state(state).data_path.clone();
+        let config = storage::load_validation_config(&data_path)?;
+        let services = cache::shared_services(state, &environment)?;
+        let relationships = cache::shared_relationships(state, &environment)?;
+
+        let issues = check_draft(&services, &relationships, &relationship, &config);
+        Ok(ValidationResult::from_issues(issues, &config, &[]))
+    })
+    .await
+}
+
+/// Runs `validate_relationship_draft`'s checks.
+fn check_draft<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    draft: &Relationship,
+    config: &ValidationConfig,
+) -> Vec<ValidationIssue> {
+    let service_ids: HashSet<&str> = services.iter().map(|s| s.borrow().id.as_str()).collect();
+    let affected = |ids: &[&str]| -> Vec<String> {
+        Some(draft.id.as_str())
+            .filter(|id| !id.is_empty())
+            .into_iter()
+            .chain(ids.iter().copied())
+            .map(str::to_string)
+            .collect()
+    };
+    let mut issues = Vec::new();
+
+    let ends = [("source", &draft.source), ("target", &draft.target)];
+    let missing_end = ends
+        .iter()
+        .any(|(_, id)| !service_ids.contains(id.as_str()));
+    if config.is_enabled(IssueType::OrphanedRelationship) {
+        for (end, service_id) in ends {
+            if service_ids.contains(service_id.as_str()) {
+                continue;
+            }
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Error,
+                issue_type: IssueType::OrphanedRelationship,
+                message: format!("The {} service '{}' doesn't exist", end, service_id),
+                affected_ids: affected(&[service_id.as_str()]),
+                suggestion: Some(format!(
+                    "Pick an existing service, or create service '{}' first",
+                    service_id
+                )),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    let others = || relationships.iter().filter(move |r| r.id != draft.id);
+    let duplicate = others().find(|r| {
+        r.source == draft.source
+            && r.target == draft.target
+            && r.relationship_type == draft.relationship_type
+    });
+    if let Some(duplicate) = duplicate {
+        if config.is_enabled(IssueType::DuplicateRelationship) {
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Error,
+                issue_type: IssueType::DuplicateRelationship,
+                message: format!(
+                    "Relationship '{}' already has '{}' {} '{}'",
+                    duplicate.id,
+                    draft.source,
+                    draft.relationship_type.as_str(),
+                    draft.target
+                ),
+                affected_ids: affected(&[duplicate.id.as_str()]),
+                suggestion: Some(format!("Edit relationship '{}' instead", duplicate.id)),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    let self_reference = draft.source == draft.target;
+    if self_reference && config.is_enabled(IssueType::SelfReference) {
+        issues.push(ValidationIssue {
+            severity: IssueSeverity::Warning,
+            issue_type: IssueType::SelfReference,
+            message: format!("'{}' would point at itself", draft.source),
+            affected_ids: affected(&[draft.source.as_str()]),
+            suggestion: Some("Pick a different target".to_string()),
+            fingerprint: String::new(),
+        });
+    }
+
+    check_relationship_directions(services, std::slice::from_ref(draft), config, &mut issues);
+
+    let closes_cycle = draft.relationship_type == RelationshipType::DependsOn
+        && duplicate.is_none()
+        && !self_reference
+        && !missing_end;
+    if closes_cycle && config.is_enabled(IssueType::CircularDependency) {
+        let dependencies = others().filter(|r| r.relationship_type == RelationshipType::DependsOn);
+        if let Some(path) = dependency_path(dependencies, &draft.target, &draft.source) {
+            let cycle: Vec<String> = [draft.source.clone()].into_iter().chain(path).collect();
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type: IssueType::CircularDependency,
+                message: format!(
+                    "The relationship would close a dependency cycle: {}",
+                    cycle.join(" -> ")
+                ),
+                affected_ids: cycle,
+                suggestion: Some("Consider breaking the circular dependency".to_string()),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    issues
+}
+
+/// Finds the shortest path of relationships from one service to another.
+///
+/// # Returns
+///
+/// The service IDs along the path, from `from` to `to` inclusive; `None` if
+/// `to` can't be reached.
+fn dependency_path<'a>(
+    relationships: impl IntoIterator<Item = &'a Relationship>,
+    from: &str,
+    to: &str,
+) -> Option<Vec<String>> {
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    for relationship in relationships {
+        edges
+            .entry(relationship.source.as_str())
+            .or_default()
+            .push(relationship.target.as_str());
+    }
+
+    // Breadth-first, remembering where each service was reached from
+    let mut reached_from: HashMap<&str, &str> = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = vec![to.to_string()];
+            let mut node = to;
+            while node != from {
+                node = reached_from[node];
+                path.push(node.to_string());
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &next in edges.get(current).into_iter().flatten() {
+            if !reached_from.contains_key(next) {
+                reached_from.insert(next, current);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// File formats of an exported validation report.
+///
+/// # Variants
+///
+/// * `Json` - The full validation result with report metadata, for scripts
+/// * `Markdown` - Issues grouped by severity, for tickets and CI summaries
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Json,
+    Markdown,
+}
+
+impl ReportFormat {
+    /// Parses a format as it is serialized (e.g., `"markdown"`).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(Self::Json),
+            "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// Limits above which an exported report counts as failed.
+///
+/// # Fields
+///
+/// * `max_errors` - Most errors allowed (no limit if absent)
+/// * `max_warnings` - Most warnings allowed (no limit if absent)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FailThreshold {
+    pub max_errors: Option<usize>,
+    pub max_warnings: Option<usize>,
+}
+
+impl FailThreshold {
+    /// Returns true if the result is within the limits.
+    pub fn passed(&self, result: &ValidationResult) -> bool {
+        self.max_errors.is_none_or(|max| result.error_count <= max)
+            && self
+                .max_warnings
+                .is_none_or(|max| result.warning_count <= max)
+    }
+}
+
+/// Contents of a JSON validation report.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonReport<'a> {
+    environment: &'a str,
+    generated_at: u64,
+    fail_threshold: Option<FailThreshold>,
+    passed: Option<bool>,
+    result: &'a ValidationResult,
+    flagged_dependencies: &'a [Relationship],
+}
+
+/// Summary of an exported validation report.
+///
+/// # Fields
+///
+/// * `output_path` - Where the report was written
+/// * `error_count` / `warning_count` / `info_count` - Counted issues by severity
+/// * `suppressed_count` - Issues left out of the counts by a suppression
+/// * `passed` - Whether the counts are within the fail threshold (absent without one)
+/// * `redacted_values` - How many values were masked in the report (0 without `redact`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReportSummary {
+    pub output_path: String,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub info_count: usize,
+    pub suppressed_count: usize,
+    pub passed: Option<bool>,
+    pub redacted_values: usize,
+}
+
+/// Validates an environment and writes the results to a report file.
+///
+/// Runs the same checks as `validate_environment`, so severity overrides
+/// and suppressions apply the same way.
+///
+/// A JSON report contains the environment, the generation time (`generatedAt`,
+/// milliseconds since the Unix epoch), the fail threshold with whether the
+/// result passed it, the full `ValidationResult` and the relationships the
+/// architecture review flagged:
+///
+/// ```json
+/// {
+///   "environment": "prod",
+///   "generatedAt": 1760400000000,
+///   "failThreshold": { "maxErrors": 0, "maxWarnings": null },
+///   "passed": false,
+///   "result": { "issues": [], "errorCount": 1, "...": "..." },
+///   "flaggedDependencies": [{ "id": "rel-a-to-b", "reviewStatus": "flagged", "...": "..." }]
+/// }
+/// ```
+///
+/// A Markdown report lists the issues grouped by severity, with their
+/// suggestions as nested bullet points, followed by the suppressed issues
+/// and the flagged dependencies with their review notes.
+///
+/// With `redact`, values the redaction config of the settings lists (see
+/// `redaction`) are masked as `***` in the messages and suggestions of the
+/// issues and in the review notes and reviewers, stripped ones too.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to validate on the blocking thread pool
+/// * `environment` - The name of the environment to validate
+/// * `output_path` - The file to write; replaced if it exists
+/// * `format` - "json" or "markdown"
+/// * `fail_threshold` - Limits the result must stay within to pass
+/// * `redact` - If true, mask the values the redaction config lists (default: false)
+///
+/// # Returns
+///
+/// * `Ok(ValidationReportSummary)` - The counts of the written report
+/// * `Err(AppError::Io)` - If the data cannot be read or the report cannot be written
+/// * `Err(AppError::FileLoad)` - If the validation config or suppressions file isn't
+///   valid JSON
+/// * `Err(AppError::ValidationError)` - If a naming pattern, metadata schema or
+///   redaction pattern is invalid
+///
+/// # Side Effects
+///
+/// - Writes the report to `output_path`
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const summary = await invoke('export_validation_report', {
+///     environment: 'prod',
+///     outputPath: '/tmp/prod-validation.json',
+///     format: 'json',
+///     failThreshold: { maxErrors: 0 }
+/// });
+/// if (!summary.passed) {
+///     console.log(`${summary.errorCount} errors`);
+/// }
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_validation_report<R: Runtime>(
+    app: AppHandle<R>,
+    environment: String,
+    output_path: String,
+    format: ReportFormat,
+    fail_threshold: Option<FailThreshold>,
+    redact: Option<bool>,
+) -> Result<ValidationReportSummary, AppError> {
+    run_blocking(app, move |state| {
+        write_validation_report(
+            state,
+            environment,
+            output_path,
+            format,
+            fail_threshold,
+            redact.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+/// Validates an environment and writes the report, for `export_validation_report`.
+fn write_validation_report(
+    state: &RwLock<AppState>,
+    environment: String,
+    output_path: String,
+    format: ReportFormat,
+    fail_threshold: Option<FailThreshold>,
+    redact: bool,
+) -> Result<ValidationReportSummary, AppError> {
+    let mut result = run_validation(state, &environment)?;
+    let mut flagged = flagged_dependencies(state, &environment)?;
+
+    let mut redacted_values = 0;
+    if redact {
+        let config = read_state(state).settings.redaction.clone();
+        let services = cache::shared_services(state, &environment)?;
+        let relationships = cache::shared_relationships(state, &environment)?;
+        let redacted = redaction::redact(&services, &relationships, &config)?;
+        let suppressed = &mut result.suppressed_issues;
+        let issue_texts = result
+            .issues
+            .iter_mut()
+            .chain(suppressed)
+            .flat_map(|issue| std::iter::once(&mut issue.message).chain(&mut issue.suggestion));
+        let review_texts = flagged
+            .iter_mut()
+            .flat_map(|r| r.review_note.iter_mut().chain(&mut r.reviewed_by));
+        for text in issue_texts.chain(review_texts) {
+            let (masked, count) = redacted.mask_in_text(text);
+            *text = masked;
+            redacted_values += count;
+        }
+    }
+
+    let passed = fail_threshold.map(|threshold| threshold.passed(&result));
+    let content =
+        render_validation_report(&environment, &result, &flagged, format, fail_threshold)?;
+    fs::write(&output_path, content)?;
+
+    Ok(ValidationReportSummary {
+        output_path,
+        error_count: result.error_count,
+        warning_count: result.warning_count,
+        info_count: result.info_count,
+        suppressed_count: result.suppressed_issues.len(),
+        passed,
+        redacted_values,
+    })
+}
+
+/// Returns the relationships of an environment the architecture review flagged,
+/// for the validation reports.
+pub fn flagged_dependencies(
+    state: &RwLock<AppState>,
+    environment: &str,
+) -> Result<Vec<Relationship>, AppError> {
+    let mut relationships = cache::relationships(state, environment)?;
+    relationships.retain(|r| r.review_status == ReviewStatus::Flagged);
+    Ok(relationships)
+}
+
+/// Renders a validation result as a report, as written by `export_validation_report`.
+///
+/// # Arguments
+///
+/// * `environment` - The name of the validated environment
+/// * `result` - The validation result
+/// * `flagged_dependencies` - The relationships the architecture review flagged
+/// * `format` - The format to render in
+/// * `fail_threshold` - Limits the result must stay within to pass, reported if given
+///
+/// # Returns
+///
+/// * `Ok(String)` - The rendered report, generated now
+/// * `Err(AppError::Json)` - If the JSON report cannot be serialized
+pub fn render_validation_report(
+    environment: &str,
+    result: &ValidationResult,
+    flagged_dependencies: &[Relationship],
+    format: ReportFormat,
+    fail_threshold: Option<FailThreshold>,
+) -> Result<String, AppError> {
+    let generated_at = audit::now_millis();
+    let passed = fail_threshold.map(|threshold| threshold.passed(result));
+
+    Ok(match format {
+        ReportFormat::Json => serde_json::to_string_pretty(&JsonReport {
+            environment,
+            generated_at,
+            fail_threshold,
+            passed,
+            result,
+            flagged_dependencies,
+        })?,
+        ReportFormat::Markdown => markdown_report(
+            environment,
+            generated_at,
+            passed,
+            result,
+            flagged_dependencies,
+        ),
+    })
+}
+
+/// Renders a validation result as a Markdown report.
+fn markdown_report(
+    environment: &str,
+    generated_at: u64,
+    passed: Option<bool>,
+    result: &ValidationResult,
+    flagged_dependencies: &[Relationship],
+) -> String {
+    let mut report = format!(
+        "# Validation report: {}\n\nGenerated {}.\n\n",
+        environment,
+        audit::format_timestamp(generated_at)
+    );
+    if let Some(passed) = passed {
+        let outcome = if passed { "Passed" } else { "Failed" };
+        report.push_str(&format!("**{}** the fail threshold.\n\n", outcome));
+    }
+
+    report.push_str("| Errors | Warnings | Info | Suppressed |\n");
+    report.push_str("| --- | --- | --- | --- |\n");
+    report.push_str(&format!(
+        "| {} | {} | {} | {} |\n",
+        result.error_count,
+        result.warning_count,
+        result.info_count,
+        result.suppressed_issues.len()
+    ));
+
+    if result.issues.is_empty() {
+        report.push_str("\nNo issues found.\n");
+    }
+
+    let sections = [
+        (IssueSeverity::Error, "Errors"),
+        (IssueSeverity::Warning, "Warnings"),
+        (IssueSeverity::Info, "Info"),
+    ];
+    for (severity, title) in sections {
+        let issues: Vec<&ValidationIssue> = result
+            .issues
+            .iter()
+            .filter(|i| i.severity == severity)
+            .collect();
+        push_markdown_section(&mut report, title, &issues);
+    }
+
+    let suppressed: Vec<&ValidationIssue> = result.suppressed_issues.iter().collect();
+    push_markdown_section(&mut report, "Suppressed", &suppressed);
+
+    if !flagged_dependencies.is_empty() {
+        report.push_str(&format!(
+            "\n## Flagged dependencies ({})\n\n",
+            flagged_dependencies.len()
+        ));
+        for relationship in flagged_dependencies {
+            report.push_str(&format!(
+                "- **{}**: {}",
+                relationship.id,
+                relationship.label()
+            ));
+            if let Some(reviewer) = &relationship.reviewed_by {
+                report.push_str(&format!(", flagged by {}", reviewer));
+            }
+            report.push('\n');
+            if let Some(note) = &relationship.review_note {
+                report.push_str(&format!("  - Note: {}\n", note));
+            }
+        }
+    }
+
+    report
+}
+
+/// Appends a section listing issues to a Markdown report, unless there are none.
+fn push_markdown_section(report: &mut String, title: &str, issues: &[&ValidationIssue]) {
+    if issues.is_empty() {
+        return;
+    }
+
+    report.push_str(&format!("\n## {} ({})\n\n", title, issues.len()));
+    for issue in issues {
+        report.push_str(&format!(
+            "- **{}**: {}\n",
+            issue.issue_type.as_str(),
+            issue.message
+        ));
+        if let Some(suggestion) = &issue.suggestion {
+            report.push_str(&format!("  - Suggestion: {}\n", suggestion));
+        }
+    }
+}
+
+impl ValidationResult {
+    /// Builds a result from a list of issues, counting them by severity.
+    ///
+    /// Sets each issue's fingerprint and applies the config's severity
+    /// overrides first, so the counts reflect the overridden severities.
+    /// Issues matching an active suppression are moved to `suppressed_issues`
+    /// and not counted.
+    pub fn from_issues(
+        issues: Vec<ValidationIssue>,
+        config: &ValidationConfig,
+        suppressions: &[Suppression],
+    ) -> Self {
+        let now = audit::now_millis();
+        let suppressed: HashSet<&str> = suppressions
+            .iter()
+            .filter(|s| s.is_active(now))
+            .map(|s| s.fingerprint.as_str())
+            .collect();
+
+        let mut counted = Vec::with_capacity(issues.len());
+        let mut suppressed_issues = Vec::new();
+        for mut issue in issues {
+            issue.fingerprint = issue_fingerprint(issue.issue_type, &issue.affected_ids);
+            issue.severity = config.severity(issue.issue_type, issue.severity);
+            if suppressed.contains(issue.fingerprint.as_str()) {
+                suppressed_issues.push(issue);
+            } else {
+                counted.push(issue);
+            }
+        }
+
+        let count =
+            |severity: IssueSeverity| counted.iter().filter(|i| i.severity == severity).count();
+        let error_count = count(IssueSeverity::Error);
+        let warning_count = count(IssueSeverity::Warning);
+        let info_count = count(IssueSeverity::Info);
+
+        Self {
+            issues: counted,
+            error_count,
+            warning_count,
+            info_count,
+            suppressed_issues,
+            issues_by_team: None,
+        }
+    }
+}
+
+/// Computes the fingerprint identifying an issue across validation runs.
+///
+/// The fingerprint is the issue type followed by the affected IDs, sorted so
+/// that discovery order doesn't matter (e.g., "orphaned_relationship:api,rel-1").
+/// Cycles are normalized with `normalize_cycle` instead, so the same cycle
+/// found from a different starting node gets the same fingerprint while
+/// cycles through the same services in a different order stay distinct.
+pub fn issue_fingerprint(issue_type: IssueType, affected_ids: &[String]) -> String {
+    let ids = if issue_type == IssueType::CircularDependency {
+        normalize_cycle(affected_ids)
+    } else {
+        let mut ids = affected_ids.to_vec();
+        ids.sort();
+        ids
+    };
+
+    format!("{}:{}", issue_type.as_str(), ids.join(","))
+}
+
+/// Loads an environment's services for validation.
+///
+/// File layout checks only apply to the directory backend; their issues are
+/// appended to `issues`.
+fn load_services_checked(
+    backend: &dyn StorageBackend,
+    environment: &str,
+    config: &ValidationConfig,
+    issues: &mut Vec<ValidationIssue>,
+) -> Result<Vec<Service>, AppError> {
+    match backend.as_file_system() {
+        Some(fs_backend) => check_file_layout(fs_backend, environment, config, issues),
+        None => backend.load_services(environment),
+    }
+}
+
+/// Reports import sources that haven't re-synced the environment recently.
+///
+/// Each source with relationships imported from it (see
+/// `RelationshipOrigin::Imported`), and each source in the `imports.sources`
+/// setting, gets one issue when the import timestamp in the environment's
+/// metadata is older than `imports.staleAfterDays`. Sources without a
+/// recorded import, e.g. everywhere in SQLite databases, aren't reported.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory, for the environment's metadata
+/// * `environment` - The environment being validated
+/// * `relationships` - All relationships in the environment
+/// * `config` - The rule configuration; nothing is checked if the rule is disabled
+/// * `issues` - Accumulator the issues found are appended to
+///
+/// # Returns
+///
+/// * `Ok(())` - If the check ran
+/// * `Err(AppError::FileLoad)` - If the environment's metadata file isn't valid JSON
+fn check_import_freshness(
+    data_path: &Path,
+    environment: &str,
+    relationships: &[Relationship],
+    config: &ValidationConfig,
+    issues: &mut Vec<ValidationIssue>,
+) -> Result<(), AppError> {
+    if !config.is_enabled(IssueType::StaleImport) {
+        return Ok(());
+    }
+
+    let mut imported: BTreeMap<&str, Vec<String>> = config
+        .imports
+        .sources
+        .iter()
+        .map(|source| (source.as_str(), Vec::new()))
+        .collect();
+    for relationship in relationships {
+        if let RelationshipOrigin::Imported(source) = &relationship.origin {
+            imported
+                .entry(source.as_str())
+                .or_default()
+                .push(relationship.id.clone());
+        }
+    }
+    if imported.is_empty() {
+        return Ok(());
+    }
+
+    let metadata =
+        storage::environment_metadata::load_environment_metadata(data_path, environment)?;
+    let stale_after = config
+        .imports
+        .stale_after_days
+        .saturating_mul(24 * 60 * 60 * 1000);
+    let stale_before = audit::now_millis().saturating_sub(stale_after);
+    for (source, relationship_ids) in imported {
+        let Some(&imported_at) = metadata.last_imports.get(source) else {
+            continue;
+        };
+        if imported_at >= stale_before {
+            continue;
+        }
+        let synced_on = &audit::format_timestamp(imported_at)[..10];
+        let (message, suggestion) = if relationship_ids.is_empty() {
+            let found = metadata
+                .import_counts
+                .get(source)
+                .map(|counts| format!(" and found {} services", counts.services))
+                .unwrap_or_default();
+            (
+                format!("{} last synced on {}{}", source, synced_on, found),
+                format!(
+                    "Re-run the {} import, or run check_drift on a fresh export",
+                    source
+                ),
+            )
+        } else {
+            (
+                format!(
+                    "{} relationships were imported from {}, which last synced on {}",
+                    relationship_ids.len(),
+                    source,
+                    synced_on
+                ),
+                format!(
+                    "Re-run the {} import to bring its relationships up to date",
+                    source
+                ),
+            )
+        };
+        issues.push(ValidationIssue {
+            severity: IssueSeverity::Info,
+            issue_type: IssueType::StaleImport,
+            message,
+            affected_ids: relationship_ids,
+            suggestion: Some(suggestion),
+            fingerprint: String::new(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Days after its `effective_until` a relationship is reported as expired.
+const EXPIRED_AFTER_DAYS: i64 = 90;
+
+/// Runs the data checks of `validate_environment` on loaded services and relationships.
+///
+/// # Arguments
+///
+/// * `services` - All services in the environment
+/// * `relationships` - All relationships in the environment
+/// * `groups` - All service groups in the environment
+/// * `annotations` - The environment's graph annotations by service ID
+/// * `config` - The rule configuration; disabled rules are skipped
+/// * `data_path` - The data directory, for the metadata schemas and type registries
+/// * `progress` - Checked for cancellation between checks and between the
+///   services of the per-service checks
+///
+/// # Returns
+///
+/// * `Ok(Vec<ValidationIssue>)` - The issues found
+/// * `Err(AppError::OperationCancelled)` - If the validation was cancelled
+/// * `Err(AppError::ValidationError)` - If a naming pattern in the config or a metadata
+///   schema is invalid
+/// * `Err(AppError::FileLoad)` - If a metadata schema or type registry file isn't valid JSON
+fn check_data(
+    services: &[Service],
+    relationships: &[Relationship],
+    groups: &[Group],
+    annotations: &BTreeMap<String, NodeAnnotation>,
+    config: &ValidationConfig,
+    data_path: &Path,
+    progress: &Progress,
+) -> Result<Vec<ValidationIssue>, AppError> {
+    let mut issues = Vec::new();
+    // Build service ID set for lookups
+    let service_ids: HashSet<String> = services.iter().map(|s| s.id.clone()).collect();
+
+    // Check for duplicate service IDs (shouldn't happen but check anyway)
+    if config.is_enabled(IssueType::DuplicateServiceId) {
+        let mut seen_ids: HashMap<String, usize> = HashMap::new();
+        for service in services {
+            *seen_ids.entry(service.id.clone()).or_insert(0) += 1;
+        }
+        for (id, count) in &seen_ids {
+            if *count > 1 {
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Error,
+                    issue_type: IssueType::DuplicateServiceId,
+                    message: format!("Duplicate service ID '{}' found {} times", id, count),
+                    affected_ids: vec![id.clone()],
+                    suggestion: Some("Rename one of the duplicate services".to_string()),
+                    fingerprint: String::new(),
+                });
+            }
+        }
+    }
+
+    // Check for missing required fields in services
+    if config.is_enabled(IssueType::MissingRequiredField) {
+        for service in services {
+            let missing_fields = check_required_fields(service);
+            if !missing_fields.is_empty() {
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Error,
+                    issue_type: IssueType::MissingRequiredField,
+                    message: format!(
+                        "Service '{}' is missing required fields: {}",
+                        service.id,
+                        missing_fields.join(", ")
+                    ),
+                    affected_ids: vec![service.id.clone()],
+                    suggestion: Some(format!("Add missing fields: {}", missing_fields.join(", "))),
+                    fingerprint: String::new(),
+                });
+            }
+        }
+    }
+
+    // Check custom service types against the registry
+    let check_registered = config.is_enabled(IssueType::UnregisteredCustomType);
+    let service_types = storage::load_custom_types(data_path, CustomTypeKind::Service)?;
+    let relationship_types = storage::load_custom_types(data_path, CustomTypeKind::Relationship)?;
+    if check_registered && !service_types.is_empty() {
+        for service in services {
+            if let ServiceType::Custom(name) = &service.service_type {
+                if !is_registered(name, &service_types) {
+                    issues.push(unregistered_type_issue(
+                        "Service",
+                        &service.id,
+                        name,
+                        &service_types,
+                    ));
+                }
+            }
+        }
+    }
+
+    // Check for orphaned relationships. Once custom relationship types are
+    // registered, unknown types are reported against the registry instead.
+    let check_orphans = config.is_enabled(IssueType::OrphanedRelationship);
+    let check_types =
+        config.is_enabled(IssueType::InvalidRelationshipType) && relationship_types.is_empty();
+    let check_registered_relationships = check_registered && !relationship_types.is_empty();
+    for relationship in relationships {
+        if check_orphans {
+            check_orphaned_ends(relationship, &service_ids, &mut issues);
+        }
+
+        // Check for invalid relationship types
+        if check_types && !is_valid_relationship_type(&relationship.relationship_type) {
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type: IssueType::InvalidRelationshipType,
+                message: format!(
+                    "Relationship '{}' has unknown type '{:?}'",
+                    relationship.id, relationship.relationship_type
+                ),
+                affected_ids: vec![relationship.id.clone()],
+                suggestion: Some(
+                    "Use a standard relationship type: depends_on, communicates_with, authenticates_via, reads_from, writes_to, publishes, subscribes".to_string()
+                ),
+                fingerprint: String::new(),
+            });
+        }
+
+        if let RelationshipType::Custom(name) = &relationship.relationship_type {
+            if check_registered_relationships && !is_registered(name, &relationship_types) {
+                issues.push(unregistered_type_issue(
+                    "Relationship",
+                    &relationship.id,
+                    name,
+                    &relationship_types,
+                ));
+            }
+        }
+    }
+
+    // Check for relationships that ended long ago
+    if config.is_enabled(IssueType::ExpiredRelationship) {
+        let today = dates::today();
+        for relationship in relationships {
+            let Some(until) = &relationship.effective_until else {
+                continue;
+            };
+            if dates::days_between(until, &today).is_none_or(|days| days <= EXPIRED_AFTER_DAYS) {
+                continue;
+            }
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Info,
+                issue_type: IssueType::ExpiredRelationship,
+                message: format!(
+                    "Relationship '{}' ({}) ended on {}",
+                    relationship.id,
+                    relationship.label(),
+                    until
+                ),
+                affected_ids: vec![relationship.id.clone()],
+                suggestion: Some("Delete the relationship if it's no longer needed".to_string()),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    // Check that the dependencies of critical services went through the review
+    if config.is_enabled(IssueType::UnreviewedDependency) {
+        let tier_one: HashSet<&str> = services
+            .iter()
+            .filter(|s| s.tier == Some(1))
+            .map(|s| s.id.as_str())
+            .collect();
+        let unreviewed: Vec<String> = relationships
+            .iter()
+            .filter(|r| {
+                r.relationship_type == RelationshipType::DependsOn
+                    && r.review_status == ReviewStatus::Unreviewed
+            })
+            .filter(|r| {
+                tier_one.contains(r.source.as_str()) || tier_one.contains(r.target.as_str())
+            })
+            .map(|r| r.id.clone())
+            .collect();
+        if !unreviewed.is_empty() {
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Info,
+                issue_type: IssueType::UnreviewedDependency,
+                message: format!(
+                    "{} depends_on relationships of tier-1 services haven't been reviewed: {}",
+                    unreviewed.len(),
+                    summarize_ids(&unreviewed)
+                ),
+                // Kept empty so the fingerprint survives relationships being reviewed
+                affected_ids: Vec::new(),
+                suggestion: Some(
+                    "Have the architecture board approve or flag them (set_relationship_review)"
+                        .to_string(),
+                ),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    check_relationship_directions(services, relationships, config, &mut issues);
+
+    // Check for services still pointing at deprecated or unhealthy services
+    if config.is_enabled(IssueType::DeprecatedDependency) {
+        let usage = dependents_with_status(services, relationships, ServiceStatus::Deprecated);
+        for (service, dependents) in usage.into_iter().filter(|(_, d)| !d.is_empty()) {
+            let mut affected_ids = vec![service.id.clone()];
+            affected_ids.extend(dependents.iter().cloned());
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type: IssueType::DeprecatedDependency,
+                message: format!(
+                    "Deprecated service '{}' is still used by {} service(s): {}",
+                    service.id,
+                    dependents.len(),
+                    dependents.join(", ")
+                ),
+                affected_ids,
+                suggestion: Some(format!("Migrate the dependents off '{}'", service.id)),
+                fingerprint: String::new(),
+            });
+        }
+    }
+    if config.is_enabled(IssueType::DecommissionedDependency) {
+        let usage = dependents_matching(services, relationships, |s| {
+            s.lifecycle == Some(ServiceLifecycle::Decommissioned)
+        });
+        for (service, dependents) in usage.into_iter().filter(|(_, d)| !d.is_empty()) {
+            let mut affected_ids = vec![service.id.clone()];
+            affected_ids.extend(dependents.iter().cloned());
+            let suggestion = match &service.successor_id {
+                Some(successor_id) => format!(
+                    "Move the dependents from '{}' to its successor '{}'",
+                    service.id, successor_id
+                ),
+                None => format!("Migrate the dependents off '{}'", service.id),
+            };
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type: IssueType::DecommissionedDependency,
+                message: format!(
+                    "Decommissioned service '{}' is still used by {} service(s): {}",
+                    service.id,
+                    dependents.len(),
+                    dependents.join(", ")
+                ),
+                affected_ids,
+                suggestion: Some(suggestion),
+                fingerprint: String::new(),
+            });
+        }
+    }
+    if config.is_enabled(IssueType::PastDecommissionDate) {
+        let today = dates::today();
+        for service in services {
+            if service.lifecycle == Some(ServiceLifecycle::Decommissioned)
+                || !lifecycle::is_past_decommission_date(service, &today)
+            {
+                continue;
+            }
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type: IssueType::PastDecommissionDate,
+                message: format!(
+                    "Service '{}' was due to be decommissioned on {}",
+                    service.id,
+                    service.decommission_date.as_deref().unwrap_or_default()
+                ),
+                affected_ids: vec![service.id.clone()],
+                suggestion: Some(
+                    "Decommission the service or move its decommission date".to_string(),
+                ),
+                fingerprint: String::new(),
+            });
+        }
+    }
+    if config.is_enabled(IssueType::UnhealthyDependency) {
+        let depends_on = relationships
+            .iter()
+            .filter(|r| r.relationship_type == RelationshipType::DependsOn);
+        let usage = dependents_with_status(services, depends_on, ServiceStatus::Unhealthy);
+        for (service, dependents) in usage.into_iter().filter(|(_, d)| !d.is_empty()) {
+            let mut affected_ids = vec![service.id.clone()];
+            affected_ids.extend(dependents.iter().cloned());
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Info,
+                issue_type: IssueType::UnhealthyDependency,
+                message: format!(
+                    "Unhealthy service '{}' is a dependency of: {}",
+                    service.id,
+                    dependents.join(", ")
+                ),
+                affected_ids,
+                suggestion: None,
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    // Check for services claiming the same port on the same host
+    let check_conflicts = config.is_enabled(IssueType::PortConflict);
+    let check_ports = config.is_enabled(IssueType::InvalidPort);
+    if check_conflicts || check_ports {
+        let port_check = ports::check(services, &config.ports);
+        for conflict in port_check.conflicts.iter().filter(|_| check_conflicts) {
+            let [first, second] = &conflict.service_ids;
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type: IssueType::PortConflict,
+                message: format!(
+                    "Services '{}' and '{}' both use port {} on {}",
+                    first, second, conflict.port, conflict.location
+                ),
+                affected_ids: conflict.service_ids.to_vec(),
+                suggestion: Some("Move one of the services to another port or host".to_string()),
+                fingerprint: String::new(),
+            });
+        }
+        for invalid in port_check.invalid_ports.iter().filter(|_| check_ports) {
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Info,
+                issue_type: IssueType::InvalidPort,
+                message: format!(
+                    "Service '{}' has port {} in metadata key '{}', which isn't a port number",
+                    invalid.service_id, invalid.value, invalid.key
+                ),
+                affected_ids: vec![invalid.service_id.clone()],
+                suggestion: Some("Use a number from 1 to 65535".to_string()),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    // Check that third parties are modeled as external services, and not the other way round
+    let check_third_parties = config.is_enabled(IssueType::MistypedThirdParty);
+    let check_external_dependencies = config.is_enabled(IssueType::ExternalDependsOnInternal);
+    if check_third_parties || check_external_dependencies {
+        let services_by_id: HashMap<&str, &Service> =
+            services.iter().map(|s| (s.id.as_str(), s)).collect();
+        let mut third_party_users: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+
+        for relationship in relationships {
+            let (Some(source), Some(target)) = (
+                services_by_id.get(relationship.source.as_str()),
+                services_by_id.get(relationship.target.as_str()),
+            ) else {
+                continue;
+            };
+            let source_external = source.service_type == ServiceType::External;
+            let target_external = target.service_type == ServiceType::External;
+
+            if check_third_parties
+                && !source_external
+                && !target_external
+                && external::is_third_party(target)
+            {
+                third_party_users
+                    .entry(target.id.as_str())
+                    .or_default()
+                    .insert(source.id.as_str());
+            }
+            if check_external_dependencies
+                && source_external
+                && !target_external
+                && relationship.relationship_type == RelationshipType::DependsOn
+            {
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Info,
+                    issue_type: IssueType::ExternalDependsOnInternal,
+                    message: format!(
+                        "External service '{}' depends on internal service '{}'",
+                        source.id, target.id
+                    ),
+                    affected_ids: vec![
+                        relationship.id.clone(),
+                        source.id.clone(),
+                        target.id.clone(),
+                    ],
+                    suggestion: Some(
+                        "External systems rarely depend on internal services; check whether \
+                         the relationship is reversed"
+                            .to_string(),
+                    ),
+                    fingerprint: String::new(),
+                });
+            }
+        }
+
+        for (target_id, users) in third_party_users {
+            let mut affected_ids = vec![target_id.to_string()];
+            affected_ids.extend(users.iter().map(|id| id.to_string()));
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type: IssueType::MistypedThirdParty,
+                message: format!(
+                    "Service '{}' is tagged {} but has type '{}'; used by: {}",
+                    target_id,
+                    external::THIRD_PARTY_TAG,
+                    services_by_id[target_id].service_type.as_str(),
+                    users.into_iter().collect::<Vec<_>>().join(", ")
+                ),
+                affected_ids,
+                suggestion: Some(format!(
+                    "Change the type of '{}' to external, or remove its {} tag",
+                    target_id,
+                    external::THIRD_PARTY_TAG
+                )),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    // Check for unreachable services (no relationships at all)
+    if config.is_enabled(IssueType::UnreachableService) {
+        let connected_services: HashSet<String> = relationships
+            .iter()
+            .flat_map(|r| vec![r.source.clone(), r.target.clone()])
+            .collect();
+
+        for service in services {
+            if !connected_services.contains(&service.id) {
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Info,
+                    issue_type: IssueType::UnreachableService,
+                    message: format!("Service '{}' has no relationships (isolated)", service.id),
+                    affected_ids: vec![service.id.clone()],
+                    suggestion: Some(
+                        "Add relationships or consider if this service is needed".to_string(),
+                    ),
+                    fingerprint: String::new(),
+                });
+            }
+        }
+    }
+
+    progress.check_cancelled()?;
+
+    // Check for circular dependencies (simple cycle detection using DFS)
+    if config.is_enabled(IssueType::CircularDependency) {
+        check_cycles(relationships, &service_ids, &mut issues);
+    }
+
+    progress.check_cancelled()?;
+
+    // Check for services with near-identical names
+    if config.is_enabled(IssueType::PossibleDuplicate) {
+        let (pairs, _) = similarity::similar_pairs(
+            services,
+            similarity::DEFAULT_MAX_DISTANCE,
+            similarity::MAX_REPORTED_PAIRS,
+        );
+        for pair in pairs {
+            let (first, second) = (&services[pair.first], &services[pair.second]);
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type: IssueType::PossibleDuplicate,
+                message: format!(
+                    "Services '{}' ({}) and '{}' ({}) have similar names",
+                    first.id, first.name, second.id, second.name
+                ),
+                affected_ids: vec![first.id.clone(), second.id.clone()],
+                suggestion: Some("Merge the services if they are the same one".to_string()),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    // Check that services only depend on services at least as critical
+    if config.is_enabled(IssueType::TierViolation) {
+        for violation in tier_violations(services, relationships) {
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type: IssueType::TierViolation,
+                message: format!(
+                    "Tier-{} service '{}' depends on tier-{} service '{}'",
+                    violation.source_tier,
+                    violation.source_id,
+                    violation.target_tier,
+                    violation.target_id
+                ),
+                affected_ids: vec![
+                    violation.relationship_id,
+                    violation.source_id.clone(),
+                    violation.target_id.clone(),
+                ],
+                suggestion: Some(format!(
+                    "Raise '{}' to tier {} or remove the dependency of '{}' on it",
+                    violation.target_id, violation.source_tier, violation.source_id
+                )),
+                fingerprint: String::new(),
+            });
+        }
+    }
+    if config.is_enabled(IssueType::UnclassifiedTier) {
+        let unclassified = unclassified_services(services);
+        if !unclassified.is_empty() && unclassified.len() < services.len() {
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Info,
+                issue_type: IssueType::UnclassifiedTier,
+                message: format!(
+                    "{} of {} services have no tier: {}",
+                    unclassified.len(),
+                    services.len(),
+                    summarize_ids(&unclassified)
+                ),
+                // Kept empty so the fingerprint survives services being classified
+                affected_ids: Vec::new(),
+                suggestion: Some("Assign a tier from 1 (critical) to 4 (best-effort)".to_string()),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    // Check that critical services have someone to page
+    if config.is_enabled(IssueType::MissingOwner) {
+        for service in services {
+            if ownership::requires_owner(service) && ownership::is_unowned(service) {
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Warning,
+                    issue_type: IssueType::MissingOwner,
+                    message: format!(
+                        "{} service '{}' has neither an owner nor a team",
+                        ownership::criticality(service),
+                        service.id
+                    ),
+                    affected_ids: vec![service.id.clone()],
+                    suggestion: Some("Set the owner or team responsible for it".to_string()),
+                    fingerprint: String::new(),
+                });
+            }
+        }
+    }
+
+    // Check that group members exist; deleting a service leaves its memberships behind
+    if config.is_enabled(IssueType::OrphanedGroupMember) {
+        for group in groups {
+            for member_id in &group.member_ids {
+                if !service_ids.contains(member_id) {
+                    issues.push(ValidationIssue {
+                        severity: IssueSeverity::Warning,
+                        issue_type: IssueType::OrphanedGroupMember,
+                        message: format!(
+                            "Group '{}' lists non-existent service '{}'",
+                            group.id, member_id
+                        ),
+                        affected_ids: vec![group.id.clone(), member_id.clone()],
+                        suggestion: Some(format!(
+                            "Remove '{}' from the group or create the service",
+                            member_id
+                        )),
+                        fingerprint: String::new(),
+                    });
+                }
+            }
+        }
+    }
+
+    // Check that annotations belong to existing services; `fix_validation_issues` prunes them
+    if config.is_enabled(IssueType::OrphanedAnnotation) {
+        for service_id in annotations.keys() {
+            if !service_ids.contains(service_id) {
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Info,
+                    issue_type: IssueType::OrphanedAnnotation,
+                    message: format!("Graph annotation for non-existent service '{}'", service_id),
+                    affected_ids: vec![service_id.clone()],
+                    suggestion: Some(
+                        "Prune orphaned annotations with fix_validation_issues".to_string(),
+                    ),
+                    fingerprint: String::new(),
+                });
+            }
+        }
+    }
+
+    // Check metadata against the schemas of the service types
+    if config.is_enabled(IssueType::MetadataSchemaViolation) {
+        let mut schemas = MetadataSchemas::new(data_path);
+        for service in services {
+            progress.check_cancelled()?;
+            for message in schemas.check(service)? {
+                issues.push(ValidationIssue {
+                    severity: IssueSeverity::Warning,
+                    issue_type: IssueType::MetadataSchemaViolation,
+                    message,
+                    affected_ids: vec![service.id.clone()],
+                    suggestion: Some(format!(
+                        "Update the metadata to match the '{}' schema",
+                        service.service_type.as_str()
+                    )),
+                    fingerprint: String::new(),
+                });
+            }
+        }
+    }
+
+    // Check naming conventions and per-type requirements
+    let check_naming = config.is_enabled(IssueType::NamingConvention);
+    let check_requirements = config.is_enabled(IssueType::MissingTypeRequirement);
+    if check_naming || check_requirements {
+        let checker = NamingChecker::new(&config.naming)?;
+        if !checker.is_empty() {
+            for service in services {
+                progress.check_cancelled()?;
+                for violation in checker.check(service) {
+                    if !config.is_enabled(violation.issue_type) {
+                        continue;
+                    }
+                    let suggestion = match violation.issue_type {
+                        IssueType::NamingConvention => "Rename to follow the naming conventions",
+                        _ => "Add the tags and metadata required for this service type",
+                    };
+                    issues.push(ValidationIssue {
+                        severity: IssueSeverity::Warning,
+                        issue_type: violation.issue_type,
+                        message: violation.message,
+                        affected_ids: vec![service.id.clone()],
+                        suggestion: Some(suggestion.to_string()),
+                        fingerprint: String::new(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Two services that may be duplicates of each other.
+///
+/// # Fields
+///
+/// * `first_id` / `first_name` - One of the services
+/// * `second_id` / `second_name` - The other service
+/// * `distance` - Edit distance between the normalized names (0 if they are equal)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PossibleDuplicate {
+    pub first_id: String,
+    pub first_name: String,
+    pub second_id: String,
+    pub second_name: String,
+    pub distance: usize,
+}
+
+/// Result of a possible duplicates scan.
+///
+/// # Fields
+///
+/// * `pairs` - The possible duplicates, closest first
+/// * `truncated` - True if the scan stopped at the maximum number of pairs
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PossibleDuplicatesResult {
+    pub pairs: Vec<PossibleDuplicate>,
+    pub truncated: bool,
+}
+
+/// Finds services that are probably the same service entered twice.
+///
+/// Service names are normalized (lowercased, with punctuation, whitespace and
+/// filler words like "service" removed) and compared pairwise by edit
+/// distance. "User API" and "user-api-service" both normalize to "userapi".
+/// Names that normalize to fewer than 6 characters only match exactly.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment to scan
+/// * `threshold` - Largest edit distance that counts as similar (default: 2);
+///   0 only matches equal normalized names
+///
+/// # Returns
+///
+/// * `Ok(PossibleDuplicatesResult)` - At most 200 pairs of possible duplicates
+/// * `Err(AppError)` - If the services cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const result = await invoke('find_possible_duplicates', { environment: 'prod', threshold: 1 });
+/// for (const pair of result.pairs) {
+///     console.log(`${pair.firstId} may duplicate ${pair.secondId}`);
+/// }
+/// ```
+#[tauri::command]
+pub fn find_possible_duplicates(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    threshold: Option<usize>,
+) -> Result<PossibleDuplicatesResult, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let (pairs, truncated) = similarity::similar_pairs(
+        &services,
+        threshold.unwrap_or(similarity::DEFAULT_MAX_DISTANCE),
+        similarity::MAX_REPORTED_PAIRS,
+    );
+
+    let pairs = pairs
+        .into_iter()
+        .map(|pair| {
+            let (first, second) = (&services[pair.first], &services[pair.second]);
+            PossibleDuplicate {
+                first_id: first.id.clone(),
+                first_name: first.name.clone(),
+                second_id: second.id.clone(),
+                second_name: second.name.clone(),
+                distance: pair.distance,
+            }
+        })
+        .collect();
+
+    Ok(PossibleDuplicatesResult { pairs, truncated })
+}
+
+/// Runs only the orphaned relationship and dependency cycle checks.
+///
+/// Used by `simulate_graph` on the data as a hypothetical change would leave
+/// it, so reviewers see the problems the change would introduce. The rules,
+/// severity overrides and suppressions apply as in `validate_environment`.
+///
+/// # Arguments
+///
+/// * `services` - All services
+/// * `relationships` - All relationships
+/// * `config` - The rule configuration; disabled rules are skipped
+/// * `suppressions` - The environment's suppressions
+pub fn check_structure<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    config: &ValidationConfig,
+    suppressions: &[Suppression],
+) -> ValidationResult {
+    let service_ids: HashSet<String> = services.iter().map(|s| s.borrow().id.clone()).collect();
+    let mut issues = Vec::new();
+
+    if config.is_enabled(IssueType::OrphanedRelationship) {
+        for relationship in relationships {
+            check_orphaned_ends(relationship, &service_ids, &mut issues);
+        }
+    }
+    if config.is_enabled(IssueType::CircularDependency) {
+        check_cycles(relationships, &service_ids, &mut issues);
+    }
+
+    ValidationResult::from_issues(issues, config, suppressions)
+}
+
+/// Reports each end of a relationship that isn't an existing service.
+fn check_orphaned_ends(
+    relationship: &Relationship,
+    service_ids: &HashSet<String>,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let ends = [
+        ("source", &relationship.source),
+        ("target", &relationship.target),
+    ];
+    for (end, service_id) in ends {
+        if service_ids.contains(service_id) {
+            continue;
+        }
+        issues.push(ValidationIssue {
+            severity: IssueSeverity::Error,
+            issue_type: IssueType::OrphanedRelationship,
+            message: format!(
+                "Relationship '{}' references non-existent {} service '{}'",
+                relationship.id, end, service_id
+            ),
+            affected_ids: vec![relationship.id.clone(), service_id.clone()],
+            suggestion: Some(format!(
+                "Create service '{}' or delete this relationship",
+                service_id
+            )),
+            fingerprint: String::new(),
+        });
+    }
+}
+
+/// Reports each dependency cycle.
+fn check_cycles(
+    relationships: &[Relationship],
+    service_ids: &HashSet<String>,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    for cycle in detect_circular_dependencies(relationships, service_ids) {
+        issues.push(ValidationIssue {
+            severity: IssueSeverity::Warning,
+            issue_type: IssueType::CircularDependency,
+            message: format!("Circular dependency detected: {}", cycle.join(" -> ")),
+            affected_ids: cycle,
+            suggestion: Some("Consider breaking the circular dependency".to_string()),
+            fingerprint: String::new(),
+        });
+    }
+}
+
+/// Runs the direction heuristics of `validate_environment` on typed relationships.
+///
+/// Relationships with a missing endpoint are skipped; they are reported as
+/// orphaned instead.
+///
+/// # Arguments
+///
+/// * `services` - All services in the environment
+/// * `relationships` - All relationships in the environment
+/// * `config` - The rule configuration; disabled heuristics are skipped
+/// * `issues` - Accumulator the issues found are appended to
+fn check_relationship_directions<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    config: &ValidationConfig,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let by_id: HashMap<&str, &Service> = services
+        .iter()
+        .map(|s| (s.borrow().id.as_str(), s.borrow()))
+        .collect();
+    let is_store = |service: &Service| {
+        matches!(
+            service.service_type,
+            ServiceType::Database | ServiceType::Cache
+        )
+    };
+    let is_auth_provider = |service: &Service| {
+        service.service_type == ServiceType::External
+            || service.tags.iter().any(|tag| {
+                let tag = tag.to_lowercase();
+                tag.contains("auth") || tag.contains("identity")
+            })
+    };
+
+    let mut push = |issue_type: IssueType, relationship: &Relationship, message, suggestion| {
+        if config.is_enabled(issue_type) {
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                issue_type,
+                message,
+                affected_ids: vec![
+                    relationship.id.clone(),
+                    relationship.source.clone(),
+                    relationship.target.clone(),
+                ],
+                suggestion: Some(suggestion),
+                fingerprint: String::new(),
+            });
+        }
+    };
+
+    for relationship in relationships {
+        let (Some(source), Some(target)) = (
+            by_id.get(relationship.source.as_str()),
+            by_id.get(relationship.target.as_str()),
+        ) else {
+            continue;
+        };
+        let kind = relationship.relationship_type.as_str();
+
+        match relationship.relationship_type {
+            RelationshipType::ReadsFrom | RelationshipType::WritesTo if is_store(source) => {
+                push(
+                    IssueType::ReversedDataFlow,
+                    relationship,
+                    format!(
+                        "Relationship '{}' has {} '{}' as the source of {}; the source should \
+                         be the service doing the I/O: '{}' {} '{}'",
+                        relationship.id,
+                        source.service_type.as_str(),
+                        source.id,
+                        kind,
+                        target.id,
+                        kind,
+                        source.id
+                    ),
+                    "Reverse the relationship".to_string(),
+                );
+            }
+            RelationshipType::DependsOn
+                if source.service_type == ServiceType::Database
+                    && target.service_type == ServiceType::Frontend =>
+            {
+                push(
+                    IssueType::ReversedDependency,
+                    relationship,
+                    format!(
+                        "Relationship '{}' has database '{}' depending on frontend '{}'; \
+                         expected '{}' depends_on '{}'",
+                        relationship.id, source.id, target.id, target.id, source.id
+                    ),
+                    "Reverse the relationship".to_string(),
+                );
+            }
+            RelationshipType::AuthenticatesVia if !is_auth_provider(target) => {
+                push(
+                    IssueType::UnlikelyAuthProvider,
+                    relationship,
+                    format!(
+                        "Relationship '{}' has '{}' authenticating via '{}', which is neither \
+                         external nor tagged 'auth' or 'identity'; the target should be the \
+                         auth provider: '{}' authenticates_via <provider>",
+                        relationship.id, source.id, target.id, source.id
+                    ),
+                    format!(
+                        "Point the relationship at the auth provider, or tag '{}' with 'auth'",
+                        target.id
+                    ),
+                );
+            }
+            RelationshipType::Publishes | RelationshipType::Subscribes
+                if source.service_type != ServiceType::Queue
+                    && target.service_type != ServiceType::Queue =>
+            {
+                push(
+                    IssueType::MissingQueue,
+                    relationship,
+                    format!(
+                        "Relationship '{}' ('{}' {} '{}') involves no queue; the target \
+                         should be the queue or topic: '{}' {} <queue>",
+                        relationship.id, source.id, kind, target.id, source.id, kind
+                    ),
+                    "Route the messages through a queue-type service".to_string(),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Joins relationships to the status of their targets.
+///
+/// Shared by the deprecated and unhealthy dependency checks and
+/// `get_deprecated_usage`.
+///
+/// # Arguments
+///
+/// * `services` - All services in the environment
+/// * `relationships` - The relationships to follow
+/// * `status` - The target status to look for
+///
+/// # Returns
+///
+/// Every service with `status`, by ID, with the sorted IDs of the services
+/// whose relationships point at it (self-references aside)
+fn dependents_with_status<'a, 'r, S: Borrow<Service>>(
+    services: &'a [S],
+    relationships: impl IntoIterator<Item = &'r Relationship>,
+    status: ServiceStatus,
+) -> Vec<(&'a Service, Vec<String>)> {
+    dependents_matching(services, relationships, |s| s.status == status)
+}
+
+/// Joins relationships to the targets matching a predicate.
+///
+/// # Returns
+///
+/// Every service matching `target`, by ID, with the sorted IDs of the
+/// services whose relationships point at it (self-references aside)
+fn dependents_matching<'a, 'r, S: Borrow<Service>>(
+    services: &'a [S],
+    relationships: impl IntoIterator<Item = &'r Relationship>,
+    target: impl Fn(&Service) -> bool,
+) -> Vec<(&'a Service, Vec<String>)> {
+    let mut dependents: HashMap<&str, BTreeSet<&str>> = services
+        .iter()
+        .map(Borrow::<Service>::borrow)
+        .filter(|s| target(s))
+        .map(|s| (s.id.as_str(), BTreeSet::new()))
+        .collect();
+
+    for relationship in relationships {
+        if relationship.source == relationship.target {
+            continue;
+        }
+        if let Some(sources) = dependents.get_mut(relationship.target.as_str()) {
+            sources.insert(relationship.source.as_str());
+        }
+    }
+
+    let mut usage: Vec<(&Service, Vec<String>)> = services
+        .iter()
+        .map(Borrow::<Service>::borrow)
+        .filter_map(|service| {
+            let sources = dependents.remove(service.id.as_str())?;
+            Some((service, sources.into_iter().map(String::from).collect()))
+        })
+        .collect();
+    usage.sort_by(|(a, _), (b, _)| a.id.cmp(&b.id));
+    usage
+}
+
+/// A depends_on relationship to a service of a less critical tier.
+///
+/// # Fields
+///
+/// * `relationship_id` - ID of the relationship
+/// * `source_id` / `source_tier` - The depending service and its tier
+/// * `target_id` / `target_tier` - The dependency and its (higher numbered) tier
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TierViolation {
+    pub relationship_id: String,
+    pub source_id: String,
+    pub source_tier: u8,
+    pub target_id: String,
+    pub target_tier: u8,
+}
+
+/// Tier violations of the services of one team.
+///
+/// # Fields
+///
+/// * `team` - The team of the depending services (absent for services without one)
+/// * `violations` - Their tier violations
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamTierViolations {
+    pub team: Option<String>,
+    pub violations: Vec<TierViolation>,
+}
+
+/// Tier classification health of an environment.
+///
+/// # Fields
+///
+/// * `teams` - Tier violations grouped by the team of the depending service, most
+///   violations first
+/// * `violation_count` - Total number of tier violations
+/// * `unclassified` - IDs of the services without a tier, sorted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TierReport {
+    pub teams: Vec<TeamTierViolations>,
+    pub violation_count: usize,
+    pub unclassified: Vec<String>,
+}
+
+/// Finds depends_on relationships from a service to one of a less critical tier.
+///
+/// Shared by the tier violation check and `get_tier_report`. Relationships
+/// with an untiered or missing endpoint are skipped.
+///
+/// # Returns
+///
+/// The violations in relationship order
+fn tier_violations<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+) -> Vec<TierViolation> {
+    let tiers: HashMap<&str, u8> = services
+        .iter()
+        .map(Borrow::<Service>::borrow)
+        .filter_map(|s| Some((s.id.as_str(), s.tier?)))
+        .collect();
+
+    relationships
+        .iter()
+        .filter(|r| r.relationship_type == RelationshipType::DependsOn)
+        .filter_map(|r| {
+            let source_tier = *tiers.get(r.source.as_str())?;
+            let target_tier = *tiers.get(r.target.as_str())?;
+            (source_tier < target_tier).then(|| TierViolation {
+                relationship_id: r.id.clone(),
+                source_id: r.source.clone(),
+                source_tier,
+                target_id: r.target.clone(),
+                target_tier,
+            })
+        })
+        .collect()
+}
+
+/// Returns the sorted IDs of the services without a tier.
+fn unclassified_services<S: Borrow<Service>>(services: &[S]) -> Vec<String> {
+    let mut ids: Vec<String> = services
+        .iter()
+        .map(Borrow::<Service>::borrow)
+        .filter(|s| s.tier.is_none())
+        .map(|s| s.id.clone())
+        .collect();
+    ids.sort();
+    ids
+}
+
+/// Lists the first few IDs, noting how many more there are.
+fn summarize_ids(ids: &[String]) -> String {
+    const SHOWN: usize = 5;
+
+    let shown = ids
+        .iter()
+        .take(SHOWN)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if ids.len() > SHOWN {
+        format!("{} and {} more", shown, ids.len() - SHOWN)
+    } else {
+        shown
+    }
+}
+
+/// Reports the tier violations of an environment, grouped by team.
+///
+/// A tier violation is a depends_on relationship from a service to one of a
+/// less critical tier (e.g., a tier-1 service depending on a tier-3 one).
+/// Violations are grouped by the team of the depending service, the team
+/// that has to remediate them.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(TierReport)` - The violations and the unclassified services
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const report = await invoke('get_tier_report', { environment: 'prod' });
+/// for (const { team, violations } of report.teams) {
+///     console.log(`${team ?? 'No team'}: ${violations.length} violations`);
+/// }
+/// ```
+#[tauri::command]
+pub fn get_tier_report(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<TierReport, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    let teams: HashMap<&str, Option<&String>> = services
+        .iter()
+        .map(|s| (s.id.as_str(), s.team.as_ref()))
+        .collect();
+
+    let violations = tier_violations(&services, &relationships);
+    let violation_count = violations.len();
+
+    let mut by_team: HashMap<Option<String>, Vec<TierViolation>> = HashMap::new();
+    for violation in violations {
+        let team = teams
+            .get(violation.source_id.as_str())
+            .copied()
+            .flatten()
+            .cloned();
+        by_team.entry(team).or_default().push(violation);
+    }
+
+    let mut teams: Vec<TeamTierViolations> = by_team
+        .into_iter()
+        .map(|(team, violations)| TeamTierViolations { team, violations })
+        .collect();
+    teams.sort_by(|a, b| {
+        b.violations
+            .len()
+            .cmp(&a.violations.len())
+            .then_with(|| a.team.cmp(&b.team))
+    });
+
+    Ok(TierReport {
+        teams,
+        violation_count,
+        unclassified: unclassified_services(&services),
+    })
+}
+
+/// A deprecated service and the services still using it.
+///
+/// # Fields
+///
+/// * `service_id` - ID of the deprecated service
+/// * `service_name` - Name of the deprecated service
+/// * `dependents` - IDs of the services with relationships pointing at it, sorted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeprecatedUsage {
+    pub service_id: String,
+    pub service_name: String,
+    pub dependents: Vec<String>,
+}
+
+/// Lists the deprecated services of an environment and who still uses them.
+///
+/// Any relationship pointing at a deprecated service counts, whatever its
+/// type. Deprecated services nothing points at are included with no
+/// dependents, as they can be removed.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(Vec<DeprecatedUsage>)` - One entry per deprecated service, most used first
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const usage = await invoke('get_deprecated_usage', { environment: 'prod' });
+/// for (const { serviceId, dependents } of usage) {
+///     console.log(`${serviceId}: ${dependents.length} services to migrate`);
+/// }
+/// ```
+#[tauri::command]
+pub fn get_deprecated_usage(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<Vec<DeprecatedUsage>, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    let deprecated =
+        dependents_with_status(&services, relationships.iter(), ServiceStatus::Deprecated);
+
+    let mut usage: Vec<DeprecatedUsage> = deprecated
+        .into_iter()
+        .map(|(service, dependents)| DeprecatedUsage {
+            service_id: service.id.clone(),
+            service_name: service.name.clone(),
+            dependents,
+        })
+        .collect();
+    usage.sort_by_key(|u| Reverse(u.dependents.len()));
+
+    Ok(usage)
+}
+
+/// A service on its way out and the services still using it.
+///
+/// # Fields
+///
+/// * `service_id` - ID of the service
+/// * `service_name` - Name of the service
+/// * `status` - The service's status
+/// * `lifecycle` - The service's lifecycle stage, if set
+/// * `decommission_date` - The planned decommission date, if set
+/// * `successor_id` - ID of the service replacing it, if set
+/// * `dependents` - IDs of the services with relationships pointing at it, sorted
+/// * `overdue` - Whether the decommission date has passed without the service being
+///   decommissioned
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecommissionPlanEntry {
+    pub service_id: String,
+    pub service_name: String,
+    pub status: ServiceStatus,
+    pub lifecycle: Option<ServiceLifecycle>,
+    pub decommission_date: Option<String>,
+    pub successor_id: Option<String>,
+    pub dependents: Vec<String>,
+    pub overdue: bool,
+}
+
+/// Lists the services being retired and what still has to migrate off them.
+///
+/// A service is included if its status is deprecated, its lifecycle is
+/// deprecated or decommissioned, or it has a decommission date. As with
+/// `get_deprecated_usage`, any relationship pointing at it counts.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(Vec<DecommissionPlanEntry>)` - The services by decommission date, earliest
+///   first, then those without a date, each by ID
+/// * `Err(AppError)` - If the services or relationships cannot be loaded
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const plan = await invoke('get_decommission_plan', { environment: 'prod' });
+/// for (const entry of plan.filter((e) => e.overdue)) {
+///     console.log(`${entry.serviceId} is overdue, used by ${entry.dependents.length}`);
+/// }
+/// ```
+#[tauri::command]
+pub fn get_decommission_plan(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<Vec<DecommissionPlanEntry>, AppError> {
+    let services = cache::shared_services(&state, &environment)?;
+    let relationships = cache::shared_relationships(&state, &environment)?;
+
+    let retiring = dependents_matching(&services, relationships.iter(), |s| {
+        s.status == ServiceStatus::Deprecated
+            || matches!(
+                s.lifecycle,
+                Some(ServiceLifecycle::Deprecated | ServiceLifecycle::Decommissioned)
+            )
+            || s.decommission_date.is_some()
+    });
+
+    let today = dates::today();
+    let mut plan: Vec<DecommissionPlanEntry> = retiring
+        .into_iter()
+        .map(|(service, dependents)| DecommissionPlanEntry {
+            service_id: service.id.clone(),
+            service_name: service.name.clone(),
+            status: service.status.clone(),
+            lifecycle: service.lifecycle,
+            decommission_date: service.decommission_date.clone(),
+            successor_id: service.successor_id.clone(),
+            dependents,
+            overdue: service.lifecycle != Some(ServiceLifecycle::Decommissioned)
+                && lifecycle::is_past_decommission_date(service, &today),
+        })
+        .collect();
+    // Entries arrive sorted by ID; undated entries go last
+    plan.sort_by(|a, b| match (&a.decommission_date, &b.decommission_date) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    Ok(plan)
+}
+
+/// Retrieves the validation rule configuration of the active data path.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+///
+/// # Returns
+///
+/// * `Ok(ValidationConfig)` - The config (defaults if none was saved)
+/// * `Err(AppError::FileLoad)` - If the config file isn't valid JSON
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const config = await invoke('get_validation_config');
+/// console.log(config.disabledRules); // ['unreachable_service']
+/// ```
+#[tauri::command]
+pub fn get_validation_config(
+    state: State<'_, RwLock<AppState>>,
+) -> Result<ValidationConfig, AppError> {
+    let state = read_state(&state);
+    storage::load_validation_config(&state.data_path)
+}
+
+/// Replaces the validation rule configuration of the active data path.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `config` - The complete new config
+///
+/// # Returns
+///
+/// * `Ok(())` - If the config was saved
+/// * `Err(AppError::ValidationError)` - If a naming pattern isn't a valid regular
+///   expression; nothing is saved
+/// * `Err(AppError::Io)` - If the config file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `.validation.json` in the data directory (or `{database}.validation.json`
+///   next to a SQLite database)
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: treat cycles as errors and hide isolated services
+/// await invoke('save_validation_config', {
+///     config: {
+///         disabledRules: ['unreachable_service'],
+///         severityOverrides: { circular_dependency: 'error' }
+///     }
+/// });
+///
+/// // Require kebab-case IDs and reject saves that break the convention
+/// await invoke('save_validation_config', {
+///     config: {
+///         naming: { serviceIdPattern: '[a-z0-9]+(-[a-z0-9]+)*', strict: true }
+///     }
+/// });
+/// ```
+#[tauri::command]
+pub fn save_validation_config(
+    state: State<'_, RwLock<AppState>>,
+    config: ValidationConfig,
+) -> Result<(), AppError> {
+    NamingChecker::new(&config.naming)?;
+
+    let state = write_state(&state);
+    storage::save_validation_config(&state.data_path, &config)
+}
+
+/// Retrieves the metadata JSON Schema of a service type.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `service_type` - The service type as serialized (e.g., "database", or a custom
+///   type's name)
+///
+/// # Returns
+///
+/// * `Ok(Some(schema))` - The stored schema
+/// * `Ok(None)` - If the type has no schema, so its metadata isn't validated
+/// * `Err(AppError::ValidationError)` - If the type can't be used as a filename
+/// * `Err(AppError::FileLoad)` - If the schema file isn't valid JSON
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const schema = await invoke('get_metadata_schema', { serviceType: 'database' });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_metadata_schema(
+    state: State<'_, RwLock<AppState>>,
+    service_type: String,
+) -> Result<Option<serde_json::Value>, AppError> {
+    let state = read_state(&state);
+    storage::load_metadata_schema(&state.data_path, &service_type)
+}
+
+/// Sets the metadata JSON Schema of a service type.
+///
+/// `validate_environment` then checks the metadata of every service of the
+/// type against it, and `save_service` rejects non-conforming metadata when
+/// the validation config's `strictMetadataSchemas` is set.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `service_type` - The service type as serialized (e.g., "database", or a custom
+///   type's name)
+/// * `schema` - The JSON Schema services' `metadata` objects must satisfy
+///
+/// # Returns
+///
+/// * `Ok(())` - If the schema was saved
+/// * `Err(AppError::ValidationError)` - If the schema isn't a valid JSON Schema or the
+///   type can't be used as a filename; nothing is saved
+/// * `Err(AppError::Io)` - If the schema file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `.schemas/{serviceType}.json` in the data directory (or
+///   `{database}.schemas/{serviceType}.json` next to a SQLite database)
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend: databases must record their engine, version and instance class
+/// await invoke('save_metadata_schema', {
+///     serviceType: 'database',
+///     schema: {
+///         type: 'object',
+///         required: ['engine', 'version', 'instanceClass'],
+///         properties: { engine: { type: 'string' }, version: { type: 'string' } }
+///     }
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn save_metadata_schema(
+    state: State<'_, RwLock<AppState>>,
+    service_type: String,
+    schema: serde_json::Value,
+) -> Result<(), AppError> {
+    metadata_schema::compile(&service_type, &schema)?;
+
+    let state = write_state(&state);
+    storage::save_metadata_schema(&state.data_path, &service_type, &schema)
+}
+
+/// Suppresses an accepted validation issue in an environment.
+///
+/// Suppressed issues are still returned by `validate_environment`, but in
+/// `suppressedIssues` and without being counted. Suppressing an issue that
+/// is already suppressed replaces the existing suppression.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+/// * `issue_fingerprint` - The `fingerprint` of the issue to suppress
+/// * `reason` - Why the issue is accepted
+/// * `expires` - When the suppression stops applying, in milliseconds since the Unix
+///   epoch; never if absent
+///
+/// # Returns
+///
+/// * `Ok(Suppression)` - The stored suppression
+/// * `Err(AppError::ValidationError)` - If the fingerprint or reason is empty, or
+///   `expires` is in the past
+/// * `Err(AppError::Io)` - If the suppressions file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `{environment}/.suppressions.json` in the data directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('suppress_validation_issue', {
+///     environment: 'prod',
+///     issueFingerprint: issue.fingerprint,
+///     reason: 'Known cycle between auth and session, tracked in OPS-42',
+///     expires: Date.parse('2027-01-01')
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn suppress_validation_issue(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    issue_fingerprint: String,
+    reason: String,
+    expires: Option<u64>,
+) -> Result<Suppression, AppError> {
+    if issue_fingerprint.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "Issue fingerprint cannot be empty".to_string(),
+        ));
+    }
+    if reason.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "A reason is required to suppress an issue".to_string(),
+        ));
+    }
+
+    let now = audit::now_millis();
+    if expires.is_some_and(|expires| expires <= now) {
+        return Err(AppError::ValidationError(
+            "Suppression expiry must be in the future".to_string(),
+        ));
+    }
+
+    let state = write_state(&state);
+    let mut suppressions = storage::load_suppressions(&state.data_path, &environment)?;
+
+    let suppression = Suppression {
+        fingerprint: issue_fingerprint,
+        reason,
+        user: audit::current_user(),
+        created_at: now,
+        expires_at: expires,
+    };
+    suppressions.retain(|s| s.fingerprint != suppression.fingerprint);
+    suppressions.push(suppression.clone());
+
+    storage::save_suppressions(&state.data_path, &environment, &suppressions)?;
+
+    Ok(suppression)
+}
+
+/// Lists the suppressions of an environment.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(Vec<Suppression>)` - All suppressions, including expired ones
+/// * `Err(AppError::FileLoad)` - If the suppressions file cannot be parsed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const suppressions = await invoke('list_suppressions', { environment: 'prod' });
+/// const expired = suppressions.filter(s => s.expiresAt && s.expiresAt < Date.now());
+/// ```
+#[tauri::command]
+pub fn list_suppressions(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+) -> Result<Vec<Suppression>, AppError> {
+    let state = read_state(&state);
+    storage::load_suppressions(&state.data_path, &environment)
+}
+
+/// Removes the suppression of an issue, so it is counted again.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment
+/// * `issue_fingerprint` - The fingerprint of the suppressed issue
+///
+/// # Returns
+///
+/// * `Ok(true)` - If a suppression was removed
+/// * `Ok(false)` - If the issue wasn't suppressed
+/// * `Err(AppError::Io)` - If the suppressions file cannot be written
+///
+/// # Side Effects
+///
+/// - Rewrites `{environment}/.suppressions.json` when a suppression was removed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('remove_suppression', {
+///     environment: 'prod',
+///     issueFingerprint: suppression.fingerprint
+/// });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn remove_suppression(
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    issue_fingerprint: String,
+) -> Result<bool, AppError> {
+    let state = write_state(&state);
+    let mut suppressions = storage::load_suppressions(&state.data_path, &environment)?;
+
+    let original_len = suppressions.len();
+    suppressions.retain(|s| s.fingerprint != issue_fingerprint);
+    if suppressions.len() == original_len {
+        return Ok(false);
+    }
+
+    storage::save_suppressions(&state.data_path, &environment, &suppressions)?;
+
+    Ok(true)
+}
+
+/// A service file that was (or would be) renamed to match its contained ID.
 ///
-/// Determines how critical an issue is and how it should be displayed in the UI.
+/// # Fields
 ///
-/// # Variants
+/// * `service_id` - The ID contained in the file
+/// * `from_path` - The file's current path
+/// * `to_path` - The path matching the service ID
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilenameRepair {
+    pub service_id: String,
+    pub from_path: String,
+    pub to_path: String,
+}
+
+/// A mismatched service file that could not be renamed automatically.
 ///
-/// * `Error` - Critical issues that indicate data corruption or invalid state
-/// * `Warning` - Potential problems that may cause issues but don't break functionality
-/// * `Info` - Informational notices about the data structure
-#[derive(Debug, Clone, Serialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
-pub enum IssueSeverity {
-    Error,
-    Warning,
-    Info,
+/// # Fields
+///
+/// * `service_id` - The ID contained in the file
+/// * `path` - The file's current path
+/// * `reason` - Why the file needs manual resolution
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilenameConflict {
+    pub service_id: String,
+    pub path: String,
+    pub reason: String,
 }
 
-/// Categories of validation issues that can be detected.
+/// The result of a `repair_filenames` run.
 ///
-/// Each issue type corresponds to a specific kind of data integrity problem.
+/// # Fields
 ///
-/// # Variants
+/// * `renamed` - Files that were renamed (or would be, in a dry run)
+/// * `conflicts` - Files that need manual resolution
+/// * `dry_run` - Whether the run only reported changes without applying them
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairFilenamesResult {
+    pub renamed: Vec<FilenameRepair>,
+    pub conflicts: Vec<FilenameConflict>,
+    pub dry_run: bool,
+}
+
+/// Renames service files so their filenames match the IDs they contain.
 ///
-/// * `OrphanedRelationship` - A relationship references a service that doesn't exist
-/// * `DuplicateServiceId` - Multiple services share the same ID
-/// * `MissingRequiredField` - A service is missing required fields (id, name)
-/// * `InvalidRelationshipType` - A relationship uses an unknown type
-/// * `CircularDependency` - Services form a dependency cycle (A -> B -> A)
-/// * `UnreachableService` - A service has no relationships (informational)
-#[derive(Debug, Clone, Serialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
-pub enum IssueType {
-    OrphanedRelationship,
-    DuplicateServiceId,
-    MissingRequiredField,
-    InvalidRelationshipType,
-    CircularDependency,
-    UnreachableService,
+/// Fixes the `FilenameMismatch` issues reported by `validate_environment`.
+/// A file is only renamed when its target path is free; when another file
+/// already occupies `{id}.json` (or several files contain the same ID), the
+/// file is reported as a conflict for manual resolution instead.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to queue git auto-commits
+/// * `state` - The application state containing the data path
+/// * `environment` - The name of the environment to repair
+/// * `dry_run` - When true, report what would be renamed without touching disk
+///
+/// # Returns
+///
+/// * `Ok(RepairFilenamesResult)` - The renamed files and unresolved conflicts
+/// * `Err(AppError::ValidationError)` - If the active data path is a SQLite database
+/// * `Err(AppError::Io)` - If there's an error reading or renaming files
+/// * `Err(AppError::FileLoad)` - If a service file cannot be read or parsed
+///
+/// # Side Effects
+///
+/// - Renames mismatched files in `{data_path}/{environment}/services/`
+/// - Invalidates the environment's services cache when anything was renamed
+/// - Appends one audit log record per renamed file
+/// - Commits all renames together to git when the `gitAutoCommit` setting is enabled
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const preview = await invoke('repair_filenames', { environment: 'dev', dryRun: true });
+/// console.log(`${preview.renamed.length} files would be renamed`);
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn repair_filenames<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    environment: String,
+    dry_run: bool,
+) -> Result<RepairFilenamesResult, AppError> {
+    let mut state = write_state(&state);
+
+    let fs_backend = state.storage.as_file_system().cloned().ok_or_else(|| {
+        AppError::ValidationError(
+            "Filename repair is only available for directory data paths".to_string(),
+        )
+    })?;
+    let data_path = fs_backend.data_path();
+
+    let service_files =
+        loader::load_service_files(data_path, &environment, fs_backend.encryption_key())?;
+
+    // Count how many files claim each ID so collisions can be reported
+    let mut id_counts: HashMap<String, usize> = HashMap::new();
+    for (_, service) in &service_files {
+        *id_counts.entry(service.id.clone()).or_insert(0) += 1;
+    }
+
+    let mut renamed = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for (path, service) in &service_files {
+        if file_stem(path) == service.id {
+            continue;
+        }
+
+        let conflict = |reason: String| FilenameConflict {
+            service_id: service.id.clone(),
+            path: path.display().to_string(),
+            reason,
+        };
+
+        let target = match loader::service_file_path(data_path, &environment, &service.id) {
+            Ok(target) => target,
+            Err(e) => {
+                conflicts.push(conflict(e.to_string()));
+                continue;
+            }
+        };
+
+        if id_counts.get(&service.id).copied().unwrap_or(0) > 1 {
+            conflicts.push(conflict(format!(
+                "Multiple files contain the ID '{}'",
+                service.id
+            )));
+            continue;
+        }
+
+        if target.exists() {
+            conflicts.push(conflict(format!(
+                "Target file '{}' already exists",
+                target.display()
+            )));
+            continue;
+        }
+
+        if !dry_run {
+            fs::rename(path, &target)?;
+        }
+
+        renamed.push(FilenameRepair {
+            service_id: service.id.clone(),
+            from_path: path.display().to_string(),
+            to_path: target.display().to_string(),
+        });
+    }
+
+    if !dry_run && !renamed.is_empty() {
+        state.record_write(&environment);
+        state.services_cache.remove(&environment);
+        state.quick_open_indexes.remove(&environment);
+
+        let records: Vec<AuditRecord> = renamed
+            .iter()
+            .map(|r| {
+                AuditRecord::new("repair_filenames", vec![r.service_id.clone()])
+                    .with_change(Some(r.from_path.clone()), Some(r.to_path.clone()))
+            })
+            .collect();
+        state.record_audit(&environment, &records);
+
+        git::auto_commit(
+            &app,
+            &state,
+            "repair_filenames",
+            format!("Rename {} service files in {}", renamed.len(), environment),
+            renamed
+                .iter()
+                .flat_map(|r| [PathBuf::from(&r.from_path), PathBuf::from(&r.to_path)])
+                .collect(),
+        );
+    }
+
+    Ok(RepairFilenamesResult {
+        renamed,
+        conflicts,
+        dry_run,
+    })
 }
 
-/// Represents a single validation issue found in the environment data.
+/// The issue categories `fix_validation_issues` should remediate.
 ///
-/// Contains all information needed to display the issue in the UI and
-/// help the user resolve it.
+/// All fields default to false.
 ///
 /// # Fields
 ///
-/// * `severity` - How critical the issue is (error, warning, info)
-/// * `issue_type` - The category of the issue
-/// * `message` - Human-readable description of the problem
-/// * `affected_ids` - IDs of services/relationships involved
-/// * `suggestion` - Optional recommendation for fixing the issue
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ValidationIssue {
-    pub severity: IssueSeverity,
-    pub issue_type: IssueType,
-    pub message: String,
-    pub affected_ids: Vec<String>,
-    pub suggestion: Option<String>,
+/// * `delete_orphaned_relationships` - Delete relationships whose source or target
+///   service doesn't exist
+/// * `remove_duplicate_relationships` - Delete relationships identical to an earlier
+///   one (same source, target, type, description and metadata)
+/// * `strip_deprecated_relationships` - Delete relationships from or to services
+///   with status Deprecated
+/// * `create_placeholder_services` - Create a stub service (status Unknown, tagged
+///   "placeholder") for every missing endpoint of an orphaned relationship; an
+///   alternative to `delete_orphaned_relationships`
+/// * `prune_orphaned_annotations` - Delete graph annotations of services that don't exist
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ValidationFixes {
+    pub delete_orphaned_relationships: bool,
+    pub remove_duplicate_relationships: bool,
+    pub strip_deprecated_relationships: bool,
+    pub create_placeholder_services: bool,
+    pub prune_orphaned_annotations: bool,
 }
 
-/// The complete result of validating an environment.
-///
-/// Contains all discovered issues along with summary counts for quick
-/// assessment of the environment's health.
+/// Issue counts of a validation run.
 ///
 /// # Fields
 ///
-/// * `issues` - All validation issues found
 /// * `error_count` - Number of critical errors
 /// * `warning_count` - Number of warnings
 /// * `info_count` - Number of informational notices
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ValidationResult {
-    pub issues: Vec<ValidationIssue>,
+pub struct IssueCounts {
     pub error_count: usize,
     pub warning_count: usize,
     pub info_count: usize,
 }
 
-/// Validates the entire environment for data integrity issues.
+impl From<&ValidationResult> for IssueCounts {
+    fn from(result: &ValidationResult) -> Self {
+        Self {
+            error_count: result.error_count,
+            warning_count: result.warning_count,
+            info_count: result.info_count,
+        }
+    }
+}
+
+/// The result of a `fix_validation_issues` run.
 ///
-/// Performs comprehensive validation of all services and relationships in
-/// the specified environment. This includes checking for:
+/// # Fields
 ///
-/// 1. **Duplicate Service IDs** (Error) - Multiple services with the same ID
-/// 2. **Missing Required Fields** (Error) - Services without id or name
-/// 3. **Orphaned Relationships** (Error) - Relationships referencing non-existent services
-/// 4. **Invalid Relationship Types** (Warning) - Unknown relationship types
-/// 5. **Circular Dependencies** (Warning) - Dependency cycles in "depends_on" relationships
-/// 6. **Unreachable Services** (Info) - Services with no relationships
+/// * `deleted_orphaned_relationships` - IDs of deleted orphaned relationships
+/// * `removed_duplicate_relationships` - IDs of deleted duplicate relationships
+/// * `stripped_deprecated_relationships` - IDs of deleted relationships of deprecated services
+/// * `placeholder_services` - The placeholder services created
+/// * `pruned_annotations` - Service IDs whose orphaned graph annotations were deleted
+/// * `before` - Issue counts before the fixes
+/// * `after` - Issue counts after the fixes (as they would be, in a dry run)
+/// * `dry_run` - Whether the run only reported changes without applying them
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FixValidationIssuesResult {
+    pub deleted_orphaned_relationships: Vec<String>,
+    pub removed_duplicate_relationships: Vec<String>,
+    pub stripped_deprecated_relationships: Vec<String>,
+    pub placeholder_services: Vec<Service>,
+    pub pruned_annotations: Vec<String>,
+    pub before: IssueCounts,
+    pub after: IssueCounts,
+    pub dry_run: bool,
+}
+
+/// Automatically remediates selected categories of validation issues.
+///
+/// Relationships are checked in file order; each one is handled by the first
+/// selected fix that applies, in the order duplicates, deprecated endpoints,
+/// orphans. Placeholder services are only created for missing IDs that are
+/// valid service IDs. After computing the fixes, validation is run again on
+/// the fixed data to report the new counts.
 ///
 /// # Arguments
 ///
+/// * `app` - The application handle, used to queue git auto-commits
 /// * `state` - The application state containing the data path
-/// * `environment` - The name of the environment to validate
+/// * `environment` - The name of the environment to fix
+/// * `fixes` - Which issue categories to remediate
+/// * `dry_run` - When true, report what would change without touching disk (default false)
 ///
 /// # Returns
 ///
-/// * `Ok(ValidationResult)` - The validation results with all issues and counts
-/// * `Err(AppError::StateLock)` - If the application state mutex cannot be acquired
-/// * `Err(AppError::Io)` - If there's an error reading the data files
+/// * `Ok(FixValidationIssuesResult)` - The items changed and the counts before and after
+/// * `Err(AppError::ValidationError)` - If both `deleteOrphanedRelationships` and
+///   `createPlaceholderServices` are selected
+/// * `Err(AppError::Io)` - If there's an error reading or writing the data files
 ///
-/// # Algorithm Details
+/// # Side Effects
 ///
-/// - Circular dependency detection uses DFS (Depth-First Search) on "depends_on" relationships
-/// - Duplicate cycle detection normalizes cycles for comparison
-/// - All checks are performed in a single pass where possible for efficiency
+/// Unless `dry_run` is set and if anything changed:
+/// - Writes the placeholder service files and `relationships.json`
+/// - Writes `.annotations.json` when annotations were pruned; pruning is not journaled
+/// - Updates the environment's caches
+/// - Records all fixes as a single entry in the undo journal
+/// - Appends one audit log record per changed service and relationship
+/// - Commits the written files together to git when the `gitAutoCommit` setting is enabled
 ///
 /// # Examples
 ///
 /// ```typescript
 /// // From the frontend:
-/// const result = await invoke('validate_environment', { environment: 'dev' });
-/// console.log(`Found ${result.errorCount} errors, ${result.warningCount} warnings`);
-///
-/// for (const issue of result.issues) {
-///     console.log(`[${issue.severity}] ${issue.message}`);
-///     if (issue.suggestion) {
-///         console.log(`  Suggestion: ${issue.suggestion}`);
-///     }
-/// }
+/// const result = await invoke('fix_validation_issues', {
+///     environment: 'dev',
+///     fixes: { deleteOrphanedRelationships: true, removeDuplicateRelationships: true },
+///     dryRun: true
+/// });
+/// console.log(`Errors: ${result.before.errorCount} -> ${result.after.errorCount}`);
 /// ```
-#[tauri::command]
-pub fn validate_environment(
-    state: State<'_, Mutex<AppState>>,
+#[tauri::command(rename_all = "camelCase")]
+pub fn fix_validation_issues<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
     environment: String,
-) -> Result<ValidationResult, AppError> {
-    let state = state.lock().map_err(|_| AppError::StateLock)?;
+    fixes: ValidationFixes,
+    dry_run: Option<bool>,
+) -> Result<FixValidationIssuesResult, AppError> {
+    let dry_run = dry_run.unwrap_or(false);
+    if fixes.delete_orphaned_relationships && fixes.create_placeholder_services {
+        return Err(AppError::ValidationError(
+            "Choose either deleting orphaned relationships or creating placeholder services"
+                .to_string(),
+        ));
+    }
 
-    let services = loader::load_services(&state.data_path, &environment)?;
-    let relationships = loader::load_relationships(&state.data_path, &environment)?;
+    let mut state = write_state(&state);
+    let config = storage::load_validation_config(&state.data_path)?;
+    let suppressions = storage::load_suppressions(&state.data_path, &environment)?;
 
-    let mut issues = Vec::new();
+    let mut layout_issues = Vec::new();
+    let services = load_services_checked(
+        state.storage.as_ref(),
+        &environment,
+        &config,
+        &mut layout_issues,
+    )?;
+    let relationships = state.storage.load_relationships(&environment)?;
+    let groups = storage::load_groups(&state.data_path, &environment)?;
+    let mut annotations = storage::load_annotations(&state.data_path, &environment)?;
 
-    // Build service ID set for lookups
-    let service_ids: HashSet<String> = services.iter().map(|s| s.id.clone()).collect();
+    let mut issues = layout_issues.clone();
+    issues.extend(check_data(
+        &services,
+        &relationships,
+        &groups,
+        &annotations,
+        &config,
+        &state.data_path,
+        &Progress::none(),
+    )?);
+    check_import_freshness(
+        &state.data_path,
+        &environment,
+        &relationships,
+        &config,
+        &mut issues,
+    )?;
+    let before_result = ValidationResult::from_issues(issues, &config, &suppressions);
+    let before = IssueCounts::from(&before_result);
 
-    // Check for duplicate service IDs (shouldn't happen but check anyway)
-    let mut seen_ids: HashMap<String, usize> = HashMap::new();
-    for service in &services {
-        *seen_ids.entry(service.id.clone()).or_insert(0) += 1;
-    }
-    for (id, count) in &seen_ids {
-        if *count > 1 {
-            issues.push(ValidationIssue {
-                severity: IssueSeverity::Error,
-                issue_type: IssueType::DuplicateServiceId,
-                message: format!("Duplicate service ID '{}' found {} times", id, count),
-                affected_ids: vec![id.clone()],
-                suggestion: Some("Rename one of the duplicate services".to_string()),
-            });
-        }
-    }
+    let service_ids: HashSet<&str> = services.iter().map(|s| s.id.as_str()).collect();
+    let deprecated_ids: HashSet<&str> = services
+        .iter()
+        .filter(|s| s.status == ServiceStatus::Deprecated)
+        .map(|s| s.id.as_str())
+        .collect();
 
-    // Check for missing required fields in services
-    for service in &services {
-        let missing_fields = check_required_fields(service);
-        if !missing_fields.is_empty() {
-            issues.push(ValidationIssue {
-                severity: IssueSeverity::Error,
-                issue_type: IssueType::MissingRequiredField,
-                message: format!(
-                    "Service '{}' is missing required fields: {}",
-                    service.id,
-                    missing_fields.join(", ")
-                ),
-                affected_ids: vec![service.id.clone()],
-                suggestion: Some(format!("Add missing fields: {}", missing_fields.join(", "))),
-            });
-        }
-    }
+    let mut result = FixValidationIssuesResult {
+        deleted_orphaned_relationships: Vec::new(),
+        removed_duplicate_relationships: Vec::new(),
+        stripped_deprecated_relationships: Vec::new(),
+        placeholder_services: Vec::new(),
+        pruned_annotations: Vec::new(),
+        before,
+        after: IssueCounts {
+            error_count: 0,
+            warning_count: 0,
+            info_count: 0,
+        },
+        dry_run,
+    };
+    let mut placeholder_ids: HashSet<&str> = HashSet::new();
+    let mut kept: Vec<Relationship> = Vec::with_capacity(relationships.len());
+    // Indices into `kept` by endpoints, to find duplicates without comparing every pair
+    let mut kept_by_endpoints: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
 
-    // Check for orphaned relationships
     for relationship in &relationships {
-        if !service_ids.contains(&relationship.source) {
-            issues.push(ValidationIssue {
-                severity: IssueSeverity::Error,
-                issue_type: IssueType::OrphanedRelationship,
-                message: format!(
-                    "Relationship '{}' references non-existent source service '{}'",
-                    relationship.id, relationship.source
-                ),
-                affected_ids: vec![relationship.id.clone(), relationship.source.clone()],
-                suggestion: Some(format!(
-                    "Create service '{}' or delete this relationship",
-                    relationship.source
-                )),
+        let endpoints = (relationship.source.as_str(), relationship.target.as_str());
+
+        if fixes.remove_duplicate_relationships {
+            let is_duplicate = kept_by_endpoints.get(&endpoints).is_some_and(|indices| {
+                indices
+                    .iter()
+                    .any(|&i| is_exact_duplicate(&kept[i], relationship))
             });
+            if is_duplicate {
+                result
+                    .removed_duplicate_relationships
+                    .push(relationship.id.clone());
+                continue;
+            }
         }
 
-        if !service_ids.contains(&relationship.target) {
-            issues.push(ValidationIssue {
-                severity: IssueSeverity::Error,
-                issue_type: IssueType::OrphanedRelationship,
-                message: format!(
-                    "Relationship '{}' references non-existent target service '{}'",
-                    relationship.id, relationship.target
-                ),
-                affected_ids: vec![relationship.id.clone(), relationship.target.clone()],
-                suggestion: Some(format!(
-                    "Create service '{}' or delete this relationship",
-                    relationship.target
-                )),
-            });
+        if fixes.strip_deprecated_relationships
+            && (deprecated_ids.contains(endpoints.0) || deprecated_ids.contains(endpoints.1))
+        {
+            result
+                .stripped_deprecated_relationships
+                .push(relationship.id.clone());
+            continue;
         }
 
-        // Check for invalid relationship types
-        if !is_valid_relationship_type(&relationship.relationship_type) {
-            issues.push(ValidationIssue {
-                severity: IssueSeverity::Warning,
-                issue_type: IssueType::InvalidRelationshipType,
-                message: format!(
-                    "Relationship '{}' has unknown type '{:?}'",
-                    relationship.id, relationship.relationship_type
-                ),
-                affected_ids: vec![relationship.id.clone()],
-                suggestion: Some(
-                    "Use a standard relationship type: depends_on, communicates_with, authenticates_via, reads_from, writes_to, publishes, subscribes".to_string()
-                ),
-            });
+        let missing: Vec<&str> = [endpoints.0, endpoints.1]
+            .into_iter()
+            .filter(|id| !service_ids.contains(id))
+            .collect();
+        if !missing.is_empty() {
+            if fixes.delete_orphaned_relationships {
+                result
+                    .deleted_orphaned_relationships
+                    .push(relationship.id.clone());
+                continue;
+            }
+            if fixes.create_placeholder_services {
+                for id in missing {
+                    if storage::validate_service_id(id).is_ok() && placeholder_ids.insert(id) {
+                        let service = placeholder_service(
+                            id,
+                            ServiceType::default(),
+                            &PlaceholderDefaults::default(),
+                        );
+                        result.placeholder_services.push(service);
+                    }
+                }
+            }
         }
+
+        kept_by_endpoints
+            .entry(endpoints)
+            .or_default()
+            .push(kept.len());
+        kept.push(relationship.clone());
     }
 
-    // Check for unreachable services (no relationships at all)
-    let connected_services: HashSet<String> = relationships
+    if fixes.prune_orphaned_annotations {
+        result.pruned_annotations = annotations
+            .keys()
+            .filter(|id| {
+                !service_ids.contains(id.as_str()) && !placeholder_ids.contains(id.as_str())
+            })
+            .cloned()
+            .collect();
+        annotations.retain(|id, _| !result.pruned_annotations.contains(id));
+    }
+
+    let mut fixed_services = services;
+    fixed_services.extend(result.placeholder_services.iter().cloned());
+    let mut issues = layout_issues;
+    issues.extend(check_data(
+        &fixed_services,
+        &kept,
+        &groups,
+        &annotations,
+        &config,
+        &state.data_path,
+        &Progress::none(),
+    )?);
+    check_import_freshness(&state.data_path, &environment, &kept, &config, &mut issues)?;
+    let after_result = ValidationResult::from_issues(issues, &config, &suppressions);
+    result.after = IssueCounts::from(&after_result);
+
+    let mut changes: Vec<Change> = result
+        .placeholder_services
         .iter()
-        .flat_map(|r| vec![r.source.clone(), r.target.clone()])
+        .map(|service| Change::Service {
+            id: service.id.clone(),
+            before: None,
+            after: Some(service.clone()),
+        })
         .collect();
+    if kept.len() != relationships.len() {
+        changes.push(Change::Relationships {
+            before: relationships,
+            after: kept,
+        });
+    }
+
+    if !dry_run && !changes.is_empty() {
+        let removed = result.deleted_orphaned_relationships.len()
+            + result.removed_duplicate_relationships.len()
+            + result.stripped_deprecated_relationships.len();
+        batch::apply_entry(
+            &app,
+            &mut state,
+            &environment,
+            "fix_validation_issues",
+            JournalEntry::new(
+                format!(
+                    "Fixed validation issues ({} relationships removed, {} placeholder services)",
+                    removed,
+                    result.placeholder_services.len()
+                ),
+                changes,
+            ),
+            &mut Progress::none(),
+        )?;
+    }
+
+    if !dry_run && !result.pruned_annotations.is_empty() {
+        storage::save_annotations(&state.data_path, &environment, &annotations)?;
+        git::auto_commit(
+            &app,
+            &state,
+            "fix_validation_issues",
+            format!(
+                "Prune {} orphaned graph annotations in {}",
+                result.pruned_annotations.len(),
+                environment
+            ),
+            vec![storage::annotations::annotations_path(
+                &state.data_path,
+                &environment,
+            )?],
+        );
+    }
+
+    Ok(result)
+}
+
+/// Returns true if two relationships are identical apart from their IDs and revisions.
+fn is_exact_duplicate(a: &Relationship, b: &Relationship) -> bool {
+    a.source == b.source
+        && a.target == b.target
+        && a.relationship_type == b.relationship_type
+        && a.description == b.description
+        && a.metadata == b.metadata
+}
+
+/// Returns the filename of a path without its extension.
+///
+/// Returns an empty string for paths without a valid UTF-8 file stem.
+fn file_stem(path: &Path) -> &str {
+    path.file_stem().and_then(|s| s.to_str()).unwrap_or("")
+}
+
+/// Loads services from a data directory while checking the on-disk layout.
+///
+/// Reports service files whose names don't match their contained ID and
+/// relationships files stored in the legacy plain-array format.
+///
+/// # Arguments
+///
+/// * `fs_backend` - The filesystem backend serving the data directory
+/// * `environment` - The environment being validated
+/// * `config` - The rule configuration; disabled rules are skipped
+/// * `issues` - Accumulator the layout issues are appended to
+///
+/// # Returns
+///
+/// * `Ok(Vec<Service>)` - All services in the environment
+/// * `Err(AppError)` - If the files cannot be read
+fn check_file_layout(
+    fs_backend: &FileSystemBackend,
+    environment: &str,
+    config: &ValidationConfig,
+    issues: &mut Vec<ValidationIssue>,
+) -> Result<Vec<Service>, AppError> {
+    let data_path = fs_backend.data_path();
+    let key = fs_backend.encryption_key();
+    let service_files = loader::load_service_files(data_path, environment, key)?;
 
-    for service in &services {
-        if !connected_services.contains(&service.id) {
+    // Check that each service file is named after the ID it contains
+    let check_filenames = config.is_enabled(IssueType::FilenameMismatch);
+    for (path, service) in &service_files {
+        let stem = file_stem(path);
+        if check_filenames && stem != service.id {
             issues.push(ValidationIssue {
-                severity: IssueSeverity::Info,
-                issue_type: IssueType::UnreachableService,
+                severity: IssueSeverity::Error,
+                issue_type: IssueType::FilenameMismatch,
                 message: format!(
-                    "Service '{}' has no relationships (isolated)",
+                    "Service file '{}' is named '{}' but contains ID '{}'",
+                    path.display(),
+                    stem,
                     service.id
                 ),
                 affected_ids: vec![service.id.clone()],
-                suggestion: Some("Add relationships or consider if this service is needed".to_string()),
+                suggestion: Some(format!(
+                    "Rename the file to '{}.json' or run repair_filenames",
+                    service.id
+                )),
+                fingerprint: String::new(),
             });
         }
     }
 
-    // Check for circular dependencies (simple cycle detection using DFS)
-    let cycles = detect_circular_dependencies(&relationships, &service_ids);
-    for cycle in cycles {
+    // Check for relationships files in the legacy plain-array format
+    if config.is_enabled(IssueType::LegacyFileFormat)
+        && loader::is_legacy_relationships_file(data_path, environment, key)?
+    {
         issues.push(ValidationIssue {
-            severity: IssueSeverity::Warning,
-            issue_type: IssueType::CircularDependency,
-            message: format!("Circular dependency detected: {}", cycle.join(" -> ")),
-            affected_ids: cycle.clone(),
-            suggestion: Some("Consider breaking the circular dependency".to_string()),
+            severity: IssueSeverity::Info,
+            issue_type: IssueType::LegacyFileFormat,
+            message: format!(
+                "relationships.json in '{}' uses the legacy plain-array format",
+                environment
+            ),
+            affected_ids: Vec::new(),
+            suggestion: Some(
+                "Save any relationship or run migrate_environment to rewrite it".to_string(),
+            ),
+            fingerprint: String::new(),
         });
     }
 
-    // Count issues by severity
-    let error_count = issues
-        .iter()
-        .filter(|i| i.severity == IssueSeverity::Error)
-        .count();
-    let warning_count = issues
-        .iter()
-        .filter(|i| i.severity == IssueSeverity::Warning)
-        .count();
-    let info_count = issues
-        .iter()
-        .filter(|i| i.severity == IssueSeverity::Info)
-        .count();
-
-    Ok(ValidationResult {
-        issues,
-        error_count,
-        warning_count,
-        info_count,
-    })
+    Ok(service_files.into_iter().map(|(_, s)| s).collect())
 }
 
 /// Checks if a service has all required fields populated.
@@ -311,7 +3307,7 @@ pub fn validate_environment(
 ///
 /// # Example
 ///
-/// ```rust
+/// ```rust,ignore
 /// let missing = check_required_fields(&service);
 /// if !missing.is_empty() {
 ///     println!("Missing fields: {}", missing.join(", "));
@@ -364,6 +3360,43 @@ fn is_valid_relationship_type(rel_type: &RelationshipType) -> bool {
     )
 }
 
+/// Returns true if a custom type is registered with exactly this spelling.
+fn is_registered(name: &str, registry: &[CustomTypeDefinition]) -> bool {
+    registry.iter().any(|definition| definition.name == name)
+}
+
+/// Builds the issue for a service or relationship with an unregistered custom type.
+///
+/// The suggestion names the registered type closest to it (ignoring case
+/// and punctuation), if one is similar enough.
+fn unregistered_type_issue(
+    owner: &str,
+    owner_id: &str,
+    name: &str,
+    registry: &[CustomTypeDefinition],
+) -> ValidationIssue {
+    let closest = similarity::closest_name(name, registry.iter().map(|d| d.name.as_str()));
+    let suggestion = match closest {
+        Some(registered) => format!(
+            "Use the registered type '{}' (normalize_custom_types can rewrite it)",
+            registered
+        ),
+        None => format!("Register '{}' or use a registered type", name),
+    };
+
+    ValidationIssue {
+        severity: IssueSeverity::Warning,
+        issue_type: IssueType::UnregisteredCustomType,
+        message: format!(
+            "{} '{}' has unregistered custom type '{}'",
+            owner, owner_id, name
+        ),
+        affected_ids: vec![owner_id.to_string()],
+        suggestion: Some(suggestion),
+        fingerprint: String::new(),
+    }
+}
+
 /// Detects circular dependencies in the service dependency graph.
 ///
 /// Uses depth-first search (DFS) to find cycles in "depends_on" relationships.
@@ -423,7 +3456,10 @@ fn detect_circular_dependencies(
     let mut unique_cycles: Vec<Vec<String>> = Vec::new();
     for cycle in cycles {
         let normalized = normalize_cycle(&cycle);
-        if !unique_cycles.iter().any(|c| normalize_cycle(c) == normalized) {
+        if !unique_cycles
+            .iter()
+            .any(|c| normalize_cycle(c) == normalized)
+        {
             unique_cycles.push(cycle);
         }
     }
@@ -493,7 +3529,7 @@ fn find_cycles(
 ///
 /// # Example
 ///
-/// ```rust
+/// ```rust,ignore
 /// let cycle = vec!["B", "C", "A", "B"];
 /// let normalized = normalize_cycle(&cycle);
 /// assert_eq!(normalized, vec!["A", "B", "C"]);
@@ -507,9 +3543,211 @@ fn normalize_cycle(cycle: &[String]) -> Vec<String> {
     let mut nodes: Vec<String> = cycle.iter().take(cycle.len() - 1).cloned().collect();
 
     // Find minimum element and rotate to start from it
-    if let Some(min_pos) = nodes.iter().enumerate().min_by_key(|(_, s)| *s).map(|(i, _)| i) {
+    if let Some(min_pos) = nodes
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, s)| *s)
+        .map(|(i, _)| i)
+    {
         nodes.rotate_left(min_pos);
     }
 
     nodes
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{relationship, service, snapshot_dir, without_dry_run, TestApp};
+    use std::fs;
+
+    fn issue_types(app: &TestApp) -> Vec<IssueType> {
+        run_validation(&app.state(), "dev")
+            .unwrap()
+            .issues
+            .iter()
+            .map(|issue| issue.issue_type)
+            .collect()
+    }
+
+    #[test]
+    fn legacy_relationships_files_are_reported() {
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[relationship("rel-1", "api", "db")],
+        );
+        assert!(!issue_types(&app).contains(&IssueType::LegacyFileFormat));
+
+        let path = loader::relationships_file_path(app.data_path(), "dev").unwrap();
+        fs::write(
+            &path,
+            r#"[{ "id": "rel-1", "source": "api", "target": "db", "relationshipType": "depends_on" }]"#,
+        )
+        .unwrap();
+        write_state(&app.state()).clear_environment_cache("dev");
+
+        assert!(issue_types(&app).contains(&IssueType::LegacyFileFormat));
+    }
+
+    fn rename_service_file(app: &TestApp, from: &str, to: &str) {
+        let services_dir = app.data_path().join("dev").join("services");
+        fs::rename(
+            services_dir.join(format!("{}.json", from)),
+            services_dir.join(format!("{}.json", to)),
+        )
+        .unwrap();
+        write_state(&app.state()).clear_environment_cache("dev");
+    }
+
+    #[test]
+    fn filename_mismatches_are_errors_naming_the_file_and_the_id() {
+        let app = TestApp::with_environment("dev", &[service("user-api")], &[]);
+        assert!(!issue_types(&app).contains(&IssueType::FilenameMismatch));
+
+        rename_service_file(&app, "user-api", "users-api");
+
+        let issues = run_validation(&app.state(), "dev").unwrap().issues;
+        let mismatch = issues
+            .iter()
+            .find(|issue| issue.issue_type == IssueType::FilenameMismatch)
+            .unwrap();
+        assert_eq!(mismatch.severity, IssueSeverity::Error);
+        assert_eq!(mismatch.affected_ids, vec!["user-api"]);
+        assert!(
+            mismatch.message.contains("users-api.json"),
+            "{}",
+            mismatch.message
+        );
+        assert!(mismatch.message.contains("'users-api'"));
+        assert!(mismatch.message.contains("'user-api'"));
+    }
+
+    #[test]
+    fn repairing_filenames_renames_free_targets_and_reports_collisions() {
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("user-api"), service("billing"), service("orders")],
+            &[],
+        );
+        rename_service_file(&app, "user-api", "users-api");
+        // A hand-copied file whose ID is already taken by `orders.json`
+        let services_dir = app.data_path().join("dev").join("services");
+        fs::copy(
+            services_dir.join("orders.json"),
+            services_dir.join("orders-copy.json"),
+        )
+        .unwrap();
+        let files = snapshot_dir(app.data_path());
+
+        let preview = repair_filenames(app.handle(), app.state(), "dev".into(), true).unwrap();
+        assert_eq!(snapshot_dir(app.data_path()), files);
+
+        let result = repair_filenames(app.handle(), app.state(), "dev".into(), false).unwrap();
+        assert_eq!(without_dry_run(&preview), without_dry_run(&result));
+        let renamed: Vec<&str> = result
+            .renamed
+            .iter()
+            .map(|r| r.service_id.as_str())
+            .collect();
+        assert_eq!(renamed, vec!["user-api"]);
+        assert!(services_dir.join("user-api.json").exists());
+        assert!(!services_dir.join("users-api.json").exists());
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].service_id, "orders");
+        assert!(services_dir.join("orders-copy.json").exists());
+
+        let types = issue_types(&app);
+        assert_eq!(
+            types
+                .iter()
+                .filter(|t| **t == IssueType::FilenameMismatch)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn port_conflicts_warn_naming_both_services_and_bad_ports_are_infos() {
+        let with_metadata = |id: &str, metadata: serde_json::Value| Service {
+            metadata: serde_json::from_value(metadata).unwrap(),
+            ..service(id)
+        };
+        let app = TestApp::with_environment(
+            "dev",
+            &[
+                with_metadata(
+                    "api",
+                    serde_json::json!({ "ports": [80, 443], "host": "n1" }),
+                ),
+                with_metadata("web", serde_json::json!({ "port": 443, "host": "n1" })),
+                with_metadata("jobs", serde_json::json!({ "port": "https" })),
+            ],
+            &[],
+        );
+
+        let issues = run_validation(&app.state(), "dev").unwrap().issues;
+
+        let ports: Vec<(IssueSeverity, Vec<&str>)> = issues
+            .iter()
+            .filter(|issue| {
+                matches!(
+                    issue.issue_type,
+                    IssueType::PortConflict | IssueType::InvalidPort
+                )
+            })
+            .map(|issue| {
+                let mut ids: Vec<&str> = issue.affected_ids.iter().map(String::as_str).collect();
+                ids.sort();
+                (issue.severity, ids)
+            })
+            .collect();
+        assert_eq!(
+            ports,
+            vec![
+                (IssueSeverity::Warning, vec!["api", "web"]),
+                (IssueSeverity::Info, vec!["jobs"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_dry_run_fix_writes_nothing_and_matches_the_real_run() {
+        let app = TestApp::with_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[
+                relationship("api-db", "api", "db"),
+                relationship("api-db-again", "api", "db"),
+                relationship("api-gone", "api", "gone"),
+            ],
+        );
+        let fixes = ValidationFixes {
+            delete_orphaned_relationships: true,
+            remove_duplicate_relationships: true,
+            ..Default::default()
+        };
+        let fix = |dry_run| {
+            fix_validation_issues(
+                app.handle(),
+                app.state(),
+                "dev".to_string(),
+                fixes.clone(),
+                Some(dry_run),
+            )
+            .unwrap()
+        };
+        let files = snapshot_dir(app.data_path());
+
+        let preview = fix(true);
+
+        assert!(preview.dry_run);
+        assert_eq!(snapshot_dir(app.data_path()), files);
+        assert_eq!(preview.deleted_orphaned_relationships, ["api-gone"]);
+        assert_eq!(preview.removed_duplicate_relationships, ["api-db-again"]);
+
+        let applied = fix(false);
+        assert_eq!(without_dry_run(&preview), without_dry_run(&applied));
+        assert_ne!(snapshot_dir(app.data_path()), files);
+    }
+}
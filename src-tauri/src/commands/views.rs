@@ -0,0 +1,336 @@
+//! Saved graph view commands for the Tauri application.
+//!
+//! A saved view (see `models::SavedView`) stores the parameters of a filtered
+//! graph so it can be rebuilt with `apply_view` instead of being set up by
+//! hand every time. Views keep working when services they mention are
+//! deleted; `apply_view` reports what it couldn't find.
+
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+use tauri::State;
+
+use crate::commands::graph::{self, GraphData, GraphQuery};
+use crate::dates;
+use crate::error::AppError;
+use crate::models::{SavedView, ServiceStatus, ViewSelector};
+use crate::state::{cache, read_state, write_state, AppState};
+use crate::storage;
+
+/// A saved view with its graph rebuilt from the current data.
+///
+/// # Fields
+///
+/// * `view` - The stored view
+/// * `graph` - The resolved graph, or `None` if none of its center services exist
+/// * `missing_service_ids` - Services the view mentions (as center, group member
+///   or positioned node) that no longer exist, sorted
+/// * `missing_group_id` - The selector's group, if it no longer exists
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedView {
+    pub view: SavedView,
+    pub graph: Option<GraphData>,
+    pub missing_service_ids: Vec<String>,
+    pub missing_group_id: Option<String>,
+}
+
+/// Saves a graph view, replacing the view with the same name.
+///
+/// The view isn't checked against the environment's services, so it can
+/// be saved before or after they change.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `view` - The view to save
+///
+/// # Returns
+///
+/// * `Ok(SavedView)` - The saved view
+/// * `Err(AppError::ValidationError)` - If the name or environment name can't be used
+///   as a path
+/// * `Err(AppError::Io)` - If the view file cannot be written
+///
+/// # Side Effects
+///
+/// - Writes `.views/{name}.json` in the data directory (or
+///   `{database}.views/{name}.json` next to a SQLite database)
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('save_view', {
+///     view: {
+///         name: 'payments-morning',
+///         environment: 'prod',
+///         selector: { group: 'payments' },
+///         depth: 2,
+///         relationshipTypes: ['depends_on', 'publishes_to', 'subscribes_to'],
+///         direction: 'both',
+///         nodePositions: cy.nodes().reduce((positions, node) => ({
+///             ...positions,
+///             [node.id()]: node.position()
+///         }), {})
+///     }
+/// });
+/// ```
+#[tauri::command]
+pub fn save_view(
+    state: State<'_, RwLock<AppState>>,
+    view: SavedView,
+) -> Result<SavedView, AppError> {
+    storage::validate_environment_name(&view.environment)?;
+
+    let state = write_state(&state);
+    storage::save_view(&state.data_path, &view)?;
+
+    Ok(view)
+}
+
+/// Lists the saved views.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `environment` - Only list the views of this environment; all views if absent
+///
+/// # Returns
+///
+/// * `Ok(Vec<SavedView>)` - The views, sorted by name
+/// * `Err(AppError::Io)` - If the views directory cannot be read
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const views = await invoke('list_views', { environment: 'prod' });
+/// ```
+#[tauri::command]
+pub fn list_views(
+    state: State<'_, RwLock<AppState>>,
+    environment: Option<String>,
+) -> Result<Vec<SavedView>, AppError> {
+    let state = read_state(&state);
+    let mut views = storage::load_views(&state.data_path)?;
+    if let Some(environment) = environment {
+        views.retain(|view| view.environment == environment);
+    }
+
+    Ok(views)
+}
+
+/// Retrieves a saved view.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `view_name` - The name of the view
+///
+/// # Returns
+///
+/// * `Ok(SavedView)` - The stored view
+/// * `Err(AppError::ViewNotFound)` - If no view has the name
+/// * `Err(AppError::FileLoad)` - If the view file cannot be parsed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const view = await invoke('get_view', { viewName: 'payments-morning' });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_view(
+    state: State<'_, RwLock<AppState>>,
+    view_name: String,
+) -> Result<SavedView, AppError> {
+    let state = read_state(&state);
+    storage::load_view(&state.data_path, &view_name)?.ok_or(AppError::ViewNotFound(view_name))
+}
+
+/// Deletes a saved view.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the data path
+/// * `view_name` - The name of the view
+///
+/// # Returns
+///
+/// * `Ok(())` - If the view was deleted
+/// * `Err(AppError::ViewNotFound)` - If no view has the name
+/// * `Err(AppError::Io)` - If the view file cannot be deleted
+///
+/// # Side Effects
+///
+/// - Deletes `.views/{name}.json` in the data directory
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('delete_view', { viewName: 'payments-morning' });
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+pub fn delete_view(state: State<'_, RwLock<AppState>>, view_name: String) -> Result<(), AppError> {
+    let state = write_state(&state);
+    if storage::delete_view(&state.data_path, &view_name)? {
+        Ok(())
+    } else {
+        Err(AppError::ViewNotFound(view_name))
+    }
+}
+
+/// Rebuilds the graph of a saved view from the current data.
+///
+/// The graph starts from the view's center service and the services its
+/// selector matches (the members of a group, or the services of a team or
+/// with a tag), and follows the stored depth, relationship types and
+/// direction like `get_service_graph`, over the relationships in effect
+/// today. Center services and group members
+/// that no longer exist are left out and reported, as are positioned nodes
+/// that no longer exist; the view itself still loads.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the cache and data path
+/// * `view_name` - The name of the view
+/// * `max_nodes` / `max_edges` - Optional size limits of the graph, as for
+///   `get_service_graph`
+/// * `exclude_statuses` / `exclude_tags` / `exclude_service_ids` - Optional services to
+///   leave out and not traverse through, as for `get_service_graph`; the view's
+///   center service and selected services are kept
+/// * `aggregate_edges` - If true, also return the aggregated edges, as for
+///   `get_service_graph` (default: false)
+///
+/// # Returns
+///
+/// * `Ok(AppliedView)` - The view, its graph and what it refers to that is missing
+/// * `Err(AppError::ViewNotFound)` - If no view has the name
+/// * `Err(AppError::FileLoad)` - If the view or groups file cannot be parsed
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const applied = await invoke('apply_view', { viewName: 'payments-morning' });
+/// if (applied.missingServiceIds.length > 0) {
+///     console.warn('No longer exist:', applied.missingServiceIds);
+/// }
+/// if (applied.graph) {
+///     renderGraph(applied.graph, applied.view.nodePositions);
+/// }
+/// ```
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub fn apply_view(
+    state: State<'_, RwLock<AppState>>,
+    view_name: String,
+    max_nodes: Option<usize>,
+    max_edges: Option<usize>,
+    exclude_statuses: Option<Vec<ServiceStatus>>,
+    exclude_tags: Option<Vec<String>>,
+    exclude_service_ids: Option<Vec<String>>,
+    aggregate_edges: Option<bool>,
+) -> Result<AppliedView, AppError> {
+    let (view, groups) = {
+        let state = read_state(&state);
+        let view = storage::load_view(&state.data_path, &view_name)?
+            .ok_or(AppError::ViewNotFound(view_name))?;
+        let groups = match view.selector {
+            Some(ViewSelector::Group(_)) => {
+                storage::load_groups(&state.data_path, &view.environment)?
+            }
+            _ => Vec::new(),
+        };
+        (view, groups)
+    };
+
+    let services = cache::shared_services(&state, &view.environment)?;
+    let exists = |id: &str| services.iter().any(|s| s.id == id);
+
+    let mut center_ids: Vec<String> = Vec::new();
+    let mut missing_service_ids: BTreeSet<String> = BTreeSet::new();
+    let mut missing_group_id = None;
+    let mut add_center = |id: &str| {
+        if !exists(id) {
+            missing_service_ids.insert(id.to_string());
+        } else if !center_ids.iter().any(|c| c == id) {
+            center_ids.push(id.to_string());
+        }
+    };
+
+    if let Some(center_service_id) = &view.center_service_id {
+        add_center(center_service_id);
+    }
+    match &view.selector {
+        Some(ViewSelector::Group(group_id)) => {
+            match groups.iter().find(|group| group.id == *group_id) {
+                Some(group) => group.member_ids.iter().for_each(|id| add_center(id)),
+                None => missing_group_id = Some(group_id.clone()),
+            }
+        }
+        Some(ViewSelector::Team(team)) => {
+            let mut ids: Vec<&str> = services
+                .iter()
+                .filter(|s| s.team.as_deref() == Some(team.as_str()))
+                .map(|s| s.id.as_str())
+                .collect();
+            ids.sort_unstable();
+            ids.into_iter().for_each(&mut add_center);
+        }
+        Some(ViewSelector::Tag(tag)) => {
+            let mut ids: Vec<&str> = services
+                .iter()
+                .filter(|s| s.tags.contains(tag))
+                .map(|s| s.id.as_str())
+                .collect();
+            ids.sort_unstable();
+            ids.into_iter().for_each(&mut add_center);
+        }
+        None => {}
+    }
+
+    if let Some(positions) = &view.node_positions {
+        for id in positions.keys() {
+            if !exists(id) {
+                missing_service_ids.insert(id.clone());
+            }
+        }
+    }
+
+    let graph = if center_ids.is_empty() {
+        None
+    } else {
+        let query = GraphQuery {
+            depth: view.depth.unwrap_or(1),
+            relationship_types: view.relationship_types.clone(),
+            direction: view.direction,
+            include_effective_status: false,
+            include_criticality: false,
+            include_groups: false,
+            include_annotations: false,
+            max_nodes,
+            max_edges,
+            exclude_statuses: exclude_statuses.unwrap_or_default(),
+            exclude_tags: exclude_tags.unwrap_or_default(),
+            exclude_service_ids: exclude_service_ids.unwrap_or_default(),
+            aggregate_edges: aggregate_edges.unwrap_or(false),
+            as_of: Some(dates::today()),
+        };
+        Some(graph::build_service_graph(
+            &state,
+            &view.environment,
+            &center_ids,
+            &query,
+        )?)
+    };
+
+    Ok(AppliedView {
+        view,
+        graph,
+        missing_service_ids: missing_service_ids.into_iter().collect(),
+        missing_group_id,
+    })
+}
@@ -0,0 +1,326 @@
+//! Workspace commands for the Tauri application.
+//!
+//! A workspace is a named data path (see `models::Workspace`), e.g. one per
+//! client, persisted in the settings. Switching workspaces keeps the caches
+//! of the workspace switched away from (see `AppState::switch_workspace`),
+//! so switching back doesn't reload anything. `set_data_path` still works
+//! and changes the path of the active workspace.
+
+use serde::Serialize;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::commands::environments::{check_data_path, watch_data_path};
+use crate::commands::settings::store_settings;
+use crate::error::AppError;
+use crate::models::Workspace;
+use crate::state::{read_state, write_state, AppState};
+use crate::webhooks::WebhookSender;
+
+/// A workspace, as listed in the workspace switcher.
+///
+/// # Fields
+///
+/// * `name` - The workspace name
+/// * `path` - The data directory or SQLite database file of the workspace
+/// * `active` - Whether this is the workspace currently open
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub path: String,
+    pub active: bool,
+}
+
+/// Lists the configured workspaces.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+///
+/// # Returns
+///
+/// * `Ok(Vec<WorkspaceInfo>)` - The workspaces, in the order they were added
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// const workspaces = await invoke('list_workspaces');
+/// const active = workspaces.find(w => w.active);
+/// ```
+#[tauri::command]
+pub fn list_workspaces(state: State<'_, RwLock<AppState>>) -> Result<Vec<WorkspaceInfo>, AppError> {
+    let state = read_state(&state);
+    Ok(state
+        .settings
+        .workspaces
+        .iter()
+        .map(|workspace| WorkspaceInfo {
+            name: workspace.name.clone(),
+            path: workspace.path.clone(),
+            active: state.workspace.as_deref() == Some(workspace.name.as_str()),
+        })
+        .collect())
+}
+
+/// Adds a workspace without switching to it.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+/// * `name` - The workspace name; must not be empty or already taken
+/// * `path` - The data directory or SQLite database file (see `set_data_path`)
+///
+/// # Returns
+///
+/// * `Ok(())` - If the workspace was added
+/// * `Err(AppError::ValidationError)` - If the name is empty or already taken
+/// * `Err(AppError::InvalidPath)` - If the path can't be used as a data path
+///
+/// # Side Effects
+///
+/// - Persists the settings
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('add_workspace', {
+///     name: 'Acme',
+///     path: '/Users/user/clients/acme/service-data'
+/// });
+/// ```
+#[tauri::command]
+pub fn add_workspace(
+    state: State<'_, RwLock<AppState>>,
+    name: String,
+    path: String,
+) -> Result<(), AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::ValidationError(
+            "Workspace name cannot be empty".to_string(),
+        ));
+    }
+    check_data_path(&path)?;
+
+    let mut state = write_state(&state);
+    if state.settings.workspace(&name).is_some() {
+        return Err(AppError::ValidationError(format!(
+            "Workspace already exists: {}",
+            name
+        )));
+    }
+
+    let mut settings = state.settings.clone();
+    settings.workspaces.push(Workspace { name, path });
+    store_settings(&mut state, settings)
+}
+
+/// Removes a workspace.
+///
+/// Only the workspace entry is removed; its data is left untouched. The
+/// active workspace can't be removed; switch to another one first.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing the settings
+/// * `name` - The workspace to remove
+///
+/// # Returns
+///
+/// * `Ok(())` - If the workspace was removed
+/// * `Err(AppError::WorkspaceNotFound)` - If there is no workspace with that name
+/// * `Err(AppError::ValidationError)` - If it is the active workspace
+///
+/// # Side Effects
+///
+/// - Persists the settings
+/// - Drops the caches kept for the workspace
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('remove_workspace', { name: 'Acme' });
+/// ```
+#[tauri::command]
+pub fn remove_workspace(state: State<'_, RwLock<AppState>>, name: String) -> Result<(), AppError> {
+    let mut state = write_state(&state);
+    if state.settings.workspace(&name).is_none() {
+        return Err(AppError::WorkspaceNotFound(name));
+    }
+    if state.workspace.as_deref() == Some(name.as_str()) {
+        return Err(AppError::ValidationError(format!(
+            "Cannot remove the active workspace: {}",
+            name
+        )));
+    }
+
+    let mut settings = state.settings.clone();
+    settings.workspaces.retain(|w| w.name != name);
+    store_settings(&mut state, settings)?;
+    state.parked_workspaces.remove(&name);
+
+    Ok(())
+}
+
+/// Switches to another workspace.
+///
+/// The caches of the workspace switched away from are kept, so switching
+/// back is instant. The workspace is opened again at the next startup.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to re-arm the data directory watcher
+/// * `state` - The application state to update
+/// * `name` - The workspace to switch to
+///
+/// # Returns
+///
+/// * `Ok(())` - If the workspace is now active
+/// * `Err(AppError::WorkspaceNotFound)` - If there is no workspace with that name
+/// * `Err(AppError::InvalidPath)` - If the workspace's path can no longer be used
+///
+/// # Side Effects
+///
+/// - Replaces the data path, storage backend, caches, undo journals and
+///   current environment with those of the workspace
+/// - Moves the data directory watcher to the workspace's path
+/// - Persists the workspace as the active one in the settings
+/// - Records the workspace's validation error counts for webhooks
+///
+/// # Examples
+///
+/// ```typescript
+/// // From the frontend:
+/// await invoke('switch_workspace', { name: 'Acme' });
+/// const environments = await invoke('list_environments');
+/// ```
+#[tauri::command]
+pub fn switch_workspace<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, RwLock<AppState>>,
+    name: String,
+) -> Result<(), AppError> {
+    let mut state = write_state(&state);
+    let workspace = state
+        .settings
+        .workspace(&name)
+        .cloned()
+        .ok_or_else(|| AppError::WorkspaceNotFound(name.clone()))?;
+    let data_path = check_data_path(&workspace.path)?;
+
+    let mut settings = state.settings.clone();
+    settings.active_workspace = Some(name.clone());
+    store_settings(&mut state, settings)?;
+
+    state.switch_workspace(&name, data_path);
+    watch_data_path(&app, &state.data_path);
+    drop(state);
+    if let Some(webhook_sender) = app.try_state::<WebhookSender>() {
+        webhook_sender.record_baselines();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::relationships::get_all_relationships;
+    use crate::commands::services::{get_all_services, get_service_by_id, quick_open};
+    use crate::commands::settings::{get_pinned_services, get_recent_services, pin_service};
+    use crate::test_support::{block_on, relationship, service, TestApp};
+    use tempfile::TempDir;
+
+    fn switch(app: &TestApp, name: &str) {
+        switch_workspace(app.handle(), app.state(), name.to_string()).unwrap();
+    }
+
+    fn service_ids(app: &TestApp) -> Vec<String> {
+        let mut ids: Vec<String> = block_on(get_all_services(app.handle(), "dev".into()))
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    fn pinned(app: &TestApp) -> Vec<String> {
+        let pinned = get_pinned_services(app.state(), "dev".into()).unwrap();
+        pinned.into_iter().map(|s| s.id).collect()
+    }
+
+    fn recent(app: &TestApp) -> Vec<String> {
+        let recent = get_recent_services(app.state(), "dev".into(), None).unwrap();
+        recent.into_iter().map(|r| r.service_id).collect()
+    }
+
+    #[test]
+    fn same_named_environments_of_two_workspaces_share_nothing() {
+        let app = TestApp::new();
+        let dirs = [TempDir::new().unwrap(), TempDir::new().unwrap()];
+        for (name, dir) in ["a", "b"].iter().zip(&dirs) {
+            let path = dir.path().display().to_string();
+            add_workspace(app.state(), name.to_string(), path).unwrap();
+        }
+
+        // In a, dev's services are cached, one viewed and one pinned
+        switch(&app, "a");
+        app.create_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[relationship("api-db", "api", "db")],
+        );
+        assert_eq!(service_ids(&app), ["api", "db"]);
+        get_service_by_id(app.state(), "dev".into(), "api".into()).unwrap();
+        pin_service(app.state(), "dev".into(), "db".into()).unwrap();
+
+        // b's dev starts with nothing of a's
+        switch(&app, "b");
+        {
+            let state = app.state();
+            let state = read_state(&state);
+            assert!(state.services_cache.is_empty());
+            assert!(state.relationships_cache.is_empty());
+            assert!(state.quick_open_indexes.is_empty());
+        }
+        app.create_environment("dev", &[service("web"), service("api")], &[]);
+        assert_eq!(service_ids(&app), ["api", "web"]);
+        assert!(block_on(get_all_relationships(
+            app.handle(),
+            "dev".into(),
+            None,
+            None,
+            None,
+            None
+        ))
+        .unwrap()
+        .is_empty());
+        assert!(quick_open(app.state(), "dev".into(), "db".into(), None)
+            .unwrap()
+            .is_empty());
+        assert!(pinned(&app).is_empty());
+        assert!(recent(&app).is_empty());
+
+        // b's own pins and views stay in b
+        get_service_by_id(app.state(), "dev".into(), "web".into()).unwrap();
+        pin_service(app.state(), "dev".into(), "api".into()).unwrap();
+        assert_eq!(pinned(&app), ["api"]);
+        assert_eq!(recent(&app), ["web"]);
+
+        // Back in a, its cache is still there and its pins and views are its own
+        switch(&app, "a");
+        assert!(read_state(&app.state()).services_cache.contains_key("dev"));
+        assert_eq!(service_ids(&app), ["api", "db"]);
+        assert_eq!(pinned(&app), ["db"]);
+        assert_eq!(recent(&app), ["api"]);
+
+        let state = app.state();
+        let state = read_state(&state);
+        assert_eq!(state.settings.pinned_services.len(), 2);
+    }
+}
@@ -0,0 +1,261 @@
+//! Service import from a Consul service registry.
+//!
+//! Reads the catalog (`/v1/catalog/services`) and each service's health
+//! (`/v1/health/service/{name}`) over Consul's HTTP API and maps every
+//! registered service to a `Service`:
+//!
+//! - The ID is the Consul name as a slug (see `storage::slugify`), e.g.
+//!   `payments_api` becomes `payments-api`
+//! - The status is the worst check of all instances: `passing` is healthy,
+//!   `warning` degraded and `critical` unhealthy; no checks or no instances
+//!   map to unknown
+//! - The tags are the Consul tags
+//! - The `consul` metadata object holds the Consul name, the datacenters and
+//!   nodes of the instances and the instance count
+//!
+//! The ACL token is only sent as the `X-Consul-Token` header; it is never
+//! part of the mapped services, so it never ends up in the data files.
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+
+use crate::error::AppError;
+use crate::models::{Service, ServiceStatus, ServiceType};
+use crate::storage;
+
+/// Metadata key holding the Consul registration a service was mapped from.
+pub const CONSUL_METADATA_KEY: &str = "consul";
+
+/// How long each request to Consul may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One instance of a service, as returned by `/v1/health/service/{name}`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HealthEntry {
+    node: HealthNode,
+    #[serde(default)]
+    checks: Vec<HealthCheck>,
+}
+
+/// The node an instance runs on.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HealthNode {
+    node: String,
+    #[serde(default)]
+    datacenter: String,
+}
+
+/// A health check of an instance.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HealthCheck {
+    status: String,
+}
+
+/// A service registered in Consul, mapped to a `Service`.
+///
+/// # Fields
+///
+/// * `consul_name` - The name the service is registered under
+/// * `service` - The mapped service
+#[derive(Debug, Clone)]
+pub struct RegisteredService {
+    pub consul_name: String,
+    pub service: Service,
+}
+
+/// Reads every service of the catalog with its health.
+///
+/// Blocks until all requests are done; call it off the async runtime.
+///
+/// # Arguments
+///
+/// * `base_url` - The Consul HTTP address, e.g. `http://consul.internal:8500`
+/// * `token` - The ACL token, if the registry requires one
+///
+/// # Returns
+///
+/// * `Ok(Vec<RegisteredService>)` - The mapped services, sorted by Consul name
+/// * `Err(AppError::RegistryAuthFailed)` - If the token is missing or invalid
+/// * `Err(AppError::RegistryTimeout)` - If Consul didn't answer in time
+/// * `Err(AppError::RegistryRequestFailed)` - If Consul couldn't be reached or
+///   answered with an error or an unexpected body
+pub fn fetch_services(
+    base_url: &str,
+    token: Option<&str>,
+) -> Result<Vec<RegisteredService>, AppError> {
+    let base_url = base_url.trim_end_matches('/');
+    let catalog: BTreeMap<String, Vec<String>> =
+        get_json(&format!("{}/v1/catalog/services", base_url), token)?;
+
+    let mut services = Vec::new();
+    for (name, tags) in catalog {
+        let url = format!(
+            "{}/v1/health/service/{}",
+            base_url,
+            encode_path_segment(&name)
+        );
+        let instances: Vec<HealthEntry> = get_json(&url, token)?;
+        services.push(RegisteredService {
+            service: map_service(&name, tags, &instances),
+            consul_name: name,
+        });
+    }
+
+    Ok(services)
+}
+
+/// Maps the services of a catalog export, without their health.
+///
+/// # Arguments
+///
+/// * `catalog` - Tags by Consul name, as `/v1/catalog/services` returns them
+///
+/// # Returns
+///
+/// The mapped services, sorted by Consul name, with an unknown status.
+pub fn services_from_catalog(catalog: BTreeMap<String, Vec<String>>) -> Vec<RegisteredService> {
+    catalog
+        .into_iter()
+        .map(|(name, tags)| RegisteredService {
+            service: map_service(&name, tags, &[]),
+            consul_name: name,
+        })
+        .collect()
+}
+
+/// Returns the service ID a Consul name is imported as.
+pub fn service_id(consul_name: &str) -> String {
+    storage::slugify(consul_name, storage::MAX_SLUG_LENGTH)
+}
+
+/// Maps a registered service and its instances.
+fn map_service(name: &str, mut tags: Vec<String>, instances: &[HealthEntry]) -> Service {
+    let status = instances
+        .iter()
+        .flat_map(|instance| &instance.checks)
+        .map(|check| check_status(&check.status))
+        .max_by_key(status_severity)
+        .unwrap_or(ServiceStatus::Unknown);
+
+    let datacenters: BTreeSet<&str> = instances
+        .iter()
+        .map(|instance| instance.node.datacenter.as_str())
+        .filter(|datacenter| !datacenter.is_empty())
+        .collect();
+    let nodes: BTreeSet<&str> = instances
+        .iter()
+        .map(|instance| instance.node.node.as_str())
+        .collect();
+    let metadata = serde_json::json!({
+        "service": name,
+        "datacenters": datacenters,
+        "nodes": nodes,
+        "instances": instances.len(),
+    });
+
+    tags.sort();
+    tags.dedup();
+
+    Service {
+        id: service_id(name),
+        name: name.to_string(),
+        service_type: ServiceType::default(),
+        status,
+        description: None,
+        version: None,
+        owner: None,
+        team: None,
+        tier: None,
+        lifecycle: None,
+        decommission_date: None,
+        successor_id: None,
+        tags,
+        metadata: HashMap::from([(CONSUL_METADATA_KEY.to_string(), metadata)]),
+        revision: 0,
+    }
+}
+
+/// Maps a Consul check status.
+fn check_status(status: &str) -> ServiceStatus {
+    match status {
+        "passing" => ServiceStatus::Healthy,
+        "warning" => ServiceStatus::Degraded,
+        "critical" => ServiceStatus::Unhealthy,
+        _ => ServiceStatus::Unknown,
+    }
+}
+
+/// Ranks statuses so the worst check of a service wins.
+fn status_severity(status: &ServiceStatus) -> u8 {
+    match status {
+        ServiceStatus::Unknown | ServiceStatus::Deprecated => 0,
+        ServiceStatus::Healthy => 1,
+        ServiceStatus::Degraded => 2,
+        ServiceStatus::Unhealthy => 3,
+    }
+}
+
+/// Sends a GET request to Consul and parses the JSON answer.
+fn get_json<T: serde::de::DeserializeOwned>(url: &str, token: Option<&str>) -> Result<T, AppError> {
+    let mut request = ureq::get(url).timeout(REQUEST_TIMEOUT);
+    if let Some(token) = token.filter(|t| !t.is_empty()) {
+        request = request.set("X-Consul-Token", token);
+    }
+
+    let unexpected = |reason: String| AppError::RegistryRequestFailed {
+        url: url.to_string(),
+        reason: format!("Unexpected response: {}", reason),
+    };
+    let body = request
+        .call()
+        .map_err(|e| request_error(url, e))?
+        .into_string()
+        .map_err(|e| unexpected(e.to_string()))?;
+    serde_json::from_str(&body).map_err(|e| unexpected(e.to_string()))
+}
+
+/// Converts a request error, telling rejected tokens and timeouts apart.
+fn request_error(url: &str, e: ureq::Error) -> AppError {
+    match e {
+        ureq::Error::Status(401 | 403, _) => AppError::RegistryAuthFailed(url.to_string()),
+        ureq::Error::Status(code, response) => AppError::RegistryRequestFailed {
+            url: url.to_string(),
+            reason: format!("HTTP {} {}", code, response.status_text()),
+        },
+        ureq::Error::Transport(transport) => {
+            let timed_out = std::error::Error::source(&transport)
+                .and_then(|source| source.downcast_ref::<std::io::Error>())
+                .is_some_and(|io| {
+                    matches!(
+                        io.kind(),
+                        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                    )
+                });
+            if timed_out {
+                AppError::RegistryTimeout(url.to_string())
+            } else {
+                AppError::RegistryRequestFailed {
+                    url: url.to_string(),
+                    reason: transport.to_string(),
+                }
+            }
+        }
+    }
+}
+
+/// Percent-encodes a value for use as one URL path segment.
+fn encode_path_segment(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
@@ -0,0 +1,205 @@
+//! Criticality ranking of services by PageRank.
+//!
+//! Counting dependents undervalues services everything funnels through
+//! transitively: a database behind three APIs that all the frontends use
+//! has only three direct dependents. PageRank lets every service pass its
+//! rank on to its dependencies, so rank accumulates at the services the most
+//! of the graph ultimately relies on.
+
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+
+use crate::error::AppError;
+use crate::models::{Relationship, RelationshipType, Service};
+
+/// Damping factor used when none is given.
+pub const DEFAULT_DAMPING: f64 = 0.85;
+
+/// Number of iterations used when none is given.
+pub const DEFAULT_ITERATIONS: u32 = 30;
+
+/// Largest number of iterations accepted.
+pub const MAX_ITERATIONS: u32 = 1000;
+
+/// Iteration stops early once the ranks change by less than this in total.
+const CONVERGENCE_TOLERANCE: f64 = 1e-10;
+
+/// Computes the PageRank of every service over its depends_on relationships.
+///
+/// Each service passes its rank on to its dependencies, split evenly.
+/// Services without dependencies (dangling nodes) spread theirs across all
+/// services, as in the standard algorithm, so rank is never lost and cycles
+/// converge like any other structure. Self-dependencies, relationships to
+/// missing services and duplicate relationships are ignored.
+///
+/// # Arguments
+///
+/// * `services` - All services in the environment
+/// * `relationships` - All relationships in the environment
+/// * `damping` - Probability of following a dependency rather than jumping to
+///   a random service; must be in (0, 1)
+/// * `iterations` - Maximum number of iterations; must be between 1 and `MAX_ITERATIONS`
+///
+/// # Returns
+///
+/// * `Ok(Vec<f64>)` - One score per service in the order of `services`, normalized
+///   so the most critical service scores 1.0
+/// * `Err(AppError::ValidationError)` - If `damping` or `iterations` is out of range
+pub fn criticality_scores<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    damping: f64,
+    iterations: u32,
+) -> Result<Vec<f64>, AppError> {
+    if !(damping > 0.0 && damping < 1.0) {
+        return Err(AppError::ValidationError(format!(
+            "Damping must be between 0 and 1 (exclusive), got {}",
+            damping
+        )));
+    }
+    if !(1..=MAX_ITERATIONS).contains(&iterations) {
+        return Err(AppError::ValidationError(format!(
+            "Iterations must be between 1 and {}, got {}",
+            MAX_ITERATIONS, iterations
+        )));
+    }
+
+    let count = services.len();
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let index: HashMap<&str, usize> = services
+        .iter()
+        .map(Borrow::<Service>::borrow)
+        .enumerate()
+        .map(|(i, s)| (s.id.as_str(), i))
+        .collect();
+
+    let mut edges: HashSet<(usize, usize)> = HashSet::new();
+    for relationship in relationships {
+        if relationship.relationship_type != RelationshipType::DependsOn {
+            continue;
+        }
+        if let (Some(&source), Some(&target)) = (
+            index.get(relationship.source.as_str()),
+            index.get(relationship.target.as_str()),
+        ) {
+            if source != target {
+                edges.insert((source, target));
+            }
+        }
+    }
+
+    // dependents[target] = services passing rank to target
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); count];
+    let mut dependency_counts = vec![0usize; count];
+    for &(source, target) in &edges {
+        dependents[target].push(source);
+        dependency_counts[source] += 1;
+    }
+
+    let n = count as f64;
+    let mut rank = vec![1.0 / n; count];
+    let mut next = vec![0.0; count];
+
+    for _ in 0..iterations {
+        let dangling: f64 = rank
+            .iter()
+            .zip(&dependency_counts)
+            .filter(|(_, &dependencies)| dependencies == 0)
+            .map(|(r, _)| r)
+            .sum();
+        let base = (1.0 - damping) / n + damping * dangling / n;
+
+        for (target, score) in next.iter_mut().enumerate() {
+            let incoming: f64 = dependents[target]
+                .iter()
+                .map(|&source| rank[source] / dependency_counts[source] as f64)
+                .sum();
+            *score = base + damping * incoming;
+        }
+
+        let change: f64 = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        std::mem::swap(&mut rank, &mut next);
+        if change < CONVERGENCE_TOLERANCE {
+            break;
+        }
+    }
+
+    let max = rank.iter().copied().fold(0.0, f64::max);
+    Ok(rank.into_iter().map(|r| r / max).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{relationship, service};
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn scores_match_a_hand_computed_ranking() {
+        // web -> api <- mobile, api -> db. With base rank B, the stationary
+        // ranks are web = mobile = B, api = B + 0.85 * 2B = 2.7B and
+        // db = B + 0.85 * 2.7B = 3.295B.
+        let services = [
+            service("web"),
+            service("mobile"),
+            service("api"),
+            service("db"),
+        ];
+        let relationships = [
+            relationship("web-api", "web", "api"),
+            relationship("mobile-api", "mobile", "api"),
+            relationship("api-db", "api", "db"),
+        ];
+
+        let scores = criticality_scores(&services, &relationships, 0.85, 200).unwrap();
+
+        assert_close(scores[3], 1.0);
+        assert_close(scores[2], 2.7 / 3.295);
+        assert_close(scores[0], 1.0 / 3.295);
+        assert_close(scores[1], 1.0 / 3.295);
+    }
+
+    #[test]
+    fn cycles_and_ignored_relationships_keep_the_ranks_finite() {
+        let services = [service("a"), service("b"), service("c")];
+        let relationships = [
+            relationship("a-b", "a", "b"),
+            relationship("b-a", "b", "a"),
+            relationship("c-a", "c", "a"),
+            relationship("a-a", "a", "a"),
+            relationship("c-missing", "c", "missing"),
+        ];
+
+        let scores = criticality_scores(&services, &relationships, 0.85, 1000).unwrap();
+
+        assert!(scores.iter().all(|s| s.is_finite() && *s > 0.0));
+        assert_close(scores[0], 1.0);
+        assert!(scores[1] > scores[2]);
+    }
+
+    #[test]
+    fn out_of_range_parameters_are_rejected() {
+        let services = [service("a")];
+        for (damping, iterations) in [
+            (0.0, 30),
+            (1.0, 30),
+            (f64::NAN, 30),
+            (0.85, 0),
+            (0.85, 1001),
+        ] {
+            assert!(matches!(
+                criticality_scores(&services, &[], damping, iterations),
+                Err(AppError::ValidationError(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Suggestions for relationships to remove to break dependency cycles.
+//!
+//! Finding the fewest relationships whose removal leaves the graph acyclic
+//! (a minimum feedback arc set) is NP-hard, so this is a greedy
+//! approximation: the shortest cycle through every relationship inside a
+//! cycle is collected, the relationship on the most of those cycles is
+//! suggested and removed, and the process repeats until no cycle is left.
+//! Between equally good candidates, communicates_with relationships are
+//! preferred over depends_on ones, as they are usually the looser coupling.
+
+use serde::Serialize;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use crate::layers::strongly_connected_components;
+use crate::models::{Relationship, RelationshipType, Service};
+
+/// A relationship whose removal breaks dependency cycles.
+///
+/// # Fields
+///
+/// * `source_id` / `target_id` - The services the relationship connects
+/// * `relationship_ids` - The relationships between them to remove (usually one)
+/// * `relationship_types` - Their types
+/// * `cycle` - The shortest cycle this breaks, as service IDs starting and ending
+///   at `source_id`
+/// * `cycles_broken` - How many of the remaining cycles this breaks
+/// * `justification` - Why this relationship was picked
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CycleBreakSuggestion {
+    pub source_id: String,
+    pub target_id: String,
+    pub relationship_ids: Vec<String>,
+    pub relationship_types: Vec<RelationshipType>,
+    pub cycle: Vec<String>,
+    pub cycles_broken: usize,
+    pub justification: String,
+}
+
+/// The suggested relationships to remove to make a graph acyclic.
+///
+/// # Fields
+///
+/// * `cyclic_groups` - The groups of services depending on each other in cycles
+///   (strongly connected components), sorted
+/// * `suggestions` - The relationships to remove, in the order they were picked;
+///   removing all of them breaks every cycle
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CycleBreakReport {
+    pub cyclic_groups: Vec<Vec<String>>,
+    pub suggestions: Vec<CycleBreakSuggestion>,
+}
+
+/// Returns true if relationships of this type take part in dependency cycles.
+fn is_dependency_type(relationship_type: &RelationshipType) -> bool {
+    matches!(
+        relationship_type,
+        RelationshipType::DependsOn | RelationshipType::CommunicatesWith
+    )
+}
+
+/// Suggests relationships to remove so no dependency cycle is left.
+///
+/// Cycles are formed by depends_on and communicates_with relationships. A
+/// relationship from a service to itself is always suggested. Relationships
+/// to missing services are ignored.
+///
+/// # Arguments
+///
+/// * `services` - All services in the environment
+/// * `relationships` - All relationships in the environment
+///
+/// # Returns
+///
+/// The cyclic groups and the suggestions (both empty without cycles)
+pub fn suggest_cycle_breaks<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+) -> CycleBreakReport {
+    let mut ids: Vec<&str> = services.iter().map(|s| s.borrow().id.as_str()).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let position = |id: &str| ids.binary_search(&id).ok();
+
+    // All relationships between the same two services count as one edge
+    let mut edges: BTreeMap<(usize, usize), Vec<&Relationship>> = BTreeMap::new();
+    for relationship in relationships {
+        if !is_dependency_type(&relationship.relationship_type) {
+            continue;
+        }
+        if let (Some(source), Some(target)) = (
+            position(&relationship.source),
+            position(&relationship.target),
+        ) {
+            edges
+                .entry((source, target))
+                .or_default()
+                .push(relationship);
+        }
+    }
+
+    let mut report = CycleBreakReport::default();
+    let suggest = |edge: (usize, usize),
+                   rels: &[&Relationship],
+                   cycle: &[usize],
+                   cycles_broken: usize,
+                   justification: String| {
+        let mut relationship_types: Vec<RelationshipType> = Vec::new();
+        for rel in rels {
+            if !relationship_types.contains(&rel.relationship_type) {
+                relationship_types.push(rel.relationship_type.clone());
+            }
+        }
+        CycleBreakSuggestion {
+            source_id: ids[edge.0].to_string(),
+            target_id: ids[edge.1].to_string(),
+            relationship_ids: rels.iter().map(|r| r.id.clone()).collect(),
+            relationship_types,
+            cycle: cycle.iter().map(|&i| ids[i].to_string()).collect(),
+            cycles_broken,
+            justification,
+        }
+    };
+
+    // Self-dependencies are cycles on their own
+    let self_loops: Vec<(usize, usize)> = edges.keys().copied().filter(|(s, t)| s == t).collect();
+    for edge in self_loops {
+        if let Some(rels) = edges.remove(&edge) {
+            report.cyclic_groups.push(vec![ids[edge.0].to_string()]);
+            report.suggestions.push(suggest(
+                edge,
+                &rels,
+                &[edge.0, edge.0],
+                1,
+                "A service depending on itself is always a cycle".to_string(),
+            ));
+        }
+    }
+
+    let mut first_round = true;
+    loop {
+        let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+        for &(source, target) in edges.keys() {
+            adjacency[source].push(target);
+        }
+
+        let components = strongly_connected_components(&adjacency);
+        let mut component_of = vec![0; ids.len()];
+        for (c, members) in components.iter().enumerate() {
+            for &member in members {
+                component_of[member] = c;
+            }
+        }
+        if first_round {
+            for members in components.iter().filter(|members| members.len() > 1) {
+                let mut group: Vec<String> = members.iter().map(|&m| ids[m].to_string()).collect();
+                group.sort();
+                report.cyclic_groups.push(group);
+            }
+            report.cyclic_groups.sort();
+            first_round = false;
+        }
+
+        // The shortest cycle through every edge within a component
+        let mut cycles: BTreeSet<Vec<usize>> = BTreeSet::new();
+        for &(source, target) in edges.keys() {
+            if component_of[source] != component_of[target] {
+                continue;
+            }
+            if let Some(path) = shortest_path(&adjacency, &component_of, target, source) {
+                let mut cycle = vec![source];
+                cycle.extend(path);
+                cycles.insert(normalize_cycle(cycle));
+            }
+        }
+        if cycles.is_empty() {
+            break;
+        }
+        let mut cycles: Vec<Vec<usize>> = cycles.into_iter().collect();
+        cycles.sort_by_key(Vec::len);
+
+        let mut broken: HashMap<(usize, usize), usize> = HashMap::new();
+        for cycle in &cycles {
+            for step in cycle.windows(2) {
+                *broken.entry((step[0], step[1])).or_default() += 1;
+            }
+        }
+
+        // Most cycles broken, then looser coupling, then the first by ID
+        let is_loose = |edge: &(usize, usize)| {
+            edges[edge]
+                .iter()
+                .all(|r| r.relationship_type == RelationshipType::CommunicatesWith)
+        };
+        let Some((&edge, &cycles_broken)) = broken.iter().min_by(|(a, a_count), (b, b_count)| {
+            b_count
+                .cmp(a_count)
+                .then_with(|| is_loose(b).cmp(&is_loose(a)))
+                .then_with(|| a.cmp(b))
+        }) else {
+            break;
+        };
+        let Some(cycle) = cycles
+            .iter()
+            .find(|cycle| cycle.windows(2).any(|step| (step[0], step[1]) == edge))
+        else {
+            break;
+        };
+
+        let mut justification = if cycles_broken == cycles.len() {
+            format!("Breaks all {} remaining cycles", cycles.len())
+        } else {
+            format!(
+                "Breaks {} of the {} remaining cycles, more than any other relationship",
+                cycles_broken,
+                cycles.len()
+            )
+        };
+        if is_loose(&edge) {
+            justification.push_str("; communicates_with is looser coupling than depends_on");
+        }
+
+        let cycle = rotate_to(cycle, edge.0);
+        let Some(rels) = edges.remove(&edge) else {
+            break;
+        };
+        report
+            .suggestions
+            .push(suggest(edge, &rels, &cycle, cycles_broken, justification));
+    }
+
+    report
+}
+
+/// Finds the shortest path between two nodes of the same component.
+///
+/// # Returns
+///
+/// The nodes from `from` to `to`, both included, or `None` if unreachable
+fn shortest_path(
+    adjacency: &[Vec<usize>],
+    component_of: &[usize],
+    from: usize,
+    to: usize,
+) -> Option<Vec<usize>> {
+    let component = component_of[from];
+    let mut previous: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    previous.insert(from, from);
+
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = vec![to];
+            let mut node = to;
+            while node != from {
+                node = previous[&node];
+                path.push(node);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &next in &adjacency[current] {
+            if component_of[next] == component && !previous.contains_key(&next) {
+                previous.insert(next, current);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Rotates a closed cycle to start (and end) at its smallest node.
+fn normalize_cycle(cycle: Vec<usize>) -> Vec<usize> {
+    let start = cycle.iter().copied().min().unwrap_or(0);
+    rotate_to(&cycle, start)
+}
+
+/// Rotates a closed cycle (first node repeated at the end) to start at a node.
+fn rotate_to(cycle: &[usize], start: usize) -> Vec<usize> {
+    let open = &cycle[..cycle.len().saturating_sub(1)];
+    let Some(offset) = open.iter().position(|&node| node == start) else {
+        return cycle.to_vec();
+    };
+
+    let mut rotated: Vec<usize> = open[offset..]
+        .iter()
+        .chain(&open[..offset])
+        .copied()
+        .collect();
+    rotated.push(start);
+    rotated
+}
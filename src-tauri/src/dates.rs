@@ -0,0 +1,88 @@
+//! Calendar dates.
+//!
+//! Dates in the data (decommission dates, effective dates of relationships)
+//! are `YYYY-MM-DD` strings in UTC, which sort like the dates they stand
+//! for, so they are compared as strings.
+
+use crate::error::AppError;
+use crate::storage::audit::{format_timestamp, now_millis};
+
+/// Returns true if a date is a real calendar date written as `YYYY-MM-DD`.
+pub fn is_valid_date(date: &str) -> bool {
+    day_number(date).is_some()
+}
+
+/// Returns today's UTC date as `YYYY-MM-DD`.
+pub fn today() -> String {
+    format_timestamp(now_millis())[..10].to_string()
+}
+
+/// Returns the number of days from the first date to the second, negative if
+/// the second is earlier, or `None` if either isn't a valid date.
+pub fn days_between(from: &str, to: &str) -> Option<i64> {
+    Some(day_number(to)? - day_number(from)?)
+}
+
+/// Resolves the `as_of` and `ignore_effective_dates` parameters of the
+/// commands that filter relationships by their effective dates.
+///
+/// # Returns
+///
+/// * `Ok(Some(date))` - The date relationships must be effective on: `as_of`, or today
+/// * `Ok(None)` - If `ignore_effective_dates` is set; every relationship counts
+/// * `Err(AppError::ValidationError)` - If `as_of` isn't a `YYYY-MM-DD` date
+pub fn resolve_as_of(
+    as_of: Option<String>,
+    ignore_effective_dates: Option<bool>,
+) -> Result<Option<String>, AppError> {
+    if ignore_effective_dates.unwrap_or(false) {
+        return Ok(None);
+    }
+
+    match as_of {
+        Some(date) if !is_valid_date(&date) => Err(AppError::ValidationError(format!(
+            "Date '{}' must be a date like 2025-09-30",
+            date
+        ))),
+        Some(date) => Ok(Some(date)),
+        None => Ok(Some(today())),
+    }
+}
+
+/// Returns the number of days since 1970-01-01 of a `YYYY-MM-DD` date, or
+/// `None` if it isn't a real calendar date.
+fn day_number(date: &str) -> Option<i64> {
+    let well_formed = date.len() == 10
+        && date.bytes().enumerate().all(|(i, byte)| match i {
+            4 | 7 => byte == b'-',
+            _ => byte.is_ascii_digit(),
+        });
+    if !well_formed {
+        return None;
+    }
+
+    let year: i64 = date[0..4].parse().ok()?;
+    let month: i64 = date[5..7].parse().ok()?;
+    let day: i64 = date[8..10].parse().ok()?;
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return None;
+    }
+
+    // Days from civil date, counting years from March so leap days come last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    Some(era * 146_097 + day_of_era - 719_468)
+}
@@ -0,0 +1,293 @@
+//! Generated relationship descriptions.
+//!
+//! With the `relationshipDescriptions.autoGenerate` setting on, a
+//! relationship saved without a description gets one rendered from the
+//! template of the setting, e.g. "Checkout depends on Payments". Generated
+//! descriptions are marked with the `generatedDescription` metadata flag, so
+//! `backfill_relationship_descriptions` can refresh them after a service is
+//! renamed or the template is changed without touching the descriptions
+//! people wrote. Editing a generated description makes it a written one,
+//! unless it is edited to what the template renders.
+//!
+//! Templates can hold these placeholders; any other text in braces is kept
+//! as written:
+//!
+//! * `{sourceName}`, `{targetName}` - The display names of the services
+//! * `{sourceId}`, `{targetId}` - The IDs of the services
+//! * `{relType}` - The relationship type as it is serialized (e.g., "reads_from")
+//! * `{relTypeVerb}` - The relationship type as a verb (e.g., "reads from", see
+//!   `RelationshipType::verb`)
+
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::models::{Relationship, RelationshipDescriptionSettings};
+
+/// The metadata key marking a description as generated.
+pub const GENERATED_FLAG: &str = "generatedDescription";
+
+/// Renders the description of a relationship from a template.
+///
+/// # Arguments
+///
+/// * `template` - The template, e.g. "{sourceName} {relTypeVerb} {targetName}"
+/// * `relationship` - The relationship described
+/// * `source_name` - The display name of its source
+/// * `target_name` - The display name of its target
+///
+/// # Returns
+///
+/// The description with surrounding whitespace trimmed; empty if the
+/// template renders to nothing.
+pub fn render(
+    template: &str,
+    relationship: &Relationship,
+    source_name: &str,
+    target_name: &str,
+) -> String {
+    let mut description = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        description.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        description.push_str(match &rest[1..end] {
+            "sourceName" => source_name,
+            "targetName" => target_name,
+            "sourceId" => relationship.source.as_str(),
+            "targetId" => relationship.target.as_str(),
+            "relType" => relationship.relationship_type.as_str(),
+            "relTypeVerb" => relationship.relationship_type.verb(),
+            _ => &rest[..=end],
+        });
+        rest = &rest[end + 1..];
+    }
+    description.push_str(rest);
+
+    description.trim().to_string()
+}
+
+/// Returns true if a relationship has a description that isn't just whitespace.
+pub fn has_description(relationship: &Relationship) -> bool {
+    relationship
+        .description
+        .as_deref()
+        .is_some_and(|description| !description.trim().is_empty())
+}
+
+/// Returns true if the description of a relationship was generated.
+pub fn is_generated(relationship: &Relationship) -> bool {
+    relationship
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get(GENERATED_FLAG))
+        == Some(&Value::Bool(true))
+}
+
+/// Sets a generated description and marks it as generated.
+pub fn set_generated(relationship: &mut Relationship, description: String) {
+    relationship.description = Some(description);
+    relationship
+        .metadata
+        .get_or_insert_with(Default::default)
+        .insert(GENERATED_FLAG.to_string(), Value::Bool(true));
+}
+
+/// Removes the generated mark, dropping metadata it leaves empty.
+pub fn clear_generated(relationship: &mut Relationship) {
+    if let Some(metadata) = relationship.metadata.as_mut() {
+        metadata.remove(GENERATED_FLAG);
+        if metadata.is_empty() {
+            relationship.metadata = None;
+        }
+    }
+}
+
+/// Applies the `relationshipDescriptions` settings to a relationship about
+/// to be saved.
+///
+/// A generated description that was cleared, or changed to text the
+/// template doesn't render (i.e. by hand), loses its generated mark. A
+/// relationship left without a description gets a generated one when
+/// `autoGenerate` is on.
+///
+/// # Arguments
+///
+/// * `settings` - The `relationshipDescriptions` settings
+/// * `relationship` - The relationship about to be saved
+/// * `stored` - The stored version of the relationship, if any
+/// * `name` - Returns the display name of a service by ID
+///
+/// # Returns
+///
+/// * `Ok(())` - The relationship is ready to save
+/// * `Err(AppError)` - If `name` failed
+pub fn describe(
+    settings: &RelationshipDescriptionSettings,
+    relationship: &mut Relationship,
+    stored: Option<&Relationship>,
+    name: impl Fn(&str) -> Result<String, AppError>,
+) -> Result<(), AppError> {
+    let rendered = |relationship: &Relationship| -> Result<String, AppError> {
+        let source_name = name(&relationship.source)?;
+        let target_name = name(&relationship.target)?;
+        Ok(render(
+            &settings.template,
+            relationship,
+            &source_name,
+            &target_name,
+        ))
+    };
+
+    if is_generated(relationship) {
+        let changed = stored.is_some_and(|stored| stored.description != relationship.description);
+        if !has_description(relationship)
+            || changed
+                && relationship.description.as_deref() != Some(rendered(relationship)?.as_str())
+        {
+            clear_generated(relationship);
+        }
+    }
+
+    if !settings.auto_generate || has_description(relationship) {
+        return Ok(());
+    }
+    let description = rendered(relationship)?;
+    if !description.is_empty() {
+        set_generated(relationship, description);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RelationshipType;
+    use crate::test_support::relationship;
+
+    fn settings(auto_generate: bool) -> RelationshipDescriptionSettings {
+        RelationshipDescriptionSettings {
+            auto_generate,
+            ..RelationshipDescriptionSettings::default()
+        }
+    }
+
+    fn display_name(service_id: &str) -> Result<String, AppError> {
+        Ok(match service_id {
+            "checkout" => "Checkout".to_string(),
+            "payments" => "Payments".to_string(),
+            other => other.to_string(),
+        })
+    }
+
+    #[test]
+    fn templates_render_names_ids_and_verbs() {
+        let mut writes = relationship("rel-1", "checkout", "orders-db");
+        writes.relationship_type = RelationshipType::WritesTo;
+
+        let description = render(
+            "{sourceName} ({sourceId}) {relTypeVerb} {targetId} [{relType}] {unknown}",
+            &writes,
+            "Checkout",
+            "Orders",
+        );
+
+        assert_eq!(
+            description,
+            "Checkout (checkout) writes to orders-db [writes_to] {unknown}"
+        );
+    }
+
+    #[test]
+    fn custom_types_use_their_name_as_the_verb() {
+        let mut custom = relationship("rel-1", "checkout", "payments");
+        custom.relationship_type = RelationshipType::Custom("mirrors".to_string());
+
+        let description = render("{sourceName} {relTypeVerb} {targetName}", &custom, "A", "B");
+
+        assert_eq!(description, "A mirrors B");
+    }
+
+    #[test]
+    fn blank_descriptions_are_generated_and_marked() {
+        let mut rel = relationship("rel-1", "checkout", "payments");
+        rel.description = Some("  ".to_string());
+
+        describe(&settings(true), &mut rel, None, display_name).unwrap();
+
+        assert_eq!(
+            rel.description.as_deref(),
+            Some("Checkout depends on Payments")
+        );
+        assert!(is_generated(&rel));
+    }
+
+    #[test]
+    fn nothing_is_generated_unless_enabled() {
+        let mut rel = relationship("rel-1", "checkout", "payments");
+
+        describe(&settings(false), &mut rel, None, display_name).unwrap();
+
+        assert_eq!(rel.description, None);
+        assert!(!is_generated(&rel));
+    }
+
+    #[test]
+    fn written_descriptions_are_kept() {
+        let mut rel = relationship("rel-1", "checkout", "payments");
+        rel.description = Some("Charges cards".to_string());
+
+        describe(&settings(true), &mut rel, None, display_name).unwrap();
+
+        assert_eq!(rel.description.as_deref(), Some("Charges cards"));
+        assert!(!is_generated(&rel));
+    }
+
+    #[test]
+    fn editing_a_generated_description_makes_it_written() {
+        let mut stored = relationship("rel-1", "checkout", "payments");
+        set_generated(&mut stored, "Checkout depends on Payments".to_string());
+        let mut edited = stored.clone();
+        edited.description = Some("Charges cards".to_string());
+
+        describe(&settings(true), &mut edited, Some(&stored), display_name).unwrap();
+
+        assert_eq!(edited.description.as_deref(), Some("Charges cards"));
+        assert!(!is_generated(&edited));
+        assert_eq!(edited.metadata, None);
+    }
+
+    #[test]
+    fn refreshing_a_generated_description_keeps_the_mark() {
+        let mut stored = relationship("rel-1", "checkout", "payments");
+        set_generated(&mut stored, "Checkout depends on payments".to_string());
+        let mut refreshed = stored.clone();
+        refreshed.description = Some("Checkout depends on Payments".to_string());
+
+        describe(
+            &settings(false),
+            &mut refreshed,
+            Some(&stored),
+            display_name,
+        )
+        .unwrap();
+
+        assert!(is_generated(&refreshed));
+    }
+
+    #[test]
+    fn clearing_a_generated_description_drops_the_mark() {
+        let mut stored = relationship("rel-1", "checkout", "payments");
+        set_generated(&mut stored, "Checkout depends on Payments".to_string());
+        let mut cleared = stored.clone();
+        cleared.description = None;
+
+        describe(&settings(false), &mut cleared, Some(&stored), display_name).unwrap();
+
+        assert_eq!(cleared.description, None);
+        assert!(!is_generated(&cleared));
+    }
+}
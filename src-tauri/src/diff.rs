@@ -0,0 +1,144 @@
+//! Comparison of two versions of an environment's data.
+//!
+//! Services and relationships are matched by ID between the two versions
+//! and reported as added, removed or changed. Revisions are ignored, since
+//! they change on every save even when nothing else does. Matching uses
+//! hash maps, so comparing environments of thousands of entities is a
+//! single pass over each side.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Borrow;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::{Relationship, Service};
+
+/// An entity that exists in both versions but differs between them.
+///
+/// # Fields
+///
+/// * `id` - The entity's ID
+/// * `before` / `after` - The entity in the old and new version
+/// * `changed_fields` - The camelCase names of the fields that differ, sorted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedEntity<T> {
+    pub id: String,
+    pub before: T,
+    pub after: T,
+    pub changed_fields: Vec<String>,
+}
+
+/// The differences between two versions of one kind of entity.
+///
+/// # Fields
+///
+/// * `added` - Entities only in the new version, sorted by ID
+/// * `removed` - Entities only in the old version, sorted by ID
+/// * `changed` - Entities in both versions that differ, sorted by ID
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityDiff<T> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+    pub changed: Vec<ChangedEntity<T>>,
+}
+
+/// The differences between two versions of an environment.
+///
+/// # Fields
+///
+/// * `services` - Added, removed and changed services
+/// * `relationships` - Added, removed and changed relationships
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentDiff {
+    pub services: EntityDiff<Service>,
+    pub relationships: EntityDiff<Relationship>,
+}
+
+/// Compares two versions of an environment.
+///
+/// # Arguments
+///
+/// * `before_services` / `before_relationships` - The old version
+/// * `after_services` / `after_relationships` - The new version
+///
+/// # Returns
+///
+/// What was added, removed and changed going from the old to the new version
+pub fn diff_environments<S: Borrow<Service>, A: Borrow<Service>>(
+    before_services: &[S],
+    before_relationships: &[Relationship],
+    after_services: &[A],
+    after_relationships: &[Relationship],
+) -> EnvironmentDiff {
+    let before_services: Vec<&Service> = before_services.iter().map(Borrow::borrow).collect();
+    let after_services: Vec<&Service> = after_services.iter().map(Borrow::borrow).collect();
+    let before_relationships: Vec<&Relationship> = before_relationships.iter().collect();
+    let after_relationships: Vec<&Relationship> = after_relationships.iter().collect();
+
+    EnvironmentDiff {
+        services: diff_entities(&before_services, &after_services, |s| &s.id),
+        relationships: diff_entities(&before_relationships, &after_relationships, |r| &r.id),
+    }
+}
+
+/// Compares two versions of a list of entities matched by ID.
+fn diff_entities<T: Clone + Serialize>(
+    before: &[&T],
+    after: &[&T],
+    id_of: impl Fn(&T) -> &str,
+) -> EntityDiff<T> {
+    let before_by_id: HashMap<&str, &T> = before.iter().map(|&e| (id_of(e), e)).collect();
+    let after_by_id: HashMap<&str, &T> = after.iter().map(|&e| (id_of(e), e)).collect();
+
+    let mut diff = EntityDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+
+    for (id, &entity) in &after_by_id {
+        match before_by_id.get(id) {
+            None => diff.added.push(entity.clone()),
+            Some(&previous) => {
+                let changed_fields = changed_fields(previous, entity);
+                if !changed_fields.is_empty() {
+                    diff.changed.push(ChangedEntity {
+                        id: id.to_string(),
+                        before: previous.clone(),
+                        after: entity.clone(),
+                        changed_fields,
+                    });
+                }
+            }
+        }
+    }
+    for (id, &entity) in &before_by_id {
+        if !after_by_id.contains_key(id) {
+            diff.removed.push(entity.clone());
+        }
+    }
+
+    diff.added.sort_by(|a, b| id_of(a).cmp(id_of(b)));
+    diff.removed.sort_by(|a, b| id_of(a).cmp(id_of(b)));
+    diff.changed.sort_by(|a, b| a.id.cmp(&b.id));
+    diff
+}
+
+/// Returns the serialized names of the fields that differ, ignoring revisions.
+fn changed_fields<T: Serialize>(before: &T, after: &T) -> Vec<String> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return Vec::new();
+    };
+
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| name.as_str() != "revision" && before.get(*name) != after.get(*name))
+        .cloned()
+        .collect()
+}
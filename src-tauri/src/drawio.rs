@@ -0,0 +1,531 @@
+//! Reading and writing draw.io (diagrams.net) diagrams.
+//!
+//! Exports are uncompressed `mxfile` documents with one page. Each service
+//! is a vertex wrapped in a `UserObject` carrying its `serviceId` and
+//! `serviceType`, and each relationship an edge carrying its
+//! `relationshipId` and `relationshipType`, so a diagram that is exported
+//! and imported again keeps the IDs, names, types and edges. Vertices are
+//! laid out on a grid, to be arranged in draw.io; the attributes survive
+//! editing there.
+//!
+//! Only the cells of the first page are read, so the reader is a tolerant
+//! scan of the XML's tags rather than a full XML parser. Diagrams drawn by
+//! hand don't carry the attributes: a vertex's ID is then the slug of its
+//! label and its type is guessed from its shape (see `type_hint`), and an
+//! edge's type is read from its label. Containers, text cells and edge
+//! labels aren't services. Diagrams saved compressed, with the page holding
+//! deflated XML, can't be read; draw.io saves them uncompressed with
+//! File > Properties > Compressed turned off.
+
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{
+    DisplayConfig, LineStyle, Relationship, RelationshipType, Service, ServiceType,
+};
+
+/// Horizontal distance between the vertices of the grid.
+const COLUMN_WIDTH: usize = 200;
+
+/// Vertical distance between the vertices of the grid.
+const ROW_HEIGHT: usize = 120;
+
+/// A vertex of a diagram.
+///
+/// # Fields
+///
+/// * `cell_id` - The ID of the cell, unique in the diagram
+/// * `label` - The text of the vertex, without markup
+/// * `service_id` - The `serviceId` attribute, if the vertex has one
+/// * `service_type` - The `serviceType` attribute, or the type its shape suggests
+#[derive(Debug, Clone)]
+pub struct DiagramNode {
+    pub cell_id: String,
+    pub label: String,
+    pub service_id: Option<String>,
+    pub service_type: Option<ServiceType>,
+}
+
+/// An edge of a diagram.
+///
+/// # Fields
+///
+/// * `cell_id` - The ID of the cell, unique in the diagram
+/// * `source` / `target` - The cell IDs of the vertices it connects; `None` if an
+///   end isn't connected to a vertex
+/// * `label` - The text of the edge or of its label cell, without markup
+/// * `relationship_id` - The `relationshipId` attribute, if the edge has one
+/// * `relationship_type` - The `relationshipType` attribute, or the type its label
+///   names (see `relationship_type_from_label`)
+#[derive(Debug, Clone)]
+pub struct DiagramEdge {
+    pub cell_id: String,
+    pub source: Option<String>,
+    pub target: Option<String>,
+    pub label: String,
+    pub relationship_id: Option<String>,
+    pub relationship_type: Option<RelationshipType>,
+}
+
+/// The vertices and edges of a diagram's first page.
+#[derive(Debug, Clone, Default)]
+pub struct Diagram {
+    pub nodes: Vec<DiagramNode>,
+    pub edges: Vec<DiagramEdge>,
+}
+
+/// Renders services and relationships as a draw.io diagram.
+///
+/// Vertices are shaped by service type (a cylinder for databases, a
+/// process for queues, a hexagon for caches, a rhombus for gateways, a
+/// cloud for external services, rounded for frontends), filled with the
+/// type's color and outlined in the status's. Edges are labeled with their
+/// relationship type and colored and dashed like it. Relationships whose
+/// endpoints aren't among `services` are left out, since importing the
+/// diagram would create them.
+///
+/// # Arguments
+///
+/// * `name` - The name of the page, e.g. the environment
+/// * `services` - The services to draw, sorted by ID on the grid
+/// * `relationships` - The relationships to draw
+/// * `display` - The colors and line styles to draw with
+///
+/// # Returns
+///
+/// The `mxfile` document.
+pub fn render<S: Borrow<Service>>(
+    name: &str,
+    services: &[S],
+    relationships: &[Relationship],
+    display: &DisplayConfig,
+) -> String {
+    let mut services: Vec<&Service> = services.iter().map(Borrow::borrow).collect();
+    services.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut relationships: Vec<&Relationship> = relationships.iter().collect();
+    relationships.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut xml = String::from("<mxfile host=\"dependency-mapping-tool\">\n");
+    xml.push_str(&format!(
+        "  <diagram id=\"page-1\" name={}>\n",
+        xml_attribute(name)
+    ));
+    xml.push_str("    <mxGraphModel grid=\"1\" gridSize=\"10\" arrows=\"1\" page=\"0\">\n");
+    xml.push_str("      <root>\n");
+    xml.push_str("        <mxCell id=\"0\" />\n");
+    xml.push_str("        <mxCell id=\"1\" parent=\"0\" />\n");
+
+    let columns = (services.len() as f64).sqrt().ceil().max(1.0) as usize;
+    let mut cell_ids: HashMap<&str, String> = HashMap::new();
+    for (index, service) in services.iter().enumerate() {
+        let cell_id = format!("n{}", index);
+        let fill = display.service_type_style(&service.service_type).color;
+        let stroke = display.status_style(&service.status).color;
+        xml.push_str(&format!(
+            "        <UserObject id=\"{}\" label={} serviceId={} serviceType={}>\n",
+            cell_id,
+            xml_attribute(&service.name),
+            xml_attribute(&service.id),
+            xml_attribute(service.service_type.as_str())
+        ));
+        xml.push_str(&format!(
+            "          <mxCell style={} vertex=\"1\" parent=\"1\">\n",
+            xml_attribute(&format!(
+                "{}whiteSpace=wrap;html=0;fillColor={};strokeColor={};strokeWidth=2;",
+                vertex_shape(&service.service_type),
+                fill,
+                stroke
+            ))
+        ));
+        xml.push_str(&format!(
+            "            <mxGeometry x=\"{}\" y=\"{}\" width=\"140\" height=\"60\" \
+             as=\"geometry\" />\n",
+            40 + index % columns * COLUMN_WIDTH,
+            40 + index / columns * ROW_HEIGHT
+        ));
+        xml.push_str("          </mxCell>\n        </UserObject>\n");
+        cell_ids.insert(service.id.as_str(), cell_id);
+    }
+
+    let drawn = relationships.iter().filter_map(|relationship| {
+        let source = cell_ids.get(relationship.source.as_str())?;
+        let target = cell_ids.get(relationship.target.as_str())?;
+        Some((relationship, source, target))
+    });
+    for (index, (relationship, source, target)) in drawn.enumerate() {
+        let relationship_type = &relationship.relationship_type;
+        let style = display.relationship_type_style(relationship_type);
+        let dashes = match style.line_style {
+            LineStyle::Solid => String::new(),
+            LineStyle::Dashed | LineStyle::Dotted => {
+                format!("dashed=1;dashPattern={};", style.line_style.dash_array())
+            }
+        };
+        xml.push_str(&format!(
+            "        <UserObject id=\"e{}\" label={} relationshipId={} relationshipType={}>\n",
+            index,
+            xml_attribute(relationship_type.as_str()),
+            xml_attribute(&relationship.id),
+            xml_attribute(relationship_type.as_str())
+        ));
+        xml.push_str(&format!(
+            "          <mxCell style={} edge=\"1\" parent=\"1\" source=\"{}\" target=\"{}\">\n",
+            xml_attribute(&format!(
+                "endArrow=block;html=0;strokeColor={};{}",
+                style.color, dashes
+            )),
+            source,
+            target
+        ));
+        xml.push_str("            <mxGeometry relative=\"1\" as=\"geometry\" />\n");
+        xml.push_str("          </mxCell>\n        </UserObject>\n");
+    }
+
+    xml.push_str("      </root>\n    </mxGraphModel>\n  </diagram>\n</mxfile>\n");
+    xml
+}
+
+/// Returns the style a vertex of a service type starts with.
+fn vertex_shape(service_type: &ServiceType) -> &'static str {
+    match service_type {
+        ServiceType::Database => "shape=cylinder3;boundedLbl=1;backgroundOutline=1;size=15;",
+        ServiceType::Queue => "shape=process;",
+        ServiceType::Cache => "shape=hexagon;perimeter=hexagonPerimeter2;",
+        ServiceType::Gateway => "rhombus;",
+        ServiceType::External => "ellipse;shape=cloud;",
+        ServiceType::Frontend => "rounded=1;",
+        _ => "rounded=0;",
+    }
+}
+
+/// Quotes and escapes a value as an XML attribute.
+fn xml_attribute(value: &str) -> String {
+    let escaped = value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;");
+    format!("\"{}\"", escaped)
+}
+
+/// A cell of a diagram, with the attributes of the `UserObject` or
+/// `object` wrapping it.
+#[derive(Debug)]
+struct Cell {
+    attributes: HashMap<String, String>,
+}
+
+impl Cell {
+    /// Returns the value of an attribute.
+    fn get(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// Returns whether a flag attribute such as `vertex` is set.
+    fn is(&self, flag: &str) -> bool {
+        self.get(flag) == Some("1")
+    }
+}
+
+/// Reads the vertices and edges of a diagram's first page.
+///
+/// # Arguments
+///
+/// * `xml` - An `mxfile` document, or a bare `mxGraphModel`
+///
+/// # Returns
+///
+/// * `Ok(Diagram)` - The vertices that can be services and the edges
+/// * `Err(String)` - Why the diagram can't be read: it is compressed, has no
+///   cells or isn't well-formed enough to find them
+pub fn parse(xml: &str) -> Result<Diagram, String> {
+    let cells = cells(xml)?;
+    if cells.is_empty() {
+        return Err("The file has no draw.io diagram cells".to_string());
+    }
+
+    let edge_ids: HashSet<&str> = cells
+        .iter()
+        .filter(|c| c.is("edge"))
+        .filter_map(|c| c.get("id"))
+        .collect();
+    let parents: HashSet<&str> = cells
+        .iter()
+        .filter(|c| c.is("vertex"))
+        .filter_map(|c| c.get("parent"))
+        .collect();
+
+    let mut edge_labels: HashMap<&str, String> = HashMap::new();
+    let mut diagram = Diagram::default();
+    for cell in cells.iter().filter(|c| c.is("vertex")) {
+        let Some(id) = cell.get("id") else {
+            continue;
+        };
+        let style = cell.get("style").unwrap_or_default();
+        let label = cell_label(cell);
+        if let Some(edge) = cell.get("parent").filter(|p| edge_ids.contains(p)) {
+            edge_labels.entry(edge).or_insert(label);
+            continue;
+        }
+        let is_container = parents.contains(id)
+            || style_entries(style).any(|(key, value)| {
+                matches!(key, "swimlane" | "group" | "text" | "edgeLabel")
+                    || (key == "shape" && matches!(value, "swimlane" | "table"))
+            });
+        if is_container || label.is_empty() {
+            continue;
+        }
+
+        diagram.nodes.push(DiagramNode {
+            cell_id: id.to_string(),
+            label,
+            service_id: cell.get("serviceId").map(str::to_string),
+            service_type: cell
+                .get("serviceType")
+                .map(ServiceType::from_name)
+                .or_else(|| type_hint(style)),
+        });
+    }
+
+    for cell in cells.iter().filter(|c| c.is("edge")) {
+        let Some(id) = cell.get("id") else {
+            continue;
+        };
+        let label = Some(cell_label(cell))
+            .filter(|label| !label.is_empty())
+            .or_else(|| edge_labels.remove(id))
+            .unwrap_or_default();
+        diagram.edges.push(DiagramEdge {
+            cell_id: id.to_string(),
+            source: cell.get("source").map(str::to_string),
+            target: cell.get("target").map(str::to_string),
+            relationship_type: cell
+                .get("relationshipType")
+                .map(RelationshipType::from_name)
+                .or_else(|| relationship_type_from_label(&label)),
+            relationship_id: cell.get("relationshipId").map(str::to_string),
+            label,
+        });
+    }
+
+    Ok(diagram)
+}
+
+/// Returns the built-in relationship type an edge label names, written
+/// as serialized or as a verb (`depends_on`, `Depends on`, `reads-from`).
+pub fn relationship_type_from_label(label: &str) -> Option<RelationshipType> {
+    let name = label
+        .trim()
+        .to_lowercase()
+        .replace(|c: char| c.is_whitespace() || c == '-', "_");
+    match RelationshipType::from_name(&name) {
+        RelationshipType::Custom(_) => None,
+        relationship_type => Some(relationship_type),
+    }
+}
+
+/// Guesses the service type of a vertex from its style: cylinders are
+/// databases, processes and queue shapes queues, hexagons caches, rhombi
+/// gateways and clouds external services.
+pub fn type_hint(style: &str) -> Option<ServiceType> {
+    style_entries(style).find_map(|(key, value)| {
+        let shape = if key == "shape" { value } else { key };
+        if ["cylinder", "datastore", "database"]
+            .iter()
+            .any(|hint| shape.contains(hint))
+        {
+            Some(ServiceType::Database)
+        } else if shape.contains("queue") || shape == "process" {
+            Some(ServiceType::Queue)
+        } else if shape.contains("hexagon") {
+            Some(ServiceType::Cache)
+        } else if shape == "rhombus" {
+            Some(ServiceType::Gateway)
+        } else if shape.contains("cloud") {
+            Some(ServiceType::External)
+        } else {
+            None
+        }
+    })
+}
+
+/// Splits a style into its `key=value` entries; a bare name such as
+/// `ellipse` has an empty value.
+fn style_entries(style: &str) -> impl Iterator<Item = (&str, &str)> {
+    style
+        .split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.split_once('=').unwrap_or((entry, "")))
+}
+
+/// Returns the text of a cell: its label (of the wrapping object) or value,
+/// without HTML markup if the style enables it, and with runs of whitespace
+/// collapsed.
+fn cell_label(cell: &Cell) -> String {
+    let value = cell
+        .get("label")
+        .or_else(|| cell.get("value"))
+        .unwrap_or_default();
+    let html = style_entries(cell.get("style").unwrap_or_default()).any(|e| e == ("html", "1"));
+    let text = if html {
+        let mut text = String::new();
+        let mut in_tag = false;
+        for character in value.chars() {
+            match character {
+                '<' => in_tag = true,
+                // Tags such as <br> and <div> separate words
+                '>' if in_tag => {
+                    in_tag = false;
+                    text.push(' ');
+                }
+                _ if !in_tag => text.push(character),
+                _ => {}
+            }
+        }
+        unescape(&text.replace("&nbsp;", " "))
+    } else {
+        value.to_string()
+    };
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Collects the cells of the first page, merging the attributes of
+/// wrapping objects into their cells.
+fn cells(xml: &str) -> Result<Vec<Cell>, String> {
+    let mut cells = Vec::new();
+    let mut wrapper: Option<HashMap<String, String>> = None;
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some(skipped) = ["<!--", "<![CDATA[", "<?", "<!"]
+            .iter()
+            .zip(["-->", "]]>", "?>", ">"])
+            .find(|(open, _)| rest.starts_with(**open))
+            .map(|(_, close)| close)
+        {
+            let end = rest
+                .find(skipped)
+                .ok_or("The file ends inside a comment or declaration")?;
+            rest = &rest[end + skipped.len()..];
+            continue;
+        }
+
+        let end = tag_end(rest).ok_or("The file ends inside a tag")?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_start_matches('/').trim_end_matches('/');
+        let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+        let name = &tag[..name_end];
+
+        match (name, closing) {
+            ("diagram", false) if !self_closing => {
+                let content = &rest[..rest.find('<').unwrap_or(rest.len())];
+                if !content.trim().is_empty() {
+                    return Err("The diagram is compressed; save it in draw.io with \
+                        File > Properties > Compressed turned off"
+                        .to_string());
+                }
+            }
+            // Only the first page is read
+            ("diagram", true) => break,
+            ("UserObject" | "object", false) => {
+                let attributes = attributes(&tag[name_end..]);
+                if self_closing {
+                    cells.push(Cell { attributes });
+                } else {
+                    wrapper = Some(attributes);
+                }
+            }
+            ("UserObject" | "object", true) => wrapper = None,
+            ("mxCell", false) => {
+                let mut attributes = attributes(&tag[name_end..]);
+                // The object holds the cell's ID and label
+                if let Some(wrapper) = wrapper.as_mut() {
+                    attributes.extend(wrapper.drain());
+                }
+                cells.push(Cell { attributes });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(cells)
+}
+
+/// Returns the position of the `>` ending the tag `rest` starts with,
+/// skipping quoted attribute values.
+fn tag_end(rest: &str) -> Option<usize> {
+    let mut quote = None;
+    for (position, character) in rest.char_indices() {
+        match (quote, character) {
+            (None, '"' | '\'') => quote = Some(character),
+            (Some(open), _) if open == character => quote = None,
+            (None, '>') => return Some(position),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses the `name="value"` attributes of a tag, unescaping the values.
+fn attributes(text: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = text;
+    while let Some(equals) = rest.find('=') {
+        let name = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| matches!(c, '"' | '\'')) else {
+            break;
+        };
+        let Some(close) = value[1..].find(quote) else {
+            break;
+        };
+        attributes.insert(name.to_string(), unescape(&value[1..close + 1]));
+        rest = &value[close + 2..];
+    }
+    attributes
+}
+
+/// Replaces the XML entities and character references of a value.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#')?.parse().ok())
+                .and_then(char::from_u32),
+        };
+        match character {
+            Some(character) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
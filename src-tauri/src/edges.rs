@@ -0,0 +1,153 @@
+//! Aggregation of parallel relationships into one edge.
+//!
+//! Two services can be related in several ways at once (e.g. one calls the
+//! other and also reads from it), which draws as overlapping arrows. The
+//! relationships sharing a source and target are collapsed into one edge
+//! that lists their types and IDs, for the graph view to draw with a badge
+//! and the exporters to draw once. Direction matters: A→B and B→A are
+//! separate edges.
+
+use serde::Serialize;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+
+use crate::models::{Relationship, RelationshipType};
+
+/// The relationships from one service to another, drawn as one edge.
+///
+/// # Fields
+///
+/// * `id` - `{source}->{target}`
+/// * `source` / `target` - IDs of the services
+/// * `count` - Number of relationships collapsed into this edge
+/// * `relationship_types` - The types among them, sorted and without duplicates
+/// * `relationship_ids` - IDs of the relationships, in the order they were given
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregatedEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub count: usize,
+    pub relationship_types: Vec<RelationshipType>,
+    pub relationship_ids: Vec<String>,
+}
+
+impl AggregatedEdge {
+    /// Returns the label exporters draw on the edge: the types, and the
+    /// count if the edge collapses more than one relationship.
+    pub fn label(&self) -> String {
+        let types: Vec<&str> = self
+            .relationship_types
+            .iter()
+            .map(RelationshipType::as_str)
+            .collect();
+        if self.count > 1 {
+            format!("{} ({})", types.join(", "), self.count)
+        } else {
+            types.join(", ")
+        }
+    }
+}
+
+/// Collapses relationships with the same source and target into one edge each.
+///
+/// # Arguments
+///
+/// * `relationships` - The relationships to aggregate
+///
+/// # Returns
+///
+/// One edge per (source, target) pair, in the order each pair first appears.
+pub fn aggregate_edges<R: Borrow<Relationship>>(relationships: &[R]) -> Vec<AggregatedEdge> {
+    let mut edges: Vec<AggregatedEdge> = Vec::new();
+    let mut positions: HashMap<(&str, &str), usize> = HashMap::new();
+
+    for rel in relationships.iter().map(Borrow::borrow) {
+        let position = *positions
+            .entry((rel.source.as_str(), rel.target.as_str()))
+            .or_insert_with(|| {
+                edges.push(AggregatedEdge {
+                    id: format!("{}->{}", rel.source, rel.target),
+                    source: rel.source.clone(),
+                    target: rel.target.clone(),
+                    count: 0,
+                    relationship_types: Vec::new(),
+                    relationship_ids: Vec::new(),
+                });
+                edges.len() - 1
+            });
+
+        let edge = &mut edges[position];
+        edge.count += 1;
+        edge.relationship_ids.push(rel.id.clone());
+        if !edge.relationship_types.contains(&rel.relationship_type) {
+            edge.relationship_types.push(rel.relationship_type.clone());
+        }
+    }
+
+    for edge in &mut edges {
+        edge.relationship_types
+            .sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    }
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::relationship;
+
+    fn typed(id: &str, source: &str, target: &str, kind: RelationshipType) -> Relationship {
+        let mut rel = relationship(id, source, target);
+        rel.relationship_type = kind;
+        rel
+    }
+
+    #[test]
+    fn parallel_relationships_collapse_into_one_edge() {
+        let edges = aggregate_edges(&[
+            typed("r1", "a", "b", RelationshipType::DependsOn),
+            typed("r2", "a", "b", RelationshipType::CommunicatesWith),
+            typed("r3", "a", "b", RelationshipType::DependsOn),
+        ]);
+
+        assert_eq!(
+            edges,
+            [AggregatedEdge {
+                id: "a->b".to_string(),
+                source: "a".to_string(),
+                target: "b".to_string(),
+                count: 3,
+                relationship_types: vec![
+                    RelationshipType::CommunicatesWith,
+                    RelationshipType::DependsOn,
+                ],
+                relationship_ids: vec!["r1".to_string(), "r2".to_string(), "r3".to_string()],
+            }]
+        );
+        assert_eq!(edges[0].label(), "communicates_with, depends_on (3)");
+    }
+
+    #[test]
+    fn opposite_directions_stay_separate() {
+        let edges = aggregate_edges(&[
+            relationship("ab", "a", "b"),
+            relationship("ba", "b", "a"),
+            relationship("ab-2", "a", "b"),
+        ]);
+
+        let ids: Vec<&str> = edges.iter().map(|edge| edge.id.as_str()).collect();
+        assert_eq!(ids, ["a->b", "b->a"]);
+        assert_eq!(edges[0].count, 2);
+        assert_eq!(edges[0].relationship_ids, ["ab", "ab-2"]);
+        assert_eq!(edges[1].count, 1);
+        assert_eq!(edges[1].relationship_ids, ["ba"]);
+        assert_eq!(edges[1].label(), "depends_on");
+    }
+
+    #[test]
+    fn no_relationships_make_no_edges() {
+        assert!(aggregate_edges::<Relationship>(&[]).is_empty());
+    }
+}
@@ -13,25 +13,48 @@ use thiserror::Error;
 ///
 /// # Serialization
 ///
-/// Errors are serialized as strings (their display representation) when
-/// returned to the frontend. This allows for consistent error handling
-/// across the IPC boundary.
+/// Errors are serialized as `{ code, message, details }` objects when
+/// returned to the frontend: a stable snake_case code per variant, the
+/// display representation, and the variant's data. The frontend matches on
+/// the code, so message wording can change without breaking the UI.
 ///
 /// # Error Conversion
 ///
 /// The `#[from]` attribute enables automatic conversion from standard
-/// library errors (std::io::Error, serde_json::Error) using the `?` operator.
+/// library errors (std::io::Error, serde_json::Error, rusqlite::Error, git2::Error) using
+/// the `?` operator.
 ///
 /// # Variants
 ///
 /// * `Io` - File system operations failed (read, write, delete)
 /// * `Json` - JSON parsing or serialization failed
+/// * `FileLoad` - A specific data file could not be read or parsed
+/// * `Database` - A SQLite storage backend operation failed
+/// * `Git` - A git repository operation failed
+/// * `WebhookFailed` - A webhook request was not accepted
+/// * `ClusterAuthFailed` - A Kubernetes cluster rejected or couldn't be given credentials
+/// * `ClusterRequestFailed` - A Kubernetes cluster couldn't be reached or a request failed
+/// * `RegistryAuthFailed` - A service registry rejected the token
+/// * `RegistryTimeout` - A service registry didn't answer in time
+/// * `RegistryRequestFailed` - A service registry couldn't be reached or answered with an error
+/// * `GitHubRateLimited` - The GitHub API rate limit is used up
+/// * `GitHubRequestFailed` - A GitHub API request failed
+/// * `EncryptionRequired` - An encrypted file was read without a passphrase set
+/// * `WrongPassphrase` - Data was encrypted with a different passphrase
 /// * `ServiceNotFound` - Requested service ID doesn't exist
 /// * `EnvironmentNotFound` - Requested environment doesn't exist
+/// * `WorkspaceNotFound` - Requested workspace name doesn't exist
 /// * `InvalidPath` - Provided file path is invalid or inaccessible
-/// * `StateLock` - Failed to acquire the application state mutex
 /// * `RelationshipNotFound` - Requested relationship ID doesn't exist
 /// * `DuplicateRelationship` - Attempted to create a duplicate relationship
+/// * `GroupNotFound` - Requested service group ID doesn't exist
+/// * `ViewNotFound` - Requested saved view name doesn't exist
+/// * `CustomTypeNotFound` - Requested custom type isn't registered
+/// * `SnapshotNotFound` - Requested environment snapshot ID doesn't exist
+/// * `Conflict` - A save was based on an outdated revision of the data
+/// * `BatchOperationFailed` - An operation of a batch failed, so nothing was written
+/// * `OperationCancelled` - A long-running command was cancelled with `cancel_operation`
+/// * `ReadOnlyMode` - A command that changes data was called in read-only mode
 /// * `ValidationError` - Data validation failed
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -45,6 +68,72 @@ pub enum AppError {
     #[error("JSON parsing error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// A specific data file could not be read or parsed.
+    /// Contains the file path and the underlying reason.
+    #[error("Failed to load '{path}': {reason}")]
+    FileLoad { path: String, reason: String },
+
+    /// A SQLite storage backend operation failed.
+    /// Contains the underlying rusqlite::Error.
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    /// A git repository operation failed.
+    /// Contains the underlying git2::Error.
+    #[error("Git error: {0}")]
+    Git(#[from] git2::Error),
+
+    /// A webhook request failed or was answered with an error status.
+    /// Contains the URL and why the request failed.
+    #[error("Webhook request to {url} failed: {reason}")]
+    WebhookFailed { url: String, reason: String },
+
+    /// Authenticating with a Kubernetes cluster failed.
+    /// Contains the reason, e.g. expired credentials or a forbidden namespace.
+    #[error("Kubernetes authentication failed: {0}")]
+    ClusterAuthFailed(String),
+
+    /// A Kubernetes cluster couldn't be reached or answered a request with an error.
+    /// Contains the reason.
+    #[error("Kubernetes request failed: {0}")]
+    ClusterRequestFailed(String),
+
+    /// A service registry answered 401 or 403: the token is missing or invalid.
+    /// Contains the URL of the rejected request.
+    #[error("Service registry rejected the token (missing or invalid) for {0}")]
+    RegistryAuthFailed(String),
+
+    /// A service registry request timed out.
+    /// Contains the URL of the request.
+    #[error("Service registry did not answer in time: {0}")]
+    RegistryTimeout(String),
+
+    /// A service registry couldn't be reached, answered with an error status or
+    /// sent an unexpected body.
+    /// Contains the URL and why the request failed.
+    #[error("Service registry request to {url} failed: {reason}")]
+    RegistryRequestFailed { url: String, reason: String },
+
+    /// The GitHub API rate limit is used up.
+    /// Contains when the limit resets, as reported by GitHub.
+    #[error("GitHub API rate limit exceeded; it resets at {0}")]
+    GitHubRateLimited(String),
+
+    /// A GitHub API request failed or was answered with an error status.
+    /// Contains the repository and why the request failed.
+    #[error("GitHub request for {repository} failed: {reason}")]
+    GitHubRequestFailed { repository: String, reason: String },
+
+    /// An encrypted data file was encountered but no passphrase is set.
+    /// Contains the path of the encrypted file.
+    #[error("Encryption passphrase required to read '{0}'")]
+    EncryptionRequired(String),
+
+    /// The passphrase doesn't match the one the data was encrypted with.
+    /// Contains the path of the file or data directory that didn't match.
+    #[error("Wrong encryption passphrase for '{0}'")]
+    WrongPassphrase(String),
+
     /// The requested service was not found.
     /// Contains the service ID that was not found.
     #[error("Service not found: {0}")]
@@ -55,6 +144,11 @@ pub enum AppError {
     #[error("Environment not found: {0}")]
     EnvironmentNotFound(String),
 
+    /// The requested workspace was not found.
+    /// Contains the workspace name that was not found.
+    #[error("Workspace not found: {0}")]
+    WorkspaceNotFound(String),
+
     /// The environment already exists.
     /// Contains the environment name that already exists.
     #[error("Environment already exists: {0}")]
@@ -65,11 +159,6 @@ pub enum AppError {
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
-    /// Failed to acquire a lock on the application state.
-    /// This typically indicates a deadlock or poisoned mutex.
-    #[error("State lock error")]
-    StateLock,
-
     /// The requested relationship was not found.
     /// Contains the relationship ID that was not found.
     #[error("Relationship not found: {0}")]
@@ -80,21 +169,601 @@ pub enum AppError {
     #[error("Duplicate relationship: {0} -> {1}")]
     DuplicateRelationship(String, String),
 
+    /// The requested service group was not found.
+    /// Contains the group ID that was not found.
+    #[error("Group not found: {0}")]
+    GroupNotFound(String),
+
+    /// The requested saved view was not found.
+    /// Contains the view name that was not found.
+    #[error("View not found: {0}")]
+    ViewNotFound(String),
+
+    /// The requested custom type is not in the registry.
+    /// Contains the type name that was not found.
+    #[error("Custom type not found: {0}")]
+    CustomTypeNotFound(String),
+
+    /// The requested environment snapshot was not found.
+    /// Contains the snapshot ID that was not found.
+    #[error("Snapshot not found: {0}")]
+    SnapshotNotFound(String),
+
+    /// A save was based on a revision that has since been overwritten.
+    /// Contains the ID, the revision the caller had, and the stored revision.
+    #[error(
+        "Conflict: '{id}' was changed elsewhere (expected revision {expected_revision}, \
+         found {actual_revision})"
+    )]
+    Conflict {
+        id: String,
+        expected_revision: u64,
+        actual_revision: u64,
+    },
+
+    /// An operation of a batch failed validation, so none were applied.
+    /// Contains the zero-based index of the operation and why it failed.
+    #[error("Batch operation {index} failed: {source}")]
+    BatchOperationFailed { index: usize, source: Box<AppError> },
+
+    /// A long-running command was cancelled before it wrote anything.
+    /// Contains the operation ID it was started with, and the phase it was
+    /// in with how many of the phase's items were done.
+    #[error("Operation cancelled: {operation_id} ({phase}, {done} of {total} done)")]
+    OperationCancelled {
+        operation_id: String,
+        phase: String,
+        done: usize,
+        total: usize,
+    },
+
+    /// A command that changes data was called while read-only mode is on.
+    /// Contains the name of the rejected command.
+    #[error("Read-only mode is on; '{0}' is disabled")]
+    ReadOnlyMode(String),
+
     /// Data validation failed.
     /// Contains a description of the validation error.
     #[error("Validation error: {0}")]
     ValidationError(String),
 }
 
+impl AppError {
+    /// Checks that a save is based on the stored revision of the data.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the service or relationship being saved
+    /// * `expected_revision` - The revision the caller last read
+    /// * `actual_revision` - The revision currently in storage (0 if it doesn't exist)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the revisions match
+    /// * `Err(AppError::Conflict)` - If the data was saved elsewhere in the meantime
+    pub fn check_revision(
+        id: &str,
+        expected_revision: u64,
+        actual_revision: u64,
+    ) -> Result<(), AppError> {
+        if expected_revision == actual_revision {
+            Ok(())
+        } else {
+            Err(AppError::Conflict {
+                id: id.to_string(),
+                expected_revision,
+                actual_revision,
+            })
+        }
+    }
+}
+
+impl AppError {
+    /// Returns the stable snake_case code of the variant.
+    ///
+    /// Codes are part of the frontend contract: the UI decides what to show
+    /// by code, never by message, so a code must not change once released.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Io(_) => "io_error",
+            AppError::Json(_) => "json_error",
+            AppError::FileLoad { .. } => "file_load_failed",
+            AppError::Database(_) => "database_error",
+            AppError::Git(_) => "git_error",
+            AppError::WebhookFailed { .. } => "webhook_failed",
+            AppError::ClusterAuthFailed(_) => "cluster_auth_failed",
+            AppError::ClusterRequestFailed(_) => "cluster_request_failed",
+            AppError::RegistryAuthFailed(_) => "registry_auth_failed",
+            AppError::RegistryTimeout(_) => "registry_timeout",
+            AppError::RegistryRequestFailed { .. } => "registry_request_failed",
+            AppError::GitHubRateLimited(_) => "github_rate_limited",
+            AppError::GitHubRequestFailed { .. } => "github_request_failed",
+            AppError::EncryptionRequired(_) => "encryption_required",
+            AppError::WrongPassphrase(_) => "wrong_passphrase",
+            AppError::ServiceNotFound(_) => "service_not_found",
+            AppError::EnvironmentNotFound(_) => "environment_not_found",
+            AppError::EnvironmentExists(_) => "environment_exists",
+            AppError::WorkspaceNotFound(_) => "workspace_not_found",
+            AppError::InvalidPath(_) => "invalid_path",
+            AppError::RelationshipNotFound(_) => "relationship_not_found",
+            AppError::DuplicateRelationship(_, _) => "duplicate_relationship",
+            AppError::GroupNotFound(_) => "group_not_found",
+            AppError::ViewNotFound(_) => "view_not_found",
+            AppError::CustomTypeNotFound(_) => "custom_type_not_found",
+            AppError::SnapshotNotFound(_) => "snapshot_not_found",
+            AppError::Conflict { .. } => "conflict",
+            AppError::BatchOperationFailed { .. } => "batch_operation_failed",
+            AppError::OperationCancelled { .. } => "operation_cancelled",
+            AppError::ReadOnlyMode(_) => "read_only_mode",
+            AppError::ValidationError(_) => "validation_error",
+        }
+    }
+
+    /// Returns the data of the variant as a camelCase JSON object.
+    ///
+    /// Variants wrapping a library error carry what the frontend can act on
+    /// (e.g. the I/O error kind); a failed batch operation nests the payload
+    /// of its cause.
+    pub fn details(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        match self {
+            AppError::Io(e) => json!({ "kind": format!("{:?}", e.kind()) }),
+            AppError::Json(e) => json!({ "line": e.line(), "column": e.column() }),
+            AppError::FileLoad { path, reason } => json!({ "path": path, "reason": reason }),
+            AppError::Database(_) | AppError::Git(_) => json!({}),
+            AppError::WebhookFailed { url, reason }
+            | AppError::RegistryRequestFailed { url, reason } => {
+                json!({ "url": url, "reason": reason })
+            }
+            AppError::ClusterAuthFailed(reason)
+            | AppError::ClusterRequestFailed(reason)
+            | AppError::ValidationError(reason) => json!({ "reason": reason }),
+            AppError::RegistryAuthFailed(url) | AppError::RegistryTimeout(url) => {
+                json!({ "url": url })
+            }
+            AppError::GitHubRateLimited(resets_at) => json!({ "resetsAt": resets_at }),
+            AppError::GitHubRequestFailed { repository, reason } => {
+                json!({ "repository": repository, "reason": reason })
+            }
+            AppError::EncryptionRequired(path)
+            | AppError::WrongPassphrase(path)
+            | AppError::InvalidPath(path) => json!({ "path": path }),
+            AppError::ServiceNotFound(id) => json!({ "serviceId": id }),
+            AppError::EnvironmentNotFound(environment)
+            | AppError::EnvironmentExists(environment) => json!({ "environment": environment }),
+            AppError::WorkspaceNotFound(name) => json!({ "workspace": name }),
+            AppError::RelationshipNotFound(id) => json!({ "relationshipId": id }),
+            AppError::DuplicateRelationship(source, target) => {
+                json!({ "source": source, "target": target })
+            }
+            AppError::GroupNotFound(id) => json!({ "groupId": id }),
+            AppError::ViewNotFound(name) => json!({ "viewName": name }),
+            AppError::CustomTypeNotFound(name) => json!({ "name": name }),
+            AppError::SnapshotNotFound(id) => json!({ "snapshotId": id }),
+            AppError::ReadOnlyMode(command) => json!({ "command": command }),
+            AppError::Conflict {
+                id,
+                expected_revision,
+                actual_revision,
+            } => json!({
+                "id": id,
+                "expectedRevision": expected_revision,
+                "actualRevision": actual_revision,
+            }),
+            AppError::OperationCancelled {
+                operation_id,
+                phase,
+                done,
+                total,
+            } => json!({
+                "operationId": operation_id,
+                "phase": phase,
+                "done": done,
+                "total": total,
+            }),
+            AppError::BatchOperationFailed { index, source } => json!({
+                "index": index,
+                "cause": {
+                    "code": source.code(),
+                    "message": source.to_string(),
+                    "details": source.details(),
+                },
+            }),
+        }
+    }
+}
+
 impl Serialize for AppError {
-    /// Serializes the error as a string for transmission to the frontend.
+    /// Serializes the error as `{ code, message, details }` for the frontend.
     ///
-    /// Uses the Display implementation to convert the error to a
-    /// human-readable string that can be displayed to users.
+    /// `code` is the stable discriminant of the variant (see `code`),
+    /// `message` the human-readable Display representation and `details` the
+    /// variant's data (see `details`). Match on `code`; the message wording
+    /// may change at any time.
+    ///
+    /// # Examples
+    ///
+    /// ```json
+    /// {
+    ///   "code": "service_not_found",
+    ///   "message": "Service not found: billing-api",
+    ///   "details": { "serviceId": "billing-api" }
+    /// }
+    /// ```
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        use serde::ser::SerializeStruct;
+
+        let mut payload = serializer.serialize_struct("AppError", 3)?;
+        payload.serialize_field("code", self.code())?;
+        payload.serialize_field("message", &self.to_string())?;
+        payload.serialize_field("details", &self.details())?;
+        payload.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// One error of every variant, with the payload it must serialize to.
+    fn payloads() -> Vec<(AppError, Value)> {
+        let json_error = serde_json::from_str::<Value>("{").unwrap_err();
+        vec![
+            (
+                AppError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "gone")),
+                json!({
+                    "code": "io_error",
+                    "message": "IO error: gone",
+                    "details": { "kind": "NotFound" },
+                }),
+            ),
+            (
+                AppError::Json(json_error),
+                json!({
+                    "code": "json_error",
+                    "message": "JSON parsing error: EOF while parsing an object at line 1 column 1",
+                    "details": { "line": 1, "column": 1 },
+                }),
+            ),
+            (
+                AppError::FileLoad {
+                    path: "dev/services/api.json".to_string(),
+                    reason: "bad".to_string(),
+                },
+                json!({
+                    "code": "file_load_failed",
+                    "message": "Failed to load 'dev/services/api.json': bad",
+                    "details": { "path": "dev/services/api.json", "reason": "bad" },
+                }),
+            ),
+            (
+                AppError::Database(rusqlite::Error::QueryReturnedNoRows),
+                json!({
+                    "code": "database_error",
+                    "message": "Database error: Query returned no rows",
+                    "details": {},
+                }),
+            ),
+            (
+                AppError::Git(git2::Error::from_str("no repository")),
+                json!({
+                    "code": "git_error",
+                    "message": "Git error: no repository",
+                    "details": {},
+                }),
+            ),
+            (
+                AppError::WebhookFailed {
+                    url: "https://hooks.example.com".to_string(),
+                    reason: "status 500".to_string(),
+                },
+                json!({
+                    "code": "webhook_failed",
+                    "message": "Webhook request to https://hooks.example.com failed: status 500",
+                    "details": { "url": "https://hooks.example.com", "reason": "status 500" },
+                }),
+            ),
+            (
+                AppError::ClusterAuthFailed("expired".to_string()),
+                json!({
+                    "code": "cluster_auth_failed",
+                    "message": "Kubernetes authentication failed: expired",
+                    "details": { "reason": "expired" },
+                }),
+            ),
+            (
+                AppError::ClusterRequestFailed("unreachable".to_string()),
+                json!({
+                    "code": "cluster_request_failed",
+                    "message": "Kubernetes request failed: unreachable",
+                    "details": { "reason": "unreachable" },
+                }),
+            ),
+            (
+                AppError::RegistryAuthFailed("https://consul".to_string()),
+                json!({
+                    "code": "registry_auth_failed",
+                    "message": "Service registry rejected the token (missing or invalid) for https://consul",
+                    "details": { "url": "https://consul" },
+                }),
+            ),
+            (
+                AppError::RegistryTimeout("https://consul".to_string()),
+                json!({
+                    "code": "registry_timeout",
+                    "message": "Service registry did not answer in time: https://consul",
+                    "details": { "url": "https://consul" },
+                }),
+            ),
+            (
+                AppError::RegistryRequestFailed {
+                    url: "https://consul".to_string(),
+                    reason: "status 503".to_string(),
+                },
+                json!({
+                    "code": "registry_request_failed",
+                    "message": "Service registry request to https://consul failed: status 503",
+                    "details": { "url": "https://consul", "reason": "status 503" },
+                }),
+            ),
+            (
+                AppError::GitHubRateLimited("12:00".to_string()),
+                json!({
+                    "code": "github_rate_limited",
+                    "message": "GitHub API rate limit exceeded; it resets at 12:00",
+                    "details": { "resetsAt": "12:00" },
+                }),
+            ),
+            (
+                AppError::GitHubRequestFailed {
+                    repository: "org/repo".to_string(),
+                    reason: "not found".to_string(),
+                },
+                json!({
+                    "code": "github_request_failed",
+                    "message": "GitHub request for org/repo failed: not found",
+                    "details": { "repository": "org/repo", "reason": "not found" },
+                }),
+            ),
+            (
+                AppError::EncryptionRequired("dev/services/api.json".to_string()),
+                json!({
+                    "code": "encryption_required",
+                    "message": "Encryption passphrase required to read 'dev/services/api.json'",
+                    "details": { "path": "dev/services/api.json" },
+                }),
+            ),
+            (
+                AppError::WrongPassphrase("data".to_string()),
+                json!({
+                    "code": "wrong_passphrase",
+                    "message": "Wrong encryption passphrase for 'data'",
+                    "details": { "path": "data" },
+                }),
+            ),
+            (
+                AppError::ServiceNotFound("api".to_string()),
+                json!({
+                    "code": "service_not_found",
+                    "message": "Service not found: api",
+                    "details": { "serviceId": "api" },
+                }),
+            ),
+            (
+                AppError::EnvironmentNotFound("prod".to_string()),
+                json!({
+                    "code": "environment_not_found",
+                    "message": "Environment not found: prod",
+                    "details": { "environment": "prod" },
+                }),
+            ),
+            (
+                AppError::WorkspaceNotFound("team".to_string()),
+                json!({
+                    "code": "workspace_not_found",
+                    "message": "Workspace not found: team",
+                    "details": { "workspace": "team" },
+                }),
+            ),
+            (
+                AppError::EnvironmentExists("dev".to_string()),
+                json!({
+                    "code": "environment_exists",
+                    "message": "Environment already exists: dev",
+                    "details": { "environment": "dev" },
+                }),
+            ),
+            (
+                AppError::InvalidPath("../x".to_string()),
+                json!({
+                    "code": "invalid_path",
+                    "message": "Invalid path: ../x",
+                    "details": { "path": "../x" },
+                }),
+            ),
+            (
+                AppError::RelationshipNotFound("rel-1".to_string()),
+                json!({
+                    "code": "relationship_not_found",
+                    "message": "Relationship not found: rel-1",
+                    "details": { "relationshipId": "rel-1" },
+                }),
+            ),
+            (
+                AppError::DuplicateRelationship("api".to_string(), "db".to_string()),
+                json!({
+                    "code": "duplicate_relationship",
+                    "message": "Duplicate relationship: api -> db",
+                    "details": { "source": "api", "target": "db" },
+                }),
+            ),
+            (
+                AppError::GroupNotFound("payments".to_string()),
+                json!({
+                    "code": "group_not_found",
+                    "message": "Group not found: payments",
+                    "details": { "groupId": "payments" },
+                }),
+            ),
+            (
+                AppError::ViewNotFound("overview".to_string()),
+                json!({
+                    "code": "view_not_found",
+                    "message": "View not found: overview",
+                    "details": { "viewName": "overview" },
+                }),
+            ),
+            (
+                AppError::CustomTypeNotFound("lambda".to_string()),
+                json!({
+                    "code": "custom_type_not_found",
+                    "message": "Custom type not found: lambda",
+                    "details": { "name": "lambda" },
+                }),
+            ),
+            (
+                AppError::SnapshotNotFound("snap-1".to_string()),
+                json!({
+                    "code": "snapshot_not_found",
+                    "message": "Snapshot not found: snap-1",
+                    "details": { "snapshotId": "snap-1" },
+                }),
+            ),
+            (
+                AppError::Conflict {
+                    id: "api".to_string(),
+                    expected_revision: 1,
+                    actual_revision: 2,
+                },
+                json!({
+                    "code": "conflict",
+                    "message": "Conflict: 'api' was changed elsewhere (expected revision 1, found 2)",
+                    "details": { "id": "api", "expectedRevision": 1, "actualRevision": 2 },
+                }),
+            ),
+            (
+                AppError::BatchOperationFailed {
+                    index: 2,
+                    source: Box::new(AppError::ServiceNotFound("api".to_string())),
+                },
+                json!({
+                    "code": "batch_operation_failed",
+                    "message": "Batch operation 2 failed: Service not found: api",
+                    "details": {
+                        "index": 2,
+                        "cause": {
+                            "code": "service_not_found",
+                            "message": "Service not found: api",
+                            "details": { "serviceId": "api" },
+                        },
+                    },
+                }),
+            ),
+            (
+                AppError::OperationCancelled {
+                    operation_id: "op-1".to_string(),
+                    phase: "Loading".to_string(),
+                    done: 3,
+                    total: 10,
+                },
+                json!({
+                    "code": "operation_cancelled",
+                    "message": "Operation cancelled: op-1 (Loading, 3 of 10 done)",
+                    "details": { "operationId": "op-1", "phase": "Loading", "done": 3, "total": 10 },
+                }),
+            ),
+            (
+                AppError::ReadOnlyMode("save_service".to_string()),
+                json!({
+                    "code": "read_only_mode",
+                    "message": "Read-only mode is on; 'save_service' is disabled",
+                    "details": { "command": "save_service" },
+                }),
+            ),
+            (
+                AppError::ValidationError("bad".to_string()),
+                json!({
+                    "code": "validation_error",
+                    "message": "Validation error: bad",
+                    "details": { "reason": "bad" },
+                }),
+            ),
+        ]
+    }
+
+    /// Returns the position of a variant in `payloads`.
+    ///
+    /// There is deliberately no wildcard arm: a new variant doesn't compile
+    /// until it's added here and to `payloads`.
+    fn position(error: &AppError) -> usize {
+        match error {
+            AppError::Io(_) => 0,
+            AppError::Json(_) => 1,
+            AppError::FileLoad { .. } => 2,
+            AppError::Database(_) => 3,
+            AppError::Git(_) => 4,
+            AppError::WebhookFailed { .. } => 5,
+            AppError::ClusterAuthFailed(_) => 6,
+            AppError::ClusterRequestFailed(_) => 7,
+            AppError::RegistryAuthFailed(_) => 8,
+            AppError::RegistryTimeout(_) => 9,
+            AppError::RegistryRequestFailed { .. } => 10,
+            AppError::GitHubRateLimited(_) => 11,
+            AppError::GitHubRequestFailed { .. } => 12,
+            AppError::EncryptionRequired(_) => 13,
+            AppError::WrongPassphrase(_) => 14,
+            AppError::ServiceNotFound(_) => 15,
+            AppError::EnvironmentNotFound(_) => 16,
+            AppError::WorkspaceNotFound(_) => 17,
+            AppError::EnvironmentExists(_) => 18,
+            AppError::InvalidPath(_) => 19,
+            AppError::RelationshipNotFound(_) => 20,
+            AppError::DuplicateRelationship(_, _) => 21,
+            AppError::GroupNotFound(_) => 22,
+            AppError::ViewNotFound(_) => 23,
+            AppError::CustomTypeNotFound(_) => 24,
+            AppError::SnapshotNotFound(_) => 25,
+            AppError::Conflict { .. } => 26,
+            AppError::BatchOperationFailed { .. } => 27,
+            AppError::OperationCancelled { .. } => 28,
+            AppError::ReadOnlyMode(_) => 29,
+            AppError::ValidationError(_) => 30,
+        }
+    }
+
+    #[test]
+    fn every_variant_serializes_to_code_message_and_details() {
+        let payloads = payloads();
+        for (index, (error, expected)) in payloads.iter().enumerate() {
+            assert_eq!(position(error), index, "payloads are out of order");
+            assert_eq!(serde_json::to_value(error).unwrap(), *expected);
+        }
+        assert_eq!(payloads.len(), 31, "every variant needs a payload");
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        let mut codes: Vec<&str> = payloads().iter().map(|(error, _)| error.code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), payloads().len());
+    }
+
+    #[test]
+    fn stale_revisions_conflict() {
+        assert!(AppError::check_revision("api", 3, 3).is_ok());
+        assert!(matches!(
+            AppError::check_revision("api", 2, 3),
+            Err(AppError::Conflict {
+                expected_revision: 2,
+                actual_revision: 3,
+                ..
+            })
+        ));
     }
 }
@@ -0,0 +1,984 @@
+//! Rendering of an environment's dependency graph for other tools.
+//!
+//! The whole environment is rendered, every service as a node and every
+//! relationship as an edge, as JSON, Graphviz DOT or a Mermaid flowchart.
+//! Nodes and edges are sorted by ID so exports of unchanged data are
+//! identical and diff cleanly in version control. Relationships whose
+//! endpoints don't exist are still drawn, with the missing endpoint labeled
+//! by its ID.
+//!
+//! The graph can also be rendered as the `nodes` and `edges` tables of
+//! Grafana's node graph panel, as CSV files or one JSON document.
+//!
+//! DOT and Mermaid exports can draw the relationships between the same two
+//! services as one edge listing their types (see `edges`).
+//!
+//! DOT, Mermaid and node graph exports are colored with the display config
+//! (see `models::DisplayConfig`), the same palette as the graph view.
+//!
+//! `render_d2` renders any part of the graph as a D2 diagram, optionally
+//! with the services of each team in a container.
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::edges::aggregate_edges;
+use crate::error::AppError;
+use crate::models::{DisplayConfig, LineStyle, Relationship, Service, ServiceStatus, ServiceType};
+
+/// Formats a graph can be exported in.
+///
+/// # Variants
+///
+/// * `Json` - Services and relationships as `{ "services": [], "relationships": [] }`
+/// * `Dot` - A Graphviz `digraph`
+/// * `Mermaid` - A Mermaid `flowchart LR`, e.g. for Markdown documentation
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphExportFormat {
+    Json,
+    Dot,
+    Mermaid,
+}
+
+impl GraphExportFormat {
+    /// Parses a format as it is serialized (e.g., `"mermaid"`).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(Self::Json),
+            "dot" => Some(Self::Dot),
+            "mermaid" => Some(Self::Mermaid),
+            _ => None,
+        }
+    }
+}
+
+/// Columns of the Grafana node graph `nodes` table, in order.
+pub const GRAFANA_NODE_COLUMNS: &[&str] = &[
+    "id",
+    "title",
+    "subTitle",
+    "mainStat",
+    "arc__healthy",
+    "arc__degraded",
+    "arc__unhealthy",
+    "icon",
+];
+
+/// Columns of the Grafana node graph `edges` table, in order.
+pub const GRAFANA_EDGE_COLUMNS: &[&str] = &[
+    "id",
+    "source",
+    "target",
+    "mainStat",
+    "color",
+    "strokeDasharray",
+];
+
+/// Formats a Grafana node graph can be exported in.
+///
+/// # Variants
+///
+/// * `Csv` - `nodes.csv` and `edges.csv`, e.g. for the CSV or Infinity datasource
+/// * `Json` - One `nodegraph.json` with `{ "nodes": [], "edges": [] }`, as the
+///   Node Graph API datasource serves it
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GrafanaExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+/// A node of Grafana's node graph panel.
+///
+/// The arc fields are the fractions of the node's circle drawn in each
+/// status color; a service has exactly one status, so one of them is 1 and
+/// the others 0 (all 0 for unknown and deprecated services). The icon is
+/// the service type's icon from the display config, empty if it has none.
+#[derive(Debug, Clone, Serialize)]
+pub struct GrafanaNode {
+    pub id: String,
+    pub title: String,
+    #[serde(rename = "subTitle")]
+    pub sub_title: String,
+    #[serde(rename = "mainStat")]
+    pub main_stat: String,
+    #[serde(rename = "arc__healthy")]
+    pub arc_healthy: u8,
+    #[serde(rename = "arc__degraded")]
+    pub arc_degraded: u8,
+    #[serde(rename = "arc__unhealthy")]
+    pub arc_unhealthy: u8,
+    pub icon: String,
+}
+
+/// An edge of Grafana's node graph panel, colored and dashed like its
+/// relationship type in the display config.
+#[derive(Debug, Clone, Serialize)]
+pub struct GrafanaEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    #[serde(rename = "mainStat")]
+    pub main_stat: String,
+    pub color: String,
+    #[serde(rename = "strokeDasharray")]
+    pub stroke_dasharray: String,
+}
+
+/// The tables of Grafana's node graph panel, sorted by ID.
+///
+/// # Fields
+///
+/// * `nodes` - One node per service, plus one per missing relationship endpoint
+/// * `edges` - One edge per relationship, labeled with its type
+#[derive(Debug, Clone, Serialize)]
+pub struct GrafanaNodeGraph {
+    pub nodes: Vec<GrafanaNode>,
+    pub edges: Vec<GrafanaEdge>,
+}
+
+impl GrafanaNodeGraph {
+    /// Renders the `nodes` table as CSV, with a header row.
+    pub fn nodes_csv(&self) -> String {
+        let mut csv = csv_row(GRAFANA_NODE_COLUMNS.iter().copied());
+        for node in &self.nodes {
+            let [healthy, degraded, unhealthy] =
+                [node.arc_healthy, node.arc_degraded, node.arc_unhealthy]
+                    .map(|arc| arc.to_string());
+            csv.push_str(&csv_row([
+                node.id.as_str(),
+                node.title.as_str(),
+                node.sub_title.as_str(),
+                node.main_stat.as_str(),
+                healthy.as_str(),
+                degraded.as_str(),
+                unhealthy.as_str(),
+                node.icon.as_str(),
+            ]));
+        }
+        csv
+    }
+
+    /// Renders the `edges` table as CSV, with a header row.
+    pub fn edges_csv(&self) -> String {
+        let mut csv = csv_row(GRAFANA_EDGE_COLUMNS.iter().copied());
+        for edge in &self.edges {
+            csv.push_str(&csv_row([
+                edge.id.as_str(),
+                edge.source.as_str(),
+                edge.target.as_str(),
+                edge.main_stat.as_str(),
+                edge.color.as_str(),
+                edge.stroke_dasharray.as_str(),
+            ]));
+        }
+        csv
+    }
+}
+
+/// Contents of a JSON graph export.
+#[derive(Serialize)]
+struct JsonGraph<'a> {
+    services: Vec<&'a Service>,
+    relationships: Vec<&'a Relationship>,
+}
+
+/// Renders an environment's graph.
+///
+/// # Arguments
+///
+/// * `environment` - The name of the environment, used as the graph's name
+/// * `services` - All services of the environment
+/// * `relationships` - All relationships of the environment
+/// * `format` - The format to render in
+/// * `aggregate` - If true, DOT and Mermaid draw one edge per source and target,
+///   labeled with the types and count; JSON always lists every relationship
+/// * `display` - The colors and line styles DOT and Mermaid draw with
+///
+/// # Returns
+///
+/// * `Ok(String)` - The rendered graph
+/// * `Err(AppError::Json)` - If the JSON export cannot be serialized
+pub fn render_graph<S: Borrow<Service>>(
+    environment: &str,
+    services: &[S],
+    relationships: &[Relationship],
+    format: GraphExportFormat,
+    aggregate: bool,
+    display: &DisplayConfig,
+) -> Result<String, AppError> {
+    let mut services: Vec<&Service> = services.iter().map(Borrow::borrow).collect();
+    services.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut relationships: Vec<&Relationship> = relationships.iter().collect();
+    relationships.sort_by(|a, b| a.id.cmp(&b.id));
+
+    match format {
+        GraphExportFormat::Json => Ok(serde_json::to_string_pretty(&JsonGraph {
+            services,
+            relationships,
+        })?),
+        GraphExportFormat::Dot => Ok(render_dot(
+            environment,
+            &services,
+            &drawn_edges(&relationships, aggregate, display),
+            display,
+        )),
+        GraphExportFormat::Mermaid => Ok(render_mermaid(
+            &services,
+            &drawn_edges(&relationships, aggregate, display),
+            display,
+        )),
+    }
+}
+
+/// An edge as DOT and Mermaid draw it.
+struct Edge {
+    source: String,
+    target: String,
+    label: String,
+    color: String,
+    line_style: LineStyle,
+}
+
+/// Returns the edges to draw: one per relationship labeled with its type,
+/// or one per source and target if `aggregate` is set.
+///
+/// Edges are styled like their relationship type; aggregated edges like
+/// the first of their types.
+fn drawn_edges(
+    relationships: &[&Relationship],
+    aggregate: bool,
+    display: &DisplayConfig,
+) -> Vec<Edge> {
+    if aggregate {
+        aggregate_edges(relationships)
+            .into_iter()
+            .map(|edge| {
+                // Every aggregated edge has at least one type
+                let style = display.relationship_type_style(&edge.relationship_types[0]);
+                Edge {
+                    label: edge.label(),
+                    source: edge.source,
+                    target: edge.target,
+                    color: style.color,
+                    line_style: style.line_style,
+                }
+            })
+            .collect()
+    } else {
+        relationships
+            .iter()
+            .map(|relationship| {
+                let style = display.relationship_type_style(&relationship.relationship_type);
+                Edge {
+                    source: relationship.source.clone(),
+                    target: relationship.target.clone(),
+                    label: relationship.relationship_type.as_str().to_string(),
+                    color: style.color,
+                    line_style: style.line_style,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Renders a Graphviz digraph, using the service IDs as node IDs.
+///
+/// Nodes are filled with their type's color and outlined in their status's.
+fn render_dot(
+    environment: &str,
+    services: &[&Service],
+    edges: &[Edge],
+    display: &DisplayConfig,
+) -> String {
+    let mut dot = format!("digraph {} {{\n    rankdir=LR;\n", dot_string(environment));
+    for service in services {
+        dot.push_str(&format!(
+            "    {} [label={}, style=filled, fillcolor={}, color={}, penwidth=2];\n",
+            dot_string(&service.id),
+            dot_string(&service.name),
+            dot_string(&display.service_type_style(&service.service_type).color),
+            dot_string(&display.status_style(&service.status).color)
+        ));
+    }
+    for edge in edges {
+        dot.push_str(&format!(
+            "    {} -> {} [label={}, color={}, style={}];\n",
+            dot_string(&edge.source),
+            dot_string(&edge.target),
+            dot_string(&edge.label),
+            dot_string(&edge.color),
+            edge.line_style.as_str()
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Quotes a value as a DOT string.
+fn dot_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Renders a Mermaid flowchart.
+///
+/// Mermaid node IDs can't contain most punctuation, so nodes are numbered
+/// (`n0`, `n1`, ...) in ID order and labeled with the service name. Each
+/// service type and status gets a class (`type_api`, `status_healthy`)
+/// filling or outlining its nodes, and each edge a `linkStyle`.
+fn render_mermaid(services: &[&Service], edges: &[Edge], display: &DisplayConfig) -> String {
+    let mut node_ids: HashMap<&str, String> = HashMap::new();
+    let mut classes: BTreeMap<String, String> = BTreeMap::new();
+    let mut assignments = String::new();
+    let mut mermaid = String::from("flowchart LR\n");
+
+    for (index, service) in services.iter().enumerate() {
+        let node_id = format!("n{}", index);
+        mermaid.push_str(&format!(
+            "    {}[\"{}\"]\n",
+            node_id,
+            mermaid_text(&service.name)
+        ));
+
+        let type_class = mermaid_class("type", service.service_type.as_str());
+        let status_class = mermaid_class("status", service.status.as_str());
+        classes.entry(type_class.clone()).or_insert_with(|| {
+            let color = display.service_type_style(&service.service_type).color;
+            format!("fill:{}", color)
+        });
+        classes.entry(status_class.clone()).or_insert_with(|| {
+            let color = display.status_style(&service.status).color;
+            format!("stroke:{},stroke-width:2px", color)
+        });
+        assignments.push_str(&format!("    class {} {}\n", node_id, type_class));
+        assignments.push_str(&format!("    class {} {}\n", node_id, status_class));
+
+        node_ids.insert(service.id.as_str(), node_id);
+    }
+
+    for edge in edges {
+        for id in [&edge.source, &edge.target] {
+            if !node_ids.contains_key(id.as_str()) {
+                let node_id = format!("n{}", services.len() + node_ids.len());
+                mermaid.push_str(&format!("    {}[\"{}\"]\n", node_id, mermaid_text(id)));
+                node_ids.insert(id.as_str(), node_id);
+            }
+        }
+    }
+
+    for edge in edges {
+        mermaid.push_str(&format!(
+            "    {} -->|{}| {}\n",
+            node_ids[edge.source.as_str()],
+            mermaid_text(&edge.label),
+            node_ids[edge.target.as_str()]
+        ));
+    }
+
+    for (class, style) in &classes {
+        mermaid.push_str(&format!("    classDef {} {}\n", class, style));
+    }
+    mermaid.push_str(&assignments);
+    for (index, edge) in edges.iter().enumerate() {
+        let dash_array = edge.line_style.dash_array();
+        if dash_array.is_empty() {
+            mermaid.push_str(&format!("    linkStyle {} stroke:{}\n", index, edge.color));
+        } else {
+            mermaid.push_str(&format!(
+                "    linkStyle {} stroke:{},stroke-dasharray:{}\n",
+                index, edge.color, dash_array
+            ));
+        }
+    }
+
+    mermaid
+}
+
+/// Returns a Mermaid class name for a type or status, replacing characters
+/// class names can't contain with `_`.
+fn mermaid_class(prefix: &str, name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_{}", prefix, name)
+}
+
+/// Escapes text for a quoted Mermaid label.
+fn mermaid_text(value: &str) -> String {
+    value
+        .replace('"', "#quot;")
+        .replace('|', "#124;")
+        .replace('\n', " ")
+}
+
+/// Renders a D2 diagram.
+///
+/// D2 keys can't contain dots, which nest shapes, and some words such as
+/// `label` or `shape` are reserved, so each service gets the key `svc_`
+/// followed by its ID with every character but ASCII letters and digits
+/// replaced by `_`, numbered (`_2`, `_3`, ...) if two IDs map to the same
+/// key. Shapes are labeled with the service name and carry the ID as
+/// tooltip. Databases are drawn as cylinders, queues as queues and any other
+/// service as a rectangle. Each service type, status and relationship type
+/// gets a class (`type_api`, `status_healthy`, `rel_depends_on`) styled
+/// from the display config, and each edge is labeled with its relationship
+/// type. Relationship endpoints that aren't among `services` are drawn
+/// labeled by their ID.
+///
+/// # Arguments
+///
+/// * `services` - The services to draw
+/// * `relationships` - The relationships to draw
+/// * `group_by_team` - If true, the services of each team are drawn in a container
+///   labeled with the team; services without a team stay at the top level
+/// * `display` - The colors and line styles to draw with
+///
+/// # Returns
+///
+/// The diagram, with shapes and edges sorted by ID.
+pub fn render_d2<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    group_by_team: bool,
+    display: &DisplayConfig,
+) -> String {
+    let mut services: Vec<&Service> = services.iter().map(Borrow::borrow).collect();
+    services.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut relationships: Vec<&Relationship> = relationships.iter().collect();
+    relationships.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut top_level: Vec<&Service> = Vec::new();
+    let mut teams: BTreeMap<&str, Vec<&Service>> = BTreeMap::new();
+    for service in services {
+        let team = service.team.as_deref().map(str::trim);
+        match team.filter(|team| group_by_team && !team.is_empty()) {
+            Some(team) => teams.entry(team).or_default().push(service),
+            None => top_level.push(service),
+        }
+    }
+
+    let mut keys = D2Keys::default();
+    let mut paths: HashMap<&str, String> = HashMap::new();
+    let mut classes: BTreeMap<String, String> = BTreeMap::new();
+    let mut shapes = String::new();
+    for service in top_level {
+        let key = keys.key("svc", &service.id);
+        shapes.push_str(&d2_shape(&key, service, "", &mut classes, display));
+        paths.insert(service.id.as_str(), key);
+    }
+    for (team, members) in teams {
+        let container = keys.key("team", team);
+        shapes.push_str(&format!("{}: {} {{\n", container, d2_string(team)));
+        for service in members {
+            let key = keys.key("svc", &service.id);
+            shapes.push_str(&d2_shape(&key, service, "  ", &mut classes, display));
+            paths.insert(service.id.as_str(), format!("{}.{}", container, key));
+        }
+        shapes.push_str("}\n");
+    }
+    for relationship in &relationships {
+        for id in [&relationship.source, &relationship.target] {
+            if !paths.contains_key(id.as_str()) {
+                let key = keys.key("svc", id);
+                shapes.push_str(&format!("{}: {}\n", key, d2_string(id)));
+                paths.insert(id.as_str(), key);
+            }
+        }
+    }
+
+    let mut edges = String::new();
+    for relationship in &relationships {
+        let relationship_type = &relationship.relationship_type;
+        let class = mermaid_class("rel", relationship_type.as_str());
+        classes.entry(class.clone()).or_insert_with(|| {
+            let style = display.relationship_type_style(relationship_type);
+            match style.line_style {
+                LineStyle::Solid => format!("style.stroke: {}", d2_string(&style.color)),
+                LineStyle::Dashed => format!(
+                    "style.stroke: {}\n    style.stroke-dash: 5",
+                    d2_string(&style.color)
+                ),
+                LineStyle::Dotted => format!(
+                    "style.stroke: {}\n    style.stroke-dash: 2",
+                    d2_string(&style.color)
+                ),
+            }
+        });
+        edges.push_str(&format!(
+            "{} -> {}: {} {{class: {}}}\n",
+            paths[relationship.source.as_str()],
+            paths[relationship.target.as_str()],
+            d2_string(relationship_type.as_str()),
+            class
+        ));
+    }
+
+    let mut d2 = String::from("direction: right\n");
+    if !classes.is_empty() {
+        d2.push_str("\nclasses: {\n");
+        for (class, style) in &classes {
+            d2.push_str(&format!("  {}: {{\n    {}\n  }}\n", class, style));
+        }
+        d2.push_str("}\n");
+    }
+    if !shapes.is_empty() {
+        d2.push('\n');
+        d2.push_str(&shapes);
+    }
+    if !edges.is_empty() {
+        d2.push('\n');
+        d2.push_str(&edges);
+    }
+    d2
+}
+
+/// Hands out D2 keys, numbering keys that are already taken.
+#[derive(Default)]
+struct D2Keys {
+    taken: HashSet<String>,
+}
+
+impl D2Keys {
+    /// Returns an unused key made of a prefix and a sanitized ID.
+    fn key(&mut self, prefix: &str, id: &str) -> String {
+        let base = mermaid_class(prefix, id);
+        let mut key = base.clone();
+        let mut number = 2;
+        while !self.taken.insert(key.clone()) {
+            key = format!("{}_{}", base, number);
+            number += 1;
+        }
+        key
+    }
+}
+
+/// Renders the shape of a service, adding its type and status classes.
+fn d2_shape(
+    key: &str,
+    service: &Service,
+    indent: &str,
+    classes: &mut BTreeMap<String, String>,
+    display: &DisplayConfig,
+) -> String {
+    let type_class = mermaid_class("type", service.service_type.as_str());
+    let status_class = mermaid_class("status", service.status.as_str());
+    classes.entry(type_class.clone()).or_insert_with(|| {
+        let color = display.service_type_style(&service.service_type).color;
+        format!("style.fill: {}", d2_string(&color))
+    });
+    classes.entry(status_class.clone()).or_insert_with(|| {
+        let color = display.status_style(&service.status).color;
+        format!(
+            "style.stroke: {}\n    style.stroke-width: 2",
+            d2_string(&color)
+        )
+    });
+    let shape = match service.service_type {
+        ServiceType::Database => "cylinder",
+        ServiceType::Queue => "queue",
+        _ => "rectangle",
+    };
+
+    let mut d2 = format!("{}{}: {} {{\n", indent, key, d2_string(&service.name));
+    d2.push_str(&format!("{}  shape: {}\n", indent, shape));
+    d2.push_str(&format!(
+        "{}  tooltip: {}\n",
+        indent,
+        d2_string(&service.id)
+    ));
+    d2.push_str(&format!(
+        "{}  class: [{}; {}]\n",
+        indent, type_class, status_class
+    ));
+    d2.push_str(&format!("{}}}\n", indent));
+    d2
+}
+
+/// Quotes a value as a D2 string, replacing line breaks with spaces and
+/// escaping `$` so it isn't substituted.
+fn d2_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('$', "\\$")
+        .replace('\n', " ");
+    format!("\"{}\"", escaped)
+}
+
+/// Builds the tables of Grafana's node graph panel for an environment.
+///
+/// Nodes are titled with the service name, subtitled with the service type
+/// and show the status as main stat and arc. Relationship endpoints that
+/// don't exist get a node titled by their ID, so every edge can be drawn.
+/// Icons, edge colors and dashes come from the display config.
+pub fn grafana_nodegraph<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    display: &DisplayConfig,
+) -> GrafanaNodeGraph {
+    let mut nodes: Vec<GrafanaNode> = services
+        .iter()
+        .map(|service| grafana_node(service.borrow(), display))
+        .collect();
+    let mut node_ids: HashSet<String> = nodes.iter().map(|node| node.id.clone()).collect();
+    for relationship in relationships {
+        for id in [&relationship.source, &relationship.target] {
+            if node_ids.insert(id.clone()) {
+                nodes.push(GrafanaNode {
+                    id: id.clone(),
+                    title: id.clone(),
+                    sub_title: "missing".to_string(),
+                    main_stat: ServiceStatus::Unknown.as_str().to_string(),
+                    arc_healthy: 0,
+                    arc_degraded: 0,
+                    arc_unhealthy: 0,
+                    icon: String::new(),
+                });
+            }
+        }
+    }
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut edges: Vec<GrafanaEdge> = relationships
+        .iter()
+        .map(|relationship| {
+            let style = display.relationship_type_style(&relationship.relationship_type);
+            GrafanaEdge {
+                id: relationship.id.clone(),
+                source: relationship.source.clone(),
+                target: relationship.target.clone(),
+                main_stat: relationship.relationship_type.as_str().to_string(),
+                color: style.color,
+                stroke_dasharray: style.line_style.dash_array().to_string(),
+            }
+        })
+        .collect();
+    edges.sort_by(|a, b| a.id.cmp(&b.id));
+
+    GrafanaNodeGraph { nodes, edges }
+}
+
+/// Builds the node graph node of a service.
+fn grafana_node(service: &Service, display: &DisplayConfig) -> GrafanaNode {
+    let arc = |status: ServiceStatus| u8::from(service.status == status);
+    GrafanaNode {
+        id: service.id.clone(),
+        title: service.name.clone(),
+        sub_title: service.service_type.as_str().to_string(),
+        main_stat: service.status.as_str().to_string(),
+        arc_healthy: arc(ServiceStatus::Healthy),
+        arc_degraded: arc(ServiceStatus::Degraded),
+        arc_unhealthy: arc(ServiceStatus::Unhealthy),
+        icon: display
+            .service_type_style(&service.service_type)
+            .icon
+            .unwrap_or_default(),
+    }
+}
+
+/// Formats one CSV line, quoting fields that contain a separator, quote or newline.
+pub fn csv_row<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RelationshipType;
+    use crate::test_support::{relationship, service};
+
+    fn with_status(id: &str, status: ServiceStatus) -> Service {
+        let mut service = service(id);
+        service.name = format!("{} service", id);
+        service.status = status;
+        service
+    }
+
+    fn sample_graph() -> GrafanaNodeGraph {
+        let services = [
+            with_status("api", ServiceStatus::Healthy),
+            with_status("cache", ServiceStatus::Degraded),
+            with_status("db", ServiceStatus::Unhealthy),
+            with_status("old", ServiceStatus::Deprecated),
+        ];
+        let relationships = [
+            relationship("api-cache", "api", "cache"),
+            relationship("api-db", "api", "db"),
+            relationship("api-ghost", "api", "ghost"),
+        ];
+        grafana_nodegraph(&services, &relationships, &DisplayConfig::default())
+    }
+
+    /// Splits CSV lines into fields; the sample data needs no quoting.
+    fn rows(csv: &str) -> Vec<Vec<&str>> {
+        csv.lines().map(|line| line.split(',').collect()).collect()
+    }
+
+    #[test]
+    fn csv_tables_have_the_exact_headers_grafana_expects() {
+        let graph = sample_graph();
+
+        let nodes = graph.nodes_csv();
+        let edges = graph.edges_csv();
+
+        assert_eq!(
+            nodes.lines().next(),
+            Some("id,title,subTitle,mainStat,arc__healthy,arc__degraded,arc__unhealthy,icon")
+        );
+        assert_eq!(
+            edges.lines().next(),
+            Some("id,source,target,mainStat,color,strokeDasharray")
+        );
+    }
+
+    #[test]
+    fn statuses_become_arc_fractions() {
+        let nodes = sample_graph().nodes_csv();
+        let arcs: Vec<(&str, &str, [&str; 3])> = rows(&nodes)[1..]
+            .iter()
+            .map(|row| (row[0], row[3], [row[4], row[5], row[6]]))
+            .collect();
+
+        assert_eq!(
+            arcs,
+            [
+                ("api", "healthy", ["1", "0", "0"]),
+                ("cache", "degraded", ["0", "1", "0"]),
+                ("db", "unhealthy", ["0", "0", "1"]),
+                ("ghost", "unknown", ["0", "0", "0"]),
+                ("old", "deprecated", ["0", "0", "0"]),
+            ]
+        );
+        let api = &rows(&nodes)[1];
+        assert_eq!(&api[1..3], ["api service", "backend"]);
+    }
+
+    #[test]
+    fn edges_are_labeled_with_their_type() {
+        let edges = sample_graph().edges_csv();
+        let edges: Vec<(&str, &str, &str, &str)> = rows(&edges)[1..]
+            .iter()
+            .map(|row| (row[0], row[1], row[2], row[3]))
+            .collect();
+
+        assert_eq!(
+            edges,
+            [
+                ("api-cache", "api", "cache", "depends_on"),
+                ("api-db", "api", "db", "depends_on"),
+                ("api-ghost", "api", "ghost", "depends_on"),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_uses_the_same_field_names_as_the_csv_headers() {
+        let json = serde_json::to_value(sample_graph()).unwrap();
+
+        let node_keys: Vec<&String> = json["nodes"][0].as_object().unwrap().keys().collect();
+        let edge_keys: Vec<&String> = json["edges"][0].as_object().unwrap().keys().collect();
+        let sorted = |columns: &[&str]| {
+            let mut columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+            columns.sort();
+            columns
+        };
+        assert_eq!(
+            node_keys.into_iter().cloned().collect::<Vec<_>>(),
+            sorted(GRAFANA_NODE_COLUMNS)
+        );
+        assert_eq!(
+            edge_keys.into_iter().cloned().collect::<Vec<_>>(),
+            sorted(GRAFANA_EDGE_COLUMNS)
+        );
+        assert_eq!(json["nodes"][0]["arc__healthy"], 1);
+    }
+
+    /// A database, a queue and a plain service with awkward IDs and names,
+    /// relationships between them, and one to a service that doesn't exist.
+    fn d2_sample() -> (Vec<Service>, Vec<Relationship>) {
+        let mut db = service("orders.db");
+        db.service_type = ServiceType::Database;
+        db.name = "Orders \"main\" $DB".to_string();
+        db.team = Some("Payments".to_string());
+        let mut queue = service("orders/queue");
+        queue.service_type = ServiceType::Queue;
+        queue.team = Some(" Payments ".to_string());
+        let mut label = service("label");
+        label.name = "Two\nlines".to_string();
+        let mut reads = relationship("r2", "label", "orders.db");
+        reads.relationship_type = RelationshipType::ReadsFrom;
+        (
+            vec![db, queue, label, service("orders_db")],
+            vec![
+                relationship("r1", "label", "orders/queue"),
+                reads,
+                relationship("r3", "orders_db", "gone.away"),
+            ],
+        )
+    }
+
+    const D2_CLASSES: &str = "direction: right
+
+classes: {
+  rel_depends_on: {
+    style.stroke: \"#E53E3E\"
+  }
+  rel_reads_from: {
+    style.stroke: \"#38A169\"
+  }
+  status_healthy: {
+    style.stroke: \"#48BB78\"
+    style.stroke-width: 2
+  }
+  type_backend: {
+    style.fill: \"#38A169\"
+  }
+  type_database: {
+    style.fill: \"#DD6B20\"
+  }
+  type_queue: {
+    style.fill: \"#D69E2E\"
+  }
+}
+";
+
+    #[test]
+    fn d2_keys_are_sanitized_and_labels_keep_the_original() {
+        let (services, relationships) = d2_sample();
+
+        let d2 = render_d2(&services, &relationships, false, &DisplayConfig::default());
+
+        // "orders.db" and "orders_db" both sanitize to orders_db; the
+        // second in ID order is numbered
+        let expected = format!(
+            "{D2_CLASSES}
+svc_label: \"Two lines\" {{
+  shape: rectangle
+  tooltip: \"label\"
+  class: [type_backend; status_healthy]
+}}
+svc_orders_db: \"Orders \\\"main\\\" \\$DB\" {{
+  shape: cylinder
+  tooltip: \"orders.db\"
+  class: [type_database; status_healthy]
+}}
+svc_orders_queue: \"orders/queue\" {{
+  shape: queue
+  tooltip: \"orders/queue\"
+  class: [type_queue; status_healthy]
+}}
+svc_orders_db_2: \"orders_db\" {{
+  shape: rectangle
+  tooltip: \"orders_db\"
+  class: [type_backend; status_healthy]
+}}
+svc_gone_away: \"gone.away\"
+
+svc_label -> svc_orders_queue: \"depends_on\" {{class: rel_depends_on}}
+svc_label -> svc_orders_db: \"reads_from\" {{class: rel_reads_from}}
+svc_orders_db_2 -> svc_gone_away: \"depends_on\" {{class: rel_depends_on}}
+"
+        );
+        assert_eq!(d2, expected);
+        assert!(!d2
+            .lines()
+            .filter_map(|line| line.split(':').next())
+            .any(|key| key.contains("orders.db") || key.contains("gone.away")));
+    }
+
+    #[test]
+    fn d2_groups_teams_into_containers() {
+        let (services, relationships) = d2_sample();
+
+        let d2 = render_d2(&services, &relationships, true, &DisplayConfig::default());
+
+        // Team names are trimmed, so both Payments services share a container
+        let expected = format!(
+            "{D2_CLASSES}
+svc_label: \"Two lines\" {{
+  shape: rectangle
+  tooltip: \"label\"
+  class: [type_backend; status_healthy]
+}}
+svc_orders_db: \"orders_db\" {{
+  shape: rectangle
+  tooltip: \"orders_db\"
+  class: [type_backend; status_healthy]
+}}
+team_Payments: \"Payments\" {{
+  svc_orders_db_2: \"Orders \\\"main\\\" \\$DB\" {{
+    shape: cylinder
+    tooltip: \"orders.db\"
+    class: [type_database; status_healthy]
+  }}
+  svc_orders_queue: \"orders/queue\" {{
+    shape: queue
+    tooltip: \"orders/queue\"
+    class: [type_queue; status_healthy]
+  }}
+}}
+svc_gone_away: \"gone.away\"
+
+svc_label -> team_Payments.svc_orders_queue: \"depends_on\" {{class: rel_depends_on}}
+svc_label -> team_Payments.svc_orders_db_2: \"reads_from\" {{class: rel_reads_from}}
+svc_orders_db -> svc_gone_away: \"depends_on\" {{class: rel_depends_on}}
+"
+        );
+        assert_eq!(d2, expected);
+    }
+
+    #[test]
+    fn d2_styles_come_from_the_display_config() {
+        let mut display = DisplayConfig::default();
+        let depends_on = display.relationship_types.get_mut("depends_on").unwrap();
+        depends_on.color = "#123456".to_string();
+        depends_on.line_style = LineStyle::Dashed;
+        let reads_from = display.relationship_types.get_mut("reads_from").unwrap();
+        reads_from.line_style = LineStyle::Dotted;
+        display.service_types.get_mut("database").unwrap().color = "#ABCDEF".to_string();
+        let (services, relationships) = d2_sample();
+
+        let d2 = render_d2(&services, &relationships, false, &display);
+
+        assert!(d2.contains(
+            "  rel_depends_on: {\n    style.stroke: \"#123456\"\n    style.stroke-dash: 5\n  }\n"
+        ));
+        assert!(d2.contains(
+            "  rel_reads_from: {\n    style.stroke: \"#38A169\"\n    style.stroke-dash: 2\n  }\n"
+        ));
+        assert!(d2.contains("  type_database: {\n    style.fill: \"#ABCDEF\"\n  }\n"));
+    }
+
+    #[test]
+    fn d2_of_nothing_is_only_the_direction() {
+        let services: [Service; 0] = [];
+        assert_eq!(
+            render_d2(&services, &[], true, &DisplayConfig::default()),
+            "direction: right\n"
+        );
+    }
+
+    #[test]
+    fn d2_strings_escape_quotes_backslashes_dollars_and_line_breaks() {
+        assert_eq!(d2_string("plain"), "\"plain\"");
+        assert_eq!(d2_string("a \"b\""), "\"a \\\"b\\\"\"");
+        assert_eq!(d2_string("C:\\data"), "\"C:\\\\data\"");
+        assert_eq!(d2_string("${HOME}"), "\"\\${HOME}\"");
+        assert_eq!(d2_string("one\ntwo"), "\"one two\"");
+        assert_eq!(d2_string("a.b: {c}"), "\"a.b: {c}\"");
+    }
+}
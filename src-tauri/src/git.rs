@@ -0,0 +1,529 @@
+//! Optional git integration for the data directory.
+//!
+//! When the `gitAutoCommit` setting is enabled and the data directory lives
+//! inside a git repository, every successful write command stages the files
+//! it changed and commits them, giving an audit trail without anyone having
+//! to remember to commit.
+//!
+//! # Background Commits
+//!
+//! Commits run on a single background thread (`GitCommitter`) so a slow
+//! repository never delays a command response, and commits are created in
+//! the order the writes happened. Failures are reported to the frontend via
+//! the `git-commit-failed` event. A data directory that isn't in a git
+//! repository is skipped with a logged warning.
+
+use git2::{ErrorCode, Repository, Signature, StatusOptions};
+use serde::Serialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::state::AppState;
+use crate::storage::audit;
+
+/// Name of the event emitted to the frontend when an auto-commit fails.
+pub const GIT_COMMIT_FAILED_EVENT: &str = "git-commit-failed";
+
+/// Author name used when the repository has no `user.name` configured.
+const FALLBACK_AUTHOR_NAME: &str = "Dependency Mapping Tool";
+
+/// Author email used when the repository has no `user.email` configured.
+const FALLBACK_AUTHOR_EMAIL: &str = "dependency-mapping-tool@localhost";
+
+/// A commit waiting to be created by the background thread.
+///
+/// # Fields
+///
+/// * `data_path` - The data directory (the repository is discovered from it)
+/// * `summary` - First line of the commit message
+/// * `command` - Name of the command that made the change
+/// * `paths` - Absolute paths of the files that were written or deleted
+#[derive(Debug)]
+struct CommitJob {
+    data_path: PathBuf,
+    summary: String,
+    command: &'static str,
+    paths: Vec<PathBuf>,
+}
+
+/// Work for the background thread.
+enum GitJob {
+    /// Creates a commit.
+    Commit(CommitJob),
+    /// Answers once every job queued before it has run.
+    #[cfg(test)]
+    Flush(Sender<()>),
+}
+
+/// Payload of the `git-commit-failed` event.
+///
+/// # Fields
+///
+/// * `summary` - First line of the commit that couldn't be created
+/// * `error` - Why the commit failed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommitFailedPayload {
+    pub summary: String,
+    pub error: String,
+}
+
+/// Git state of one environment's files.
+///
+/// # Fields
+///
+/// * `is_repository` - Whether the data directory is inside a git repository
+/// * `branch` - The checked-out branch, if any
+/// * `dirty` - Whether the environment has uncommitted changes
+/// * `changed_files` - Repository-relative paths of changed or untracked files
+///
+/// Audit logs are left out: every write appends to them, but auto-commit
+/// only commits the data files a command wrote.
+///
+/// # Serialization
+///
+/// Uses camelCase field names for JSON compatibility with the frontend.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    pub is_repository: bool,
+    pub branch: Option<String>,
+    pub dirty: bool,
+    pub changed_files: Vec<String>,
+}
+
+/// Owns the sending half of the background commit queue.
+///
+/// Managed as Tauri state; the commit thread exits when it is dropped.
+pub struct GitCommitter {
+    sender: Sender<GitJob>,
+}
+
+impl GitCommitter {
+    /// Spawns the background commit thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - Handle used to emit failure events
+    pub fn start<R: Runtime>(app: AppHandle<R>) -> Self {
+        let (sender, receiver) = channel::<GitJob>();
+
+        thread::spawn(move || commit_loop(app, receiver));
+
+        Self { sender }
+    }
+
+    /// Waits until every commit queued so far has been created.
+    #[cfg(test)]
+    fn flush(&self) {
+        let (done, finished) = channel();
+        self.sender.send(GitJob::Flush(done)).unwrap();
+        finished.recv().unwrap();
+    }
+}
+
+/// Queues a commit of files a command just wrote, if auto-commit is enabled.
+///
+/// Does nothing when the setting is off, when the storage backend isn't a
+/// data directory, or when the commit thread isn't running. Call this once
+/// per command, after the write succeeded, with every file it touched, so
+/// multi-file operations produce a single commit.
+///
+/// # Arguments
+///
+/// * `app` - Handle used to reach the commit thread
+/// * `state` - The application state (settings and data path)
+/// * `command` - Name of the command that made the change
+/// * `summary` - First line of the commit message (e.g., "Update service user-api in dev")
+/// * `paths` - Absolute paths of the files that were written or deleted
+pub fn auto_commit<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    command: &'static str,
+    summary: String,
+    paths: Vec<PathBuf>,
+) {
+    if !state.settings.git_auto_commit {
+        return;
+    }
+
+    let data_path = match state.storage.as_file_system() {
+        Some(fs_backend) => fs_backend.data_path().to_path_buf(),
+        None => return,
+    };
+
+    if let Some(committer) = app.try_state::<GitCommitter>() {
+        let job = CommitJob {
+            data_path,
+            summary,
+            command,
+            paths,
+        };
+        if committer.sender.send(GitJob::Commit(job)).is_err() {
+            eprintln!("Warning: git commit thread is not running");
+        }
+    }
+}
+
+/// Reports the git state of an environment's directory.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory
+/// * `environment` - The environment whose files to inspect
+///
+/// # Returns
+///
+/// * `Ok(GitStatus)` - The status (`is_repository: false` if there's no repository)
+/// * `Err(git2::Error)` - If the repository exists but cannot be read
+pub fn environment_status(data_path: &Path, environment: &str) -> Result<GitStatus, git2::Error> {
+    let repo = match Repository::discover(data_path) {
+        Ok(repo) => repo,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(GitStatus::default()),
+        Err(e) => return Err(e),
+    };
+
+    let branch = repo
+        .head()
+        .ok()
+        .and_then(|head| head.shorthand().map(str::to_string));
+
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    if let Some(pathspec) = repo_relative(&repo, &data_path.join(environment)) {
+        options.pathspec(pathspec);
+    }
+
+    let changed_files: Vec<String> = repo
+        .statuses(Some(&mut options))?
+        .iter()
+        .filter_map(|entry| entry.path().map(str::to_string))
+        .filter(|path| !audit::is_audit_log_file(Path::new(path)))
+        .collect();
+
+    Ok(GitStatus {
+        is_repository: true,
+        branch,
+        dirty: !changed_files.is_empty(),
+        changed_files,
+    })
+}
+
+/// Creates queued commits one at a time until the committer is dropped.
+fn commit_loop<R: Runtime>(app: AppHandle<R>, receiver: Receiver<GitJob>) {
+    for job in receiver {
+        match job {
+            GitJob::Commit(job) => {
+                if let Err(e) = commit(&job) {
+                    report_failure(&app, job.summary, e);
+                }
+            }
+            #[cfg(test)]
+            GitJob::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+/// Tells the frontend that a commit couldn't be created.
+fn report_failure<R: Runtime>(app: &AppHandle<R>, summary: String, error: git2::Error) {
+    let payload = GitCommitFailedPayload {
+        summary,
+        error: error.to_string(),
+    };
+    if let Err(e) = app.emit(GIT_COMMIT_FAILED_EVENT, payload) {
+        eprintln!(
+            "Warning: failed to emit {} event: {}",
+            GIT_COMMIT_FAILED_EVENT, e
+        );
+    }
+}
+
+/// Stages a job's files and commits them on the current branch.
+///
+/// Skips (with a warning) data directories outside a repository, and skips
+/// silently when staging produced no change.
+fn commit(job: &CommitJob) -> Result<(), git2::Error> {
+    let repo = match Repository::discover(&job.data_path) {
+        Ok(repo) => repo,
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            eprintln!(
+                "Warning: skipping git auto-commit, {} is not in a git repository",
+                job.data_path.display()
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut index = repo.index()?;
+    for path in &job.paths {
+        let relative = match repo_relative(&repo, path) {
+            Some(relative) => relative,
+            None => continue,
+        };
+
+        if path.exists() {
+            index.add_path(Path::new(&relative))?;
+        } else {
+            index.remove_path(Path::new(&relative))?;
+        }
+    }
+    index.write()?;
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    if parent.as_ref().map(|p| p.tree_id()) == Some(tree.id()) {
+        return Ok(());
+    }
+
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now(FALLBACK_AUTHOR_NAME, FALLBACK_AUTHOR_EMAIL))?;
+    let message = format!("{}\n\nCommand: {}", job.summary, job.command);
+    let parents: Vec<_> = parent.iter().collect();
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &parents,
+    )?;
+
+    Ok(())
+}
+
+/// Converts an absolute path into a `/`-separated path relative to the
+/// repository's working directory.
+///
+/// Both sides are canonicalized (the file's parent directory, since deleted
+/// files no longer exist) so symlinked data paths still match. Returns
+/// `None` for bare repositories and paths outside the working directory.
+fn repo_relative(repo: &Repository, path: &Path) -> Option<String> {
+    let workdir = fs::canonicalize(repo.workdir()?).ok()?;
+
+    let absolute = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent).ok()?.join(name),
+        _ => fs::canonicalize(path).ok()?,
+    };
+
+    let components: Vec<&str> = absolute
+        .strip_prefix(&workdir)
+        .ok()?
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+
+    Some(components.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::batch::{execute_batch, Operation};
+    use crate::commands::services::save_service;
+    use crate::state::write_state;
+    use crate::test_support::{block_on, relationship, service, TestApp};
+
+    /// Creates an app with auto-commit on, committing in the background.
+    fn committing_app() -> TestApp {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        write_state(&app.state()).settings.git_auto_commit = true;
+        app.app.manage(GitCommitter::start(app.handle()));
+        app
+    }
+
+    /// Initializes a repository in the data directory, with everything in it committed.
+    fn init_repository(app: &TestApp) -> Repository {
+        let repo = Repository::init(app.data_path()).unwrap();
+        {
+            let mut index = repo.index().unwrap();
+            index
+                .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+                .unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = Signature::now("Test", "test@localhost").unwrap();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Initial data",
+                &tree,
+                &[],
+            )
+            .unwrap();
+        }
+        repo
+    }
+
+    fn save(app: &TestApp, id: &str) {
+        save_service(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            service(id),
+            None,
+        )
+        .unwrap();
+    }
+
+    /// Returns the messages of the commits on HEAD, newest first.
+    fn messages(repo: &Repository) -> Vec<String> {
+        let mut walk = repo.revwalk().unwrap();
+        walk.push_head().unwrap();
+        walk.map(|oid| {
+            let commit = repo.find_commit(oid.unwrap()).unwrap();
+            commit.message().unwrap().to_string()
+        })
+        .collect()
+    }
+
+    /// Returns the files the HEAD commit changed.
+    fn changed_in_head(repo: &Repository) -> Vec<String> {
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let parent = head.parent(0).unwrap();
+        let diff = repo
+            .diff_tree_to_tree(
+                Some(&parent.tree().unwrap()),
+                Some(&head.tree().unwrap()),
+                None,
+            )
+            .unwrap();
+        let mut files: Vec<String> = diff
+            .deltas()
+            .map(|d| d.new_file().path().unwrap().display().to_string())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn saving_a_service_commits_it_with_the_command() {
+        let app = committing_app();
+        let repo = init_repository(&app);
+
+        save(&app, "billing");
+        app.app.state::<GitCommitter>().flush();
+
+        assert_eq!(
+            messages(&repo),
+            [
+                "Update service billing in dev\n\nCommand: save_service",
+                "Initial data",
+            ]
+        );
+        assert_eq!(changed_in_head(&repo), ["dev/services/billing.json"]);
+        assert!(!environment_status(app.data_path(), "dev").unwrap().dirty);
+    }
+
+    #[test]
+    fn a_batch_is_one_commit() {
+        let app = committing_app();
+        let repo = init_repository(&app);
+
+        let operations = vec![
+            Operation::SaveService {
+                service: service("billing"),
+                force: false,
+            },
+            Operation::SaveService {
+                service: service("web"),
+                force: false,
+            },
+            Operation::SaveRelationship {
+                relationship: relationship("web-to-billing", "web", "billing"),
+                force: false,
+            },
+        ];
+        block_on(execute_batch(
+            app.handle(),
+            "dev".to_string(),
+            operations,
+            None,
+            None,
+        ))
+        .unwrap();
+        app.app.state::<GitCommitter>().flush();
+
+        let messages = messages(&repo);
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert!(
+            messages[0].ends_with("\n\nCommand: execute_batch"),
+            "{}",
+            messages[0]
+        );
+        assert_eq!(
+            changed_in_head(&repo),
+            [
+                "dev/relationships.json",
+                "dev/services/billing.json",
+                "dev/services/web.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn data_outside_a_repository_is_skipped() {
+        let app = committing_app();
+
+        save(&app, "billing");
+        app.app.state::<GitCommitter>().flush();
+
+        assert!(Repository::discover(app.data_path()).is_err());
+        assert!(app.data_path().join("dev/services/billing.json").exists());
+
+        // Skipping isn't a failure the frontend is told about
+        let job = CommitJob {
+            data_path: app.data_path().to_path_buf(),
+            summary: "Update service billing in dev".to_string(),
+            command: "save_service",
+            paths: vec![app.data_path().join("dev/services/billing.json")],
+        };
+        assert!(commit(&job).is_ok());
+        let status = environment_status(app.data_path(), "dev").unwrap();
+        assert!(!status.is_repository);
+        assert!(!status.dirty);
+    }
+
+    #[test]
+    fn status_reports_uncommitted_environment_files() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        app.create_environment("prod", &[service("api")], &[]);
+        init_repository(&app);
+
+        let status = environment_status(app.data_path(), "dev").unwrap();
+        assert!(status.is_repository);
+        assert!(!status.dirty);
+        assert!(status.changed_files.is_empty());
+
+        // Auto-commit is off, so the save stays uncommitted; the audit log
+        // it appended to isn't reported
+        save(&app, "billing");
+        let status = environment_status(app.data_path(), "dev").unwrap();
+        assert!(status.dirty);
+        assert_eq!(status.changed_files, ["dev/services/billing.json"]);
+
+        // Changes to prod don't make dev dirty
+        let prod_service = app.data_path().join("prod/services/web.json");
+        fs::write(&prod_service, "{}").unwrap();
+        let prod = environment_status(app.data_path(), "prod").unwrap();
+        assert_eq!(prod.changed_files, ["prod/services/web.json"]);
+        let dev = environment_status(app.data_path(), "dev").unwrap();
+        assert_eq!(dev.changed_files, ["dev/services/billing.json"]);
+    }
+}
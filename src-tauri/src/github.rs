@@ -0,0 +1,175 @@
+//! Repository facts from the GitHub REST API.
+//!
+//! Recognizes GitHub repository URLs (`https://github.com/owner/repo`, with
+//! or without `.git` or a trailing path, and `git@github.com:owner/repo.git`)
+//! and reads a repository's description, primary language, archived flag
+//! and last push date from `GET /repos/{owner}/{repo}`.
+//!
+//! Every response reports how many requests are left in the
+//! `X-RateLimit-Remaining` header; callers stop once it reaches zero
+//! instead of sending requests GitHub would reject.
+
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::error::AppError;
+
+/// The GitHub REST API address.
+const API_URL: &str = "https://api.github.com";
+
+/// The user agent GitHub requires on every request.
+const USER_AGENT: &str = concat!("dependency-mapping-tool/", env!("CARGO_PKG_VERSION"));
+
+/// How long each request to GitHub may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A GitHub repository, identified by its owner and name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    pub owner: String,
+    pub name: String,
+}
+
+impl std::fmt::Display for Repository {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.owner, self.name)
+    }
+}
+
+/// The facts read about a repository.
+///
+/// # Fields
+///
+/// * `description` - The repository description, if it has one
+/// * `language` - The primary language GitHub detected, if any
+/// * `archived` - Whether the repository is archived (read-only)
+/// * `pushed_at` - When the repository was last pushed to (ISO 8601)
+#[derive(Debug, Clone, Deserialize)]
+pub struct RepositoryFacts {
+    pub description: Option<String>,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub archived: bool,
+    pub pushed_at: Option<String>,
+}
+
+/// The answer to a repository request.
+///
+/// # Fields
+///
+/// * `facts` - The repository facts
+/// * `remaining` - Requests left before the rate limit, if GitHub reported it
+/// * `reset` - When the rate limit resets (Unix seconds), if GitHub reported it
+#[derive(Debug, Clone)]
+pub struct RepositoryResponse {
+    pub facts: RepositoryFacts,
+    pub remaining: Option<u64>,
+    pub reset: Option<u64>,
+}
+
+/// Parses a GitHub repository URL.
+///
+/// # Returns
+///
+/// The repository, or `None` if the URL doesn't point to a GitHub repository.
+pub fn parse_repository_url(url: &str) -> Option<Repository> {
+    let url = url.trim();
+    let path = ["https://", "http://", "ssh://git@", "git://"]
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))
+        .and_then(|rest| {
+            rest.strip_prefix("github.com/")
+                .or_else(|| rest.strip_prefix("www.github.com/"))
+        })
+        .or_else(|| url.strip_prefix("git@github.com:"))?;
+
+    let mut segments = path.split(['/', '?', '#']);
+    let owner = segments.next().filter(|s| !s.is_empty())?;
+    let name = segments.next()?.trim_end_matches(".git");
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(Repository {
+        owner: owner.to_string(),
+        name: name.to_string(),
+    })
+}
+
+/// Reads the facts of a repository.
+///
+/// Blocks until the request is done; call it off the async runtime.
+///
+/// # Arguments
+///
+/// * `repository` - The repository to read
+/// * `token` - A GitHub token, if any; without one, GitHub allows 60 requests an hour
+///
+/// # Returns
+///
+/// * `Ok(RepositoryResponse)` - The facts and the rate limit left
+/// * `Err(AppError::GitHubRateLimited)` - If the rate limit is used up
+/// * `Err(AppError::GitHubRequestFailed)` - If the repository doesn't exist, isn't
+///   accessible with the token, or GitHub couldn't be reached
+pub fn fetch_repository(
+    repository: &Repository,
+    token: Option<&str>,
+) -> Result<RepositoryResponse, AppError> {
+    let url = format!("{}/repos/{}/{}", API_URL, repository.owner, repository.name);
+    let mut request = ureq::get(&url)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Accept", "application/vnd.github+json")
+        .set("X-GitHub-Api-Version", "2022-11-28")
+        .set("User-Agent", USER_AGENT);
+    if let Some(token) = token.filter(|t| !t.is_empty()) {
+        request = request.set("Authorization", &format!("Bearer {}", token));
+    }
+
+    let failed = |reason: String| AppError::GitHubRequestFailed {
+        repository: repository.to_string(),
+        reason,
+    };
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(code, response)) => {
+            let remaining = header_number(&response, "x-ratelimit-remaining");
+            if code == 429 || (code == 403 && remaining == Some(0)) {
+                let reset = header_number(&response, "x-ratelimit-reset");
+                return Err(AppError::GitHubRateLimited(describe_reset(reset)));
+            }
+            return Err(failed(match code {
+                401 => "GitHub rejected the token".to_string(),
+                403 | 404 => "Repository not found or not accessible with the token".to_string(),
+                _ => format!("HTTP {} {}", code, response.status_text()),
+            }));
+        }
+        Err(e) => return Err(failed(e.to_string())),
+    };
+
+    let remaining = header_number(&response, "x-ratelimit-remaining");
+    let reset = header_number(&response, "x-ratelimit-reset");
+    let body = response
+        .into_string()
+        .map_err(|e| failed(format!("Unexpected response: {}", e)))?;
+    let facts =
+        serde_json::from_str(&body).map_err(|e| failed(format!("Unexpected response: {}", e)))?;
+
+    Ok(RepositoryResponse {
+        facts,
+        remaining,
+        reset,
+    })
+}
+
+/// Describes when the rate limit resets, for error messages.
+pub fn describe_reset(reset: Option<u64>) -> String {
+    match reset {
+        Some(seconds) => format!("Unix time {}", seconds),
+        None => "an unknown time".to_string(),
+    }
+}
+
+/// Reads a numeric response header.
+fn header_number(response: &ureq::Response, name: &str) -> Option<u64> {
+    response.header(name)?.trim().parse().ok()
+}
@@ -0,0 +1,257 @@
+//! Propagation of service health through the dependency graph.
+//!
+//! A service is only as healthy as what it depends on: a healthy service
+//! whose (transitive) dependency is unhealthy is effectively degraded. The
+//! worst dependency of every service is found with one breadth-first search
+//! per impact level over the reversed dependency edges, starting from all
+//! services at that level at once. Each service is visited at most once per
+//! search, so cycles need no special handling and the cost stays linear in
+//! the size of the graph.
+
+use serde::Serialize;
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+
+use crate::models::{Relationship, RelationshipType, Service, ServiceStatus};
+
+/// The own and effective status of a service.
+///
+/// # Fields
+///
+/// * `service_id` - The service
+/// * `own_status` - The status recorded on the service
+/// * `effective_status` - The own status, degraded if a dependency is degraded or unhealthy
+/// * `worst_dependency_path` - Service IDs from this service to its worst
+///   dependency (the shortest such path); empty if no dependency affects it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveStatus {
+    pub service_id: String,
+    pub own_status: ServiceStatus,
+    pub effective_status: ServiceStatus,
+    pub worst_dependency_path: Vec<String>,
+}
+
+/// How much a status affects the services depending on it.
+///
+/// Unknown and deprecated services don't count as impaired.
+fn impact(status: &ServiceStatus) -> u8 {
+    match status {
+        ServiceStatus::Unhealthy => 2,
+        ServiceStatus::Degraded => 1,
+        ServiceStatus::Healthy | ServiceStatus::Unknown | ServiceStatus::Deprecated => 0,
+    }
+}
+
+/// Computes the effective status of every service.
+///
+/// A service whose worst transitive dependency is degraded or unhealthy is
+/// effectively degraded, unless its own status is already worse.
+///
+/// # Arguments
+///
+/// * `services` - All services in the environment
+/// * `relationships` - All relationships in the environment
+/// * `include_data_flow` - Also follow reads_from and writes_to relationships,
+///   not only depends_on
+///
+/// # Returns
+///
+/// One entry per service, in the order of `services`
+pub fn effective_statuses<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    include_data_flow: bool,
+) -> Vec<EffectiveStatus> {
+    let services: Vec<&Service> = services.iter().map(Borrow::borrow).collect();
+    let index: HashMap<&str, usize> = services
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.id.as_str(), i))
+        .collect();
+
+    // dependents[target] = services with a followed relationship to target
+    let mut dependencies: Vec<Vec<usize>> = vec![Vec::new(); services.len()];
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); services.len()];
+    for relationship in relationships {
+        let followed = match relationship.relationship_type {
+            RelationshipType::DependsOn => true,
+            RelationshipType::ReadsFrom | RelationshipType::WritesTo => include_data_flow,
+            _ => false,
+        };
+        if !followed {
+            continue;
+        }
+        let (Some(&source), Some(&target)) = (
+            index.get(relationship.source.as_str()),
+            index.get(relationship.target.as_str()),
+        ) else {
+            continue;
+        };
+        if source == target {
+            continue;
+        }
+        dependencies[source].push(target);
+        dependents[target].push(source);
+    }
+
+    // worst[i] = (impact, path from i to the impaired dependency)
+    let mut worst: Vec<(u8, Vec<usize>)> = vec![(0, Vec::new()); services.len()];
+    for level in [2, 1] {
+        let (distance, next_hop) = nearest_at_level(&services, &dependents, level);
+
+        for (i, deps) in dependencies.iter().enumerate() {
+            if worst[i].0 >= level {
+                continue;
+            }
+            // The dependency closest to a service at this level, if any reaches one
+            let Some(&first) = deps
+                .iter()
+                .filter(|&&d| distance[d].is_some())
+                .min_by_key(|&&d| distance[d])
+            else {
+                continue;
+            };
+
+            let mut path = vec![i, first];
+            let mut current = first;
+            while let Some(next) = next_hop[current] {
+                path.push(next);
+                current = next;
+            }
+            worst[i] = (level, path);
+        }
+    }
+
+    services
+        .iter()
+        .zip(worst)
+        .map(|(service, (level, path))| {
+            let effective_status = if level > impact(&service.status) {
+                ServiceStatus::Degraded
+            } else {
+                service.status.clone()
+            };
+            EffectiveStatus {
+                service_id: service.id.clone(),
+                own_status: service.status.clone(),
+                effective_status,
+                worst_dependency_path: path.into_iter().map(|i| services[i].id.clone()).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Finds, for every service, the nearest service with the given impact among
+/// itself and its transitive dependencies.
+///
+/// # Returns
+///
+/// A tuple of, per service:
+/// * The number of hops to the nearest such service (`None` if none is reachable)
+/// * The next service on the way there (`None` at the service itself)
+fn nearest_at_level(
+    services: &[&Service],
+    dependents: &[Vec<usize>],
+    level: u8,
+) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+    let mut distance: Vec<Option<usize>> = vec![None; services.len()];
+    let mut next_hop: Vec<Option<usize>> = vec![None; services.len()];
+    let mut queue = VecDeque::new();
+
+    for (i, service) in services.iter().enumerate() {
+        if impact(&service.status) == level {
+            distance[i] = Some(0);
+            queue.push_back(i);
+        }
+    }
+
+    while let Some(current) = queue.pop_front() {
+        let hops = distance[current].unwrap_or(0) + 1;
+        for &dependent in &dependents[current] {
+            if distance[dependent].is_none() {
+                distance[dependent] = Some(hops);
+                next_hop[dependent] = Some(current);
+                queue.push_back(dependent);
+            }
+        }
+    }
+
+    (distance, next_hop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{relationship, service};
+
+    fn with_status(id: &str, status: ServiceStatus) -> Service {
+        let mut service = service(id);
+        service.status = status;
+        service
+    }
+
+    fn by_id(statuses: Vec<EffectiveStatus>) -> HashMap<String, EffectiveStatus> {
+        statuses
+            .into_iter()
+            .map(|status| (status.service_id.clone(), status))
+            .collect()
+    }
+
+    #[test]
+    fn an_unhealthy_leaf_degrades_every_ancestor_of_a_chain() {
+        let services = [
+            service("web"),
+            service("api"),
+            service("cache"),
+            with_status("db", ServiceStatus::Unhealthy),
+        ];
+        let relationships = [
+            relationship("web-api", "web", "api"),
+            relationship("api-cache", "api", "cache"),
+            relationship("cache-db", "cache", "db"),
+        ];
+
+        let statuses = by_id(effective_statuses(&services, &relationships, false));
+
+        for (id, path) in [
+            ("web", vec!["web", "api", "cache", "db"]),
+            ("api", vec!["api", "cache", "db"]),
+            ("cache", vec!["cache", "db"]),
+        ] {
+            let status = &statuses[id];
+            assert_eq!(status.own_status, ServiceStatus::Healthy);
+            assert_eq!(status.effective_status, ServiceStatus::Degraded, "{id}");
+            assert_eq!(status.worst_dependency_path, path);
+        }
+        let db = &statuses["db"];
+        assert_eq!(db.effective_status, ServiceStatus::Unhealthy);
+        assert!(db.worst_dependency_path.is_empty());
+    }
+
+    #[test]
+    fn cycles_terminate_and_only_followed_types_propagate() {
+        let services = [
+            service("a"),
+            service("b"),
+            with_status("c", ServiceStatus::Degraded),
+            service("reader"),
+        ];
+        let mut reads = relationship("reader-a", "reader", "a");
+        reads.relationship_type = RelationshipType::ReadsFrom;
+        let relationships = [
+            relationship("a-b", "a", "b"),
+            relationship("b-a", "b", "a"),
+            relationship("b-c", "b", "c"),
+            reads,
+        ];
+
+        let statuses = by_id(effective_statuses(&services, &relationships, false));
+        assert_eq!(statuses["a"].effective_status, ServiceStatus::Degraded);
+        assert_eq!(statuses["a"].worst_dependency_path, ["a", "b", "c"]);
+        assert_eq!(statuses["reader"].effective_status, ServiceStatus::Healthy);
+
+        let statuses = by_id(effective_statuses(&services, &relationships, true));
+        assert_eq!(statuses["reader"].effective_status, ServiceStatus::Degraded);
+    }
+}
@@ -0,0 +1,362 @@
+//! Embedded read-only HTTP API for other tools.
+//!
+//! When the `httpApi` setting is enabled, a small HTTP server listens on
+//! `127.0.0.1:{port}` so tools such as chat bots and deploy pipelines can
+//! query the dependency map while the app is running. Every request must
+//! send the configured token as `Authorization: Bearer {token}`; anything
+//! else is answered with `401`.
+//!
+//! # Endpoints
+//!
+//! All endpoints are `GET` and return JSON in the same camelCase shape as
+//! the corresponding Tauri commands. Endpoints taking an environment use the
+//! `environment` query parameter, defaulting to the app's current one.
+//!
+//! | Path | Returns | Like |
+//! | --- | --- | --- |
+//! | `/environments` | Environment names | `list_environments` |
+//! | `/environments/{env}/services` | All services of the environment | `get_all_services` |
+//! | `/services/{id}` | One service | `get_service_by_id` |
+//! | `/graph?center={id}&depth={n}` | The graph around a service | `get_service_graph` |
+//! | `/validate` | The validation result | `validate_environment` |
+//!
+//! `/graph` follows the relationships in effect on its `asOf` date
+//! (`YYYY-MM-DD`), today if absent.
+//!
+//! Errors are returned as `{ "error": "..." }` with `400` for bad
+//! parameters, `404` for unknown paths, environments and services, and `500`
+//! otherwise.
+//!
+//! # Threading
+//!
+//! Requests are served one at a time on a background thread and read the
+//! managed `AppState` through the same lock and caches as the commands. The
+//! server stops when the setting is disabled, when its port or token
+//! changes (it is then restarted) and when the app exits.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use tauri::{AppHandle, Manager, Runtime};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::commands::environments;
+use crate::commands::graph::{self, GraphQuery};
+use crate::commands::validation;
+use crate::dates;
+use crate::error::AppError;
+use crate::models::{HttpApiSettings, TraversalDirection};
+use crate::state::{cache, read_state, AppState};
+
+/// Owns the running HTTP server, if the API is enabled.
+///
+/// Managed as Tauri state (`Mutex<HttpApi>`) so `update_settings` can start,
+/// restart or stop it.
+#[derive(Default)]
+pub struct HttpApi {
+    running: Option<RunningServer>,
+}
+
+/// A started server and the thread serving it.
+struct RunningServer {
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+    settings: HttpApiSettings,
+}
+
+impl HttpApi {
+    /// Starts, restarts or stops the server to match the settings.
+    ///
+    /// A running server whose port and token are unchanged is left alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - Handle used by the server to access the application state
+    /// * `settings` - The HTTP API settings
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the server now matches the settings
+    /// * `Err(AppError::ValidationError)` - If the API is enabled without a token
+    /// * `Err(AppError::Io)` - If the port cannot be bound; the server is then stopped
+    pub fn apply<R: Runtime>(
+        &mut self,
+        app: &AppHandle<R>,
+        settings: &HttpApiSettings,
+    ) -> Result<(), AppError> {
+        if settings.enabled
+            && self
+                .running
+                .as_ref()
+                .is_some_and(|running| running.settings == *settings)
+        {
+            return Ok(());
+        }
+
+        self.stop();
+        if !settings.enabled {
+            return Ok(());
+        }
+        if settings.token.trim().is_empty() {
+            return Err(AppError::ValidationError(
+                "The HTTP API needs a token before it can be enabled".to_string(),
+            ));
+        }
+
+        let server = Server::http(("127.0.0.1", settings.port)).map_err(|e| {
+            AppError::Io(std::io::Error::other(format!(
+                "Cannot listen on port {}: {}",
+                settings.port, e
+            )))
+        })?;
+        let server = Arc::new(server);
+
+        let thread = {
+            let server = Arc::clone(&server);
+            let app = app.clone();
+            let token = settings.token.clone();
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    respond(&app, &token, request);
+                }
+            })
+        };
+
+        self.running = Some(RunningServer {
+            server,
+            thread,
+            settings: settings.clone(),
+        });
+        Ok(())
+    }
+
+    /// Stops the server, if it is running, and waits for its thread to finish.
+    pub fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.server.unblock();
+            if running.thread.join().is_err() {
+                eprintln!("Warning: HTTP API thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for HttpApi {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Body of an error response.
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// A failed request, with the status it is answered with.
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(e: AppError) -> Self {
+        let status = match e {
+            AppError::ServiceNotFound(_) | AppError::EnvironmentNotFound(_) => 404,
+            AppError::ValidationError(_) | AppError::InvalidPath(_) => 400,
+            _ => 500,
+        };
+        Self::new(status, e.to_string())
+    }
+}
+
+/// Answers a request, logging responses that can't be sent.
+fn respond<R: Runtime>(app: &AppHandle<R>, token: &str, request: Request) {
+    let (status, body) = match handle(app, token, &request) {
+        Ok(body) => (200, body),
+        Err(e) => {
+            let body = serde_json::to_string(&ErrorBody { error: e.message })
+                .unwrap_or_else(|_| "{}".to_string());
+            (e.status, body)
+        }
+    };
+
+    let content_type: Header = "Content-Type: application/json"
+        .parse()
+        .expect("Content-Type header is valid");
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        eprintln!("Warning: failed to send HTTP API response: {}", e);
+    }
+}
+
+/// Authenticates and routes a request, returning the JSON body.
+fn handle<R: Runtime>(
+    app: &AppHandle<R>,
+    token: &str,
+    request: &Request,
+) -> Result<String, ApiError> {
+    let authorized = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|sent| constant_time_eq(sent.as_bytes(), token.as_bytes()));
+    if !authorized {
+        return Err(ApiError::new(401, "Missing or wrong bearer token"));
+    }
+    if *request.method() != Method::Get {
+        return Err(ApiError::new(405, "Only GET requests are supported"));
+    }
+
+    let (path, query) = match request.url().split_once('?') {
+        Some((path, query)) => (path, parse_query(query)),
+        None => (request.url(), HashMap::new()),
+    };
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    let state = app.state::<RwLock<AppState>>();
+    let state: &RwLock<AppState> = &state;
+    let requested_environment = query.get("environment").map(String::as_str);
+
+    match segments.as_slice() {
+        ["environments"] => to_json(&environments::sorted_environments(&read_state(state))?),
+        ["environments", environment, "services"] => {
+            let environment = existing_environment(state, Some(*environment))?;
+            to_json(&cache::services(state, &environment)?)
+        }
+        ["services", service_id] => {
+            let environment = existing_environment(state, requested_environment)?;
+            to_json(&cache::service(state, &environment, service_id)?)
+        }
+        ["graph"] => {
+            let environment = existing_environment(state, requested_environment)?;
+            let center = query
+                .get("center")
+                .ok_or_else(|| ApiError::new(400, "Missing query parameter 'center'"))?;
+            let depth = match query.get("depth") {
+                Some(depth) => depth
+                    .parse()
+                    .map_err(|_| ApiError::new(400, format!("Invalid depth '{}'", depth)))?,
+                None => 1,
+            };
+            let as_of = dates::resolve_as_of(query.get("asOf").cloned(), None)?;
+            let query = GraphQuery {
+                depth,
+                relationship_types: None,
+                direction: TraversalDirection::Both,
+                include_effective_status: false,
+                include_criticality: false,
+                include_groups: false,
+                include_annotations: false,
+                max_nodes: None,
+                max_edges: None,
+                exclude_statuses: Vec::new(),
+                exclude_tags: Vec::new(),
+                exclude_service_ids: Vec::new(),
+                aggregate_edges: false,
+                as_of,
+            };
+            to_json(&graph::build_service_graph(
+                state,
+                &environment,
+                std::slice::from_ref(center),
+                &query,
+            )?)
+        }
+        ["validate"] => {
+            let environment = existing_environment(state, requested_environment)?;
+            let result = validation::run_validation(state, &environment)?;
+            to_json(&result)
+        }
+        _ => Err(ApiError::new(404, format!("Unknown endpoint '{}'", path))),
+    }
+}
+
+/// Resolves the requested environment (or the current one) and checks it exists.
+fn existing_environment(
+    state: &RwLock<AppState>,
+    environment: Option<&str>,
+) -> Result<String, ApiError> {
+    let state = read_state(state);
+    let environment = environment
+        .unwrap_or(&state.current_environment)
+        .to_string();
+    if !state.storage.environment_exists(&environment)? {
+        return Err(AppError::EnvironmentNotFound(environment).into());
+    }
+
+    Ok(environment)
+}
+
+/// Serializes a response body.
+fn to_json<T: Serialize>(value: &T) -> Result<String, ApiError> {
+    serde_json::to_string(value).map_err(|e| AppError::from(e).into())
+}
+
+/// Parses a query string into decoded parameters; later duplicates win.
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes and `+` (as space) in a URL component.
+///
+/// Malformed escapes are kept as they are.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let escaped = bytes
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match escaped {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Compares two byte strings in time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
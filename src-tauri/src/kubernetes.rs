@@ -0,0 +1,520 @@
+//! Kubernetes topology import.
+//!
+//! Lists Deployments, StatefulSets, Services and Ingresses in a cluster's
+//! namespaces (`fetch_resources`) and maps them to services and inferred
+//! communicates_with edges (`map_resources`). The mapping only looks at
+//! the Kubernetes objects, not where they came from, so resources parsed
+//! from manifest files or a `kubectl get -o json` export
+//! (`resources_from_list`) map the same way.
+//!
+//! # Mapping Rules
+//!
+//! - Each Deployment and StatefulSet becomes a service `{namespace}-{name}`,
+//!   with its first image tag as the version, its readiness as the status
+//!   and a `team` label as the team
+//! - Each Ingress becomes a gateway service `{namespace}-{name}-ingress`
+//! - Each ExternalName Service becomes an external service
+//!   `{namespace}-{name}-external`; other Services only link others to the
+//!   workloads their selector matches
+//! - An Ingress communicates with the workloads behind its backend Services
+//! - A workload communicates with the workloads behind every Service whose
+//!   DNS name (`name`, `name.namespace`, `name.namespace.svc[.cluster.local]`)
+//!   appears in one of its containers' environment variable values
+//!
+//! Every mapped service carries a `kubernetes` metadata object with the
+//! namespace, kind, name and images it was mapped from, which re-imports use
+//! to recognize it.
+
+use k8s_openapi::api::apps::v1::{Deployment, StatefulSet};
+use k8s_openapi::api::core::v1::{PodTemplateSpec, Service as KubeService};
+use k8s_openapi::api::networking::v1::{Ingress, IngressBackend};
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::{Api, ListParams};
+use kube::config::{KubeConfigOptions, Kubeconfig};
+use kube::{Client, Config, Resource};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Debug;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::error::AppError;
+use crate::models::{Service, ServiceStatus, ServiceType};
+
+/// Metadata key holding the Kubernetes object a service was mapped from.
+pub const KUBERNETES_METADATA_KEY: &str = "kubernetes";
+
+/// How long connecting to or reading from the cluster may take per request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Image name fragments that identify a service type, checked in order.
+const IMAGE_TYPES: &[(&str, ServiceType)] = &[
+    ("postgres", ServiceType::Database),
+    ("mysql", ServiceType::Database),
+    ("mariadb", ServiceType::Database),
+    ("mongo", ServiceType::Database),
+    ("cassandra", ServiceType::Database),
+    ("redis", ServiceType::Cache),
+    ("memcached", ServiceType::Cache),
+    ("kafka", ServiceType::Queue),
+    ("rabbitmq", ServiceType::Queue),
+    ("nats", ServiceType::Queue),
+    ("nginx", ServiceType::Gateway),
+    ("envoy", ServiceType::Gateway),
+    ("traefik", ServiceType::Gateway),
+];
+
+/// The Kubernetes objects an import maps.
+#[derive(Debug, Default)]
+pub struct ClusterResources {
+    pub deployments: Vec<Deployment>,
+    pub stateful_sets: Vec<StatefulSet>,
+    pub services: Vec<KubeService>,
+    pub ingresses: Vec<Ingress>,
+}
+
+/// An edge inferred from the cluster.
+///
+/// # Fields
+///
+/// * `source` / `target` - IDs of the mapped services
+/// * `reason` - Where the edge was found, used as the relationship description
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InferredEdge {
+    pub source: String,
+    pub target: String,
+    pub reason: String,
+}
+
+/// Services and edges mapped from cluster resources.
+#[derive(Debug, Default)]
+pub struct MappedTopology {
+    /// Mapped services, sorted by ID.
+    pub services: Vec<Service>,
+    /// Inferred communicates_with edges, sorted and without duplicates.
+    pub edges: Vec<InferredEdge>,
+}
+
+/// Lists the resources to import from a cluster.
+///
+/// # Arguments
+///
+/// * `kubeconfig_path` - The kubeconfig to use (its current context); `None`
+///   uses `KUBECONFIG`, `~/.kube/config` or the in-cluster configuration
+/// * `namespaces` - The namespaces to list
+///
+/// # Returns
+///
+/// * `Ok(ClusterResources)` - The resources of all namespaces
+/// * `Err(AppError::InvalidPath)` - If the kubeconfig cannot be read
+/// * `Err(AppError::ClusterAuthFailed)` - If no credentials could be obtained or the
+///   cluster rejected them
+/// * `Err(AppError::ClusterRequestFailed)` - If the cluster couldn't be reached or
+///   a request failed
+pub async fn fetch_resources(
+    kubeconfig_path: Option<&Path>,
+    namespaces: &[String],
+) -> Result<ClusterResources, AppError> {
+    let mut config = match kubeconfig_path {
+        Some(path) => {
+            let kubeconfig = Kubeconfig::read_from(path).map_err(|e| {
+                AppError::InvalidPath(format!(
+                    "Cannot read kubeconfig '{}': {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            Config::from_custom_kubeconfig(kubeconfig, &KubeConfigOptions::default())
+                .await
+                .map_err(|e| AppError::ClusterAuthFailed(e.to_string()))?
+        }
+        None => Config::infer()
+            .await
+            .map_err(|e| AppError::ClusterAuthFailed(e.to_string()))?,
+    };
+    config.connect_timeout = Some(REQUEST_TIMEOUT);
+    config.read_timeout = Some(REQUEST_TIMEOUT);
+    let client = Client::try_from(config).map_err(cluster_error)?;
+
+    let mut resources = ClusterResources::default();
+    for namespace in namespaces {
+        resources
+            .deployments
+            .extend(list(&client, namespace).await?);
+        resources
+            .stateful_sets
+            .extend(list(&client, namespace).await?);
+        resources.services.extend(list(&client, namespace).await?);
+        resources.ingresses.extend(list(&client, namespace).await?);
+    }
+
+    Ok(resources)
+}
+
+/// Lists all objects of one kind in a namespace.
+async fn list<K>(client: &Client, namespace: &str) -> Result<Vec<K>, AppError>
+where
+    K: Resource<Scope = NamespaceResourceScope> + Clone + DeserializeOwned + Debug,
+    K::DynamicType: Default,
+{
+    let api: Api<K> = Api::namespaced(client.clone(), namespace);
+    let list = api
+        .list(&ListParams::default())
+        .await
+        .map_err(cluster_error)?;
+    Ok(list.items)
+}
+
+/// Collects the resources to import from the items of a Kubernetes `List`.
+///
+/// The items are read as the API returns them, e.g. the `items` of
+/// `kubectl get deployments,statefulsets,services,ingresses -A -o json`.
+/// Objects of other kinds are skipped.
+///
+/// # Returns
+///
+/// * `Ok(ClusterResources)` - The Deployments, StatefulSets, Services and Ingresses
+/// * `Err(serde_json::Error)` - If an object of one of those kinds is malformed
+pub fn resources_from_list(items: Vec<Value>) -> Result<ClusterResources, serde_json::Error> {
+    let mut resources = ClusterResources::default();
+    for item in items {
+        let kind = item.get("kind").and_then(Value::as_str).map(str::to_string);
+        match kind.as_deref() {
+            Some("Deployment") => resources.deployments.push(serde_json::from_value(item)?),
+            Some("StatefulSet") => resources.stateful_sets.push(serde_json::from_value(item)?),
+            Some("Service") => resources.services.push(serde_json::from_value(item)?),
+            Some("Ingress") => resources.ingresses.push(serde_json::from_value(item)?),
+            _ => {}
+        }
+    }
+
+    Ok(resources)
+}
+
+/// Converts a client error, telling authentication failures apart.
+fn cluster_error(e: kube::Error) -> AppError {
+    match e {
+        kube::Error::Api(response) if response.code == 401 || response.code == 403 => {
+            AppError::ClusterAuthFailed(response.message)
+        }
+        kube::Error::Auth(e) => AppError::ClusterAuthFailed(e.to_string()),
+        e => AppError::ClusterRequestFailed(e.to_string()),
+    }
+}
+
+/// A Deployment or StatefulSet.
+struct Workload<'a> {
+    id: String,
+    namespace: &'a str,
+    name: &'a str,
+    kind: &'static str,
+    labels: Option<&'a BTreeMap<String, String>>,
+    template: &'a PodTemplateSpec,
+    replicas: i32,
+    ready: i32,
+}
+
+impl Workload<'_> {
+    /// Returns true if the workload's pods carry every label of the selector.
+    fn matches(&self, namespace: &str, selector: &BTreeMap<String, String>) -> bool {
+        let pod_labels = self
+            .template
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.labels.as_ref());
+        self.namespace == namespace
+            && !selector.is_empty()
+            && pod_labels.is_some_and(|labels| {
+                selector
+                    .iter()
+                    .all(|(key, value)| labels.get(key) == Some(value))
+            })
+    }
+
+    /// Returns the images of the workload's containers.
+    fn images(&self) -> Vec<&str> {
+        self.template
+            .spec
+            .iter()
+            .flat_map(|spec| &spec.containers)
+            .filter_map(|container| container.image.as_deref())
+            .collect()
+    }
+}
+
+/// Maps cluster resources to services and communicates_with edges.
+///
+/// Objects without a name or namespace are skipped. See the module
+/// documentation for the rules.
+pub fn map_resources(resources: &ClusterResources) -> MappedTopology {
+    let workloads = workloads(resources);
+    let mut services: BTreeMap<String, Service> = BTreeMap::new();
+
+    for workload in &workloads {
+        services.insert(workload.id.clone(), workload_service(workload));
+    }
+
+    // Each Kubernetes Service resolves to the mapped services it fronts
+    let mut fronted: HashMap<(&str, &str), Vec<String>> = HashMap::new();
+    for kube_service in &resources.services {
+        let (Some(namespace), Some(name)) = object_key(&kube_service.metadata) else {
+            continue;
+        };
+        let Some(spec) = &kube_service.spec else {
+            continue;
+        };
+
+        let targets = match (&spec.external_name, &spec.selector) {
+            (Some(external_name), _) => {
+                let id = format!("{}-{}-external", namespace, name);
+                let mut service =
+                    mapped_service(&id, name, ServiceType::External, namespace, "Service", &[]);
+                service.description = Some(format!("External name {}", external_name));
+                services.entry(id.clone()).or_insert(service);
+                vec![id]
+            }
+            (None, Some(selector)) => workloads
+                .iter()
+                .filter(|workload| workload.matches(namespace, selector))
+                .map(|workload| workload.id.clone())
+                .collect(),
+            (None, None) => Vec::new(),
+        };
+        fronted.insert((namespace, name), targets);
+    }
+
+    let mut edges = BTreeSet::new();
+
+    for ingress in &resources.ingresses {
+        let (Some(namespace), Some(name)) = object_key(&ingress.metadata) else {
+            continue;
+        };
+        let id = format!("{}-{}-ingress", namespace, name);
+        services.insert(
+            id.clone(),
+            mapped_service(&id, name, ServiceType::Gateway, namespace, "Ingress", &[]),
+        );
+
+        for backend in ingress_backends(ingress) {
+            let Some(backend_service) = &backend.service else {
+                continue;
+            };
+            let targets = fronted.get(&(namespace, backend_service.name.as_str()));
+            for target in targets.into_iter().flatten() {
+                edges.insert(InferredEdge {
+                    source: id.clone(),
+                    target: target.clone(),
+                    reason: format!("Ingress backend {}", backend_service.name),
+                });
+            }
+        }
+    }
+
+    for workload in &workloads {
+        let containers = workload
+            .template
+            .spec
+            .iter()
+            .flat_map(|spec| &spec.containers);
+        for env in containers.flat_map(|container| container.env.iter().flatten()) {
+            let Some(value) = &env.value else {
+                continue;
+            };
+            for (namespace, name) in service_references(value, workload.namespace) {
+                let targets = fronted.get(&(namespace, name));
+                for target in targets.into_iter().flatten() {
+                    if *target != workload.id {
+                        edges.insert(InferredEdge {
+                            source: workload.id.clone(),
+                            target: target.clone(),
+                            reason: format!("Referenced in environment variable {}", env.name),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Keep one edge per pair of services
+    let mut seen = BTreeSet::new();
+    let edges = edges
+        .into_iter()
+        .filter(|edge| seen.insert((edge.source.clone(), edge.target.clone())))
+        .collect();
+
+    MappedTopology {
+        services: services.into_values().collect(),
+        edges,
+    }
+}
+
+/// Collects the named Deployments and StatefulSets that have a spec.
+fn workloads(resources: &ClusterResources) -> Vec<Workload<'_>> {
+    let deployments = resources.deployments.iter().filter_map(|deployment| {
+        let (Some(namespace), Some(name)) = object_key(&deployment.metadata) else {
+            return None;
+        };
+        let spec = deployment.spec.as_ref()?;
+        let status = deployment.status.as_ref();
+        Some(Workload {
+            id: format!("{}-{}", namespace, name),
+            namespace,
+            name,
+            kind: "Deployment",
+            labels: deployment.metadata.labels.as_ref(),
+            template: &spec.template,
+            replicas: spec.replicas.unwrap_or(1),
+            ready: status.and_then(|s| s.ready_replicas).unwrap_or(0),
+        })
+    });
+    let stateful_sets = resources.stateful_sets.iter().filter_map(|stateful_set| {
+        let (Some(namespace), Some(name)) = object_key(&stateful_set.metadata) else {
+            return None;
+        };
+        let spec = stateful_set.spec.as_ref()?;
+        let status = stateful_set.status.as_ref();
+        Some(Workload {
+            id: format!("{}-{}", namespace, name),
+            namespace,
+            name,
+            kind: "StatefulSet",
+            labels: stateful_set.metadata.labels.as_ref(),
+            template: &spec.template,
+            replicas: spec.replicas.unwrap_or(1),
+            ready: status.and_then(|s| s.ready_replicas).unwrap_or(0),
+        })
+    });
+
+    deployments.chain(stateful_sets).collect()
+}
+
+/// Maps a workload to a service.
+fn workload_service(workload: &Workload) -> Service {
+    let images = workload.images();
+    let lowercase_images = images.join(" ").to_lowercase();
+    let service_type = IMAGE_TYPES
+        .iter()
+        .find(|(fragment, _)| lowercase_images.contains(fragment))
+        .map_or(ServiceType::Backend, |(_, service_type)| {
+            service_type.clone()
+        });
+
+    let mut service = mapped_service(
+        &workload.id,
+        workload.name,
+        service_type,
+        workload.namespace,
+        workload.kind,
+        &images,
+    );
+    service.status = if workload.replicas == 0 {
+        ServiceStatus::Unknown
+    } else if workload.ready >= workload.replicas {
+        ServiceStatus::Healthy
+    } else if workload.ready > 0 {
+        ServiceStatus::Degraded
+    } else {
+        ServiceStatus::Unhealthy
+    };
+    service.version = images.first().and_then(|image| image_tag(image));
+    service.team = workload
+        .labels
+        .and_then(|labels| labels.get("team").cloned());
+    if let Some(serde_json::Value::Object(object)) =
+        service.metadata.get_mut(KUBERNETES_METADATA_KEY)
+    {
+        object.insert("replicas".to_string(), workload.replicas.into());
+    }
+
+    service
+}
+
+/// Builds a mapped service with the tags and metadata every mapped service has.
+fn mapped_service(
+    id: &str,
+    name: &str,
+    service_type: ServiceType,
+    namespace: &str,
+    kind: &str,
+    images: &[&str],
+) -> Service {
+    let metadata = serde_json::json!({
+        "namespace": namespace,
+        "kind": kind,
+        "name": name,
+        "images": images,
+    });
+
+    Service {
+        id: id.to_string(),
+        name: name.to_string(),
+        service_type,
+        status: ServiceStatus::Unknown,
+        description: None,
+        version: None,
+        owner: None,
+        team: None,
+        tier: None,
+        lifecycle: None,
+        decommission_date: None,
+        successor_id: None,
+        tags: vec!["kubernetes".to_string(), format!("namespace:{}", namespace)],
+        metadata: HashMap::from([(KUBERNETES_METADATA_KEY.to_string(), metadata)]),
+        revision: 0,
+    }
+}
+
+/// Returns an object's namespace and name, if it has both.
+fn object_key(
+    metadata: &k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta,
+) -> (Option<&str>, Option<&str>) {
+    (metadata.namespace.as_deref(), metadata.name.as_deref())
+}
+
+/// Returns the default backend and the backends of all rules of an Ingress.
+fn ingress_backends(ingress: &Ingress) -> Vec<&IngressBackend> {
+    let Some(spec) = &ingress.spec else {
+        return Vec::new();
+    };
+
+    let rule_backends = spec
+        .rules
+        .iter()
+        .flatten()
+        .filter_map(|rule| rule.http.as_ref())
+        .flat_map(|http| &http.paths)
+        .map(|path| &path.backend);
+    spec.default_backend.iter().chain(rule_backends).collect()
+}
+
+/// Finds Service DNS names in an environment variable value.
+///
+/// # Returns
+///
+/// The `(namespace, name)` of every referenced Service; names without a
+/// namespace refer to `namespace`.
+fn service_references<'a>(value: &'a str, namespace: &'a str) -> Vec<(&'a str, &'a str)> {
+    value
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '.'))
+        .filter(|token| !token.is_empty())
+        .filter_map(|token| {
+            let host = token
+                .strip_suffix(".svc.cluster.local")
+                .or_else(|| token.strip_suffix(".svc"))
+                .unwrap_or(token);
+            match host.split('.').collect::<Vec<_>>().as_slice() {
+                [name] => Some((namespace, *name)),
+                [name, namespace] => Some((*namespace, *name)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Returns the tag of a container image (`registry/name:tag`), if it has one.
+fn image_tag(image: &str) -> Option<String> {
+    let name = image.rsplit('/').next()?;
+    let name = name.split('@').next()?;
+    name.split_once(':').map(|(_, tag)| tag.to_string())
+}
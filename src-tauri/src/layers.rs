@@ -0,0 +1,222 @@
+//! Assignment of services to layers for a left-to-right architecture view.
+//!
+//! A service's layer is the length of the longest depends_on path reaching
+//! it from a service nothing depends on. Those entry points (typically
+//! frontends) are layer 0 and every service sits at least one layer to the
+//! right of everything depending on it, so data stores end up last.
+//!
+//! Longest paths are only defined without cycles, so the services of each
+//! dependency cycle (a strongly connected component) are collapsed into one
+//! group that shares a layer and is reported separately. The result only
+//! depends on the data, not on load order, so nodes positioned from it don't
+//! move between refreshes.
+
+use serde::Serialize;
+use std::borrow::Borrow;
+use std::collections::BTreeSet;
+
+use crate::models::{Relationship, RelationshipType, Service, ServiceType};
+
+/// Services grouped into layers.
+///
+/// # Fields
+///
+/// * `layers` - Service IDs per layer, from layer 0 (nothing depends on them)
+///   onwards; sorted within each layer
+/// * `cycles` - The groups of services that depend on each other in a cycle; each
+///   group shares a layer. Sorted, as are the IDs within a group.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyLayers {
+    pub layers: Vec<Vec<String>>,
+    pub cycles: Vec<Vec<String>>,
+}
+
+/// Assigns every service to a layer.
+///
+/// With `seed_by_type`, frontends are forced into layer 0 and databases into
+/// the last layer, whatever their relationships say, which matches how
+/// architecture diagrams are usually drawn. A cycle group is only forced if
+/// all of its services are of that type.
+///
+/// # Arguments
+///
+/// * `services` - All services in the environment
+/// * `relationships` - All relationships in the environment; only depends_on counts
+/// * `seed_by_type` - Force frontends first and databases last
+///
+/// # Returns
+///
+/// The layers, without empty ones, and the cycle groups
+pub fn dependency_layers<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    seed_by_type: bool,
+) -> DependencyLayers {
+    let mut services: Vec<&Service> = services.iter().map(Borrow::borrow).collect();
+    services.sort_by(|a, b| a.id.cmp(&b.id));
+    services.dedup_by(|a, b| a.id == b.id);
+    if services.is_empty() {
+        return DependencyLayers::default();
+    }
+
+    let position = |id: &str| services.binary_search_by(|s| s.id.as_str().cmp(id)).ok();
+    let mut edges: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for relationship in relationships {
+        if relationship.relationship_type != RelationshipType::DependsOn {
+            continue;
+        }
+        if let (Some(source), Some(target)) = (
+            position(&relationship.source),
+            position(&relationship.target),
+        ) {
+            if source != target {
+                edges.insert((source, target));
+            }
+        }
+    }
+    let mut dependencies: Vec<Vec<usize>> = vec![Vec::new(); services.len()];
+    for (source, target) in edges {
+        dependencies[source].push(target);
+    }
+
+    let components = strongly_connected_components(&dependencies);
+    let mut component_of = vec![0; services.len()];
+    for (c, members) in components.iter().enumerate() {
+        for &member in members {
+            component_of[member] = c;
+        }
+    }
+
+    let all_of_type = |members: &[usize], service_type: ServiceType| {
+        members
+            .iter()
+            .all(|&m| services[m].service_type == service_type)
+    };
+    let forced_first: Vec<bool> = components
+        .iter()
+        .map(|members| seed_by_type && all_of_type(members, ServiceType::Frontend))
+        .collect();
+    let forced_last: Vec<bool> = components
+        .iter()
+        .map(|members| seed_by_type && all_of_type(members, ServiceType::Database))
+        .collect();
+
+    // Components come out with dependencies before their dependents, so walk
+    // them backwards to see every dependent before its dependencies
+    let mut layer_of = vec![0; components.len()];
+    for (c, members) in components.iter().enumerate().rev() {
+        for &member in members {
+            for &dependency in &dependencies[member] {
+                let d = component_of[dependency];
+                if d != c && !forced_first[d] {
+                    layer_of[d] = layer_of[d].max(layer_of[c] + 1);
+                }
+            }
+        }
+    }
+
+    let last = layer_of.iter().copied().max().unwrap_or(0);
+    for (c, layer) in layer_of.iter_mut().enumerate() {
+        if forced_last[c] {
+            *layer = last;
+        }
+    }
+
+    // Drop layers emptied by forcing databases last
+    let mut used: Vec<usize> = layer_of.clone();
+    used.sort_unstable();
+    used.dedup();
+    let mut layers: Vec<Vec<String>> = vec![Vec::new(); used.len()];
+    for (c, members) in components.iter().enumerate() {
+        let layer = used.binary_search(&layer_of[c]).unwrap_or(0);
+        layers[layer].extend(members.iter().map(|&m| services[m].id.clone()));
+    }
+    for layer in &mut layers {
+        layer.sort();
+    }
+
+    let mut cycles: Vec<Vec<String>> = components
+        .iter()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let mut ids: Vec<String> = members.iter().map(|&m| services[m].id.clone()).collect();
+            ids.sort();
+            ids
+        })
+        .collect();
+    cycles.sort();
+
+    DependencyLayers { layers, cycles }
+}
+
+/// Finds the strongly connected components of a graph with Tarjan's algorithm.
+///
+/// Iterative, so long dependency chains can't overflow the stack.
+///
+/// # Returns
+///
+/// The components, each after every component reachable from it
+pub fn strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut next_index = 0;
+    let mut index: Vec<Option<usize>> = vec![None; edges.len()];
+    let mut lowlink = vec![0; edges.len()];
+    let mut on_stack = vec![false; edges.len()];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+
+    for root in 0..edges.len() {
+        if index[root].is_some() {
+            continue;
+        }
+
+        // (node, number of its edges followed so far)
+        let mut call_stack = vec![(root, 0)];
+        index[root] = Some(next_index);
+        lowlink[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&(node, followed)) = call_stack.last() {
+            if let Some(&next) = edges[node].get(followed) {
+                if let Some(last) = call_stack.last_mut() {
+                    last.1 += 1;
+                }
+                match index[next] {
+                    None => {
+                        index[next] = Some(next_index);
+                        lowlink[next] = next_index;
+                        next_index += 1;
+                        stack.push(next);
+                        on_stack[next] = true;
+                        call_stack.push((next, 0));
+                    }
+                    Some(next_at) if on_stack[next] => {
+                        lowlink[node] = lowlink[node].min(next_at);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[node]);
+            }
+            if Some(lowlink[node]) == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
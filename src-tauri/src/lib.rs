@@ -1,12 +1,51 @@
+pub mod cli;
 mod commands;
+mod consul;
+mod criticality;
+mod cycles;
+mod dates;
+mod descriptions;
+mod diff;
+mod drawio;
+mod edges;
 mod error;
+mod export;
+mod git;
+mod github;
+mod health;
+mod http_api;
+mod kubernetes;
+mod layers;
+mod lifecycle;
+mod manifest;
+mod merge;
+mod metadata_schema;
 mod models;
+mod naming;
+mod nginx;
+mod ports;
+mod probes;
+mod read_only;
+mod redaction;
+mod sample;
+mod scheduler;
+mod similarity;
 mod state;
 mod storage;
+#[cfg(test)]
+mod test_support;
+mod watcher;
+mod webhooks;
 
-use state::AppState;
-use std::sync::Mutex;
-use tauri::Manager;
+use git::GitCommitter;
+use http_api::HttpApi;
+use scheduler::Scheduler;
+use state::{read_state, AppState};
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::ipc::Invoke;
+use tauri::{Manager, Runtime};
+use watcher::DataWatcher;
+use webhooks::WebhookSender;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -19,29 +58,223 @@ pub fn run() {
                 .app_data_dir()
                 .expect("Failed to get app data directory");
 
-            app.manage(Mutex::new(AppState::new(data_path)));
+            let config_dir = app
+                .path()
+                .app_config_dir()
+                .expect("Failed to get app config directory");
+
+            let mut app_state = AppState::new(data_path);
+            app_state.load_settings(config_dir.join(storage::SETTINGS_FILE));
+
+            // Open the workspace that was active when the app was last closed
+            if let Some(name) = app_state.settings.active_workspace.clone() {
+                let workspace = app_state.settings.workspace(&name).cloned();
+                match workspace.map(|w| commands::environments::check_data_path(&w.path)) {
+                    Some(Ok(path)) => app_state.switch_workspace(&name, path),
+                    Some(Err(e)) => eprintln!("Warning: not opening workspace {}: {}", name, e),
+                    None => eprintln!("Warning: active workspace {} no longer exists", name),
+                }
+            }
+            app_state.load_recent_services(config_dir.join(storage::RECENT_SERVICES_FILE));
+            let http_api_settings = app_state.settings.http_api.clone();
+            let data_path = app_state.data_path.clone();
+            // Operations are cancelled without the state lock, which they may hold
+            app.manage(Arc::clone(&app_state.running_operations));
+            app.manage(RwLock::new(app_state));
+
+            // Serve the read-only HTTP API when it is enabled in the settings
+            let mut http_api = HttpApi::default();
+            if let Err(e) = http_api.apply(app.handle(), &http_api_settings) {
+                eprintln!("Warning: failed to start HTTP API: {}", e);
+            }
+            app.manage(Mutex::new(http_api));
+
+            // Commit data changes in the background when git auto-commit is enabled
+            app.manage(GitCommitter::start(app.handle().clone()));
+
+            // Send webhook notifications in the background when a webhook is configured
+            app.manage(WebhookSender::start(app.handle().clone()));
+
+            // Run scheduled snapshots and exports when they are enabled in the settings
+            app.manage(Scheduler::start(app.handle().clone()));
+
+            // Watch the data directory for external changes (e.g., git pull)
+            match DataWatcher::start(app.handle().clone(), &data_path) {
+                Ok(data_watcher) => {
+                    app.manage(Mutex::new(data_watcher));
+                }
+                Err(e) => eprintln!("Warning: failed to start data directory watcher: {}", e),
+            }
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            commands::services::get_all_services,
-            commands::services::get_service_by_id,
-            commands::services::search_services,
-            commands::services::save_service,
-            commands::services::delete_service,
-            commands::graph::get_service_graph,
-            commands::environments::list_environments,
-            commands::environments::get_current_environment,
-            commands::environments::switch_environment,
-            commands::environments::set_data_path,
-            commands::environments::create_environment,
-            commands::relationships::get_all_relationships,
-            commands::relationships::get_relationships_for_service,
-            commands::relationships::save_relationship,
-            commands::relationships::delete_relationship,
-            commands::relationships::delete_relationships_for_service,
-            commands::validation::validate_environment,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler(invoke_handler())
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Stop the HTTP API so its port is released before the process exits
+            if let tauri::RunEvent::Exit = event {
+                if let Ok(mut http_api) = app.state::<Mutex<HttpApi>>().lock() {
+                    http_api.stop();
+                }
+                // Write the views the save thread hasn't written yet
+                read_state(&app.state::<RwLock<AppState>>())
+                    .recent_services
+                    .flush();
+            }
+        });
+}
+
+/// Builds the handler of every command the frontend can invoke, guarded by
+/// read-only mode (see `read_only`).
+fn invoke_handler<R: Runtime>() -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    read_only::guard(tauri::generate_handler![
+        commands::services::get_all_services,
+        commands::services::get_service_summaries,
+        commands::services::get_service_by_id,
+        commands::services::service_exists,
+        commands::services::generate_id,
+        commands::services::search_services,
+        commands::services::quick_open,
+        commands::services::save_service,
+        commands::services::delete_service,
+        commands::graph::get_service_graph,
+        commands::graph::expand_graph_node,
+        commands::graph::get_effective_status,
+        commands::graph::get_criticality_ranking,
+        commands::graph::get_dependency_layers,
+        commands::graph::compare_service_dependencies,
+        commands::graph::is_reachable,
+        commands::graph::are_reachable,
+        commands::graph::suggest_cycle_breaks,
+        commands::graph::get_grouped_graph,
+        commands::simulation::simulate_graph,
+        commands::graph::export_graph,
+        commands::graph::export_grafana_nodegraph,
+        commands::graph::export_graph_d2,
+        commands::drawio::export_graph_drawio,
+        commands::drawio::import_drawio,
+        commands::graph::export_deployment_manifest,
+        commands::graph::export_deployment_manifests,
+        commands::annotations::get_graph_annotations,
+        commands::annotations::save_graph_annotations,
+        commands::views::save_view,
+        commands::views::list_views,
+        commands::views::get_view,
+        commands::views::delete_view,
+        commands::views::apply_view,
+        commands::custom_types::list_custom_types,
+        commands::custom_types::add_custom_type,
+        commands::custom_types::rename_custom_type,
+        commands::custom_types::delete_custom_type,
+        commands::custom_types::discover_custom_types,
+        commands::custom_types::normalize_custom_types,
+        commands::metadata::get_relationship_metadata_report,
+        commands::metadata::get_service_metadata_report,
+        commands::metadata::rename_relationship_metadata_key,
+        commands::metadata::rename_service_metadata_key,
+        commands::snapshots::create_snapshot,
+        commands::snapshots::list_snapshots,
+        commands::snapshots::diff_snapshot,
+        commands::snapshots::delete_snapshot,
+        commands::environments::list_environments,
+        commands::environments::get_current_environment,
+        commands::environments::switch_environment,
+        commands::environments::set_data_path,
+        commands::environments::validate_data_path,
+        commands::environments::get_recent_data_paths,
+        commands::environments::create_environment,
+        commands::environments::migrate_environment,
+        commands::environments::normalize_environment_files,
+        commands::environments::migrate_relationship_storage,
+        commands::environments::migrate_to_sqlite,
+        commands::environments::get_counts,
+        commands::environments::generate_sample_environment,
+        commands::extraction::extract_to_environment,
+        commands::relationships::get_all_relationships,
+        commands::relationships::get_relationships_for_service,
+        commands::relationships::get_relationship_counts,
+        commands::placeholders::create_placeholder_services,
+        commands::relationships::save_relationship,
+        commands::relationships::backfill_relationship_descriptions,
+        commands::relationships::set_relationship_review,
+        commands::relationships::retarget_relationships,
+        commands::relationships::resource_relationships,
+        commands::relationships::delete_relationship,
+        commands::relationships::delete_relationships_for_service,
+        commands::relationships::delete_relationships_for_service_preview,
+        commands::groups::list_groups,
+        commands::groups::save_group,
+        commands::groups::delete_group,
+        commands::groups::add_group_members,
+        commands::groups::remove_group_members,
+        commands::ownership::get_ownership_report,
+        commands::ownership::export_ownership_report,
+        commands::external::get_external_dependencies,
+        commands::external::export_external_dependencies,
+        commands::reconciliation::reconcile_declared_dependencies,
+        commands::reconciliation::apply_reconciliation,
+        commands::kubernetes::import_from_kubernetes_cluster,
+        commands::consul::import_from_consul,
+        commands::probes::probe_service_health,
+        commands::drift::check_drift,
+        commands::github::enrich_from_github,
+        commands::nginx::import_nginx_config,
+        commands::validation::validate_environment,
+        commands::validation::validate_relationship_draft,
+        commands::teams::get_team_overview,
+        commands::teams::transfer_ownership,
+        commands::validation::repair_filenames,
+        commands::validation::fix_validation_issues,
+        commands::validation::find_possible_duplicates,
+        commands::validation::get_deprecated_usage,
+        commands::validation::get_decommission_plan,
+        commands::validation::get_tier_report,
+        commands::validation::export_validation_report,
+        commands::validation::get_metadata_schema,
+        commands::validation::save_metadata_schema,
+        commands::validation::get_validation_config,
+        commands::validation::save_validation_config,
+        commands::validation::suppress_validation_issue,
+        commands::validation::list_suppressions,
+        commands::validation::remove_suppression,
+        commands::settings::get_settings,
+        commands::settings::update_settings,
+        commands::settings::set_read_only,
+        commands::settings::pin_service,
+        commands::settings::unpin_service,
+        commands::settings::get_pinned_services,
+        commands::settings::get_recent_services,
+        commands::settings::get_webhook_config,
+        commands::settings::set_webhook_config,
+        commands::settings::test_webhook,
+        commands::settings::get_display_config,
+        commands::settings::save_display_config,
+        commands::settings::get_graph_preferences,
+        commands::settings::save_graph_preferences,
+        commands::scheduler::get_scheduled_task_history,
+        commands::scheduler::run_scheduled_export_now,
+        commands::git::get_git_status,
+        commands::encryption::set_encryption_passphrase,
+        commands::encryption::clear_encryption_passphrase,
+        commands::encryption::migrate_encryption,
+        commands::storage::get_storage_report,
+        commands::cache::refresh_environment,
+        commands::cache::get_cache_info,
+        commands::cache::preload_environment,
+        commands::cache::clear_cache,
+        commands::diagnostics::get_diagnostics,
+        commands::diagnostics::reset_diagnostics,
+        commands::operations::list_running_operations,
+        commands::operations::cancel_operation,
+        commands::workspaces::list_workspaces,
+        commands::workspaces::add_workspace,
+        commands::workspaces::remove_workspace,
+        commands::workspaces::switch_workspace,
+        commands::history::undo,
+        commands::history::redo,
+        commands::history::get_undo_history,
+        commands::audit::get_audit_log,
+        commands::batch::execute_batch,
+    ])
 }
@@ -0,0 +1,84 @@
+//! Lifecycle checks for services.
+//!
+//! A service can record where it is in its lifecycle (see
+//! `models::ServiceLifecycle`), when it is to be decommissioned and which
+//! service replaces it. Saves reject lifecycle fields that can't be right;
+//! `validate_environment` and `get_decommission_plan` track the migration.
+//!
+//! Decommission dates are `YYYY-MM-DD` strings (see `dates`), compared with
+//! today's UTC date.
+
+use crate::dates::is_valid_date;
+use crate::error::AppError;
+use crate::models::Service;
+use crate::storage::StorageBackend;
+
+/// Returns true if a service's decommission date has passed.
+///
+/// A missing or malformed date never has.
+pub fn is_past_decommission_date(service: &Service, today: &str) -> bool {
+    service
+        .decommission_date
+        .as_deref()
+        .is_some_and(|date| is_valid_date(date) && date < today)
+}
+
+/// Rejects lifecycle fields of a service about to be saved that can't be right.
+///
+/// # Arguments
+///
+/// * `backend` - The storage backend, to look up the successor
+/// * `environment` - The environment the service is saved to
+/// * `service` - The service about to be saved
+///
+/// # Returns
+///
+/// * `Ok(())` - If the fields are consistent
+/// * `Err(AppError::ValidationError)` - If the decommission date isn't a `YYYY-MM-DD`
+///   date, or the successor is the service itself or doesn't exist
+/// * `Err(AppError)` - If the successor cannot be looked up
+pub fn enforce(
+    backend: &dyn StorageBackend,
+    environment: &str,
+    service: &Service,
+) -> Result<(), AppError> {
+    enforce_with(service, |successor_id| {
+        backend.service_exists(environment, successor_id)
+    })
+}
+
+/// Rejects lifecycle fields like `enforce`, asking `exists` whether the
+/// successor exists.
+///
+/// Batches use this to see services created or deleted by their earlier
+/// operations.
+pub fn enforce_with(
+    service: &Service,
+    exists: impl FnOnce(&str) -> Result<bool, AppError>,
+) -> Result<(), AppError> {
+    if let Some(date) = &service.decommission_date {
+        if !is_valid_date(date) {
+            return Err(AppError::ValidationError(format!(
+                "Decommission date '{}' of service '{}' must be a date like 2025-09-30",
+                date, service.id
+            )));
+        }
+    }
+
+    if let Some(successor_id) = &service.successor_id {
+        if *successor_id == service.id {
+            return Err(AppError::ValidationError(format!(
+                "Service '{}' cannot be its own successor",
+                service.id
+            )));
+        }
+        if !exists(successor_id)? {
+            return Err(AppError::ValidationError(format!(
+                "Successor '{}' of service '{}' does not exist",
+                successor_id, service.id
+            )));
+        }
+    }
+
+    Ok(())
+}
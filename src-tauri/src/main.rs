@@ -2,5 +2,38 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // Run headless CLI commands (e.g. `validate` in CI) without starting the GUI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if dependency_mapping_tool_lib::cli::is_command(&args) {
+        attach_parent_console();
+    }
+    if let Some(code) = dependency_mapping_tool_lib::cli::run(&args) {
+        std::process::exit(code);
+    }
+
     dependency_mapping_tool_lib::run()
 }
+
+/// Connects stdout and stderr to the console the app was started from.
+///
+/// Release builds on Windows are GUI programs, which Windows starts without
+/// a console, so CLI output would go nowhere. Without a parent console (e.g.
+/// started from Explorer) this does nothing; redirected output is unaffected.
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // Fails if the parent has no console or this process already has one
+    // (debug builds); both are fine
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
@@ -0,0 +1,340 @@
+//! Deployment manifests: what must be healthy before a service is deployed.
+//!
+//! A deploy pipeline gets the answer from the map instead of a hand-kept
+//! list. The prerequisites of a service are the services it reaches by
+//! following depends_on relationships transitively (optionally reads_from
+//! and writes_to too), in the order they can be checked or deployed: every
+//! prerequisite comes after its own prerequisites. Prerequisites depending
+//! on each other in a cycle can't be ordered, so building a manifest fails
+//! with the cycle named.
+//!
+//! Manifests are rendered as JSON or YAML. The YAML is written here rather
+//! than by a YAML library: scalars and metadata values are written as JSON,
+//! which every YAML 1.2 parser reads as is.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::error::AppError;
+use crate::models::{Relationship, RelationshipType, Service, ServiceType};
+
+/// Formats a deployment manifest can be written in.
+///
+/// # Variants
+///
+/// * `Yaml` - A YAML document (the default)
+/// * `Json` - A pretty-printed JSON document
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestFormat {
+    #[default]
+    Yaml,
+    Json,
+}
+
+impl ManifestFormat {
+    /// Parses a format as it is serialized (e.g., `"yaml"`).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "yaml" => Some(Self::Yaml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Returns the file extension of the format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// A service that must be healthy before the manifest's service is deployed.
+///
+/// # Fields
+///
+/// * `id` - The service's ID
+/// * `name` - The service's display name
+/// * `service_type` - The service's type
+/// * `tier` - The service's criticality tier, if it has one
+/// * `external` - Whether it is an external service, outside the pipeline's control
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prerequisite {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub service_type: ServiceType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tier: Option<u8>,
+    pub external: bool,
+}
+
+/// A relationship of the manifest's service to one of its direct prerequisites.
+///
+/// # Fields
+///
+/// * `id` - The relationship's ID
+/// * `target` - The prerequisite
+/// * `relationship_type` - The relationship's type
+/// * `metadata` - The relationship's metadata, e.g. the port or connection pool
+///   size, sorted by key; empty if it has none
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestRelationship {
+    pub id: String,
+    pub target: String,
+    #[serde(rename = "type")]
+    pub relationship_type: RelationshipType,
+    pub metadata: BTreeMap<String, Value>,
+}
+
+/// What must be healthy before a service is deployed.
+///
+/// # Fields
+///
+/// * `environment` - The environment the manifest was built from
+/// * `service_id` - The service to deploy
+/// * `prerequisites` - Its transitive prerequisites, each after its own
+/// * `relationships` - Its relationships to its direct prerequisites, sorted by target
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentManifest {
+    pub environment: String,
+    pub service_id: String,
+    pub prerequisites: Vec<Prerequisite>,
+    pub relationships: Vec<ManifestRelationship>,
+}
+
+/// Builds the deployment manifest of a service.
+///
+/// # Arguments
+///
+/// * `environment` - The name of the environment
+/// * `services` - All services of the environment
+/// * `relationships` - The relationships to follow, e.g. those in effect today
+/// * `service_id` - The service to deploy
+/// * `include_data_flow` - Also follow reads_from and writes_to relationships
+///
+/// # Returns
+///
+/// * `Ok(DeploymentManifest)` - The manifest
+/// * `Err(AppError::ServiceNotFound)` - If the service doesn't exist
+/// * `Err(AppError::ValidationError)` - If prerequisites depend on each other in a
+///   cycle, or a followed relationship points at a service that doesn't exist
+pub fn build<S: Borrow<Service>>(
+    environment: &str,
+    services: &[S],
+    relationships: &[Relationship],
+    service_id: &str,
+    include_data_flow: bool,
+) -> Result<DeploymentManifest, AppError> {
+    let services_by_id: HashMap<&str, &Service> = services
+        .iter()
+        .map(|s| (s.borrow().id.as_str(), s.borrow()))
+        .collect();
+    if !services_by_id.contains_key(service_id) {
+        return Err(AppError::ServiceNotFound(service_id.to_string()));
+    }
+
+    // Followed relationships by source, sorted by target for a stable order
+    let mut outgoing: HashMap<&str, Vec<&Relationship>> = HashMap::new();
+    for relationship in relationships.iter().filter(|r| {
+        r.relationship_type == RelationshipType::DependsOn
+            || include_data_flow
+                && matches!(
+                    r.relationship_type,
+                    RelationshipType::ReadsFrom | RelationshipType::WritesTo
+                )
+    }) {
+        outgoing
+            .entry(relationship.source.as_str())
+            .or_default()
+            .push(relationship);
+    }
+    for edges in outgoing.values_mut() {
+        edges.sort_by(|a, b| a.target.cmp(&b.target).then_with(|| a.id.cmp(&b.id)));
+    }
+
+    let mut order = Ordering {
+        outgoing: &outgoing,
+        services_by_id: &services_by_id,
+        finished: Vec::new(),
+        path: Vec::new(),
+    };
+    order.visit(service_id)?;
+
+    let prerequisites = order
+        .finished
+        .iter()
+        .filter(|id| **id != service_id)
+        .map(|id| {
+            let service = services_by_id[id];
+            Prerequisite {
+                id: service.id.clone(),
+                name: service.name.clone(),
+                service_type: service.service_type.clone(),
+                tier: service.tier,
+                external: service.service_type == ServiceType::External,
+            }
+        })
+        .collect();
+
+    let relationships = outgoing
+        .get(service_id)
+        .into_iter()
+        .flatten()
+        .map(|relationship| ManifestRelationship {
+            id: relationship.id.clone(),
+            target: relationship.target.clone(),
+            relationship_type: relationship.relationship_type.clone(),
+            metadata: relationship
+                .metadata
+                .iter()
+                .flatten()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        })
+        .collect();
+
+    Ok(DeploymentManifest {
+        environment: environment.to_string(),
+        service_id: service_id.to_string(),
+        prerequisites,
+        relationships,
+    })
+}
+
+/// Depth-first ordering of the prerequisites, for `build`.
+///
+/// # Fields
+///
+/// * `outgoing` - The followed relationships by source
+/// * `services_by_id` - All services by ID
+/// * `finished` - Services whose prerequisites are all ordered, in order
+/// * `path` - The services being visited, to name a cycle when one closes
+struct Ordering<'a> {
+    outgoing: &'a HashMap<&'a str, Vec<&'a Relationship>>,
+    services_by_id: &'a HashMap<&'a str, &'a Service>,
+    finished: Vec<&'a str>,
+    path: Vec<&'a str>,
+}
+
+impl<'a> Ordering<'a> {
+    /// Orders a service after its prerequisites.
+    fn visit(&mut self, id: &'a str) -> Result<(), AppError> {
+        if self.finished.contains(&id) {
+            return Ok(());
+        }
+        if let Some(start) = self.path.iter().position(|visiting| *visiting == id) {
+            let mut cycle = self.path[start..].to_vec();
+            cycle.push(id);
+            return Err(AppError::ValidationError(format!(
+                "Prerequisites of '{}' depend on each other in a cycle, so they can't be \
+                 ordered: {}",
+                self.path[0],
+                cycle.join(" -> ")
+            )));
+        }
+
+        self.path.push(id);
+        for relationship in self.outgoing.get(id).into_iter().flatten() {
+            let target = relationship.target.as_str();
+            if !self.services_by_id.contains_key(target) {
+                return Err(AppError::ValidationError(format!(
+                    "Relationship '{}' points at service '{}', which doesn't exist",
+                    relationship.id, target
+                )));
+            }
+            self.visit(target)?;
+        }
+        self.path.pop();
+        self.finished.push(id);
+
+        Ok(())
+    }
+}
+
+impl DeploymentManifest {
+    /// Renders the manifest.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The document, ending with a newline
+    /// * `Err(AppError::Json)` - If a metadata value can't be serialized
+    pub fn render(&self, format: ManifestFormat) -> Result<String, AppError> {
+        match format {
+            ManifestFormat::Json => Ok(serde_json::to_string_pretty(self)? + "\n"),
+            ManifestFormat::Yaml => self.render_yaml(),
+        }
+    }
+
+    /// Renders the manifest as YAML, with the same keys as the JSON.
+    fn render_yaml(&self) -> Result<String, AppError> {
+        let mut yaml = format!(
+            "# Deployment manifest of {} in {}\nenvironment: {}\nserviceId: {}\n",
+            self.service_id,
+            self.environment,
+            yaml_string(&self.environment),
+            yaml_string(&self.service_id)
+        );
+
+        yaml.push_str(if self.prerequisites.is_empty() {
+            "prerequisites: []\n"
+        } else {
+            "prerequisites:\n"
+        });
+        for prerequisite in &self.prerequisites {
+            yaml.push_str(&format!("  - id: {}\n", yaml_string(&prerequisite.id)));
+            yaml.push_str(&format!("    name: {}\n", yaml_string(&prerequisite.name)));
+            yaml.push_str(&format!(
+                "    type: {}\n",
+                yaml_string(prerequisite.service_type.as_str())
+            ));
+            if let Some(tier) = prerequisite.tier {
+                yaml.push_str(&format!("    tier: {}\n", tier));
+            }
+            yaml.push_str(&format!("    external: {}\n", prerequisite.external));
+        }
+
+        yaml.push_str(if self.relationships.is_empty() {
+            "relationships: []\n"
+        } else {
+            "relationships:\n"
+        });
+        for relationship in &self.relationships {
+            yaml.push_str(&format!("  - id: {}\n", yaml_string(&relationship.id)));
+            yaml.push_str(&format!(
+                "    target: {}\n",
+                yaml_string(&relationship.target)
+            ));
+            yaml.push_str(&format!(
+                "    type: {}\n",
+                yaml_string(relationship.relationship_type.as_str())
+            ));
+            if relationship.metadata.is_empty() {
+                yaml.push_str("    metadata: {}\n");
+                continue;
+            }
+            yaml.push_str("    metadata:\n");
+            for (key, value) in &relationship.metadata {
+                yaml.push_str(&format!(
+                    "      {}: {}\n",
+                    yaml_string(key),
+                    serde_json::to_string(value)?
+                ));
+            }
+        }
+
+        Ok(yaml)
+    }
+}
+
+/// Quotes a string for YAML, as a JSON string.
+fn yaml_string(value: &str) -> String {
+    Value::from(value).to_string()
+}
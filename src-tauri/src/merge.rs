@@ -0,0 +1,676 @@
+//! Merging imported services and relationships into existing ones.
+//!
+//! Every importer has to decide what happens when something it imports
+//! already exists. They all decide it here: an importer picks a
+//! `MergeStrategy` for each class of fields (see `MergeStrategies`) and
+//! passes each incoming item, with the stored one it matches, to a `Merger`.
+//! The merger returns what to save, or nothing if the stored item stays as
+//! it is, and collects a `MergeConflict` for every field the two sides
+//! disagreed on, with the side that won.
+//!
+//! A side without a value (no value, an empty string, list or object)
+//! doesn't disagree with the other side; it is filled in unless the
+//! strategy keeps the existing value. Overwriting a value with nothing
+//! does count as a conflict.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::{Relationship, Service};
+
+/// How a class of fields is merged when an imported item already exists.
+///
+/// # Variants
+///
+/// * `Overwrite` - The incoming values replace the existing ones, even if empty
+/// * `KeepExisting` - The existing values are kept as they are
+/// * `Merge` - Tags are combined, metadata is merged key by key at every depth,
+///   and other fields take the incoming value unless it is empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    Overwrite,
+    KeepExisting,
+    Merge,
+}
+
+/// The strategy an importer uses for each class of fields.
+///
+/// Relationships are matched on their endpoints and type, so only their
+/// `details` and `metadata` are merged.
+///
+/// # Fields
+///
+/// * `name` - The service name
+/// * `service_type` - The service type
+/// * `status` - The service status
+/// * `details` - The optional fields: description, version, owner, team, tier,
+///   lifecycle, decommission date and successor of services, and description
+///   and effective dates of relationships
+/// * `tags` - The service tags
+/// * `metadata` - The metadata of services and relationships
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeStrategies {
+    pub name: MergeStrategy,
+    pub service_type: MergeStrategy,
+    pub status: MergeStrategy,
+    pub details: MergeStrategy,
+    pub tags: MergeStrategy,
+    pub metadata: MergeStrategy,
+}
+
+/// The kind of item a conflict is about.
+///
+/// # Variants
+///
+/// * `Service` - A service, identified by its ID
+/// * `Relationship` - A relationship, identified by its ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergedItem {
+    Service,
+    Relationship,
+}
+
+/// Which side of a conflict ended up in the saved item.
+///
+/// # Variants
+///
+/// * `Existing` - The stored value was kept
+/// * `Incoming` - The imported value replaced it
+/// * `Merged` - The two were combined, e.g. the union of both tag lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictWinner {
+    Existing,
+    Incoming,
+    Merged,
+}
+
+/// A field the imported and the stored item disagreed on.
+///
+/// # Fields
+///
+/// * `item_type` - Whether the item is a service or a relationship
+/// * `item_id` - The ID of the stored item
+/// * `field` - The field, as named in the data files; metadata is named by its
+///   path, e.g. `metadata.kubernetes.namespace`
+/// * `existing` - The stored value
+/// * `incoming` - The imported value
+/// * `winner` - Which side was saved
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    pub item_type: MergedItem,
+    pub item_id: String,
+    pub field: String,
+    pub existing: Value,
+    pub incoming: Value,
+    pub winner: ConflictWinner,
+}
+
+/// Merges the items of one import, collecting the conflicts.
+#[derive(Debug)]
+pub struct Merger {
+    strategies: MergeStrategies,
+    conflicts: Vec<MergeConflict>,
+}
+
+impl Merger {
+    /// Creates a merger that merges with the given strategies.
+    pub fn new(strategies: MergeStrategies) -> Self {
+        Merger {
+            strategies,
+            conflicts: Vec::new(),
+        }
+    }
+
+    /// Merges an imported service into the stored one it matches.
+    ///
+    /// The stored service's ID and revision are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `existing` - The stored service, if the import matched one
+    /// * `incoming` - The imported service
+    ///
+    /// # Returns
+    ///
+    /// The service to save: `incoming` if nothing was stored, or the merged
+    /// service. `None` if the stored service is unchanged.
+    pub fn service(&mut self, existing: Option<&Service>, incoming: Service) -> Option<Service> {
+        let Some(existing) = existing else {
+            return Some(incoming);
+        };
+
+        let strategies = self.strategies;
+        let mut merged = existing.clone();
+        let mut fields = FieldMerger {
+            item_type: MergedItem::Service,
+            item_id: &existing.id,
+            conflicts: &mut self.conflicts,
+        };
+        fields.field("name", strategies.name, &mut merged.name, &incoming.name);
+        fields.field(
+            "serviceType",
+            strategies.service_type,
+            &mut merged.service_type,
+            &incoming.service_type,
+        );
+        fields.field(
+            "status",
+            strategies.status,
+            &mut merged.status,
+            &incoming.status,
+        );
+
+        let details = strategies.details;
+        fields.field(
+            "description",
+            details,
+            &mut merged.description,
+            &incoming.description,
+        );
+        fields.field("version", details, &mut merged.version, &incoming.version);
+        fields.field("owner", details, &mut merged.owner, &incoming.owner);
+        fields.field("team", details, &mut merged.team, &incoming.team);
+        fields.field("tier", details, &mut merged.tier, &incoming.tier);
+        fields.field(
+            "lifecycle",
+            details,
+            &mut merged.lifecycle,
+            &incoming.lifecycle,
+        );
+        fields.field(
+            "decommissionDate",
+            details,
+            &mut merged.decommission_date,
+            &incoming.decommission_date,
+        );
+        fields.field(
+            "successorId",
+            details,
+            &mut merged.successor_id,
+            &incoming.successor_id,
+        );
+
+        fields.tags(strategies.tags, &mut merged.tags, &incoming.tags);
+        fields.metadata(
+            strategies.metadata,
+            &mut merged.metadata,
+            &incoming.metadata,
+        );
+
+        (!same_content(existing, &merged)).then_some(merged)
+    }
+
+    /// Merges an imported relationship into the stored one it matches.
+    ///
+    /// The stored relationship's ID, endpoints, type, origin and revision
+    /// are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `existing` - The stored relationship, if the import matched one
+    /// * `incoming` - The imported relationship
+    ///
+    /// # Returns
+    ///
+    /// The relationship to save: `incoming` if nothing was stored, or the
+    /// merged relationship. `None` if the stored relationship is unchanged.
+    pub fn relationship(
+        &mut self,
+        existing: Option<&Relationship>,
+        incoming: Relationship,
+    ) -> Option<Relationship> {
+        let Some(existing) = existing else {
+            return Some(incoming);
+        };
+
+        let strategies = self.strategies;
+        let mut merged = existing.clone();
+        let mut fields = FieldMerger {
+            item_type: MergedItem::Relationship,
+            item_id: &existing.id,
+            conflicts: &mut self.conflicts,
+        };
+        let details = strategies.details;
+        fields.field(
+            "description",
+            details,
+            &mut merged.description,
+            &incoming.description,
+        );
+        fields.field(
+            "effectiveFrom",
+            details,
+            &mut merged.effective_from,
+            &incoming.effective_from,
+        );
+        fields.field(
+            "effectiveUntil",
+            details,
+            &mut merged.effective_until,
+            &incoming.effective_until,
+        );
+
+        let mut metadata = merged.metadata.take().unwrap_or_default();
+        let incoming_metadata = incoming.metadata.unwrap_or_default();
+        fields.metadata(strategies.metadata, &mut metadata, &incoming_metadata);
+        merged.metadata = (!metadata.is_empty() || existing.metadata.is_some()).then_some(metadata);
+
+        (!same_content(existing, &merged)).then_some(merged)
+    }
+
+    /// Returns the conflicts of every item merged so far, in merge order.
+    pub fn into_conflicts(self) -> Vec<MergeConflict> {
+        self.conflicts
+    }
+}
+
+/// Returns true if two items serialize to the same data.
+fn same_content<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Merges the fields of one item, recording conflicts against it.
+struct FieldMerger<'a> {
+    item_type: MergedItem,
+    item_id: &'a str,
+    conflicts: &'a mut Vec<MergeConflict>,
+}
+
+impl FieldMerger<'_> {
+    /// Merges one field, taking the incoming value unless the strategy keeps
+    /// the existing one (or it is `Merge` and the incoming value is empty).
+    fn field<T>(&mut self, name: &str, strategy: MergeStrategy, existing: &mut T, incoming: &T)
+    where
+        T: Clone + Serialize,
+    {
+        let existing_value = to_value(existing);
+        let incoming_value = to_value(incoming);
+        if existing_value == incoming_value {
+            return;
+        }
+
+        let takes_incoming = match strategy {
+            MergeStrategy::Overwrite => true,
+            MergeStrategy::KeepExisting => false,
+            MergeStrategy::Merge => !is_empty(&incoming_value),
+        };
+        let disagrees = !is_empty(&existing_value)
+            && (!is_empty(&incoming_value) || strategy == MergeStrategy::Overwrite);
+        if disagrees {
+            let winner = if takes_incoming {
+                ConflictWinner::Incoming
+            } else {
+                ConflictWinner::Existing
+            };
+            self.conflict(name, existing_value, incoming_value, winner);
+        }
+        if takes_incoming {
+            *existing = incoming.clone();
+        }
+    }
+
+    /// Merges tag lists; `Merge` appends the incoming tags that are missing.
+    fn tags(&mut self, strategy: MergeStrategy, existing: &mut Vec<String>, incoming: &[String]) {
+        if strategy != MergeStrategy::Merge {
+            self.field("tags", strategy, existing, &incoming.to_vec());
+            return;
+        }
+
+        let existing_set: BTreeSet<&String> = existing.iter().collect();
+        let incoming_set: BTreeSet<&String> = incoming.iter().collect();
+        if existing_set == incoming_set || incoming.is_empty() {
+            return;
+        }
+        if !existing.is_empty() {
+            let (existing_value, incoming_value) = (to_value(&*existing), to_value(incoming));
+            self.conflict(
+                "tags",
+                existing_value,
+                incoming_value,
+                ConflictWinner::Merged,
+            );
+        }
+        for tag in incoming {
+            if !existing.contains(tag) {
+                existing.push(tag.clone());
+            }
+        }
+    }
+
+    /// Merges metadata; `Merge` and `KeepExisting` compare it key by key at
+    /// every depth, `Overwrite` replaces it as a whole.
+    fn metadata(
+        &mut self,
+        strategy: MergeStrategy,
+        existing: &mut HashMap<String, Value>,
+        incoming: &HashMap<String, Value>,
+    ) {
+        if strategy == MergeStrategy::Overwrite {
+            let mut keys: Vec<&String> = existing.keys().chain(incoming.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let mut value = existing.get(key).cloned().unwrap_or(Value::Null);
+                let incoming_value = incoming.get(key).cloned().unwrap_or(Value::Null);
+                self.field(
+                    &format!("metadata.{}", key),
+                    strategy,
+                    &mut value,
+                    &incoming_value,
+                );
+            }
+            *existing = incoming.clone();
+            return;
+        }
+
+        let mut keys: Vec<&String> = incoming.keys().collect();
+        keys.sort();
+        for key in keys {
+            let incoming_value = &incoming[key];
+            let path = format!("metadata.{}", key);
+            match existing.get_mut(key) {
+                Some(value) => self.value(&path, strategy, value, incoming_value),
+                None if strategy == MergeStrategy::Merge => {
+                    existing.insert(key.clone(), incoming_value.clone());
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Merges a metadata value, recursing into objects on both sides.
+    fn value(
+        &mut self,
+        path: &str,
+        strategy: MergeStrategy,
+        existing: &mut Value,
+        incoming: &Value,
+    ) {
+        let (Value::Object(existing), Value::Object(incoming)) = (&mut *existing, incoming) else {
+            self.field(path, strategy, existing, incoming);
+            return;
+        };
+
+        for (key, incoming_value) in incoming {
+            let path = format!("{}.{}", path, key);
+            match existing.get_mut(key) {
+                Some(value) => self.value(&path, strategy, value, incoming_value),
+                None if strategy == MergeStrategy::Merge => {
+                    existing.insert(key.clone(), incoming_value.clone());
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Records a conflict of the item.
+    fn conflict(&mut self, field: &str, existing: Value, incoming: Value, winner: ConflictWinner) {
+        self.conflicts.push(MergeConflict {
+            item_type: self.item_type,
+            item_id: self.item_id.to_string(),
+            field: field.to_string(),
+            existing,
+            incoming,
+            winner,
+        });
+    }
+}
+
+/// Serializes a field value for comparison and the conflict report.
+fn to_value<T: Serialize + ?Sized>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Returns true if a value is missing or empty.
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(object) => object.is_empty(),
+        Value::Bool(_) | Value::Number(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ServiceStatus;
+    use crate::test_support::{relationship, service};
+    use serde_json::json;
+
+    const MERGE_ALL: MergeStrategies = MergeStrategies {
+        name: MergeStrategy::Merge,
+        service_type: MergeStrategy::Merge,
+        status: MergeStrategy::Merge,
+        details: MergeStrategy::Merge,
+        tags: MergeStrategy::Merge,
+        metadata: MergeStrategy::Merge,
+    };
+
+    const KEEP_ALL: MergeStrategies = MergeStrategies {
+        name: MergeStrategy::KeepExisting,
+        service_type: MergeStrategy::KeepExisting,
+        status: MergeStrategy::KeepExisting,
+        details: MergeStrategy::KeepExisting,
+        tags: MergeStrategy::KeepExisting,
+        metadata: MergeStrategy::KeepExisting,
+    };
+
+    fn tagged(id: &str, tags: &[&str]) -> Service {
+        Service {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..service(id)
+        }
+    }
+
+    fn conflict_fields(conflicts: &[MergeConflict]) -> Vec<(&str, ConflictWinner)> {
+        conflicts
+            .iter()
+            .map(|conflict| (conflict.field.as_str(), conflict.winner))
+            .collect()
+    }
+
+    #[test]
+    fn merging_tags_appends_the_missing_ones_in_order() {
+        let mut merger = Merger::new(MERGE_ALL);
+        let existing = tagged("api", &["team-a", "public"]);
+
+        let merged = merger
+            .service(
+                Some(&existing),
+                tagged("api", &["public", "grpc", "team-a"]),
+            )
+            .unwrap();
+
+        assert_eq!(merged.tags, vec!["team-a", "public", "grpc"]);
+        let conflicts = merger.into_conflicts();
+        assert_eq!(
+            conflict_fields(&conflicts),
+            vec![("tags", ConflictWinner::Merged)]
+        );
+        assert_eq!(conflicts[0].existing, json!(["team-a", "public"]));
+        assert_eq!(conflicts[0].incoming, json!(["public", "grpc", "team-a"]));
+    }
+
+    #[test]
+    fn the_same_tags_in_another_order_change_nothing() {
+        let mut merger = Merger::new(MERGE_ALL);
+        let existing = tagged("api", &["a", "b"]);
+
+        assert!(merger
+            .service(Some(&existing), tagged("api", &["b", "a"]))
+            .is_none());
+        assert!(merger.into_conflicts().is_empty());
+    }
+
+    #[test]
+    fn tags_filling_an_empty_list_are_no_conflict() {
+        let mut merger = Merger::new(MERGE_ALL);
+
+        let merged = merger
+            .service(Some(&service("api")), tagged("api", &["a"]))
+            .unwrap();
+
+        assert_eq!(merged.tags, vec!["a"]);
+        assert!(merger.into_conflicts().is_empty());
+    }
+
+    #[test]
+    fn merging_metadata_merges_nested_objects_key_by_key() {
+        let mut merger = Merger::new(MERGE_ALL);
+        let mut existing = service("api");
+        existing.metadata = HashMap::from([
+            (
+                "kubernetes".to_string(),
+                json!({ "namespace": "shop", "replicas": 2, "labels": { "app": "api" } }),
+            ),
+            ("runbook".to_string(), json!("https://wiki/api")),
+        ]);
+        let mut incoming = service("api");
+        incoming.metadata = HashMap::from([
+            (
+                "kubernetes".to_string(),
+                json!({ "namespace": "store", "labels": { "tier": "web" } }),
+            ),
+            ("image".to_string(), json!("shop/api:2.0")),
+        ]);
+
+        let merged = merger.service(Some(&existing), incoming).unwrap();
+
+        assert_eq!(
+            merged.metadata,
+            HashMap::from([
+                (
+                    "kubernetes".to_string(),
+                    json!({
+                        "namespace": "store",
+                        "replicas": 2,
+                        "labels": { "app": "api", "tier": "web" }
+                    }),
+                ),
+                ("runbook".to_string(), json!("https://wiki/api")),
+                ("image".to_string(), json!("shop/api:2.0")),
+            ])
+        );
+        let conflicts = merger.into_conflicts();
+        assert_eq!(
+            conflict_fields(&conflicts),
+            vec![("metadata.kubernetes.namespace", ConflictWinner::Incoming)]
+        );
+        assert_eq!(conflicts[0].existing, json!("shop"));
+        assert_eq!(conflicts[0].incoming, json!("store"));
+    }
+
+    #[test]
+    fn merging_prefers_non_empty_descriptions() {
+        let mut merger = Merger::new(MERGE_ALL);
+        let mut existing = service("api");
+        existing.description = Some("Public API".to_string());
+        existing.owner = Some("alice".to_string());
+        let mut incoming = service("api");
+        incoming.description = Some(String::new());
+        incoming.owner = Some("bob".to_string());
+
+        let merged = merger.service(Some(&existing), incoming).unwrap();
+
+        assert_eq!(merged.description.as_deref(), Some("Public API"));
+        assert_eq!(merged.owner.as_deref(), Some("bob"));
+        assert_eq!(
+            conflict_fields(&merger.into_conflicts()),
+            vec![("owner", ConflictWinner::Incoming)]
+        );
+    }
+
+    #[test]
+    fn keeping_existing_values_changes_nothing_and_reports_every_disagreement() {
+        let mut merger = Merger::new(KEEP_ALL);
+        let mut existing = tagged("api", &["a"]);
+        existing.name = "API".to_string();
+        existing.metadata = HashMap::from([("kubernetes".to_string(), json!({ "ns": "shop" }))]);
+        let mut incoming = tagged("api", &["b"]);
+        incoming.name = "api-server".to_string();
+        incoming.status = ServiceStatus::Degraded;
+        incoming.description = Some("Imported".to_string());
+        incoming.metadata = HashMap::from([
+            (
+                "kubernetes".to_string(),
+                json!({ "ns": "store", "extra": 1 }),
+            ),
+            ("image".to_string(), json!("shop/api:2.0")),
+        ]);
+
+        assert!(merger.service(Some(&existing), incoming).is_none());
+        assert_eq!(
+            conflict_fields(&merger.into_conflicts()),
+            vec![
+                ("name", ConflictWinner::Existing),
+                ("status", ConflictWinner::Existing),
+                ("tags", ConflictWinner::Existing),
+                ("metadata.kubernetes.ns", ConflictWinner::Existing),
+            ]
+        );
+    }
+
+    #[test]
+    fn overwriting_with_nothing_is_a_conflict() {
+        let strategies = MergeStrategies {
+            details: MergeStrategy::Overwrite,
+            ..KEEP_ALL
+        };
+        let mut merger = Merger::new(strategies);
+        let mut existing = service("api");
+        existing.description = Some("Public API".to_string());
+
+        let merged = merger.service(Some(&existing), service("api")).unwrap();
+
+        assert_eq!(merged.description, None);
+        assert_eq!(
+            conflict_fields(&merger.into_conflicts()),
+            vec![("description", ConflictWinner::Incoming)]
+        );
+    }
+
+    #[test]
+    fn a_new_item_is_saved_as_imported() {
+        let mut merger = Merger::new(KEEP_ALL);
+
+        assert_eq!(
+            merger.service(None, tagged("api", &["a"])).unwrap().tags,
+            vec!["a"]
+        );
+        assert!(merger
+            .relationship(None, relationship("r1", "api", "db"))
+            .is_some());
+        assert!(merger.into_conflicts().is_empty());
+    }
+
+    #[test]
+    fn relationships_keep_their_identity_and_merge_metadata() {
+        let mut merger = Merger::new(MERGE_ALL);
+        let mut existing = relationship("r1", "api", "db");
+        existing.metadata = Some(HashMap::from([(
+            "nginx".to_string(),
+            json!({ "locations": ["/a"] }),
+        )]));
+        let mut incoming = relationship("imported", "api", "db");
+        incoming.metadata = Some(HashMap::from([("port".to_string(), json!(5432))]));
+
+        let merged = merger.relationship(Some(&existing), incoming).unwrap();
+
+        assert_eq!(merged.id, "r1");
+        let metadata = merged.metadata.unwrap();
+        assert_eq!(metadata["nginx"], json!({ "locations": ["/a"] }));
+        assert_eq!(metadata["port"], json!(5432));
+        assert!(merger.into_conflicts().is_empty());
+
+        let mut merger = Merger::new(MERGE_ALL);
+        assert!(merger
+            .relationship(Some(&existing), relationship("imported", "api", "db"))
+            .is_none());
+    }
+}
@@ -0,0 +1,140 @@
+//! Validation of service metadata against per-type JSON Schemas.
+//!
+//! Schemas are stored per service type (see `storage::metadata_schemas`)
+//! and compiled on first use. Used by `validate_environment`, which reports
+//! violations as warnings, and by saves, which reject them when the
+//! validation config's `strictMetadataSchemas` is set.
+
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::models::{IssueType, Service, ValidationConfig};
+use crate::storage;
+
+/// Compiles a metadata schema.
+///
+/// # Arguments
+///
+/// * `service_type` - The service type the schema is for, used in the error
+/// * `schema` - The JSON Schema
+///
+/// # Returns
+///
+/// * `Ok(JSONSchema)` - The compiled schema
+/// * `Err(AppError::ValidationError)` - If the schema isn't a valid JSON Schema
+pub fn compile(service_type: &str, schema: &Value) -> Result<JSONSchema, AppError> {
+    JSONSchema::compile(schema).map_err(|e| {
+        AppError::ValidationError(format!(
+            "Invalid metadata schema for service type '{}': {}",
+            service_type, e
+        ))
+    })
+}
+
+/// The metadata schemas of a data path, loaded and compiled per type on demand.
+pub struct MetadataSchemas<'a> {
+    data_path: &'a Path,
+    compiled: HashMap<String, Option<JSONSchema>>,
+}
+
+impl<'a> MetadataSchemas<'a> {
+    /// Creates an empty schema set for a data path.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path` - The data directory or SQLite database file
+    pub fn new(data_path: &'a Path) -> Self {
+        Self {
+            data_path,
+            compiled: HashMap::new(),
+        }
+    }
+
+    /// Checks a service's metadata against the schema for its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The service to check
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` - One message per failed property, naming it and why it
+    ///   failed (empty if the metadata conforms or the type has no schema)
+    /// * `Err(AppError::ValidationError)` - If the type's schema is invalid
+    /// * `Err(AppError::FileLoad)` - If the type's schema file isn't valid JSON
+    pub fn check(&mut self, service: &Service) -> Result<Vec<String>, AppError> {
+        let service_type = service.service_type.as_str();
+        if !self.compiled.contains_key(service_type) {
+            let schema = match storage::load_metadata_schema(self.data_path, service_type) {
+                Ok(Some(schema)) => Some(compile(service_type, &schema)?),
+                Ok(None) => None,
+                // Types that can't name a file can't have a schema either
+                Err(AppError::ValidationError(_)) => None,
+                Err(e) => return Err(e),
+            };
+            self.compiled.insert(service_type.to_string(), schema);
+        }
+
+        let Some(Some(schema)) = self.compiled.get(service_type) else {
+            return Ok(Vec::new());
+        };
+
+        let metadata = Value::Object(
+            service
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        );
+
+        let messages = match schema.validate(&metadata) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .map(|error| {
+                    format!(
+                        "Metadata of service '{}' at 'metadata{}' fails the '{}' schema: {}",
+                        service.id, error.instance_path, service_type, error
+                    )
+                })
+                .collect(),
+        };
+
+        Ok(messages)
+    }
+}
+
+/// Rejects a service whose metadata fails its type's schema, if schemas are strict.
+///
+/// A disabled `metadata_schema_violation` rule is not enforced either.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `config` - The validation config of the data path
+/// * `service` - The service about to be saved
+///
+/// # Returns
+///
+/// * `Ok(())` - If schemas aren't strict, the type has none or the metadata conforms
+/// * `Err(AppError::ValidationError)` - Listing every failed property, or if the schema
+///   is invalid
+/// * `Err(AppError::FileLoad)` - If the schema file isn't valid JSON
+pub fn enforce(
+    data_path: &Path,
+    config: &ValidationConfig,
+    service: &Service,
+) -> Result<(), AppError> {
+    if !config.strict_metadata_schemas || !config.is_enabled(IssueType::MetadataSchemaViolation) {
+        return Ok(());
+    }
+
+    let violations = MetadataSchemas::new(data_path).check(service)?;
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::ValidationError(violations.join("; ")))
+    }
+}
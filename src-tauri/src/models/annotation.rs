@@ -0,0 +1,39 @@
+//! Graph node annotation data model definitions.
+//!
+//! Annotations hold what users add to the graph view on top of the data:
+//! where a node was dragged to, a sticky note and a highlight color. They
+//! are kept per environment, keyed by service ID, so they follow the user
+//! between machines.
+
+use serde::{Deserialize, Serialize};
+
+/// The position and notes of one node in the graph view.
+///
+/// # Example JSON
+///
+/// ```json
+/// {
+///   "x": 120.5,
+///   "y": -40.0,
+///   "pinnedPosition": true,
+///   "note": "Being replaced by billing-v2 in Q3",
+///   "color": "#f59e0b"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeAnnotation {
+    /// Horizontal position of the node.
+    pub x: f64,
+    /// Vertical position of the node.
+    pub y: f64,
+    /// Whether layouts should keep the node at its position.
+    #[serde(default)]
+    pub pinned_position: bool,
+    /// Optional sticky-note comment.
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Optional highlight color, as a CSS color.
+    #[serde(default)]
+    pub color: Option<String>,
+}
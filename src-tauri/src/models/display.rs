@@ -0,0 +1,391 @@
+//! Display configuration model.
+//!
+//! The display config is the one palette the graph view and every exporter
+//! draw with: a color (and optional icon) per service type and status, and
+//! a color and line style per relationship type. It is part of the settings
+//! (see `Settings::display`); types it doesn't list use the built-in
+//! defaults, and custom types without an entry get a color derived from
+//! their name, so they look the same in every session and export.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::models::{RelationshipType, ServiceStatus, ServiceType};
+
+/// Built-in colors of the service types.
+const SERVICE_TYPE_COLORS: &[(&str, &str)] = &[
+    ("api", "#3182CE"),
+    ("backend", "#38A169"),
+    ("cache", "#E53E3E"),
+    ("database", "#DD6B20"),
+    ("external", "#718096"),
+    ("frontend", "#00B5D8"),
+    ("gateway", "#805AD5"),
+    ("identity-provider", "#D53F8C"),
+    ("queue", "#D69E2E"),
+];
+
+/// Built-in colors of the service statuses.
+const STATUS_COLORS: &[(&str, &str)] = &[
+    ("degraded", "#ECC94B"),
+    ("deprecated", "#718096"),
+    ("healthy", "#48BB78"),
+    ("unhealthy", "#F56565"),
+    ("unknown", "#A0AEC0"),
+];
+
+/// Built-in colors and line styles of the relationship types.
+const RELATIONSHIP_STYLES: &[(&str, &str, LineStyle)] = &[
+    ("authenticates_via", "#805AD5", LineStyle::Dashed),
+    ("communicates_with", "#3182CE", LineStyle::Solid),
+    ("depends_on", "#E53E3E", LineStyle::Solid),
+    ("publishes", "#D69E2E", LineStyle::Dotted),
+    ("reads_from", "#38A169", LineStyle::Solid),
+    ("subscribes", "#00B5D8", LineStyle::Dotted),
+    ("writes_to", "#DD6B20", LineStyle::Solid),
+];
+
+/// How an edge's line is drawn.
+///
+/// # Variants
+///
+/// * `Dashed` - Long dashes
+/// * `Dotted` - Dots
+/// * `Solid` - A continuous line; also used for unknown styles in a saved config
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineStyle {
+    Dashed,
+    Dotted,
+    #[default]
+    #[serde(other)]
+    Solid,
+}
+
+impl LineStyle {
+    /// Returns the style as it is serialized, which is also its Graphviz name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineStyle::Dashed => "dashed",
+            LineStyle::Dotted => "dotted",
+            LineStyle::Solid => "solid",
+        }
+    }
+
+    /// Returns the SVG `stroke-dasharray` drawing the style, empty for solid lines.
+    pub fn dash_array(&self) -> &'static str {
+        match self {
+            LineStyle::Dashed => "5 5",
+            LineStyle::Dotted => "2 2",
+            LineStyle::Solid => "",
+        }
+    }
+}
+
+/// How the nodes of a service type or status are drawn.
+///
+/// # Fields
+///
+/// * `color` - A CSS color, e.g. `#3182CE`
+/// * `icon` - Optional icon name for the UI and the Grafana node graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStyle {
+    pub color: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+/// How the edges of a relationship type are drawn.
+///
+/// # Fields
+///
+/// * `color` - A CSS color, e.g. `#E53E3E`
+/// * `line_style` - Solid, dashed or dotted (default: solid)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EdgeStyle {
+    pub color: String,
+    #[serde(default)]
+    pub line_style: LineStyle,
+}
+
+/// Colors, icons and line styles for drawing graphs.
+///
+/// Maps are keyed by the type or status as it is serialized (e.g.
+/// `"database"`, `"depends_on"`). The default config lists the built-in
+/// types and statuses; a saved config can leave any of them out or add
+/// custom types. Fields and entries a version doesn't know are ignored.
+///
+/// # Fields
+///
+/// * `service_types` - Node style per service type
+/// * `statuses` - Node style per service status, drawn as the node's border
+/// * `relationship_types` - Edge style per relationship type
+///
+/// # Example JSON
+///
+/// ```json
+/// {
+///   "serviceTypes": { "lambda": { "color": "#FF9900", "icon": "bolt" } },
+///   "statuses": { "healthy": { "color": "#48BB78" } },
+///   "relationshipTypes": { "replicates_to": { "color": "#319795", "lineStyle": "dashed" } }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DisplayConfig {
+    pub service_types: BTreeMap<String, NodeStyle>,
+    pub statuses: BTreeMap<String, NodeStyle>,
+    pub relationship_types: BTreeMap<String, EdgeStyle>,
+}
+
+impl Default for DisplayConfig {
+    /// Returns the built-in palette.
+    fn default() -> Self {
+        let node_styles = |colors: &[(&str, &str)]| -> BTreeMap<String, NodeStyle> {
+            colors
+                .iter()
+                .map(|(name, color)| (name.to_string(), node_style(color)))
+                .collect()
+        };
+
+        Self {
+            service_types: node_styles(SERVICE_TYPE_COLORS),
+            statuses: node_styles(STATUS_COLORS),
+            relationship_types: RELATIONSHIP_STYLES
+                .iter()
+                .map(|(name, color, line_style)| {
+                    let style = EdgeStyle {
+                        color: color.to_string(),
+                        line_style: *line_style,
+                    };
+                    (name.to_string(), style)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Returns the style of a service type: its entry, else the built-in
+    /// default, else a color derived from the name.
+    pub fn service_type_style(&self, service_type: &ServiceType) -> NodeStyle {
+        let name = service_type.as_str();
+        self.service_types
+            .get(name)
+            .cloned()
+            .or_else(|| built_in_color(SERVICE_TYPE_COLORS, name).map(node_style))
+            .unwrap_or_else(|| node_style(&hashed_color(name)))
+    }
+
+    /// Returns the style of a service status: its entry, else the built-in default.
+    pub fn status_style(&self, status: &ServiceStatus) -> NodeStyle {
+        let name = status.as_str();
+        self.statuses
+            .get(name)
+            .cloned()
+            .or_else(|| built_in_color(STATUS_COLORS, name).map(node_style))
+            .unwrap_or_else(|| node_style(&hashed_color(name)))
+    }
+
+    /// Returns the style of a relationship type: its entry, else the
+    /// built-in default, else a solid line in a color derived from the name.
+    pub fn relationship_type_style(&self, relationship_type: &RelationshipType) -> EdgeStyle {
+        let name = relationship_type.as_str();
+        if let Some(style) = self.relationship_types.get(name) {
+            return style.clone();
+        }
+
+        match RELATIONSHIP_STYLES
+            .iter()
+            .find(|(built_in, _, _)| *built_in == name)
+        {
+            Some((_, color, line_style)) => EdgeStyle {
+                color: color.to_string(),
+                line_style: *line_style,
+            },
+            None => EdgeStyle {
+                color: hashed_color(name),
+                line_style: LineStyle::Solid,
+            },
+        }
+    }
+
+    /// Returns every color of the config with the key it belongs to, e.g.
+    /// `("serviceTypes.api", "#3182CE")`.
+    pub fn colors(&self) -> Vec<(String, &str)> {
+        let mut colors = Vec::new();
+        for (section, styles) in [
+            ("serviceTypes", &self.service_types),
+            ("statuses", &self.statuses),
+        ] {
+            colors.extend(
+                styles
+                    .iter()
+                    .map(|(name, style)| (format!("{}.{}", section, name), style.color.as_str())),
+            );
+        }
+        colors.extend(
+            self.relationship_types
+                .iter()
+                .map(|(name, style)| (format!("relationshipTypes.{}", name), style.color.as_str())),
+        );
+        colors
+    }
+}
+
+/// Returns a node style with only a color.
+fn node_style(color: &str) -> NodeStyle {
+    NodeStyle {
+        color: color.to_string(),
+        icon: None,
+    }
+}
+
+/// Looks up a built-in color by type or status name.
+fn built_in_color<'a>(colors: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    colors
+        .iter()
+        .find(|(built_in, _)| *built_in == name)
+        .map(|(_, color)| *color)
+}
+
+/// Derives a color from a name, for types without a configured color.
+///
+/// The hue comes from the name's 32-bit FNV-1a hash, with a fixed
+/// saturation and lightness, so the same name always gets the same color
+/// and different names are usually easy to tell apart. The frontend
+/// derives the same colors (see `hashedColor` in
+/// `services/graphTransforms.ts`).
+pub fn hashed_color(name: &str) -> String {
+    let hash = name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    let hue = f64::from(hash % 360);
+
+    // HSL to RGB with 55% saturation and 50% lightness
+    let chroma = 0.55;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = 0.5 - chroma / 2.0;
+    let channel = |value: f64| ((value + m) * 255.0).round() as u8;
+
+    format!("#{:02X}{:02X}{:02X}", channel(r), channel(g), channel(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unknown_keys_in_a_saved_config_are_ignored() {
+        let saved = json!({
+            "serviceTypes": {
+                "database": { "color": "#000000", "icon": "db", "shape": "cylinder" },
+                "lambda": { "color": "#FF9900" }
+            },
+            "relationshipTypes": {
+                "depends_on": { "color": "#111111", "lineStyle": "wavy", "width": 3 }
+            },
+            "edgeLabels": { "visible": false },
+            "theme": "dark"
+        });
+
+        let config: DisplayConfig = serde_json::from_value(saved).unwrap();
+
+        assert_eq!(
+            config.service_types["database"],
+            NodeStyle {
+                color: "#000000".to_string(),
+                icon: Some("db".to_string()),
+            }
+        );
+        assert_eq!(
+            config.relationship_types["depends_on"],
+            EdgeStyle {
+                color: "#111111".to_string(),
+                line_style: LineStyle::Solid,
+            }
+        );
+        assert_eq!(config.statuses, DisplayConfig::default().statuses);
+    }
+
+    #[test]
+    fn types_a_saved_config_leaves_out_use_the_built_in_defaults() {
+        let config: DisplayConfig =
+            serde_json::from_value(json!({ "serviceTypes": { "api": { "color": "red" } } }))
+                .unwrap();
+
+        assert_eq!(config.service_type_style(&ServiceType::Api).color, "red");
+        assert_eq!(
+            config.service_type_style(&ServiceType::Database).color,
+            "#DD6B20"
+        );
+        assert_eq!(
+            config.status_style(&ServiceStatus::Healthy).color,
+            "#48BB78"
+        );
+        assert_eq!(
+            config.relationship_type_style(&RelationshipType::Publishes),
+            EdgeStyle {
+                color: "#D69E2E".to_string(),
+                line_style: LineStyle::Dotted,
+            }
+        );
+    }
+
+    #[test]
+    fn the_default_config_lists_every_built_in_entry() {
+        let config = DisplayConfig::default();
+
+        assert_eq!(config.service_types.len(), SERVICE_TYPE_COLORS.len());
+        assert_eq!(config.statuses.len(), STATUS_COLORS.len());
+        assert_eq!(config.relationship_types.len(), RELATIONSHIP_STYLES.len());
+        let round_tripped: DisplayConfig =
+            serde_json::from_value(serde_json::to_value(&config).unwrap()).unwrap();
+        assert_eq!(round_tripped, config);
+    }
+
+    #[test]
+    fn custom_types_get_stable_hashed_colors() {
+        let config = DisplayConfig::default();
+        let lambda = ServiceType::Custom("lambda".to_string());
+        let replicates_to = RelationshipType::Custom("replicates_to".to_string());
+
+        // The values `hashedColor` in the frontend derives for the same names
+        assert_eq!(config.service_type_style(&lambda).color, "#A5C639");
+        assert_eq!(
+            config.relationship_type_style(&replicates_to),
+            EdgeStyle {
+                color: "#39C6B1".to_string(),
+                line_style: LineStyle::Solid,
+            }
+        );
+        assert_eq!(hashed_color("lambda"), hashed_color("lambda"));
+        assert_ne!(hashed_color("lambda"), hashed_color("lambdas"));
+    }
+
+    #[test]
+    fn a_configured_custom_type_beats_its_hashed_color() {
+        let mut config = DisplayConfig::default();
+        config
+            .service_types
+            .insert("lambda".to_string(), node_style("#FF9900"));
+
+        assert_eq!(
+            config
+                .service_type_style(&ServiceType::Custom("lambda".to_string()))
+                .color,
+            "#FF9900"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Environment metadata model definitions.
+//!
+//! Environment metadata holds the per-environment storage options and
+//! import history, kept in `{environment}/environment.json` next to the
+//! environment's data. An environment without the file uses the defaults.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How an environment's relationships are stored on disk.
+///
+/// # Variants
+///
+/// * `Single` - All relationships in one `relationships.json` (the default)
+/// * `Sharded` - One `relationships/{source_service_id}.json` per source service,
+///   so saving a relationship only rewrites the file of its source
+///
+/// # Serialization
+///
+/// Layouts are serialized as snake_case strings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationshipLayout {
+    #[default]
+    Single,
+    Sharded,
+}
+
+/// Storage options and import history of one environment.
+///
+/// # Example JSON
+///
+/// ```json
+/// {
+///   "relationshipLayout": "sharded",
+///   "lastImports": { "kubernetes": 1727690000000 },
+///   "importCounts": { "kubernetes": { "services": 42, "relationships": 57 } }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentMetadata {
+    /// How the environment's relationships are stored.
+    #[serde(default)]
+    pub relationship_layout: RelationshipLayout,
+    /// When each import source last synced the environment, in milliseconds since
+    /// the Unix epoch, keyed by the source of `RelationshipOrigin::Imported`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub last_imports: BTreeMap<String, u64>,
+    /// What each import source held when it last synced the environment, keyed
+    /// like `last_imports`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub import_counts: BTreeMap<String, ImportCounts>,
+}
+
+/// How many services and relationships an import source held when it synced.
+///
+/// # Fields
+///
+/// * `services` - Services mapped from the source
+/// * `relationships` - Relationships (edges or routes) mapped from the source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCounts {
+    pub services: usize,
+    pub relationships: usize,
+}
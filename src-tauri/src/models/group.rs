@@ -0,0 +1,39 @@
+//! Service group data model definitions.
+//!
+//! A group is an explicit domain (e.g., "payments" or "identity") that
+//! services belong to, with its own description and owner. Unlike teams and
+//! tags, groups are maintained separately from the services, and a service
+//! can belong to any number of them.
+
+use serde::{Deserialize, Serialize};
+
+/// A named group of services.
+///
+/// # Example JSON
+///
+/// ```json
+/// {
+///   "id": "payments",
+///   "name": "Payments",
+///   "description": "Everything involved in charging customers",
+///   "owner": "payments-lead@company.com",
+///   "memberIds": ["billing-api", "payments-db"]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    /// Unique identifier for the group within its environment.
+    pub id: String,
+    /// Human-readable display name.
+    pub name: String,
+    /// Optional description of what the group covers.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Optional owner email or identifier.
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// IDs of the services in the group.
+    #[serde(default)]
+    pub member_ids: Vec<String>,
+}
@@ -1,5 +1,34 @@
+mod annotation;
+mod display;
+mod environment;
+mod group;
+mod redaction;
 mod relationship;
 mod service;
+mod settings;
+mod snapshot;
+mod sorted_map;
+mod type_registry;
+mod validation;
+mod view;
 
-pub use relationship::{Relationship, RelationshipType, RelationshipsFile};
-pub use service::Service;
+pub use annotation::NodeAnnotation;
+pub use display::{DisplayConfig, LineStyle};
+pub use environment::{EnvironmentMetadata, ImportCounts, RelationshipLayout};
+pub use group::Group;
+pub use redaction::{RedactedField, RedactionAction, RedactionConfig};
+pub use relationship::{
+    Relationship, RelationshipOrigin, RelationshipType, RelationshipsFile, ReviewStatus,
+};
+pub use service::{Service, ServiceLifecycle, ServiceStatus, ServiceSummary, ServiceType};
+pub use settings::{
+    data_path_key, ExportSchedule, ExportTarget, GraphPreferences, HealthProbeSettings,
+    HttpApiSettings, RecentService, RecentViews, RelationshipDescriptionSettings,
+    ScheduledExportSettings, Settings, WebhookConfig, WebhookEvent, Workspace,
+};
+pub use snapshot::{Snapshot, SnapshotInfo};
+pub use type_registry::{CustomTypeDefinition, CustomTypeKind};
+pub use validation::{
+    IssueSeverity, IssueType, NamingRules, PortRules, Suppression, ValidationConfig,
+};
+pub use view::{SavedView, TraversalDirection, ViewSelector};
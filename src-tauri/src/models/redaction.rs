@@ -0,0 +1,95 @@
+//! Redaction configuration model.
+//!
+//! The redaction config says which values exports made with `redact` hide,
+//! e.g. connection strings in metadata or owners' emails before a diagram
+//! is sent to a vendor. It is part of the settings (see
+//! `Settings::redaction`); see `redaction` for how it is applied.
+
+use serde::{Deserialize, Serialize};
+
+/// What happens to a redacted value.
+///
+/// # Variants
+///
+/// * `Mask` - The value is replaced with `"***"` (the default)
+/// * `Strip` - The metadata key or field is left out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionAction {
+    #[default]
+    Mask,
+    Strip,
+}
+
+/// Service fields that can be redacted.
+///
+/// # Variants
+///
+/// * `Description` - The service's description
+/// * `Version` - The deployed version
+/// * `Owner` - The owning person or team, often an email
+/// * `Team` - The owning team
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactedField {
+    Description,
+    Version,
+    Owner,
+    Team,
+}
+
+/// Metadata keys to redact.
+///
+/// # Fields
+///
+/// * `pattern` - Regular expression a key must match as a whole, ignoring case
+/// * `action` - Whether values of matching keys are masked or stripped (default: mask)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataRedaction {
+    pub pattern: String,
+    #[serde(default)]
+    pub action: RedactionAction,
+}
+
+/// A service field to redact.
+///
+/// # Fields
+///
+/// * `field` - The field
+/// * `action` - Whether the field is masked or stripped (default: mask)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldRedaction {
+    pub field: RedactedField,
+    #[serde(default)]
+    pub action: RedactionAction,
+}
+
+/// Which values redacted exports hide.
+///
+/// Metadata keys are matched at every depth of the metadata of services
+/// and relationships. The default config redacts nothing.
+///
+/// # Fields
+///
+/// * `metadata_keys` - Metadata keys to redact; the first matching pattern decides
+/// * `service_fields` - Service fields to redact
+///
+/// # Example JSON
+///
+/// ```json
+/// {
+///   "metadataKeys": [
+///     { "pattern": ".*(password|secret|token).*", "action": "strip" },
+///     { "pattern": "connection_?string|host(name)?" }
+///   ],
+///   "serviceFields": [{ "field": "owner", "action": "mask" }]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RedactionConfig {
+    pub metadata_keys: Vec<MetadataRedaction>,
+    pub service_fields: Vec<FieldRedaction>,
+}
@@ -52,6 +52,111 @@ impl Default for RelationshipType {
     }
 }
 
+impl RelationshipType {
+    /// Returns the type as it is serialized (e.g., `"depends_on"`).
+    pub fn as_str(&self) -> &str {
+        match self {
+            RelationshipType::DependsOn => "depends_on",
+            RelationshipType::CommunicatesWith => "communicates_with",
+            RelationshipType::AuthenticatesVia => "authenticates_via",
+            RelationshipType::ReadsFrom => "reads_from",
+            RelationshipType::WritesTo => "writes_to",
+            RelationshipType::Publishes => "publishes",
+            RelationshipType::Subscribes => "subscribes",
+            RelationshipType::Custom(name) => name,
+        }
+    }
+
+    /// Returns the type serialized as `name`: a built-in type if the name is
+    /// one of theirs (e.g., `"depends_on"`), a custom type otherwise.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "depends_on" => RelationshipType::DependsOn,
+            "communicates_with" => RelationshipType::CommunicatesWith,
+            "authenticates_via" => RelationshipType::AuthenticatesVia,
+            "reads_from" => RelationshipType::ReadsFrom,
+            "writes_to" => RelationshipType::WritesTo,
+            "publishes" => RelationshipType::Publishes,
+            "subscribes" => RelationshipType::Subscribes,
+            _ => RelationshipType::Custom(name.to_string()),
+        }
+    }
+
+    /// Returns the type as a verb phrase for sentences like "api depends on
+    /// db" (e.g., `"depends on"`); custom types are used as they are named.
+    pub fn verb(&self) -> &str {
+        match self {
+            RelationshipType::DependsOn => "depends on",
+            RelationshipType::CommunicatesWith => "communicates with",
+            RelationshipType::AuthenticatesVia => "authenticates via",
+            RelationshipType::ReadsFrom => "reads from",
+            RelationshipType::WritesTo => "writes to",
+            RelationshipType::Publishes => "publishes to",
+            RelationshipType::Subscribes => "subscribes to",
+            RelationshipType::Custom(name) => name,
+        }
+    }
+}
+
+/// Where a relationship came from.
+///
+/// # Variants
+///
+/// * `Manual` - Created or curated by hand (the default, also for relationships
+///   stored before origins existed)
+/// * `Imported(source)` - Created by an importer, e.g. `"kubernetes"` or `"nginx"`
+///
+/// # Serialization
+///
+/// Serialized as `"manual"` or `{ "imported": "kubernetes" }`.
+///
+/// # Re-imports
+///
+/// An importer only updates or removes relationships imported from its own
+/// source, so re-running an import never overwrites manual work.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationshipOrigin {
+    #[default]
+    Manual,
+    Imported(String),
+}
+
+impl RelationshipOrigin {
+    /// Returns true if the relationship was imported from the given source.
+    pub fn is_imported_from(&self, source: &str) -> bool {
+        matches!(self, RelationshipOrigin::Imported(imported) if imported == source)
+    }
+}
+
+/// Where a relationship stands in the architecture review.
+///
+/// # Variants
+///
+/// * `Unreviewed` - Not reviewed yet (the default, also for relationships stored
+///   before reviews existed)
+/// * `Approved` - Signed off by the architecture board
+/// * `Flagged` - Reviewed and questioned; the review note says why
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    #[default]
+    Unreviewed,
+    Approved,
+    Flagged,
+}
+
+impl ReviewStatus {
+    /// Returns the status as it is serialized (e.g., `"approved"`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Unreviewed => "unreviewed",
+            ReviewStatus::Approved => "approved",
+            ReviewStatus::Flagged => "flagged",
+        }
+    }
+}
+
 /// Represents a directed relationship between two services.
 ///
 /// A relationship defines a connection from a source service to a target
@@ -82,10 +187,18 @@ impl Default for RelationshipType {
 ///   "metadata": {
 ///     "protocol": "tcp",
 ///     "port": 5432
-///   }
+///   },
+///   "effectiveFrom": "2025-01-01",
+///   "effectiveUntil": "2025-06-30",
+///   "origin": "manual",
+///   "reviewStatus": "approved",
+///   "reviewNote": "Signed off in the Q1 architecture review",
+///   "reviewedBy": "arch-board@example.com",
+///   "reviewedAt": 1760400000000,
+///   "revision": 1
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Relationship {
     /// Unique identifier for this relationship.
@@ -101,8 +214,58 @@ pub struct Relationship {
     #[serde(default)]
     pub description: Option<String>,
     /// Optional arbitrary metadata for extensibility.
-    #[serde(default)]
+    #[serde(default, serialize_with = "super::sorted_map::serialize_option")]
     pub metadata: Option<HashMap<String, serde_json::Value>>,
+    /// Optional first day the relationship is in effect, as `YYYY-MM-DD`.
+    #[serde(default)]
+    pub effective_from: Option<String>,
+    /// Optional last day the relationship is in effect, as `YYYY-MM-DD`.
+    #[serde(default)]
+    pub effective_until: Option<String>,
+    /// Whether the relationship was created by hand or by an importer (defaults to manual).
+    #[serde(default)]
+    pub origin: RelationshipOrigin,
+    /// Where the relationship stands in the architecture review (defaults to unreviewed).
+    #[serde(default)]
+    pub review_status: ReviewStatus,
+    /// Optional note of the last review, e.g. why the relationship was flagged.
+    #[serde(default)]
+    pub review_note: Option<String>,
+    /// Optional name or email of whoever reviewed the relationship last.
+    #[serde(default)]
+    pub reviewed_by: Option<String>,
+    /// Optional time of the last review, in milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub reviewed_at: Option<u64>,
+    /// Incremented by every save; used to detect concurrent edits
+    /// (relationships written before revisions existed load as 0).
+    #[serde(default)]
+    pub revision: u64,
+}
+
+impl Relationship {
+    /// Describes the relationship in one line (e.g., "api→db (depends_on)").
+    pub fn label(&self) -> String {
+        format!(
+            "{}→{} ({})",
+            self.source,
+            self.target,
+            self.relationship_type.as_str()
+        )
+    }
+
+    /// Returns true if the relationship is in effect on a `YYYY-MM-DD` date.
+    ///
+    /// Both ends are inclusive; a relationship without dates is always in effect.
+    pub fn is_effective_on(&self, date: &str) -> bool {
+        self.effective_from
+            .as_deref()
+            .is_none_or(|from| from <= date)
+            && self
+                .effective_until
+                .as_deref()
+                .is_none_or(|until| date <= until)
+    }
 }
 
 /// Container for the relationships JSON file format.
@@ -114,6 +277,7 @@ pub struct Relationship {
 ///
 /// ```json
 /// {
+///   "schemaVersion": 2,
 ///   "relationships": [
 ///     { "id": "rel-1", "source": "a", "target": "b", ... },
 ///     { "id": "rel-2", "source": "b", "target": "c", ... }
@@ -121,7 +285,16 @@ pub struct Relationship {
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct RelationshipsFile {
+    /// Schema version of the file format (defaults to 1 when absent).
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     /// The list of all relationships in the environment.
     pub relationships: Vec<Relationship>,
 }
+
+/// Returns the schema version assumed for files written before versioning existed.
+fn legacy_schema_version() -> u32 {
+    1
+}
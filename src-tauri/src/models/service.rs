@@ -49,6 +49,39 @@ impl Default for ServiceType {
     }
 }
 
+impl ServiceType {
+    /// Returns the type as it is serialized (e.g., `"api"`).
+    pub fn as_str(&self) -> &str {
+        match self {
+            ServiceType::Api => "api",
+            ServiceType::Database => "database",
+            ServiceType::Cache => "cache",
+            ServiceType::Queue => "queue",
+            ServiceType::Gateway => "gateway",
+            ServiceType::Frontend => "frontend",
+            ServiceType::Backend => "backend",
+            ServiceType::External => "external",
+            ServiceType::Custom(name) => name,
+        }
+    }
+
+    /// Returns the type serialized as `name`: a built-in type if the name is
+    /// one of theirs (e.g., `"api"`), a custom type otherwise.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "api" => ServiceType::Api,
+            "database" => ServiceType::Database,
+            "cache" => ServiceType::Cache,
+            "queue" => ServiceType::Queue,
+            "gateway" => ServiceType::Gateway,
+            "frontend" => ServiceType::Frontend,
+            "backend" => ServiceType::Backend,
+            "external" => ServiceType::External,
+            _ => ServiceType::Custom(name.to_string()),
+        }
+    }
+}
+
 /// The operational status of a service.
 ///
 /// Used to indicate the current health state of a service for
@@ -65,7 +98,7 @@ impl Default for ServiceType {
 /// # Serialization
 ///
 /// Statuses are serialized as snake_case strings.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum ServiceStatus {
     Healthy,
@@ -82,6 +115,40 @@ impl Default for ServiceStatus {
     }
 }
 
+impl ServiceStatus {
+    /// Returns the status as it is serialized (e.g., `"healthy"`).
+    pub fn as_str(&self) -> &str {
+        match self {
+            ServiceStatus::Healthy => "healthy",
+            ServiceStatus::Degraded => "degraded",
+            ServiceStatus::Unhealthy => "unhealthy",
+            ServiceStatus::Unknown => "unknown",
+            ServiceStatus::Deprecated => "deprecated",
+        }
+    }
+}
+
+/// Where a service is in its lifecycle, independent of its health.
+///
+/// # Variants
+///
+/// * `Planned` - Not built or not in production yet
+/// * `Active` - In use
+/// * `Deprecated` - Being replaced; nothing new should depend on it
+/// * `Decommissioned` - Shut down; nothing should depend on it any more
+///
+/// # Serialization
+///
+/// Lifecycle stages are serialized as snake_case strings.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceLifecycle {
+    Planned,
+    Active,
+    Deprecated,
+    Decommissioned,
+}
+
 /// Represents a service in the dependency graph.
 ///
 /// A service is any distinct component in the architecture that can have
@@ -115,11 +182,16 @@ impl Default for ServiceStatus {
 ///   "version": "2.1.0",
 ///   "owner": "auth-team@company.com",
 ///   "team": "Authentication Team",
+///   "tier": 1,
+///   "lifecycle": "active",
+///   "decommissionDate": null,
+///   "successorId": null,
 ///   "tags": ["auth", "users", "core"],
 ///   "metadata": {
 ///     "repository": "https://github.com/company/user-service",
 ///     "port": 8080
-///   }
+///   },
+///   "revision": 3
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -147,12 +219,28 @@ pub struct Service {
     /// Optional team name responsible for the service.
     #[serde(default)]
     pub team: Option<String>,
+    /// Optional criticality tier, from 1 (critical) to 4 (best-effort).
+    #[serde(default)]
+    pub tier: Option<u8>,
+    /// Optional lifecycle stage, e.g. deprecated while a successor takes over.
+    #[serde(default)]
+    pub lifecycle: Option<ServiceLifecycle>,
+    /// Optional planned decommission date, as `YYYY-MM-DD`.
+    #[serde(default)]
+    pub decommission_date: Option<String>,
+    /// Optional ID of the service replacing this one.
+    #[serde(default)]
+    pub successor_id: Option<String>,
     /// Tags for filtering and categorization.
     #[serde(default)]
     pub tags: Vec<String>,
     /// Arbitrary key-value metadata for extensibility.
-    #[serde(default)]
+    #[serde(default, serialize_with = "super::sorted_map::serialize")]
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Incremented by every save; used to detect concurrent edits
+    /// (files written before revisions existed load as 0).
+    #[serde(default)]
+    pub revision: u64,
 }
 
 impl Service {
@@ -165,6 +253,7 @@ impl Service {
     /// - `description`
     /// - `owner`
     /// - `team`
+    /// - `tier`, written as "tier-1"
     /// - Any tag in `tags`
     ///
     /// # Arguments
@@ -177,7 +266,7 @@ impl Service {
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// let service = Service {
     ///     id: "user-api".to_string(),
     ///     name: "User API Service".to_string(),
@@ -194,18 +283,80 @@ impl Service {
 
         self.name.to_lowercase().contains(&query_lower)
             || self.id.to_lowercase().contains(&query_lower)
-            || self.description
+            || self
+                .description
                 .as_ref()
                 .map(|d| d.to_lowercase().contains(&query_lower))
                 .unwrap_or(false)
-            || self.owner
+            || self
+                .owner
                 .as_ref()
                 .map(|o| o.to_lowercase().contains(&query_lower))
                 .unwrap_or(false)
-            || self.team
+            || self
+                .team
                 .as_ref()
                 .map(|t| t.to_lowercase().contains(&query_lower))
                 .unwrap_or(false)
-            || self.tags.iter().any(|tag| tag.to_lowercase().contains(&query_lower))
+            || self
+                .tier
+                .is_some_and(|tier| format!("tier-{}", tier).contains(&query_lower))
+            || self
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&query_lower))
+    }
+}
+
+/// A lightweight view of a service for list views.
+///
+/// Carries only what the services list displays, so listing thousands of
+/// services doesn't ship descriptions and metadata maps to the frontend.
+///
+/// # Fields
+///
+/// * `id` - The service ID
+/// * `name` - The display name
+/// * `service_type` - The service type
+/// * `status` - The operational status
+/// * `team` - The responsible team, if any
+/// * `tier` - The criticality tier, if any
+/// * `tag_count` - Number of tags
+/// * `has_description` - Whether the service has a non-empty description
+/// * `incoming_count` - Number of relationships targeting the service; only set by
+///   `get_service_summaries`
+/// * `outgoing_count` - Number of relationships from the service; only set by
+///   `get_service_summaries`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceSummary {
+    pub id: String,
+    pub name: String,
+    pub service_type: ServiceType,
+    pub status: ServiceStatus,
+    pub team: Option<String>,
+    pub tier: Option<u8>,
+    pub tag_count: usize,
+    pub has_description: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incoming_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outgoing_count: Option<usize>,
+}
+
+impl From<&Service> for ServiceSummary {
+    fn from(service: &Service) -> Self {
+        Self {
+            id: service.id.clone(),
+            name: service.name.clone(),
+            service_type: service.service_type.clone(),
+            status: service.status.clone(),
+            team: service.team.clone(),
+            tier: service.tier,
+            tag_count: service.tags.len(),
+            has_description: service.description.as_ref().is_some_and(|d| !d.is_empty()),
+            incoming_count: None,
+            outgoing_count: None,
+        }
     }
 }
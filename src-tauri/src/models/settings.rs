@@ -0,0 +1,434 @@
+//! Application settings model.
+//!
+//! Settings are user preferences that persist across sessions. They are
+//! stored in `settings.json` in the application config directory, separate
+//! from the data directory, so they survive switching data paths.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::models::{
+    DisplayConfig, RedactionConfig, ServiceStatus, ServiceType, TraversalDirection,
+};
+
+/// User-configurable application settings.
+///
+/// Every field has a default, so settings files written by older versions
+/// of the application (or missing fields in general) load without error.
+///
+/// # Fields
+///
+/// * `git_auto_commit` - Commit every data change to the data directory's git repository
+/// * `pinned_services` - Pinned service IDs by data path (see `data_path_key`) and
+///   environment, in the order they were pinned, so same-named environments of two
+///   workspaces have pins of their own. IDs of deleted services are kept in case
+///   the services come back.
+/// * `http_api` - The embedded read-only HTTP API for other tools (off by default)
+/// * `webhook` - Where to send notifications about data changes, if anywhere
+/// * `scheduled_export` - Periodic snapshots or exports while the app runs (off by default)
+/// * `workspaces` - Named data paths to switch between, e.g. one per client
+/// * `active_workspace` - The workspace opened at startup; `None` opens the app data directory
+/// * `recent_data_paths` - Data paths set via `set_data_path`, most recent first
+/// * `display` - Colors, icons and line styles of the graph and the exports
+/// * `read_only` - Reject every command that changes data (see `read_only`)
+/// * `redaction` - Values exports made with `redact` hide
+/// * `relationship_descriptions` - Descriptions generated for relationships saved without one
+/// * `health_probe` - How `probe_service_health` finds and calls health endpoints
+/// * `graph_preferences` - What `get_service_graph` does when a parameter is omitted
+///
+/// # Serialization
+///
+/// Uses camelCase field names for JSON compatibility with the frontend.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub git_auto_commit: bool,
+    #[serde(deserialize_with = "lenient_pins")]
+    pub pinned_services: PinnedServices,
+    pub http_api: HttpApiSettings,
+    pub webhook: Option<WebhookConfig>,
+    pub scheduled_export: ScheduledExportSettings,
+    pub workspaces: Vec<Workspace>,
+    pub active_workspace: Option<String>,
+    pub recent_data_paths: Vec<String>,
+    pub display: DisplayConfig,
+    pub read_only: bool,
+    pub redaction: RedactionConfig,
+    pub relationship_descriptions: RelationshipDescriptionSettings,
+    pub health_probe: HealthProbeSettings,
+    pub graph_preferences: GraphPreferences,
+}
+
+/// How many recently used data paths are kept.
+pub const MAX_RECENT_DATA_PATHS: usize = 10;
+
+impl Settings {
+    /// Returns the pinned service IDs of an environment in a data path.
+    pub fn pinned(&self, data_path: &Path, environment: &str) -> &[String] {
+        self.pinned_services
+            .get(&data_path_key(data_path))
+            .and_then(|environments| environments.get(environment))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the workspace with the given name, if there is one.
+    pub fn workspace(&self, name: &str) -> Option<&Workspace> {
+        self.workspaces.iter().find(|w| w.name == name)
+    }
+
+    /// Moves a data path to the front of the recently used data paths.
+    ///
+    /// The list is capped at `MAX_RECENT_DATA_PATHS`.
+    pub fn record_data_path(&mut self, path: &str) {
+        self.recent_data_paths.retain(|p| p != path);
+        self.recent_data_paths.insert(0, path.to_string());
+        self.recent_data_paths.truncate(MAX_RECENT_DATA_PATHS);
+    }
+}
+
+/// Default port of the embedded HTTP API.
+pub const DEFAULT_HTTP_API_PORT: u16 = 7878;
+
+/// Settings of the embedded read-only HTTP API.
+///
+/// The server only listens on localhost and every request must carry the
+/// token as `Authorization: Bearer {token}`.
+///
+/// # Fields
+///
+/// * `enabled` - Whether the server runs
+/// * `port` - The localhost port to listen on
+/// * `token` - The bearer token requests must send; the server won't start without one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HttpApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+impl Default for HttpApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_HTTP_API_PORT,
+            token: String::new(),
+        }
+    }
+}
+
+/// Default template of generated relationship descriptions.
+pub const DEFAULT_RELATIONSHIP_DESCRIPTION_TEMPLATE: &str =
+    "{sourceName} {relTypeVerb} {targetName}";
+
+/// Settings of generated relationship descriptions (see `descriptions`).
+///
+/// # Fields
+///
+/// * `auto_generate` - Give relationships saved without a description a generated one
+///   (off by default)
+/// * `template` - What descriptions are rendered from, with placeholders like
+///   `{sourceName}`; also used by `backfill_relationship_descriptions`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RelationshipDescriptionSettings {
+    pub auto_generate: bool,
+    pub template: String,
+}
+
+impl Default for RelationshipDescriptionSettings {
+    fn default() -> Self {
+        Self {
+            auto_generate: false,
+            template: DEFAULT_RELATIONSHIP_DESCRIPTION_TEMPLATE.to_string(),
+        }
+    }
+}
+
+/// Default metadata key of the health endpoint a service is probed at.
+pub const DEFAULT_HEALTH_URL_KEY: &str = "healthUrl";
+
+/// Settings of the health probes of `probe_service_health`.
+///
+/// # Fields
+///
+/// * `url_key` - The metadata key holding a service's health endpoint URL
+/// * `timeout_ms` - How long a probe may take before the service counts as unhealthy
+/// * `concurrency` - How many services are probed at the same time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HealthProbeSettings {
+    pub url_key: String,
+    pub timeout_ms: u64,
+    pub concurrency: usize,
+}
+
+impl Default for HealthProbeSettings {
+    fn default() -> Self {
+        Self {
+            url_key: DEFAULT_HEALTH_URL_KEY.to_string(),
+            timeout_ms: 3000,
+            concurrency: 8,
+        }
+    }
+}
+
+/// Default depth of `get_service_graph`.
+pub const DEFAULT_GRAPH_DEPTH: u32 = 1;
+
+/// The defaults of `get_service_graph`, used for the parameters a call omits.
+///
+/// The preferences are global, not per environment. Stored values that
+/// don't make sense are ignored rather than failing to load the settings:
+/// a depth that isn't a positive number or an unknown direction falls back
+/// to the built-in default, and statuses that don't exist are dropped.
+///
+/// # Fields
+///
+/// * `depth` - Maximum traversal depth (default: 1)
+/// * `direction` - Which way relationships are followed (default: both)
+/// * `exclude_statuses` - Statuses of services to leave out
+/// * `exclude_service_types` - Types of services to leave out
+/// * `aggregate_edges` - Whether to also return the aggregated edges (default: false)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", from = "Value")]
+pub struct GraphPreferences {
+    pub depth: u32,
+    pub direction: TraversalDirection,
+    pub exclude_statuses: Vec<ServiceStatus>,
+    pub exclude_service_types: Vec<ServiceType>,
+    pub aggregate_edges: bool,
+}
+
+impl Default for GraphPreferences {
+    fn default() -> Self {
+        Self {
+            depth: DEFAULT_GRAPH_DEPTH,
+            direction: TraversalDirection::default(),
+            exclude_statuses: Vec::new(),
+            exclude_service_types: Vec::new(),
+            aggregate_edges: false,
+        }
+    }
+}
+
+impl From<Value> for GraphPreferences {
+    /// Reads the preferences field by field, keeping the default of every
+    /// field whose stored value is invalid.
+    fn from(value: Value) -> Self {
+        let defaults = Self::default();
+        let field = |key: &str| value.get(key).cloned().unwrap_or(Value::Null);
+
+        Self {
+            depth: field("depth")
+                .as_u64()
+                .and_then(|depth| u32::try_from(depth).ok())
+                .filter(|depth| *depth > 0)
+                .unwrap_or(defaults.depth),
+            direction: serde_json::from_value(field("direction")).unwrap_or(defaults.direction),
+            exclude_statuses: lenient_list(field("excludeStatuses")),
+            exclude_service_types: lenient_list(field("excludeServiceTypes")),
+            aggregate_edges: field("aggregateEdges")
+                .as_bool()
+                .unwrap_or(defaults.aggregate_edges),
+        }
+    }
+}
+
+/// Pinned service IDs by data path (see `data_path_key`) and environment.
+pub type PinnedServices = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+
+/// Returns the key that per-data-path settings and files are stored under.
+pub fn data_path_key(data_path: &Path) -> String {
+    data_path.display().to_string()
+}
+
+/// Reads the pinned services, skipping data paths whose pins aren't an
+/// object of ID lists (such as pins written before they were kept per data
+/// path), so they can't make the whole settings file fail to load.
+fn lenient_pins<'de, D>(deserializer: D) -> Result<PinnedServices, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    let Value::Object(data_paths) = value else {
+        return Ok(BTreeMap::new());
+    };
+    Ok(data_paths
+        .into_iter()
+        .filter_map(|(data_path, pins)| Some((data_path, serde_json::from_value(pins).ok()?)))
+        .collect())
+}
+
+/// Reads a list, skipping the items that aren't valid; anything but an
+/// array reads as an empty list.
+fn lenient_list<T: DeserializeOwned>(value: Value) -> Vec<T> {
+    match value {
+        Value::Array(items) => items
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A named data path (see `switch_workspace`).
+///
+/// # Fields
+///
+/// * `name` - The unique name shown in the workspace switcher
+/// * `path` - The data directory or SQLite database file of the workspace
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub name: String,
+    pub path: String,
+}
+
+/// Recently viewed services by data path (see `data_path_key`) and
+/// environment, most recent first.
+pub type RecentViews = BTreeMap<String, BTreeMap<String, Vec<RecentService>>>;
+
+/// A service the user recently viewed.
+///
+/// Recently viewed services are kept per data path and environment, most
+/// recent first, in `recent_services.json` next to the settings file, so
+/// that viewing a service doesn't rewrite the settings.
+///
+/// # Fields
+///
+/// * `service_id` - The service that was viewed
+/// * `viewed_at` - When it was last viewed, in milliseconds since the Unix epoch
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentService {
+    pub service_id: String,
+    pub viewed_at: u64,
+}
+
+/// Events a webhook can subscribe to.
+///
+/// # Variants
+///
+/// * `DataChanged` - A command changed services or relationships
+/// * `ValidationErrorsIncreased` - A change raised an environment's validation error count
+/// * `ServiceDeleted` - A command deleted a service
+/// * `Test` - Sent by `test_webhook` only, whatever the subscriptions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+    DataChanged,
+    ValidationErrorsIncreased,
+    ServiceDeleted,
+    Test,
+}
+
+/// Where and when to send webhook notifications.
+///
+/// Notifications are POSTed as JSON (see `webhooks::WebhookPayload`). With a
+/// secret, each request carries an HMAC-SHA256 of its body, so receivers can
+/// check it came from the app.
+///
+/// # Fields
+///
+/// * `url` - The http(s) URL to POST to
+/// * `secret` - Key for the `X-Webhook-Signature` header; unsigned if absent
+/// * `events` - The events to send
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub secret: Option<String>,
+    pub events: Vec<WebhookEvent>,
+}
+
+impl WebhookConfig {
+    /// Returns true if the webhook subscribes to the event.
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.events.contains(&event)
+    }
+}
+
+/// Default number of scheduled outputs kept per environment.
+pub const DEFAULT_SCHEDULED_EXPORT_RETENTION: usize = 7;
+
+/// Settings of the scheduled exports (see `scheduler`).
+///
+/// # Fields
+///
+/// * `enabled` - Whether scheduled exports run
+/// * `schedule` - When they run
+/// * `environments` - The environments to export on each run
+/// * `target` - Where the exports go
+/// * `retention` - How many scheduled outputs to keep per environment; older ones
+///   are deleted after each run. Outputs not made by the scheduler are never deleted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScheduledExportSettings {
+    pub enabled: bool,
+    pub schedule: ExportSchedule,
+    pub environments: Vec<String>,
+    pub target: ExportTarget,
+    pub retention: usize,
+}
+
+impl Default for ScheduledExportSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedule: ExportSchedule::Daily {
+                time: "02:00".to_string(),
+            },
+            environments: Vec::new(),
+            target: ExportTarget::Snapshot,
+            retention: DEFAULT_SCHEDULED_EXPORT_RETENTION,
+        }
+    }
+}
+
+/// When scheduled exports run.
+///
+/// # Variants
+///
+/// * `Interval` - Every `minutes` minutes, counted from app start, the last run or
+///   the last change of the schedule
+/// * `Daily` - Once a day at `time`, as `"HH:MM"` in UTC
+///
+/// # Example JSON
+///
+/// ```json
+/// { "type": "interval", "minutes": 60 }
+/// { "type": "daily", "time": "02:00" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExportSchedule {
+    Interval { minutes: u64 },
+    Daily { time: String },
+}
+
+/// Where scheduled exports go.
+///
+/// # Variants
+///
+/// * `Snapshot` - An environment snapshot (see `models::Snapshot`), listed with the
+///   user's own snapshots
+/// * `Directory` - A snapshot bundle file `{environment}-{timestamp}.json` in `path`,
+///   encrypted when a passphrase is set
+///
+/// # Example JSON
+///
+/// ```json
+/// { "type": "snapshot" }
+/// { "type": "directory", "path": "/backups/dependency-map" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExportTarget {
+    Snapshot,
+    Directory { path: String },
+}
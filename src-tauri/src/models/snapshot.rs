@@ -0,0 +1,68 @@
+//! Environment snapshot data model definitions.
+//!
+//! A snapshot is a frozen copy of an environment's services and
+//! relationships, taken for example before an architecture review, so the
+//! environment can later be compared against it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Relationship, Service};
+
+/// A copy of an environment's data at one point in time.
+///
+/// Snapshots are immutable once written.
+///
+/// # Example JSON
+///
+/// ```json
+/// {
+///   "id": "1767225600000-q4-review",
+///   "label": "Q4 review",
+///   "createdAt": 1767225600000,
+///   "services": [{ "id": "billing-api", "name": "Billing API", ... }],
+///   "relationships": [{ "id": "rel-1", "source": "billing-api", ... }]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    /// Unique ID within the environment: `{createdAt}-{label slug}`.
+    pub id: String,
+    /// Human-readable label given when the snapshot was taken.
+    pub label: String,
+    /// When the snapshot was taken, in milliseconds since the Unix epoch.
+    pub created_at: u64,
+    /// The environment's services, sorted by ID.
+    pub services: Vec<Service>,
+    /// The environment's relationships, in file order.
+    pub relationships: Vec<Relationship>,
+}
+
+/// A snapshot without its data, for listings.
+///
+/// # Fields
+///
+/// * `id` / `label` / `created_at` - As in `Snapshot`
+/// * `service_count` - Number of services in the snapshot
+/// * `relationship_count` - Number of relationships in the snapshot
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub label: String,
+    pub created_at: u64,
+    pub service_count: usize,
+    pub relationship_count: usize,
+}
+
+impl From<&Snapshot> for SnapshotInfo {
+    fn from(snapshot: &Snapshot) -> Self {
+        Self {
+            id: snapshot.id.clone(),
+            label: snapshot.label.clone(),
+            created_at: snapshot.created_at,
+            service_count: snapshot.services.len(),
+            relationship_count: snapshot.relationships.len(),
+        }
+    }
+}
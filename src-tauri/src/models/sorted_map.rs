@@ -0,0 +1,29 @@
+//! Serialization of metadata maps with their keys in order.
+//!
+//! Metadata is kept in `HashMap`s, which serialize in an arbitrary order
+//! that differs from run to run. Serializing through these functions sorts
+//! the keys, so a saved file only changes when its contents do. Objects
+//! nested in the values are already sorted (`serde_json::Map` keeps its
+//! keys in order).
+
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Serializes a map with its keys sorted.
+pub fn serialize<S: Serializer>(
+    map: &HashMap<String, Value>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Serializes an optional map with its keys sorted.
+pub fn serialize_option<S: Serializer>(
+    map: &Option<HashMap<String, Value>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.as_ref()
+        .map(|map| map.iter().collect::<BTreeMap<_, _>>())
+        .serialize(serializer)
+}
@@ -0,0 +1,62 @@
+//! Custom type registry model definitions.
+//!
+//! Services and relationships can use custom types (`ServiceType::Custom`
+//! and `RelationshipType::Custom`) besides the built-in ones. The registry
+//! is the catalog of those custom types for a data path, so teams reuse
+//! "lambda" instead of inventing "Lambda" and "lambda-fn" next to it.
+
+use serde::{Deserialize, Serialize};
+
+/// Which types a registry entry is for.
+///
+/// # Variants
+///
+/// * `Service` - A custom `ServiceType`
+/// * `Relationship` - A custom `RelationshipType`
+///
+/// # Serialization
+///
+/// Kinds are serialized as snake_case strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomTypeKind {
+    Service,
+    Relationship,
+}
+
+impl CustomTypeKind {
+    /// Returns the kind as it is serialized (e.g., `"service"`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CustomTypeKind::Service => "service",
+            CustomTypeKind::Relationship => "relationship",
+        }
+    }
+}
+
+/// A registered custom type.
+///
+/// # Example JSON
+///
+/// ```json
+/// {
+///   "name": "lambda",
+///   "label": "AWS Lambda",
+///   "description": "Functions deployed to AWS Lambda",
+///   "color": "#ff9900"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomTypeDefinition {
+    /// The type as services or relationships store it (the canonical spelling).
+    pub name: String,
+    /// Human-readable display name.
+    pub label: String,
+    /// Optional description of what the type is for.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Optional color to draw the type with, as a CSS color.
+    #[serde(default)]
+    pub color: Option<String>,
+}
@@ -0,0 +1,341 @@
+//! Validation issue categories, the validation rule configuration and
+//! issue suppressions.
+//!
+//! The rule configuration is stored per data path in `.validation.json` (see
+//! `storage::validation_config`) and lets a team disable rules or change the
+//! severity they report with. Suppressions are stored per environment in
+//! `.suppressions.json` (see `storage::suppressions`) and mark individual,
+//! accepted issues.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Severity levels for validation issues.
+///
+/// Determines how critical an issue is and how it should be displayed in the UI.
+///
+/// # Variants
+///
+/// * `Error` - Critical issues that indicate data corruption or invalid state
+/// * `Warning` - Potential problems that may cause issues but don't break functionality
+/// * `Info` - Informational notices about the data structure
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// Categories of validation issues that can be detected.
+///
+/// Each issue type corresponds to a specific kind of data integrity problem.
+///
+/// # Variants
+///
+/// * `OrphanedRelationship` - A relationship references a service that doesn't exist
+/// * `DuplicateServiceId` - Multiple services share the same ID
+/// * `MissingRequiredField` - A service is missing required fields (id, name)
+/// * `InvalidRelationshipType` - A relationship uses an unknown type
+/// * `CircularDependency` - Services form a dependency cycle (A -> B -> A)
+/// * `UnreachableService` - A service has no relationships (informational)
+/// * `FilenameMismatch` - A service file's name doesn't match the ID inside it
+/// * `LegacyFileFormat` - A data file uses an outdated on-disk format (informational)
+/// * `NamingConvention` - A service ID, name or tag doesn't match the configured pattern
+/// * `MissingTypeRequirement` - A service lacks a tag or metadata key its type requires
+/// * `PossibleDuplicate` - Two services have such similar names they may be the same service
+/// * `ReversedDataFlow` - A reads_from/writes_to relationship starts at a database or cache
+/// * `ReversedDependency` - A database depends on a frontend
+/// * `UnlikelyAuthProvider` - An authenticates_via relationship targets something that
+///   doesn't look like an auth provider
+/// * `MissingQueue` - A publishes/subscribes relationship involves no queue
+/// * `DeprecatedDependency` - Services still point at a deprecated service
+/// * `UnhealthyDependency` - Services depend on a currently unhealthy service (informational)
+/// * `MetadataSchemaViolation` - A service's metadata fails the JSON Schema for its type
+/// * `TierViolation` - A service depends on a service of a less critical tier
+/// * `UnclassifiedTier` - Services have no tier while others do (informational)
+/// * `MissingOwner` - A tier-1 or database service has neither an owner nor a team
+/// * `OrphanedGroupMember` - A group lists a service that doesn't exist
+/// * `OrphanedAnnotation` - A graph annotation belongs to a service that doesn't exist
+///   (informational)
+/// * `UnregisteredCustomType` - A service or relationship uses a custom type that isn't
+///   in the type registry
+/// * `PastDecommissionDate` - A service's decommission date has passed but it isn't
+///   decommissioned
+/// * `DecommissionedDependency` - Services still point at a decommissioned service
+/// * `ExpiredRelationship` - A relationship's effective period ended long ago
+///   (informational)
+/// * `StaleImport` - An import source that relationships were imported from, or that
+///   is expected to sync the environment, hasn't re-synced it recently (informational)
+/// * `UnreviewedDependency` - depends_on relationships of tier-1 services haven't been
+///   through the architecture review (informational)
+/// * `PortConflict` - Two services claim the same port on the same host
+/// * `InvalidPort` - A port in a service's metadata isn't a port number (informational)
+/// * `MistypedThirdParty` - A service tagged third-party that internal services use
+///   isn't of type external
+/// * `ExternalDependsOnInternal` - An external service depends on an internal service,
+///   usually a modeling mistake (informational)
+/// * `DuplicateRelationship` - A relationship draft has the source, target and type of
+///   an existing relationship
+/// * `SelfReference` - A relationship draft points from a service to itself
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueType {
+    OrphanedRelationship,
+    DuplicateServiceId,
+    MissingRequiredField,
+    InvalidRelationshipType,
+    CircularDependency,
+    UnreachableService,
+    FilenameMismatch,
+    LegacyFileFormat,
+    NamingConvention,
+    MissingTypeRequirement,
+    PossibleDuplicate,
+    ReversedDataFlow,
+    ReversedDependency,
+    UnlikelyAuthProvider,
+    MissingQueue,
+    DeprecatedDependency,
+    UnhealthyDependency,
+    MetadataSchemaViolation,
+    TierViolation,
+    UnclassifiedTier,
+    MissingOwner,
+    OrphanedGroupMember,
+    OrphanedAnnotation,
+    UnregisteredCustomType,
+    PastDecommissionDate,
+    DecommissionedDependency,
+    ExpiredRelationship,
+    StaleImport,
+    UnreviewedDependency,
+    PortConflict,
+    InvalidPort,
+    MistypedThirdParty,
+    ExternalDependsOnInternal,
+    DuplicateRelationship,
+    SelfReference,
+}
+
+impl IssueType {
+    /// Returns the snake_case name used in JSON (e.g., "circular_dependency").
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueType::OrphanedRelationship => "orphaned_relationship",
+            IssueType::DuplicateServiceId => "duplicate_service_id",
+            IssueType::MissingRequiredField => "missing_required_field",
+            IssueType::InvalidRelationshipType => "invalid_relationship_type",
+            IssueType::CircularDependency => "circular_dependency",
+            IssueType::UnreachableService => "unreachable_service",
+            IssueType::FilenameMismatch => "filename_mismatch",
+            IssueType::LegacyFileFormat => "legacy_file_format",
+            IssueType::NamingConvention => "naming_convention",
+            IssueType::MissingTypeRequirement => "missing_type_requirement",
+            IssueType::PossibleDuplicate => "possible_duplicate",
+            IssueType::ReversedDataFlow => "reversed_data_flow",
+            IssueType::ReversedDependency => "reversed_dependency",
+            IssueType::UnlikelyAuthProvider => "unlikely_auth_provider",
+            IssueType::MissingQueue => "missing_queue",
+            IssueType::DeprecatedDependency => "deprecated_dependency",
+            IssueType::UnhealthyDependency => "unhealthy_dependency",
+            IssueType::MetadataSchemaViolation => "metadata_schema_violation",
+            IssueType::TierViolation => "tier_violation",
+            IssueType::UnclassifiedTier => "unclassified_tier",
+            IssueType::MissingOwner => "missing_owner",
+            IssueType::OrphanedGroupMember => "orphaned_group_member",
+            IssueType::OrphanedAnnotation => "orphaned_annotation",
+            IssueType::UnregisteredCustomType => "unregistered_custom_type",
+            IssueType::PastDecommissionDate => "past_decommission_date",
+            IssueType::DecommissionedDependency => "decommissioned_dependency",
+            IssueType::ExpiredRelationship => "expired_relationship",
+            IssueType::StaleImport => "stale_import",
+            IssueType::UnreviewedDependency => "unreviewed_dependency",
+            IssueType::PortConflict => "port_conflict",
+            IssueType::InvalidPort => "invalid_port",
+            IssueType::MistypedThirdParty => "mistyped_third_party",
+            IssueType::ExternalDependsOnInternal => "external_depends_on_internal",
+            IssueType::DuplicateRelationship => "duplicate_relationship",
+            IssueType::SelfReference => "self_reference",
+        }
+    }
+}
+
+/// Per-data-path configuration of the validation rules.
+///
+/// Every field has a default, so an empty or missing config file keeps the
+/// built-in behavior.
+///
+/// # Fields
+///
+/// * `disabled_rules` - Issue types that are not checked at all
+/// * `severity_overrides` - Severity to report an issue type with instead of its default
+/// * `naming` - Naming conventions services are checked against (see `NamingRules`)
+/// * `strict_metadata_schemas` - Reject saves of services whose metadata fails the
+///   JSON Schema for their type (see `save_metadata_schema`) instead of only
+///   reporting them during validation
+/// * `departed_owners` - Owners (usually emails) who have left, matched
+///   case-insensitively; their services are listed by `get_ownership_report`
+/// * `ports` - Where the port conflict check finds ports and hosts (see `PortRules`)
+/// * `imports` - When import sources count as out of sync (see `ImportRules`)
+///
+/// # Serialization
+///
+/// Uses camelCase field names; issue types and severities use their snake_case names:
+///
+/// ```json
+/// {
+///   "disabledRules": ["unreachable_service"],
+///   "severityOverrides": { "circular_dependency": "error" },
+///   "naming": { "serviceIdPattern": "[a-z0-9]+(-[a-z0-9]+)*" },
+///   "departedOwners": ["former.colleague@company.com"],
+///   "ports": { "portKeys": ["listenPort"], "colocationTagPrefix": "host-group:" },
+///   "imports": { "sources": ["consul", "kubernetes"], "staleAfterDays": 14 }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ValidationConfig {
+    pub disabled_rules: Vec<IssueType>,
+    pub severity_overrides: HashMap<IssueType, IssueSeverity>,
+    pub naming: NamingRules,
+    pub strict_metadata_schemas: bool,
+    pub departed_owners: Vec<String>,
+    pub ports: PortRules,
+    pub imports: ImportRules,
+}
+
+/// Naming and metadata conventions for the services of a data path.
+///
+/// Patterns are regular expressions that must match the whole value (they
+/// are anchored when compiled, see `naming::NamingChecker`). Unset patterns
+/// and empty maps check nothing, so the defaults accept every service.
+///
+/// # Fields
+///
+/// * `service_id_pattern` - Pattern every service ID must match
+/// * `name_pattern` - Pattern every service name must match
+/// * `tag_pattern` - Pattern every tag must match
+/// * `required_tags` - Tags each service of a type must have, keyed by the
+///   type's snake_case name (e.g., "database")
+/// * `required_metadata` - Metadata keys each service of a type must have,
+///   keyed like `required_tags`
+/// * `strict` - Reject saves of services that break these conventions instead
+///   of only reporting them during validation
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NamingRules {
+    pub service_id_pattern: Option<String>,
+    pub name_pattern: Option<String>,
+    pub tag_pattern: Option<String>,
+    pub required_tags: HashMap<String, Vec<String>>,
+    pub required_metadata: HashMap<String, Vec<String>>,
+    pub strict: bool,
+}
+
+/// Where the port conflict check finds the ports and hosts of services.
+///
+/// Two services claiming the same port conflict if they have the same host,
+/// or if neither has a host and they share a co-location tag (see `ports`).
+///
+/// # Fields
+///
+/// * `port_keys` - Metadata keys holding a port or an array of ports
+///   (default: `port`, `listenPort` and `ports`)
+/// * `host_keys` - Metadata keys holding the host; the first one set is used
+///   (default: `host`)
+/// * `colocation_tag_prefix` - Prefix of the tags marking services that run on
+///   the same hosts, e.g. `host-group:`; without one, services without a host
+///   never conflict
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PortRules {
+    pub port_keys: Vec<String>,
+    pub host_keys: Vec<String>,
+    pub colocation_tag_prefix: Option<String>,
+}
+
+impl Default for PortRules {
+    fn default() -> Self {
+        Self {
+            port_keys: vec![
+                "port".to_string(),
+                "listenPort".to_string(),
+                "ports".to_string(),
+            ],
+            host_keys: vec!["host".to_string()],
+            colocation_tag_prefix: None,
+        }
+    }
+}
+
+/// When the import freshness check reports an import source as out of sync.
+///
+/// # Fields
+///
+/// * `sources` - Import sources expected to sync the environments regularly,
+///   e.g. `consul`; sources with imported relationships are always checked
+/// * `stale_after_days` - Days without a sync after which a source is reported
+///   (default: 30)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportRules {
+    pub sources: Vec<String>,
+    pub stale_after_days: u64,
+}
+
+impl Default for ImportRules {
+    fn default() -> Self {
+        Self {
+            sources: Vec::new(),
+            stale_after_days: 30,
+        }
+    }
+}
+
+impl ValidationConfig {
+    /// Returns true if issues of this type should be checked for.
+    pub fn is_enabled(&self, issue_type: IssueType) -> bool {
+        !self.disabled_rules.contains(&issue_type)
+    }
+
+    /// Returns the severity to report an issue type with.
+    pub fn severity(&self, issue_type: IssueType, default: IssueSeverity) -> IssueSeverity {
+        self.severity_overrides
+            .get(&issue_type)
+            .copied()
+            .unwrap_or(default)
+    }
+}
+
+/// An accepted validation issue that should no longer be counted.
+///
+/// # Fields
+///
+/// * `fingerprint` - The fingerprint of the suppressed issue (see `ValidationIssue`)
+/// * `reason` - Why the issue was accepted
+/// * `user` - The OS username of whoever suppressed it
+/// * `created_at` - When it was suppressed, in milliseconds since the Unix epoch
+/// * `expires_at` - When the suppression stops applying, in milliseconds since the
+///   Unix epoch; never if absent
+///
+/// # Serialization
+///
+/// Uses camelCase field names; `expiresAt` is omitted when absent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Suppression {
+    pub fingerprint: String,
+    pub reason: String,
+    pub user: String,
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl Suppression {
+    /// Returns true if the suppression applies at the given time (milliseconds since the epoch).
+    pub fn is_active(&self, now: u64) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
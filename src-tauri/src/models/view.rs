@@ -0,0 +1,88 @@
+//! Saved graph view data model definitions.
+//!
+//! A saved view records how a filtered graph was built (where it starts,
+//! how deep it goes, which relationships it follows) so it can be rebuilt
+//! later, along with the layout the frontend had arranged it in.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::commands::graph::GroupBy;
+use crate::models::RelationshipType;
+
+/// Which way a graph traversal follows relationships.
+///
+/// Serialized as snake_case strings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraversalDirection {
+    /// Follow relationships both ways (the default).
+    #[default]
+    Both,
+    /// Follow relationships from source to target, towards dependencies.
+    Downstream,
+    /// Follow relationships from target to source, towards dependents.
+    Upstream,
+}
+
+/// The services a view starts its traversal from, instead of one service.
+///
+/// Deserialized as `{ "group": "payments" }`, `{ "team": "Platform" }` or
+/// `{ "tag": "pci" }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ViewSelector {
+    /// The members of a group (see `Group`).
+    Group(String),
+    /// The services of a team.
+    Team(String),
+    /// The services with a tag.
+    Tag(String),
+}
+
+/// The stored parameters of a graph view.
+///
+/// # Example JSON
+///
+/// ```json
+/// {
+///   "name": "payments-morning",
+///   "environment": "prod",
+///   "selector": { "group": "payments" },
+///   "depth": 2,
+///   "relationshipTypes": ["depends_on", "publishes_to", "subscribes_to"],
+///   "direction": "both",
+///   "groupBy": "team",
+///   "nodePositions": { "billing-api": { "x": 120, "y": 40 } }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedView {
+    /// Unique name of the view, also its filename.
+    pub name: String,
+    /// The environment the view is of.
+    pub environment: String,
+    /// The service the graph is centered on.
+    #[serde(default)]
+    pub center_service_id: Option<String>,
+    /// Services to center the graph on as well, in addition to `center_service_id`.
+    #[serde(default)]
+    pub selector: Option<ViewSelector>,
+    /// Maximum traversal depth (default: 1).
+    #[serde(default)]
+    pub depth: Option<u32>,
+    /// Relationship types to follow; all of them if absent.
+    #[serde(default)]
+    pub relationship_types: Option<Vec<RelationshipType>>,
+    /// Which way relationships are followed.
+    #[serde(default)]
+    pub direction: TraversalDirection,
+    /// How the frontend collapses the graph into groups, if it does.
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    /// Node positions by service ID, stored as the frontend sent them.
+    #[serde(default)]
+    pub node_positions: Option<HashMap<String, Value>>,
+}
@@ -0,0 +1,185 @@
+//! Naming convention checks for services.
+//!
+//! Compiles the `NamingRules` of a validation config once and checks
+//! services against them. Used by `validate_environment`, which reports
+//! violations as warnings, and by saves, which reject them when the rules
+//! are strict.
+
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::error::AppError;
+use crate::models::{IssueType, NamingRules, Service, ValidationConfig};
+
+/// A way in which a service breaks the naming rules.
+///
+/// # Fields
+///
+/// * `issue_type` - `NamingConvention` for pattern mismatches,
+///   `MissingTypeRequirement` for missing tags and metadata keys
+/// * `message` - Human-readable description naming the failing pattern or requirement
+#[derive(Debug, Clone)]
+pub struct NamingViolation {
+    pub issue_type: IssueType,
+    pub message: String,
+}
+
+/// Compiled naming rules.
+#[derive(Debug)]
+pub struct NamingChecker {
+    service_id: Option<(String, Regex)>,
+    name: Option<(String, Regex)>,
+    tag: Option<(String, Regex)>,
+    required_tags: HashMap<String, Vec<String>>,
+    required_metadata: HashMap<String, Vec<String>>,
+}
+
+impl NamingChecker {
+    /// Compiles naming rules.
+    ///
+    /// Each pattern is anchored so that it has to match the whole value.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - The naming rules of a validation config
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(NamingChecker)` - The compiled rules
+    /// * `Err(AppError::ValidationError)` - If a pattern isn't a valid regular expression
+    pub fn new(rules: &NamingRules) -> Result<Self, AppError> {
+        Ok(Self {
+            service_id: compile("serviceIdPattern", &rules.service_id_pattern)?,
+            name: compile("namePattern", &rules.name_pattern)?,
+            tag: compile("tagPattern", &rules.tag_pattern)?,
+            required_tags: rules.required_tags.clone(),
+            required_metadata: rules.required_metadata.clone(),
+        })
+    }
+
+    /// Returns true if the rules check nothing.
+    pub fn is_empty(&self) -> bool {
+        self.service_id.is_none()
+            && self.name.is_none()
+            && self.tag.is_none()
+            && self.required_tags.values().all(Vec::is_empty)
+            && self.required_metadata.values().all(Vec::is_empty)
+    }
+
+    /// Checks a service against the rules.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The service to check
+    ///
+    /// # Returns
+    ///
+    /// Every violation found, in field order (empty if the service conforms)
+    pub fn check(&self, service: &Service) -> Vec<NamingViolation> {
+        let mut violations = Vec::new();
+        let mismatch = |message: String| NamingViolation {
+            issue_type: IssueType::NamingConvention,
+            message,
+        };
+        let missing = |message: String| NamingViolation {
+            issue_type: IssueType::MissingTypeRequirement,
+            message,
+        };
+
+        if let Some((pattern, regex)) = &self.service_id {
+            if !regex.is_match(&service.id) {
+                violations.push(mismatch(format!(
+                    "Service ID '{}' doesn't match the pattern '{}'",
+                    service.id, pattern
+                )));
+            }
+        }
+
+        if let Some((pattern, regex)) = &self.name {
+            if !regex.is_match(&service.name) {
+                violations.push(mismatch(format!(
+                    "Name '{}' of service '{}' doesn't match the pattern '{}'",
+                    service.name, service.id, pattern
+                )));
+            }
+        }
+
+        if let Some((pattern, regex)) = &self.tag {
+            for tag in service.tags.iter().filter(|t| !regex.is_match(t)) {
+                violations.push(mismatch(format!(
+                    "Tag '{}' of service '{}' doesn't match the pattern '{}'",
+                    tag, service.id, pattern
+                )));
+            }
+        }
+
+        let service_type = service.service_type.as_str();
+        if let Some(tags) = self.required_tags.get(service_type) {
+            for tag in tags.iter().filter(|t| !service.tags.contains(t)) {
+                violations.push(missing(format!(
+                    "Service '{}' of type '{}' is missing the required tag '{}'",
+                    service.id, service_type, tag
+                )));
+            }
+        }
+        if let Some(keys) = self.required_metadata.get(service_type) {
+            for key in keys.iter().filter(|k| !service.metadata.contains_key(*k)) {
+                violations.push(missing(format!(
+                    "Service '{}' of type '{}' is missing the required metadata key '{}'",
+                    service.id, service_type, key
+                )));
+            }
+        }
+
+        violations
+    }
+}
+
+/// Rejects a service that breaks the naming rules, if they are strict.
+///
+/// Disabled `naming_convention` and `missing_type_requirement` rules are not
+/// enforced either.
+///
+/// # Arguments
+///
+/// * `config` - The validation config of the data path
+/// * `service` - The service about to be saved
+///
+/// # Returns
+///
+/// * `Ok(())` - If the rules aren't strict or the service conforms
+/// * `Err(AppError::ValidationError)` - Listing every violation, or if a pattern is invalid
+pub fn enforce(config: &ValidationConfig, service: &Service) -> Result<(), AppError> {
+    if !config.naming.strict {
+        return Ok(());
+    }
+
+    let violations: Vec<String> = NamingChecker::new(&config.naming)?
+        .check(service)
+        .into_iter()
+        .filter(|v| config.is_enabled(v.issue_type))
+        .map(|v| v.message)
+        .collect();
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::ValidationError(violations.join("; ")))
+    }
+}
+
+/// Compiles an optional pattern, anchored to match whole values.
+fn compile(field: &str, pattern: &Option<String>) -> Result<Option<(String, Regex)>, AppError> {
+    let Some(pattern) = pattern else {
+        return Ok(None);
+    };
+
+    let regex = Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| {
+        AppError::ValidationError(format!(
+            "Invalid {} '{}' in the validation config: {}",
+            field, pattern, e
+        ))
+    })?;
+
+    Ok(Some((pattern.clone(), regex)))
+}
@@ -0,0 +1,419 @@
+//! Tolerant parsing of nginx configuration for gateway routes.
+//!
+//! Only what a gateway's routing needs is read: `location` blocks with a
+//! `proxy_pass` and the `server` entries of `upstream` blocks. The parser
+//! is line-oriented rather than a full nginx grammar: it splits the config
+//! into directives at `;`, `{` and `}`, skipping comments and honoring
+//! quotes, and ignores directives it doesn't need. `include` directives of
+//! the given config are followed (relative to its directory, with `*`
+//! wildcards in the file name), but the includes of included files are not.
+//!
+//! Directives that can't be used, such as a `proxy_pass` to a variable or
+//! a missing include, are skipped and reported instead of failing the
+//! whole parse.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+
+/// A `proxy_pass` of a location.
+///
+/// # Fields
+///
+/// * `location` - The matching location, e.g. `/api/` or `~ ^/billing`
+/// * `host` - The proxied host without port, or the upstream name
+/// * `upstream_servers` - The hosts of the upstream's servers, if `host` names an upstream
+/// * `file` - The config file of the `proxy_pass`
+/// * `line` - The line of the `proxy_pass`
+#[derive(Debug, Clone)]
+pub struct ProxyRoute {
+    pub location: String,
+    pub host: String,
+    pub upstream_servers: Vec<String>,
+    pub file: String,
+    pub line: usize,
+}
+
+/// A directive that was skipped.
+///
+/// # Fields
+///
+/// * `file` - The config file of the directive
+/// * `line` - The line the directive starts on
+/// * `directive` - The directive, as written
+/// * `reason` - Why it was skipped
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedDirective {
+    pub file: String,
+    pub line: usize,
+    pub directive: String,
+    pub reason: String,
+}
+
+/// The routes of a config and the directives skipped while reading them.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedConfig {
+    pub routes: Vec<ProxyRoute>,
+    pub skipped: Vec<SkippedDirective>,
+}
+
+/// A `proxy_pass` waiting for the upstreams to be known.
+struct PendingProxy {
+    location: String,
+    target: String,
+    file: String,
+    line: usize,
+}
+
+/// State of a parse across the config and its includes.
+#[derive(Default)]
+struct Parser {
+    /// The words opening each enclosing block, innermost last
+    blocks: Vec<Vec<String>>,
+    /// Server addresses by upstream name
+    upstreams: HashMap<String, Vec<String>>,
+    proxies: Vec<PendingProxy>,
+    skipped: Vec<SkippedDirective>,
+}
+
+/// Reads the gateway routes of an nginx config.
+///
+/// # Arguments
+///
+/// * `path` - The main config file, e.g. `/etc/nginx/nginx.conf`
+///
+/// # Returns
+///
+/// * `Ok(ParsedConfig)` - The routes found and the directives skipped
+/// * `Err(AppError::FileLoad)` - If the main config can't be read
+pub fn parse_config(path: &Path) -> Result<ParsedConfig, AppError> {
+    let content = fs::read_to_string(path).map_err(|e| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    })?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    let mut parser = Parser::default();
+    parser.parse_file(path, &content, base_dir, true);
+
+    Ok(parser.finish())
+}
+
+impl Parser {
+    /// Parses one file; `follow_includes` is only set for the main config.
+    fn parse_file(&mut self, path: &Path, content: &str, base_dir: &Path, follow_includes: bool) {
+        let file = path.display().to_string();
+        let depth = self.blocks.len();
+        let mut words: Vec<String> = Vec::new();
+        let mut word = String::new();
+        let mut start_line = 1;
+        let mut line = 1;
+        let mut quote: Option<char> = None;
+        let mut chars = content.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c == '\n' {
+                line += 1;
+            }
+            if let Some(q) = quote {
+                if c == '\\' {
+                    word.extend(chars.next());
+                } else if c == q {
+                    quote = None;
+                } else {
+                    word.push(c);
+                }
+                continue;
+            }
+
+            match c {
+                '#' => {
+                    while chars.peek().is_some_and(|&next| next != '\n') {
+                        chars.next();
+                    }
+                }
+                '"' | '\'' => {
+                    if words.is_empty() && word.is_empty() {
+                        start_line = line;
+                    }
+                    quote = Some(c);
+                }
+                ';' | '{' | '}' => {
+                    if !word.is_empty() {
+                        words.push(std::mem::take(&mut word));
+                    }
+                    let directive = std::mem::take(&mut words);
+                    match c {
+                        ';' => {
+                            self.directive(&file, start_line, directive, base_dir, follow_includes)
+                        }
+                        '{' => self.blocks.push(directive),
+                        _ => {
+                            if !directive.is_empty() {
+                                self.skip(&file, start_line, &directive, "Missing ';' before '}'");
+                            }
+                            if self.blocks.len() > depth {
+                                self.blocks.pop();
+                            } else {
+                                self.skip(&file, line, &["}".to_string()], "Unmatched '}'");
+                            }
+                        }
+                    }
+                }
+                c if c.is_whitespace() => {
+                    if !word.is_empty() {
+                        words.push(std::mem::take(&mut word));
+                    }
+                }
+                c => {
+                    if words.is_empty() && word.is_empty() {
+                        start_line = line;
+                    }
+                    word.push(c);
+                }
+            }
+        }
+
+        if !word.is_empty() {
+            words.push(word);
+        }
+        if !words.is_empty() {
+            self.skip(
+                &file,
+                start_line,
+                &words,
+                "Unterminated directive at end of file",
+            );
+        }
+        if self.blocks.len() > depth {
+            let opening = self.blocks[depth].clone();
+            self.skip(&file, line, &opening, "Block is not closed at end of file");
+            self.blocks.truncate(depth);
+        }
+    }
+
+    /// Handles a directive ending in `;`.
+    fn directive(
+        &mut self,
+        file: &str,
+        line: usize,
+        words: Vec<String>,
+        base_dir: &Path,
+        follow_includes: bool,
+    ) {
+        let Some(name) = words.first() else {
+            return;
+        };
+
+        match name.as_str() {
+            "include" => {
+                if !follow_includes {
+                    self.skip(
+                        file,
+                        line,
+                        &words,
+                        "Includes are only followed one level deep",
+                    );
+                } else if let [_, pattern] = words.as_slice() {
+                    self.include(file, line, &words, base_dir, pattern);
+                } else {
+                    self.skip(file, line, &words, "Expected one file");
+                }
+            }
+            "proxy_pass" => match words.as_slice() {
+                [_, target] => self.proxies.push(PendingProxy {
+                    location: self.location(),
+                    target: target.clone(),
+                    file: file.to_string(),
+                    line,
+                }),
+                _ => self.skip(file, line, &words, "Expected one URL"),
+            },
+            "server" => {
+                let Some(upstream) = self.in_upstream() else {
+                    return;
+                };
+                match words.get(1) {
+                    Some(address) => self
+                        .upstreams
+                        .entry(upstream)
+                        .or_default()
+                        .push(address.clone()),
+                    None => self.skip(file, line, &words, "Expected a server address"),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Parses the files an `include` names.
+    fn include(
+        &mut self,
+        file: &str,
+        line: usize,
+        words: &[String],
+        base_dir: &Path,
+        pattern: &str,
+    ) {
+        let pattern_path = base_dir.join(pattern);
+        let paths = if pattern.contains('*') {
+            match expand_wildcard(&pattern_path) {
+                Ok(paths) => paths,
+                Err(e) => {
+                    self.skip(
+                        file,
+                        line,
+                        words,
+                        &format!("Cannot read included files: {}", e),
+                    );
+                    return;
+                }
+            }
+        } else {
+            vec![pattern_path]
+        };
+
+        for path in paths {
+            match fs::read_to_string(&path) {
+                Ok(content) => self.parse_file(&path, &content, base_dir, false),
+                Err(e) => self.skip(
+                    file,
+                    line,
+                    words,
+                    &format!("Cannot read {}: {}", path.display(), e),
+                ),
+            }
+        }
+    }
+
+    /// Returns the location of the innermost enclosing `location` block.
+    fn location(&self) -> String {
+        self.blocks
+            .iter()
+            .rev()
+            .find(|block| block.first().is_some_and(|name| name == "location"))
+            .map(|block| block[1..].join(" "))
+            .unwrap_or_else(|| "/".to_string())
+    }
+
+    /// Returns the name of the enclosing `upstream` block, if directly in one.
+    fn in_upstream(&self) -> Option<String> {
+        match self.blocks.last()?.as_slice() {
+            [name, upstream] if name == "upstream" => Some(upstream.clone()),
+            _ => None,
+        }
+    }
+
+    /// Reports a skipped directive.
+    fn skip(&mut self, file: &str, line: usize, words: &[String], reason: &str) {
+        self.skipped.push(SkippedDirective {
+            file: file.to_string(),
+            line,
+            directive: words.join(" "),
+            reason: reason.to_string(),
+        });
+    }
+
+    /// Resolves the `proxy_pass` targets now that all upstreams are known.
+    fn finish(mut self) -> ParsedConfig {
+        let mut routes = Vec::new();
+        for proxy in std::mem::take(&mut self.proxies) {
+            let words = ["proxy_pass".to_string(), proxy.target.clone()];
+            let host = match proxy_host(&proxy.target) {
+                Ok(host) => host,
+                Err(reason) => {
+                    self.skip(&proxy.file, proxy.line, &words, reason);
+                    continue;
+                }
+            };
+            let upstream_servers = self
+                .upstreams
+                .get(&host)
+                .map(|servers| servers.iter().map(|s| strip_port(s).to_string()).collect())
+                .unwrap_or_default();
+
+            routes.push(ProxyRoute {
+                location: proxy.location,
+                host,
+                upstream_servers,
+                file: proxy.file,
+                line: proxy.line,
+            });
+        }
+
+        ParsedConfig {
+            routes,
+            skipped: self.skipped,
+        }
+    }
+}
+
+/// Returns the host of a `proxy_pass` URL, without its port.
+fn proxy_host(target: &str) -> Result<String, &'static str> {
+    if target.contains('$') {
+        return Err("Uses variables, so the upstream can't be known without a request");
+    }
+    let rest = target
+        .strip_prefix("http://")
+        .or_else(|| target.strip_prefix("https://"))
+        .ok_or("Only http:// and https:// targets are supported")?;
+    let authority = rest.split(['/', '?']).next().unwrap_or_default();
+    let host = strip_port(authority);
+    if host.is_empty() || host.starts_with("unix:") {
+        return Err("Has no host name");
+    }
+
+    Ok(host.to_lowercase())
+}
+
+/// Strips the port from `host:port`.
+fn strip_port(address: &str) -> &str {
+    if address.starts_with('[') {
+        return address.split(']').next().map_or(address, |h| &h[1..]);
+    }
+    address.split(':').next().unwrap_or(address)
+}
+
+/// Lists the files matching a path whose file name contains `*` wildcards.
+fn expand_wildcard(pattern: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let dir = pattern.parent().unwrap_or(Path::new("."));
+    let name_pattern = pattern
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .filter(|entry| wildcard_match(&name_pattern, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Matches a name against a pattern where `*` matches any characters.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
@@ -0,0 +1,351 @@
+//! Port conflict checks for services.
+//!
+//! Services can record the ports they listen on and the host they run on in
+//! their metadata. `validate_environment` warns about two services claiming
+//! the same port on the same host, and reports port values that aren't port
+//! numbers. Which metadata keys hold the ports and hosts, and which tags mark
+//! services running together, is configured by the `ports` rules of the
+//! validation config (see `models::PortRules`).
+//!
+//! Two services are on the same host if their hosts are equal, or if
+//! neither has a host and they share a co-location tag. A service with a
+//! host never conflicts with one without.
+
+use serde_json::Value;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::models::{PortRules, Service};
+
+/// Two services claiming the same port on the same host.
+///
+/// # Fields
+///
+/// * `port` - The port
+/// * `location` - Where both run, e.g. "host 'node-1'" or "co-location tag 'rack:a'"
+/// * `service_ids` - The IDs of the two services, in the order they were checked
+#[derive(Debug, Clone)]
+pub struct PortConflict {
+    pub port: u16,
+    pub location: String,
+    pub service_ids: [String; 2],
+}
+
+/// A port value in the metadata of a service that isn't a port number.
+///
+/// # Fields
+///
+/// * `service_id` - The service
+/// * `key` - The metadata key holding the value
+/// * `value` - The value
+#[derive(Debug, Clone)]
+pub struct InvalidPort {
+    pub service_id: String,
+    pub key: String,
+    pub value: Value,
+}
+
+/// What `check` found.
+///
+/// # Fields
+///
+/// * `conflicts` - Pairs of services claiming the same port on the same host
+/// * `invalid_ports` - Port values that aren't port numbers
+#[derive(Debug, Default)]
+pub struct PortCheck {
+    pub conflicts: Vec<PortConflict>,
+    pub invalid_ports: Vec<InvalidPort>,
+}
+
+/// Where a service runs, for grouping services by host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Location<'a> {
+    Host(&'a str),
+    Tag(&'a str),
+}
+
+/// Checks the ports of services for conflicts and invalid values.
+///
+/// A port is a whole number from 1 to 65535, or a string holding one; the
+/// value of a port key can also be an array of ports. A service listing a
+/// port under several keys claims it once.
+///
+/// # Arguments
+///
+/// * `services` - The services to check
+/// * `rules` - The metadata keys and co-location tags to use
+///
+/// # Returns
+///
+/// The conflicts, ordered by port, and the invalid port values, ordered
+/// like the services.
+pub fn check<S: Borrow<Service>>(services: &[S], rules: &PortRules) -> PortCheck {
+    let mut result = PortCheck::default();
+    let mut claims: BTreeMap<(u16, Location), Vec<usize>> = BTreeMap::new();
+
+    for (index, service) in services.iter().enumerate() {
+        let service = service.borrow();
+        let mut ports = BTreeSet::new();
+        for key in &rules.port_keys {
+            let Some(value) = service.metadata.get(key) else {
+                continue;
+            };
+            let values = match value {
+                Value::Array(items) => items.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            for value in values.iter().filter(|value| !value.is_null()) {
+                match port_number(value) {
+                    Some(port) => {
+                        ports.insert(port);
+                    }
+                    None => result.invalid_ports.push(InvalidPort {
+                        service_id: service.id.clone(),
+                        key: key.clone(),
+                        value: value.clone(),
+                    }),
+                }
+            }
+        }
+        if ports.is_empty() {
+            continue;
+        }
+
+        for location in locations(service, rules) {
+            for &port in &ports {
+                claims.entry((port, location)).or_default().push(index);
+            }
+        }
+    }
+
+    // Services sharing several co-location tags conflict once per port
+    let mut reported = BTreeSet::new();
+    for ((port, location), indices) in &claims {
+        let location = match location {
+            Location::Host(host) => format!("host '{}'", host),
+            Location::Tag(tag) => format!("co-location tag '{}'", tag),
+        };
+        for (position, &first) in indices.iter().enumerate() {
+            for &second in &indices[position + 1..] {
+                if !reported.insert((*port, first, second)) {
+                    continue;
+                }
+                result.conflicts.push(PortConflict {
+                    port: *port,
+                    location: location.clone(),
+                    service_ids: [
+                        services[first].borrow().id.clone(),
+                        services[second].borrow().id.clone(),
+                    ],
+                });
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns the port a metadata value holds, if it is a port number.
+fn port_number(value: &Value) -> Option<u16> {
+    let port = match value {
+        Value::Number(number) => u16::try_from(number.as_u64()?).ok()?,
+        Value::String(text) => text.trim().parse().ok()?,
+        _ => return None,
+    };
+    (port != 0).then_some(port)
+}
+
+/// Returns where a service runs: its host, or else its co-location tags.
+fn locations<'a>(service: &'a Service, rules: &'a PortRules) -> Vec<Location<'a>> {
+    let host = rules
+        .host_keys
+        .iter()
+        .filter_map(|key| service.metadata.get(key)?.as_str())
+        .find(|host| !host.is_empty());
+    if let Some(host) = host {
+        return vec![Location::Host(host)];
+    }
+
+    match rules.colocation_tag_prefix.as_deref() {
+        Some(prefix) if !prefix.is_empty() => service
+            .tags
+            .iter()
+            .filter(|tag| tag.starts_with(prefix))
+            .map(|tag| Location::Tag(tag.as_str()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::service;
+    use serde_json::json;
+
+    fn listening(id: &str, metadata: Value, tags: &[&str]) -> Service {
+        let mut service = service(id);
+        service.metadata = serde_json::from_value(metadata).unwrap();
+        service.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        service
+    }
+
+    fn rules_with_prefix(prefix: &str) -> PortRules {
+        PortRules {
+            colocation_tag_prefix: Some(prefix.to_string()),
+            ..PortRules::default()
+        }
+    }
+
+    fn conflicts(check: &PortCheck) -> Vec<(u16, &str, [&str; 2])> {
+        check
+            .conflicts
+            .iter()
+            .map(|c| {
+                let [first, second] = &c.service_ids;
+                (
+                    c.port,
+                    c.location.as_str(),
+                    [first.as_str(), second.as_str()],
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_port_of_an_array_is_claimed() {
+        let services = [
+            listening(
+                "api",
+                json!({ "ports": [8080, "9090"], "host": "node-1" }),
+                &[],
+            ),
+            listening("metrics", json!({ "port": 9090, "host": "node-1" }), &[]),
+            listening("web", json!({ "listenPort": 8080, "host": "node-1" }), &[]),
+        ];
+
+        let check = check(&services, &PortRules::default());
+
+        assert_eq!(
+            conflicts(&check),
+            vec![
+                (8080, "host 'node-1'", ["api", "web"]),
+                (9090, "host 'node-1'", ["api", "metrics"]),
+            ]
+        );
+        assert!(check.invalid_ports.is_empty());
+    }
+
+    #[test]
+    fn a_port_under_several_keys_is_claimed_once() {
+        let services = [listening(
+            "api",
+            json!({ "port": 8080, "ports": [8080, 8080], "host": "node-1" }),
+            &[],
+        )];
+
+        assert!(check(&services, &PortRules::default()).conflicts.is_empty());
+    }
+
+    #[test]
+    fn other_hosts_do_not_conflict() {
+        let services = [
+            listening("api", json!({ "port": 8080, "host": "node-1" }), &[]),
+            listening("web", json!({ "port": 8080, "host": "node-2" }), &[]),
+        ];
+
+        assert!(check(&services, &PortRules::default()).conflicts.is_empty());
+    }
+
+    #[test]
+    fn a_missing_host_only_conflicts_through_a_shared_colocation_tag() {
+        let services = [
+            listening("api", json!({ "port": 8080 }), &["host-group:a"]),
+            listening("web", json!({ "port": 8080 }), &["host-group:a"]),
+            listening("jobs", json!({ "port": 8080 }), &["host-group:b"]),
+            listening(
+                "edge",
+                json!({ "port": 8080, "host": "node-1" }),
+                &["host-group:a"],
+            ),
+            listening("worker", json!({ "port": 8080 }), &[]),
+        ];
+
+        let check_without_prefix = check(&services, &PortRules::default());
+        assert!(check_without_prefix.conflicts.is_empty());
+
+        let check_with_prefix = check(&services, &rules_with_prefix("host-group:"));
+        assert_eq!(
+            conflicts(&check_with_prefix),
+            vec![(8080, "co-location tag 'host-group:a'", ["api", "web"])]
+        );
+    }
+
+    #[test]
+    fn services_sharing_several_tags_conflict_once_per_port() {
+        let tags = ["rack:a", "rack:b"];
+        let services = [
+            listening("api", json!({ "port": 8080 }), &tags),
+            listening("web", json!({ "port": 8080 }), &tags),
+        ];
+
+        assert_eq!(
+            check(&services, &rules_with_prefix("rack:"))
+                .conflicts
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn the_configured_keys_are_used() {
+        let rules = PortRules {
+            port_keys: vec!["containerPort".to_string()],
+            host_keys: vec!["node".to_string(), "host".to_string()],
+            colocation_tag_prefix: None,
+        };
+        let services = [
+            listening("api", json!({ "containerPort": 80, "node": "n1" }), &[]),
+            listening("web", json!({ "containerPort": 80, "host": "n1" }), &[]),
+            listening("jobs", json!({ "port": 80, "node": "n1" }), &[]),
+        ];
+
+        assert_eq!(
+            conflicts(&check(&services, &rules)),
+            vec![(80, "host 'n1'", ["api", "web"])]
+        );
+    }
+
+    #[test]
+    fn values_that_are_not_port_numbers_are_reported() {
+        let services = [listening(
+            "api",
+            json!({
+                "port": "http",
+                "listenPort": 70000,
+                "ports": [0, true, " 8443 ", null, -1, 8080.5]
+            }),
+            &[],
+        )];
+
+        let check = check(&services, &PortRules::default());
+
+        let invalid: Vec<(&str, &Value)> = check
+            .invalid_ports
+            .iter()
+            .map(|invalid| (invalid.key.as_str(), &invalid.value))
+            .collect();
+        assert_eq!(
+            invalid,
+            vec![
+                ("port", &json!("http")),
+                ("listenPort", &json!(70000)),
+                ("ports", &json!(0)),
+                ("ports", &json!(true)),
+                ("ports", &json!(-1)),
+                ("ports", &json!(8080.5)),
+            ]
+        );
+        assert!(check.invalid_ports.iter().all(|i| i.service_id == "api"));
+    }
+}
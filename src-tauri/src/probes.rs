@@ -0,0 +1,84 @@
+//! HTTP probes of services' health endpoints.
+//!
+//! A probe is one GET request, and its answer maps to a status the way the
+//! status code suggests: 2xx is healthy, 4xx degraded (the endpoint is
+//! there but won't answer, e.g. without credentials), and 5xx, a timeout or
+//! no answer at all unhealthy. Redirects are followed; a final answer with
+//! another code counts as degraded.
+
+use std::time::{Duration, Instant};
+
+use crate::models::ServiceStatus;
+
+/// The answer to a probe.
+///
+/// # Fields
+///
+/// * `status` - The status the answer maps to
+/// * `http_status` - The status code of the answer; `None` if there was none
+/// * `latency_ms` - How long the request took, in milliseconds
+/// * `error` - Why the endpoint is not healthy, if it isn't
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub status: ServiceStatus,
+    pub http_status: Option<u16>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Returns whether a URL can be probed: an `http` or `https` URL.
+pub fn is_probe_url(url: &str) -> bool {
+    let url = url.trim().to_lowercase();
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Sends a GET request to a health endpoint.
+///
+/// # Arguments
+///
+/// * `url` - The health endpoint, an `http` or `https` URL
+/// * `timeout` - How long the whole request may take
+///
+/// # Returns
+///
+/// The status the answer maps to; a failed request is an unhealthy result,
+/// never an error.
+pub fn probe(url: &str, timeout: Duration) -> ProbeResult {
+    let started = Instant::now();
+    let response = ureq::get(url.trim()).timeout(timeout).call();
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match response {
+        Ok(response) => {
+            let code = response.status();
+            ProbeResult {
+                status: status_for_code(code),
+                http_status: Some(code),
+                latency_ms,
+                error: (!(200..300).contains(&code))
+                    .then(|| format!("HTTP {} {}", code, response.status_text())),
+            }
+        }
+        Err(ureq::Error::Status(code, response)) => ProbeResult {
+            status: status_for_code(code),
+            http_status: Some(code),
+            latency_ms,
+            error: Some(format!("HTTP {} {}", code, response.status_text())),
+        },
+        Err(ureq::Error::Transport(transport)) => ProbeResult {
+            status: ServiceStatus::Unhealthy,
+            http_status: None,
+            latency_ms,
+            error: Some(transport.to_string()),
+        },
+    }
+}
+
+/// Maps the status code of a health endpoint's answer to a status.
+pub fn status_for_code(code: u16) -> ServiceStatus {
+    match code {
+        200..=299 => ServiceStatus::Healthy,
+        500..=599 => ServiceStatus::Unhealthy,
+        _ => ServiceStatus::Degraded,
+    }
+}
@@ -0,0 +1,425 @@
+//! Read-only application mode.
+//!
+//! With the `readOnly` setting on (see `set_read_only`), nobody can change
+//! data by accident, e.g. while the app is shown on a shared screen during
+//! an incident review. The mode is enforced in one place, `guard`, which
+//! wraps the command handler: commands not listed in `READ_ONLY_COMMANDS`
+//! are rejected with `AppError::ReadOnlyMode` before they run, so they
+//! never touch disk.
+//!
+//! The list names the commands that are safe, not the ones that aren't, so
+//! a new command is blocked in read-only mode until it is added here.
+//! Commands that can write are blocked as a whole, even for a dry run.
+
+use std::sync::RwLock;
+use tauri::ipc::Invoke;
+use tauri::{Manager, Runtime};
+
+use crate::error::AppError;
+use crate::state::{read_state, AppState};
+
+/// Commands that keep working in read-only mode: reads, queries, exports to
+/// files outside the data directory, switching what is shown, and the
+/// in-memory caches, diagnostics and encryption passphrase.
+const READ_ONLY_COMMANDS: &[&str] = &[
+    // Services and relationships
+    "get_all_services",
+    "get_service_summaries",
+    "get_service_by_id",
+    "service_exists",
+    "generate_id",
+    "search_services",
+    "quick_open",
+    "get_all_relationships",
+    "get_relationships_for_service",
+    "get_relationship_counts",
+    "delete_relationships_for_service_preview",
+    // Graph
+    "get_service_graph",
+    "expand_graph_node",
+    "get_effective_status",
+    "get_criticality_ranking",
+    "get_dependency_layers",
+    "compare_service_dependencies",
+    "is_reachable",
+    "are_reachable",
+    "suggest_cycle_breaks",
+    "get_grouped_graph",
+    "simulate_graph",
+    "export_graph",
+    "export_grafana_nodegraph",
+    "export_graph_d2",
+    "export_graph_drawio",
+    "export_deployment_manifest",
+    "export_deployment_manifests",
+    "get_graph_annotations",
+    // Views, groups, custom types and metadata keys
+    "list_views",
+    "get_view",
+    // Only reads the saved view and builds its graph; saving one is blocked
+    "apply_view",
+    "list_groups",
+    "list_custom_types",
+    "get_relationship_metadata_report",
+    "get_service_metadata_report",
+    // Snapshots
+    "list_snapshots",
+    "diff_snapshot",
+    // Environments and workspaces
+    "list_environments",
+    "get_current_environment",
+    // Only changes which environment is shown, in memory
+    "switch_environment",
+    "validate_data_path",
+    "get_recent_data_paths",
+    "get_counts",
+    "list_workspaces",
+    // Writes nothing but which workspace is active to the settings file, so
+    // a review can look at every workspace; adding and removing them is blocked
+    "switch_workspace",
+    // Reports and validation
+    "get_ownership_report",
+    "export_ownership_report",
+    "get_external_dependencies",
+    "export_external_dependencies",
+    "reconcile_declared_dependencies",
+    "validate_environment",
+    "validate_relationship_draft",
+    "get_team_overview",
+    "check_drift",
+    "find_possible_duplicates",
+    "get_deprecated_usage",
+    "get_decommission_plan",
+    "get_tier_report",
+    "export_validation_report",
+    "get_metadata_schema",
+    "get_validation_config",
+    "list_suppressions",
+    // Settings
+    "get_settings",
+    // Must stay available to turn the mode off again
+    "set_read_only",
+    "get_pinned_services",
+    "get_recent_services",
+    "get_webhook_config",
+    "test_webhook",
+    "get_display_config",
+    "get_graph_preferences",
+    // Status, history and diagnostics
+    "get_scheduled_task_history",
+    "get_git_status",
+    "get_storage_report",
+    "get_undo_history",
+    "get_audit_log",
+    "get_diagnostics",
+    "reset_diagnostics",
+    "list_running_operations",
+    "cancel_operation",
+    // In-memory state only
+    "set_encryption_passphrase",
+    "clear_encryption_passphrase",
+    "refresh_environment",
+    "get_cache_info",
+    "preload_environment",
+    "clear_cache",
+];
+
+/// Returns true if a command keeps working in read-only mode.
+pub fn allows(command: &str) -> bool {
+    READ_ONLY_COMMANDS.contains(&command)
+}
+
+/// Wraps a command handler so read-only mode rejects the commands it doesn't allow.
+///
+/// # Arguments
+///
+/// * `handler` - The handler built by `tauri::generate_handler!`
+///
+/// # Returns
+///
+/// A handler that rejects commands not in `READ_ONLY_COMMANDS` with
+/// `AppError::ReadOnlyMode` while the `readOnly` setting is on, and passes
+/// every other call to `handler`.
+pub fn guard<R, F>(handler: F) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static
+where
+    R: Runtime,
+    F: Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+{
+    move |invoke| {
+        let command = invoke.message.command();
+        if !allows(command) && is_enabled(invoke.message.webview_ref()) {
+            let error = AppError::ReadOnlyMode(command.to_string());
+            invoke.resolver.reject(error);
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+/// Returns true if read-only mode is on.
+fn is_enabled<R: Runtime>(manager: &impl Manager<R>) -> bool {
+    manager
+        .try_state::<RwLock<AppState>>()
+        .is_some_and(|state| read_state(&state).settings.read_only)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{relationship, service, snapshot_dir, TestApp};
+    use serde_json::{json, Value};
+    use std::collections::HashSet;
+    use tauri::ipc::{CallbackFn, InvokeBody};
+    use tauri::test::{get_ipc_response, mock_builder, MockRuntime, INVOKE_KEY};
+    use tauri::webview::InvokeRequest;
+    use tauri::{WebviewWindow, WebviewWindowBuilder};
+
+    /// An app over dev (api depends on db) with every command registered
+    /// behind the guard, and a webview to invoke them from.
+    fn guarded_app() -> (TestApp, WebviewWindow<MockRuntime>) {
+        let app = TestApp::from_builder(mock_builder().invoke_handler(crate::invoke_handler()));
+        app.create_environment(
+            "dev",
+            &[service("api"), service("db")],
+            &[relationship("api-db", "api", "db")],
+        );
+        let webview = WebviewWindowBuilder::new(&app.app, "main", Default::default())
+            .build()
+            .unwrap();
+        (app, webview)
+    }
+
+    /// Invokes a command the way the frontend does.
+    fn invoke(
+        webview: &WebviewWindow<MockRuntime>,
+        command: &str,
+        args: Value,
+    ) -> Result<Value, Value> {
+        let request = InvokeRequest {
+            cmd: command.to_string(),
+            callback: CallbackFn(0),
+            error: CallbackFn(1),
+            url: "tauri://localhost".parse().unwrap(),
+            body: InvokeBody::Json(args),
+            headers: Default::default(),
+            invoke_key: INVOKE_KEY.to_string(),
+        };
+        get_ipc_response(webview, request).map(|body| body.deserialize().unwrap())
+    }
+
+    fn set_read_only(webview: &WebviewWindow<MockRuntime>, enabled: bool) {
+        invoke(webview, "set_read_only", json!({ "enabled": enabled })).unwrap();
+    }
+
+    fn assert_blocked(webview: &WebviewWindow<MockRuntime>, command: &str, args: Value) {
+        let error = invoke(webview, command, args).expect_err(command);
+        assert_eq!(error["code"], "read_only_mode", "{command}: {error}");
+        assert_eq!(error["details"]["command"], command);
+    }
+
+    /// The names of the commands registered in `invoke_handler`.
+    fn registered_commands() -> Vec<String> {
+        let lib = include_str!("lib.rs");
+        let start = lib.find("tauri::generate_handler![").unwrap();
+        let end = start + lib[start..].find("])").unwrap();
+        lib[start..end]
+            .lines()
+            .filter_map(|line| line.trim().strip_suffix(','))
+            .filter_map(|path| path.rsplit("::").next())
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn blocks_a_mutating_command_of_every_module() {
+        let (app, webview) = guarded_app();
+        set_read_only(&webview, true);
+        let before = snapshot_dir(app.data_path());
+
+        let service = serde_json::to_value(service("web")).unwrap();
+        let commands = [
+            (
+                "save_service",
+                json!({ "environment": "dev", "service": service }),
+            ),
+            (
+                "delete_service",
+                json!({ "environment": "dev", "serviceId": "api" }),
+            ),
+            (
+                "delete_relationship",
+                json!({ "environment": "dev", "relationshipId": "api-db" }),
+            ),
+            (
+                "delete_relationships_for_service",
+                json!({ "environment": "dev", "serviceId": "db" }),
+            ),
+            (
+                "execute_batch",
+                json!({ "environment": "dev", "operations": [] }),
+            ),
+            ("create_environment", json!({ "environment": "staging" })),
+            (
+                "generate_sample_environment",
+                json!({ "environment": "demo" }),
+            ),
+            (
+                "import_drawio",
+                json!({ "environment": "dev", "filePath": "map.drawio" }),
+            ),
+            (
+                "import_nginx_config",
+                json!({ "environment": "dev", "configPath": "nginx.conf", "gatewayServiceId": "api" }),
+            ),
+            (
+                "import_from_consul",
+                json!({ "environment": "dev", "baseUrl": "http://127.0.0.1:1" }),
+            ),
+            (
+                "import_from_kubernetes_cluster",
+                json!({ "environment": "dev", "namespaces": [] }),
+            ),
+            (
+                "enrich_from_github",
+                json!({ "environment": "dev", "serviceIds": ["api"] }),
+            ),
+            (
+                "probe_service_health",
+                json!({ "environment": "dev", "apply": true }),
+            ),
+            (
+                "fix_validation_issues",
+                json!({ "environment": "dev", "fixes": {} }),
+            ),
+            ("suppress_validation_issue", json!({ "environment": "dev" })),
+            (
+                "save_graph_annotations",
+                json!({ "environment": "dev", "annotations": {} }),
+            ),
+            ("save_view", json!({ "view": {} })),
+            ("save_group", json!({ "environment": "dev", "group": {} })),
+            (
+                "add_custom_type",
+                json!({ "kind": "service", "definition": {} }),
+            ),
+            (
+                "rename_service_metadata_key",
+                json!({ "environment": "dev", "oldKey": "a", "newKey": "b" }),
+            ),
+            (
+                "create_snapshot",
+                json!({ "environment": "dev", "label": "before" }),
+            ),
+            (
+                "extract_to_environment",
+                json!({ "sourceEnvironment": "dev", "targetEnvironment": "prod", "selector": {} }),
+            ),
+            (
+                "create_placeholder_services",
+                json!({ "environment": "dev", "serviceIds": ["web"] }),
+            ),
+            (
+                "apply_reconciliation",
+                json!({ "environment": "dev", "accepted": [] }),
+            ),
+            (
+                "transfer_ownership",
+                json!({ "environment": "dev", "fromTeam": "a", "toTeam": "b" }),
+            ),
+            (
+                "pin_service",
+                json!({ "environment": "dev", "serviceId": "api" }),
+            ),
+            ("update_settings", json!({ "settings": {} })),
+            ("run_scheduled_export_now", json!({})),
+            (
+                "migrate_encryption",
+                json!({ "environment": "dev", "enable": true }),
+            ),
+            ("add_workspace", json!({ "name": "other", "path": "/tmp" })),
+            ("undo", json!({ "environment": "dev" })),
+        ];
+        for (command, args) in commands {
+            assert_blocked(&webview, command, args);
+        }
+        assert_eq!(snapshot_dir(app.data_path()), before);
+
+        // The same calls go through once the mode is off
+        set_read_only(&webview, false);
+        invoke(
+            &webview,
+            "delete_relationship",
+            json!({ "environment": "dev", "relationshipId": "api-db" }),
+        )
+        .unwrap();
+        invoke(
+            &webview,
+            "create_environment",
+            json!({ "environment": "staging" }),
+        )
+        .unwrap();
+        assert!(app.data_path().join("staging").is_dir());
+    }
+
+    #[test]
+    fn reads_keep_working() {
+        let (_app, webview) = guarded_app();
+        set_read_only(&webview, true);
+
+        let services = invoke(
+            &webview,
+            "get_all_services",
+            json!({ "environment": "dev" }),
+        )
+        .unwrap();
+        assert_eq!(services.as_array().unwrap().len(), 2);
+        invoke(
+            &webview,
+            "get_relationships_for_service",
+            json!({ "environment": "dev", "serviceId": "db" }),
+        )
+        .unwrap();
+        invoke(
+            &webview,
+            "validate_environment",
+            json!({ "environment": "dev" }),
+        )
+        .unwrap();
+        invoke(
+            &webview,
+            "switch_environment",
+            json!({ "environment": "dev" }),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn every_registered_command_is_allowed_on_purpose_or_blocked() {
+        let registered = registered_commands();
+        assert!(registered.len() > READ_ONLY_COMMANDS.len());
+
+        // An allowed name that isn't registered is a typo or a removed command
+        let names: HashSet<&str> = registered.iter().map(String::as_str).collect();
+        for command in READ_ONLY_COMMANDS {
+            assert!(
+                names.contains(command),
+                "{command} is allowed but not registered"
+            );
+        }
+        let allowed: HashSet<&str> = READ_ONLY_COMMANDS.iter().copied().collect();
+        assert_eq!(
+            allowed.len(),
+            READ_ONLY_COMMANDS.len(),
+            "a command is listed twice"
+        );
+
+        // Everything else is rejected before its arguments are even looked at
+        let (app, webview) = guarded_app();
+        set_read_only(&webview, true);
+        let before = snapshot_dir(app.data_path());
+        for command in registered.iter().filter(|command| !allows(command)) {
+            assert_blocked(&webview, command, json!({}));
+        }
+        assert_eq!(snapshot_dir(app.data_path()), before);
+    }
+}
@@ -0,0 +1,393 @@
+//! Redaction of sensitive values from exports.
+//!
+//! Exports made with `redact` pass the services and relationships through
+//! `redact` first, which applies the redaction config of the settings (see
+//! `models::RedactionConfig`): matching metadata keys, at any depth, and
+//! the configured service fields are masked as `"***"` or stripped.
+//!
+//! Structured exports (JSON) are rendered from the redacted data. Text
+//! reports built from the data, such as CSV and Markdown, are rendered
+//! as usual and then have every redacted value masked wherever it still
+//! appears (see `Redacted::mask_in_text`); a stripped value is masked
+//! there too, since a word can't be left out of a sentence.
+
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+use std::borrow::Borrow;
+
+use crate::error::AppError;
+use crate::models::{RedactedField, RedactionAction, RedactionConfig, Relationship, Service};
+
+/// What masked values are replaced with.
+pub const MASK: &str = "***";
+
+/// Services and relationships with the sensitive values redacted.
+///
+/// # Fields
+///
+/// * `services` - The services, redacted
+/// * `relationships` - The relationships, redacted
+/// * `redacted_values` - How many metadata values and fields were masked or stripped
+/// * `hidden_values` - The strings that were redacted, longest first, for `mask_in_text`
+#[derive(Debug)]
+pub struct Redacted {
+    pub services: Vec<Service>,
+    pub relationships: Vec<Relationship>,
+    pub redacted_values: usize,
+    hidden_values: Vec<String>,
+}
+
+impl Redacted {
+    /// Masks the redacted values wherever they appear in a text.
+    ///
+    /// Only whole occurrences are masked: a value next to a letter, digit,
+    /// `-` or `_` is part of another word (a team `api` in `api-gateway`),
+    /// so it is kept.
+    ///
+    /// # Returns
+    ///
+    /// The masked text and how many occurrences were masked.
+    pub fn mask_in_text(&self, text: &str) -> (String, usize) {
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        let mut text = text.to_string();
+        let mut masked_count = 0;
+
+        for value in &self.hidden_values {
+            let mut masked = String::with_capacity(text.len());
+            let mut rest = text.as_str();
+            while let Some(at) = rest.find(value.as_str()) {
+                let (head, tail) = rest.split_at(at);
+                let after = &tail[value.len()..];
+                masked.push_str(head);
+                let whole = !masked.chars().next_back().is_some_and(is_word_char)
+                    && !after.chars().next().is_some_and(is_word_char);
+                if whole {
+                    masked.push_str(MASK);
+                    masked_count += 1;
+                } else {
+                    masked.push_str(value);
+                }
+                rest = after;
+            }
+            masked.push_str(rest);
+            text = masked;
+        }
+
+        (text, masked_count)
+    }
+}
+
+/// Checks that every metadata key pattern of a redaction config compiles.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the config can be applied
+/// * `Err(AppError::ValidationError)` - If a pattern isn't a valid regular expression
+pub fn validate_config(config: &RedactionConfig) -> Result<(), AppError> {
+    compile(config).map(|_| ())
+}
+
+/// Redacts services and relationships as a redaction config says.
+///
+/// Metadata keys are matched against the config's patterns ignoring case,
+/// in the metadata of services and relationships and in every object nested
+/// in it (including objects in arrays). The value of a matching key counts
+/// as one redacted value, however much it contains. Configured service
+/// fields count once each if they are set.
+///
+/// # Arguments
+///
+/// * `services` - The services to redact
+/// * `relationships` - The relationships to redact
+/// * `config` - What to redact
+///
+/// # Returns
+///
+/// * `Ok(Redacted)` - Redacted copies of the services and relationships
+/// * `Err(AppError::ValidationError)` - If a pattern isn't a valid regular expression
+pub fn redact<S: Borrow<Service>>(
+    services: &[S],
+    relationships: &[Relationship],
+    config: &RedactionConfig,
+) -> Result<Redacted, AppError> {
+    let rules = compile(config)?;
+    let mut tally = Tally::default();
+
+    let services = services
+        .iter()
+        .map(|service| {
+            let mut service = service.borrow().clone();
+            for redaction in &config.service_fields {
+                let field = match redaction.field {
+                    RedactedField::Description => &mut service.description,
+                    RedactedField::Version => &mut service.version,
+                    RedactedField::Owner => &mut service.owner,
+                    RedactedField::Team => &mut service.team,
+                };
+                if let Some(value) = field.take() {
+                    tally.hide(&Value::String(value));
+                    if redaction.action == RedactionAction::Mask {
+                        *field = Some(MASK.to_string());
+                    }
+                }
+            }
+            service
+                .metadata
+                .retain(|key, value| redact_entry(key, value, &rules, &mut tally));
+            service
+        })
+        .collect();
+
+    let relationships = relationships
+        .iter()
+        .map(|relationship| {
+            let mut relationship = relationship.clone();
+            if let Some(metadata) = &mut relationship.metadata {
+                metadata.retain(|key, value| redact_entry(key, value, &rules, &mut tally));
+            }
+            relationship
+        })
+        .collect();
+
+    let mut hidden_values = tally.hidden_values;
+    hidden_values.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    hidden_values.dedup();
+
+    Ok(Redacted {
+        services,
+        relationships,
+        redacted_values: tally.redacted_values,
+        hidden_values,
+    })
+}
+
+/// Compiled metadata key patterns with their actions.
+type Rules = Vec<(Regex, RedactionAction)>;
+
+/// Counts what `redact` hid.
+#[derive(Default)]
+struct Tally {
+    redacted_values: usize,
+    hidden_values: Vec<String>,
+}
+
+impl Tally {
+    /// Records a redacted value and the strings in it.
+    fn hide(&mut self, value: &Value) {
+        self.redacted_values += 1;
+        collect_strings(value, &mut self.hidden_values);
+    }
+}
+
+/// Compiles the metadata key patterns, anchored and ignoring case.
+fn compile(config: &RedactionConfig) -> Result<Rules, AppError> {
+    config
+        .metadata_keys
+        .iter()
+        .map(|redaction| {
+            let regex = RegexBuilder::new(&format!("^(?:{})$", redaction.pattern))
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| {
+                    AppError::ValidationError(format!(
+                        "Invalid redaction pattern '{}': {}",
+                        redaction.pattern, e
+                    ))
+                })?;
+            Ok((regex, redaction.action))
+        })
+        .collect()
+}
+
+/// Redacts one metadata entry, recursing into values whose key doesn't match.
+///
+/// Returns false if the entry must be stripped.
+fn redact_entry(key: &str, value: &mut Value, rules: &Rules, tally: &mut Tally) -> bool {
+    match rules.iter().find(|(regex, _)| regex.is_match(key)) {
+        Some((_, RedactionAction::Strip)) => {
+            tally.hide(value);
+            false
+        }
+        Some((_, RedactionAction::Mask)) => {
+            tally.hide(value);
+            *value = Value::String(MASK.to_string());
+            true
+        }
+        None => {
+            redact_nested(value, rules, tally);
+            true
+        }
+    }
+}
+
+/// Redacts the objects nested in a metadata value.
+fn redact_nested(value: &mut Value, rules: &Rules, tally: &mut Tally) {
+    match value {
+        Value::Object(object) => {
+            object.retain(|key, value| redact_entry(key, value, rules, tally));
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact_nested(item, rules, tally);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Appends the non-empty strings in a value, at any depth.
+fn collect_strings(value: &Value, strings: &mut Vec<String>) {
+    match value {
+        Value::String(text) if !text.is_empty() => strings.push(text.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, strings)),
+        Value::Object(object) => object
+            .values()
+            .for_each(|value| collect_strings(value, strings)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{relationship, service};
+    use serde_json::json;
+
+    /// A config as the settings store it.
+    fn config(config: Value) -> RedactionConfig {
+        serde_json::from_value(config).unwrap()
+    }
+
+    fn with_metadata(metadata: Value) -> Service {
+        let mut service = service("api");
+        service.metadata = serde_json::from_value(metadata).unwrap();
+        service
+    }
+
+    fn redacted_metadata(redacted: &Redacted) -> Value {
+        serde_json::to_value(&redacted.services[0].metadata).unwrap()
+    }
+
+    #[test]
+    fn keys_match_ignoring_case() {
+        let service = with_metadata(json!({
+            "Password": "hunter2",
+            "DB_PASSWORD": "s3cret",
+            "password_hint": "pet name",
+            "region": "eu-west-1",
+        }));
+        let config = config(json!({ "metadataKeys": [{ "pattern": ".*password" }] }));
+
+        let redacted = redact(&[service], &[], &config).unwrap();
+
+        assert_eq!(
+            redacted_metadata(&redacted),
+            json!({
+                "Password": MASK,
+                "DB_PASSWORD": MASK,
+                "password_hint": "pet name",
+                "region": "eu-west-1",
+            })
+        );
+        assert_eq!(redacted.redacted_values, 2);
+    }
+
+    #[test]
+    fn nested_objects_are_redacted_at_any_depth() {
+        let service = with_metadata(json!({
+            "database": {
+                "host": "db.internal",
+                "credentials": { "user": "app", "Token": "abc" },
+            },
+            "replicas": [
+                { "host": "replica-1.internal", "token": "def" },
+                { "host": "replica-2.internal" },
+            ],
+        }));
+        let config = config(json!({
+            "metadataKeys": [
+                { "pattern": "host", "action": "mask" },
+                { "pattern": "token", "action": "strip" },
+            ],
+        }));
+
+        let redacted = redact(&[service], &[], &config).unwrap();
+
+        assert_eq!(
+            redacted_metadata(&redacted),
+            json!({
+                "database": {
+                    "host": MASK,
+                    "credentials": { "user": "app" },
+                },
+                "replicas": [{ "host": MASK }, { "host": MASK }],
+            })
+        );
+        assert_eq!(redacted.redacted_values, 5);
+    }
+
+    #[test]
+    fn a_matching_object_counts_once_and_hides_every_string_in_it() {
+        let service = with_metadata(json!({
+            "Connection": { "url": "postgres://db.internal/app", "pool": 5 },
+        }));
+        let config = config(json!({
+            "metadataKeys": [{ "pattern": "connection", "action": "strip" }],
+        }));
+
+        let redacted = redact(&[service], &[], &config).unwrap();
+
+        assert_eq!(redacted_metadata(&redacted), json!({}));
+        assert_eq!(redacted.redacted_values, 1);
+        let (text, masked) = redacted.mask_in_text("uses postgres://db.internal/app daily");
+        assert_eq!(text, "uses *** daily");
+        assert_eq!(masked, 1);
+    }
+
+    #[test]
+    fn relationship_metadata_and_service_fields_are_redacted() {
+        let mut rel = relationship("rel-1", "api", "db");
+        rel.metadata = Some(serde_json::from_value(json!({ "ApiKey": "k-123" })).unwrap());
+        let mut owned = service("api");
+        owned.owner = Some("alice@example.com".to_string());
+        owned.team = Some("payments".to_string());
+        let config = config(json!({
+            "metadataKeys": [{ "pattern": "apikey" }],
+            "serviceFields": [
+                { "field": "owner" },
+                { "field": "team", "action": "strip" },
+            ],
+        }));
+
+        let redacted = redact(&[owned], &[rel], &config).unwrap();
+
+        assert_eq!(redacted.services[0].owner.as_deref(), Some(MASK));
+        assert_eq!(redacted.services[0].team, None);
+        let metadata = redacted.relationships[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata["ApiKey"], json!(MASK));
+        assert_eq!(redacted.redacted_values, 3);
+    }
+
+    #[test]
+    fn masking_in_text_keeps_parts_of_other_words() {
+        let mut owned = service("api");
+        owned.team = Some("api".to_string());
+        let config = config(json!({ "serviceFields": [{ "field": "team" }] }));
+
+        let redacted = redact(&[owned], &[], &config).unwrap();
+
+        let (text, masked) = redacted.mask_in_text("api owns api-gateway");
+        assert_eq!(text, "*** owns api-gateway");
+        assert_eq!(masked, 1);
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        let config = config(json!({ "metadataKeys": [{ "pattern": "(unclosed" }] }));
+
+        assert!(matches!(
+            validate_config(&config),
+            Err(AppError::ValidationError(_))
+        ));
+    }
+}
@@ -0,0 +1,354 @@
+//! Synthetic environment generation for benchmarks and demos.
+//!
+//! Generates plausible-looking services and relationships from a seed, so
+//! the same spec always produces the same environment. Names come from
+//! adjective-noun word lists; nothing is derived from real data.
+//!
+//! The base graph is acyclic: each service only depends on services created
+//! before it. Cycles and orphaned relationships are only added on request,
+//! to give the validation checks something to find.
+
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{
+    Relationship, RelationshipOrigin, RelationshipType, ReviewStatus, Service, ServiceStatus,
+    ServiceType,
+};
+
+/// Largest number of services a single spec may generate.
+pub const MAX_SAMPLE_SERVICES: usize = 20_000;
+
+/// Number of deliberate cycles added when `include_cycles` is set.
+const CYCLE_COUNT: usize = 3;
+
+/// Number of orphaned relationships added when `include_orphans` is set.
+const ORPHAN_COUNT: usize = 3;
+
+const ADJECTIVES: &[&str] = &[
+    "amber", "brisk", "calm", "silent", "swift", "bold", "crimson", "distant", "eager", "frozen",
+    "gentle", "hidden", "iron", "jade", "keen", "lunar", "mellow", "noble", "olive", "polar",
+    "quiet", "rapid", "solar", "tidal", "urban", "vivid", "wild", "young", "zesty", "cobalt",
+];
+
+const NOUNS: &[&str] = &[
+    "ledger", "falcon", "harbor", "beacon", "canyon", "comet", "delta", "ember", "forge",
+    "glacier", "grove", "horizon", "island", "journal", "kernel", "lantern", "meadow", "nebula",
+    "orbit", "pilot", "quarry", "relay", "summit", "tundra", "vault", "willow", "atlas", "bridge",
+    "cipher", "dynamo",
+];
+
+const TEAMS: &[&str] = &[
+    "Platform",
+    "Payments",
+    "Identity",
+    "Search",
+    "Growth",
+    "Data",
+    "Mobile",
+    "Infrastructure",
+    "Messaging",
+    "Analytics",
+    "Security",
+    "Billing",
+];
+
+const TAGS: &[&str] = &[
+    "core", "internal", "public", "critical", "legacy", "beta", "pci", "gdpr", "batch", "realtime",
+];
+
+/// Parameters for a generated environment.
+///
+/// Every field is optional in JSON; missing fields take the defaults below.
+///
+/// # Fields
+///
+/// * `service_count` - Number of services to generate (default 50)
+/// * `seed` - Seed for the generator; the same seed and spec give the same data (default 42)
+/// * `type_weights` - Relative weights per service type; empty means all built-in types equally
+/// * `status_weights` - Relative weights per status; empty means mostly healthy
+/// * `team_count` - Number of distinct teams services are spread over (default 5)
+/// * `average_fan_out` - Average number of dependencies per service (default 2.0)
+/// * `include_cycles` - Add a few circular dependencies (default false)
+/// * `include_orphans` - Add a few relationships to services that don't exist (default false)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SampleSpec {
+    pub service_count: usize,
+    pub seed: u64,
+    pub type_weights: Vec<(ServiceType, u32)>,
+    pub status_weights: Vec<(ServiceStatus, u32)>,
+    pub team_count: usize,
+    pub average_fan_out: f64,
+    pub include_cycles: bool,
+    pub include_orphans: bool,
+}
+
+impl Default for SampleSpec {
+    fn default() -> Self {
+        Self {
+            service_count: 50,
+            seed: 42,
+            type_weights: Vec::new(),
+            status_weights: Vec::new(),
+            team_count: 5,
+            average_fan_out: 2.0,
+            include_cycles: false,
+            include_orphans: false,
+        }
+    }
+}
+
+/// Generated services and relationships, ready to be saved.
+#[derive(Debug, Clone)]
+pub struct SampleData {
+    pub services: Vec<Service>,
+    pub relationships: Vec<Relationship>,
+}
+
+/// Generates an environment's worth of services and relationships.
+///
+/// Callers are expected to have checked `service_count` against
+/// `MAX_SAMPLE_SERVICES`.
+///
+/// # Arguments
+///
+/// * `spec` - What to generate
+///
+/// # Returns
+///
+/// The generated data. Service IDs are unique; relationship IDs are
+/// `sample-rel-00001`, `sample-rel-00002`, and so on.
+pub fn generate(spec: &SampleSpec) -> SampleData {
+    let mut rng = SampleRng::new(spec.seed);
+
+    let type_weights = if spec.type_weights.is_empty() {
+        vec![
+            (ServiceType::Api, 1),
+            (ServiceType::Database, 1),
+            (ServiceType::Cache, 1),
+            (ServiceType::Queue, 1),
+            (ServiceType::Gateway, 1),
+            (ServiceType::Frontend, 1),
+            (ServiceType::Backend, 1),
+            (ServiceType::External, 1),
+        ]
+    } else {
+        spec.type_weights.clone()
+    };
+    let status_weights = if spec.status_weights.is_empty() {
+        vec![
+            (ServiceStatus::Healthy, 70),
+            (ServiceStatus::Degraded, 15),
+            (ServiceStatus::Unhealthy, 5),
+            (ServiceStatus::Unknown, 5),
+            (ServiceStatus::Deprecated, 5),
+        ]
+    } else {
+        spec.status_weights.clone()
+    };
+    let teams = team_names(spec.team_count.max(1));
+
+    let mut used_ids = HashSet::new();
+    let mut services = Vec::with_capacity(spec.service_count);
+    for _ in 0..spec.service_count {
+        let adjective = ADJECTIVES[rng.below(ADJECTIVES.len())];
+        let noun = NOUNS[rng.below(NOUNS.len())];
+
+        let mut id = format!("{}-{}", adjective, noun);
+        let mut suffix = 2;
+        while used_ids.contains(&id) {
+            id = format!("{}-{}-{}", adjective, noun, suffix);
+            suffix += 1;
+        }
+        used_ids.insert(id.clone());
+
+        let service_type = rng.weighted(&type_weights).clone();
+        let tag_count = 1 + rng.below(3);
+        let mut tags: Vec<String> = Vec::new();
+        for _ in 0..tag_count {
+            let tag = TAGS[rng.below(TAGS.len())].to_string();
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        services.push(Service {
+            name: format!("{} {}", capitalize(adjective), capitalize(noun)),
+            description: Some(format!("Generated sample service #{}", services.len() + 1)),
+            version: Some(format!(
+                "{}.{}.{}",
+                1 + rng.below(3),
+                rng.below(10),
+                rng.below(20)
+            )),
+            owner: None,
+            team: Some(teams[rng.below(teams.len())].clone()),
+            tier: None,
+            lifecycle: None,
+            decommission_date: None,
+            successor_id: None,
+            status: rng.weighted(&status_weights).clone(),
+            service_type,
+            tags,
+            metadata: HashMap::from([("sample".to_string(), serde_json::Value::Bool(true))]),
+            revision: 0,
+            id,
+        });
+    }
+
+    let relationship_types = [
+        (RelationshipType::DependsOn, 6),
+        (RelationshipType::CommunicatesWith, 2),
+        (RelationshipType::ReadsFrom, 1),
+        (RelationshipType::WritesTo, 1),
+    ];
+
+    let mut relationships = Vec::new();
+    let mut edges = HashSet::new();
+    let mut dependencies = Vec::new();
+    let max_fan_out = (spec.average_fan_out.max(0.0) * 2.0).round() as usize;
+
+    // Each service only depends on earlier services, so the base graph is acyclic
+    for source in 1..services.len() {
+        let fan_out = rng.below(max_fan_out + 1).min(source);
+        for _ in 0..fan_out {
+            let target = rng.below(source);
+            if edges.insert((source, target)) {
+                let relationship_type = rng.weighted(&relationship_types).clone();
+                if relationship_type == RelationshipType::DependsOn {
+                    dependencies.push((source, target));
+                }
+                push_relationship(
+                    &mut relationships,
+                    &services[source].id,
+                    &services[target].id,
+                    relationship_type,
+                );
+            }
+        }
+    }
+
+    if spec.include_cycles {
+        // Close a cycle by adding the reverse of an existing dependency
+        for _ in 0..CYCLE_COUNT.min(dependencies.len()) {
+            let (source, target) = dependencies[rng.below(dependencies.len())];
+            if edges.insert((target, source)) {
+                push_relationship(
+                    &mut relationships,
+                    &services[target].id,
+                    &services[source].id,
+                    RelationshipType::DependsOn,
+                );
+            }
+        }
+    }
+
+    if spec.include_orphans && !services.is_empty() {
+        for n in 1..=ORPHAN_COUNT {
+            let source = services[rng.below(services.len())].id.clone();
+            let target = format!("missing-service-{}", n);
+            push_relationship(
+                &mut relationships,
+                &source,
+                &target,
+                RelationshipType::DependsOn,
+            );
+        }
+    }
+
+    SampleData {
+        services,
+        relationships,
+    }
+}
+
+/// Appends a relationship with the next sequential sample ID.
+fn push_relationship(
+    relationships: &mut Vec<Relationship>,
+    source: &str,
+    target: &str,
+    relationship_type: RelationshipType,
+) {
+    relationships.push(Relationship {
+        id: format!("sample-rel-{:05}", relationships.len() + 1),
+        source: source.to_string(),
+        target: target.to_string(),
+        relationship_type,
+        description: None,
+        metadata: None,
+        effective_from: None,
+        effective_until: None,
+        origin: RelationshipOrigin::Manual,
+        review_status: ReviewStatus::Unreviewed,
+        review_note: None,
+        reviewed_by: None,
+        reviewed_at: None,
+        revision: 0,
+    });
+}
+
+/// Returns `count` distinct team names, numbering repeats once the list runs out.
+fn team_names(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| match i / TEAMS.len() {
+            0 => TEAMS[i].to_string(),
+            round => format!("{} {}", TEAMS[i % TEAMS.len()], round + 1),
+        })
+        .collect()
+}
+
+/// Upper-cases the first letter of a word.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Small deterministic random number generator (SplitMix64).
+///
+/// Not suitable for anything security-related; it only has to be fast and
+/// reproducible across platforms and releases.
+struct SampleRng(u64);
+
+impl SampleRng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..n` (`n` must be non-zero).
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Picks a value with probability proportional to its weight.
+    ///
+    /// Falls back to the first value when all weights are zero.
+    fn weighted<'a, T>(&mut self, choices: &'a [(T, u32)]) -> &'a T {
+        let total: u64 = choices.iter().map(|(_, w)| u64::from(*w)).sum();
+        if total == 0 {
+            return &choices[0].0;
+        }
+
+        let mut pick = self.next_u64() % total;
+        for (value, weight) in choices {
+            let weight = u64::from(*weight);
+            if pick < weight {
+                return value;
+            }
+            pick -= weight;
+        }
+
+        &choices[choices.len() - 1].0
+    }
+}
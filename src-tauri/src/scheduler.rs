@@ -0,0 +1,608 @@
+//! Scheduled snapshots and exports while the app is running.
+//!
+//! When the `scheduledExport` setting is enabled, a background thread
+//! (`Scheduler`) exports the configured environments on an interval or once
+//! a day, either as internal snapshots or as snapshot bundles in a
+//! directory (see `models::ExportTarget`). `run_scheduled_export_now` runs
+//! the same export on demand.
+//!
+//! # Runs
+//!
+//! Each environment is exported separately. Its data is cloned through the
+//! caches, which only hold the state lock briefly, and written without any
+//! lock held. An environment that is being written when a run starts is
+//! skipped rather than waited for. Every export is reported to the frontend
+//! via the `scheduled-export-finished` event and recorded in an in-memory
+//! history of the last `MAX_HISTORY` runs (`get_scheduled_task_history`).
+//!
+//! # Retention
+//!
+//! After each successful export, scheduled outputs of the environment
+//! beyond the `retention` count are deleted, oldest first. Only outputs the
+//! scheduler made are considered: snapshots with IDs ending in
+//! `-scheduled` and files named `{environment}-{timestamp}.json`.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Mutex, PoisonError, RwLock, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::error::AppError;
+use crate::models::{ExportSchedule, ExportTarget, ScheduledExportSettings, Snapshot};
+use crate::state::{cache, read_state, AppState};
+use crate::storage;
+use crate::storage::audit::now_millis;
+
+/// Name of the event emitted to the frontend after each environment's export.
+pub const SCHEDULED_EXPORT_EVENT: &str = "scheduled-export-finished";
+
+/// Number of runs kept in the history.
+const MAX_HISTORY: usize = 100;
+
+/// Label of snapshots made by the scheduler.
+const SNAPSHOT_LABEL: &str = "Scheduled export";
+
+/// Suffix of the IDs of snapshots made by the scheduler.
+const SNAPSHOT_ID_SUFFIX: &str = "-scheduled";
+
+/// Milliseconds in a day.
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+/// What started a run.
+///
+/// # Variants
+///
+/// * `Schedule` - The configured schedule
+/// * `Manual` - `run_scheduled_export_now`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RunTrigger {
+    Schedule,
+    Manual,
+}
+
+/// How an environment's export ended.
+///
+/// # Variants
+///
+/// * `Succeeded` - The export was written
+/// * `Skipped` - The environment was being written, so nothing was exported
+/// * `Failed` - The export could not be made; see the run's `message`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RunOutcome {
+    Succeeded,
+    Skipped,
+    Failed,
+}
+
+/// One environment's export, as recorded in the history.
+///
+/// Also the payload of the `scheduled-export-finished` event.
+///
+/// # Fields
+///
+/// * `environment` - The exported environment
+/// * `trigger` - What started the run
+/// * `started_at` - When the export started, in milliseconds since the Unix epoch
+/// * `outcome` - How the export ended
+/// * `output` - The snapshot ID or file path written, if any
+/// * `pruned` - Number of older scheduled outputs deleted afterwards
+/// * `message` - Why the export was skipped or failed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledRun {
+    pub environment: String,
+    pub trigger: RunTrigger,
+    pub started_at: u64,
+    pub outcome: RunOutcome,
+    pub output: Option<String>,
+    pub pruned: usize,
+    pub message: Option<String>,
+}
+
+/// Handle to the background scheduler thread.
+///
+/// Managed as Tauri state; `run_exports` records its runs in the history.
+pub struct Scheduler {
+    sender: Sender<()>,
+    history: Mutex<VecDeque<ScheduledRun>>,
+}
+
+impl Scheduler {
+    /// Spawns the background scheduler thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - Handle used to read the settings and data and to emit events
+    pub fn start<R: Runtime>(app: AppHandle<R>) -> Self {
+        let (sender, receiver) = channel::<()>();
+
+        thread::spawn(move || schedule_loop(app, receiver));
+
+        Self {
+            sender,
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Makes the thread pick up changed settings.
+    ///
+    /// An interval schedule starts counting again from now.
+    pub fn reschedule(&self) {
+        if self.sender.send(()).is_err() {
+            eprintln!("Warning: scheduler thread is not running");
+        }
+    }
+
+    /// Returns the recorded runs, most recent first.
+    pub fn history(&self) -> Vec<ScheduledRun> {
+        let history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        history.iter().rev().cloned().collect()
+    }
+}
+
+/// Checks scheduled export settings before they are stored.
+///
+/// Disabled settings are not checked, so they can be saved half-filled.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the settings can be run
+/// * `Err(AppError::ValidationError)` - Naming the first problem found
+pub fn validate_settings(settings: &ScheduledExportSettings) -> Result<(), AppError> {
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    match &settings.schedule {
+        ExportSchedule::Interval { minutes: 0 } => {
+            return Err(AppError::ValidationError(
+                "The export interval must be at least one minute".to_string(),
+            ))
+        }
+        ExportSchedule::Interval { .. } => {}
+        ExportSchedule::Daily { time } => {
+            if daily_offset(time).is_none() {
+                return Err(AppError::ValidationError(format!(
+                    "Daily export time must be HH:MM, got '{}'",
+                    time
+                )));
+            }
+        }
+    }
+    if let ExportTarget::Directory { path } = &settings.target {
+        if !Path::new(path).is_absolute() {
+            return Err(AppError::ValidationError(format!(
+                "Export directory must be an absolute path, got '{}'",
+                path
+            )));
+        }
+    }
+    if settings.environments.is_empty() {
+        return Err(AppError::ValidationError(
+            "Choose at least one environment to export".to_string(),
+        ));
+    }
+    for environment in &settings.environments {
+        storage::validate_environment_name(environment)?;
+    }
+    if settings.retention == 0 {
+        return Err(AppError::ValidationError(
+            "Keep at least one scheduled export".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Exports every configured environment now and records the runs.
+///
+/// # Arguments
+///
+/// * `app` - Handle used to read the data and to emit events
+/// * `settings` - The scheduled export settings to run with
+/// * `trigger` - What started the run
+///
+/// # Returns
+///
+/// The runs, one per environment, in the order of `settings.environments`.
+pub fn run_exports<R: Runtime>(
+    app: &AppHandle<R>,
+    settings: &ScheduledExportSettings,
+    trigger: RunTrigger,
+) -> Vec<ScheduledRun> {
+    let state = app.state::<RwLock<AppState>>();
+    let runs: Vec<ScheduledRun> = settings
+        .environments
+        .iter()
+        .map(|environment| export_environment(&state, environment, settings, trigger))
+        .collect();
+
+    if let Some(scheduler) = app.try_state::<Scheduler>() {
+        let mut history = scheduler
+            .history
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for run in &runs {
+            history.push_back(run.clone());
+            if history.len() > MAX_HISTORY {
+                history.pop_front();
+            }
+        }
+    }
+    for run in &runs {
+        if let Err(e) = app.emit(SCHEDULED_EXPORT_EVENT, run) {
+            eprintln!(
+                "Warning: failed to emit {} event: {}",
+                SCHEDULED_EXPORT_EVENT, e
+            );
+        }
+    }
+
+    runs
+}
+
+/// Runs exports on schedule until the scheduler is dropped.
+///
+/// Settings are re-read before every wait, so disabled or changed settings
+/// take effect once `Scheduler::reschedule` wakes the thread.
+fn schedule_loop<R: Runtime>(app: AppHandle<R>, receiver: Receiver<()>) {
+    let mut since = Instant::now();
+
+    loop {
+        let settings = {
+            let state = app.state::<RwLock<AppState>>();
+            let state = read_state(&state);
+            state.settings.scheduled_export.clone()
+        };
+        let wait = if settings.enabled {
+            time_until_next_run(&settings.schedule, since)
+        } else {
+            None
+        };
+
+        let message = match wait {
+            Some(wait) => receiver.recv_timeout(wait),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match message {
+            Ok(()) => since = Instant::now(),
+            Err(RecvTimeoutError::Timeout) => {
+                run_exports(&app, &settings, RunTrigger::Schedule);
+                since = Instant::now();
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+/// Returns how long to wait for the next scheduled run (`None` if it can't be scheduled).
+fn time_until_next_run(schedule: &ExportSchedule, since: Instant) -> Option<Duration> {
+    match schedule {
+        ExportSchedule::Interval { minutes } => {
+            let next = since + Duration::from_secs((*minutes).max(1).saturating_mul(60));
+            Some(next.saturating_duration_since(Instant::now()))
+        }
+        ExportSchedule::Daily { time } => {
+            let offset = daily_offset(time)?;
+            let now = now_millis();
+            let today = now - now % DAY_MILLIS + offset;
+            let next = if today > now {
+                today
+            } else {
+                today + DAY_MILLIS
+            };
+            Some(Duration::from_millis(next - now))
+        }
+    }
+}
+
+/// Parses a `"HH:MM"` time of day into milliseconds after midnight.
+fn daily_offset(time: &str) -> Option<u64> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let hours: u64 = hours.parse().ok().filter(|h| *h < 24)?;
+    let minutes: u64 = minutes.parse().ok().filter(|m| *m < 60)?;
+    Some((hours * 60 + minutes) * 60 * 1000)
+}
+
+/// Exports one environment and prunes its older scheduled outputs.
+fn export_environment(
+    state: &RwLock<AppState>,
+    environment: &str,
+    settings: &ScheduledExportSettings,
+    trigger: RunTrigger,
+) -> ScheduledRun {
+    let started_at = now_millis();
+    let mut run = ScheduledRun {
+        environment: environment.to_string(),
+        trigger,
+        started_at,
+        outcome: RunOutcome::Succeeded,
+        output: None,
+        pruned: 0,
+        message: None,
+    };
+
+    match write_export(state, environment, settings, started_at) {
+        Ok(Some((output, pruned))) => {
+            run.output = Some(output);
+            run.pruned = pruned;
+        }
+        Ok(None) => {
+            run.outcome = RunOutcome::Skipped;
+            run.message = Some(format!("Environment {} is being written", environment));
+        }
+        Err(e) => {
+            run.outcome = RunOutcome::Failed;
+            run.message = Some(e.to_string());
+        }
+    }
+
+    run
+}
+
+/// Writes one environment's export.
+///
+/// # Returns
+///
+/// * `Ok(Some((output, pruned)))` - The snapshot ID or file written, and the
+///   number of older outputs deleted
+/// * `Ok(None)` - If the environment is being written
+/// * `Err(AppError)` - If the environment doesn't exist or the export can't be written
+fn write_export(
+    state: &RwLock<AppState>,
+    environment: &str,
+    settings: &ScheduledExportSettings,
+    created_at: u64,
+) -> Result<Option<(String, usize)>, AppError> {
+    // Skip rather than wait while a command is writing
+    let (data_path, key) = {
+        let guard = match state.try_read() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return Ok(None),
+        };
+        if !guard.storage.environment_exists(environment)? {
+            return Err(AppError::EnvironmentNotFound(environment.to_string()));
+        }
+        (guard.data_path.clone(), guard.encryption_key.clone())
+    };
+
+    let mut services: Vec<_> = cache::shared_services(state, environment)?
+        .iter()
+        .map(|s| s.as_ref().clone())
+        .collect();
+    services.sort_by(|a, b| a.id.cmp(&b.id));
+    let relationships = cache::shared_relationships(state, environment)?.to_vec();
+
+    let snapshot = Snapshot {
+        id: format!("{}{}", created_at, SNAPSHOT_ID_SUFFIX),
+        label: SNAPSHOT_LABEL.to_string(),
+        created_at,
+        services,
+        relationships,
+    };
+
+    match &settings.target {
+        ExportTarget::Snapshot => {
+            storage::save_snapshot(&data_path, environment, &snapshot, key.as_ref())?;
+            let dir = storage::snapshots::snapshots_dir(&data_path, environment)?;
+            let pruned = prune_outputs(&dir, "", SNAPSHOT_ID_SUFFIX, settings.retention)?;
+            Ok(Some((snapshot.id, pruned)))
+        }
+        ExportTarget::Directory { path } => {
+            let dir = PathBuf::from(path);
+            let prefix = format!("{}-", environment);
+            let file = dir.join(format!("{}{}.json", prefix, created_at));
+            storage::snapshots::write_bundle(&file, &snapshot, key.as_ref())?;
+            let pruned = prune_outputs(&dir, &prefix, "", settings.retention)?;
+            Ok(Some((file.display().to_string(), pruned)))
+        }
+    }
+}
+
+/// Deletes the oldest scheduled outputs in a directory beyond the retention count.
+///
+/// Outputs are the files named `{prefix}{timestamp}{suffix}.json`; others
+/// are left alone.
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The number of files deleted
+/// * `Err(AppError::Io)` - If the directory cannot be read or a file cannot be deleted
+fn prune_outputs(
+    dir: &Path,
+    prefix: &str,
+    suffix: &str,
+    retention: usize,
+) -> Result<usize, AppError> {
+    let mut outputs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let timestamp = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| output_timestamp(name, prefix, suffix));
+        if let Some(timestamp) = timestamp {
+            outputs.push((timestamp, path));
+        }
+    }
+
+    let expired = outputs_to_prune(outputs, retention);
+    for path in &expired {
+        fs::remove_file(path)?;
+    }
+
+    Ok(expired.len())
+}
+
+/// Returns the timestamp of a scheduled output's file name, if it is one.
+fn output_timestamp(name: &str, prefix: &str, suffix: &str) -> Option<u64> {
+    let timestamp = name
+        .strip_prefix(prefix)?
+        .strip_suffix(".json")?
+        .strip_suffix(suffix)?;
+    if timestamp.is_empty() || !timestamp.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    timestamp.parse().ok()
+}
+
+/// Picks the outputs beyond the newest `retention`, given with their timestamps.
+fn outputs_to_prune(mut outputs: Vec<(u64, PathBuf)>, retention: usize) -> Vec<PathBuf> {
+    outputs.sort_by(|a, b| b.cmp(a));
+    outputs
+        .into_iter()
+        .skip(retention)
+        .map(|(_, path)| path)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::write_state;
+    use crate::test_support::{service, TestApp};
+    use tempfile::TempDir;
+
+    fn settings(target: ExportTarget, retention: usize) -> ScheduledExportSettings {
+        ScheduledExportSettings {
+            enabled: true,
+            environments: vec!["dev".to_string()],
+            target,
+            retention,
+            ..Default::default()
+        }
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn the_newest_outputs_are_kept_whatever_their_order() {
+        let outputs = [30, 10, 50, 20, 40]
+            .map(|t| (t, PathBuf::from(format!("dev-{}.json", t))))
+            .to_vec();
+
+        let expired = outputs_to_prune(outputs.clone(), 2);
+        assert_eq!(
+            expired,
+            ["dev-30.json", "dev-20.json", "dev-10.json"].map(PathBuf::from)
+        );
+        assert!(outputs_to_prune(outputs, 5).is_empty());
+    }
+
+    #[test]
+    fn only_scheduled_output_names_have_timestamps() {
+        assert_eq!(
+            output_timestamp("dev-1700000000000.json", "dev-", ""),
+            Some(1700000000000)
+        );
+        assert_eq!(
+            output_timestamp("17-scheduled.json", "", SNAPSHOT_ID_SUFFIX),
+            Some(17)
+        );
+        for name in [
+            "dev-staging-17.json",
+            "dev-.json",
+            "dev-17.json.bak",
+            "prod-17.json",
+            "notes.txt",
+        ] {
+            assert_eq!(output_timestamp(name, "dev-", ""), None, "{name}");
+        }
+        // Manual snapshots are never pruned
+        assert_eq!(output_timestamp("17.json", "", SNAPSHOT_ID_SUFFIX), None);
+    }
+
+    #[test]
+    fn directory_exports_are_pruned_to_the_retention_count() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        let out = TempDir::new().unwrap();
+        fs::write(out.path().join("dev-notes.json"), "{}").unwrap();
+        fs::write(out.path().join("prod-1.json"), "{}").unwrap();
+        let settings = settings(
+            ExportTarget::Directory {
+                path: out.path().display().to_string(),
+            },
+            2,
+        );
+
+        let mut pruned = Vec::new();
+        for created_at in [1000, 2000, 3000, 4000] {
+            let (_, count) = write_export(&app.state(), "dev", &settings, created_at)
+                .unwrap()
+                .unwrap();
+            pruned.push(count);
+        }
+
+        assert_eq!(pruned, [0, 0, 1, 1]);
+        assert_eq!(
+            file_names(out.path()),
+            [
+                "dev-3000.json",
+                "dev-4000.json",
+                "dev-notes.json",
+                "prod-1.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshot_exports_leave_manual_snapshots_alone() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        let manual = Snapshot {
+            id: "500".to_string(),
+            label: "Before the migration".to_string(),
+            created_at: 500,
+            services: Vec::new(),
+            relationships: Vec::new(),
+        };
+        storage::save_snapshot(app.data_path(), "dev", &manual, None).unwrap();
+        let settings = settings(ExportTarget::Snapshot, 1);
+
+        for created_at in [1000, 2000, 3000] {
+            write_export(&app.state(), "dev", &settings, created_at)
+                .unwrap()
+                .unwrap();
+        }
+
+        let dir = storage::snapshots::snapshots_dir(app.data_path(), "dev").unwrap();
+        assert_eq!(file_names(&dir), ["3000-scheduled.json", "500.json"]);
+    }
+
+    #[test]
+    fn an_environment_being_written_is_skipped() {
+        let app = TestApp::with_environment("dev", &[service("api")], &[]);
+        let out = TempDir::new().unwrap();
+        let settings = settings(
+            ExportTarget::Directory {
+                path: out.path().display().to_string(),
+            },
+            3,
+        );
+        let state = app.state();
+
+        let guard = write_state(&state);
+        let run = export_environment(&state, "dev", &settings, RunTrigger::Manual);
+        drop(guard);
+
+        assert_eq!(run.outcome, RunOutcome::Skipped);
+        assert!(file_names(out.path()).is_empty());
+        let run = export_environment(&state, "dev", &settings, RunTrigger::Manual);
+        assert_eq!(run.outcome, RunOutcome::Succeeded);
+        assert_eq!(file_names(out.path()).len(), 1);
+    }
+}
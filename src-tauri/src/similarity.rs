@@ -0,0 +1,213 @@
+//! Detection of services that are probably duplicates of each other.
+//!
+//! Two teams entering the same service rarely pick the same ID, but the
+//! names tend to stay close ("User API" and "user-api-service"). Names are
+//! normalized (lowercased, punctuation and filler words like "service"
+//! removed) and compared by edit distance.
+//!
+//! The comparison is pairwise, so it is quadratic in the number of
+//! services. Names are sorted by length so the scan can stop as soon as the
+//! length difference alone exceeds the distance, the distance computation
+//! gives up once a row exceeds it, and the number of reported pairs is
+//! capped.
+
+use std::borrow::Borrow;
+
+use crate::models::Service;
+
+/// Maximum edit distance used when no threshold is given.
+pub const DEFAULT_MAX_DISTANCE: usize = 2;
+
+/// Maximum number of pairs reported by a single scan.
+pub const MAX_REPORTED_PAIRS: usize = 200;
+
+/// Normalized names shorter than this only match exactly, as a couple of
+/// edits turn any short name into another ("api" and "app").
+const MIN_FUZZY_LENGTH: usize = 6;
+
+/// Words that don't distinguish services and are dropped from names.
+const FILLER_WORDS: &[&str] = &["service", "services", "svc", "server", "the"];
+
+/// Two services with similar names.
+///
+/// # Fields
+///
+/// * `first` - Index of one service in the scanned slice
+/// * `second` - Index of the other service
+/// * `distance` - Edit distance between the normalized names (0 if equal)
+#[derive(Debug, Clone, Copy)]
+pub struct SimilarPair {
+    pub first: usize,
+    pub second: usize,
+    pub distance: usize,
+}
+
+/// Finds pairs of services with similar names.
+///
+/// Services whose name normalizes to nothing are skipped.
+///
+/// # Arguments
+///
+/// * `services` - The services to compare
+/// * `max_distance` - Largest edit distance between normalized names that counts as similar
+/// * `limit` - Maximum number of pairs to return
+///
+/// # Returns
+///
+/// A tuple of:
+/// * The similar pairs, closest first, then by the services' IDs
+/// * Whether the scan stopped at `limit`, so more pairs may exist
+pub fn similar_pairs<S: Borrow<Service>>(
+    services: &[S],
+    max_distance: usize,
+    limit: usize,
+) -> (Vec<SimilarPair>, bool) {
+    let mut names: Vec<(usize, Vec<char>)> = services
+        .iter()
+        .enumerate()
+        .map(|(index, service)| (index, normalize_name(&service.borrow().name)))
+        .filter(|(_, name)| !name.is_empty())
+        .collect();
+    names.sort_by(|(a_index, a), (b_index, b)| {
+        let a_id = &services[*a_index].borrow().id;
+        let b_id = &services[*b_index].borrow().id;
+        a.len().cmp(&b.len()).then_with(|| a_id.cmp(b_id))
+    });
+
+    let mut pairs = Vec::new();
+    let mut truncated = false;
+
+    'scan: for (i, (first, first_name)) in names.iter().enumerate() {
+        // Only exact matches for short names
+        let max = if first_name.len() < MIN_FUZZY_LENGTH {
+            0
+        } else {
+            max_distance
+        };
+
+        for (second, second_name) in &names[i + 1..] {
+            // Sorted by length, so every later name is at least as far off
+            if second_name.len() - first_name.len() > max {
+                break;
+            }
+            // Reported as duplicate IDs instead
+            if services[*first].borrow().id == services[*second].borrow().id {
+                continue;
+            }
+            let Some(distance) = bounded_distance(first_name, second_name, max) else {
+                continue;
+            };
+
+            if pairs.len() == limit {
+                truncated = true;
+                break 'scan;
+            }
+            pairs.push(SimilarPair {
+                first: *first,
+                second: *second,
+                distance,
+            });
+        }
+    }
+
+    pairs.sort_by(|a, b| {
+        let ids = |pair: &SimilarPair| {
+            (
+                services[pair.first].borrow().id.clone(),
+                services[pair.second].borrow().id.clone(),
+            )
+        };
+        a.distance
+            .cmp(&b.distance)
+            .then_with(|| ids(a).cmp(&ids(b)))
+    });
+
+    (pairs, truncated)
+}
+
+/// Returns the candidate closest to a name, if one is similar enough.
+///
+/// Names are normalized and compared like service names in `similar_pairs`
+/// with the default maximum distance, e.g. to suggest the registered spelling
+/// of a custom type. Ties go to the first candidate.
+pub fn closest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let normalized = normalize_name(name);
+    let max = if normalized.len() < MIN_FUZZY_LENGTH {
+        0
+    } else {
+        DEFAULT_MAX_DISTANCE
+    };
+
+    let mut closest: Option<(usize, &str)> = None;
+    for candidate in candidates {
+        let Some(distance) = bounded_distance(&normalized, &normalize_name(candidate), max) else {
+            continue;
+        };
+        if closest.is_none_or(|(best, _)| distance < best) {
+            closest = Some((distance, candidate));
+        }
+    }
+    closest.map(|(_, candidate)| candidate)
+}
+
+/// Lowercases a name and drops punctuation, whitespace and filler words.
+///
+/// Filler words are kept if the name consists of nothing else.
+fn normalize_name(name: &str) -> Vec<char> {
+    let lowercase = name.to_lowercase();
+    let words: Vec<&str> = lowercase
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let significant: Vec<&str> = words
+        .iter()
+        .copied()
+        .filter(|word| !FILLER_WORDS.contains(word))
+        .collect();
+    let words = if significant.is_empty() {
+        words
+    } else {
+        significant
+    };
+
+    words.concat().chars().collect()
+}
+
+/// Returns the Levenshtein distance between two strings if it's at most `max`.
+///
+/// Gives up as soon as every entry of a row exceeds `max`, since a row's
+/// minimum never decreases.
+fn bounded_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    if a == b {
+        return Some(0);
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        let mut row_min = current[0];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            row_min = row_min.min(current[j + 1]);
+        }
+
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let distance = previous[b.len()];
+    (distance <= max).then_some(distance)
+}
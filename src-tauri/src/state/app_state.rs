@@ -1,44 +1,154 @@
 //! Application state management for the Tauri backend.
 //!
 //! This module defines the shared application state that is accessible
-//! to all Tauri commands through a Mutex-protected State wrapper.
+//! to all Tauri commands through an RwLock-protected State wrapper.
 
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::models::{Relationship, Service};
+use crate::models::{ImportCounts, Relationship, Service, Settings};
+use crate::state::cache::CacheStamp;
+use crate::state::diagnostics::CommandTimings;
+use crate::state::journal::{Journal, JournalEntry};
+use crate::state::progress::RunningOperations;
+use crate::state::quick_open::QuickOpenIndex;
+use crate::state::recent::RecentServices;
+use crate::state::relationship_index::RelationshipIndex;
+use crate::storage::audit::{self, AuditRecord};
+use crate::storage::{self, EncryptionKey, StorageBackend};
+
+/// How long after one of our own writes filesystem events for the same
+/// environment are attributed to that write and ignored by the watcher.
+const SELF_WRITE_GRACE: Duration = Duration::from_secs(2);
 
 /// Global application state shared across all Tauri commands.
 ///
 /// This structure maintains the current session state including:
 /// - The currently selected environment
-/// - The data directory path
+/// - The data directory path and the storage backend serving it
+/// - The encryption key, if a passphrase has been set this session
+/// - The user's settings and where they are persisted
+/// - The recently viewed services and where they are persisted
 /// - In-memory caches for services and relationships
+/// - The active workspace, and the caches of workspaces switched away from
+/// - Call counts and durations of instrumented commands
+/// - The cancellation flags of long-running commands in progress
 ///
 /// # Thread Safety
 ///
-/// This state is wrapped in a `Mutex` when used with Tauri, ensuring
-/// thread-safe access from concurrent command invocations.
+/// This state is wrapped in an `RwLock` when used with Tauri (see
+/// `read_state()` / `write_state()`), so concurrent read-only commands don't
+/// block each other and writes are serialized.
 ///
 /// # Caching Strategy
 ///
 /// Both services and relationships are cached per-environment to minimize
 /// disk I/O. Caches are invalidated when:
-/// - Data is modified (write operations invalidate affected caches)
+/// - Data is modified (write operations update or invalidate affected caches)
 /// - The data path changes (all caches cleared)
+/// - A workspace's path changes while it is switched away from (its caches dropped)
+/// - The data directory watcher sees an external change (affected environment cleared)
+/// - A cache hit finds the environment's modification time has moved on (reloaded)
 /// - Explicitly cleared via `clear_cache()` methods
+///
+/// Reads go through the `cache` module, which uses `cache_generation` to
+/// avoid installing data loaded concurrently with a write.
 #[derive(Debug)]
 pub struct AppState {
     /// The currently active environment name (e.g., "dev", "staging", "prod").
     pub current_environment: String,
-    /// Root directory path where environment data is stored.
+    /// Root directory path where environment data is stored, or the path
+    /// of a SQLite database file.
     pub data_path: PathBuf,
+    /// Storage backend for `data_path`. Always replace it together with
+    /// `data_path` via `set_data_path()`.
+    pub storage: Arc<dyn StorageBackend>,
+    /// Key derived from the encryption passphrase. Never persisted; it is
+    /// cleared when the data path changes since keys are per data directory.
+    pub encryption_key: Option<EncryptionKey>,
     /// Services cache: environment name → (service ID → Service).
-    /// Nested HashMap allows O(1) lookup of individual services.
-    pub services_cache: HashMap<String, HashMap<String, Service>>,
+    /// Nested HashMap allows O(1) lookup of individual services. Services
+    /// are shared via `Arc` so reads don't have to deep-clone them.
+    pub services_cache: HashMap<String, HashMap<String, Arc<Service>>>,
     /// Relationships cache: environment name → list of relationships.
-    /// All relationships for an environment are cached together.
-    pub relationships_cache: HashMap<String, Vec<Relationship>>,
+    /// All relationships for an environment are cached (and shared) together.
+    pub relationships_cache: HashMap<String, Arc<[Relationship]>>,
+    /// Per-service index of each `relationships_cache` entry. Always
+    /// installed and removed together with the entry it indexes.
+    pub relationship_indexes: HashMap<String, Arc<RelationshipIndex>>,
+    /// Prefix index of each `services_cache` entry, for `quick_open`. Always
+    /// installed and removed together with the entry it indexes.
+    pub quick_open_indexes: HashMap<String, QuickOpenIndex>,
+    /// When each `services_cache` entry was loaded, for freshness checks.
+    pub services_stamps: HashMap<String, CacheStamp>,
+    /// When each `relationships_cache` entry was loaded, for freshness checks.
+    pub relationships_stamps: HashMap<String, CacheStamp>,
+    /// Incremented on every write and cache invalidation.
+    pub cache_generation: u64,
+    /// User settings, loaded at startup.
+    pub settings: Settings,
+    /// Where `settings` are persisted. `None` keeps settings in memory only.
+    pub settings_path: Option<PathBuf>,
+    /// Recently viewed services per environment, behind a lock of their own
+    /// so reads can record views without the write lock.
+    pub recent_services: Arc<RecentServices>,
+    /// Undo/redo journals per environment. In memory only.
+    pub journals: HashMap<String, Journal>,
+    /// Last time the application itself wrote to each environment.
+    /// Used by the data directory watcher to skip self-inflicted changes.
+    pub recent_writes: HashMap<String, Instant>,
+    /// The name of the active workspace, or `None` when the data path isn't one.
+    pub workspace: Option<String>,
+    /// Inactive workspaces by name, kept so switching back to them is instant.
+    pub parked_workspaces: HashMap<String, ParkedWorkspace>,
+    /// Call statistics of instrumented commands. In memory only.
+    pub command_timings: Arc<CommandTimings>,
+    /// Cancellation flags of the long-running commands in progress.
+    pub running_operations: Arc<RunningOperations>,
+}
+
+/// Everything that belongs to one workspace's data path.
+///
+/// The active workspace lives in the `AppState` fields of the same names;
+/// `switch_workspace()` swaps it with a parked one. Keeping the caches of
+/// each workspace apart means environments with the same name in two
+/// workspaces never see each other's data.
+#[derive(Debug)]
+pub struct ParkedWorkspace {
+    data_path: PathBuf,
+    storage: Arc<dyn StorageBackend>,
+    encryption_key: Option<EncryptionKey>,
+    current_environment: String,
+    services_cache: HashMap<String, HashMap<String, Arc<Service>>>,
+    relationships_cache: HashMap<String, Arc<[Relationship]>>,
+    relationship_indexes: HashMap<String, Arc<RelationshipIndex>>,
+    quick_open_indexes: HashMap<String, QuickOpenIndex>,
+    services_stamps: HashMap<String, CacheStamp>,
+    relationships_stamps: HashMap<String, CacheStamp>,
+    journals: HashMap<String, Journal>,
+    recent_writes: HashMap<String, Instant>,
+}
+
+impl ParkedWorkspace {
+    /// Opens a workspace that has nothing cached yet.
+    fn open(data_path: PathBuf) -> Self {
+        Self {
+            storage: storage::open_backend(&data_path, None),
+            data_path,
+            encryption_key: None,
+            current_environment: "dev".to_string(),
+            services_cache: HashMap::new(),
+            relationships_cache: HashMap::new(),
+            relationship_indexes: HashMap::new(),
+            quick_open_indexes: HashMap::new(),
+            services_stamps: HashMap::new(),
+            relationships_stamps: HashMap::new(),
+            journals: HashMap::new(),
+            recent_writes: HashMap::new(),
+        }
+    }
 }
 
 impl AppState {
@@ -47,6 +157,7 @@ impl AppState {
     /// Initializes with:
     /// - Default environment: "dev"
     /// - Empty caches for services and relationships
+    /// - Default settings, not persisted (see `load_settings()`)
     ///
     /// # Arguments
     ///
@@ -58,17 +169,160 @@ impl AppState {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// let state = AppState::new(PathBuf::from("/path/to/data"));
     /// assert_eq!(state.current_environment, "dev");
     /// ```
     pub fn new(data_path: PathBuf) -> Self {
         Self {
             current_environment: "dev".to_string(),
+            storage: storage::open_backend(&data_path, None),
+            encryption_key: None,
             data_path,
             services_cache: HashMap::new(),
             relationships_cache: HashMap::new(),
+            relationship_indexes: HashMap::new(),
+            quick_open_indexes: HashMap::new(),
+            services_stamps: HashMap::new(),
+            relationships_stamps: HashMap::new(),
+            cache_generation: 0,
+            settings: Settings::default(),
+            settings_path: None,
+            recent_services: Arc::default(),
+            journals: HashMap::new(),
+            recent_writes: HashMap::new(),
+            workspace: None,
+            parked_workspaces: HashMap::new(),
+            command_timings: Arc::default(),
+            running_operations: Arc::default(),
+        }
+    }
+
+    /// Loads settings from a settings file and persists future changes there.
+    ///
+    /// A missing file yields default settings. An unreadable file also falls
+    /// back to defaults (with a logged warning) so a corrupt settings file
+    /// never prevents the application from starting.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings_path` - Path of the settings file
+    pub fn load_settings(&mut self, settings_path: PathBuf) {
+        self.settings = storage::load_settings(&settings_path).unwrap_or_else(|e| {
+            eprintln!("Warning: using default settings: {}", e);
+            Settings::default()
+        });
+        self.settings_path = Some(settings_path);
+    }
+
+    /// Loads the recently viewed services and persists future views there
+    /// (see `RecentServices::load`).
+    ///
+    /// # Arguments
+    ///
+    /// * `recent_services_path` - Path of the recently viewed services file
+    pub fn load_recent_services(&mut self, recent_services_path: PathBuf) {
+        self.recent_services = RecentServices::load(recent_services_path);
+    }
+
+    /// Points the application at a new data path.
+    ///
+    /// Opens the matching storage backend (directory or SQLite file) and
+    /// clears all caches and undo journals, since they belong to the previous
+    /// location. The encryption key is dropped as well; the new location
+    /// needs its own passphrase.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path` - The new data directory or database file
+    pub fn set_data_path(&mut self, data_path: PathBuf) {
+        self.clear_cache();
+        self.journals.clear();
+        self.encryption_key = None;
+        self.storage = storage::open_backend(&data_path, None);
+        self.data_path = data_path;
+    }
+
+    /// Makes a workspace the active one.
+    ///
+    /// The data path, storage backend, encryption key, current environment,
+    /// caches and undo journals of the active workspace are parked under its
+    /// name and those parked for `name` are restored, so switching back is
+    /// instant. A workspace that was never opened, or whose path changed
+    /// since it was parked, starts with empty caches. A data path that isn't
+    /// a workspace is not parked.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The workspace to activate
+    /// * `data_path` - The workspace's data directory or database file
+    ///
+    /// # Side Effects
+    ///
+    /// - Increments `cache_generation`, so loads started in the previous
+    ///   workspace are not installed in this one
+    pub fn switch_workspace(&mut self, name: &str, data_path: PathBuf) {
+        if self.workspace.as_deref() == Some(name) && self.data_path == data_path {
+            return;
         }
+
+        let incoming = match self.parked_workspaces.remove(name) {
+            Some(parked) if parked.data_path == data_path => parked,
+            _ => ParkedWorkspace::open(data_path),
+        };
+        let outgoing = self.swap_workspace(incoming);
+        if let Some(previous) = self.workspace.replace(name.to_string()) {
+            if previous != name {
+                self.parked_workspaces.insert(previous, outgoing);
+            }
+        }
+        self.cache_generation += 1;
+    }
+
+    /// Replaces the active workspace's data with `workspace`, returning the old data.
+    fn swap_workspace(&mut self, workspace: ParkedWorkspace) -> ParkedWorkspace {
+        use std::mem::replace;
+
+        ParkedWorkspace {
+            data_path: replace(&mut self.data_path, workspace.data_path),
+            storage: replace(&mut self.storage, workspace.storage),
+            encryption_key: replace(&mut self.encryption_key, workspace.encryption_key),
+            current_environment: replace(
+                &mut self.current_environment,
+                workspace.current_environment,
+            ),
+            services_cache: replace(&mut self.services_cache, workspace.services_cache),
+            relationships_cache: replace(
+                &mut self.relationships_cache,
+                workspace.relationships_cache,
+            ),
+            relationship_indexes: replace(
+                &mut self.relationship_indexes,
+                workspace.relationship_indexes,
+            ),
+            quick_open_indexes: replace(&mut self.quick_open_indexes, workspace.quick_open_indexes),
+            services_stamps: replace(&mut self.services_stamps, workspace.services_stamps),
+            relationships_stamps: replace(
+                &mut self.relationships_stamps,
+                workspace.relationships_stamps,
+            ),
+            journals: replace(&mut self.journals, workspace.journals),
+            recent_writes: replace(&mut self.recent_writes, workspace.recent_writes),
+        }
+    }
+
+    /// Sets (or clears) the encryption key and reopens the storage backend with it.
+    ///
+    /// Caches are cleared so data that previously failed to load (or was
+    /// loaded with another key) is read again.
+    ///
+    /// # Arguments
+    ///
+    /// * `encryption_key` - The key derived from the passphrase, or `None` to stop encrypting
+    pub fn set_encryption_key(&mut self, encryption_key: Option<EncryptionKey>) {
+        self.clear_cache();
+        self.storage = storage::open_backend(&self.data_path, encryption_key.clone());
+        self.encryption_key = encryption_key;
     }
 
     /// Clears all cached data for all environments.
@@ -78,11 +332,18 @@ impl AppState {
     ///
     /// # Side Effects
     ///
-    /// - Removes all entries from `services_cache`
-    /// - Removes all entries from `relationships_cache`
+    /// - Removes all entries from `services_cache` and their indexes
+    /// - Removes all entries from `relationships_cache` and their indexes
+    /// - Removes all cache stamps
+    /// - Increments `cache_generation`
     pub fn clear_cache(&mut self) {
         self.services_cache.clear();
+        self.quick_open_indexes.clear();
         self.relationships_cache.clear();
+        self.relationship_indexes.clear();
+        self.services_stamps.clear();
+        self.relationships_stamps.clear();
+        self.cache_generation += 1;
     }
 
     /// Clears cached data for a specific environment.
@@ -96,10 +357,195 @@ impl AppState {
     ///
     /// # Side Effects
     ///
-    /// - Removes the environment's entry from `services_cache`
-    /// - Removes the environment's entry from `relationships_cache`
+    /// - Removes the environment's entry from `services_cache` and its index
+    /// - Removes the environment's entry from `relationships_cache` and its index
+    /// - Removes the environment's cache stamps
+    /// - Increments `cache_generation`
     pub fn clear_environment_cache(&mut self, environment: &str) {
         self.services_cache.remove(environment);
+        self.quick_open_indexes.remove(environment);
         self.relationships_cache.remove(environment);
+        self.relationship_indexes.remove(environment);
+        self.services_stamps.remove(environment);
+        self.relationships_stamps.remove(environment);
+        self.cache_generation += 1;
+    }
+
+    /// Records that the application just wrote to an environment's files.
+    ///
+    /// Call this after every successful write, while still holding the write
+    /// lock, so the data directory watcher doesn't invalidate caches that the
+    /// write already updated and concurrent cache loads started before the
+    /// write are discarded.
+    ///
+    /// The environment's cache stamps are moved to the new modification time,
+    /// so the cache entries the write keeps up to date aren't reloaded just
+    /// because of the write itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The name of the environment that was written to
+    pub fn record_write(&mut self, environment: &str) {
+        self.recent_writes
+            .insert(environment.to_string(), Instant::now());
+        self.cache_generation += 1;
+
+        let modified = self.storage.last_modified(environment);
+        for stamps in [&mut self.services_stamps, &mut self.relationships_stamps] {
+            if let Some(stamp) = stamps.get_mut(environment) {
+                stamp.modified = modified;
+            }
+        }
+    }
+
+    /// Patches an environment's cached services after one was saved or deleted.
+    ///
+    /// Only a cached environment is patched, along with its quick-open
+    /// index; a partial entry would hide the environment's other services.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The name of the environment that was written to
+    /// * `service_id` - The ID of the service
+    /// * `service` - The service as saved, or `None` if it was deleted
+    pub fn update_cached_service(
+        &mut self,
+        environment: &str,
+        service_id: &str,
+        service: Option<&Service>,
+    ) {
+        let Some(services_map) = self.services_cache.get_mut(environment) else {
+            return;
+        };
+        match service {
+            Some(service) => {
+                services_map.insert(service_id.to_string(), Arc::new(service.clone()));
+            }
+            None => {
+                services_map.remove(service_id);
+            }
+        }
+        if let Some(index) = self.quick_open_indexes.get_mut(environment) {
+            index.update(service_id, service);
+        }
+    }
+
+    /// Replaces an environment's cached relationships with a vector just written to storage.
+    ///
+    /// Call this after `record_write()`, so the entry is stamped with the
+    /// modification time the write produced and isn't reloaded. The vector
+    /// is cached in the order storage keeps it (see `storage::relationship_order`),
+    /// so the index matches what the next command loads.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The name of the environment that was written to
+    /// * `relationships` - The complete relationships vector that was saved
+    pub fn cache_relationships(&mut self, environment: &str, mut relationships: Vec<Relationship>) {
+        relationships.sort_by(storage::relationship_order);
+        let index = RelationshipIndex::build(&relationships);
+        self.cache_indexed_relationships(environment, relationships, index);
+    }
+
+    /// Like `cache_relationships`, with an index of `relationships` the caller already has.
+    ///
+    /// Commands that change a single relationship patch the previous index
+    /// (see `RelationshipIndex::after_change`) rather than have it rebuilt.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The name of the environment that was written to
+    /// * `relationships` - The complete relationships vector that was saved,
+    ///   in the order storage keeps it (see `storage::relationship_order`)
+    /// * `index` - The per-service index of `relationships`
+    pub fn cache_indexed_relationships(
+        &mut self,
+        environment: &str,
+        relationships: Vec<Relationship>,
+        index: RelationshipIndex,
+    ) {
+        let stamp = CacheStamp::new(self.storage.last_modified(environment));
+        self.relationships_cache
+            .insert(environment.to_string(), relationships.into());
+        self.relationship_indexes
+            .insert(environment.to_string(), Arc::new(index));
+        self.relationships_stamps
+            .insert(environment.to_string(), stamp);
+    }
+
+    /// Records a completed mutation in the environment's undo journal.
+    ///
+    /// Clears the environment's redo stack.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The name of the environment that was changed
+    /// * `entry` - The changes the mutation made
+    pub fn record_undo(&mut self, environment: &str, entry: JournalEntry) {
+        self.journals
+            .entry(environment.to_string())
+            .or_default()
+            .record(entry);
+    }
+
+    /// Appends records of a completed mutation to the environment's audit log.
+    ///
+    /// Never fails: the mutation has already happened, so a log that can't
+    /// be written is reported with a warning and otherwise ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The name of the environment that was changed
+    /// * `records` - One record per change, in the order the changes were made
+    pub fn record_audit(&self, environment: &str, records: &[AuditRecord]) {
+        if let Err(e) = audit::append_records(&self.data_path, environment, records) {
+            eprintln!(
+                "Warning: failed to write audit log for environment {}: {}",
+                environment, e
+            );
+        }
+    }
+
+    /// Records in the environment's metadata that an import source just synced it.
+    ///
+    /// Never fails, like `record_audit`: the import has already been written,
+    /// so a timestamp that can't be stored is reported with a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The name of the environment that was imported into
+    /// * `source` - The import source, e.g. `"kubernetes"`
+    /// * `counts` - How many services and relationships the source held
+    pub fn record_import(&mut self, environment: &str, source: &str, counts: ImportCounts) {
+        let imported_at = audit::now_millis();
+        match storage::environment_metadata::record_import(
+            &self.data_path,
+            environment,
+            source,
+            imported_at,
+            counts,
+        ) {
+            Ok(()) => self.record_write(environment),
+            Err(e) => eprintln!(
+                "Warning: failed to record {} import for environment {}: {}",
+                source, environment, e
+            ),
+        }
+    }
+
+    /// Checks whether the application wrote to an environment recently.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The name of the environment to check
+    ///
+    /// # Returns
+    ///
+    /// `true` if a write was recorded within the self-write grace period.
+    pub fn is_recent_write(&self, environment: &str) -> bool {
+        self.recent_writes
+            .get(environment)
+            .map(|written_at| written_at.elapsed() < SELF_WRITE_GRACE)
+            .unwrap_or(false)
     }
 }
@@ -0,0 +1,549 @@
+//! Cached reads of services and relationships.
+//!
+//! Commands read through these functions rather than touching the caches
+//! directly. A warm cache is served under the read lock. On a miss, data is
+//! loaded from the storage backend without holding any lock and the write
+//! lock is only taken briefly to install the result.
+//!
+//! Cached data is shared via `Arc`. Internal traversals (the graph command,
+//! counts) use the `shared_*` functions and borrow; owned copies are only
+//! made where a command has to return them to the frontend. Cached
+//! relationships come with a per-service index (see `relationship_index`),
+//! so a single service's relationships are found without a full scan, and
+//! cached services with a prefix index for `quick_open` (see `quick_open`).
+//!
+//! # Freshness
+//!
+//! Each cached entry is stamped with the storage backend's modification time
+//! for the environment (see `StorageBackend::last_modified`) as of the load.
+//! Every cache hit re-checks that time, which costs a couple of `stat` calls,
+//! and reloads when it has moved on. This catches edits made by scripts, git
+//! or another window even when the data directory watcher misses them.
+//!
+//! # Races
+//!
+//! Every write and invalidation bumps `AppState::cache_generation`. A load
+//! is only installed if the generation is unchanged since the miss, so a
+//! load that raced with a write never overwrites newer data with older data
+//! read from disk before the write.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use crate::error::AppError;
+use crate::models::{Relationship, Service, ServiceSummary};
+use crate::state::quick_open::{MatchRank, QuickOpenIndex};
+use crate::state::relationship_index::RelationshipIndex;
+use crate::state::{read_state, write_state, AppState};
+
+/// When a cache entry was loaded and what the data looked like at the time.
+///
+/// # Fields
+///
+/// * `loaded_at` - When the entry was loaded
+/// * `modified` - The environment's modification time at load; `None` if unknown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStamp {
+    pub loaded_at: SystemTime,
+    pub modified: Option<SystemTime>,
+}
+
+impl CacheStamp {
+    /// Creates a stamp for an entry loaded now.
+    pub fn new(modified: Option<SystemTime>) -> Self {
+        Self {
+            loaded_at: SystemTime::now(),
+            modified,
+        }
+    }
+}
+
+/// Checks whether a cache entry still matches the data in storage.
+///
+/// Entries without a stamp are never considered fresh.
+fn is_fresh(state: &AppState, stamp: Option<&CacheStamp>, environment: &str) -> bool {
+    stamp.is_some_and(|stamp| stamp.modified == state.storage.last_modified(environment))
+}
+
+/// Returns all services of an environment, loading and caching them on a miss.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to read
+///
+/// # Returns
+///
+/// * `Ok(Vec<Service>)` - All services in the environment (in no particular order)
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn services(state: &RwLock<AppState>, environment: &str) -> Result<Vec<Service>, AppError> {
+    map_services(state, environment, Service::clone)
+}
+
+/// Returns shared handles to all services of an environment, loading and caching them on a miss.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to read
+///
+/// # Returns
+///
+/// * `Ok(Vec<Arc<Service>>)` - All services in the environment (in no particular order)
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn shared_services(
+    state: &RwLock<AppState>,
+    environment: &str,
+) -> Result<Vec<Arc<Service>>, AppError> {
+    map_shared_services(state, environment, Arc::clone)
+}
+
+/// Maps every service of an environment, loading and caching them on a miss.
+///
+/// On a warm cache `f` runs under the read lock against the cached services,
+/// so callers that only need part of each service (summaries, counts) avoid
+/// cloning whole services.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to read
+/// * `f` - Builds the result for one service
+///
+/// # Returns
+///
+/// * `Ok(Vec<T>)` - One result per service (in no particular order)
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn map_services<T>(
+    state: &RwLock<AppState>,
+    environment: &str,
+    f: impl Fn(&Service) -> T,
+) -> Result<Vec<T>, AppError> {
+    map_shared_services(state, environment, |service| f(service))
+}
+
+/// Maps every cached service handle of an environment, loading and caching on a miss.
+fn map_shared_services<T>(
+    state: &RwLock<AppState>,
+    environment: &str,
+    f: impl Fn(&Arc<Service>) -> T,
+) -> Result<Vec<T>, AppError> {
+    let (storage, generation) = {
+        let state = read_state(state);
+        if let Some(services_map) = state.services_cache.get(environment) {
+            if is_fresh(&state, state.services_stamps.get(environment), environment) {
+                return Ok(services_map.values().map(&f).collect());
+            }
+        }
+        (Arc::clone(&state.storage), state.cache_generation)
+    };
+
+    // Stamp before loading, so an edit made during the load triggers another reload
+    let stamp = CacheStamp::new(storage.last_modified(environment));
+    let services: Vec<Arc<Service>> = storage
+        .load_services(environment)?
+        .into_iter()
+        .map(Arc::new)
+        .collect();
+    let results = services.iter().map(&f).collect();
+
+    let mut state = write_state(state);
+    if state.cache_generation == generation {
+        let index = QuickOpenIndex::build(services.iter().map(|s| s.as_ref()));
+        let services_map: HashMap<String, Arc<Service>> =
+            services.into_iter().map(|s| (s.id.clone(), s)).collect();
+        state
+            .services_cache
+            .insert(environment.to_string(), services_map);
+        state
+            .quick_open_indexes
+            .insert(environment.to_string(), index);
+        state.services_stamps.insert(environment.to_string(), stamp);
+    }
+
+    Ok(results)
+}
+
+/// Returns the services matching a quick-open prefix, best match first,
+/// loading and caching the environment on a miss.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to search
+/// * `prefix` - The prefix of an ID, name or word of them, compared ignoring case
+/// * `limit` - The most services to return
+///
+/// # Returns
+///
+/// * `Ok(Vec<(ServiceSummary, MatchRank)>)` - The matching services and how they match
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn quick_open(
+    state: &RwLock<AppState>,
+    environment: &str,
+    prefix: &str,
+    limit: usize,
+) -> Result<Vec<(ServiceSummary, MatchRank)>, AppError> {
+    let cached = |state: &AppState| {
+        let services_map = state.services_cache.get(environment)?;
+        let index = state.quick_open_indexes.get(environment)?;
+        is_fresh(state, state.services_stamps.get(environment), environment)
+            .then(|| search_index(services_map, index, prefix, limit))
+    };
+    if let Some(matches) = cached(&read_state(state)) {
+        return Ok(matches);
+    }
+
+    let services = shared_services(state, environment)?;
+    if let Some(matches) = cached(&read_state(state)) {
+        return Ok(matches);
+    }
+    // A write raced the load, so nothing was cached; index the loaded services once
+    let index = QuickOpenIndex::build(services.iter().map(|s| s.as_ref()));
+    let services_map: HashMap<String, Arc<Service>> =
+        services.into_iter().map(|s| (s.id.clone(), s)).collect();
+    Ok(search_index(&services_map, &index, prefix, limit))
+}
+
+/// Looks a prefix up in the index of a services map, for `quick_open`.
+fn search_index(
+    services_map: &HashMap<String, Arc<Service>>,
+    index: &QuickOpenIndex,
+    prefix: &str,
+    limit: usize,
+) -> Vec<(ServiceSummary, MatchRank)> {
+    index
+        .search(prefix, limit)
+        .into_iter()
+        .filter_map(|(id, rank)| Some((ServiceSummary::from(services_map.get(id)?.as_ref()), rank)))
+        .collect()
+}
+
+/// Returns a single service, preferring the cache.
+///
+/// A service that isn't cached (or whose cached environment is stale) is
+/// loaded on its own and *not* cached, since the services cache always holds
+/// complete environments.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment containing the service
+/// * `service_id` - The ID of the service
+///
+/// # Returns
+///
+/// * `Ok(Service)` - The service
+/// * `Err(AppError::ServiceNotFound)` - If the service doesn't exist
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn service(
+    state: &RwLock<AppState>,
+    environment: &str,
+    service_id: &str,
+) -> Result<Service, AppError> {
+    let storage = {
+        let state = read_state(state);
+        if let Some(services_map) = state.services_cache.get(environment) {
+            if is_fresh(&state, state.services_stamps.get(environment), environment) {
+                return services_map
+                    .get(service_id)
+                    .map(|service| service.as_ref().clone())
+                    .ok_or_else(|| AppError::ServiceNotFound(service_id.to_string()));
+            }
+        }
+        Arc::clone(&state.storage)
+    };
+
+    storage.load_service(environment, service_id)
+}
+
+/// Checks whether a service exists, preferring the cache.
+///
+/// When the environment isn't cached (or is stale) the storage backend is
+/// asked directly, which doesn't parse the service.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to look in
+/// * `service_id` - The ID of the service
+///
+/// # Returns
+///
+/// * `Ok(bool)` - Whether the service exists
+/// * `Err(AppError)` - If the storage backend check fails
+pub fn service_exists(
+    state: &RwLock<AppState>,
+    environment: &str,
+    service_id: &str,
+) -> Result<bool, AppError> {
+    let storage = {
+        let state = read_state(state);
+        if let Some(services_map) = state.services_cache.get(environment) {
+            if is_fresh(&state, state.services_stamps.get(environment), environment) {
+                return Ok(services_map.contains_key(service_id));
+            }
+        }
+        Arc::clone(&state.storage)
+    };
+
+    storage.service_exists(environment, service_id)
+}
+
+/// Returns all relationships of an environment, loading and caching them on a miss.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to read
+///
+/// # Returns
+///
+/// * `Ok(Vec<Relationship>)` - All relationships in stored order
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn relationships(
+    state: &RwLock<AppState>,
+    environment: &str,
+) -> Result<Vec<Relationship>, AppError> {
+    with_relationships(state, environment, <[Relationship]>::to_vec)
+}
+
+/// Returns the shared relationships of an environment, loading and caching them on a miss.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to read
+///
+/// # Returns
+///
+/// * `Ok(Arc<[Relationship]>)` - All relationships in stored order
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn shared_relationships(
+    state: &RwLock<AppState>,
+    environment: &str,
+) -> Result<Arc<[Relationship]>, AppError> {
+    with_shared_relationships(state, environment, Arc::clone)
+}
+
+/// Runs `f` against all relationships of an environment, loading and caching them on a miss.
+///
+/// On a warm cache `f` runs under the read lock, so callers that only need
+/// a count or a few relationships avoid cloning the whole vector.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to read
+/// * `f` - Computes the result from the relationships (in stored order)
+///
+/// # Returns
+///
+/// * `Ok(T)` - The result of `f`
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn with_relationships<T>(
+    state: &RwLock<AppState>,
+    environment: &str,
+    f: impl FnOnce(&[Relationship]) -> T,
+) -> Result<T, AppError> {
+    with_shared_relationships(state, environment, |relationships| f(relationships))
+}
+
+/// Returns the shared relationships of an environment with their per-service
+/// index, loading and caching them on a miss.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to read
+///
+/// # Returns
+///
+/// * `Ok((Arc<[Relationship]>, Arc<RelationshipIndex>))` - All relationships in
+///   stored order and the index of their positions by service
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn shared_indexed_relationships(
+    state: &RwLock<AppState>,
+    environment: &str,
+) -> Result<(Arc<[Relationship]>, Arc<RelationshipIndex>), AppError> {
+    with_indexed(state, environment, |relationships, index| {
+        (Arc::clone(relationships), Arc::clone(index))
+    })
+}
+
+/// Returns the relationships a service is the source or target of, loading
+/// and caching the environment's relationships on a miss.
+///
+/// Looks the service up in the per-service index instead of scanning every
+/// relationship, and only clones the relationships it returns.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+/// * `environment` - The environment to read
+/// * `service_id` - The ID of the service
+///
+/// # Returns
+///
+/// * `Ok(Vec<Relationship>)` - The service's relationships in stored order
+/// * `Err(AppError)` - If loading from the storage backend fails
+pub fn relationships_for_service(
+    state: &RwLock<AppState>,
+    environment: &str,
+    service_id: &str,
+) -> Result<Vec<Relationship>, AppError> {
+    with_indexed(state, environment, |relationships, index| {
+        index
+            .relationships_of(relationships, service_id)
+            .cloned()
+            .collect()
+    })
+}
+
+/// Returns the cached per-service index of an environment's relationships,
+/// if it is cached and still matches the data in storage.
+///
+/// For commands that already hold the state lock, e.g. to patch the index
+/// after changing a single relationship.
+///
+/// # Arguments
+///
+/// * `state` - The locked application state
+/// * `environment` - The environment to look up
+///
+/// # Returns
+///
+/// The index, or `None` if it isn't cached or is stale
+pub fn fresh_relationship_index(
+    state: &AppState,
+    environment: &str,
+) -> Option<Arc<RelationshipIndex>> {
+    let index = state.relationship_indexes.get(environment)?;
+    let stamp = state.relationships_stamps.get(environment);
+    is_fresh(state, stamp, environment).then(|| Arc::clone(index))
+}
+
+/// Runs `f` against the cached relationships handle, loading and caching on a miss.
+fn with_shared_relationships<T>(
+    state: &RwLock<AppState>,
+    environment: &str,
+    f: impl FnOnce(&Arc<[Relationship]>) -> T,
+) -> Result<T, AppError> {
+    with_indexed(state, environment, |relationships, _| f(relationships))
+}
+
+/// Runs `f` against the cached relationships and their index, loading and caching on a miss.
+fn with_indexed<T>(
+    state: &RwLock<AppState>,
+    environment: &str,
+    f: impl FnOnce(&Arc<[Relationship]>, &Arc<RelationshipIndex>) -> T,
+) -> Result<T, AppError> {
+    let (storage, generation) = {
+        let state = read_state(state);
+        let cached = state
+            .relationships_cache
+            .get(environment)
+            .zip(state.relationship_indexes.get(environment));
+        if let Some((relationships, index)) = cached {
+            if is_fresh(
+                &state,
+                state.relationships_stamps.get(environment),
+                environment,
+            ) {
+                return Ok(f(relationships, index));
+            }
+        }
+        (Arc::clone(&state.storage), state.cache_generation)
+    };
+
+    // Stamp before loading, so an edit made during the load triggers another reload
+    let stamp = CacheStamp::new(storage.last_modified(environment));
+    let relationships: Arc<[Relationship]> = storage.load_relationships(environment)?.into();
+    let index = Arc::new(RelationshipIndex::build(&relationships));
+    let result = f(&relationships, &index);
+
+    let mut state = write_state(state);
+    if state.cache_generation == generation {
+        state
+            .relationships_cache
+            .insert(environment.to_string(), relationships);
+        state
+            .relationship_indexes
+            .insert(environment.to_string(), index);
+        state
+            .relationships_stamps
+            .insert(environment.to_string(), stamp);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::write_state;
+    use crate::test_support::{service, TestApp};
+    use std::collections::HashMap;
+
+    #[test]
+    fn shared_reads_of_5k_services_copy_no_services() {
+        let generated: Vec<Service> = (0..5000)
+            .map(|i| {
+                let mut service = service(&format!("service-{}", i));
+                service.description = Some(format!("Service number {} of the benchmark", i));
+                service.tags = vec!["benchmark".to_string(), format!("shard-{}", i % 10)];
+                for key in 0..10 {
+                    service.metadata.insert(
+                        format!("key-{}", key),
+                        serde_json::json!({ "value": format!("{}-{}", i, key) }),
+                    );
+                }
+                service
+            })
+            .collect();
+        let test_app = TestApp::with_environment("bench", &generated, &[]);
+        let state = test_app.state();
+        assert_eq!(shared_services(&state, "bench").unwrap().len(), 5000);
+
+        // The shared handles all point into the one cached copy: each
+        // service is held by the cache and the two reads, and by nothing else
+        let by_id = |services: Vec<Arc<Service>>| -> HashMap<String, Arc<Service>> {
+            services
+                .into_iter()
+                .map(|service| (service.id.clone(), service))
+                .collect()
+        };
+        let first = by_id(shared_services(&state, "bench").unwrap());
+        let second = by_id(shared_services(&state, "bench").unwrap());
+        assert_eq!(first.len(), 5000);
+        for (id, service) in &first {
+            assert!(Arc::ptr_eq(service, &second[id]));
+            assert_eq!(Arc::strong_count(service), 3, "{id}");
+        }
+
+        // Cloning reads hand out copies that share nothing with the cache
+        let cloned = services(&state, "bench").unwrap();
+        assert_eq!(cloned.len(), 5000);
+        assert!(first
+            .values()
+            .all(|service| Arc::strong_count(service) == 3));
+
+        // Replacing one service swaps only its handle; handles already
+        // given out keep the old version
+        let mut edited = (*first["service-7"]).clone();
+        edited.description = Some("Edited".to_string());
+        write_state(&state).update_cached_service("bench", "service-7", Some(&edited));
+        let third = by_id(shared_services(&state, "bench").unwrap());
+        assert_eq!(third["service-7"].description.as_deref(), Some("Edited"));
+        assert_eq!(
+            first["service-7"].description.as_deref(),
+            Some("Service number 7 of the benchmark")
+        );
+        assert!(!Arc::ptr_eq(&first["service-7"], &third["service-7"]));
+        assert!(third
+            .iter()
+            .filter(|(id, _)| id.as_str() != "service-7")
+            .all(|(id, service)| Arc::ptr_eq(service, &first[id])));
+    }
+}
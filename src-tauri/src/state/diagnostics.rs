@@ -0,0 +1,150 @@
+//! Timing of command calls, for diagnosing slowness.
+//!
+//! Instrumented commands start a `CommandTimer` as the first statement of
+//! their body; when it is dropped at the end of the command, the call and
+//! its duration are recorded in `AppState::command_timings`. The timer
+//! holds its own handle to the counters, so recording never takes the state
+//! lock (which the command may still hold in its return expression) and
+//! only briefly locks the counters themselves.
+//!
+//! Timings are kept in memory only and start over on every launch.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::state::{read_state, AppState};
+
+/// Call statistics of one command.
+///
+/// # Fields
+///
+/// * `calls` - How often the command was called
+/// * `last_duration_ms` - How long the last call took, in milliseconds
+/// * `max_duration_ms` - How long the slowest call took, in milliseconds
+/// * `total_duration_ms` - How long all calls took together, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandStats {
+    pub calls: u64,
+    pub last_duration_ms: f64,
+    pub max_duration_ms: f64,
+    pub total_duration_ms: f64,
+}
+
+/// The call statistics of every instrumented command.
+#[derive(Debug, Default)]
+pub struct CommandTimings {
+    stats: Mutex<HashMap<&'static str, CommandStats>>,
+}
+
+impl CommandTimings {
+    /// Records one call of a command.
+    pub fn record(&self, command: &'static str, duration: Duration) {
+        let millis = duration.as_secs_f64() * 1000.0;
+        let mut stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+        let entry = stats.entry(command).or_default();
+        entry.calls += 1;
+        entry.last_duration_ms = millis;
+        entry.max_duration_ms = entry.max_duration_ms.max(millis);
+        entry.total_duration_ms += millis;
+    }
+
+    /// Returns the statistics of every command called so far, by command name.
+    pub fn snapshot(&self) -> BTreeMap<String, CommandStats> {
+        let stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+        stats
+            .iter()
+            .map(|(command, stats)| (command.to_string(), *stats))
+            .collect()
+    }
+
+    /// Forgets all statistics.
+    pub fn reset(&self) {
+        self.stats
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+/// Times a command call from its creation until it is dropped.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[tauri::command]
+/// pub fn get_all_services(state: State<'_, RwLock<AppState>>, environment: String) -> ... {
+///     let _timer = CommandTimer::start(&state, "get_all_services");
+///     // ...
+/// }
+/// ```
+pub struct CommandTimer {
+    timings: Arc<CommandTimings>,
+    command: &'static str,
+    started: Instant,
+}
+
+impl CommandTimer {
+    /// Starts timing a call of `command`.
+    pub fn start(state: &RwLock<AppState>, command: &'static str) -> Self {
+        Self {
+            timings: Arc::clone(&read_state(state).command_timings),
+            command,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Drop for CommandTimer {
+    fn drop(&mut self) {
+        // A panicking command is not a call worth timing
+        if !std::thread::panicking() {
+            self.timings.record(self.command, self.started.elapsed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    #[test]
+    fn recording_counts_calls_and_tracks_durations() {
+        let timings = CommandTimings::default();
+
+        timings.record("get_all_services", Duration::from_millis(30));
+        timings.record("get_all_services", Duration::from_millis(10));
+        timings.record("save_service", Duration::from_millis(5));
+
+        let stats = timings.snapshot();
+        assert_eq!(
+            stats["get_all_services"],
+            CommandStats {
+                calls: 2,
+                last_duration_ms: 10.0,
+                max_duration_ms: 30.0,
+                total_duration_ms: 40.0,
+            }
+        );
+        assert_eq!(stats["save_service"].calls, 1);
+
+        timings.reset();
+        assert!(timings.snapshot().is_empty());
+    }
+
+    #[test]
+    fn a_timer_records_once_when_dropped() {
+        let app = TestApp::new();
+        let state = app.state();
+        let timings = Arc::clone(&read_state(&state).command_timings);
+
+        let timer = CommandTimer::start(&state, "validate_environment");
+        assert!(timings.snapshot().is_empty());
+        drop(timer);
+
+        assert_eq!(timings.snapshot()["validate_environment"].calls, 1);
+    }
+}
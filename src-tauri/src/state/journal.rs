@@ -0,0 +1,399 @@
+//! In-memory undo/redo journal.
+//!
+//! Every mutating command records a `JournalEntry` holding the before and
+//! after state of everything it changed. Undoing an entry writes the before
+//! state back through the storage backend; redoing writes the after state.
+//! An entry may hold many changes (bulk operations), which are always
+//! undone and redone together.
+//!
+//! Journals are kept per environment, bounded to `MAX_UNDO_ENTRIES`, and
+//! are never persisted; they are lost on restart and dropped when the data
+//! path changes.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::AppError;
+use crate::models::{Relationship, Service};
+use crate::storage::audit::AuditRecord;
+use crate::storage::{self, StorageBackend};
+
+/// How many undo entries are kept per environment.
+pub const MAX_UNDO_ENTRIES: usize = 50;
+
+/// One change made by a command.
+///
+/// # Variants
+///
+/// * `Service` - A service was created, updated, or deleted (`None` = absent)
+/// * `Relationship` - A single relationship was created, updated, or deleted;
+///   `index` is its position in the relationships list while it existed
+/// * `Relationships` - The whole relationships list was replaced
+#[derive(Debug, Clone)]
+pub enum Change {
+    Service {
+        id: String,
+        before: Option<Service>,
+        after: Option<Service>,
+    },
+    Relationship {
+        id: String,
+        index: usize,
+        before: Option<Relationship>,
+        after: Option<Relationship>,
+    },
+    Relationships {
+        before: Vec<Relationship>,
+        after: Vec<Relationship>,
+    },
+}
+
+/// A recorded command that can be undone and redone as a unit.
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    /// What the command did, for the UI (e.g., "Deleted relationship api→db (depends_on)").
+    pub description: String,
+    /// When the command ran.
+    pub recorded_at: SystemTime,
+    /// The changes, in the order they were made.
+    pub changes: Vec<Change>,
+}
+
+impl JournalEntry {
+    /// Creates an entry recorded now.
+    pub fn new(description: String, changes: Vec<Change>) -> Self {
+        Self {
+            description,
+            recorded_at: SystemTime::now(),
+            changes,
+        }
+    }
+
+    /// Describes the entry for the frontend.
+    pub fn info(&self) -> JournalEntryInfo {
+        JournalEntryInfo {
+            description: self.description.clone(),
+            recorded_at: self
+                .recorded_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            change_count: self.changes.len(),
+        }
+    }
+}
+
+/// A journal entry as shown in the UI.
+///
+/// # Fields
+///
+/// * `description` - What the command did
+/// * `recorded_at` - When it ran, in milliseconds since the Unix epoch
+/// * `change_count` - How many services/relationships it touched
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntryInfo {
+    pub description: String,
+    pub recorded_at: u64,
+    pub change_count: usize,
+}
+
+/// Which state of a journal entry to restore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Restore the state before the entry.
+    Undo,
+    /// Restore the state after the entry.
+    Redo,
+}
+
+/// The undo and redo stacks of one environment.
+#[derive(Debug, Default)]
+pub struct Journal {
+    undo: VecDeque<JournalEntry>,
+    redo: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Records a new entry, dropping the oldest if the journal is full.
+    ///
+    /// Recording a new mutation invalidates everything that could be redone.
+    pub fn record(&mut self, entry: JournalEntry) {
+        self.redo.clear();
+        self.undo.push_back(entry);
+        if self.undo.len() > MAX_UNDO_ENTRIES {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Takes the next entry to apply in a direction.
+    pub fn take(&mut self, direction: Direction) -> Option<JournalEntry> {
+        match direction {
+            Direction::Undo => self.undo.pop_back(),
+            Direction::Redo => self.redo.pop(),
+        }
+    }
+
+    /// Files an entry after it has been applied in a direction.
+    ///
+    /// An undone entry becomes redoable and vice versa.
+    pub fn applied(&mut self, direction: Direction, entry: JournalEntry) {
+        match direction {
+            Direction::Undo => self.redo.push(entry),
+            Direction::Redo => self.undo.push_back(entry),
+        }
+    }
+
+    /// Puts back an entry that could not be applied.
+    pub fn restore(&mut self, direction: Direction, entry: JournalEntry) {
+        match direction {
+            Direction::Undo => self.undo.push_back(entry),
+            Direction::Redo => self.redo.push(entry),
+        }
+    }
+
+    /// Describes the undo stack, most recent first.
+    pub fn undo_history(&self) -> Vec<JournalEntryInfo> {
+        self.undo.iter().rev().map(JournalEntry::info).collect()
+    }
+
+    /// Describes the redo stack, next redo first.
+    pub fn redo_history(&self) -> Vec<JournalEntryInfo> {
+        self.redo.iter().rev().map(JournalEntry::info).collect()
+    }
+}
+
+/// Writes the before (undo) or after (redo) state of an entry to storage.
+///
+/// Goes through the regular storage backend operations, so files stay in
+/// their normal layout. Changes are applied in reverse order when undoing.
+/// Restored services and relationships are written with a revision above
+/// the stored one, so editors holding the overwritten version get a conflict.
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend to write to
+/// * `data_path` - The data path, used to report the files that were written
+/// * `environment` - The environment the entry belongs to
+/// * `entry` - The entry to apply
+/// * `direction` - Which state to restore
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` - The files that were written or deleted (for git auto-commits)
+/// * `Err(AppError)` - If a storage operation fails; earlier changes stay applied
+pub fn apply(
+    storage: &dyn StorageBackend,
+    data_path: &Path,
+    environment: &str,
+    entry: &JournalEntry,
+    direction: Direction,
+) -> Result<Vec<PathBuf>, AppError> {
+    let changes = ordered(&entry.changes, direction);
+
+    let mut paths = Vec::new();
+    let edits_relationships = changes.iter().any(|change| {
+        matches!(
+            change,
+            Change::Relationship { .. } | Change::Relationships { .. }
+        )
+    });
+    let original = if edits_relationships {
+        storage.load_relationships(environment)?
+    } else {
+        Vec::new()
+    };
+    let mut relationships = edits_relationships.then(|| original.clone());
+
+    for change in changes {
+        match change {
+            Change::Service { id, before, after } => {
+                let target = match direction {
+                    Direction::Undo => before,
+                    Direction::Redo => after,
+                };
+                match target {
+                    Some(service) => {
+                        // Restored data gets a new revision so stale editors still conflict
+                        let current = storage
+                            .load_service(environment, id)
+                            .map_or(0, |stored| stored.revision);
+                        let mut service = service.clone();
+                        service.revision = current.max(service.revision) + 1;
+                        storage.save_service(environment, &service)?;
+                    }
+                    None => match storage.delete_service_file(environment, id) {
+                        Ok(()) | Err(AppError::ServiceNotFound(_)) => {}
+                        Err(e) => return Err(e),
+                    },
+                }
+                paths.extend(storage::service_file_path(data_path, environment, id));
+            }
+            Change::Relationship {
+                id,
+                index,
+                before,
+                after,
+            } => {
+                let target = match direction {
+                    Direction::Undo => before,
+                    Direction::Redo => after,
+                };
+                let Some(list) = relationships.as_mut() else {
+                    continue;
+                };
+
+                let position = list.iter().position(|r| r.id == *id);
+                match (position, target) {
+                    (Some(position), Some(relationship)) => list[position] = relationship.clone(),
+                    (Some(position), None) => {
+                        list.remove(position);
+                    }
+                    (None, Some(relationship)) => {
+                        let index = (*index).min(list.len());
+                        list.insert(index, relationship.clone());
+                    }
+                    (None, None) => {}
+                }
+            }
+            Change::Relationships { before, after } => {
+                let target = match direction {
+                    Direction::Undo => before,
+                    Direction::Redo => after,
+                };
+                relationships = Some(target.clone());
+            }
+        }
+    }
+
+    if let Some(mut relationships) = relationships {
+        bump_revisions(&original, &mut relationships);
+        storage.save_relationships(environment, &relationships)?;
+        paths.extend(storage::relationship_file_paths(
+            data_path,
+            environment,
+            storage::changed_relationship_sources(&original, &relationships),
+        ));
+    }
+
+    Ok(paths)
+}
+
+/// Gives every relationship that differs from its stored version a new revision.
+fn bump_revisions(original: &[Relationship], relationships: &mut [Relationship]) {
+    let original_by_id: HashMap<&str, &Relationship> =
+        original.iter().map(|r| (r.id.as_str(), r)).collect();
+
+    for relationship in relationships {
+        match original_by_id.get(relationship.id.as_str()) {
+            Some(stored) if *stored == relationship => {}
+            Some(stored) => {
+                relationship.revision = stored.revision.max(relationship.revision) + 1;
+            }
+            None => relationship.revision += 1,
+        }
+    }
+}
+
+/// Loads the current version of a service so a change to it can be undone.
+///
+/// # Returns
+///
+/// * `Some(Some(service))` - The service exists
+/// * `Some(None)` - The service doesn't exist
+/// * `None` - The service couldn't be loaded (e.g., a corrupt file), so the
+///   change can't be undone and shouldn't be journaled
+pub fn previous_service(
+    storage: &dyn StorageBackend,
+    environment: &str,
+    service_id: &str,
+) -> Option<Option<Service>> {
+    match storage.load_service(environment, service_id) {
+        Ok(service) => Some(Some(service)),
+        Err(AppError::ServiceNotFound(_)) => Some(None),
+        Err(e) => {
+            eprintln!(
+                "Warning: not journaling change to service {}: {}",
+                service_id, e
+            );
+            None
+        }
+    }
+}
+
+/// Describes changes for the audit log, as they take effect in a direction.
+///
+/// `Direction::Redo` describes the changes as originally made; `Undo`
+/// describes them reverted. A whole-list `Relationships` change is broken
+/// down into one record per relationship that was added, removed or edited.
+///
+/// # Arguments
+///
+/// * `command` - The command that is making the changes
+/// * `changes` - The changes, in the order they were originally made
+/// * `direction` - Which way the changes are being applied
+///
+/// # Returns
+///
+/// One audit record per affected service or relationship, in application order.
+pub fn audit_records(command: &str, changes: &[Change], direction: Direction) -> Vec<AuditRecord> {
+    let mut records = Vec::new();
+
+    for change in ordered(changes, direction) {
+        match change {
+            Change::Service { before, after, .. } => {
+                let (from, to) = match direction {
+                    Direction::Undo => (after, before),
+                    Direction::Redo => (before, after),
+                };
+                records.push(AuditRecord::service(command, from.as_ref(), to.as_ref()));
+            }
+            Change::Relationship { before, after, .. } => {
+                let (from, to) = match direction {
+                    Direction::Undo => (after, before),
+                    Direction::Redo => (before, after),
+                };
+                records.push(AuditRecord::relationship(
+                    command,
+                    from.as_ref(),
+                    to.as_ref(),
+                ));
+            }
+            Change::Relationships { before, after } => {
+                let (from, to) = match direction {
+                    Direction::Undo => (after, before),
+                    Direction::Redo => (before, after),
+                };
+                let from_by_id: HashMap<&str, &Relationship> =
+                    from.iter().map(|r| (r.id.as_str(), r)).collect();
+                let to_ids: HashSet<&str> = to.iter().map(|r| r.id.as_str()).collect();
+
+                for relationship in from.iter().filter(|r| !to_ids.contains(r.id.as_str())) {
+                    records.push(AuditRecord::relationship(command, Some(relationship), None));
+                }
+                for relationship in to {
+                    match from_by_id.get(relationship.id.as_str()) {
+                        Some(previous) if *previous == relationship => {}
+                        previous => records.push(AuditRecord::relationship(
+                            command,
+                            previous.copied(),
+                            Some(relationship),
+                        )),
+                    }
+                }
+            }
+        }
+    }
+
+    records
+}
+
+/// Returns changes in the order they are applied in a direction.
+fn ordered(changes: &[Change], direction: Direction) -> Vec<&Change> {
+    match direction {
+        Direction::Undo => changes.iter().rev().collect(),
+        Direction::Redo => changes.iter().collect(),
+    }
+}
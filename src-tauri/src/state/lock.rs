@@ -0,0 +1,259 @@
+//! Lock helpers for the shared application state.
+//!
+//! The state is managed by Tauri as `RwLock<AppState>`. Read-only commands
+//! take the read lock, so they run concurrently; only writes and cache
+//! population take the write lock.
+//!
+//! # Poisoning
+//!
+//! A command that panics while holding the lock poisons it. The state is
+//! only ever mutated through small, self-contained updates, so the data
+//! behind a poisoned lock is still usable; these helpers recover it instead
+//! of failing every later command. The first helper to see the poisoning
+//! logs a warning, clears the caches (the panicking command may have left
+//! them half-updated; they are reloaded from disk on the next read) and
+//! clears the poison, so the recovery happens once per panic.
+//!
+//! # Blocking Commands
+//!
+//! Synchronous commands run on the IPC thread, so a command busy with the
+//! disk holds up every other invocation. Disk-heavy commands are async and
+//! run their body on the blocking thread pool via `run_blocking()`, taking
+//! the lock only around the parts that need it.
+
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Acquires the application state for reading.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+///
+/// # Returns
+///
+/// A read guard, recovered from poisoning if necessary (see the module docs).
+pub fn read_state(state: &RwLock<AppState>) -> RwLockReadGuard<'_, AppState> {
+    if state.is_poisoned() {
+        drop(write_state(state));
+    }
+    state.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Acquires the application state for writing.
+///
+/// # Arguments
+///
+/// * `state` - The managed application state
+///
+/// # Returns
+///
+/// A write guard, recovered from poisoning if necessary (see the module docs).
+pub fn write_state(state: &RwLock<AppState>) -> RwLockWriteGuard<'_, AppState> {
+    let mut guard = state.write().unwrap_or_else(PoisonError::into_inner);
+    if state.is_poisoned() {
+        eprintln!(
+            "Warning: A command panicked while holding the application state; \
+             recovered the state and cleared the caches"
+        );
+        guard.clear_cache();
+        state.clear_poison();
+    }
+    guard
+}
+
+/// Runs a command body on the blocking thread pool.
+///
+/// # Arguments
+///
+/// * `app` - The application handle, used to reach the state from the pool thread
+/// * `body` - The command body; it takes the lock itself, as briefly as it can
+///
+/// # Returns
+///
+/// * `Ok(T)` - What the body returned
+/// * `Err(AppError)` - The body's error
+/// * `Err(AppError::Io)` - If the body panicked
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[tauri::command]
+/// pub async fn validate_environment(app: AppHandle, environment: String) -> ... {
+///     run_blocking(app, move |state| validation::run_validation(state, &environment)).await
+/// }
+/// ```
+pub async fn run_blocking<T, F, R: Runtime>(app: AppHandle<R>, body: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce(&RwLock<AppState>) -> Result<T, AppError> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(move || body(&app.state::<RwLock<AppState>>()))
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::batch::{execute_batch, Operation};
+    use crate::commands::environments::get_current_environment;
+    use crate::commands::services::{get_all_services, save_service};
+    use crate::state::cache;
+    use crate::test_support::{block_on, service, SlowStorage, TestApp};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc, Barrier};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn readers_hold_the_lock_at_the_same_time() {
+        let test_app = TestApp::with_environment("dev", &[service("api")], &[]);
+        let readers = 4;
+        let all_in = Barrier::new(readers);
+        let (done, finished) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..readers {
+                let (handle, all_in, done) = (test_app.handle(), &all_in, done.clone());
+                scope.spawn(move || {
+                    let state = handle.state::<RwLock<AppState>>();
+                    let _guard = read_state(&state);
+                    // Only passes once every reader holds a read guard
+                    all_in.wait();
+                    done.send(()).unwrap();
+                });
+            }
+            for _ in 0..readers {
+                finished
+                    .recv_timeout(Duration::from_secs(10))
+                    .expect("the readers were serialized");
+            }
+        });
+    }
+
+    #[test]
+    fn parallel_readers_see_a_slow_write_whole() {
+        let test_app = TestApp::with_environment("dev", &[], &[]);
+        SlowStorage::install(&test_app, Duration::ZERO, Duration::from_millis(20));
+        let services = 10;
+        let operations: Vec<Operation> = (0..services)
+            .map(|index| Operation::SaveService {
+                service: service(&format!("service-{index}")),
+                force: false,
+            })
+            .collect();
+        let writing = AtomicBool::new(true);
+        let observed = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                let (handle, writing, observed) = (test_app.handle(), &writing, observed.clone());
+                scope.spawn(move || {
+                    let state = handle.state::<RwLock<AppState>>();
+                    while writing.load(Ordering::SeqCst) {
+                        let count = cache::services(&state, "dev").unwrap().len();
+                        observed.lock().unwrap().push(count);
+                        thread::sleep(Duration::from_millis(5));
+                    }
+                });
+            }
+
+            thread::sleep(Duration::from_millis(20));
+            let result = block_on(execute_batch(
+                test_app.handle(),
+                "dev".to_string(),
+                operations,
+                None,
+                None,
+            ))
+            .unwrap();
+            assert_eq!(result.saved_services.len(), services);
+            thread::sleep(Duration::from_millis(20));
+            writing.store(false, Ordering::SeqCst);
+        });
+
+        let observed = observed.lock().unwrap();
+        assert!(observed.contains(&0), "no read before the write");
+        assert!(observed.contains(&services), "no read after the write");
+        assert!(
+            observed
+                .iter()
+                .all(|count| *count == 0 || *count == services),
+            "a reader saw a partial write: {:?}",
+            observed
+        );
+    }
+
+    #[test]
+    fn commands_recover_from_a_poisoned_lock() {
+        let test_app = TestApp::with_environment("dev", &[service("api")], &[]);
+        assert_eq!(cache::services(&test_app.state(), "dev").unwrap().len(), 1);
+
+        let handle = test_app.handle();
+        let panicked = thread::spawn(move || {
+            let state = handle.state::<RwLock<AppState>>();
+            let mut state = write_state(&state);
+            // Leave the cache half-updated, as a failing write might
+            state
+                .services_cache
+                .entry("dev".to_string())
+                .or_default()
+                .insert("phantom".to_string(), Arc::new(service("phantom")));
+            panic!("command failed while holding the lock");
+        })
+        .join();
+        assert!(panicked.is_err());
+        assert!(test_app.state().is_poisoned());
+
+        let services = block_on(get_all_services(test_app.handle(), "dev".to_string())).unwrap();
+        let ids: Vec<&str> = services.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["api"]);
+        assert!(!test_app.state().is_poisoned());
+        save_service(
+            test_app.handle(),
+            test_app.state(),
+            "dev".to_string(),
+            service("web"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(cache::services(&test_app.state(), "dev").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn fast_reads_complete_while_a_slow_load_is_in_progress() {
+        let test_app = TestApp::with_environment("dev", &[service("api")], &[]);
+        test_app.create_environment("prod", &[service("web")], &[]);
+        cache::services(&test_app.state(), "prod").unwrap();
+        let storage = SlowStorage::install(&test_app, Duration::from_millis(500), Duration::ZERO);
+
+        let handle = test_app.handle();
+        let slow = thread::spawn(move || block_on(get_all_services(handle, "dev".to_string())));
+        let started = Instant::now();
+        while storage.service_loads() == 0 {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "the load never started"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let current = get_current_environment(test_app.state()).unwrap();
+        let warm = block_on(get_all_services(test_app.handle(), "prod".to_string())).unwrap();
+        let fast = started.elapsed();
+
+        assert!(!slow.is_finished(), "the slow load finished first");
+        assert!(
+            fast < Duration::from_millis(400),
+            "fast reads took {:?}",
+            fast
+        );
+        assert_eq!(current, read_state(&test_app.state()).current_environment);
+        assert_eq!(warm.len(), 1);
+        assert_eq!(slow.join().unwrap().unwrap().len(), 1);
+    }
+}
@@ -1,3 +1,12 @@
 mod app_state;
+pub mod cache;
+pub mod diagnostics;
+pub mod journal;
+mod lock;
+pub mod progress;
+pub mod quick_open;
+pub mod recent;
+pub mod relationship_index;
 
 pub use app_state::AppState;
+pub use lock::{read_state, run_blocking, write_state};
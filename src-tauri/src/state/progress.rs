@@ -0,0 +1,355 @@
+//! Progress reporting and cancellation of long-running commands.
+//!
+//! Commands that can take a while (imports, bulk saves, validation of large
+//! environments, exports) accept an optional `operationId` and report their
+//! progress as `operation:progress` events carrying that ID, the current
+//! phase and how many of its items are done. The frontend picks the ID
+//! before invoking the command, so it can match the events to the call and
+//! cancel it with `cancel_operation`.
+//!
+//! # Throttling
+//!
+//! Progress within a phase is emitted at most every `EMIT_INTERVAL`, plus
+//! once when the phase starts and once when its last item is done, so a
+//! batch of thousands of operations doesn't flood the IPC channel. The final
+//! event, emitted by `Progress::finish`, carries the outcome.
+//!
+//! # Cancellation
+//!
+//! Running operations are registered in `AppState::running_operations` with
+//! a cancellation flag (see `list_running_operations`). The registry is also
+//! managed on its own, so operations can be listed and cancelled while the
+//! command running them holds the state lock. A cancelled
+//! operation stops at its next `Progress::check_cancelled`, between two of
+//! its items, and fails with `AppError::OperationCancelled` saying how far it
+//! got. Commands only check before they start writing: changes are computed
+//! in memory first and, once files are being written, the operation runs to
+//! completion, so a cancel never leaves half an import on disk.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::error::AppError;
+use crate::state::{read_state, AppState};
+use crate::storage::audit;
+
+/// Name of the event emitted to the frontend as an operation progresses.
+pub const OPERATION_PROGRESS_EVENT: &str = "operation:progress";
+
+/// Minimum time between two progress events within a phase.
+const EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How an operation ended.
+///
+/// # Variants
+///
+/// * `Succeeded` - The command returned its result
+/// * `Failed` - The command returned an error other than a cancellation
+/// * `Cancelled` - The operation was cancelled with `cancel_operation`
+///
+/// # Serialization
+///
+/// Outcomes are serialized as snake_case strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationOutcome {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// Payload of the `operation:progress` event.
+///
+/// # Fields
+///
+/// * `operation_id` - The ID the operation was started with
+/// * `phase` - What the operation is doing, e.g. "Applying operations"
+/// * `done` - How many items of the phase are done
+/// * `total` - How many items the phase has
+/// * `outcome` - How the operation ended; only set on the final event
+/// * `error` - The error of a failed operation, as the command returned it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationProgress {
+    pub operation_id: String,
+    pub phase: String,
+    pub done: usize,
+    pub total: usize,
+    pub outcome: Option<OperationOutcome>,
+    pub error: Option<serde_json::Value>,
+}
+
+/// A long-running operation in progress.
+///
+/// # Fields
+///
+/// * `operation_id` - The ID the operation was started with
+/// * `command` - The command running it, e.g. "execute_batch"
+/// * `started_at` - When it started, in milliseconds since the Unix epoch
+/// * `cancel_requested` - Whether `cancel_operation` was called for it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningOperation {
+    pub operation_id: String,
+    pub command: String,
+    pub started_at: u64,
+    pub cancel_requested: bool,
+}
+
+/// A registered operation and its cancellation flag.
+#[derive(Debug)]
+struct Registration {
+    command: &'static str,
+    started_at: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// The operations currently running, by ID.
+#[derive(Debug, Default)]
+pub struct RunningOperations {
+    operations: Mutex<HashMap<String, Registration>>,
+    next_id: AtomicU64,
+}
+
+impl RunningOperations {
+    /// Asks a running operation to stop.
+    ///
+    /// Returns false if no operation with that ID is running (e.g. because
+    /// it has already finished).
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        let operations = self.lock();
+        match operations.get(operation_id) {
+            Some(registration) => {
+                registration.cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the running operations, oldest first.
+    pub fn list(&self) -> Vec<RunningOperation> {
+        let mut running: Vec<RunningOperation> = self
+            .lock()
+            .iter()
+            .map(|(operation_id, registration)| RunningOperation {
+                operation_id: operation_id.clone(),
+                command: registration.command.to_string(),
+                started_at: registration.started_at,
+                cancel_requested: registration.cancelled.load(Ordering::Relaxed),
+            })
+            .collect();
+        running.sort_by(|a, b| {
+            a.started_at
+                .cmp(&b.started_at)
+                .then_with(|| a.operation_id.cmp(&b.operation_id))
+        });
+        running
+    }
+
+    /// Registers an operation, replacing an operation still running under the same ID.
+    fn register(&self, operation_id: &str, command: &'static str) -> Arc<AtomicBool> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.lock().insert(
+            operation_id.to_string(),
+            Registration {
+                command,
+                started_at: audit::now_millis(),
+                cancelled: Arc::clone(&cancelled),
+            },
+        );
+        cancelled
+    }
+
+    /// Forgets an operation, unless another one has taken over its ID.
+    fn unregister(&self, operation_id: &str, cancelled: &Arc<AtomicBool>) {
+        let mut operations = self.lock();
+        if operations
+            .get(operation_id)
+            .is_some_and(|registration| Arc::ptr_eq(&registration.cancelled, cancelled))
+        {
+            operations.remove(operation_id);
+        }
+    }
+
+    /// Returns an ID for an operation the caller didn't name.
+    fn generate_id(&self, command: &str) -> String {
+        let n = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        format!("{}-{}", command, n)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Registration>> {
+        self.operations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Sends an `operation:progress` event.
+type Emit = Box<dyn Fn(&OperationProgress) -> tauri::Result<()> + Send + Sync>;
+
+/// Where a `Progress` sends its events.
+struct Reporter {
+    emit: Emit,
+    operations: Arc<RunningOperations>,
+    operation_id: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Reports the progress of one operation and tells it when it was cancelled.
+///
+/// `Progress::none` reports nothing and is never cancelled, for the callers
+/// of shared code (e.g. `Plan::compute`) that aren't tracked.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// fn run_batch(operations: Vec<Operation>, progress: &mut Progress) -> Result<(), AppError> {
+///     progress.phase("Applying operations", operations.len());
+///     for operation in operations {
+///         progress.check_cancelled()?;
+///         // ...
+///         progress.advance();
+///     }
+///     Ok(())
+/// }
+///
+/// let mut progress = Progress::start(&app, state, operation_id, "execute_batch");
+/// let result = run_batch(operations, &mut progress);
+/// progress.finish(result)
+/// ```
+pub struct Progress {
+    reporter: Option<Reporter>,
+    phase: &'static str,
+    done: usize,
+    total: usize,
+    last_emit: Option<Instant>,
+}
+
+impl Progress {
+    /// Starts tracking an operation of `command`.
+    ///
+    /// If `operation_id` is absent, an ID is generated from the command
+    /// name; the events are still emitted, but the operation can only be
+    /// cancelled by listening for that ID first.
+    pub fn start<R: Runtime>(
+        app: &AppHandle<R>,
+        state: &RwLock<AppState>,
+        operation_id: Option<String>,
+        command: &'static str,
+    ) -> Self {
+        let app = app.clone();
+        let operations = Arc::clone(&read_state(state).running_operations);
+        let operation_id = operation_id.unwrap_or_else(|| operations.generate_id(command));
+        let cancelled = operations.register(&operation_id, command);
+
+        Self {
+            reporter: Some(Reporter {
+                emit: Box::new(move |payload| app.emit(OPERATION_PROGRESS_EVENT, payload)),
+                operations,
+                operation_id,
+                cancelled,
+            }),
+            phase: "Starting",
+            done: 0,
+            total: 0,
+            last_emit: None,
+        }
+    }
+
+    /// Returns a progress that reports nothing and is never cancelled.
+    pub fn none() -> Self {
+        Self {
+            reporter: None,
+            phase: "Starting",
+            done: 0,
+            total: 0,
+            last_emit: None,
+        }
+    }
+
+    /// Starts a phase of `total` items and reports it.
+    pub fn phase(&mut self, phase: &'static str, total: usize) {
+        self.phase = phase;
+        self.done = 0;
+        self.total = total;
+        self.emit(None, None);
+    }
+
+    /// Marks one more item of the phase done, reporting it if it is time to.
+    pub fn advance(&mut self) {
+        self.done += 1;
+        let due = self
+            .last_emit
+            .is_none_or(|last| last.elapsed() >= EMIT_INTERVAL);
+        if due || self.done >= self.total {
+            self.emit(None, None);
+        }
+    }
+
+    /// Returns `Err(AppError::OperationCancelled)` if the operation was cancelled.
+    ///
+    /// The error carries the phase and how many of its items were done, so
+    /// the caller can see how far the operation got.
+    pub fn check_cancelled(&self) -> Result<(), AppError> {
+        match &self.reporter {
+            Some(reporter) if reporter.cancelled.load(Ordering::Relaxed) => {
+                Err(AppError::OperationCancelled {
+                    operation_id: reporter.operation_id.clone(),
+                    phase: self.phase.to_string(),
+                    done: self.done,
+                    total: self.total,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reports how the operation ended and passes its result through.
+    pub fn finish<T>(mut self, result: Result<T, AppError>) -> Result<T, AppError> {
+        match &result {
+            Ok(_) => self.emit(Some(OperationOutcome::Succeeded), None),
+            Err(AppError::OperationCancelled { .. }) => {
+                self.emit(Some(OperationOutcome::Cancelled), None)
+            }
+            Err(e) => self.emit(Some(OperationOutcome::Failed), serde_json::to_value(e).ok()),
+        }
+        result
+    }
+
+    fn emit(&mut self, outcome: Option<OperationOutcome>, error: Option<serde_json::Value>) {
+        let Some(reporter) = &self.reporter else {
+            return;
+        };
+        self.last_emit = Some(Instant::now());
+
+        let payload = OperationProgress {
+            operation_id: reporter.operation_id.clone(),
+            phase: self.phase.to_string(),
+            done: self.done,
+            total: self.total,
+            outcome,
+            error,
+        };
+        if let Err(e) = (reporter.emit)(&payload) {
+            eprintln!(
+                "Warning: failed to emit {} event: {}",
+                OPERATION_PROGRESS_EVENT, e
+            );
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(reporter) = &self.reporter {
+            reporter
+                .operations
+                .unregister(&reporter.operation_id, &reporter.cancelled);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Prefix index of an environment's cached services, for `quick_open`.
+//!
+//! The quick switcher searches on every keystroke, and scanning thousands of
+//! services for a substring each time makes typing lag. The index keeps the
+//! lowercased ID and name of every service, and each word of them, in one
+//! sorted list, so the entries starting with a prefix are found by binary
+//! search.
+//!
+//! The index belongs to the `services_cache` entry it was built from and is
+//! always installed, patched and removed together with it (see
+//! `AppState::update_cached_service`).
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::models::Service;
+
+/// How a service matches a prefix, best first.
+///
+/// # Variants
+///
+/// * `ExactId` - The prefix is the whole ID
+/// * `IdPrefix` - The ID starts with the prefix
+/// * `NamePrefix` - The name starts with the prefix
+/// * `WordPrefix` - A later word of the ID or name starts with the prefix
+///
+/// # Serialization
+///
+/// Ranks are serialized as snake_case strings (e.g., `"id_prefix"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchRank {
+    ExactId,
+    IdPrefix,
+    NamePrefix,
+    WordPrefix,
+}
+
+/// What an index key was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Field {
+    Id,
+    Name,
+    Word,
+}
+
+/// Sorted lowercased IDs, names and words of an environment's services.
+///
+/// # Fields
+///
+/// * `entries` - (key, service ID, field) triples, sorted
+/// * `keys` - The keys of each service, to remove them when it changes
+#[derive(Debug, Clone, Default)]
+pub struct QuickOpenIndex {
+    entries: Vec<(String, String, Field)>,
+    keys: HashMap<String, Vec<(String, Field)>>,
+}
+
+impl QuickOpenIndex {
+    /// Indexes services.
+    pub fn build<'a>(services: impl IntoIterator<Item = &'a Service>) -> Self {
+        let mut index = Self::default();
+        for service in services {
+            let keys = keys_of(service);
+            for (key, field) in &keys {
+                index
+                    .entries
+                    .push((key.clone(), service.id.clone(), *field));
+            }
+            index.keys.insert(service.id.clone(), keys);
+        }
+        index.entries.sort();
+        index.entries.dedup();
+        index
+    }
+
+    /// Replaces the entries of a service, or removes them if it was deleted.
+    pub fn update(&mut self, service_id: &str, service: Option<&Service>) {
+        for (key, field) in self.keys.remove(service_id).unwrap_or_default() {
+            let entry = (key, service_id.to_string(), field);
+            if let Ok(position) = self.entries.binary_search(&entry) {
+                self.entries.remove(position);
+            }
+        }
+
+        let Some(service) = service else {
+            return;
+        };
+        let keys = keys_of(service);
+        for (key, field) in &keys {
+            let entry = (key.clone(), service.id.clone(), *field);
+            if let Err(position) = self.entries.binary_search(&entry) {
+                self.entries.insert(position, entry);
+            }
+        }
+        self.keys.insert(service.id.clone(), keys);
+    }
+
+    /// Returns the services matching a prefix, best match first.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The prefix, compared ignoring case; an empty prefix matches
+    ///   every service by ID
+    /// * `limit` - The most services to return
+    ///
+    /// # Returns
+    ///
+    /// Service IDs with their best match, ordered by rank and then by ID.
+    pub fn search(&self, prefix: &str, limit: usize) -> Vec<(&str, MatchRank)> {
+        let prefix = prefix.trim().to_lowercase();
+        let start = self
+            .entries
+            .partition_point(|(key, _, _)| key.as_str() < prefix.as_str());
+
+        let mut best: HashMap<&str, MatchRank> = HashMap::new();
+        for (key, service_id, field) in self.entries[start..]
+            .iter()
+            .take_while(|(key, _, _)| key.starts_with(&prefix))
+        {
+            let rank = match field {
+                Field::Id if *key == prefix => MatchRank::ExactId,
+                Field::Id => MatchRank::IdPrefix,
+                Field::Name => MatchRank::NamePrefix,
+                Field::Word => MatchRank::WordPrefix,
+            };
+            best.entry(service_id.as_str())
+                .and_modify(|best| *best = (*best).min(rank))
+                .or_insert(rank);
+        }
+
+        let mut matches: Vec<(&str, MatchRank)> = best.into_iter().collect();
+        matches.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+/// Returns the index keys of a service: its lowercased ID and name, and
+/// every later word of them (after a space, `-`, `_`, `.`, `/` or `:`).
+fn keys_of(service: &Service) -> Vec<(String, Field)> {
+    let id = service.id.to_lowercase();
+    let name = service.name.trim().to_lowercase();
+    let mut keys = vec![(id.clone(), Field::Id), (name.clone(), Field::Name)];
+    let separator = |c: char| c.is_whitespace() || matches!(c, '-' | '_' | '.' | '/' | ':');
+    for text in [id, name] {
+        let mut previous = ' ';
+        for (position, character) in text.char_indices() {
+            if position > 0 && separator(previous) && !separator(character) {
+                keys.push((text[position..].to_string(), Field::Word));
+            }
+            previous = character;
+        }
+    }
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::service;
+
+    fn named(id: &str, name: &str) -> Service {
+        Service {
+            name: name.to_string(),
+            ..service(id)
+        }
+    }
+
+    #[test]
+    fn each_service_is_listed_once_with_its_best_rank() {
+        let services = [
+            named("checkout", "Checkout Pay"),
+            named("ledger", "Pay Ledger"),
+            named("pay", "Payments"),
+            named("payments-api", "Checkout Payments"),
+            named("payroll", "payroll"),
+            named("search", "Search"),
+        ];
+        let index = QuickOpenIndex::build(&services);
+
+        assert_eq!(
+            index.search("PAY", 10),
+            vec![
+                ("pay", MatchRank::ExactId),
+                ("payments-api", MatchRank::IdPrefix),
+                ("payroll", MatchRank::IdPrefix),
+                ("ledger", MatchRank::NamePrefix),
+                ("checkout", MatchRank::WordPrefix),
+            ]
+        );
+        assert_eq!(
+            index.search("pay", 2),
+            vec![
+                ("pay", MatchRank::ExactId),
+                ("payments-api", MatchRank::IdPrefix),
+            ]
+        );
+        assert_eq!(
+            index.search("api", 10),
+            vec![("payments-api", MatchRank::WordPrefix)]
+        );
+        assert!(index.search("x", 10).is_empty());
+        assert_eq!(index.search("", 10).len(), services.len());
+    }
+
+    #[test]
+    fn updates_replace_and_remove_a_service_s_entries() {
+        let mut index = QuickOpenIndex::build(&[named("api", "Gateway"), named("db", "Database")]);
+        assert_eq!(
+            index.search("gate", 10),
+            vec![("api", MatchRank::NamePrefix)]
+        );
+
+        // Renamed: the old name no longer matches, the new one does
+        index.update("api", Some(&named("api", "Public Edge")));
+        assert!(index.search("gate", 10).is_empty());
+        assert_eq!(
+            index.search("edge", 10),
+            vec![("api", MatchRank::WordPrefix)]
+        );
+        assert_eq!(index.search("api", 10), vec![("api", MatchRank::ExactId)]);
+
+        // Added
+        index.update("billing", Some(&named("billing", "Billing")));
+        assert_eq!(
+            index.search("bill", 10),
+            vec![("billing", MatchRank::IdPrefix)]
+        );
+
+        // Deleted: none of its entries are left behind
+        index.update("db", None);
+        assert!(index.search("d", 10).is_empty());
+        assert_eq!(
+            index.search("", 10),
+            vec![
+                ("api", MatchRank::IdPrefix),
+                ("billing", MatchRank::IdPrefix)
+            ]
+        );
+
+        // Updating with unchanged keys doesn't duplicate them
+        index.update("billing", Some(&named("billing", "Billing")));
+        assert_eq!(index.search("bill", 10).len(), 1);
+        assert_eq!(
+            index.entries.len(),
+            QuickOpenIndex::build(&[named("api", "Public Edge"), named("billing", "Billing")])
+                .entries
+                .len()
+        );
+    }
+}
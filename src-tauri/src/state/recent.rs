@@ -0,0 +1,233 @@
+//! Recently viewed services, kept apart from the application state lock.
+//!
+//! Every `get_service_by_id` and `get_service_graph` records a view, so
+//! recording one must not take the state's write lock or touch the disk:
+//! reads would wait on each other and on the recents file. Views are
+//! recorded in memory under a lock of their own, and a background thread
+//! writes the file at most once per `SAVE_DELAY`. Views recorded less than
+//! `SAVE_DELAY` before the application exits are written by `flush`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::thread;
+use std::time::Duration;
+
+use crate::models::{data_path_key, RecentService, RecentViews};
+use crate::storage::{self, audit};
+
+/// How many recently viewed services are kept per environment.
+pub const MAX_RECENT_SERVICES: usize = 50;
+
+/// How long after a view the recents file is written, so a burst of views
+/// is written once.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Recently viewed services per data path and environment, most recent first.
+///
+/// Views are kept per data path, not only per environment, so that viewing
+/// `dev` in one workspace doesn't show up in the `dev` of another.
+#[derive(Debug, Default)]
+pub struct RecentServices {
+    views: Mutex<RecentViews>,
+    /// Where the views are persisted. `None` keeps them in memory only.
+    path: Option<PathBuf>,
+    /// Whether views were recorded since the file was last written.
+    dirty: AtomicBool,
+    /// Serializes writes of the file, so an older snapshot never overwrites a newer one.
+    saving: Mutex<()>,
+    /// Wakes the save thread; `None` when the views aren't persisted.
+    saver: Option<Sender<()>>,
+}
+
+impl RecentServices {
+    /// Loads the recently viewed services and persists future views there.
+    ///
+    /// Like settings, an unreadable file falls back to an empty list (with a
+    /// logged warning). Spawns the thread that writes the file; it exits when
+    /// the returned value is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the recently viewed services file
+    pub fn load(path: PathBuf) -> Arc<Self> {
+        let views = storage::load_recent_services(&path).unwrap_or_else(|e| {
+            eprintln!("Warning: forgetting recently viewed services: {}", e);
+            BTreeMap::new()
+        });
+        let (sender, receiver) = channel();
+
+        Arc::new_cyclic(|recent: &Weak<Self>| {
+            let recent = Weak::clone(recent);
+            thread::spawn(move || save_loop(recent, receiver));
+            Self {
+                views: Mutex::new(views),
+                path: Some(path),
+                dirty: AtomicBool::new(false),
+                saving: Mutex::new(()),
+                saver: Some(sender),
+            }
+        })
+    }
+
+    /// Records that the user viewed a service.
+    ///
+    /// The service moves to the front of the environment's recently viewed
+    /// list, which is capped at `MAX_RECENT_SERVICES`. The file is written
+    /// later, on the save thread; failing to write it only logs a warning,
+    /// as it must never fail the view itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path` - The data path the environment is in
+    /// * `environment` - The name of the environment the service is in
+    /// * `service_id` - The service that was viewed
+    pub fn record(&self, data_path: &Path, environment: &str, service_id: &str) {
+        {
+            let mut views = self.lock();
+            let recent = views
+                .entry(data_path_key(data_path))
+                .or_default()
+                .entry(environment.to_string())
+                .or_default();
+            recent.retain(|r| r.service_id != service_id);
+            recent.insert(
+                0,
+                RecentService {
+                    service_id: service_id.to_string(),
+                    viewed_at: audit::now_millis(),
+                },
+            );
+            recent.truncate(MAX_RECENT_SERVICES);
+        }
+
+        self.dirty.store(true, Ordering::SeqCst);
+        if let Some(saver) = &self.saver {
+            let _ = saver.send(());
+        }
+    }
+
+    /// Returns the recently viewed services of an environment in a data
+    /// path, most recent first.
+    pub fn get(&self, data_path: &Path, environment: &str) -> Vec<RecentService> {
+        self.lock()
+            .get(&data_path_key(data_path))
+            .and_then(|environments| environments.get(environment))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Writes the views recorded since the file was last written, if any.
+    pub fn flush(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let _saving = self.saving.lock().unwrap_or_else(PoisonError::into_inner);
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        let views = self.lock().clone();
+        if let Err(e) = storage::save_recent_services(path, &views) {
+            eprintln!("Warning: failed to save recently viewed services: {}", e);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RecentViews> {
+        self.views.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for RecentServices {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Writes the recents file `SAVE_DELAY` after a view, until the views are dropped.
+fn save_loop(recent: Weak<RecentServices>, views_recorded: Receiver<()>) {
+    while views_recorded.recv().is_ok() {
+        thread::sleep(SAVE_DELAY);
+        while views_recorded.try_recv().is_ok() {}
+
+        match recent.upgrade() {
+            Some(recent) => recent.flush(),
+            None => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    const DATA_PATH: &str = "/data/acme";
+
+    fn ids(recent: &[RecentService]) -> Vec<&str> {
+        recent.iter().map(|r| r.service_id.as_str()).collect()
+    }
+
+    #[test]
+    fn views_move_to_the_front_and_are_capped() {
+        let data_path = Path::new(DATA_PATH);
+        let recent = RecentServices::default();
+        recent.record(data_path, "dev", "api");
+        recent.record(data_path, "dev", "db");
+        recent.record(data_path, "prod", "web");
+        recent.record(data_path, "dev", "api");
+
+        assert_eq!(ids(&recent.get(data_path, "dev")), ["api", "db"]);
+        assert_eq!(ids(&recent.get(data_path, "prod")), ["web"]);
+        assert!(recent.get(data_path, "staging").is_empty());
+
+        // The dev of another data path has views of its own
+        let other = Path::new("/data/globex");
+        assert!(recent.get(other, "dev").is_empty());
+        recent.record(other, "dev", "billing");
+        assert_eq!(ids(&recent.get(other, "dev")), ["billing"]);
+        assert_eq!(ids(&recent.get(data_path, "dev")), ["api", "db"]);
+
+        for index in 0..MAX_RECENT_SERVICES + 10 {
+            recent.record(data_path, "dev", &format!("service-{index}"));
+        }
+        let dev = recent.get(data_path, "dev");
+        assert_eq!(dev.len(), MAX_RECENT_SERVICES);
+        assert_eq!(
+            dev[0].service_id,
+            format!("service-{}", MAX_RECENT_SERVICES + 9)
+        );
+    }
+
+    #[test]
+    fn views_are_written_in_the_background_and_flushed_when_dropped() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("recent_services.json");
+        let recent = RecentServices::load(path.clone());
+        let data_path = Path::new(DATA_PATH);
+
+        // Recording returns before the file is written
+        recent.record(data_path, "dev", "api");
+        recent.record(data_path, "dev", "db");
+        assert!(!path.exists());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !path.exists() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        let saved = storage::load_recent_services(&path).unwrap();
+        assert_eq!(ids(&saved[DATA_PATH]["dev"]), ["db", "api"]);
+
+        // A view the save thread hasn't written yet is written on drop
+        recent.record(data_path, "dev", "web");
+        drop(recent);
+        let saved = storage::load_recent_services(&path).unwrap();
+        assert_eq!(ids(&saved[DATA_PATH]["dev"]), ["web", "db", "api"]);
+
+        let reloaded = RecentServices::load(path);
+        assert_eq!(ids(&reloaded.get(data_path, "dev")), ["web", "db", "api"]);
+    }
+}
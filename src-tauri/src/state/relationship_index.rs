@@ -0,0 +1,333 @@
+//! Per-service index of an environment's cached relationships.
+//!
+//! Finding the relationships a service takes part in would otherwise mean
+//! scanning every relationship of the environment, which is noticeable with
+//! tens of thousands of edges. The index maps each service ID to the
+//! positions of the relationships it is the source or target of, in stored
+//! order.
+//!
+//! Positions refer to the cached relationships slice the index was built
+//! from, so the two are always installed together (see
+//! `AppState::cache_relationships`). Both are kept in the order storage
+//! returns relationships in (see `storage::relationship_order`), so an index
+//! still fits the list the next command loads. Single saves and deletes
+//! patch the previous index (see `RelationshipIndex::after_change`) instead
+//! of rebuilding it.
+
+use std::collections::HashMap;
+
+use crate::models::Relationship;
+
+/// Positions of each service's relationships in an environment's relationships.
+#[derive(Debug, Clone, Default)]
+pub struct RelationshipIndex {
+    positions: HashMap<String, Vec<usize>>,
+    relationship_count: usize,
+}
+
+impl RelationshipIndex {
+    /// Indexes a list of relationships.
+    pub fn build(relationships: &[Relationship]) -> Self {
+        let mut index = Self {
+            positions: HashMap::new(),
+            relationship_count: relationships.len(),
+        };
+        for (position, relationship) in relationships.iter().enumerate() {
+            index.add(position, relationship);
+        }
+        index
+    }
+
+    /// Returns the positions of the relationships a service is the source or
+    /// target of, in stored order.
+    pub fn positions(&self, service_id: &str) -> &[usize] {
+        self.positions
+            .get(service_id)
+            .map_or(&[], |positions| positions.as_slice())
+    }
+
+    /// Returns the relationships a service is the source or target of, in stored order.
+    ///
+    /// `relationships` must be the list the index was built from.
+    pub fn relationships_of<'a>(
+        &'a self,
+        relationships: &'a [Relationship],
+        service_id: &str,
+    ) -> impl Iterator<Item = &'a Relationship> {
+        self.positions(service_id)
+            .iter()
+            .filter_map(|&position| relationships.get(position))
+    }
+
+    /// Records a relationship inserted at `position`, shifting the ones after it.
+    fn insert(&mut self, position: usize, relationship: &Relationship) {
+        if position < self.relationship_count {
+            self.shift(|p| if p >= position { p + 1 } else { p });
+        }
+        self.relationship_count += 1;
+        self.add(position, relationship);
+    }
+
+    /// Forgets the relationship removed from `position`, shifting the ones after it back.
+    fn delete(&mut self, position: usize, relationship: &Relationship) {
+        self.remove(position, relationship);
+        self.relationship_count = self.relationship_count.saturating_sub(1);
+        if position < self.relationship_count {
+            self.shift(|p| if p > position { p - 1 } else { p });
+        }
+    }
+
+    /// Returns the index of `relationships` after one change, patching
+    /// `previous` when it was built from the list before the change.
+    ///
+    /// `before` is the changed relationship and its position before the
+    /// change, `after` the same after it (`None` for a created or deleted
+    /// relationship), like a journal `Change::Relationship`. A relationship
+    /// whose position changed, e.g. because it was re-sorted under a new
+    /// source, is removed from its old position and inserted at its new one.
+    ///
+    /// Falls back to building a new index when there is no previous index
+    /// or its size doesn't fit the change.
+    pub fn after_change(
+        previous: Option<&Self>,
+        relationships: &[Relationship],
+        before: Option<(usize, &Relationship)>,
+        after: Option<(usize, &Relationship)>,
+    ) -> Self {
+        let count_before = match (before, after) {
+            (None, Some(_)) => relationships.len().checked_sub(1),
+            (Some(_), None) => Some(relationships.len() + 1),
+            _ => Some(relationships.len()),
+        };
+
+        match previous {
+            Some(previous) if Some(previous.relationship_count) == count_before => {
+                let mut index = previous.clone();
+                if let Some((position, relationship)) = before {
+                    index.delete(position, relationship);
+                }
+                if let Some((position, relationship)) = after {
+                    index.insert(position, relationship);
+                }
+                index
+            }
+            _ => Self::build(relationships),
+        }
+    }
+
+    /// Records a relationship's position under its source and target.
+    fn add(&mut self, position: usize, relationship: &Relationship) {
+        for service_id in endpoints(relationship) {
+            let positions = self.positions.entry(service_id.to_string()).or_default();
+            if let Err(slot) = positions.binary_search(&position) {
+                positions.insert(slot, position);
+            }
+        }
+    }
+
+    /// Forgets a relationship's position under its source and target.
+    fn remove(&mut self, position: usize, relationship: &Relationship) {
+        for service_id in endpoints(relationship) {
+            let Some(positions) = self.positions.get_mut(service_id) else {
+                continue;
+            };
+            if let Ok(slot) = positions.binary_search(&position) {
+                positions.remove(slot);
+            }
+            if positions.is_empty() {
+                self.positions.remove(service_id);
+            }
+        }
+    }
+
+    /// Moves every recorded position; `f` must keep them in the same order.
+    fn shift(&mut self, f: impl Fn(usize) -> usize) {
+        for positions in self.positions.values_mut() {
+            for position in positions.iter_mut() {
+                *position = f(*position);
+            }
+        }
+    }
+}
+
+/// Returns the services a relationship connects, once each for self-loops.
+fn endpoints(relationship: &Relationship) -> impl Iterator<Item = &str> {
+    let target =
+        (relationship.target != relationship.source).then_some(relationship.target.as_str());
+    std::iter::once(relationship.source.as_str()).chain(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::relationship;
+    use std::time::Instant;
+
+    /// A small deterministic generator, so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn relationship(&mut self, id: usize, services: usize) -> Relationship {
+            let source = format!("svc-{}", self.below(services));
+            let target = format!("svc-{}", self.below(services));
+            relationship(&format!("rel-{id}"), &source, &target)
+        }
+    }
+
+    fn brute_force(relationships: &[Relationship], service_id: &str) -> Vec<usize> {
+        relationships
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.source == service_id || r.target == service_id)
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    fn assert_matches_brute_force(
+        index: &RelationshipIndex,
+        relationships: &[Relationship],
+        services: usize,
+    ) {
+        for n in 0..services {
+            let service_id = format!("svc-{n}");
+            assert_eq!(
+                index.positions(&service_id),
+                brute_force(relationships, &service_id),
+                "{service_id}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_a_brute_force_filter_on_a_large_set() {
+        const SERVICES: usize = 2_000;
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        let relationships: Vec<Relationship> = (0..20_000)
+            .map(|id| rng.relationship(id, SERVICES))
+            .collect();
+
+        let index = RelationshipIndex::build(&relationships);
+
+        assert_matches_brute_force(&index, &relationships, SERVICES);
+        assert!(index.positions("svc-unknown").is_empty());
+    }
+
+    #[test]
+    fn stays_correct_through_incremental_saves_and_deletes() {
+        const SERVICES: usize = 300;
+        let mut rng = Rng(42);
+        let mut relationships: Vec<Relationship> = (0..3_000)
+            .map(|id| rng.relationship(id, SERVICES))
+            .collect();
+        let mut index = RelationshipIndex::build(&relationships);
+
+        for step in 0..600 {
+            let next_id = 3_000 + step;
+            let position = rng.below(relationships.len());
+            index = match step % 3 {
+                // Created, inserted anywhere
+                0 => {
+                    let created = rng.relationship(next_id, SERVICES);
+                    relationships.insert(position, created.clone());
+                    RelationshipIndex::after_change(
+                        Some(&index),
+                        &relationships,
+                        None,
+                        Some((position, &created)),
+                    )
+                }
+                // Updated to new endpoints, and moved like a re-sorted save
+                1 => {
+                    let before = relationships.remove(position);
+                    let updated = rng.relationship(next_id, SERVICES);
+                    let new_position = rng.below(relationships.len() + 1);
+                    relationships.insert(new_position, updated.clone());
+                    RelationshipIndex::after_change(
+                        Some(&index),
+                        &relationships,
+                        Some((position, &before)),
+                        Some((new_position, &updated)),
+                    )
+                }
+                // Deleted
+                _ => {
+                    let removed = relationships.remove(position);
+                    RelationshipIndex::after_change(
+                        Some(&index),
+                        &relationships,
+                        Some((position, &removed)),
+                        None,
+                    )
+                }
+            };
+
+            if step % 100 == 0 {
+                assert_matches_brute_force(&index, &relationships, SERVICES);
+            }
+        }
+
+        assert_matches_brute_force(&index, &relationships, SERVICES);
+    }
+
+    #[test]
+    fn self_loops_are_indexed_once() {
+        let relationships = [relationship("loop", "a", "a"), relationship("ab", "a", "b")];
+
+        let index = RelationshipIndex::build(&relationships);
+
+        assert_eq!(index.positions("a"), [0, 1]);
+        assert_eq!(index.positions("b"), [1]);
+    }
+
+    #[test]
+    fn lookups_beat_scanning_every_relationship() {
+        const SERVICES: usize = 2_000;
+        let mut rng = Rng(7);
+        let relationships: Vec<Relationship> = (0..20_000)
+            .map(|id| rng.relationship(id, SERVICES))
+            .collect();
+        let index = RelationshipIndex::build(&relationships);
+        let service_ids: Vec<String> = (0..200).map(|n| format!("svc-{n}")).collect();
+
+        let started = Instant::now();
+        let scanned: usize = service_ids
+            .iter()
+            .map(|id| {
+                relationships
+                    .iter()
+                    .filter(|r| r.source == *id || r.target == *id)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .len()
+            })
+            .sum();
+        let scanning = started.elapsed();
+
+        let started = Instant::now();
+        let looked_up: usize = service_ids
+            .iter()
+            .map(|id| {
+                index
+                    .relationships_of(&relationships, id)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .len()
+            })
+            .sum();
+        let lookup = started.elapsed();
+
+        assert_eq!(looked_up, scanned);
+        // Typically three orders of magnitude apart; a wide margin keeps slow machines green
+        assert!(
+            lookup * 10 < scanning,
+            "index {lookup:?} vs scan {scanning:?}"
+        );
+    }
+}
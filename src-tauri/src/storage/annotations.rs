@@ -0,0 +1,110 @@
+//! Persistence for graph node annotations.
+//!
+//! Each environment keeps its annotations (see `models::NodeAnnotation`) in
+//! a hidden, pretty-printed `.annotations.json` next to its data, as a map
+//! of service ID to annotation, so they are never loaded as services and the
+//! data directory watcher and storage report ignore them. For SQLite
+//! databases they are kept in a `{database}.annotations/` directory next to
+//! the database file, one `{environment}.json` per environment.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::models::NodeAnnotation;
+use crate::storage::backend::{environment_sidecar_path, is_sqlite_path};
+use crate::storage::ids::validate_environment_name;
+
+/// File name of an environment's annotations file.
+pub const ANNOTATIONS_FILE: &str = ".annotations.json";
+
+/// Returns the path of an environment's annotations file.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/.annotations.json`, or
+///   `{database}.annotations/{environment}.json` for SQLite databases
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+pub fn annotations_path(data_path: &Path, environment: &str) -> Result<PathBuf, AppError> {
+    validate_environment_name(environment)?;
+
+    Ok(environment_sidecar_path(
+        data_path,
+        environment,
+        ANNOTATIONS_FILE,
+    ))
+}
+
+/// Returns true if the file is an environment's annotations file.
+///
+/// Used to keep annotations out of data file scans.
+pub fn is_annotations_file(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()) == Some(ANNOTATIONS_FILE)
+}
+
+/// Loads an environment's annotations.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(BTreeMap)` - Annotations by service ID (empty if none were saved)
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or parsed
+pub fn load_annotations(
+    data_path: &Path,
+    environment: &str,
+) -> Result<BTreeMap<String, NodeAnnotation>, AppError> {
+    let path = annotations_path(data_path, environment)?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(&path).map_err(|e| load_error(e.to_string()))?;
+    serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))
+}
+
+/// Saves an environment's annotations, replacing the stored map.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+/// * `annotations` - All annotations by service ID
+///
+/// # Returns
+///
+/// * `Ok(())` - If the annotations were written
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::Io)` - If the file cannot be written
+pub fn save_annotations(
+    data_path: &Path,
+    environment: &str,
+    annotations: &BTreeMap<String, NodeAnnotation>,
+) -> Result<(), AppError> {
+    let path = annotations_path(data_path, environment)?;
+    if is_sqlite_path(data_path) {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+
+    let content = serde_json::to_string_pretty(annotations)?;
+    fs::write(path, content)?;
+
+    Ok(())
+}
@@ -0,0 +1,405 @@
+//! Append-only audit log of data changes.
+//!
+//! Every mutating command appends one JSON line per change to the affected
+//! environment's audit log, recording when the change happened, who made it
+//! (the OS username), which command made it, the IDs it touched and a compact
+//! summary of the data before and after.
+//!
+//! # Location
+//!
+//! ```text
+//! {data_path}/
+//! ├── {environment}/
+//! │   ├── services/
+//! │   ├── relationships.json
+//! │   ├── .audit.jsonl        (current log)
+//! │   ├── .audit.1.jsonl      (most recently rotated)
+//! │   └── .audit.2.jsonl
+//! ```
+//!
+//! The log is a hidden file next to the data rather than inside `services/`,
+//! so it is never loaded as a service, and the data directory watcher and
+//! storage report ignore it. For SQLite databases the logs are kept in a
+//! `{database}.audit/` directory next to the database file, one
+//! `{environment}.jsonl` per environment.
+//!
+//! # Rotation
+//!
+//! Before an append, a log larger than `MAX_AUDIT_LOG_BYTES` is renamed to
+//! `.audit.1.jsonl` (shifting older logs up by one) and a new log is started.
+//! At most `MAX_ROTATED_LOGS` rotated logs are kept; the oldest is dropped.
+//! Reads span the current and all rotated logs.
+//!
+//! The audit log is never encrypted. Summaries are limited to names, types
+//! and statuses for that reason.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::AppError;
+use crate::models::{Relationship, Service};
+use crate::storage::backend::{environment_sidecar_path, is_sqlite_path};
+use crate::storage::ids::validate_environment_name;
+
+/// File name of an environment's current audit log.
+pub const AUDIT_LOG_FILE: &str = ".audit.jsonl";
+
+/// Size above which the audit log is rotated before the next append.
+pub const MAX_AUDIT_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Number of rotated audit logs kept per environment.
+pub const MAX_ROTATED_LOGS: usize = 5;
+
+/// User name recorded when the OS username can't be determined.
+const UNKNOWN_USER: &str = "unknown";
+
+/// One change recorded in the audit log.
+///
+/// # Fields
+///
+/// * `timestamp` - When the change was made, in milliseconds since the Unix epoch
+/// * `user` - The OS username of whoever ran the application
+/// * `command` - The command that made the change (e.g., "save_service")
+/// * `affected_ids` - IDs of the changed services and relationships; relationship
+///   changes also list their source and target services
+/// * `before` - Summary of the data before the change, if it existed
+/// * `after` - Summary of the data after the change, if it still exists
+///
+/// # Serialization
+///
+/// Uses camelCase field names; `before` and `after` are omitted when absent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub timestamp: u64,
+    pub user: String,
+    pub command: String,
+    pub affected_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+impl AuditRecord {
+    /// Creates a record of a change made now by the current user.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command that made the change
+    /// * `affected_ids` - IDs of the changed services and relationships
+    pub fn new(command: &str, affected_ids: Vec<String>) -> Self {
+        Self {
+            timestamp: now_millis(),
+            user: current_user(),
+            command: command.to_string(),
+            affected_ids,
+            before: None,
+            after: None,
+        }
+    }
+
+    /// Sets the before and after summaries.
+    pub fn with_change(mut self, before: Option<String>, after: Option<String>) -> Self {
+        self.before = before;
+        self.after = after;
+        self
+    }
+
+    /// Creates a record of a change to one service.
+    pub fn service(command: &str, before: Option<&Service>, after: Option<&Service>) -> Self {
+        let id = after.or(before).map(|s| s.id.clone()).unwrap_or_default();
+        Self::new(command, vec![id])
+            .with_change(before.map(service_summary), after.map(service_summary))
+    }
+
+    /// Creates a record of a change to one relationship.
+    pub fn relationship(
+        command: &str,
+        before: Option<&Relationship>,
+        after: Option<&Relationship>,
+    ) -> Self {
+        let mut affected_ids = Vec::new();
+        if let Some(relationship) = after.or(before) {
+            affected_ids.push(relationship.id.clone());
+        }
+        for relationship in before.into_iter().chain(after) {
+            for id in [&relationship.source, &relationship.target] {
+                if !affected_ids.contains(id) {
+                    affected_ids.push(id.clone());
+                }
+            }
+        }
+
+        Self::new(command, affected_ids).with_change(
+            before.map(Relationship::label),
+            after.map(Relationship::label),
+        )
+    }
+
+    /// Returns true if the record touched the given service.
+    pub fn affects(&self, service_id: &str) -> bool {
+        self.affected_ids.iter().any(|id| id == service_id)
+    }
+}
+
+/// Summarizes a service for the audit log (e.g., "User API (api, healthy)").
+pub fn service_summary(service: &Service) -> String {
+    format!(
+        "{} ({}, {})",
+        service.name,
+        service.service_type.as_str(),
+        service.status.as_str()
+    )
+}
+
+/// Returns the current time in milliseconds since the Unix epoch.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Formats milliseconds since the Unix epoch as an ISO 8601 UTC timestamp.
+///
+/// # Examples
+///
+/// `format_timestamp(0)` returns `"1970-01-01T00:00:00Z"`.
+pub fn format_timestamp(millis: u64) -> String {
+    let seconds = millis / 1000;
+    let (days, time) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Returns the OS username of the current user.
+///
+/// Reads `USER` (Unix) or `USERNAME` (Windows); falls back to "unknown".
+pub fn current_user() -> String {
+    ["USER", "USERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|user| !user.is_empty())
+        .unwrap_or_else(|| UNKNOWN_USER.to_string())
+}
+
+/// Returns the path of an environment's current audit log.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/.audit.jsonl`, or
+///   `{database}.audit/{environment}.jsonl` for SQLite databases
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+pub fn audit_log_path(data_path: &Path, environment: &str) -> Result<PathBuf, AppError> {
+    validate_environment_name(environment)?;
+
+    Ok(environment_sidecar_path(
+        data_path,
+        environment,
+        AUDIT_LOG_FILE,
+    ))
+}
+
+/// Returns true if the file name is a current or rotated audit log.
+///
+/// Used to keep audit logs out of data file scans.
+pub fn is_audit_log_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    match name.strip_prefix(".audit.") {
+        Some("jsonl") => true,
+        Some(rest) => rest
+            .strip_suffix(".jsonl")
+            .is_some_and(|n| n.parse::<usize>().is_ok()),
+        None => false,
+    }
+}
+
+/// Returns the path of the `n`th rotated log (1 = most recent).
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    path.with_file_name(format!("{}.{}.jsonl", stem, n))
+}
+
+/// Appends records to an environment's audit log, rotating it first if needed.
+///
+/// Records are written with a single `write` call so a crash can at worst
+/// leave one truncated last line, which readers skip.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The environment the records belong to
+/// * `records` - The records to append, in the order the changes were made
+///
+/// # Returns
+///
+/// * `Ok(())` - If the records were appended
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::Io)` - If the log cannot be rotated or written
+pub fn append_records(
+    data_path: &Path,
+    environment: &str,
+    records: &[AuditRecord],
+) -> Result<(), AppError> {
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    let path = audit_log_path(data_path, environment)?;
+    if is_sqlite_path(data_path) {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+
+    rotate_if_needed(&path)?;
+
+    let mut lines = String::new();
+    for record in records {
+        lines.push_str(&serde_json::to_string(record)?);
+        lines.push('\n');
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(lines.as_bytes())?;
+
+    Ok(())
+}
+
+/// Rotates a log that has grown past `MAX_AUDIT_LOG_BYTES`.
+fn rotate_if_needed(path: &Path) -> Result<(), AppError> {
+    let size = match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    if size < MAX_AUDIT_LOG_BYTES {
+        return Ok(());
+    }
+
+    // The oldest log is overwritten by the one before it
+    for n in (1..MAX_ROTATED_LOGS).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, n + 1))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))?;
+
+    Ok(())
+}
+
+/// A page of audit records, newest first.
+///
+/// # Fields
+///
+/// * `entries` - The records on this page
+/// * `total` - The number of records matching the filter across all pages
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditRecord>,
+    pub total: usize,
+}
+
+/// Reads a page of an environment's audit log, newest first.
+///
+/// Reads the current log and every rotated log. Lines that don't parse (for
+/// example a line cut short by a crash) are skipped with a warning.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The environment whose log to read
+/// * `limit` - Maximum number of records to return
+/// * `offset` - Number of matching records to skip, counting from the newest
+/// * `service_id` - Only return records that touched this service
+///
+/// # Returns
+///
+/// * `Ok(AuditLogPage)` - The page (empty if the environment has no log yet)
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::Io)` - If a log exists but cannot be read
+pub fn read_records(
+    data_path: &Path,
+    environment: &str,
+    limit: usize,
+    offset: usize,
+    service_id: Option<&str>,
+) -> Result<AuditLogPage, AppError> {
+    let path = audit_log_path(data_path, environment)?;
+    let paths =
+        std::iter::once(path.clone()).chain((1..=MAX_ROTATED_LOGS).map(|n| rotated_path(&path, n)));
+
+    let mut entries = Vec::new();
+    let mut total = 0;
+    for path in paths {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let lines: Vec<&str> = contents.lines().collect();
+        for (index, line) in lines.into_iter().enumerate().rev() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: AuditRecord = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(e) => {
+                    eprintln!(
+                        "Warning: skipping audit log line {} in {}: {}",
+                        index + 1,
+                        path.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+            if service_id.is_some_and(|id| !record.affects(id)) {
+                continue;
+            }
+
+            if total >= offset && entries.len() < limit {
+                entries.push(record);
+            }
+            total += 1;
+        }
+    }
+
+    Ok(AuditLogPage { entries, total })
+}
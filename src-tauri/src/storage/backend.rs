@@ -0,0 +1,534 @@
+//! Storage backend abstraction.
+//!
+//! Commands access persisted data through the `StorageBackend` trait so they
+//! don't care where data actually lives. Two implementations exist:
+//!
+//! * `FileSystemBackend` - JSON files in a directory tree (see `loader`)
+//! * `SqliteBackend` - A single SQLite database file (see `sqlite`)
+//!
+//! The backend is selected from the data path: a path ending in `.db`,
+//! `.sqlite`, or `.sqlite3` opens the SQLite backend, anything else is
+//! treated as a data directory.
+//!
+//! Encryption at rest (see `encryption`) is only supported by the filesystem
+//! backend; the SQLite backend ignores the encryption key.
+
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::error::AppError;
+use crate::models::{Relationship, Service};
+use crate::storage::encryption::EncryptionKey;
+use crate::storage::environment_metadata::ENVIRONMENT_METADATA_FILE;
+use crate::storage::ids::validate_environment_name;
+use crate::storage::loader;
+use crate::storage::sqlite::SqliteBackend;
+
+/// Persistence operations used by the Tauri commands.
+///
+/// Every method takes an environment name; implementations are responsible
+/// for validating it (and any service IDs) before use. Implementations must
+/// be thread-safe since the backend is shared across command invocations.
+pub trait StorageBackend: Debug + Send + Sync {
+    /// Loads all services in an environment (empty if the environment has none).
+    fn load_services(&self, environment: &str) -> Result<Vec<Service>, AppError>;
+
+    /// Loads a single service, returning `ServiceNotFound` if it doesn't exist.
+    fn load_service(&self, environment: &str, service_id: &str) -> Result<Service, AppError>;
+
+    /// Checks whether a service exists without loading it.
+    fn service_exists(&self, environment: &str, service_id: &str) -> Result<bool, AppError>;
+
+    /// Creates or overwrites a service.
+    fn save_service(&self, environment: &str, service: &Service) -> Result<(), AppError>;
+
+    /// Deletes a service, returning `ServiceNotFound` if it doesn't exist.
+    fn delete_service_file(&self, environment: &str, service_id: &str) -> Result<(), AppError>;
+
+    /// Loads all relationships in an environment (empty if the environment has none).
+    fn load_relationships(&self, environment: &str) -> Result<Vec<Relationship>, AppError>;
+
+    /// Replaces all relationships in an environment.
+    fn save_relationships(
+        &self,
+        environment: &str,
+        relationships: &[Relationship],
+    ) -> Result<(), AppError>;
+
+    /// Lists the names of all environments (unsorted).
+    fn list_environments(&self) -> Result<Vec<String>, AppError>;
+
+    /// Checks whether an environment exists.
+    fn environment_exists(&self, environment: &str) -> Result<bool, AppError>;
+
+    /// Creates an empty environment, returning `EnvironmentExists` if it already exists.
+    fn create_environment(&self, environment: &str) -> Result<(), AppError>;
+
+    /// Returns when an environment's data last changed, as cheaply as possible.
+    ///
+    /// Used to detect edits made outside the application. `None` means the
+    /// time is unknown (for example because the environment doesn't exist).
+    fn last_modified(&self, environment: &str) -> Option<SystemTime>;
+
+    /// Returns the filesystem backend if this is one.
+    ///
+    /// Used by file-specific maintenance features (filename repair, schema
+    /// migration of files) that have no meaning for other backends.
+    fn as_file_system(&self) -> Option<&FileSystemBackend> {
+        None
+    }
+}
+
+/// Opens the storage backend appropriate for a data path.
+///
+/// # Arguments
+///
+/// * `data_path` - A data directory, or a SQLite database file
+/// * `encryption` - The encryption key for a data directory, if a passphrase is set
+///
+/// # Returns
+///
+/// A shared backend instance. Opening never touches the disk; errors surface
+/// on first use.
+pub fn open_backend(
+    data_path: &Path,
+    encryption: Option<EncryptionKey>,
+) -> Arc<dyn StorageBackend> {
+    if is_sqlite_path(data_path) {
+        Arc::new(SqliteBackend::new(data_path.to_path_buf()))
+    } else {
+        Arc::new(FileSystemBackend::new(data_path.to_path_buf()).with_encryption(encryption))
+    }
+}
+
+/// Returns true if the path names a SQLite database file (by extension).
+pub fn is_sqlite_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("db") | Some("sqlite") | Some("sqlite3")
+    )
+}
+
+/// Returns where a file or directory kept for a whole data path lives, such
+/// as the validation config or the saved views.
+///
+/// `name` is its name within a data directory, e.g. `.views`. A SQLite
+/// database has no directory to hold it, so it is kept next to the database
+/// file instead: the `.views` of `map.db` are in `map.db.views`.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `name` - The hidden name within a data directory, starting with a dot
+pub fn sidecar_path(data_path: &Path, name: &str) -> PathBuf {
+    if is_sqlite_path(data_path) {
+        let mut path = data_path.as_os_str().to_owned();
+        path.push(name);
+        PathBuf::from(path)
+    } else {
+        data_path.join(name)
+    }
+}
+
+/// Returns where a file or directory kept per environment lives, such as
+/// its groups or its snapshots.
+///
+/// `name` is its name within the environment's directory, e.g. `groups.json`.
+/// Next to a SQLite database each kind gets a directory of its own, named
+/// after `name` without its leading dot and extension, holding one entry per
+/// environment: `groups.json` of `dev` in `map.db` is
+/// `map.db.groups/dev.json`, and `.snapshots` is `map.db.snapshots/dev`.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment, already validated
+/// * `name` - The name within an environment directory
+pub fn environment_sidecar_path(data_path: &Path, environment: &str, name: &str) -> PathBuf {
+    if !is_sqlite_path(data_path) {
+        return data_path.join(environment).join(name);
+    }
+
+    let (kind, entry) = match name.trim_start_matches('.').split_once('.') {
+        Some((kind, extension)) => (kind, format!("{}.{}", environment, extension)),
+        None => (name.trim_start_matches('.'), environment.to_string()),
+    };
+    sidecar_path(data_path, &format!(".{}", kind)).join(entry)
+}
+
+/// Storage backend using one JSON file per service plus one relationships
+/// file per environment, as described in the `loader` module.
+///
+/// Files are written encrypted when an encryption key is set.
+#[derive(Debug, Clone)]
+pub struct FileSystemBackend {
+    data_path: PathBuf,
+    encryption: Option<EncryptionKey>,
+}
+
+impl FileSystemBackend {
+    /// Creates a backend rooted at a data directory (without encryption).
+    pub fn new(data_path: PathBuf) -> Self {
+        Self {
+            data_path,
+            encryption: None,
+        }
+    }
+
+    /// Sets the key used to encrypt and decrypt files.
+    pub fn with_encryption(mut self, encryption: Option<EncryptionKey>) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// Returns the root data directory.
+    pub fn data_path(&self) -> &Path {
+        &self.data_path
+    }
+
+    /// Returns the encryption key, if one is set.
+    pub fn encryption_key(&self) -> Option<&EncryptionKey> {
+        self.encryption.as_ref()
+    }
+}
+
+impl StorageBackend for FileSystemBackend {
+    fn load_services(&self, environment: &str) -> Result<Vec<Service>, AppError> {
+        loader::load_services(&self.data_path, environment, self.encryption_key())
+    }
+
+    fn load_service(&self, environment: &str, service_id: &str) -> Result<Service, AppError> {
+        loader::load_service(
+            &self.data_path,
+            environment,
+            service_id,
+            self.encryption_key(),
+        )
+    }
+
+    /// Only checks that the service file exists; it may still fail to parse.
+    fn service_exists(&self, environment: &str, service_id: &str) -> Result<bool, AppError> {
+        Ok(loader::service_file_path(&self.data_path, environment, service_id)?.exists())
+    }
+
+    fn save_service(&self, environment: &str, service: &Service) -> Result<(), AppError> {
+        loader::save_service(&self.data_path, environment, service, self.encryption_key())
+    }
+
+    fn delete_service_file(&self, environment: &str, service_id: &str) -> Result<(), AppError> {
+        loader::delete_service_file(&self.data_path, environment, service_id)
+    }
+
+    fn load_relationships(&self, environment: &str) -> Result<Vec<Relationship>, AppError> {
+        loader::load_relationships(&self.data_path, environment, self.encryption_key())
+    }
+
+    fn save_relationships(
+        &self,
+        environment: &str,
+        relationships: &[Relationship],
+    ) -> Result<(), AppError> {
+        loader::save_relationships(
+            &self.data_path,
+            environment,
+            relationships,
+            self.encryption_key(),
+        )
+    }
+
+    /// Treats every non-hidden subdirectory of the data path as an environment.
+    fn list_environments(&self) -> Result<Vec<String>, AppError> {
+        let mut environments = Vec::new();
+
+        if self.data_path.exists() {
+            for entry in fs::read_dir(&self.data_path)? {
+                let entry = entry?;
+                let path = entry.path();
+
+                if path.is_dir() {
+                    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                        // Skip hidden directories
+                        if !name.starts_with('.') {
+                            environments.push(name.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(environments)
+    }
+
+    fn environment_exists(&self, environment: &str) -> Result<bool, AppError> {
+        validate_environment_name(environment)?;
+
+        Ok(self.data_path.join(environment).exists())
+    }
+
+    /// Creates `{environment}/services/` and an empty `relationships.json`.
+    fn create_environment(&self, environment: &str) -> Result<(), AppError> {
+        validate_environment_name(environment)?;
+
+        let env_path = self.data_path.join(environment);
+
+        // Check if environment already exists
+        if env_path.exists() {
+            return Err(AppError::EnvironmentExists(environment.to_string()));
+        }
+
+        // Create the environment directory with its services subdirectory
+        fs::create_dir_all(env_path.join("services"))?;
+
+        // Create an empty relationships.json file in the canonical format
+        loader::save_relationships(&self.data_path, environment, &[], self.encryption_key())
+    }
+
+    /// Uses the latest of the `services/` directory, `relationships.json`,
+    /// the `relationships/` shards directory and `environment.json`
+    /// modification times. Adding, removing or atomically replacing a service
+    /// file or shard updates its directory; in-place edits of a service file
+    /// or shard are left to the data directory watcher.
+    fn last_modified(&self, environment: &str) -> Option<SystemTime> {
+        validate_environment_name(environment).ok()?;
+
+        let env_path = self.data_path.join(environment);
+        [
+            env_path.join("services"),
+            env_path.join("relationships.json"),
+            env_path.join("relationships"),
+            env_path.join(ENVIRONMENT_METADATA_FILE),
+        ]
+        .iter()
+        .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+    }
+
+    fn as_file_system(&self) -> Option<&FileSystemBackend> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{relationship, service};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    /// Runs a test against an empty data directory and an empty database.
+    ///
+    /// The data is kept in the canonical stored form (sorted tags and
+    /// relationships), which both backends return unchanged.
+    fn on_both_backends(test: impl Fn(&dyn StorageBackend)) {
+        for file_name in ["data", "data.db"] {
+            let dir = TempDir::new().unwrap();
+            let backend = open_backend(&dir.path().join(file_name), None);
+            assert_eq!(backend.as_file_system().is_some(), file_name == "data");
+            test(backend.as_ref());
+        }
+    }
+
+    fn detailed_service(id: &str) -> Service {
+        let mut service = service(id);
+        service.description = Some("Serves the public API".to_string());
+        service.tags = vec!["public".to_string(), "tier-1".to_string()];
+        service.metadata.insert(
+            "kubernetes".to_string(),
+            json!({ "namespace": "shop", "ports": [80, 443] }),
+        );
+        service
+    }
+
+    fn as_json(service: impl serde::Serialize) -> serde_json::Value {
+        serde_json::to_value(service).unwrap()
+    }
+
+    #[test]
+    fn environments_are_created_once_and_listed() {
+        on_both_backends(|backend| {
+            assert!(backend.list_environments().unwrap().is_empty());
+            assert!(!backend.environment_exists("dev").unwrap());
+
+            backend.create_environment("dev").unwrap();
+            backend.create_environment("prod").unwrap();
+
+            assert!(matches!(
+                backend.create_environment("dev"),
+                Err(AppError::EnvironmentExists(name)) if name == "dev"
+            ));
+            let mut environments = backend.list_environments().unwrap();
+            environments.sort();
+            assert_eq!(environments, vec!["dev", "prod"]);
+            assert!(backend.environment_exists("prod").unwrap());
+            assert!(backend.load_services("dev").unwrap().is_empty());
+            assert!(backend.load_relationships("dev").unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn services_round_trip_with_their_metadata() {
+        on_both_backends(|backend| {
+            backend.create_environment("dev").unwrap();
+            let api = detailed_service("api");
+
+            backend.save_service("dev", &api).unwrap();
+            backend.save_service("dev", &service("db")).unwrap();
+
+            assert_eq!(
+                as_json(backend.load_service("dev", "api").unwrap()),
+                as_json(&api)
+            );
+            assert!(backend.service_exists("dev", "db").unwrap());
+            assert!(!backend.service_exists("dev", "cache").unwrap());
+            let mut ids: Vec<String> = backend
+                .load_services("dev")
+                .unwrap()
+                .into_iter()
+                .map(|s| s.id)
+                .collect();
+            ids.sort();
+            assert_eq!(ids, vec!["api", "db"]);
+
+            let mut renamed = api.clone();
+            renamed.name = "Public API".to_string();
+            backend.save_service("dev", &renamed).unwrap();
+            assert_eq!(
+                as_json(backend.load_service("dev", "api").unwrap()),
+                as_json(&renamed)
+            );
+            assert_eq!(backend.load_services("dev").unwrap().len(), 2);
+        });
+    }
+
+    #[test]
+    fn deleting_a_service_removes_only_it() {
+        on_both_backends(|backend| {
+            backend.create_environment("dev").unwrap();
+            backend.create_environment("prod").unwrap();
+            backend.save_service("dev", &service("api")).unwrap();
+            backend.save_service("prod", &service("api")).unwrap();
+
+            backend.delete_service_file("dev", "api").unwrap();
+
+            assert!(matches!(
+                backend.load_service("dev", "api"),
+                Err(AppError::ServiceNotFound(id)) if id == "api"
+            ));
+            assert!(matches!(
+                backend.delete_service_file("dev", "api"),
+                Err(AppError::ServiceNotFound(_))
+            ));
+            assert!(backend.service_exists("prod", "api").unwrap());
+        });
+    }
+
+    #[test]
+    fn relationships_are_replaced_as_a_whole() {
+        on_both_backends(|backend| {
+            backend.create_environment("dev").unwrap();
+            let mut api_db = relationship("api-db", "api", "db");
+            api_db.metadata = Some([("port".to_string(), json!(5432))].into());
+            let first = vec![
+                api_db,
+                relationship("api-queue", "api", "queue"),
+                relationship("web-api", "web", "api"),
+            ];
+
+            backend.save_relationships("dev", &first).unwrap();
+            assert_eq!(backend.load_relationships("dev").unwrap(), first);
+
+            let second = vec![relationship("web-api", "web", "api")];
+            backend.save_relationships("dev", &second).unwrap();
+            assert_eq!(backend.load_relationships("dev").unwrap(), second);
+
+            backend.save_relationships("dev", &[]).unwrap();
+            assert!(backend.load_relationships("dev").unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn environments_are_kept_apart() {
+        on_both_backends(|backend| {
+            backend.create_environment("dev").unwrap();
+            backend.create_environment("prod").unwrap();
+            backend.save_service("dev", &service("api")).unwrap();
+            backend
+                .save_relationships("dev", &[relationship("api-db", "api", "db")])
+                .unwrap();
+
+            assert!(backend.load_services("prod").unwrap().is_empty());
+            assert!(backend.load_relationships("prod").unwrap().is_empty());
+            assert!(matches!(
+                backend.load_service("prod", "api"),
+                Err(AppError::ServiceNotFound(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn unsafe_names_are_rejected() {
+        on_both_backends(|backend| {
+            let is_rejected =
+                |result: Result<(), AppError>| matches!(result, Err(AppError::ValidationError(_)));
+            backend.create_environment("dev").unwrap();
+
+            assert!(is_rejected(backend.create_environment("../dev")));
+            assert!(is_rejected(backend.save_service("dev", &service("../api"))));
+            assert!(is_rejected(backend.save_service("", &service("api"))));
+            assert!(is_rejected(backend.delete_service_file("dev", "a/b")));
+            assert!(is_rejected(backend.load_service("dev", "..").map(drop)));
+            assert!(is_rejected(backend.save_relationships("../dev", &[])));
+            assert!(backend.load_services("dev").unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn writes_are_seen_as_modifications() {
+        on_both_backends(|backend| {
+            assert!(backend.last_modified("dev").is_none());
+            backend.create_environment("dev").unwrap();
+            let created = backend.last_modified("dev").unwrap();
+
+            backend.save_service("dev", &service("api")).unwrap();
+
+            assert!(backend.last_modified("dev").unwrap() >= created);
+        });
+    }
+
+    #[test]
+    fn sidecars_live_in_the_data_directory_or_next_to_the_database() {
+        let dir = Path::new("/data");
+        let db = Path::new("/data/map.db");
+
+        assert_eq!(sidecar_path(dir, ".views"), Path::new("/data/.views"));
+        assert_eq!(sidecar_path(db, ".views"), Path::new("/data/map.db.views"));
+        assert_eq!(
+            sidecar_path(db, ".validation.json"),
+            Path::new("/data/map.db.validation.json")
+        );
+
+        assert_eq!(
+            environment_sidecar_path(dir, "dev", "groups.json"),
+            Path::new("/data/dev/groups.json")
+        );
+        assert_eq!(
+            environment_sidecar_path(db, "dev", "groups.json"),
+            Path::new("/data/map.db.groups/dev.json")
+        );
+        assert_eq!(
+            environment_sidecar_path(db, "dev", ".audit.jsonl"),
+            Path::new("/data/map.db.audit/dev.jsonl")
+        );
+        assert_eq!(
+            environment_sidecar_path(dir, "dev", ".snapshots"),
+            Path::new("/data/dev/.snapshots")
+        );
+        assert_eq!(
+            environment_sidecar_path(db, "dev", ".snapshots"),
+            Path::new("/data/map.db.snapshots/dev")
+        );
+    }
+}
@@ -0,0 +1,281 @@
+//! Optional at-rest encryption for data files.
+//!
+//! When a passphrase is set, every file the loader writes is encrypted with
+//! AES-256-GCM. Files are recognized as encrypted by a small header, so a
+//! directory can hold a mix of encrypted and plaintext files and both load
+//! correctly.
+//!
+//! # Key Derivation
+//!
+//! The key is derived from the passphrase with Argon2id and a random salt
+//! stored in `{data_path}/.encryption.json`, together with a short key ID.
+//! The key itself is never written to disk; it only lives in `AppState`.
+//!
+//! # Encrypted File Layout
+//!
+//! ```text
+//! "DMTENC" | format version (1 byte) | key ID (8 bytes) | nonce (12 bytes) | ciphertext + tag
+//! ```
+//!
+//! Everything before the nonce is authenticated as associated data. The key
+//! ID lets a wrong passphrase be reported as such instead of as corruption.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::error::AppError;
+
+/// Name of the file in the data directory holding the salt and key ID.
+pub const KEY_FILE: &str = ".encryption.json";
+
+/// Marker at the start of every encrypted file.
+const MAGIC: &[u8] = b"DMTENC";
+
+/// Version of the encrypted file layout.
+const FORMAT_VERSION: u8 = 1;
+
+const KEY_LEN: usize = 32;
+const KEY_ID_LEN: usize = 8;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+/// Length of the authenticated header (magic, version, key ID).
+const AAD_LEN: usize = MAGIC.len() + 1 + KEY_ID_LEN;
+
+/// Length of the full header including the nonce.
+const HEADER_LEN: usize = AAD_LEN + NONCE_LEN;
+
+/// A key derived from the user's passphrase.
+///
+/// `Debug` output never includes key material.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey {
+    cipher_key: [u8; KEY_LEN],
+    key_id: [u8; KEY_ID_LEN],
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("key_id", &to_hex(&self.key_id))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Contents of `.encryption.json`.
+///
+/// # Fields
+///
+/// * `version` - Version of the key file format
+/// * `salt` - Hex-encoded Argon2 salt
+/// * `key_id` - Hex-encoded ID of the key derived from the correct passphrase
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyFile {
+    version: u32,
+    salt: String,
+    key_id: String,
+}
+
+impl EncryptionKey {
+    /// Derives a key from a passphrase and salt.
+    ///
+    /// Argon2 produces the cipher key followed by the key ID, so the ID
+    /// identifies the passphrase without revealing anything about the key.
+    fn derive(passphrase: &str, salt: &[u8]) -> Result<Self, AppError> {
+        let mut output = [0u8; KEY_LEN + KEY_ID_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut output)
+            .map_err(|e| AppError::ValidationError(format!("Key derivation failed: {}", e)))?;
+
+        let mut cipher_key = [0u8; KEY_LEN];
+        let mut key_id = [0u8; KEY_ID_LEN];
+        cipher_key.copy_from_slice(&output[..KEY_LEN]);
+        key_id.copy_from_slice(&output[KEY_LEN..]);
+
+        Ok(Self { cipher_key, key_id })
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.cipher_key))
+    }
+}
+
+/// Derives the encryption key for a data directory from a passphrase.
+///
+/// The first passphrase set for a data directory creates its key file; later
+/// passphrases are checked against it.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory
+/// * `passphrase` - The user's passphrase
+///
+/// # Returns
+///
+/// * `Ok(EncryptionKey)` - The derived key
+/// * `Err(AppError::ValidationError)` - If the passphrase is empty
+/// * `Err(AppError::WrongPassphrase)` - If the passphrase doesn't match the key file
+/// * `Err(AppError::FileLoad)` - If the key file exists but cannot be read
+/// * `Err(AppError::Io)` - If a new key file cannot be written
+///
+/// # Side Effects
+///
+/// - Creates `{data_path}/.encryption.json` if it doesn't exist
+pub fn unlock(data_path: &Path, passphrase: &str) -> Result<EncryptionKey, AppError> {
+    if passphrase.is_empty() {
+        return Err(AppError::ValidationError(
+            "Passphrase cannot be empty".to_string(),
+        ));
+    }
+
+    let key_path = data_path.join(KEY_FILE);
+
+    if !key_path.exists() {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+
+        let key = EncryptionKey::derive(passphrase, &salt)?;
+        let key_file = KeyFile {
+            version: 1,
+            salt: to_hex(&salt),
+            key_id: to_hex(&key.key_id),
+        };
+        fs::write(&key_path, serde_json::to_string_pretty(&key_file)?)?;
+
+        return Ok(key);
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: key_path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(&key_path).map_err(|e| load_error(e.to_string()))?;
+    let key_file: KeyFile =
+        serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))?;
+    let salt = from_hex(&key_file.salt).ok_or_else(|| load_error("invalid salt".to_string()))?;
+
+    let key = EncryptionKey::derive(passphrase, &salt)?;
+    if to_hex(&key.key_id) != key_file.key_id {
+        return Err(AppError::WrongPassphrase(data_path.display().to_string()));
+    }
+
+    Ok(key)
+}
+
+/// Returns true if file contents start with the encrypted file header.
+pub fn is_encrypted(contents: &[u8]) -> bool {
+    contents.starts_with(MAGIC)
+}
+
+/// Encrypts file contents with a fresh random nonce.
+///
+/// # Arguments
+///
+/// * `key` - The key to encrypt with
+/// * `plaintext` - The plaintext file contents
+///
+/// # Returns
+///
+/// The header followed by the ciphertext, ready to be written to disk.
+pub fn encrypt(key: &EncryptionKey, plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut output = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    output.extend_from_slice(MAGIC);
+    output.push(FORMAT_VERSION);
+    output.extend_from_slice(&key.key_id);
+
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = key
+        .cipher()
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &output[..AAD_LEN],
+            },
+        )
+        .map_err(|_| AppError::ValidationError("Encryption failed".to_string()))?;
+
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+
+    Ok(output)
+}
+
+/// Returns plaintext file contents, decrypting them if they are encrypted.
+///
+/// Plaintext contents are returned unchanged, which is what lets mixed
+/// directories load.
+///
+/// # Arguments
+///
+/// * `path` - Path the contents were read from (for error messages)
+/// * `contents` - The raw file contents
+/// * `key` - The current key, if a passphrase has been set
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - The plaintext contents
+/// * `Err(AppError::EncryptionRequired)` - If the file is encrypted and no key is set
+/// * `Err(AppError::WrongPassphrase)` - If the file was encrypted with a different passphrase
+/// * `Err(AppError::FileLoad)` - If the encrypted data is truncated, corrupt, or tampered with
+pub fn decrypt(
+    path: &Path,
+    contents: Vec<u8>,
+    key: Option<&EncryptionKey>,
+) -> Result<Vec<u8>, AppError> {
+    if !is_encrypted(&contents) {
+        return Ok(contents);
+    }
+
+    let key = key.ok_or_else(|| AppError::EncryptionRequired(path.display().to_string()))?;
+
+    let corrupt = |reason: &str| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason: reason.to_string(),
+    };
+
+    if contents.len() < HEADER_LEN {
+        return Err(corrupt("encrypted file header is truncated"));
+    }
+    if contents[MAGIC.len()] != FORMAT_VERSION {
+        return Err(corrupt("unsupported encrypted file format"));
+    }
+    if contents[MAGIC.len() + 1..AAD_LEN] != key.key_id {
+        return Err(AppError::WrongPassphrase(path.display().to_string()));
+    }
+
+    let nonce = Nonce::from_slice(&contents[AAD_LEN..HEADER_LEN]);
+    key.cipher()
+        .decrypt(
+            nonce,
+            Payload {
+                msg: &contents[HEADER_LEN..],
+                aad: &contents[..AAD_LEN],
+            },
+        )
+        .map_err(|_| corrupt("encrypted data is corrupt or has been modified"))
+}
+
+/// Encodes bytes as lowercase hexadecimal.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
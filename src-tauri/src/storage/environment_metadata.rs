@@ -0,0 +1,149 @@
+//! Persistence for environment metadata.
+//!
+//! Each environment keeps its storage options and import history (see
+//! `models::EnvironmentMetadata`) in a pretty-printed `environment.json`
+//! next to its data. The file is optional and never encrypted: it only says
+//! how the rest of the environment is laid out and when it was last synced.
+//! SQLite databases have no environment directories, so their environments
+//! always use the defaults and record no imports.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::models::{EnvironmentMetadata, ImportCounts, RelationshipLayout};
+use crate::storage::backend::is_sqlite_path;
+use crate::storage::ids::validate_environment_name;
+
+/// File name of an environment's metadata file.
+pub const ENVIRONMENT_METADATA_FILE: &str = "environment.json";
+
+/// Returns the path of an environment's metadata file.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/environment.json`
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+pub fn environment_metadata_path(data_path: &Path, environment: &str) -> Result<PathBuf, AppError> {
+    validate_environment_name(environment)?;
+
+    Ok(data_path.join(environment).join(ENVIRONMENT_METADATA_FILE))
+}
+
+/// Loads an environment's metadata.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(EnvironmentMetadata)` - The metadata (the defaults if none was saved)
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or parsed
+pub fn load_environment_metadata(
+    data_path: &Path,
+    environment: &str,
+) -> Result<EnvironmentMetadata, AppError> {
+    let path = environment_metadata_path(data_path, environment)?;
+    if !path.is_file() {
+        return Ok(EnvironmentMetadata::default());
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(&path).map_err(|e| load_error(e.to_string()))?;
+    serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))
+}
+
+/// Saves an environment's metadata, replacing the stored file.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment
+/// * `metadata` - The metadata to store
+///
+/// # Returns
+///
+/// * `Ok(())` - If the metadata was written
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::Io)` - If the file cannot be written
+pub fn save_environment_metadata(
+    data_path: &Path,
+    environment: &str,
+    metadata: &EnvironmentMetadata,
+) -> Result<(), AppError> {
+    let path = environment_metadata_path(data_path, environment)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let content = serde_json::to_string_pretty(metadata)?;
+    fs::write(path, content)?;
+
+    Ok(())
+}
+
+/// Returns how an environment stores its relationships.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(RelationshipLayout)` - The layout from the metadata file, `Single` without one
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::FileLoad)` - If the metadata file cannot be read or parsed
+pub fn relationship_layout(
+    data_path: &Path,
+    environment: &str,
+) -> Result<RelationshipLayout, AppError> {
+    Ok(load_environment_metadata(data_path, environment)?.relationship_layout)
+}
+
+/// Records that an import source has just synced an environment.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path, or a SQLite database file
+/// * `environment` - The name of the environment
+/// * `source` - The import source, e.g. `"kubernetes"`
+/// * `imported_at` - When the import ran, in milliseconds since the Unix epoch
+/// * `counts` - How many services and relationships the source held
+///
+/// # Returns
+///
+/// * `Ok(())` - If the import was written, or the data path is a SQLite database
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::FileLoad)` - If the metadata file cannot be read or parsed
+/// * `Err(AppError::Io)` - If the metadata file cannot be written
+pub fn record_import(
+    data_path: &Path,
+    environment: &str,
+    source: &str,
+    imported_at: u64,
+    counts: ImportCounts,
+) -> Result<(), AppError> {
+    if is_sqlite_path(data_path) {
+        return Ok(());
+    }
+
+    let mut metadata = load_environment_metadata(data_path, environment)?;
+    metadata
+        .last_imports
+        .insert(source.to_string(), imported_at);
+    metadata.import_counts.insert(source.to_string(), counts);
+    save_environment_metadata(data_path, environment, &metadata)
+}
@@ -0,0 +1,93 @@
+//! Persistence for service groups.
+//!
+//! Each environment keeps its groups (see `models::Group`) in a
+//! pretty-printed `groups.json` next to its `relationships.json`. For SQLite
+//! databases they are kept in a `{database}.groups/` directory next to the
+//! database file, one `{environment}.json` per environment.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::models::Group;
+use crate::storage::backend::{environment_sidecar_path, is_sqlite_path};
+use crate::storage::ids::validate_environment_name;
+
+/// File name of an environment's groups file.
+pub const GROUPS_FILE: &str = "groups.json";
+
+/// Returns the path of an environment's groups file.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/groups.json`, or
+///   `{database}.groups/{environment}.json` for SQLite databases
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+pub fn groups_path(data_path: &Path, environment: &str) -> Result<PathBuf, AppError> {
+    validate_environment_name(environment)?;
+
+    Ok(environment_sidecar_path(
+        data_path,
+        environment,
+        GROUPS_FILE,
+    ))
+}
+
+/// Loads an environment's groups.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(Vec<Group>)` - The groups (empty if none were saved)
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or parsed
+pub fn load_groups(data_path: &Path, environment: &str) -> Result<Vec<Group>, AppError> {
+    let path = groups_path(data_path, environment)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(&path).map_err(|e| load_error(e.to_string()))?;
+    serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))
+}
+
+/// Saves an environment's groups, replacing the stored list.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+/// * `groups` - The complete list of groups
+///
+/// # Returns
+///
+/// * `Ok(())` - If the groups were written
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::Io)` - If the file cannot be written
+pub fn save_groups(data_path: &Path, environment: &str, groups: &[Group]) -> Result<(), AppError> {
+    let path = groups_path(data_path, environment)?;
+    if is_sqlite_path(data_path) {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+
+    let content = serde_json::to_string_pretty(groups)?;
+    fs::write(path, content)?;
+
+    Ok(())
+}
@@ -0,0 +1,374 @@
+//! Validation of identifiers that are used as path segments.
+//!
+//! Service IDs become filenames (`{service_id}.json`), environment names
+//! become directory names and service types, view names and snapshot IDs
+//! name metadata schema, saved view and snapshot files (`{type}.json`,
+//! `{name}.json`, `{id}.json`), so all are joined into filesystem paths.
+//! This module rejects values that could escape the data directory or
+//! produce unusable filenames, and generates IDs that are always safe:
+//! slugs of names for services and random UUIDs for relationships.
+
+use std::path::{Component, Path};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// Longest slug `slugify` returns, in bytes.
+pub const MAX_SLUG_LENGTH: usize = 64;
+
+/// Turns a name into a lowercase, dash-separated ID.
+///
+/// Latin letters with accents are folded to plain ASCII (`é` to `e`, `ß` to
+/// `ss`), other ASCII letters and digits are lowercased, and every run of
+/// anything else becomes one dash. Leading and trailing dashes are trimmed
+/// and the slug is cut to `max_length` bytes, so it is either empty (a name
+/// of only emoji or punctuation) or a valid service ID, see
+/// `validate_service_id`.
+///
+/// # Examples
+///
+/// `"Zürich Payments API (v2)"` becomes `"zurich-payments-api-v2"`.
+pub fn slugify(name: &str, max_length: usize) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if let Some(letters) = fold_to_ascii(c) {
+            slug.push_str(letters);
+        } else if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= max_length {
+            slug.truncate(max_length);
+            break;
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Returns a new random relationship ID, a version 4 UUID
+/// (e.g., `"3f2b8c1e-9d4a-4e6f-b1c2-7a8d9e0f1a2b"`).
+pub fn new_relationship_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Returns the lowercase ASCII letters a Latin letter with a diacritic (or a
+/// ligature) is written as, for `slugify`.
+fn fold_to_ascii(c: char) -> Option<&'static str> {
+    let letters = match c {
+        'À'..='Å' | 'à'..='å' | 'Ā'..='ą' => "a",
+        'Æ' | 'æ' => "ae",
+        'Ç' | 'ç' | 'Ć'..='č' => "c",
+        'Ð' | 'ð' | 'Ď'..='đ' => "d",
+        'È'..='Ë' | 'è'..='ë' | 'Ē'..='ě' => "e",
+        'Ĝ'..='ģ' => "g",
+        'Ĥ'..='ħ' => "h",
+        'Ì'..='Ï' | 'ì'..='ï' | 'Ĩ'..='ı' => "i",
+        'Ĳ' | 'ĳ' => "ij",
+        'Ĵ' | 'ĵ' => "j",
+        'Ķ'..='ĸ' => "k",
+        'Ĺ'..='ł' => "l",
+        'Ñ' | 'ñ' | 'Ń'..='ŋ' => "n",
+        'Ò'..='Ö' | 'Ø' | 'ò'..='ö' | 'ø' | 'Ō'..='ő' => "o",
+        'Œ' | 'œ' => "oe",
+        'Ŕ'..='ř' => "r",
+        'ß' => "ss",
+        'Ś'..='š' | 'ſ' => "s",
+        'Ţ'..='ŧ' => "t",
+        'Þ' | 'þ' => "th",
+        'Ù'..='Ü' | 'ù'..='ü' | 'Ũ'..='ų' => "u",
+        'Ŵ' | 'ŵ' => "w",
+        'Ý' | 'ý' | 'ÿ' | 'Ŷ'..='Ÿ' => "y",
+        'Ź'..='ž' => "z",
+        _ => return None,
+    };
+    Some(letters)
+}
+
+/// Validates a service ID before it is used as a filename.
+///
+/// # Arguments
+///
+/// * `service_id` - The service ID to validate
+///
+/// # Returns
+///
+/// * `Ok(())` - If the ID is safe to use as a filename
+/// * `Err(AppError::ValidationError)` - If the ID is unsafe, naming the offending ID
+pub fn validate_service_id(service_id: &str) -> Result<(), AppError> {
+    validate_path_segment(service_id).map_err(|reason| {
+        AppError::ValidationError(format!("Invalid service ID '{}': {}", service_id, reason))
+    })
+}
+
+/// Validates an environment name before it is used as a directory name.
+///
+/// # Arguments
+///
+/// * `environment` - The environment name to validate
+///
+/// # Returns
+///
+/// * `Ok(())` - If the name is safe to use as a directory name
+/// * `Err(AppError::ValidationError)` - If the name is unsafe, naming the offending value
+pub fn validate_environment_name(environment: &str) -> Result<(), AppError> {
+    validate_path_segment(environment).map_err(|reason| {
+        AppError::ValidationError(format!(
+            "Invalid environment name '{}': {}",
+            environment, reason
+        ))
+    })
+}
+
+/// Validates a service type before it is used as a metadata schema filename.
+///
+/// # Arguments
+///
+/// * `service_type` - The service type as serialized (e.g., "database")
+///
+/// # Returns
+///
+/// * `Ok(())` - If the type is safe to use as a filename
+/// * `Err(AppError::ValidationError)` - If the type is unsafe, naming the offending value
+pub fn validate_service_type_name(service_type: &str) -> Result<(), AppError> {
+    validate_path_segment(service_type).map_err(|reason| {
+        AppError::ValidationError(format!(
+            "Invalid service type '{}': {}",
+            service_type, reason
+        ))
+    })
+}
+
+/// Validates a saved view name before it is used as a filename.
+///
+/// # Arguments
+///
+/// * `name` - The view name to validate
+///
+/// # Returns
+///
+/// * `Ok(())` - If the name is safe to use as a filename
+/// * `Err(AppError::ValidationError)` - If the name is unsafe, naming the offending value
+pub fn validate_view_name(name: &str) -> Result<(), AppError> {
+    validate_path_segment(name).map_err(|reason| {
+        AppError::ValidationError(format!("Invalid view name '{}': {}", name, reason))
+    })
+}
+
+/// Validates a snapshot ID before it is used as a filename.
+///
+/// # Arguments
+///
+/// * `snapshot_id` - The snapshot ID to validate
+///
+/// # Returns
+///
+/// * `Ok(())` - If the ID is safe to use as a filename
+/// * `Err(AppError::ValidationError)` - If the ID is unsafe, naming the offending value
+pub fn validate_snapshot_id(snapshot_id: &str) -> Result<(), AppError> {
+    validate_path_segment(snapshot_id).map_err(|reason| {
+        AppError::ValidationError(format!("Invalid snapshot ID '{}': {}", snapshot_id, reason))
+    })
+}
+
+/// Checks that a value is a single, plain path segment.
+///
+/// Rejects values that are empty (after trimming), contain path separators
+/// or control characters, start with a dot (which also covers `..` and
+/// hidden files), or that the platform would interpret as anything other
+/// than one normal path component (e.g., Windows drive prefixes).
+///
+/// # Returns
+///
+/// `Ok(())` if the value is safe, otherwise a short reason for the rejection.
+fn validate_path_segment(value: &str) -> Result<(), &'static str> {
+    if value.trim().is_empty() {
+        return Err("must not be empty");
+    }
+    if value.contains('/') || value.contains('\\') {
+        return Err("must not contain path separators");
+    }
+    if value.starts_with('.') {
+        return Err("must not start with a dot");
+    }
+    if value.chars().any(|c| c.is_control()) {
+        return Err("must not contain control characters");
+    }
+
+    let mut components = Path::new(value).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(segment)), None) if segment == value => Ok(()),
+        _ => Err("must be a plain file name"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejects(value: &str) -> bool {
+        validate_service_id(value).is_err() && validate_environment_name(value).is_err()
+    }
+
+    #[test]
+    fn dot_segments_are_rejected() {
+        for value in ["..", ".", "...", ".hidden", "..json"] {
+            assert!(rejects(value), "{:?} was accepted", value);
+        }
+    }
+
+    #[test]
+    fn path_separators_are_rejected() {
+        for value in [
+            "../../prod/services/api-gateway",
+            "services/api",
+            "api/",
+            "..\\..\\prod",
+            "a\\b",
+        ] {
+            assert!(rejects(value), "{:?} was accepted", value);
+        }
+    }
+
+    #[test]
+    fn absolute_paths_are_rejected() {
+        for value in ["/etc/passwd", "/", "\\\\server\\share", "C:\\Windows"] {
+            assert!(rejects(value), "{:?} was accepted", value);
+        }
+    }
+
+    #[test]
+    fn empty_values_and_control_characters_are_rejected() {
+        for value in ["", " ", "\t\n", "api\0", "api\nprod", "\u{1b}[31m"] {
+            assert!(rejects(value), "{:?} was accepted", value);
+        }
+    }
+
+    #[test]
+    fn the_error_names_the_offending_value() {
+        let error = validate_service_id("../x").unwrap_err();
+        assert!(
+            matches!(&error, AppError::ValidationError(message) if message.contains("'../x'")),
+            "{:?}",
+            error
+        );
+    }
+
+    #[test]
+    fn plain_names_are_accepted() {
+        for value in ["api-gateway", "prod", "user_service.v2", "Zürich", "a..b"] {
+            assert!(
+                validate_service_id(value).is_ok(),
+                "{:?} was rejected",
+                value
+            );
+            assert!(validate_environment_name(value).is_ok());
+        }
+    }
+
+    #[test]
+    fn generated_ids_are_valid() {
+        assert_eq!(slugify("../../etc/passwd", MAX_SLUG_LENGTH), "etc-passwd");
+        assert!(validate_service_id(&slugify(".env", MAX_SLUG_LENGTH)).is_ok());
+
+        let id = new_relationship_id();
+        assert!(validate_service_id(&id).is_ok());
+        let uuid = Uuid::parse_str(&id).unwrap();
+        assert_eq!(uuid.get_version_num(), 4);
+        assert_ne!(new_relationship_id(), id);
+    }
+
+    /// Characters slugs are built from in the generated names: ASCII, folded
+    /// Latin letters, other scripts, emoji, combining marks, separators,
+    /// dots, whitespace and control characters.
+    const ALPHABET: &[char] = &[
+        'a', 'Z', '0', '9', '-', '_', ' ', '.', '/', '\\', ':', '*', '?', '"', '<', '>', '|', '\0',
+        '\n', '\t', '\u{7f}', 'é', 'Ø', 'ß', 'Œ', 'ł', 'ĳ', '東', '京', 'и', 'λ', '🙂', '🚀', '👍',
+        '\u{301}', '\u{200d}', '\u{fe0f}', '\u{202e}', 'ﬀ', '①',
+    ];
+
+    /// Generates names of up to 200 characters from a fixed seed, so every
+    /// run checks the same inputs.
+    fn generated_names(count: usize) -> Vec<String> {
+        let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        (0..count)
+            .map(|_| {
+                let length = (next() % 200) as usize;
+                (0..length)
+                    .map(|_| ALPHABET[(next() % ALPHABET.len() as u64) as usize])
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn assert_valid_slug(name: &str, slug: &str, max_length: usize) {
+        assert!(
+            slug.len() <= max_length,
+            "{:?} -> {:?} is too long",
+            name,
+            slug
+        );
+        assert!(
+            slug.bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-'),
+            "{:?} -> {:?} has other characters",
+            name,
+            slug
+        );
+        assert!(
+            !slug.starts_with('-') && !slug.ends_with('-'),
+            "{:?} -> {:?}",
+            name,
+            slug
+        );
+        assert!(!slug.contains("--"), "{:?} -> {:?}", name, slug);
+        if !slug.is_empty() {
+            assert!(
+                validate_service_id(slug).is_ok(),
+                "{:?} -> {:?}",
+                name,
+                slug
+            );
+        }
+    }
+
+    #[test]
+    fn slugs_of_generated_names_are_empty_or_valid_ids() {
+        for name in generated_names(2000) {
+            for max_length in [1, 8, MAX_SLUG_LENGTH] {
+                let slug = slugify(&name, max_length);
+                assert_valid_slug(&name, &slug, max_length);
+                assert_eq!(slugify(&slug, max_length), slug, "slugify isn't idempotent");
+            }
+        }
+    }
+
+    #[test]
+    fn weird_names_slug_as_expected() {
+        for (name, expected) in [
+            ("Zürich Payments API (v2)", "zurich-payments-api-v2"),
+            ("🙂🚀👍", ""),
+            ("...", ""),
+            ("   ", ""),
+            ("東京 gateway", "gateway"),
+            ("Straße", "strasse"),
+            ("--api--", "api"),
+            ("café\u{301}", "cafe"),
+            ("a\0b", "a-b"),
+        ] {
+            assert_eq!(slugify(name, MAX_SLUG_LENGTH), expected, "{:?}", name);
+        }
+
+        let long = "Payments ".repeat(40);
+        let slug = slugify(&long, MAX_SLUG_LENGTH);
+        assert!(slug.len() <= MAX_SLUG_LENGTH);
+        assert!(slug.starts_with("payments-payments") && !slug.ends_with('-'));
+    }
+}
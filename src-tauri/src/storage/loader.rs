@@ -13,14 +13,51 @@
 //! │   │   ├── service-1.json
 //! │   │   ├── service-2.json
 //! │   │   └── ...
-//! │   └── relationships.json
+//! │   ├── relationships.json
+//! │   └── .audit.jsonl
 //! ```
+//!
+//! Only `.json` files directly inside `services/` are loaded as services, so
+//! the audit log (see the `audit` module) is never mistaken for data.
+//!
+//! # Relationship Layouts
+//!
+//! Relationships are stored in `relationships.json` unless the environment's
+//! metadata (see the `environment_metadata` module) selects the sharded
+//! layout, where each source service's relationships live in
+//! `relationships/{source_service_id}.json`. Both layouts load into and save
+//! from the same combined list; with shards, a save only rewrites the shards
+//! whose relationships changed, so editing one edge of a large environment
+//! doesn't rewrite every edge.
+//!
+//! # Schema Versions
+//!
+//! Every file is written with a `schemaVersion` field and upgraded in memory
+//! on load by the `migrations` module, so files written by older versions of
+//! the application keep loading without being rewritten.
+//!
+//! # Encryption
+//!
+//! Functions that read or write file contents take an optional
+//! `EncryptionKey`. With a key, files are written encrypted; encrypted and
+//! plaintext files are both read transparently (see the `encryption` module).
 
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::error::AppError;
-use crate::models::{Relationship, RelationshipsFile, Service};
+use crate::models::{Relationship, RelationshipLayout, RelationshipsFile, Service};
+use crate::storage::encryption::{self, EncryptionKey};
+use crate::storage::environment_metadata::{self, relationship_layout};
+use crate::storage::ids::{validate_environment_name, validate_service_id};
+use crate::storage::migrations::{
+    self, DocumentKind, FileMigration, MigratedDocument, Versioned, CURRENT_SCHEMA_VERSION,
+};
 
 /// Loads all services from an environment's services directory.
 ///
@@ -32,12 +69,16 @@ use crate::models::{Relationship, RelationshipsFile, Service};
 ///
 /// * `data_path` - The root data directory path
 /// * `environment` - The name of the environment to load services from
+/// * `key` - The encryption key, if a passphrase has been set
 ///
 /// # Returns
 ///
 /// * `Ok(Vec<Service>)` - All services in the environment (empty if directory doesn't exist)
-/// * `Err(AppError::Io)` - If there's an error reading files
-/// * `Err(AppError::Json)` - If a JSON file cannot be parsed
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::Io)` - If the services directory cannot be listed
+/// * `Err(AppError::FileLoad)` - If a service file cannot be read or parsed, naming the file
+/// * `Err(AppError::EncryptionRequired)` - If a service file is encrypted and no key is given
+/// * `Err(AppError::WrongPassphrase)` - If a service file was encrypted with a different key
 ///
 /// # File Format
 ///
@@ -52,27 +93,400 @@ use crate::models::{Relationship, RelationshipsFile, Service};
 ///   "tags": ["tag1", "tag2"]
 /// }
 /// ```
-pub fn load_services(data_path: &Path, environment: &str) -> Result<Vec<Service>, AppError> {
+pub fn load_services(
+    data_path: &Path,
+    environment: &str,
+    key: Option<&EncryptionKey>,
+) -> Result<Vec<Service>, AppError> {
+    let service_files = load_service_files(data_path, environment, key)?;
+
+    Ok(service_files
+        .into_iter()
+        .map(|(_, service)| service)
+        .collect())
+}
+
+/// Loads all services along with the path of the file each one was read from.
+///
+/// Behaves exactly like `load_services` but keeps the source path, which is
+/// needed to detect files whose name doesn't match the ID they contain.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment to load services from
+/// * `key` - The encryption key, if a passphrase has been set
+///
+/// # Returns
+///
+/// * `Ok(Vec<(PathBuf, Service)>)` - Each service paired with its file path (in no particular order)
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::Io)` - If the services directory cannot be listed
+/// * `Err(AppError::FileLoad)` - If a service file cannot be read or parsed, naming the
+///   first such file by path and counting the others
+/// * `Err(AppError::EncryptionRequired)` - If a service file is encrypted and no key is given
+/// * `Err(AppError::WrongPassphrase)` - If a service file was encrypted with a different key
+///
+/// # Performance
+///
+/// Files are read and parsed in parallel on the rayon thread pool, which
+/// matters for environments with thousands of service files.
+pub fn load_service_files(
+    data_path: &Path,
+    environment: &str,
+    key: Option<&EncryptionKey>,
+) -> Result<Vec<(PathBuf, Service)>, AppError> {
+    validate_environment_name(environment)?;
+
     let services_dir = data_path.join(environment).join("services");
 
     if !services_dir.exists() {
         return Ok(Vec::new());
     }
 
-    let mut services = Vec::new();
-
+    let mut paths = Vec::new();
     for entry in fs::read_dir(&services_dir)? {
-        let entry = entry?;
-        let path = entry.path();
+        let path = entry?.path();
+        if is_json_file(&path) {
+            paths.push(path);
+        }
+    }
+
+    // Read and parse in parallel; each path is handled by exactly one worker.
+    // Every file is read, so the failures can be reported together.
+    let results: Vec<(PathBuf, Result<Service, AppError>)> = paths
+        .into_par_iter()
+        .map(|path| {
+            let service = read_service_file(&path, key);
+            (path, service)
+        })
+        .collect();
+
+    let mut services = Vec::with_capacity(results.len());
+    let mut failures = Vec::new();
+    for (path, result) in results {
+        match result {
+            Ok(service) => services.push((path, service)),
+            Err(e) => failures.push((path, e)),
+        }
+    }
+
+    match first_failure(failures) {
+        Some(e) => Err(e),
+        None => Ok(services),
+    }
+}
+
+/// Returns the error of the first failed file by path, or `None` if none failed.
+///
+/// Files are read in parallel and all of them are read; picking by path
+/// keeps the error the same from run to run whatever order they were read
+/// in. A `FileLoad` error also says how many other files failed.
+fn first_failure(mut failures: Vec<(PathBuf, AppError)>) -> Option<AppError> {
+    failures.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let others = failures.len().checked_sub(1)?;
+    let (_, error) = failures.into_iter().next()?;
+
+    Some(match error {
+        AppError::FileLoad { path, reason } if others > 0 => {
+            let files = if others == 1 { "file" } else { "files" };
+            AppError::FileLoad {
+                path,
+                reason: format!("{} ({} other {} failed to load too)", reason, others, files),
+            }
+        }
+        error => error,
+    })
+}
+
+/// Reads and parses a single service file.
+///
+/// Errors are reported with the offending file path so a single corrupt
+/// file in a large environment is easy to find.
+///
+/// # Arguments
+///
+/// * `path` - Path of the service JSON file
+/// * `key` - The encryption key, if a passphrase has been set
+///
+/// # Returns
+///
+/// * `Ok(Service)` - The parsed service
+/// * `Err(AppError::FileLoad)` - If the file cannot be read or parsed
+fn read_service_file(path: &Path, key: Option<&EncryptionKey>) -> Result<Service, AppError> {
+    read_document(path, DocumentKind::Service, key)
+}
+
+/// Reads a stored document, migrates it to the current schema, and deserializes it.
+///
+/// # Arguments
+///
+/// * `path` - Path of the JSON file
+/// * `kind` - What kind of document the file holds
+/// * `key` - The encryption key, if a passphrase has been set
+///
+/// # Returns
+///
+/// * `Ok(T)` - The typed document
+/// * `Err(AppError::FileLoad)` - If the file cannot be read, migrated, or parsed
+fn read_document<T: DeserializeOwned>(
+    path: &Path,
+    kind: DocumentKind,
+    key: Option<&EncryptionKey>,
+) -> Result<T, AppError> {
+    let migrated = read_migrated_document(path, kind, key)?;
+    parse_document(path, migrated.document)
+}
+
+/// Reads a stored document and migrates it to the current schema without
+/// deserializing it into a typed model.
+fn read_migrated_document(
+    path: &Path,
+    kind: DocumentKind,
+    key: Option<&EncryptionKey>,
+) -> Result<MigratedDocument, AppError> {
+    let raw = read_raw_document(path, key)?;
+
+    migrations::migrate(kind, raw).map_err(|e| file_load_error(path, e))
+}
+
+/// Reads a stored document as untyped JSON, exactly as it is on disk
+/// (after decryption).
+fn read_raw_document(path: &Path, key: Option<&EncryptionKey>) -> Result<Value, AppError> {
+    let contents = fs::read(path).map_err(|e| file_load_error(path, e))?;
+    let plaintext = encryption::decrypt(path, contents, key)?;
+
+    serde_json::from_slice(&plaintext).map_err(|e| file_load_error(path, e))
+}
+
+/// Writes serialized JSON to a file, encrypting it when a key is given.
+fn write_document(
+    path: &Path,
+    content: String,
+    key: Option<&EncryptionKey>,
+) -> Result<(), AppError> {
+    let contents = match key {
+        Some(key) => encryption::encrypt(key, content.as_bytes())?,
+        None => content.into_bytes(),
+    };
+
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+/// Deserializes a migrated document into its typed model.
+fn parse_document<T: DeserializeOwned>(path: &Path, document: Value) -> Result<T, AppError> {
+    serde_json::from_value(document).map_err(|e| file_load_error(path, e))
+}
+
+/// Builds a `FileLoad` error for a specific file.
+fn file_load_error(path: &Path, reason: impl ToString) -> AppError {
+    AppError::FileLoad {
+        path: path.display().to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Returns true if the path has a `.json` extension.
+fn is_json_file(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("json")
+}
+
+/// Returns the path of the file a service with the given ID is stored in.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment containing the service
+/// * `service_id` - The unique identifier of the service
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/services/{service_id}.json`
+/// * `Err(AppError::ValidationError)` - If the environment name or service ID is not path-safe
+pub fn service_file_path(
+    data_path: &Path,
+    environment: &str,
+    service_id: &str,
+) -> Result<PathBuf, AppError> {
+    validate_environment_name(environment)?;
+    validate_service_id(service_id)?;
+
+    Ok(data_path
+        .join(environment)
+        .join("services")
+        .join(format!("{}.json", service_id)))
+}
+
+/// Builds the path of an environment's relationships file.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/relationships.json`
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+pub fn relationships_file_path(data_path: &Path, environment: &str) -> Result<PathBuf, AppError> {
+    validate_environment_name(environment)?;
+
+    Ok(data_path.join(environment).join("relationships.json"))
+}
+
+/// Builds the path of the directory holding an environment's relationship shards.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/relationships`
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+pub fn relationship_shards_dir(data_path: &Path, environment: &str) -> Result<PathBuf, AppError> {
+    validate_environment_name(environment)?;
+
+    Ok(data_path.join(environment).join("relationships"))
+}
+
+/// Builds the path of the shard holding one source service's relationships.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment
+/// * `source_id` - The ID of the relationships' source service
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/relationships/{source_id}.json`
+/// * `Err(AppError::ValidationError)` - If the environment name or source ID is not path-safe
+pub fn relationship_shard_path(
+    data_path: &Path,
+    environment: &str,
+    source_id: &str,
+) -> Result<PathBuf, AppError> {
+    validate_service_id(source_id)?;
+
+    Ok(relationship_shards_dir(data_path, environment)?.join(format!("{}.json", source_id)))
+}
+
+/// Returns the files that store the relationships of the given source services.
+///
+/// With the single-file layout this is `relationships.json` whatever the
+/// sources; with the sharded layout it is the shard of each source. Used to
+/// tell git auto-commits which files a relationship change touched.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment
+/// * `sources` - The source service IDs of the changed relationships
+///
+/// # Returns
+///
+/// The file paths, without the sources or environment names that aren't path-safe
+pub fn relationship_file_paths<'a>(
+    data_path: &Path,
+    environment: &str,
+    sources: impl IntoIterator<Item = &'a str>,
+) -> Vec<PathBuf> {
+    match relationship_layout(data_path, environment).unwrap_or_default() {
+        RelationshipLayout::Single => relationships_file_path(data_path, environment)
+            .into_iter()
+            .collect(),
+        RelationshipLayout::Sharded => sources
+            .into_iter()
+            .filter_map(|source| relationship_shard_path(data_path, environment, source).ok())
+            .collect(),
+    }
+}
+
+/// Returns the source services whose relationships differ between two lists.
+///
+/// # Arguments
+///
+/// * `before` - The relationships before a change
+/// * `after` - The relationships after it
+///
+/// # Returns
+///
+/// The IDs of the sources with an added, removed or modified relationship
+pub fn changed_relationship_sources<'a>(
+    before: &'a [Relationship],
+    after: &'a [Relationship],
+) -> BTreeSet<&'a str> {
+    let before = group_by_source(before);
+    let after = group_by_source(after);
+
+    before
+        .keys()
+        .chain(after.keys())
+        .filter(|source| before.get(*source) != after.get(*source))
+        .copied()
+        .collect()
+}
+
+/// Groups relationships by their source service, keeping their order.
+fn group_by_source(relationships: &[Relationship]) -> BTreeMap<&str, Vec<&Relationship>> {
+    let mut groups: BTreeMap<&str, Vec<&Relationship>> = BTreeMap::new();
+    for relationship in relationships {
+        groups
+            .entry(relationship.source.as_str())
+            .or_default()
+            .push(relationship);
+    }
+    groups
+}
 
-        if path.extension().and_then(|s| s.to_str()) == Some("json") {
-            let content = fs::read_to_string(&path)?;
-            let service: Service = serde_json::from_str(&content)?;
-            services.push(service);
+/// Returns the existing files an environment's relationships are stored in.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` - `relationships.json` if it exists, or every shard
+///   sorted by file name with the sharded layout
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::FileLoad)` - If the environment metadata cannot be read
+/// * `Err(AppError::Io)` - If the shards directory cannot be listed
+pub fn relationship_files(data_path: &Path, environment: &str) -> Result<Vec<PathBuf>, AppError> {
+    match relationship_layout(data_path, environment)? {
+        RelationshipLayout::Single => {
+            let rel_path = relationships_file_path(data_path, environment)?;
+            Ok(rel_path.exists().then_some(rel_path).into_iter().collect())
+        }
+        RelationshipLayout::Sharded => {
+            json_files_in(&relationship_shards_dir(data_path, environment)?)
         }
     }
+}
 
-    Ok(services)
+/// Lists the `.json` files directly inside a directory, sorted by name.
+///
+/// Returns an empty list if the directory doesn't exist.
+fn json_files_in(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if is_json_file(&path) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
 }
 
 /// Loads a single service by its unique identifier.
@@ -85,13 +499,14 @@ pub fn load_services(data_path: &Path, environment: &str) -> Result<Vec<Service>
 /// * `data_path` - The root data directory path
 /// * `environment` - The name of the environment containing the service
 /// * `service_id` - The unique identifier of the service (matches filename without .json)
+/// * `key` - The encryption key, if a passphrase has been set
 ///
 /// # Returns
 ///
 /// * `Ok(Service)` - The requested service
+/// * `Err(AppError::ValidationError)` - If the environment name or service ID is not path-safe
 /// * `Err(AppError::ServiceNotFound)` - If the service file doesn't exist
-/// * `Err(AppError::Io)` - If there's an error reading the file
-/// * `Err(AppError::Json)` - If the JSON file cannot be parsed
+/// * `Err(AppError::FileLoad)` - If the file cannot be read or parsed
 ///
 /// # File Path
 ///
@@ -100,20 +515,15 @@ pub fn load_service(
     data_path: &Path,
     environment: &str,
     service_id: &str,
+    key: Option<&EncryptionKey>,
 ) -> Result<Service, AppError> {
-    let service_path = data_path
-        .join(environment)
-        .join("services")
-        .join(format!("{}.json", service_id));
+    let service_path = service_file_path(data_path, environment, service_id)?;
 
     if !service_path.exists() {
         return Err(AppError::ServiceNotFound(service_id.to_string()));
     }
 
-    let content = fs::read_to_string(&service_path)?;
-    let service: Service = serde_json::from_str(&content)?;
-
-    Ok(service)
+    read_service_file(&service_path, key)
 }
 
 /// Saves a service to its JSON file.
@@ -127,10 +537,12 @@ pub fn load_service(
 /// * `data_path` - The root data directory path
 /// * `environment` - The name of the environment to save the service to
 /// * `service` - The service object to save
+/// * `key` - The encryption key; when given, the file is written encrypted
 ///
 /// # Returns
 ///
 /// * `Ok(())` - If the service was successfully saved
+/// * `Err(AppError::ValidationError)` - If the environment name or service ID is not path-safe
 /// * `Err(AppError::Io)` - If there's an error creating directories or writing the file
 /// * `Err(AppError::Json)` - If the service cannot be serialized
 ///
@@ -138,23 +550,65 @@ pub fn load_service(
 ///
 /// - Creates `{data_path}/{environment}/services/` directory if it doesn't exist
 /// - Creates or overwrites `{service.id}.json` in the services directory
-/// - JSON is written with pretty formatting for readability
+/// - JSON is written in the canonical form (see `service_document`), stamped
+///   with the current `schemaVersion`
 pub fn save_service(
     data_path: &Path,
     environment: &str,
     service: &Service,
+    key: Option<&EncryptionKey>,
 ) -> Result<(), AppError> {
-    let services_dir = data_path.join(environment).join("services");
+    let service_path = service_file_path(data_path, environment, &service.id)?;
 
     // Create directory if it doesn't exist
-    fs::create_dir_all(&services_dir)?;
+    if let Some(services_dir) = service_path.parent() {
+        fs::create_dir_all(services_dir)?;
+    }
 
-    let service_path = services_dir.join(format!("{}.json", service.id));
-    let content = serde_json::to_string_pretty(service)?;
+    let content = service_document(service)?;
 
-    fs::write(&service_path, content)?;
+    write_document(&service_path, content, key)
+}
 
-    Ok(())
+/// Serializes a service in the canonical form its file is written in.
+///
+/// Fields are written in the order of the model with the tags and metadata
+/// keys sorted, indented by two spaces and ending with a newline, so saving
+/// a service that hasn't changed reproduces its file byte for byte and git
+/// diffs of the data directory show only real changes.
+fn service_document(service: &Service) -> Result<String, AppError> {
+    let mut service = service.clone();
+    service.tags.sort();
+
+    Ok(serde_json::to_string_pretty(&Versioned::new(&service))? + "\n")
+}
+
+/// Orders relationships the way relationships files (and shards) store
+/// them: by source, target, type and ID.
+///
+/// Relationships read back from a file written by this module come in this
+/// order, so code that keeps positions into a loaded list (like the cached
+/// `RelationshipIndex`) sorts its own copy the same way.
+pub fn relationship_order(a: &Relationship, b: &Relationship) -> Ordering {
+    (&a.source, &a.target, a.relationship_type.as_str(), &a.id).cmp(&(
+        &b.source,
+        &b.target,
+        b.relationship_type.as_str(),
+        &b.id,
+    ))
+}
+
+/// Serializes relationships in the canonical form relationships files (and
+/// shards) are written in: sorted by `relationship_order`, otherwise like
+/// `service_document`.
+fn relationships_document(mut relationships: Vec<Relationship>) -> Result<String, AppError> {
+    relationships.sort_by(relationship_order);
+    let file = RelationshipsFile {
+        schema_version: CURRENT_SCHEMA_VERSION,
+        relationships,
+    };
+
+    Ok(serde_json::to_string_pretty(&file)? + "\n")
 }
 
 /// Deletes a service's JSON file from disk.
@@ -171,6 +625,7 @@ pub fn save_service(
 /// # Returns
 ///
 /// * `Ok(())` - If the file was successfully deleted
+/// * `Err(AppError::ValidationError)` - If the environment name or service ID is not path-safe
 /// * `Err(AppError::ServiceNotFound)` - If the service file doesn't exist
 /// * `Err(AppError::Io)` - If there's an error deleting the file
 ///
@@ -183,10 +638,7 @@ pub fn delete_service_file(
     environment: &str,
     service_id: &str,
 ) -> Result<(), AppError> {
-    let service_path = data_path
-        .join(environment)
-        .join("services")
-        .join(format!("{}.json", service_id));
+    let service_path = service_file_path(data_path, environment, service_id)?;
 
     if !service_path.exists() {
         return Err(AppError::ServiceNotFound(service_id.to_string()));
@@ -201,24 +653,33 @@ pub fn delete_service_file(
 ///
 /// Reads and deserializes the relationships.json file from the environment
 /// directory. Unlike services, all relationships for an environment are
-/// stored in a single file.
+/// stored in a single file, unless the environment uses the sharded layout:
+/// then every shard in `relationships/` is read (in parallel) and their
+/// relationships are combined, ordered by source service ID.
+///
+/// Legacy files containing a bare array of relationships (instead of the
+/// wrapper object) are accepted; `validate_environment` reports them as
+/// `legacy_file_format` issues. They are rewritten in the canonical format
+/// the next time relationships are saved.
 ///
 /// # Arguments
 ///
 /// * `data_path` - The root data directory path
 /// * `environment` - The name of the environment to load relationships from
+/// * `key` - The encryption key, if a passphrase has been set
 ///
 /// # Returns
 ///
 /// * `Ok(Vec<Relationship>)` - All relationships in the environment (empty if file doesn't exist)
-/// * `Err(AppError::Io)` - If there's an error reading the file
-/// * `Err(AppError::Json)` - If the JSON file cannot be parsed
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::FileLoad)` - If the file cannot be read, migrated, or parsed
 ///
 /// # File Format
 ///
-/// The relationships file should contain:
+/// The relationships file (and each shard) should contain:
 /// ```json
 /// {
+///   "schemaVersion": 2,
 ///   "relationships": [
 ///     {
 ///       "id": "rel-1",
@@ -230,41 +691,112 @@ pub fn delete_service_file(
 ///   ]
 /// }
 /// ```
-pub fn load_relationships(data_path: &Path, environment: &str) -> Result<Vec<Relationship>, AppError> {
-    let rel_path = data_path.join(environment).join("relationships.json");
+pub fn load_relationships(
+    data_path: &Path,
+    environment: &str,
+    key: Option<&EncryptionKey>,
+) -> Result<Vec<Relationship>, AppError> {
+    validate_environment_name(environment)?;
 
-    if !rel_path.exists() {
-        return Ok(Vec::new());
+    let results: Vec<(PathBuf, Result<Vec<Relationship>, AppError>)> =
+        relationship_files(data_path, environment)?
+            .into_par_iter()
+            .map(|path| {
+                let relationships = read_relationships_file(&path, key);
+                (path, relationships)
+            })
+            .collect();
+
+    let mut relationships = Vec::new();
+    let mut failures = Vec::new();
+    for (path, result) in results {
+        match result {
+            Ok(file) => relationships.extend(file),
+            Err(e) => failures.push((path, e)),
+        }
+    }
+
+    match first_failure(failures) {
+        Some(e) => Err(e),
+        None => Ok(relationships),
     }
+}
 
-    let content = fs::read_to_string(&rel_path)?;
-    let file: RelationshipsFile = serde_json::from_str(&content)?;
+/// Reads, migrates and parses one relationships file or shard.
+fn read_relationships_file(
+    path: &Path,
+    key: Option<&EncryptionKey>,
+) -> Result<Vec<Relationship>, AppError> {
+    let raw = read_raw_document(path, key)?;
+    let migrated = migrations::migrate(DocumentKind::Relationships, raw)
+        .map_err(|e| file_load_error(path, e))?;
+    let file: RelationshipsFile = parse_document(path, migrated.document)?;
 
     Ok(file.relationships)
 }
 
+/// Checks whether an environment's relationships file uses the legacy
+/// plain-array format.
+///
+/// With the sharded layout, every shard is checked.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment to check
+/// * `key` - The encryption key, if a passphrase has been set
+///
+/// # Returns
+///
+/// * `Ok(true)` - If `relationships.json` (or a shard) exists and is a bare array
+/// * `Ok(false)` - If the file is missing or uses the wrapper format
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::FileLoad)` - If the file cannot be read or isn't valid JSON
+pub fn is_legacy_relationships_file(
+    data_path: &Path,
+    environment: &str,
+    key: Option<&EncryptionKey>,
+) -> Result<bool, AppError> {
+    validate_environment_name(environment)?;
+
+    for rel_path in relationship_files(data_path, environment)? {
+        if read_raw_document(&rel_path, key)?.is_array() {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
 /// Saves all relationships to an environment's relationships file.
 ///
 /// Serializes all relationships to JSON and writes them to the environment's
 /// relationships.json file. This operation replaces the entire file contents.
 ///
+/// With the sharded layout, the relationships are grouped by source service
+/// and only the shards whose contents changed are written; shards of
+/// sources that no longer have relationships are removed.
+///
 /// # Arguments
 ///
 /// * `data_path` - The root data directory path
 /// * `environment` - The name of the environment to save relationships to
 /// * `relationships` - The complete list of relationships to save
+/// * `key` - The encryption key; when given, the file is written encrypted
 ///
 /// # Returns
 ///
 /// * `Ok(())` - If the relationships were successfully saved
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name,
+///   or with the sharded layout, a source service ID is not path-safe
 /// * `Err(AppError::Io)` - If there's an error creating directories or writing the file
 /// * `Err(AppError::Json)` - If the relationships cannot be serialized
 ///
 /// # Side Effects
 ///
 /// - Creates `{data_path}/{environment}/` directory if it doesn't exist
-/// - Overwrites `relationships.json` with the new data
-/// - JSON is written with pretty formatting for readability
+/// - Overwrites `relationships.json` with the new data, or the changed shards
+/// - JSON is written in the canonical form (see `relationships_document`)
 ///
 /// # Note
 ///
@@ -274,19 +806,627 @@ pub fn save_relationships(
     data_path: &Path,
     environment: &str,
     relationships: &[Relationship],
+    key: Option<&EncryptionKey>,
 ) -> Result<(), AppError> {
+    validate_environment_name(environment)?;
+
+    if relationship_layout(data_path, environment)? == RelationshipLayout::Sharded {
+        save_relationship_shards(data_path, environment, relationships, key)?;
+        return Ok(());
+    }
+
     let env_dir = data_path.join(environment);
 
     // Create directory if it doesn't exist
     fs::create_dir_all(&env_dir)?;
 
-    let rel_path = env_dir.join("relationships.json");
-    let file = RelationshipsFile {
-        relationships: relationships.to_vec(),
+    write_relationships_file(&env_dir.join("relationships.json"), relationships, key)
+}
+
+/// Writes a relationships file (or shard) in the canonical format.
+fn write_relationships_file(
+    path: &Path,
+    relationships: &[Relationship],
+    key: Option<&EncryptionKey>,
+) -> Result<(), AppError> {
+    let content = relationships_document(relationships.to_vec())?;
+
+    write_document(path, content, key)
+}
+
+/// Writes relationships as one shard per source service, returning the files
+/// written or removed.
+///
+/// Shards that already hold exactly what would be written are left alone.
+fn save_relationship_shards(
+    data_path: &Path,
+    environment: &str,
+    relationships: &[Relationship],
+    key: Option<&EncryptionKey>,
+) -> Result<Vec<PathBuf>, AppError> {
+    let shards_dir = relationship_shards_dir(data_path, environment)?;
+    fs::create_dir_all(&shards_dir)?;
+
+    let mut shards: BTreeMap<PathBuf, Vec<Relationship>> = BTreeMap::new();
+    for (source, group) in group_by_source(relationships) {
+        let path = relationship_shard_path(data_path, environment, source)?;
+        shards.insert(path, group.into_iter().cloned().collect());
+    }
+
+    let stale: Vec<PathBuf> = json_files_in(&shards_dir)?
+        .into_iter()
+        .filter(|path| !shards.contains_key(path))
+        .collect();
+
+    // Compare and write in parallel; each shard is handled by exactly one worker
+    let written = shards
+        .into_par_iter()
+        .map(|(path, relationships)| write_shard(path, relationships, key))
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    for path in &stale {
+        fs::remove_file(path)?;
+    }
+
+    Ok(written.into_iter().flatten().chain(stale).collect())
+}
+
+/// Writes one shard unless it already holds these relationships, returning
+/// its path if it was written.
+fn write_shard(
+    path: PathBuf,
+    relationships: Vec<Relationship>,
+    key: Option<&EncryptionKey>,
+) -> Result<Option<PathBuf>, AppError> {
+    let content = relationships_document(relationships)?;
+    if holds_content(&path, &content, key)? {
+        return Ok(None);
+    }
+
+    write_document(&path, content, key)?;
+    Ok(Some(path))
+}
+
+/// Returns true if a file already holds `content`, stored the way `key`
+/// says it should be (encrypted or not).
+fn holds_content(
+    path: &Path,
+    content: &str,
+    key: Option<&EncryptionKey>,
+) -> Result<bool, AppError> {
+    let Ok(stored) = fs::read(path) else {
+        return Ok(false);
     };
-    let content = serde_json::to_string_pretty(&file)?;
+    if encryption::is_encrypted(&stored) != key.is_some() {
+        return Ok(false);
+    }
 
-    fs::write(&rel_path, content)?;
+    Ok(encryption::decrypt(path, stored, key)? == content.as_bytes())
+}
 
-    Ok(())
+/// Switches an environment to another relationship layout.
+///
+/// The relationships are written in the new layout before the environment
+/// metadata is switched over, and the files of the old layout are only
+/// removed afterwards, so an interrupted migration leaves the old layout in
+/// charge and can simply be re-run. Migrating to the layout already in use
+/// changes nothing.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment to migrate
+/// * `layout` - The layout to switch to
+/// * `key` - The encryption key; written files are encrypted when given
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` - The files that were written or removed (empty if nothing changed)
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name,
+///   or when sharding, a source service ID is not path-safe
+/// * `Err(AppError::FileLoad)` - If the relationships or metadata cannot be read
+/// * `Err(AppError::Io)` - If there's an error writing or removing files
+pub fn migrate_relationship_layout(
+    data_path: &Path,
+    environment: &str,
+    layout: RelationshipLayout,
+    key: Option<&EncryptionKey>,
+) -> Result<Vec<PathBuf>, AppError> {
+    let mut metadata = environment_metadata::load_environment_metadata(data_path, environment)?;
+    if metadata.relationship_layout == layout {
+        return Ok(Vec::new());
+    }
+
+    let relationships = load_relationships(data_path, environment, key)?;
+    let rel_path = relationships_file_path(data_path, environment)?;
+    let shards_dir = relationship_shards_dir(data_path, environment)?;
+
+    let mut paths = match layout {
+        RelationshipLayout::Sharded => {
+            save_relationship_shards(data_path, environment, &relationships, key)?
+        }
+        RelationshipLayout::Single => {
+            write_relationships_file(&rel_path, &relationships, key)?;
+            vec![rel_path.clone()]
+        }
+    };
+
+    metadata.relationship_layout = layout;
+    environment_metadata::save_environment_metadata(data_path, environment, &metadata)?;
+    paths.push(environment_metadata::environment_metadata_path(
+        data_path,
+        environment,
+    )?);
+
+    match layout {
+        RelationshipLayout::Sharded => {
+            if rel_path.exists() {
+                fs::remove_file(&rel_path)?;
+                paths.push(rel_path);
+            }
+        }
+        RelationshipLayout::Single => {
+            for path in json_files_in(&shards_dir)? {
+                fs::remove_file(&path)?;
+                paths.push(path);
+            }
+            // Leave the directory if it holds anything that isn't a shard
+            let is_empty =
+                fs::read_dir(&shards_dir).is_ok_and(|mut entries| entries.next().is_none());
+            if is_empty {
+                fs::remove_dir(&shards_dir)?;
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Rewrites an environment's files that are stored at an older schema version.
+///
+/// Every service file and the relationships file (or each of its shards)
+/// is read, migrated to `CURRENT_SCHEMA_VERSION`, validated against the
+/// typed models, and written back in place. Files that are already current are left untouched.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment to migrate
+/// * `key` - The encryption key; rewritten files are encrypted when given
+/// * `dry_run` - When true, report what would change without writing anything
+///
+/// # Returns
+///
+/// * `Ok(Vec<FileMigration>)` - One entry per file that was (or would be) rewritten
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::FileLoad)` - If a file cannot be read, migrated, or parsed
+/// * `Err(AppError::Io)` - If there's an error listing or writing files
+///
+/// # Note
+///
+/// Service files are rewritten at their existing path, even if the filename
+/// doesn't match the contained ID, so migration never moves files around.
+pub fn migrate_environment_files(
+    data_path: &Path,
+    environment: &str,
+    key: Option<&EncryptionKey>,
+    dry_run: bool,
+) -> Result<Vec<FileMigration>, AppError> {
+    validate_environment_name(environment)?;
+
+    let env_dir = data_path.join(environment);
+    let mut migrated_files = Vec::new();
+
+    let services_dir = env_dir.join("services");
+    if services_dir.exists() {
+        for entry in fs::read_dir(&services_dir)? {
+            let path = entry?.path();
+            if !is_json_file(&path) {
+                continue;
+            }
+
+            let migrated = read_migrated_document(&path, DocumentKind::Service, key)?;
+            if migrated.applied.is_empty() {
+                continue;
+            }
+
+            let service: Service = parse_document(&path, migrated.document)?;
+            if !dry_run {
+                write_document(&path, service_document(&service)?, key)?;
+            }
+
+            migrated_files.push(FileMigration::new(
+                &path,
+                migrated.from_version,
+                migrated.applied,
+            ));
+        }
+    }
+
+    for rel_path in relationship_files(data_path, environment)? {
+        let migrated = read_migrated_document(&rel_path, DocumentKind::Relationships, key)?;
+        if !migrated.applied.is_empty() {
+            let file: RelationshipsFile = parse_document(&rel_path, migrated.document)?;
+            if !dry_run {
+                write_relationships_file(&rel_path, &file.relationships, key)?;
+            }
+
+            migrated_files.push(FileMigration::new(
+                &rel_path,
+                migrated.from_version,
+                migrated.applied,
+            ));
+        }
+    }
+
+    Ok(migrated_files)
+}
+
+/// Rewrites an environment's files in the canonical form saves write.
+///
+/// Files written by older versions of the application or edited by hand
+/// differ in key order, indentation and whitespace, which clutters git
+/// diffs of the data directory. Every service file and the relationships
+/// file (or each of its shards) is read, migrated and parsed, and written
+/// back as `service_document` and `relationships_document` serialize it.
+/// Files that already hold exactly that are left untouched, so normalizing
+/// twice changes nothing.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment to normalize
+/// * `key` - The encryption key; rewritten files are encrypted when given
+/// * `dry_run` - When true, report what would change without writing anything
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` - The files that were (or would be) rewritten
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::FileLoad)` - If a file cannot be read, migrated, or parsed
+/// * `Err(AppError::Io)` - If there's an error listing or writing files
+///
+/// # Note
+///
+/// Like migration, files are rewritten at their existing path and
+/// relationships stay in the shard they were read from.
+pub fn normalize_environment_files(
+    data_path: &Path,
+    environment: &str,
+    key: Option<&EncryptionKey>,
+    dry_run: bool,
+) -> Result<Vec<PathBuf>, AppError> {
+    validate_environment_name(environment)?;
+
+    let mut documents = Vec::new();
+    for path in json_files_in(&data_path.join(environment).join("services"))? {
+        let migrated = read_migrated_document(&path, DocumentKind::Service, key)?;
+        let service: Service = parse_document(&path, migrated.document)?;
+        documents.push((path, service_document(&service)?));
+    }
+    for path in relationship_files(data_path, environment)? {
+        let relationships = read_relationships_file(&path, key)?;
+        documents.push((path, relationships_document(relationships)?));
+    }
+
+    let mut normalized = Vec::new();
+    for (path, content) in documents {
+        if holds_content(&path, &content, key)? {
+            continue;
+        }
+        if !dry_run {
+            write_document(&path, content, key)?;
+        }
+        normalized.push(path);
+    }
+
+    Ok(normalized)
+}
+
+/// Encrypts or decrypts an environment's files in place.
+///
+/// Only the file encoding changes; contents (including the schema version)
+/// are preserved byte for byte. Files that are already in the requested
+/// form are left untouched, so the conversion can be safely re-run after a
+/// partial failure.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory path
+/// * `environment` - The name of the environment to convert
+/// * `key` - The encryption key (needed in both directions)
+/// * `enable` - True to encrypt plaintext files, false to decrypt encrypted files
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` - The files that were converted
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::WrongPassphrase)` - If a file was encrypted with a different key
+/// * `Err(AppError::FileLoad)` - If an encrypted file is corrupt
+/// * `Err(AppError::Io)` - If there's an error listing, reading, or writing files
+pub fn convert_environment_encryption(
+    data_path: &Path,
+    environment: &str,
+    key: &EncryptionKey,
+    enable: bool,
+) -> Result<Vec<PathBuf>, AppError> {
+    validate_environment_name(environment)?;
+
+    let env_dir = data_path.join(environment);
+    let mut paths = Vec::new();
+
+    let services_dir = env_dir.join("services");
+    if services_dir.exists() {
+        for entry in fs::read_dir(&services_dir)? {
+            let path = entry?.path();
+            if is_json_file(&path) {
+                paths.push(path);
+            }
+        }
+    }
+
+    paths.extend(relationship_files(data_path, environment)?);
+
+    let mut converted = Vec::new();
+    for path in paths {
+        let contents = fs::read(&path)?;
+        if encryption::is_encrypted(&contents) == enable {
+            continue;
+        }
+
+        let rewritten = if enable {
+            encryption::encrypt(key, &contents)?
+        } else {
+            encryption::decrypt(&path, contents, Some(key))?
+        };
+        fs::write(&path, rewritten)?;
+
+        converted.push(path);
+    }
+
+    Ok(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::service;
+    use tempfile::TempDir;
+
+    fn write_services(data_path: &Path, count: usize) {
+        for index in 0..count {
+            let mut service = service(&format!("service-{index:04}"));
+            service.description = Some("x".repeat(200));
+            service
+                .metadata
+                .insert("owner".to_string(), serde_json::json!("team-a"));
+            save_service(data_path, "dev", &service, None).unwrap();
+        }
+    }
+
+    /// Reads every service file one after the other, sorted by path.
+    fn load_sequentially(data_path: &Path) -> Vec<(PathBuf, Result<Service, AppError>)> {
+        let services_dir = data_path.join("dev").join("services");
+        let mut paths: Vec<PathBuf> = fs::read_dir(services_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let service = read_service_file(&path, None);
+                (path, service)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_parallel_load_returns_what_a_sequential_load_does() {
+        let dir = TempDir::new().unwrap();
+        write_services(dir.path(), 3000);
+
+        let sequential: Vec<(PathBuf, Service)> = load_sequentially(dir.path())
+            .into_iter()
+            .map(|(path, service)| (path, service.unwrap()))
+            .collect();
+        let mut parallel = load_service_files(dir.path(), "dev", None).unwrap();
+        parallel.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(parallel.len(), 3000);
+        assert_eq!(
+            serde_json::to_value(&parallel).unwrap(),
+            serde_json::to_value(&sequential).unwrap()
+        );
+    }
+
+    #[test]
+    fn a_parallel_load_fails_on_the_file_a_sequential_load_fails_on_first() {
+        let dir = TempDir::new().unwrap();
+        write_services(dir.path(), 500);
+        let services_dir = dir.path().join("dev").join("services");
+        fs::write(services_dir.join("service-0300.json"), "{ not json").unwrap();
+        fs::write(services_dir.join("service-0100.json"), "[1, 2]").unwrap();
+
+        let (first_path, first_error) = load_sequentially(dir.path())
+            .into_iter()
+            .find_map(|(path, service)| service.err().map(|error| (path, error)))
+            .unwrap();
+        let AppError::FileLoad {
+            reason: first_reason,
+            ..
+        } = first_error
+        else {
+            panic!("expected a file load error, got {first_error:?}");
+        };
+
+        match load_service_files(dir.path(), "dev", None) {
+            Err(AppError::FileLoad { path, reason }) => {
+                assert_eq!(path, first_path.display().to_string());
+                assert_eq!(
+                    reason,
+                    format!("{first_reason} (1 other file failed to load too)")
+                );
+            }
+            other => panic!("expected a file load error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn every_file_is_read_and_the_first_failure_by_path_is_reported() {
+        let dir = TempDir::new().unwrap();
+        write_services(dir.path(), 50);
+        let services_dir = dir.path().join("dev").join("services");
+        fs::write(services_dir.join("broken-b.json"), "{ not json").unwrap();
+        fs::write(services_dir.join("broken-a.json"), "[1, 2]").unwrap();
+        fs::write(services_dir.join("broken-c.json"), "").unwrap();
+
+        for _ in 0..5 {
+            match load_service_files(dir.path(), "dev", None) {
+                Err(AppError::FileLoad { path, reason }) => {
+                    assert!(path.ends_with("broken-a.json"), "{path}");
+                    assert!(
+                        reason.ends_with("(2 other files failed to load too)"),
+                        "{reason}"
+                    );
+                }
+                other => panic!("expected a file load error, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn a_single_failure_is_reported_as_is() {
+        let dir = TempDir::new().unwrap();
+        write_services(dir.path(), 3);
+        let broken = dir.path().join("dev").join("services").join("broken.json");
+        fs::write(&broken, "{ not json").unwrap();
+
+        match load_services(dir.path(), "dev", None) {
+            Err(AppError::FileLoad { path, reason }) => {
+                assert_eq!(path, broken.display().to_string());
+                assert!(!reason.contains("failed to load too"), "{reason}");
+            }
+            other => panic!("expected a file load error, got {other:?}"),
+        }
+    }
+
+    /// Renames `user-api.json` to `users-api.json` by hand, without editing the ID inside.
+    fn rename_by_hand(data_path: &Path) -> PathBuf {
+        save_service(data_path, "dev", &service("user-api"), None).unwrap();
+        let services_dir = data_path.join("dev").join("services");
+        let renamed = services_dir.join("users-api.json");
+        fs::rename(services_dir.join("user-api.json"), &renamed).unwrap();
+        renamed
+    }
+
+    #[test]
+    fn a_renamed_file_is_listed_with_its_path_but_not_found_by_id() {
+        let dir = TempDir::new().unwrap();
+        let renamed = rename_by_hand(dir.path());
+
+        let files = load_service_files(dir.path(), "dev", None).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, renamed);
+        assert_eq!(files[0].1.id, "user-api");
+        let ids: Vec<String> = load_services(dir.path(), "dev", None)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(ids, vec!["user-api"]);
+
+        assert!(matches!(
+            load_service(dir.path(), "dev", "user-api", None),
+            Err(AppError::ServiceNotFound(id)) if id == "user-api"
+        ));
+        assert!(matches!(
+            load_service(dir.path(), "dev", "users-api", None),
+            Ok(service) if service.id == "user-api"
+        ));
+    }
+
+    #[test]
+    fn saving_a_mismatched_service_writes_the_file_named_after_its_id() {
+        let dir = TempDir::new().unwrap();
+        let renamed = rename_by_hand(dir.path());
+        let stored = fs::read(&renamed).unwrap();
+
+        save_service(dir.path(), "dev", &service("user-api"), None).unwrap();
+
+        let expected = service_file_path(dir.path(), "dev", "user-api").unwrap();
+        assert!(expected.exists());
+        assert_eq!(fs::read(&renamed).unwrap(), stored);
+        assert_eq!(
+            load_service_files(dir.path(), "dev", None).unwrap().len(),
+            2
+        );
+    }
+
+    const RELATIONSHIP: &str =
+        r#"{ "id": "rel-1", "source": "api", "target": "db", "relationshipType": "depends_on" }"#;
+
+    fn write_relationships_json(contents: &str) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("dev")).unwrap();
+        fs::write(
+            relationships_file_path(dir.path(), "dev").unwrap(),
+            contents,
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn a_legacy_empty_array_holds_no_relationships() {
+        let dir = write_relationships_json("[]");
+
+        assert!(load_relationships(dir.path(), "dev", None)
+            .unwrap()
+            .is_empty());
+        assert!(is_legacy_relationships_file(dir.path(), "dev", None).unwrap());
+    }
+
+    #[test]
+    fn a_legacy_populated_array_loads_and_is_rewritten_on_save() {
+        let dir = write_relationships_json(&format!("[{}]", RELATIONSHIP));
+
+        let relationships = load_relationships(dir.path(), "dev", None).unwrap();
+        assert_eq!(relationships.len(), 1);
+        assert_eq!(relationships[0].id, "rel-1");
+        assert!(is_legacy_relationships_file(dir.path(), "dev", None).unwrap());
+
+        save_relationships(dir.path(), "dev", &relationships, None).unwrap();
+        assert!(!is_legacy_relationships_file(dir.path(), "dev", None).unwrap());
+        assert_eq!(
+            load_relationships(dir.path(), "dev", None).unwrap(),
+            relationships
+        );
+    }
+
+    #[test]
+    fn the_wrapper_format_loads_and_is_not_legacy() {
+        let dir = write_relationships_json(&format!(
+            r#"{{ "schemaVersion": 1, "relationships": [{}] }}"#,
+            RELATIONSHIP
+        ));
+
+        let relationships = load_relationships(dir.path(), "dev", None).unwrap();
+        assert_eq!(relationships.len(), 1);
+        assert!(!is_legacy_relationships_file(dir.path(), "dev", None).unwrap());
+    }
+
+    #[test]
+    fn garbage_fails_naming_the_file() {
+        for garbage in ["not json", r#"{ "relationships": 7 }"#, "[1, 2]"] {
+            let dir = write_relationships_json(garbage);
+
+            match load_relationships(dir.path(), "dev", None) {
+                Err(AppError::FileLoad { path, .. }) => {
+                    assert!(path.ends_with("relationships.json"), "{path}")
+                }
+                other => panic!("expected a file load error for {garbage}, got {other:?}"),
+            }
+        }
+    }
 }
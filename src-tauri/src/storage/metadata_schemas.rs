@@ -0,0 +1,99 @@
+//! Persistence for per-service-type metadata JSON Schemas.
+//!
+//! Each service type can have a JSON Schema its services' `metadata` must
+//! satisfy, stored as `{data_path}/.schemas/{type}.json` (custom types use
+//! their string value). The directory is hidden, so it is never listed as an
+//! environment. For SQLite databases the schemas are kept in a
+//! `{database}.schemas/` directory next to the database file.
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::storage::backend::sidecar_path;
+use crate::storage::ids::validate_service_type_name;
+
+/// Name of the schema directory within the data directory.
+pub const SCHEMAS_DIR: &str = ".schemas";
+
+/// Returns the path of a service type's metadata schema.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `service_type` - The service type as serialized (e.g., "database")
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/.schemas/{type}.json`, or
+///   `{database}.schemas/{type}.json` for SQLite databases
+/// * `Err(AppError::ValidationError)` - If the type is not path-safe
+pub fn metadata_schema_path(data_path: &Path, service_type: &str) -> Result<PathBuf, AppError> {
+    validate_service_type_name(service_type)?;
+
+    let dir = sidecar_path(data_path, SCHEMAS_DIR);
+    Ok(dir.join(format!("{}.json", service_type)))
+}
+
+/// Loads a service type's metadata schema.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `service_type` - The service type as serialized
+///
+/// # Returns
+///
+/// * `Ok(Some(Value))` - The stored schema
+/// * `Ok(None)` - If the type has no schema
+/// * `Err(AppError::ValidationError)` - If the type is not path-safe
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or isn't valid JSON
+pub fn load_metadata_schema(
+    data_path: &Path,
+    service_type: &str,
+) -> Result<Option<Value>, AppError> {
+    let path = metadata_schema_path(data_path, service_type)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(&path).map_err(|e| load_error(e.to_string()))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| load_error(e.to_string()))
+}
+
+/// Saves a service type's metadata schema, replacing any previous one.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `service_type` - The service type as serialized
+/// * `schema` - The JSON Schema
+///
+/// # Returns
+///
+/// * `Ok(())` - If the schema was written
+/// * `Err(AppError::ValidationError)` - If the type is not path-safe
+/// * `Err(AppError::Io)` - If the file cannot be written
+pub fn save_metadata_schema(
+    data_path: &Path,
+    service_type: &str,
+    schema: &Value,
+) -> Result<(), AppError> {
+    let path = metadata_schema_path(data_path, service_type)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let content = serde_json::to_string_pretty(schema)?;
+    fs::write(path, content)?;
+
+    Ok(())
+}
@@ -0,0 +1,311 @@
+//! Schema versioning and migrations for stored data files.
+//!
+//! Every service file and relationships file carries a `schemaVersion`
+//! integer. Files without one are treated as version 1. Before a file is
+//! deserialized into its typed model, its raw JSON document is passed through
+//! the ordered list of migrations until it reaches `CURRENT_SCHEMA_VERSION`,
+//! so old data keeps loading even if it is never rewritten.
+//!
+//! # Adding a Migration
+//!
+//! 1. Bump `CURRENT_SCHEMA_VERSION`
+//! 2. Write a function upgrading a document from the previous version
+//! 3. Append it to `MIGRATIONS`
+//!
+//! Migrations operate on `serde_json::Value` so they can handle shapes the
+//! current typed models no longer accept.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::error::AppError;
+
+/// The schema version written by this version of the application.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// The schema version assumed for files without a `schemaVersion` field.
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+/// Name of the version field in stored documents.
+const SCHEMA_VERSION_KEY: &str = "schemaVersion";
+
+/// The kind of stored document being migrated.
+///
+/// # Variants
+///
+/// * `Service` - A single `{service_id}.json` file
+/// * `Relationships` - An environment's `relationships.json` file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Service,
+    Relationships,
+}
+
+/// A single migration step from one schema version to the next.
+struct Migration {
+    /// The version this migration upgrades from (it produces `from + 1`).
+    from: u32,
+    /// Short human-readable description, reported by `migrate_environment`.
+    description: &'static str,
+    /// The upgrade function.
+    apply: fn(DocumentKind, Value) -> Result<Value, AppError>,
+}
+
+/// All known migrations, ordered by `from` version.
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "Add schema version and wrap plain-array relationships files",
+    apply: migrate_v1_to_v2,
+}];
+
+/// The result of migrating a document.
+///
+/// # Fields
+///
+/// * `document` - The document at `CURRENT_SCHEMA_VERSION`
+/// * `from_version` - The version the document was stored at
+/// * `applied` - Descriptions of the migrations that were applied, in order
+#[derive(Debug)]
+pub struct MigratedDocument {
+    pub document: Value,
+    pub from_version: u32,
+    pub applied: Vec<String>,
+}
+
+/// A file that was (or would be) rewritten at the current schema version.
+///
+/// # Fields
+///
+/// * `path` - Path of the migrated file
+/// * `from_version` - The schema version the file was stored at
+/// * `to_version` - The schema version the file was upgraded to
+/// * `applied` - Descriptions of the migrations that were applied, in order
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMigration {
+    pub path: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub applied: Vec<String>,
+}
+
+impl FileMigration {
+    /// Creates a report entry for a file upgraded to `CURRENT_SCHEMA_VERSION`.
+    pub fn new(path: &Path, from_version: u32, applied: Vec<String>) -> Self {
+        Self {
+            path: path.display().to_string(),
+            from_version,
+            to_version: CURRENT_SCHEMA_VERSION,
+            applied,
+        }
+    }
+}
+
+/// Serialization wrapper that stamps a document with the current schema version.
+///
+/// The `schemaVersion` field is written first, followed by the document's
+/// own fields, so stored files stay readable.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Versioned<'a, T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    document: &'a T,
+}
+
+impl<'a, T> Versioned<'a, T> {
+    /// Wraps a document for serialization at `CURRENT_SCHEMA_VERSION`.
+    pub fn new(document: &'a T) -> Self {
+        Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            document,
+        }
+    }
+}
+
+/// Returns the schema version of a raw stored document.
+///
+/// # Arguments
+///
+/// * `document` - The raw JSON document
+///
+/// # Returns
+///
+/// The value of the `schemaVersion` field, or `LEGACY_SCHEMA_VERSION` when
+/// the field is absent (or the document isn't an object at all).
+pub fn document_version(document: &Value) -> u32 {
+    document
+        .get(SCHEMA_VERSION_KEY)
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(LEGACY_SCHEMA_VERSION)
+}
+
+/// Upgrades a raw stored document to `CURRENT_SCHEMA_VERSION`.
+///
+/// # Arguments
+///
+/// * `kind` - What kind of document this is
+/// * `document` - The raw JSON document as read from disk
+///
+/// # Returns
+///
+/// * `Ok(MigratedDocument)` - The upgraded document and what was applied
+/// * `Err(AppError::ValidationError)` - If the document was written by a newer
+///   version of the application, or a migration is missing
+pub fn migrate(kind: DocumentKind, document: Value) -> Result<MigratedDocument, AppError> {
+    let from_version = document_version(&document);
+
+    if from_version > CURRENT_SCHEMA_VERSION {
+        return Err(AppError::ValidationError(format!(
+            "Schema version {} is newer than the supported version {}",
+            from_version, CURRENT_SCHEMA_VERSION
+        )));
+    }
+
+    let mut document = document;
+    let mut version = from_version;
+    let mut applied = Vec::new();
+
+    while version < CURRENT_SCHEMA_VERSION {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| {
+                AppError::ValidationError(format!(
+                    "No migration available from schema version {}",
+                    version
+                ))
+            })?;
+
+        document = (migration.apply)(kind, document)?;
+        version += 1;
+        set_version(&mut document, version);
+        applied.push(migration.description.to_string());
+    }
+
+    Ok(MigratedDocument {
+        document,
+        from_version,
+        applied,
+    })
+}
+
+/// Writes the `schemaVersion` field into an object document.
+fn set_version(document: &mut Value, version: u32) {
+    if let Value::Object(map) = document {
+        map.insert(SCHEMA_VERSION_KEY.to_string(), Value::from(version));
+    }
+}
+
+/// Version 1 → 2.
+///
+/// Early environments stored `relationships.json` as a bare array of
+/// relationships. Version 2 always uses the `{"relationships": [...]}`
+/// wrapper and stamps a schema version on every file. Service documents are
+/// unchanged apart from the version stamp.
+fn migrate_v1_to_v2(kind: DocumentKind, document: Value) -> Result<Value, AppError> {
+    match (kind, document) {
+        (DocumentKind::Relationships, Value::Array(relationships)) => {
+            let mut wrapper = serde_json::Map::new();
+            wrapper.insert("relationships".to_string(), Value::Array(relationships));
+            Ok(Value::Object(wrapper))
+        }
+        (_, document) => Ok(document),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn relationship() -> Value {
+        json!({ "id": "rel-1", "source": "api", "target": "db", "relationshipType": "depends_on" })
+    }
+
+    #[test]
+    fn a_legacy_plain_array_is_wrapped() {
+        let migrated = migrate(DocumentKind::Relationships, json!([relationship()])).unwrap();
+
+        assert_eq!(
+            migrated.document,
+            json!({ "schemaVersion": 2, "relationships": [relationship()] })
+        );
+        assert_eq!(migrated.from_version, LEGACY_SCHEMA_VERSION);
+        assert_eq!(migrated.applied, vec![MIGRATIONS[0].description]);
+    }
+
+    #[test]
+    fn an_unversioned_wrapper_is_only_stamped() {
+        let migrated = migrate(
+            DocumentKind::Relationships,
+            json!({ "relationships": [relationship()] }),
+        )
+        .unwrap();
+
+        assert_eq!(
+            migrated.document,
+            json!({ "schemaVersion": 2, "relationships": [relationship()] })
+        );
+        assert_eq!(migrated.applied.len(), 1);
+    }
+
+    #[test]
+    fn service_documents_are_stamped_and_otherwise_unchanged() {
+        let service = json!({ "id": "api", "name": "API", "tags": ["a"] });
+
+        let migrated = migrate(DocumentKind::Service, service.clone()).unwrap();
+
+        let mut expected = service;
+        expected["schemaVersion"] = json!(2);
+        assert_eq!(migrated.document, expected);
+    }
+
+    #[test]
+    fn current_documents_pass_through() {
+        let document = json!({ "schemaVersion": CURRENT_SCHEMA_VERSION, "relationships": [] });
+
+        let migrated = migrate(DocumentKind::Relationships, document.clone()).unwrap();
+
+        assert_eq!(migrated.document, document);
+        assert_eq!(migrated.from_version, CURRENT_SCHEMA_VERSION);
+        assert!(migrated.applied.is_empty());
+    }
+
+    #[test]
+    fn documents_from_a_newer_version_are_rejected() {
+        let document = json!({ "schemaVersion": CURRENT_SCHEMA_VERSION + 1, "id": "api" });
+
+        assert!(matches!(
+            migrate(DocumentKind::Service, document),
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn the_version_defaults_to_legacy() {
+        assert_eq!(document_version(&json!([])), LEGACY_SCHEMA_VERSION);
+        assert_eq!(
+            document_version(&json!({ "id": "api" })),
+            LEGACY_SCHEMA_VERSION
+        );
+        assert_eq!(document_version(&json!({ "schemaVersion": 2 })), 2);
+    }
+
+    #[test]
+    fn versioned_documents_write_the_version_first() {
+        let document = json!({ "id": "api" });
+
+        let written = serde_json::to_string(&Versioned::new(&document)).unwrap();
+
+        assert_eq!(
+            written,
+            format!(
+                r#"{{"schemaVersion":{},"id":"api"}}"#,
+                CURRENT_SCHEMA_VERSION
+            )
+        );
+    }
+}
@@ -1,6 +1,42 @@
+pub mod annotations;
+pub mod audit;
+pub mod backend;
+pub mod encryption;
+pub mod environment_metadata;
+pub mod groups;
+pub mod ids;
 pub mod loader;
+pub mod metadata_schemas;
+pub mod migrations;
+pub mod report;
+pub mod settings;
+pub mod snapshots;
+pub mod sqlite;
+pub mod suppressions;
+pub mod type_registry;
+pub mod validation_config;
+pub mod views;
 
+pub use annotations::{load_annotations, save_annotations};
+pub use backend::{is_sqlite_path, open_backend, FileSystemBackend, StorageBackend};
+pub use encryption::EncryptionKey;
+pub use groups::{load_groups, save_groups};
+pub use ids::{
+    new_relationship_id, slugify, validate_environment_name, validate_service_id, MAX_SLUG_LENGTH,
+};
 pub use loader::{
-    delete_service_file, load_relationships, load_service, load_services, save_relationships,
-    save_service,
+    changed_relationship_sources, convert_environment_encryption, migrate_environment_files,
+    migrate_relationship_layout, normalize_environment_files, relationship_file_paths,
+    relationship_order, service_file_path,
+};
+pub use metadata_schemas::{load_metadata_schema, save_metadata_schema};
+pub use settings::{
+    load_recent_services, load_settings, save_recent_services, save_settings, RECENT_SERVICES_FILE,
+    SETTINGS_FILE,
 };
+pub use snapshots::{delete_snapshot, load_snapshot, load_snapshots, save_snapshot};
+pub use sqlite::SqliteBackend;
+pub use suppressions::{load_suppressions, save_suppressions};
+pub use type_registry::{load_custom_types, save_custom_types};
+pub use validation_config::{load_validation_config, save_validation_config};
+pub use views::{delete_view, load_view, load_views, save_view};
@@ -0,0 +1,515 @@
+//! Disk usage and file health reporting for data directories.
+//!
+//! Reports are built from file metadata only, so they stay fast even for
+//! environments with thousands of files. The one exception is
+//! `relationships.json` (or its shards), which is parsed to check that it
+//! loads.
+//!
+//! `preflight_data_path` looks at a candidate data directory the same way
+//! before the application is pointed at it.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::error::AppError;
+use crate::storage::annotations;
+use crate::storage::audit;
+use crate::storage::backend;
+use crate::storage::encryption::EncryptionKey;
+use crate::storage::environment_metadata::ENVIRONMENT_METADATA_FILE;
+use crate::storage::groups::GROUPS_FILE;
+use crate::storage::ids::validate_environment_name;
+use crate::storage::loader;
+use crate::storage::snapshots;
+use crate::storage::suppressions;
+
+/// How many of the largest files are listed per environment.
+const LARGEST_FILES_LIMIT: usize = 10;
+
+/// Directory names whose contents are treated as history/backup artifacts.
+const BACKUP_DIRECTORIES: &[&str] = &["history", "backups", ".history", ".backups"];
+
+/// Seconds in a day, for modification ages.
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// What an unexpected file in an environment directory appears to be.
+///
+/// # Variants
+///
+/// * `Temporary` - Editor or write leftovers (`.tmp`, `.swp`, `~` suffix)
+/// * `Backup` - Backup copies (`.bak`, `.orig`) or files in a history/backup directory
+/// * `Other` - Any other file that isn't part of the data layout
+///
+/// # Serialization
+///
+/// Kinds are serialized as snake_case strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    Temporary,
+    Backup,
+    Other,
+}
+
+/// Size and age of a single file.
+///
+/// # Fields
+///
+/// * `path` - Path of the file
+/// * `bytes` - File size in bytes
+/// * `days_since_modified` - Whole days since the last modification, if known
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStat {
+    pub path: String,
+    pub bytes: u64,
+    pub days_since_modified: Option<u64>,
+}
+
+/// A file in an environment directory that isn't part of the data layout.
+///
+/// # Fields
+///
+/// * `file` - Size and age of the file
+/// * `kind` - What the file appears to be
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedArtifact {
+    pub file: FileStat,
+    pub kind: ArtifactKind,
+}
+
+/// Health of an environment's `relationships.json`.
+///
+/// With the sharded layout, the shards are reported together as one file.
+///
+/// # Fields
+///
+/// * `exists` - Whether the file (or any shard) exists
+/// * `bytes` - File size in bytes, or the shards' combined size (0 if missing)
+/// * `parses` - Whether the file loads successfully (a missing file counts as parsing)
+/// * `error` - Why the file failed to load, if it did
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelationshipsFileHealth {
+    pub exists: bool,
+    pub bytes: u64,
+    pub parses: bool,
+    pub error: Option<String>,
+}
+
+/// Disk usage and file health for one environment.
+///
+/// # Fields
+///
+/// * `environment` - The environment name
+/// * `service_file_count` - Number of `.json` files in `services/`
+/// * `total_bytes` - Size of every file in the environment directory
+/// * `largest_files` - The largest files, biggest first
+/// * `stale_files` - Data files not modified within the staleness threshold, oldest first
+/// * `orphaned_artifacts` - Files that aren't part of the data layout
+/// * `orphaned_bytes` - Combined size of the orphaned artifacts
+/// * `relationships` - Health of `relationships.json`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentStorageReport {
+    pub environment: String,
+    pub service_file_count: usize,
+    pub total_bytes: u64,
+    pub largest_files: Vec<FileStat>,
+    pub stale_files: Vec<FileStat>,
+    pub orphaned_artifacts: Vec<OrphanedArtifact>,
+    pub orphaned_bytes: u64,
+    pub relationships: RelationshipsFileHealth,
+}
+
+/// One subdirectory of a candidate data directory that looks like an environment.
+///
+/// # Fields
+///
+/// * `name` - The directory name
+/// * `has_services_dir` - Whether it has a `services/` directory
+/// * `has_relationships_file` - Whether it has a `relationships.json` or a `relationships/`
+///   shards directory
+/// * `service_file_count` - Number of `.json` files in `services/`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentPreflight {
+    pub name: String,
+    pub has_services_dir: bool,
+    pub has_relationships_file: bool,
+    pub service_file_count: usize,
+}
+
+/// What a candidate data path contains (see `preflight_data_path`).
+///
+/// # Fields
+///
+/// * `path` - The candidate path
+/// * `usable` - Whether `set_data_path` would accept the path
+/// * `sqlite` - Whether the path is a SQLite database file rather than a directory
+/// * `environments` - Subdirectories that look like environments, by name
+/// * `other_directories` - Non-hidden subdirectories that don't look like environments
+/// * `service_file_count` - Number of service files across all environments
+/// * `warnings` - Why the path may not be the intended data directory
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataPathPreflight {
+    pub path: String,
+    pub usable: bool,
+    pub sqlite: bool,
+    pub environments: Vec<EnvironmentPreflight>,
+    pub other_directories: Vec<String>,
+    pub service_file_count: usize,
+    pub warnings: Vec<String>,
+}
+
+/// Builds the storage report for one environment.
+///
+/// # Arguments
+///
+/// * `data_path` - The root data directory
+/// * `environment` - The environment to report on
+/// * `key` - The encryption key, used to check that `relationships.json` loads
+/// * `stale_after_days` - Data files older than this many days are reported as stale
+///
+/// # Returns
+///
+/// * `Ok(EnvironmentStorageReport)` - The report
+/// * `Err(AppError::ValidationError)` - If the environment name is not a safe directory name
+/// * `Err(AppError::Io)` - If the environment directory cannot be listed
+pub fn environment_report(
+    data_path: &Path,
+    environment: &str,
+    key: Option<&EncryptionKey>,
+    stale_after_days: u64,
+) -> Result<EnvironmentStorageReport, AppError> {
+    validate_environment_name(environment)?;
+
+    let env_dir = data_path.join(environment);
+    let services_dir = env_dir.join("services");
+    let rel_path = env_dir.join("relationships.json");
+    let shards_dir = env_dir.join("relationships");
+    let groups_path = env_dir.join(GROUPS_FILE);
+    let metadata_path = env_dir.join(ENVIRONMENT_METADATA_FILE);
+
+    let mut files = Vec::new();
+    if env_dir.is_dir() {
+        collect_files(&env_dir, &mut files)?;
+    }
+
+    let mut report = EnvironmentStorageReport {
+        environment: environment.to_string(),
+        service_file_count: 0,
+        total_bytes: 0,
+        largest_files: Vec::new(),
+        stale_files: Vec::new(),
+        orphaned_artifacts: Vec::new(),
+        orphaned_bytes: 0,
+        relationships: relationships_health(data_path, environment, key),
+    };
+
+    let mut all_files = Vec::new();
+    for (path, metadata) in files {
+        let stat = FileStat {
+            path: path.display().to_string(),
+            bytes: metadata.len(),
+            days_since_modified: days_since_modified(&metadata),
+        };
+        report.total_bytes += stat.bytes;
+
+        // Audit logs, suppressions, annotations and snapshots count towards the total but
+        // are neither data nor leftovers
+        if audit::is_audit_log_file(&path)
+            || suppressions::is_suppressions_file(&path)
+            || annotations::is_annotations_file(&path)
+            || snapshots::is_snapshot_path(&path)
+        {
+            all_files.push(stat);
+            continue;
+        }
+
+        let is_json = path.extension().and_then(|e| e.to_str()) == Some("json");
+        let is_service_file = is_json && path.parent() == Some(services_dir.as_path());
+        let is_shard = is_json && path.parent() == Some(shards_dir.as_path());
+        let is_data_file = is_service_file
+            || is_shard
+            || path == rel_path
+            || path == groups_path
+            || path == metadata_path;
+
+        if is_service_file {
+            report.service_file_count += 1;
+        }
+
+        if is_data_file {
+            if stat
+                .days_since_modified
+                .is_some_and(|days| days > stale_after_days)
+            {
+                report.stale_files.push(stat.clone());
+            }
+        } else {
+            report.orphaned_bytes += stat.bytes;
+            report.orphaned_artifacts.push(OrphanedArtifact {
+                file: stat.clone(),
+                kind: artifact_kind(&env_dir, &path),
+            });
+        }
+
+        all_files.push(stat);
+    }
+
+    all_files.sort_by_key(|file| Reverse(file.bytes));
+    all_files.truncate(LARGEST_FILES_LIMIT);
+    report.largest_files = all_files;
+
+    report
+        .stale_files
+        .sort_by_key(|file| Reverse(file.days_since_modified));
+
+    Ok(report)
+}
+
+/// Recursively collects every file below a directory with its metadata.
+fn collect_files(dir: &Path, files: &mut Vec<(PathBuf, fs::Metadata)>) -> Result<(), AppError> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+
+        if metadata.is_dir() {
+            collect_files(&path, files)?;
+        } else if metadata.is_file() {
+            files.push((path, metadata));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether an environment's relationships file (or its shards) exists and loads.
+fn relationships_health(
+    data_path: &Path,
+    environment: &str,
+    key: Option<&EncryptionKey>,
+) -> RelationshipsFileHealth {
+    let paths = match loader::relationship_files(data_path, environment) {
+        Ok(paths) => paths,
+        Err(e) => {
+            return RelationshipsFileHealth {
+                exists: false,
+                bytes: 0,
+                parses: false,
+                error: Some(e.to_string()),
+            };
+        }
+    };
+    if paths.is_empty() {
+        return RelationshipsFileHealth {
+            exists: false,
+            bytes: 0,
+            parses: true,
+            error: None,
+        };
+    }
+
+    let bytes = paths
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    let error = loader::load_relationships(data_path, environment, key)
+        .err()
+        .map(|e| e.to_string());
+
+    RelationshipsFileHealth {
+        exists: true,
+        bytes,
+        parses: error.is_none(),
+        error,
+    }
+}
+
+/// Classifies a file that isn't part of the data layout.
+fn artifact_kind(env_dir: &Path, path: &Path) -> ArtifactKind {
+    let in_backup_dir = path
+        .strip_prefix(env_dir)
+        .ok()
+        .and_then(|relative| relative.parent())
+        .is_some_and(|parent| {
+            parent.components().any(|c| {
+                c.as_os_str()
+                    .to_str()
+                    .is_some_and(|name| BACKUP_DIRECTORIES.contains(&name))
+            })
+        });
+
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+    if name.ends_with('~') || matches!(extension, "tmp" | "temp" | "swp") {
+        ArtifactKind::Temporary
+    } else if in_backup_dir || matches!(extension, "bak" | "orig" | "backup") {
+        ArtifactKind::Backup
+    } else {
+        ArtifactKind::Other
+    }
+}
+
+/// Returns whole days since a file was last modified.
+fn days_since_modified(metadata: &fs::Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or(Duration::ZERO);
+
+    Some(age.as_secs() / SECONDS_PER_DAY)
+}
+
+/// Inspects a candidate data path without opening it.
+///
+/// A subdirectory looks like an environment if it has a `services/`
+/// directory or a `relationships.json`. Warnings point out an empty
+/// directory, one without environments, and the common mistake of picking
+/// an environment or `services/` directory instead of the data directory.
+/// SQLite database files are only checked for existence, as reading them
+/// would create them.
+///
+/// # Arguments
+///
+/// * `path` - The candidate data directory or database file
+///
+/// # Returns
+///
+/// The preflight report. Unreadable directories are reported as warnings.
+pub fn preflight_data_path(path: &Path) -> DataPathPreflight {
+    let mut report = DataPathPreflight {
+        path: path.display().to_string(),
+        usable: false,
+        sqlite: backend::is_sqlite_path(path),
+        environments: Vec::new(),
+        other_directories: Vec::new(),
+        service_file_count: 0,
+        warnings: Vec::new(),
+    };
+
+    if report.sqlite {
+        report.usable = path.parent().is_some_and(|parent| parent.is_dir());
+        if !report.usable {
+            report
+                .warnings
+                .push("The folder of the database file doesn't exist".to_string());
+        } else if !path.is_file() {
+            report
+                .warnings
+                .push("The database file doesn't exist yet and will be created".to_string());
+        }
+        return report;
+    }
+
+    if !path.is_dir() {
+        let reason = if path.exists() {
+            "The path is not a directory"
+        } else {
+            "The path doesn't exist"
+        };
+        report.warnings.push(reason.to_string());
+        return report;
+    }
+    report.usable = true;
+
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report
+                .warnings
+                .push(format!("The directory can't be listed: {}", e));
+            return report;
+        }
+    };
+
+    let mut is_empty = true;
+    for entry in entries.filter_map(Result::ok) {
+        is_empty = false;
+        let entry_path = entry.path();
+        let Some(name) = entry_path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !entry_path.is_dir() || name.starts_with('.') {
+            continue;
+        }
+
+        let services_dir = entry_path.join("services");
+        let environment = EnvironmentPreflight {
+            name: name.to_string(),
+            has_services_dir: services_dir.is_dir(),
+            has_relationships_file: entry_path.join("relationships.json").is_file()
+                || entry_path.join("relationships").is_dir(),
+            service_file_count: count_json_files(&services_dir),
+        };
+        if !environment.has_services_dir && !environment.has_relationships_file {
+            report.other_directories.push(name.to_string());
+            continue;
+        }
+        if validate_environment_name(name).is_err() {
+            report.warnings.push(format!(
+                "{} looks like an environment but its name can't be used as one",
+                name
+            ));
+        }
+        report.service_file_count += environment.service_file_count;
+        report.environments.push(environment);
+    }
+    report.environments.sort_by(|a, b| a.name.cmp(&b.name));
+    report.other_directories.sort();
+
+    if path.join("services").is_dir()
+        || path.join("relationships.json").is_file()
+        || path.join("relationships").is_dir()
+    {
+        report.warnings.push(
+            "This looks like an environment directory; choose the directory containing it"
+                .to_string(),
+        );
+    } else if path.file_name().is_some_and(|name| name == "services") && count_json_files(path) > 0
+    {
+        report.warnings.push(
+            "This looks like the services directory of an environment; choose the directory \
+             two levels up"
+                .to_string(),
+        );
+    } else if is_empty {
+        report
+            .warnings
+            .push("The directory is empty; it has no environments yet".to_string());
+    } else if report.environments.is_empty() {
+        report.warnings.push(
+            "No subdirectory looks like an environment (none has a services/ directory or \
+             relationships.json)"
+                .to_string(),
+        );
+    } else if report.service_file_count == 0 {
+        report
+            .warnings
+            .push("The environments contain no service files".to_string());
+    }
+
+    report
+}
+
+/// Counts the `.json` files directly in a directory; 0 if it can't be listed.
+fn count_json_files(dir: &Path) -> usize {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().extension().and_then(|e| e.to_str()) == Some("json"))
+                .count()
+        })
+        .unwrap_or(0)
+}
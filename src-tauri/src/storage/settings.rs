@@ -0,0 +1,169 @@
+//! Persistence for application settings.
+//!
+//! Settings live in a single pretty-printed JSON file outside the data
+//! directory (see `models::Settings`). The recently viewed services (see
+//! `models::RecentService`) are kept in a file next to it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::models::{RecentViews, Settings};
+
+/// File name of the settings file within the application config directory.
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// File name of the recently viewed services file within the application config directory.
+pub const RECENT_SERVICES_FILE: &str = "recent_services.json";
+
+/// Loads settings from a settings file.
+///
+/// # Arguments
+///
+/// * `path` - Path of the settings file
+///
+/// # Returns
+///
+/// * `Ok(Settings)` - The stored settings, or defaults if the file doesn't exist
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or parsed
+pub fn load_settings(path: &Path) -> Result<Settings, AppError> {
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(path).map_err(|e| load_error(e.to_string()))?;
+    serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))
+}
+
+/// Saves settings to a settings file.
+///
+/// # Arguments
+///
+/// * `path` - Path of the settings file
+/// * `settings` - The settings to save
+///
+/// # Returns
+///
+/// * `Ok(())` - If the settings were written
+/// * `Err(AppError::Io)` - If the file or its directory cannot be written
+///
+/// # Side Effects
+///
+/// - Creates the settings file's parent directory if it doesn't exist
+pub fn save_settings(path: &Path, settings: &Settings) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let content = serde_json::to_string_pretty(settings)?;
+    fs::write(path, content)?;
+
+    Ok(())
+}
+
+/// Loads the recently viewed services.
+///
+/// # Arguments
+///
+/// * `path` - Path of the recently viewed services file
+///
+/// # Returns
+///
+/// * `Ok(RecentViews)` - Recently viewed services per data path and environment;
+///   empty if the file doesn't exist
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or parsed
+pub fn load_recent_services(path: &Path) -> Result<RecentViews, AppError> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(path).map_err(|e| load_error(e.to_string()))?;
+    serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))
+}
+
+/// Saves the recently viewed services.
+///
+/// # Arguments
+///
+/// * `path` - Path of the recently viewed services file
+/// * `recent_services` - Recently viewed services per data path and environment
+///
+/// # Returns
+///
+/// * `Ok(())` - If the file was written
+/// * `Err(AppError::Io)` - If the file or its directory cannot be written
+pub fn save_recent_services(path: &Path, recent_services: &RecentViews) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let content = serde_json::to_string_pretty(recent_services)?;
+    fs::write(path, content)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DisplayConfig, ServiceType};
+    use tempfile::TempDir;
+
+    #[test]
+    fn settings_with_unknown_display_keys_load() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        fs::write(
+            &path,
+            r##"{
+                "display": {
+                    "serviceTypes": { "api": { "color": "#000000", "badge": "v2" } },
+                    "edgeLabels": true
+                },
+                "addedInALaterVersion": { "enabled": true }
+            }"##,
+        )
+        .unwrap();
+
+        let settings = load_settings(&path).unwrap();
+
+        let display = &settings.display;
+        assert_eq!(
+            display.service_type_style(&ServiceType::Api).color,
+            "#000000"
+        );
+        assert_eq!(
+            display.service_type_style(&ServiceType::Database),
+            DisplayConfig::default().service_type_style(&ServiceType::Database)
+        );
+    }
+
+    #[test]
+    fn settings_without_a_display_config_use_the_built_in_palette() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        fs::write(&path, "{}").unwrap();
+
+        assert_eq!(
+            load_settings(&path).unwrap().display,
+            DisplayConfig::default()
+        );
+        assert_eq!(
+            load_settings(&dir.path().join("missing.json"))
+                .unwrap()
+                .display,
+            DisplayConfig::default()
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Persistence for environment snapshots.
+//!
+//! Each snapshot (see `models::Snapshot`) is a single bundle file in a hidden
+//! `{environment}/.snapshots/` directory, `{id}.json`. Services are only
+//! loaded from `services/`, so snapshots are never loaded as data, and the
+//! data directory watcher and storage report ignore them. For SQLite
+//! databases they are kept in a `{database}.snapshots/{environment}/`
+//! directory next to the database file. Bundles are encrypted like the
+//! other data files when a passphrase is set.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::AppError;
+use crate::models::Snapshot;
+use crate::storage::backend::environment_sidecar_path;
+use crate::storage::encryption::{self, EncryptionKey};
+use crate::storage::ids::{validate_environment_name, validate_snapshot_id};
+
+/// Name of an environment's snapshot directory.
+pub const SNAPSHOTS_DIR: &str = ".snapshots";
+
+/// Returns the directory an environment's snapshots are stored in.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/.snapshots`, or
+///   `{database}.snapshots/{environment}` for SQLite databases
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+pub fn snapshots_dir(data_path: &Path, environment: &str) -> Result<PathBuf, AppError> {
+    validate_environment_name(environment)?;
+
+    Ok(environment_sidecar_path(
+        data_path,
+        environment,
+        SNAPSHOTS_DIR,
+    ))
+}
+
+/// Returns the path of a snapshot bundle.
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{snapshots_dir}/{snapshot_id}.json`
+/// * `Err(AppError::ValidationError)` - If the environment name or ID is not path-safe
+pub fn snapshot_path(
+    data_path: &Path,
+    environment: &str,
+    snapshot_id: &str,
+) -> Result<PathBuf, AppError> {
+    validate_snapshot_id(snapshot_id)?;
+
+    Ok(snapshots_dir(data_path, environment)?.join(format!("{}.json", snapshot_id)))
+}
+
+/// Returns true if the path is inside an environment's snapshot directory.
+///
+/// Used to keep snapshots out of data file scans.
+pub fn is_snapshot_path(path: &Path) -> bool {
+    path.components()
+        .any(|c| c == Component::Normal(SNAPSHOTS_DIR.as_ref()))
+}
+
+/// Loads a snapshot.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+/// * `snapshot_id` - The ID of the snapshot
+/// * `key` - The encryption key, if a passphrase has been set
+///
+/// # Returns
+///
+/// * `Ok(Some(Snapshot))` - The stored snapshot
+/// * `Ok(None)` - If the environment has no snapshot with the ID
+/// * `Err(AppError::ValidationError)` - If the environment name or ID is not path-safe
+/// * `Err(AppError::EncryptionRequired)` - If the bundle is encrypted and no key is set
+/// * `Err(AppError::FileLoad)` - If the bundle cannot be read, decrypted or parsed
+pub fn load_snapshot(
+    data_path: &Path,
+    environment: &str,
+    snapshot_id: &str,
+    key: Option<&EncryptionKey>,
+) -> Result<Option<Snapshot>, AppError> {
+    let path = snapshot_path(data_path, environment, snapshot_id)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    read_snapshot(&path, key).map(Some)
+}
+
+/// Loads all snapshots of an environment.
+///
+/// Bundles that cannot be read are skipped with a warning, so one corrupt
+/// snapshot doesn't hide the others.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+/// * `key` - The encryption key, if a passphrase has been set
+///
+/// # Returns
+///
+/// * `Ok(Vec<Snapshot>)` - The snapshots, newest first (empty if none were taken)
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::Io)` - If the snapshot directory cannot be read
+pub fn load_snapshots(
+    data_path: &Path,
+    environment: &str,
+    key: Option<&EncryptionKey>,
+) -> Result<Vec<Snapshot>, AppError> {
+    let dir = snapshots_dir(data_path, environment)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            match read_snapshot(&path, key) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => eprintln!("Warning: Skipping snapshot: {}", e),
+            }
+        }
+    }
+    snapshots.sort_by(|a, b| {
+        b.created_at
+            .cmp(&a.created_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    Ok(snapshots)
+}
+
+/// Writes a new snapshot.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+/// * `snapshot` - The snapshot to write
+/// * `key` - The encryption key; when given, the bundle is written encrypted
+///
+/// # Returns
+///
+/// * `Ok(())` - If the snapshot was written
+/// * `Err(AppError::ValidationError)` - If the environment name or ID is not path-safe,
+///   or a snapshot with the ID already exists (snapshots are immutable)
+/// * `Err(AppError::Io)` - If the bundle cannot be written
+pub fn save_snapshot(
+    data_path: &Path,
+    environment: &str,
+    snapshot: &Snapshot,
+    key: Option<&EncryptionKey>,
+) -> Result<(), AppError> {
+    let path = snapshot_path(data_path, environment, &snapshot.id)?;
+    if path.exists() {
+        return Err(AppError::ValidationError(format!(
+            "Snapshot '{}' already exists",
+            snapshot.id
+        )));
+    }
+
+    write_bundle(&path, snapshot, key)
+}
+
+/// Writes a snapshot bundle to a file, creating its directory.
+///
+/// Used for snapshots and for scheduled exports to other directories, so
+/// both are written in the same format.
+///
+/// # Arguments
+///
+/// * `path` - The file to write; an existing file is replaced
+/// * `snapshot` - The snapshot to write
+/// * `key` - The encryption key; when given, the bundle is written encrypted
+///
+/// # Returns
+///
+/// * `Ok(())` - If the bundle was written
+/// * `Err(AppError::Io)` - If the bundle cannot be written
+pub fn write_bundle(
+    path: &Path,
+    snapshot: &Snapshot,
+    key: Option<&EncryptionKey>,
+) -> Result<(), AppError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let content = serde_json::to_vec_pretty(snapshot)?;
+    let contents = match key {
+        Some(key) => encryption::encrypt(key, &content)?,
+        None => content,
+    };
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+/// Deletes a snapshot.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+/// * `snapshot_id` - The ID of the snapshot
+///
+/// # Returns
+///
+/// * `Ok(true)` - If the snapshot was deleted
+/// * `Ok(false)` - If the environment has no snapshot with the ID
+/// * `Err(AppError::ValidationError)` - If the environment name or ID is not path-safe
+/// * `Err(AppError::Io)` - If the bundle cannot be deleted
+pub fn delete_snapshot(
+    data_path: &Path,
+    environment: &str,
+    snapshot_id: &str,
+) -> Result<bool, AppError> {
+    let path = snapshot_path(data_path, environment, snapshot_id)?;
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    fs::remove_file(path)?;
+    Ok(true)
+}
+
+/// Reads, decrypts and parses one snapshot bundle.
+fn read_snapshot(path: &Path, key: Option<&EncryptionKey>) -> Result<Snapshot, AppError> {
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let contents = fs::read(path).map_err(|e| load_error(e.to_string()))?;
+    let plaintext = encryption::decrypt(path, contents, key)?;
+    serde_json::from_slice(&plaintext).map_err(|e| load_error(e.to_string()))
+}
@@ -0,0 +1,371 @@
+//! SQLite storage backend.
+//!
+//! Stores every environment of a data path in a single database file, which
+//! avoids slow directory listings on network drives and makes concurrent
+//! writes transactional.
+//!
+//! # Schema
+//!
+//! ```sql
+//! environments  (name TEXT PRIMARY KEY)
+//! services      (environment, id, name, document)       -- PK (environment, id)
+//! relationships (environment, position, id, source, target, document)
+//! ```
+//!
+//! Full service and relationship objects (including metadata) are stored as
+//! JSON in the `document` column; the other columns exist for lookups and
+//! ordering. Service documents carry a `schemaVersion` and are migrated on
+//! load exactly like service files.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::error::AppError;
+use crate::models::{Relationship, Service};
+use crate::storage::backend::{FileSystemBackend, StorageBackend};
+use crate::storage::ids::{validate_environment_name, validate_service_id};
+use crate::storage::migrations::{self, DocumentKind, Versioned};
+
+/// Storage backend keeping all data in one SQLite database file.
+///
+/// A connection is opened per operation, so the backend itself holds no
+/// connection state and is trivially `Send + Sync`.
+#[derive(Debug, Clone)]
+pub struct SqliteBackend {
+    db_path: PathBuf,
+}
+
+/// Counts of entities copied by `import_directory`.
+///
+/// # Fields
+///
+/// * `environments` - Names of the environments that were imported
+/// * `services` - Total number of services imported
+/// * `relationships` - Total number of relationships imported
+#[derive(Debug, Clone, Default)]
+pub struct ImportCounts {
+    pub environments: Vec<String>,
+    pub services: usize,
+    pub relationships: usize,
+}
+
+impl SqliteBackend {
+    /// Creates a backend for a database file (created on first use).
+    pub fn new(db_path: PathBuf) -> Self {
+        Self { db_path }
+    }
+
+    /// Opens a connection and ensures the schema exists.
+    fn connect(&self) -> Result<Connection, AppError> {
+        let conn = Connection::open(&self.db_path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS environments (
+                 name TEXT PRIMARY KEY
+             );
+             CREATE TABLE IF NOT EXISTS services (
+                 environment TEXT NOT NULL,
+                 id TEXT NOT NULL,
+                 name TEXT NOT NULL,
+                 document TEXT NOT NULL,
+                 PRIMARY KEY (environment, id)
+             );
+             CREATE TABLE IF NOT EXISTS relationships (
+                 environment TEXT NOT NULL,
+                 position INTEGER NOT NULL,
+                 id TEXT NOT NULL,
+                 source TEXT NOT NULL,
+                 target TEXT NOT NULL,
+                 document TEXT NOT NULL,
+                 PRIMARY KEY (environment, position)
+             );",
+        )?;
+        Ok(conn)
+    }
+
+    /// Returns a location string used in load errors (`{db}#{environment}/{id}`).
+    fn location(&self, environment: &str, id: &str) -> String {
+        format!("{}#{}/{}", self.db_path.display(), environment, id)
+    }
+
+    /// Imports every environment of a data directory into this database.
+    ///
+    /// Each environment is written in a single transaction, replacing any
+    /// data the database already holds for an environment of the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The filesystem backend to read from
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ImportCounts)` - What was imported
+    /// * `Err(AppError)` - If reading the source or writing the database fails
+    pub fn import_directory(&self, source: &FileSystemBackend) -> Result<ImportCounts, AppError> {
+        let mut conn = self.connect()?;
+        let mut counts = ImportCounts::default();
+
+        for environment in source.list_environments()? {
+            let services = source.load_services(&environment)?;
+            let relationships = source.load_relationships(&environment)?;
+
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT OR IGNORE INTO environments (name) VALUES (?1)",
+                params![environment],
+            )?;
+            tx.execute(
+                "DELETE FROM services WHERE environment = ?1",
+                params![environment],
+            )?;
+            for service in &services {
+                insert_service(&tx, &environment, service)?;
+            }
+            replace_relationships(&tx, &environment, &relationships)?;
+            tx.commit()?;
+
+            counts.services += services.len();
+            counts.relationships += relationships.len();
+            counts.environments.push(environment);
+        }
+
+        Ok(counts)
+    }
+}
+
+impl StorageBackend for SqliteBackend {
+    fn load_services(&self, environment: &str) -> Result<Vec<Service>, AppError> {
+        validate_environment_name(environment)?;
+
+        let conn = self.connect()?;
+        let mut stmt = conn.prepare("SELECT id, document FROM services WHERE environment = ?1")?;
+        let rows = stmt.query_map(params![environment], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut services = Vec::new();
+        for row in rows {
+            let (id, document) = row?;
+            services.push(decode_service(&self.location(environment, &id), &document)?);
+        }
+
+        Ok(services)
+    }
+
+    fn load_service(&self, environment: &str, service_id: &str) -> Result<Service, AppError> {
+        validate_environment_name(environment)?;
+        validate_service_id(service_id)?;
+
+        let conn = self.connect()?;
+        let document: Option<String> = conn
+            .query_row(
+                "SELECT document FROM services WHERE environment = ?1 AND id = ?2",
+                params![environment, service_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match document {
+            Some(document) => decode_service(&self.location(environment, service_id), &document),
+            None => Err(AppError::ServiceNotFound(service_id.to_string())),
+        }
+    }
+
+    fn service_exists(&self, environment: &str, service_id: &str) -> Result<bool, AppError> {
+        validate_environment_name(environment)?;
+        validate_service_id(service_id)?;
+
+        let conn = self.connect()?;
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM services WHERE environment = ?1 AND id = ?2",
+                params![environment, service_id],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+
+        Ok(exists)
+    }
+
+    fn save_service(&self, environment: &str, service: &Service) -> Result<(), AppError> {
+        validate_environment_name(environment)?;
+        validate_service_id(&service.id)?;
+
+        let mut conn = self.connect()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO environments (name) VALUES (?1)",
+            params![environment],
+        )?;
+        insert_service(&tx, environment, service)?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn delete_service_file(&self, environment: &str, service_id: &str) -> Result<(), AppError> {
+        validate_environment_name(environment)?;
+        validate_service_id(service_id)?;
+
+        let conn = self.connect()?;
+        let deleted = conn.execute(
+            "DELETE FROM services WHERE environment = ?1 AND id = ?2",
+            params![environment, service_id],
+        )?;
+
+        if deleted == 0 {
+            return Err(AppError::ServiceNotFound(service_id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn load_relationships(&self, environment: &str) -> Result<Vec<Relationship>, AppError> {
+        validate_environment_name(environment)?;
+
+        let conn = self.connect()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, document FROM relationships WHERE environment = ?1 ORDER BY position",
+        )?;
+        let rows = stmt.query_map(params![environment], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut relationships = Vec::new();
+        for row in rows {
+            let (id, document) = row?;
+            let relationship = serde_json::from_str(&document).map_err(|e| AppError::FileLoad {
+                path: self.location(environment, &id),
+                reason: e.to_string(),
+            })?;
+            relationships.push(relationship);
+        }
+
+        Ok(relationships)
+    }
+
+    fn save_relationships(
+        &self,
+        environment: &str,
+        relationships: &[Relationship],
+    ) -> Result<(), AppError> {
+        validate_environment_name(environment)?;
+
+        let mut conn = self.connect()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO environments (name) VALUES (?1)",
+            params![environment],
+        )?;
+        replace_relationships(&tx, environment, relationships)?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn list_environments(&self) -> Result<Vec<String>, AppError> {
+        let conn = self.connect()?;
+        let mut stmt = conn.prepare("SELECT name FROM environments")?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(names)
+    }
+
+    fn environment_exists(&self, environment: &str) -> Result<bool, AppError> {
+        validate_environment_name(environment)?;
+
+        let conn = self.connect()?;
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM environments WHERE name = ?1",
+                params![environment],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+
+        Ok(exists)
+    }
+
+    fn create_environment(&self, environment: &str) -> Result<(), AppError> {
+        validate_environment_name(environment)?;
+
+        let conn = self.connect()?;
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO environments (name) VALUES (?1)",
+            params![environment],
+        )?;
+
+        if inserted == 0 {
+            return Err(AppError::EnvironmentExists(environment.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Uses the database file's modification time, so a write to any
+    /// environment marks every environment as changed.
+    fn last_modified(&self, _environment: &str) -> Option<SystemTime> {
+        fs::metadata(&self.db_path).and_then(|m| m.modified()).ok()
+    }
+}
+
+/// Inserts or replaces a service row.
+fn insert_service(conn: &Connection, environment: &str, service: &Service) -> Result<(), AppError> {
+    let document = serde_json::to_string(&Versioned::new(service))?;
+    conn.execute(
+        "INSERT INTO services (environment, id, name, document) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (environment, id) DO UPDATE SET name = excluded.name, document = excluded.document",
+        params![environment, service.id, service.name, document],
+    )?;
+    Ok(())
+}
+
+/// Replaces all relationship rows of an environment, preserving order.
+fn replace_relationships(
+    conn: &Connection,
+    environment: &str,
+    relationships: &[Relationship],
+) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM relationships WHERE environment = ?1",
+        params![environment],
+    )?;
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO relationships (environment, position, id, source, target, document)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    for (position, relationship) in relationships.iter().enumerate() {
+        let document = serde_json::to_string(relationship)?;
+        stmt.execute(params![
+            environment,
+            position as i64,
+            relationship.id,
+            relationship.source,
+            relationship.target,
+            document
+        ])?;
+    }
+
+    Ok(())
+}
+
+/// Decodes a stored service document, applying schema migrations.
+fn decode_service(location: &str, document: &str) -> Result<Service, AppError> {
+    let load_error = |reason: String| AppError::FileLoad {
+        path: location.to_string(),
+        reason,
+    };
+
+    let raw: Value = serde_json::from_str(document).map_err(|e| load_error(e.to_string()))?;
+    let migrated =
+        migrations::migrate(DocumentKind::Service, raw).map_err(|e| load_error(e.to_string()))?;
+
+    serde_json::from_value(migrated.document).map_err(|e| load_error(e.to_string()))
+}
@@ -0,0 +1,109 @@
+//! Persistence for validation issue suppressions.
+//!
+//! Each environment keeps its suppressions (see `models::Suppression`) in a
+//! hidden, pretty-printed `.suppressions.json` next to its data, so they are
+//! never loaded as services and the data directory watcher and storage report
+//! ignore them. For SQLite databases they are kept in a
+//! `{database}.suppressions/` directory next to the database file, one
+//! `{environment}.json` per environment.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::models::Suppression;
+use crate::storage::backend::{environment_sidecar_path, is_sqlite_path};
+use crate::storage::ids::validate_environment_name;
+
+/// File name of an environment's suppressions file.
+pub const SUPPRESSIONS_FILE: &str = ".suppressions.json";
+
+/// Returns the path of an environment's suppressions file.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/{environment}/.suppressions.json`, or
+///   `{database}.suppressions/{environment}.json` for SQLite databases
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+pub fn suppressions_path(data_path: &Path, environment: &str) -> Result<PathBuf, AppError> {
+    validate_environment_name(environment)?;
+
+    Ok(environment_sidecar_path(
+        data_path,
+        environment,
+        SUPPRESSIONS_FILE,
+    ))
+}
+
+/// Returns true if the file is an environment's suppressions file.
+///
+/// Used to keep suppressions out of data file scans.
+pub fn is_suppressions_file(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()) == Some(SUPPRESSIONS_FILE)
+}
+
+/// Loads an environment's suppressions.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+///
+/// # Returns
+///
+/// * `Ok(Vec<Suppression>)` - The suppressions, including expired ones (empty if none were saved)
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or parsed
+pub fn load_suppressions(
+    data_path: &Path,
+    environment: &str,
+) -> Result<Vec<Suppression>, AppError> {
+    let path = suppressions_path(data_path, environment)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(&path).map_err(|e| load_error(e.to_string()))?;
+    serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))
+}
+
+/// Saves an environment's suppressions, replacing the stored list.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `environment` - The name of the environment
+/// * `suppressions` - The complete list of suppressions
+///
+/// # Returns
+///
+/// * `Ok(())` - If the suppressions were written
+/// * `Err(AppError::ValidationError)` - If the environment name is not path-safe
+/// * `Err(AppError::Io)` - If the file cannot be written
+pub fn save_suppressions(
+    data_path: &Path,
+    environment: &str,
+    suppressions: &[Suppression],
+) -> Result<(), AppError> {
+    let path = suppressions_path(data_path, environment)?;
+    if is_sqlite_path(data_path) {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+
+    let content = serde_json::to_string_pretty(suppressions)?;
+    fs::write(path, content)?;
+
+    Ok(())
+}
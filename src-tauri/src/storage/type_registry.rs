@@ -0,0 +1,97 @@
+//! Persistence for the custom type registries.
+//!
+//! The registered custom service types and relationship types (see
+//! `models::CustomTypeDefinition`) are stored as pretty-printed
+//! `{data_path}/.types/service_types.json` and
+//! `{data_path}/.types/relationship_types.json`, shared by every
+//! environment. The directory is hidden, so it is never listed as an
+//! environment. For SQLite databases the registries are kept in a
+//! `{database}.types/` directory next to the database file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::models::{CustomTypeDefinition, CustomTypeKind};
+use crate::storage::backend::sidecar_path;
+
+/// Name of the registry directory within the data directory.
+pub const TYPES_DIR: &str = ".types";
+
+/// Returns the path of the registry of one kind of custom types.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `kind` - Which registry
+///
+/// # Returns
+///
+/// `{data_path}/.types/{kind}_types.json`, or `{database}.types/{kind}_types.json`
+/// for SQLite databases
+pub fn custom_types_path(data_path: &Path, kind: CustomTypeKind) -> PathBuf {
+    let dir = sidecar_path(data_path, TYPES_DIR);
+    dir.join(format!("{}_types.json", kind.as_str()))
+}
+
+/// Loads the registered custom types of one kind.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `kind` - Which registry
+///
+/// # Returns
+///
+/// * `Ok(Vec<CustomTypeDefinition>)` - The registered types, sorted by name (empty
+///   if none were registered)
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or parsed
+pub fn load_custom_types(
+    data_path: &Path,
+    kind: CustomTypeKind,
+) -> Result<Vec<CustomTypeDefinition>, AppError> {
+    let path = custom_types_path(data_path, kind);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(&path).map_err(|e| load_error(e.to_string()))?;
+    let mut types: Vec<CustomTypeDefinition> =
+        serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))?;
+    types.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(types)
+}
+
+/// Saves the registered custom types of one kind, replacing the stored registry.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `kind` - Which registry
+/// * `types` - The registered types
+///
+/// # Returns
+///
+/// * `Ok(())` - If the registry was written
+/// * `Err(AppError::Io)` - If the file cannot be written
+pub fn save_custom_types(
+    data_path: &Path,
+    kind: CustomTypeKind,
+    types: &[CustomTypeDefinition],
+) -> Result<(), AppError> {
+    let path = custom_types_path(data_path, kind);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let content = serde_json::to_string_pretty(types)?;
+    fs::write(path, content)?;
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Persistence for the validation rule configuration.
+//!
+//! The configuration (see `models::ValidationConfig`) is a pretty-printed
+//! JSON file at the root of the data directory, so it applies to every
+//! environment in it. For SQLite databases it is kept next to the database
+//! file as `{database}.validation.json`.
+//!
+//! The file is meant to be edited by hand as well, so loading is lenient:
+//! unknown keys and unknown rule names are skipped with a warning instead of
+//! making validation fail.
+
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::models::{IssueType, ValidationConfig};
+use crate::storage::backend::sidecar_path;
+
+/// File name of the validation config within the data directory.
+pub const VALIDATION_CONFIG_FILE: &str = ".validation.json";
+
+/// Returns the path of the validation config for a data path.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+pub fn validation_config_path(data_path: &Path) -> PathBuf {
+    sidecar_path(data_path, VALIDATION_CONFIG_FILE)
+}
+
+/// Loads the validation config of a data path.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+///
+/// # Returns
+///
+/// * `Ok(ValidationConfig)` - The stored config, or defaults if the file doesn't exist
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or isn't valid JSON
+pub fn load_validation_config(data_path: &Path) -> Result<ValidationConfig, AppError> {
+    let path = validation_config_path(data_path);
+    if !path.exists() {
+        return Ok(ValidationConfig::default());
+    }
+
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(&path).map_err(|e| load_error(e.to_string()))?;
+    let mut value: Value = serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))?;
+
+    drop_unknown_entries(&path, &mut value);
+
+    serde_json::from_value(value).map_err(|e| load_error(e.to_string()))
+}
+
+/// Saves the validation config of a data path.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `config` - The config to save
+///
+/// # Returns
+///
+/// * `Ok(())` - If the config was written
+/// * `Err(AppError::Io)` - If the file cannot be written
+pub fn save_validation_config(data_path: &Path, config: &ValidationConfig) -> Result<(), AppError> {
+    let content = serde_json::to_string_pretty(config)?;
+    fs::write(validation_config_path(data_path), content)?;
+
+    Ok(())
+}
+
+/// Removes keys (including those of `naming` and `ports`) and rule names the config doesn't
+/// know, warning about each.
+fn drop_unknown_entries(path: &Path, value: &mut Value) {
+    let Value::Object(fields) = value else {
+        // Left for deserialization to report
+        return;
+    };
+
+    // The default config serializes every known key
+    let known = match serde_json::to_value(ValidationConfig::default()) {
+        Ok(Value::Object(known)) => known,
+        _ => return,
+    };
+    let retain_known = |fields: &mut Map<String, Value>, known: &Map<String, Value>| {
+        fields.retain(|key, _| {
+            let is_known = known.contains_key(key);
+            if !is_known {
+                eprintln!(
+                    "Warning: ignoring unknown key '{}' in {}",
+                    key,
+                    path.display()
+                );
+            }
+            is_known
+        });
+    };
+    retain_known(fields, &known);
+    for section in ["naming", "ports"] {
+        if let (Some(Value::Object(nested)), Some(Value::Object(known_nested))) =
+            (fields.get_mut(section), known.get(section))
+        {
+            retain_known(nested, known_nested);
+        }
+    }
+
+    let is_rule = |name: &str| {
+        let is_rule = serde_json::from_value::<IssueType>(Value::from(name)).is_ok();
+        if !is_rule {
+            eprintln!(
+                "Warning: ignoring unknown validation rule '{}' in {}",
+                name,
+                path.display()
+            );
+        }
+        is_rule
+    };
+
+    if let Some(Value::Array(rules)) = fields.get_mut("disabledRules") {
+        rules.retain(|rule| match rule.as_str() {
+            Some(name) => is_rule(name),
+            None => true,
+        });
+    }
+    if let Some(Value::Object(overrides)) = fields.get_mut("severityOverrides") {
+        overrides.retain(|rule, _| is_rule(rule));
+    }
+}
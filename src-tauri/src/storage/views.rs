@@ -0,0 +1,155 @@
+//! Persistence for saved graph views.
+//!
+//! Each view (see `models::SavedView`) is stored as a pretty-printed
+//! `{data_path}/.views/{name}.json`, whichever environment it is of. The
+//! directory is hidden, so it is never listed as an environment. For SQLite
+//! databases the views are kept in a `{database}.views/` directory next to
+//! the database file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::models::SavedView;
+use crate::storage::backend::sidecar_path;
+use crate::storage::ids::validate_view_name;
+
+/// Name of the views directory within the data directory.
+pub const VIEWS_DIR: &str = ".views";
+
+/// Returns the directory the views are stored in.
+fn views_dir(data_path: &Path) -> PathBuf {
+    sidecar_path(data_path, VIEWS_DIR)
+}
+
+/// Returns the path of a saved view.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `name` - The name of the view
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - `{data_path}/.views/{name}.json`, or
+///   `{database}.views/{name}.json` for SQLite databases
+/// * `Err(AppError::ValidationError)` - If the name is not path-safe
+pub fn view_path(data_path: &Path, name: &str) -> Result<PathBuf, AppError> {
+    validate_view_name(name)?;
+
+    Ok(views_dir(data_path).join(format!("{}.json", name)))
+}
+
+/// Loads a saved view.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `name` - The name of the view
+///
+/// # Returns
+///
+/// * `Ok(Some(SavedView))` - The stored view
+/// * `Ok(None)` - If no view has the name
+/// * `Err(AppError::ValidationError)` - If the name is not path-safe
+/// * `Err(AppError::FileLoad)` - If the file exists but cannot be read or parsed
+pub fn load_view(data_path: &Path, name: &str) -> Result<Option<SavedView>, AppError> {
+    let path = view_path(data_path, name)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    read_view(&path).map(Some)
+}
+
+/// Loads all saved views.
+///
+/// Files that cannot be parsed are skipped with a warning, so one corrupt
+/// view doesn't hide the others.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+///
+/// # Returns
+///
+/// * `Ok(Vec<SavedView>)` - The views, sorted by name (empty if none were saved)
+/// * `Err(AppError::Io)` - If the views directory cannot be read
+pub fn load_views(data_path: &Path) -> Result<Vec<SavedView>, AppError> {
+    let dir = views_dir(data_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut views = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            match read_view(&path) {
+                Ok(view) => views.push(view),
+                Err(e) => eprintln!("Warning: Skipping saved view: {}", e),
+            }
+        }
+    }
+    views.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(views)
+}
+
+/// Saves a view, replacing the view with the same name.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `view` - The view to save
+///
+/// # Returns
+///
+/// * `Ok(())` - If the view was written
+/// * `Err(AppError::ValidationError)` - If the name is not path-safe
+/// * `Err(AppError::Io)` - If the file cannot be written
+pub fn save_view(data_path: &Path, view: &SavedView) -> Result<(), AppError> {
+    let path = view_path(data_path, &view.name)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let content = serde_json::to_string_pretty(view)?;
+    fs::write(path, content)?;
+
+    Ok(())
+}
+
+/// Deletes a saved view.
+///
+/// # Arguments
+///
+/// * `data_path` - The data directory or SQLite database file
+/// * `name` - The name of the view
+///
+/// # Returns
+///
+/// * `Ok(true)` - If the view was deleted
+/// * `Ok(false)` - If no view has the name
+/// * `Err(AppError::ValidationError)` - If the name is not path-safe
+/// * `Err(AppError::Io)` - If the file cannot be deleted
+pub fn delete_view(data_path: &Path, name: &str) -> Result<bool, AppError> {
+    let path = view_path(data_path, name)?;
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    fs::remove_file(path)?;
+    Ok(true)
+}
+
+/// Reads and parses one view file.
+fn read_view(path: &Path) -> Result<SavedView, AppError> {
+    let load_error = |reason: String| AppError::FileLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = fs::read_to_string(path).map_err(|e| load_error(e.to_string()))?;
+    serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))
+}
@@ -0,0 +1,295 @@
+//! Helpers shared by the unit tests.
+//!
+//! `TestApp` runs commands against a mock Tauri app whose data directory is
+//! a fresh temporary directory, so tests can call commands the way the
+//! frontend does and look at the files they leave behind.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::test::{mock_app, mock_context, noop_assets, MockRuntime};
+use tauri::{App, AppHandle, Builder, Manager, State};
+use tempfile::TempDir;
+
+use crate::error::AppError;
+use crate::models::{Relationship, RelationshipType, Service, ServiceStatus, ServiceType};
+use crate::state::{write_state, AppState};
+use crate::storage::{FileSystemBackend, StorageBackend};
+
+/// A mock app managing an `AppState` over a temporary data directory.
+///
+/// The directory is removed when the `TestApp` is dropped.
+pub struct TestApp {
+    pub app: App<MockRuntime>,
+    dir: TempDir,
+}
+
+impl TestApp {
+    /// Creates an app over an empty data directory.
+    pub fn new() -> Self {
+        Self::from_app(mock_app())
+    }
+
+    /// Creates an app from a builder, e.g. one with a command handler.
+    pub fn from_builder(builder: Builder<MockRuntime>) -> Self {
+        Self::from_app(builder.build(mock_context(noop_assets())).unwrap())
+    }
+
+    /// Creates an app storing its data in an empty SQLite database in the
+    /// temporary directory.
+    pub fn with_sqlite() -> Self {
+        Self::from_app_at(mock_app(), Some("data.db"))
+    }
+
+    fn from_app(app: App<MockRuntime>) -> Self {
+        Self::from_app_at(app, None)
+    }
+
+    /// Manages a state whose data path is the temporary directory, or the
+    /// file of the given name in it.
+    fn from_app_at(app: App<MockRuntime>, file_name: Option<&str>) -> Self {
+        let dir = TempDir::new().expect("failed to create a temporary directory");
+        let data_path = match file_name {
+            Some(file_name) => dir.path().join(file_name),
+            None => dir.path().to_path_buf(),
+        };
+        let state = AppState::new(data_path);
+        app.manage(Arc::clone(&state.running_operations));
+        app.manage(RwLock::new(state));
+        Self { app, dir }
+    }
+
+    /// Creates an app with one environment holding the given data.
+    pub fn with_environment(
+        environment: &str,
+        services: &[Service],
+        relationships: &[Relationship],
+    ) -> Self {
+        let test_app = Self::new();
+        test_app.create_environment(environment, services, relationships);
+        test_app
+    }
+
+    /// Creates an environment and writes the given data to it.
+    pub fn create_environment(
+        &self,
+        environment: &str,
+        services: &[Service],
+        relationships: &[Relationship],
+    ) {
+        let state = self.state();
+        let state = write_state(&state);
+        state.storage.create_environment(environment).unwrap();
+        for service in services {
+            state.storage.save_service(environment, service).unwrap();
+        }
+        state
+            .storage
+            .save_relationships(environment, relationships)
+            .unwrap();
+    }
+
+    /// Returns the app handle commands take.
+    pub fn handle(&self) -> AppHandle<MockRuntime> {
+        self.app.handle().clone()
+    }
+
+    /// Returns the managed application state, as commands take it.
+    pub fn state(&self) -> State<'_, RwLock<AppState>> {
+        self.app.state::<RwLock<AppState>>()
+    }
+
+    /// Returns the temporary directory, which is the data directory unless
+    /// the app uses SQLite.
+    pub fn data_path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// A storage backend that delays loads and saves, for testing what other
+/// commands can do while one waits on a slow disk. It also counts the
+/// service and relationship loads, to tell what was served from the caches.
+#[derive(Debug)]
+pub struct SlowStorage {
+    inner: Arc<dyn StorageBackend>,
+    load_delay: Duration,
+    save_delay: Duration,
+    service_loads: AtomicUsize,
+    relationship_loads: AtomicUsize,
+}
+
+impl SlowStorage {
+    /// Puts a slow backend in front of an app's storage.
+    ///
+    /// Every `load_services`, `load_service` and `load_relationships` call
+    /// sleeps `load_delay` first, every service save and relationships save
+    /// `save_delay`.
+    pub fn install(
+        test_app: &TestApp,
+        load_delay: Duration,
+        save_delay: Duration,
+    ) -> Arc<SlowStorage> {
+        let state = test_app.state();
+        let mut state = write_state(&state);
+        let storage = Arc::new(SlowStorage {
+            inner: state.storage.clone(),
+            load_delay,
+            save_delay,
+            service_loads: AtomicUsize::new(0),
+            relationship_loads: AtomicUsize::new(0),
+        });
+        state.storage = storage.clone();
+        storage
+    }
+
+    /// Returns how often all services of an environment were loaded.
+    pub fn service_loads(&self) -> usize {
+        self.service_loads.load(Ordering::SeqCst)
+    }
+
+    /// Returns how often all relationships of an environment were loaded.
+    pub fn relationship_loads(&self) -> usize {
+        self.relationship_loads.load(Ordering::SeqCst)
+    }
+}
+
+impl StorageBackend for SlowStorage {
+    fn load_services(&self, environment: &str) -> Result<Vec<Service>, AppError> {
+        self.service_loads.fetch_add(1, Ordering::SeqCst);
+        thread::sleep(self.load_delay);
+        self.inner.load_services(environment)
+    }
+
+    fn load_service(&self, environment: &str, service_id: &str) -> Result<Service, AppError> {
+        thread::sleep(self.load_delay);
+        self.inner.load_service(environment, service_id)
+    }
+
+    fn service_exists(&self, environment: &str, service_id: &str) -> Result<bool, AppError> {
+        self.inner.service_exists(environment, service_id)
+    }
+
+    fn save_service(&self, environment: &str, service: &Service) -> Result<(), AppError> {
+        thread::sleep(self.save_delay);
+        self.inner.save_service(environment, service)
+    }
+
+    fn delete_service_file(&self, environment: &str, service_id: &str) -> Result<(), AppError> {
+        self.inner.delete_service_file(environment, service_id)
+    }
+
+    fn load_relationships(&self, environment: &str) -> Result<Vec<Relationship>, AppError> {
+        self.relationship_loads.fetch_add(1, Ordering::SeqCst);
+        thread::sleep(self.load_delay);
+        self.inner.load_relationships(environment)
+    }
+
+    fn save_relationships(
+        &self,
+        environment: &str,
+        relationships: &[Relationship],
+    ) -> Result<(), AppError> {
+        thread::sleep(self.save_delay);
+        self.inner.save_relationships(environment, relationships)
+    }
+
+    fn list_environments(&self) -> Result<Vec<String>, AppError> {
+        self.inner.list_environments()
+    }
+
+    fn environment_exists(&self, environment: &str) -> Result<bool, AppError> {
+        self.inner.environment_exists(environment)
+    }
+
+    fn create_environment(&self, environment: &str) -> Result<(), AppError> {
+        self.inner.create_environment(environment)
+    }
+
+    fn last_modified(&self, environment: &str) -> Option<SystemTime> {
+        self.inner.last_modified(environment)
+    }
+
+    fn as_file_system(&self) -> Option<&FileSystemBackend> {
+        self.inner.as_file_system()
+    }
+}
+
+/// Runs an async command to completion.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tauri::async_runtime::block_on(future)
+}
+
+/// Returns the contents of every file under a directory, by path.
+///
+/// Two snapshots compare equal only if no file was added, removed or
+/// changed in between.
+pub fn snapshot_dir(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.insert(path.clone(), fs::read(&path).unwrap());
+            }
+        }
+    }
+    files
+}
+
+/// Serializes a command result with its `dryRun` flag left out, so a dry
+/// run's result can be compared with the real run's.
+pub fn without_dry_run<T: Serialize>(result: &T) -> serde_json::Value {
+    let mut value = serde_json::to_value(result).unwrap();
+    value.as_object_mut().unwrap().remove("dryRun");
+    value
+}
+
+/// Builds a backend service with only an ID and a name.
+pub fn service(id: &str) -> Service {
+    Service {
+        id: id.to_string(),
+        name: id.to_string(),
+        service_type: ServiceType::Backend,
+        status: ServiceStatus::Healthy,
+        description: None,
+        version: None,
+        owner: None,
+        team: None,
+        tier: None,
+        lifecycle: None,
+        decommission_date: None,
+        successor_id: None,
+        tags: Vec::new(),
+        metadata: HashMap::new(),
+        revision: 0,
+    }
+}
+
+/// Builds a `depends_on` relationship.
+pub fn relationship(id: &str, source: &str, target: &str) -> Relationship {
+    Relationship {
+        id: id.to_string(),
+        source: source.to_string(),
+        target: target.to_string(),
+        relationship_type: RelationshipType::DependsOn,
+        description: None,
+        metadata: None,
+        effective_from: None,
+        effective_until: None,
+        origin: Default::default(),
+        review_status: Default::default(),
+        review_note: None,
+        reviewed_by: None,
+        reviewed_at: None,
+        revision: 0,
+    }
+}
@@ -0,0 +1,255 @@
+//! Filesystem watcher for the data directory.
+//!
+//! This module watches the data directory for changes made outside the
+//! application (e.g., `git pull`, scripts, manual edits) and invalidates the
+//! affected environment caches so subsequent queries read fresh data. A
+//! `data-changed` event is emitted to the frontend for each affected
+//! environment so it can re-query.
+//!
+//! # Debouncing
+//!
+//! A single `git pull` can touch hundreds of files. Raw filesystem events are
+//! collected on a background thread and flushed once the directory has been
+//! quiet for `DEBOUNCE_WINDOW` (or after `MAX_BURST_DURATION` of continuous
+//! activity), producing at most one event per environment and change kind.
+//!
+//! # Self-Inflicted Writes
+//!
+//! Commands that write to disk already keep the cache up to date. Changes to
+//! environments that were written by the application within the self-write
+//! grace period (see `AppState::record_write`) are ignored.
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::state::{write_state, AppState};
+use crate::storage::environment_metadata::ENVIRONMENT_METADATA_FILE;
+use crate::storage::{annotations, audit, snapshots, suppressions};
+
+/// Name of the event emitted to the frontend when data changes on disk.
+pub const DATA_CHANGED_EVENT: &str = "data-changed";
+
+/// How long the data directory must be quiet before pending changes are flushed.
+const DEBOUNCE_WINDOW: Duration = Duration::from_millis(500);
+
+/// Upper bound on how long a continuous burst of events can delay a flush.
+const MAX_BURST_DURATION: Duration = Duration::from_secs(5);
+
+/// The part of an environment that changed on disk.
+///
+/// # Variants
+///
+/// * `Services` - A file in the environment's `services/` directory changed
+/// * `Relationships` - The environment's `relationships.json`, one of its relationship
+///   shards or its `environment.json` (which selects the layout) changed
+/// * `Environment` - The environment directory itself (or another file in it) changed
+///
+/// # Serialization
+///
+/// Kinds are serialized as snake_case strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataChangeKind {
+    Services,
+    Relationships,
+    Environment,
+}
+
+/// Payload of the `data-changed` event.
+///
+/// # Fields
+///
+/// * `environment` - The name of the environment whose data changed
+/// * `kind` - Which part of the environment changed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataChangedPayload {
+    pub environment: String,
+    pub kind: DataChangeKind,
+}
+
+/// Owns the underlying filesystem watcher for the current data path.
+///
+/// Managed as Tauri state (`Mutex<DataWatcher>`) so `set_data_path` can
+/// re-arm it when the data directory changes.
+pub struct DataWatcher {
+    watcher: RecommendedWatcher,
+    watched_path: Option<PathBuf>,
+}
+
+impl DataWatcher {
+    /// Starts watching the data directory and spawns the debounce thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - Handle used to access application state and emit events
+    /// * `data_path` - The root data directory to watch recursively
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DataWatcher)` - The running watcher
+    /// * `Err(notify::Error)` - If the platform watcher could not be created
+    pub fn start<R: Runtime>(app: AppHandle<R>, data_path: &Path) -> notify::Result<Self> {
+        let (tx, rx) = channel::<notify::Result<Event>>();
+        let watcher = notify::recommended_watcher(tx)?;
+
+        thread::spawn(move || debounce_loop(app, rx));
+
+        let mut data_watcher = Self {
+            watcher,
+            watched_path: None,
+        };
+        data_watcher.watch(data_path)?;
+
+        Ok(data_watcher)
+    }
+
+    /// Re-arms the watcher on a new data directory.
+    ///
+    /// Stops watching the previous root (if any) and starts watching the new
+    /// one. Directories that don't exist yet are not watched.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path` - The new root data directory
+    pub fn watch(&mut self, data_path: &Path) -> notify::Result<()> {
+        if let Some(previous) = self.watched_path.take() {
+            // The previous root may have been removed; nothing to do then.
+            let _ = self.watcher.unwatch(&previous);
+        }
+
+        if data_path.is_dir() {
+            self.watcher.watch(data_path, RecursiveMode::Recursive)?;
+            self.watched_path = Some(data_path.to_path_buf());
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects raw filesystem events and flushes them after a quiet period.
+///
+/// Runs until the watcher (and therefore the sending half of the channel)
+/// is dropped.
+fn debounce_loop<R: Runtime>(app: AppHandle<R>, rx: Receiver<notify::Result<Event>>) {
+    let mut pending: Vec<PathBuf> = Vec::new();
+    let mut burst_started = Instant::now();
+
+    loop {
+        let received = if pending.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(DEBOUNCE_WINDOW)
+        };
+
+        match received {
+            Ok(Ok(event)) => {
+                if is_relevant(&event.kind) {
+                    if pending.is_empty() {
+                        burst_started = Instant::now();
+                    }
+                    pending.extend(event.paths);
+                }
+            }
+            Ok(Err(e)) => eprintln!("Warning: data directory watcher error: {}", e),
+            Err(RecvTimeoutError::Timeout) => flush(&app, std::mem::take(&mut pending)),
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if !pending.is_empty() && burst_started.elapsed() >= MAX_BURST_DURATION {
+            flush(&app, std::mem::take(&mut pending));
+        }
+    }
+}
+
+/// Returns true for event kinds that can change the data we serve.
+fn is_relevant(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    )
+}
+
+/// Invalidates caches for the environments touched by `paths` and notifies the frontend.
+fn flush<R: Runtime>(app: &AppHandle<R>, paths: Vec<PathBuf>) {
+    let state = app.state::<RwLock<AppState>>();
+    let mut state = write_state(&state);
+
+    let data_path = state.data_path.clone();
+    let canonical_data_path = data_path.canonicalize().ok();
+
+    let changes: HashSet<(String, DataChangeKind)> = paths
+        .iter()
+        .filter_map(|path| {
+            classify_path(&data_path, path).or_else(|| {
+                canonical_data_path
+                    .as_deref()
+                    .and_then(|root| classify_path(root, path))
+            })
+        })
+        .collect();
+
+    let mut notifications = Vec::new();
+    for (environment, kind) in changes {
+        if state.is_recent_write(&environment) {
+            continue;
+        }
+        state.clear_environment_cache(&environment);
+        notifications.push(DataChangedPayload { environment, kind });
+    }
+
+    // Release the lock before emitting so listeners can query immediately
+    drop(state);
+
+    for payload in notifications {
+        if let Err(e) = app.emit(DATA_CHANGED_EVENT, payload) {
+            eprintln!(
+                "Warning: failed to emit {} event: {}",
+                DATA_CHANGED_EVENT, e
+            );
+        }
+    }
+}
+
+/// Maps a changed path to the environment and part of it that changed.
+///
+/// Returns `None` for paths outside the data directory, the data directory
+/// itself, hidden directories (which are never environments), audit logs,
+/// suppression files, annotation files and snapshots (which aren't data).
+fn classify_path(data_path: &Path, path: &Path) -> Option<(String, DataChangeKind)> {
+    if audit::is_audit_log_file(path)
+        || suppressions::is_suppressions_file(path)
+        || annotations::is_annotations_file(path)
+        || snapshots::is_snapshot_path(path)
+    {
+        return None;
+    }
+
+    let relative = path.strip_prefix(data_path).ok()?;
+    let mut components = relative.components().filter_map(|c| match c {
+        Component::Normal(name) => name.to_str(),
+        _ => None,
+    });
+
+    let environment = components.next()?;
+    if environment.starts_with('.') {
+        return None;
+    }
+
+    let kind = match components.next() {
+        Some("services") => DataChangeKind::Services,
+        Some("relationships.json" | "relationships" | ENVIRONMENT_METADATA_FILE) => {
+            DataChangeKind::Relationships
+        }
+        _ => DataChangeKind::Environment,
+    };
+
+    Some((environment.to_string(), kind))
+}
@@ -0,0 +1,603 @@
+//! Webhook notifications about data changes.
+//!
+//! When a webhook is configured (see `models::WebhookConfig`), write
+//! commands queue a notification after they succeed and a background thread
+//! (`WebhookSender`) POSTs it as JSON, so a slow or unreachable receiver
+//! never delays a command response.
+//!
+//! # Events
+//!
+//! - `dataChanged` - Sent for every command that changed services or relationships
+//! - `serviceDeleted` - Sent for every deleted service
+//! - `validationErrorsIncreased` - After each data change, the background thread
+//!   validates the environment and notifies when its error count rose. Counts
+//!   are remembered per data path and environment from startup, and recorded
+//!   again whenever the webhook is configured or the data path or workspace
+//!   changes; environments without a baseline count as having no errors.
+//!
+//! # Delivery
+//!
+//! Each notification is tried up to `MAX_ATTEMPTS` times, waiting longer
+//! before every retry, and is then dropped with a logged warning. Failed
+//! notifications are never reported as command errors. With a secret the
+//! `X-Webhook-Signature` header carries `sha256={hex}`, the HMAC-SHA256 of
+//! the body keyed by the secret.
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::RwLock;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::commands::{environments, validation};
+use crate::error::AppError;
+use crate::models::{WebhookConfig, WebhookEvent};
+use crate::state::{read_state, AppState};
+use crate::storage::audit::now_millis;
+use crate::storage::encryption::to_hex;
+
+/// Header carrying the HMAC signature of a request body.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// How often a notification is tried before it is dropped.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry; doubled before every further one.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// How long a request may take before it counts as failed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body of a webhook request.
+///
+/// # Fields
+///
+/// * `environment` - The environment the event happened in
+/// * `event` - What happened
+/// * `summary` - Human-readable description, e.g. "Delete service user-api in prod"
+/// * `timestamp` - When the event happened, in milliseconds since the Unix epoch
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookPayload {
+    pub environment: String,
+    pub event: WebhookEvent,
+    pub summary: String,
+    pub timestamp: u64,
+}
+
+/// Work for the background thread.
+enum WebhookJob {
+    /// Sends a notification.
+    Send {
+        config: WebhookConfig,
+        payload: WebhookPayload,
+    },
+    /// Validates an environment that changed and notifies if its error count rose.
+    ///
+    /// Skipped if the data path changed since the job was queued.
+    CheckValidation {
+        config: WebhookConfig,
+        data_path: PathBuf,
+        environment: String,
+        summary: String,
+    },
+    /// Records the current error count of every environment at the current
+    /// data path as its baseline, if a webhook subscribes to
+    /// `ValidationErrorsIncreased`.
+    RecordBaselines,
+    /// Answers once every job queued before it has run.
+    #[cfg(test)]
+    Flush(Sender<()>),
+}
+
+/// Handle to the background webhook thread.
+///
+/// Managed as Tauri state; `notify` looks it up to queue notifications.
+pub struct WebhookSender {
+    sender: Sender<WebhookJob>,
+}
+
+impl WebhookSender {
+    /// Spawns the background webhook thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - Handle used to read the application state for validation
+    pub fn start<R: Runtime>(app: AppHandle<R>) -> Self {
+        let (sender, receiver) = channel::<WebhookJob>();
+
+        thread::spawn(move || send_loop(app, receiver));
+
+        let webhook_sender = Self { sender };
+        webhook_sender.record_baselines();
+        webhook_sender
+    }
+
+    /// Queues recording the current validation error counts of all environments.
+    ///
+    /// Called at startup, when the webhook is configured and when the data
+    /// path changes, so the first change afterwards is compared against the
+    /// data as it was.
+    pub fn record_baselines(&self) {
+        if self.sender.send(WebhookJob::RecordBaselines).is_err() {
+            eprintln!("Warning: webhook thread is not running");
+        }
+    }
+
+    /// Waits until every job queued so far has run.
+    #[cfg(test)]
+    fn flush(&self) {
+        let (done, finished) = channel();
+        self.sender.send(WebhookJob::Flush(done)).unwrap();
+        finished.recv().unwrap();
+    }
+}
+
+/// Queues a notification, if a webhook subscribes to the event.
+///
+/// Does nothing when no webhook is configured or the webhook thread isn't
+/// running. A `DataChanged` event also queues the validation check for
+/// `ValidationErrorsIncreased` subscribers. Call this after the write
+/// succeeded, next to `git::auto_commit`.
+///
+/// # Arguments
+///
+/// * `app` - Handle used to reach the webhook thread
+/// * `state` - The application state (settings)
+/// * `environment` - The environment the event happened in
+/// * `event` - What happened
+/// * `summary` - Human-readable description (e.g., "Update service user-api in dev")
+pub fn notify<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    environment: &str,
+    event: WebhookEvent,
+    summary: String,
+) {
+    let Some(config) = &state.settings.webhook else {
+        return;
+    };
+    let Some(webhook_sender) = app.try_state::<WebhookSender>() else {
+        return;
+    };
+
+    let mut jobs = Vec::new();
+    if event == WebhookEvent::DataChanged
+        && config.subscribes_to(WebhookEvent::ValidationErrorsIncreased)
+    {
+        jobs.push(WebhookJob::CheckValidation {
+            config: config.clone(),
+            data_path: state.data_path.clone(),
+            environment: environment.to_string(),
+            summary: summary.clone(),
+        });
+    }
+    if config.subscribes_to(event) {
+        jobs.push(WebhookJob::Send {
+            config: config.clone(),
+            payload: WebhookPayload {
+                environment: environment.to_string(),
+                event,
+                summary,
+                timestamp: now_millis(),
+            },
+        });
+    }
+
+    for job in jobs {
+        if webhook_sender.sender.send(job).is_err() {
+            eprintln!("Warning: webhook thread is not running");
+        }
+    }
+}
+
+/// Sends one webhook request, without retrying.
+///
+/// # Arguments
+///
+/// * `config` - The webhook to send to
+/// * `payload` - The notification to send
+///
+/// # Returns
+///
+/// * `Ok(())` - If the receiver answered with a success status
+/// * `Err(AppError::WebhookFailed)` - If the request failed or got an error status
+pub fn send(config: &WebhookConfig, payload: &WebhookPayload) -> Result<(), AppError> {
+    let body = serde_json::to_string(payload)?;
+    let failed = |reason: String| AppError::WebhookFailed {
+        url: config.url.clone(),
+        reason,
+    };
+
+    let mut request = ureq::post(&config.url)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Content-Type", "application/json");
+    if let Some(secret) = config.secret.as_deref().filter(|s| !s.is_empty()) {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(secret.as_bytes()).map_err(|e| failed(e.to_string()))?;
+        mac.update(body.as_bytes());
+        let signature = format!("sha256={}", to_hex(&mac.finalize().into_bytes()));
+        request = request.set(SIGNATURE_HEADER, &signature);
+    }
+
+    request
+        .send_string(&body)
+        .map(|_| ())
+        .map_err(|e| failed(e.to_string()))
+}
+
+/// Runs jobs in the order they were queued until the app shuts down.
+fn send_loop<R: Runtime>(app: AppHandle<R>, receiver: Receiver<WebhookJob>) {
+    // Keyed by data path too, as workspaces can have environments of the same name
+    let mut error_counts: HashMap<(PathBuf, String), usize> = HashMap::new();
+
+    for job in receiver {
+        match job {
+            WebhookJob::Send { config, payload } => send_with_retries(&config, &payload),
+            WebhookJob::CheckValidation {
+                config,
+                data_path,
+                environment,
+                summary,
+            } => {
+                if read_state(&app.state::<RwLock<AppState>>()).data_path != data_path {
+                    continue;
+                }
+                let Some(errors) = error_count(&app, &environment) else {
+                    continue;
+                };
+                let previous = error_counts
+                    .insert((data_path, environment.clone()), errors)
+                    .unwrap_or(0);
+                if errors > previous {
+                    let payload = WebhookPayload {
+                        summary: format!(
+                            "Validation errors in {} rose from {} to {} after: {}",
+                            environment, previous, errors, summary
+                        ),
+                        environment,
+                        event: WebhookEvent::ValidationErrorsIncreased,
+                        timestamp: now_millis(),
+                    };
+                    send_with_retries(&config, &payload);
+                }
+            }
+            WebhookJob::RecordBaselines => {
+                let (data_path, environments) = {
+                    let state = app.state::<RwLock<AppState>>();
+                    let state = read_state(&state);
+                    let subscribed = state.settings.webhook.as_ref().is_some_and(|config| {
+                        config.subscribes_to(WebhookEvent::ValidationErrorsIncreased)
+                    });
+                    if !subscribed {
+                        continue;
+                    }
+                    let environments =
+                        environments::sorted_environments(&state).unwrap_or_default();
+                    (state.data_path.clone(), environments)
+                };
+                error_counts.retain(|(path, _), _| *path != data_path);
+                for environment in environments {
+                    if let Some(errors) = error_count(&app, &environment) {
+                        error_counts.insert((data_path.clone(), environment), errors);
+                    }
+                }
+            }
+            #[cfg(test)]
+            WebhookJob::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+/// Validates an environment, returning its error count (`None` if it can't be validated).
+fn error_count<R: Runtime>(app: &AppHandle<R>, environment: &str) -> Option<usize> {
+    let state = app.state::<RwLock<AppState>>();
+    let result = validation::run_validation(&state, environment);
+    match result {
+        Ok(result) => Some(result.error_count),
+        Err(e) => {
+            eprintln!(
+                "Warning: cannot validate {} for webhooks: {}",
+                environment, e
+            );
+            None
+        }
+    }
+}
+
+/// Sends a notification, retrying with backoff, and logs it if it never goes through.
+fn send_with_retries(config: &WebhookConfig, payload: &WebhookPayload) {
+    if let Err(e) = retry_send(config, payload) {
+        eprintln!(
+            "Warning: dropping webhook notification after {} attempts: {}",
+            MAX_ATTEMPTS, e
+        );
+    }
+}
+
+/// Tries a notification up to `MAX_ATTEMPTS` times, returning the error of the last attempt.
+fn retry_send(config: &WebhookConfig, payload: &WebhookPayload) -> Result<(), AppError> {
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match send(config, payload) {
+            Err(_) if attempt < MAX_ATTEMPTS => {
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::relationships::save_relationship;
+    use crate::commands::services::{delete_service, save_service};
+    use crate::commands::workspaces::{add_workspace, switch_workspace};
+    use crate::state::write_state;
+    use crate::test_support::{relationship, service, TestApp};
+    use tempfile::TempDir;
+    use tiny_http::{Response, Server};
+
+    /// A webhook request as the receiver got it.
+    struct Received {
+        payload: serde_json::Value,
+        body: String,
+        signature: Option<String>,
+    }
+
+    /// Receives the next webhook request and answers it with `status`.
+    fn receive(server: &Server, status: u16) -> Received {
+        let mut request = server
+            .recv_timeout(Duration::from_secs(30))
+            .unwrap()
+            .expect("no webhook request arrived");
+        let signature = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv(SIGNATURE_HEADER))
+            .map(|h| h.value.to_string());
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body).unwrap();
+        request.respond(Response::empty(status)).unwrap();
+
+        Received {
+            payload: serde_json::from_str(&body).unwrap(),
+            body,
+            signature,
+        }
+    }
+
+    /// Receives the next webhook request and returns the summary it carries.
+    fn next_summary(server: &Server) -> String {
+        let payload = receive(server, 200).payload;
+        assert_eq!(payload["event"], "validationErrorsIncreased");
+        payload["summary"].as_str().unwrap().to_string()
+    }
+
+    /// Starts a receiver and returns it with a webhook sending it `events`.
+    fn receiver(secret: Option<&str>, events: &[WebhookEvent]) -> (Server, WebhookConfig) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let config = WebhookConfig {
+            url: format!("http://127.0.0.1:{port}/hook"),
+            secret: secret.map(str::to_string),
+            events: events.to_vec(),
+        };
+        (server, config)
+    }
+
+    fn sample_payload(event: WebhookEvent) -> WebhookPayload {
+        WebhookPayload {
+            environment: "dev".to_string(),
+            event,
+            summary: "Update service api in dev".to_string(),
+            timestamp: 1_700_000_000_000,
+        }
+    }
+
+    /// Creates an app with `api` and `db` in dev that notifies `config`.
+    fn notifying_app(config: WebhookConfig) -> TestApp {
+        let app = TestApp::with_environment("dev", &[service("api"), service("db")], &[]);
+        write_state(&app.state()).settings.webhook = Some(config);
+        app.app.manage(WebhookSender::start(app.handle()));
+        app
+    }
+
+    #[test]
+    fn requests_are_signed_with_the_secret() {
+        let (server, config) = receiver(Some("shared-secret"), &[WebhookEvent::Test]);
+        let received = thread::scope(|scope| {
+            let sent = scope.spawn(|| send(&config, &sample_payload(WebhookEvent::Test)));
+            let received = receive(&server, 200);
+            sent.join().unwrap().unwrap();
+            received
+        });
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"shared-secret").unwrap();
+        mac.update(received.body.as_bytes());
+        let expected = format!("sha256={}", to_hex(&mac.finalize().into_bytes()));
+        assert_eq!(received.signature.as_deref(), Some(expected.as_str()));
+
+        // Without a secret, requests go out unsigned
+        let unsigned = WebhookConfig {
+            secret: None,
+            ..config
+        };
+        let received = thread::scope(|scope| {
+            let sent = scope.spawn(|| send(&unsigned, &sample_payload(WebhookEvent::Test)));
+            let received = receive(&server, 200);
+            sent.join().unwrap().unwrap();
+            received
+        });
+        assert_eq!(received.signature, None);
+    }
+
+    #[test]
+    fn deleting_a_service_sends_data_changed_then_service_deleted() {
+        let (server, config) = receiver(
+            None,
+            &[WebhookEvent::DataChanged, WebhookEvent::ServiceDeleted],
+        );
+        let app = notifying_app(config);
+
+        delete_service(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            "api".to_string(),
+        )
+        .unwrap();
+
+        for event in ["dataChanged", "serviceDeleted"] {
+            let payload = receive(&server, 200).payload;
+            let fields: Vec<&str> = payload
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect();
+            assert_eq!(fields, ["environment", "event", "summary", "timestamp"]);
+            assert_eq!(payload["environment"], "dev");
+            assert_eq!(payload["event"], event);
+            assert_eq!(payload["summary"], "Delete service api in dev");
+            assert!(payload["timestamp"].as_u64().unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn only_subscribed_events_are_sent() {
+        let (server, config) = receiver(None, &[WebhookEvent::ServiceDeleted]);
+        let app = notifying_app(config);
+
+        // Saving only changes data, which the webhook didn't subscribe to
+        save_service(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            service("web"),
+            None,
+        )
+        .unwrap();
+        delete_service(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            "db".to_string(),
+        )
+        .unwrap();
+
+        let payload = receive(&server, 200).payload;
+        assert_eq!(payload["event"], "serviceDeleted");
+        assert_eq!(payload["summary"], "Delete service db in dev");
+        app.app.state::<WebhookSender>().flush();
+        assert!(server.try_recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn failed_requests_are_retried_then_given_up() {
+        let (server, config) = receiver(None, &[WebhookEvent::DataChanged]);
+
+        // A receiver that keeps failing gets every attempt, then the error
+        let result = thread::scope(|scope| {
+            let sent =
+                scope.spawn(|| retry_send(&config, &sample_payload(WebhookEvent::DataChanged)));
+            for _ in 0..MAX_ATTEMPTS {
+                assert_eq!(receive(&server, 500).payload["event"], "dataChanged");
+            }
+            sent.join().unwrap()
+        });
+        match result {
+            Err(AppError::WebhookFailed { url, reason }) => {
+                assert_eq!(url, config.url);
+                assert!(reason.contains("500"), "{reason}");
+            }
+            other => panic!("expected WebhookFailed, got {other:?}"),
+        }
+        assert!(server.try_recv().unwrap().is_none());
+
+        // One that recovers isn't sent the notification again
+        thread::scope(|scope| {
+            let sent =
+                scope.spawn(|| retry_send(&config, &sample_payload(WebhookEvent::DataChanged)));
+            receive(&server, 500);
+            receive(&server, 200);
+            sent.join().unwrap().unwrap();
+        });
+        assert!(server.try_recv().unwrap().is_none());
+    }
+
+    fn add_orphan(app: &TestApp, id: &str) {
+        save_relationship(
+            app.handle(),
+            app.state(),
+            "dev".to_string(),
+            relationship(id, "api", &format!("{id}-target")),
+            None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn error_counts_of_same_named_environments_stay_apart() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+
+        // Workspace "a" starts with two errors in dev, workspace "b" with none
+        let app = TestApp::new();
+        let workspaces = [TempDir::new().unwrap(), TempDir::new().unwrap()];
+        for (name, dir) in ["a", "b"].iter().zip(&workspaces) {
+            let path = dir.path().display().to_string();
+            add_workspace(app.state(), name.to_string(), path).unwrap();
+        }
+        switch_workspace(app.handle(), app.state(), "a".to_string()).unwrap();
+        app.create_environment(
+            "dev",
+            &[service("api")],
+            &[
+                relationship("orphan-1", "api", "gone-1"),
+                relationship("orphan-2", "api", "gone-2"),
+            ],
+        );
+        switch_workspace(app.handle(), app.state(), "b".to_string()).unwrap();
+        app.create_environment("dev", &[service("api")], &[]);
+
+        write_state(&app.state()).settings.webhook = Some(WebhookConfig {
+            url: format!("http://127.0.0.1:{port}/hook"),
+            secret: None,
+            events: vec![WebhookEvent::ValidationErrorsIncreased],
+        });
+        app.app.manage(WebhookSender::start(app.handle()));
+        let flush = || app.app.state::<WebhookSender>().flush();
+
+        // Changes must not race the baselines they're compared with
+        flush();
+
+        // b's dev is compared with its own baseline, not a's two errors
+        add_orphan(&app, "orphan-b");
+        assert!(
+            next_summary(&server).starts_with("Validation errors in dev rose from 0 to 1"),
+            "b's first error was not reported"
+        );
+
+        // Back in a, its own baseline applies again, not b's single error
+        switch_workspace(app.handle(), app.state(), "a".to_string()).unwrap();
+        flush();
+        add_orphan(&app, "orphan-a");
+        let summary = next_summary(&server);
+        assert!(
+            summary.starts_with("Validation errors in dev rose from 2 to 3"),
+            "{summary}"
+        );
+    }
+}
@@ -22,6 +22,7 @@ import { ValidationPanel } from "./components/validation";
 import { useServicesStore, useGraphStore, useNavigationStore } from "./store";
 import * as api from "./services/tauri"
 import { transformToGraphElements } from "./services/graphTransforms";
+import { errorMessage } from "./utils/errors";
 
 /**
  * Root application component.
@@ -73,7 +74,7 @@ function App() {
           await loadGraph(services[0].id);
         }
       } catch (error) {
-        const message = error instanceof Error ? error.message : "Failed to initialize";
+        const message = errorMessage(error);
         setServicesError(message);
         toast({
           title: "Initialization Error",
@@ -98,8 +99,7 @@ function App() {
 
         const graphData = await api.getServiceGraph(
           currentEnvironment,
-          serviceId,
-          1
+          serviceId
         );
 
         const elements = transformToGraphElements(graphData);
@@ -107,7 +107,7 @@ function App() {
         setCenterNode(serviceId, graphData.centerService);
         pushNavigation(serviceId);
       } catch (error) {
-        const message = error instanceof Error ? error.message : "Failed to load graph";
+        const message = errorMessage(error);
         setGraphError(message);
         toast({
           title: "Graph Error",
@@ -47,7 +47,7 @@ import { useEditorStore } from "@/store/editorStore";
  * Displays:
  * - Service name, type, and status
  * - Description
- * - Version, owner, and team
+ * - Version, owner, team, and tier
  * - Tags
  * - Custom metadata
  *
@@ -222,6 +222,15 @@ export function ServiceDetailPanel() {
               </Box>
             )}
 
+            {service.tier !== undefined && service.tier !== null && (
+              <Box>
+                <Text fontSize="xs" color={labelColor} textTransform="uppercase">
+                  Tier
+                </Text>
+                <Text fontSize="sm">Tier {service.tier}</Text>
+              </Box>
+            )}
+
             {service.tags.length > 0 && (
               <Box>
                 <Text fontSize="xs" color={labelColor} textTransform="uppercase" mb={1}>
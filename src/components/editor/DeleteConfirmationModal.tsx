@@ -24,6 +24,7 @@ import { useEditorStore } from "@/store/editorStore";
 import { useServicesStore } from "@/store/servicesStore";
 import { useGraphStore } from "@/store/graphStore";
 import { deleteService, deleteRelationship, deleteRelationshipsForService } from "@/services/tauri";
+import { errorMessage } from "@/utils/errors";
 
 /**
  * Confirmation modal for deleting services or relationships.
@@ -102,7 +103,7 @@ export function DeleteConfirmationModal() {
     } catch (error) {
       toast({
         title: `Error deleting ${type}`,
-        description: String(error),
+        description: errorMessage(error),
         status: "error",
         duration: 5000,
         isClosable: true,
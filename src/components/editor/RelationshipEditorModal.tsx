@@ -23,7 +23,8 @@ import { RelationshipForm } from "./RelationshipForm";
 import { useEditorStore } from "@/store/editorStore";
 import { useServicesStore } from "@/store/servicesStore";
 import { useGraphStore } from "@/store/graphStore";
-import { saveRelationship } from "@/services/tauri";
+import { generateId, saveRelationship } from "@/services/tauri";
+import { errorMessage } from "@/utils/errors";
 import type { Relationship } from "@/types/relationship";
 
 /**
@@ -136,16 +137,16 @@ export function RelationshipEditorModal() {
       return;
     }
 
-    // Generate ID if creating new relationship
-    const relationshipToSave: Relationship = {
-      ...relationship,
-      id:
-        relationship.id ||
-        `rel-${relationship.source}-${relationship.target}-${Date.now()}`,
-    } as Relationship;
-
     setIsSaving(true);
     try {
+      // Generate ID if creating new relationship
+      const relationshipToSave: Relationship = {
+        ...relationship,
+        id:
+          relationship.id ||
+          (await generateId(currentEnvironment, "relationship")),
+      } as Relationship;
+
       await saveRelationship(currentEnvironment, relationshipToSave);
       await refreshGraph(currentEnvironment);
 
@@ -161,7 +162,7 @@ export function RelationshipEditorModal() {
     } catch (error) {
       toast({
         title: "Error saving relationship",
-        description: String(error),
+        description: errorMessage(error),
         status: "error",
         duration: 5000,
         isClosable: true,
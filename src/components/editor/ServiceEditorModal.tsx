@@ -24,6 +24,7 @@ import { useEditorStore } from "@/store/editorStore";
 import { useServicesStore } from "@/store/servicesStore";
 import { useGraphStore } from "@/store/graphStore";
 import { saveService } from "@/services/tauri";
+import { errorMessage } from "@/utils/errors";
 import type { Service } from "@/types/service";
 
 /**
@@ -156,7 +157,7 @@ export function ServiceEditorModal() {
     } catch (error) {
       toast({
         title: "Error saving service",
-        description: String(error),
+        description: errorMessage(error),
         status: "error",
         duration: 5000,
         isClosable: true,
@@ -31,6 +31,11 @@ import { AddIcon, DeleteIcon } from "@chakra-ui/icons";
 import { useState, useCallback } from "react";
 import type { Service, ServiceType, ServiceStatus } from "@/types/service";
 
+/**
+ * Available tiers, from 1 (critical) to 4 (best-effort).
+ */
+const TIERS = [1, 2, 3, 4];
+
 /**
  * Available service type options for the dropdown.
  */
@@ -247,7 +252,7 @@ export function ServiceForm({
         />
       </FormControl>
 
-      {/* Version, Owner, Team Row */}
+      {/* Version, Owner, Team, Tier Row */}
       <HStack spacing={4}>
         <FormControl>
           <FormLabel>Version</FormLabel>
@@ -275,6 +280,25 @@ export function ServiceForm({
             placeholder="Platform Team"
           />
         </FormControl>
+
+        <FormControl>
+          <FormLabel>Tier</FormLabel>
+          <Select
+            value={service.tier ?? ""}
+            onChange={(e) =>
+              onChange({
+                tier: e.target.value ? Number(e.target.value) : undefined,
+              })
+            }
+            placeholder="Unclassified"
+          >
+            {TIERS.map((tier) => (
+              <option key={tier} value={tier}>
+                Tier {tier}
+              </option>
+            ))}
+          </Select>
+        </FormControl>
       </HStack>
 
       <Divider />
@@ -36,6 +36,7 @@ import { ChevronDownIcon, AddIcon } from "@chakra-ui/icons";
 import { useState, useCallback } from "react";
 import { useServicesStore, useGraphStore, useNavigationStore } from "@/store";
 import { createEnvironment, listEnvironments } from "@/services/tauri";
+import { errorMessage } from "@/utils/errors";
 
 /**
  * Dropdown for switching between deployment environments.
@@ -194,7 +195,7 @@ export function EnvironmentSwitch() {
 
       handleCloseModal();
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
     } finally {
       setIsCreating(false);
     }
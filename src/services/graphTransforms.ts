@@ -8,7 +8,13 @@
  * @module services/graphTransforms
  */
 
-import type { GraphData, GraphElements, GraphNode, GraphEdge } from "@/types/graph";
+import type {
+  DisplayConfig,
+  GraphData,
+  GraphElements,
+  GraphNode,
+  GraphEdge,
+} from "@/types/graph";
 import type { Service } from "@/types/service";
 import type { Relationship } from "@/types/relationship";
 
@@ -97,13 +103,51 @@ function formatRelationshipLabel(type: string): string {
   return type.replace(/_/g, " ");
 }
 
+/**
+ * Derives a color from a name, for types without a configured color.
+ *
+ * Matches the backend's derived colors: the hue is the name's 32-bit
+ * FNV-1a hash modulo 360, at 55% saturation and 50% lightness.
+ *
+ * @param name - The type name
+ * @returns Hex color code, the same for the same name in every session
+ */
+export function hashedColor(name: string): string {
+  let hash = 0x811c9dc5;
+  for (const byte of new TextEncoder().encode(name)) {
+    hash = Math.imul(hash ^ byte, 0x01000193) >>> 0;
+  }
+  const hue = hash % 360;
+
+  const chroma = 0.55;
+  const x = chroma * (1 - Math.abs(((hue / 60) % 2) - 1));
+  const [r, g, b] = [
+    [chroma, x, 0],
+    [x, chroma, 0],
+    [0, chroma, x],
+    [0, x, chroma],
+    [x, 0, chroma],
+    [chroma, 0, x],
+  ][Math.floor(hue / 60)];
+  const m = 0.5 - chroma / 2;
+  const channel = (value: number) =>
+    Math.round((value + m) * 255)
+      .toString(16)
+      .toUpperCase()
+      .padStart(2, "0");
+
+  return `#${channel(r)}${channel(g)}${channel(b)}`;
+}
+
 /**
  * Returns the color associated with a service type.
  *
  * Used for consistent visual differentiation of service types
- * across the graph and UI.
+ * across the graph and UI. Custom types without a color get one derived
+ * from their name.
  *
  * @param type - The service type (e.g., "api", "database")
+ * @param display - Optional display config overriding the built-in colors
  * @returns Hex color code for the service type
  *
  * @example
@@ -111,7 +155,10 @@ function formatRelationshipLabel(type: string): string {
  * const color = getServiceTypeColor('api'); // "#3182CE" (blue)
  * ```
  */
-export function getServiceTypeColor(type: string): string {
+export function getServiceTypeColor(
+  type: string,
+  display?: DisplayConfig
+): string {
   const colors: Record<string, string> = {
     gateway: "#805AD5", // purple
     api: "#3182CE", // blue
@@ -122,7 +169,9 @@ export function getServiceTypeColor(type: string): string {
     frontend: "#00B5D8", // cyan
     external: "#718096", // gray
   };
-  return colors[type] || "#4A5568";
+  return (
+    display?.serviceTypes[type]?.color ?? colors[type] ?? hashedColor(type)
+  );
 }
 
 /**
@@ -131,6 +180,7 @@ export function getServiceTypeColor(type: string): string {
  * Used for visual health indicators in the graph and UI.
  *
  * @param status - The service status (e.g., "healthy", "degraded")
+ * @param display - Optional display config overriding the built-in colors
  * @returns Hex color code for the status
  *
  * @example
@@ -138,7 +188,10 @@ export function getServiceTypeColor(type: string): string {
  * const color = getStatusColor('healthy'); // "#48BB78" (green)
  * ```
  */
-export function getStatusColor(status: string): string {
+export function getStatusColor(
+  status: string,
+  display?: DisplayConfig
+): string {
   const colors: Record<string, string> = {
     healthy: "#48BB78", // green
     degraded: "#ECC94B", // yellow
@@ -146,15 +199,17 @@ export function getStatusColor(status: string): string {
     unknown: "#A0AEC0", // gray
     deprecated: "#718096", // dark gray
   };
-  return colors[status] || "#A0AEC0";
+  return display?.statuses[status]?.color ?? colors[status] ?? "#A0AEC0";
 }
 
 /**
  * Returns the color associated with a relationship type.
  *
- * Used for visual differentiation of edge types in the graph.
+ * Used for visual differentiation of edge types in the graph. Custom types
+ * without a color get one derived from their name.
  *
  * @param type - The relationship type (e.g., "depends_on", "communicates_with")
+ * @param display - Optional display config overriding the built-in colors
  * @returns Hex color code for the relationship type
  *
  * @example
@@ -162,7 +217,10 @@ export function getStatusColor(status: string): string {
  * const color = getRelationshipColor('depends_on'); // "#E53E3E" (red)
  * ```
  */
-export function getRelationshipColor(type: string): string {
+export function getRelationshipColor(
+  type: string,
+  display?: DisplayConfig
+): string {
   const colors: Record<string, string> = {
     depends_on: "#E53E3E", // red
     communicates_with: "#3182CE", // blue
@@ -172,5 +230,7 @@ export function getRelationshipColor(type: string): string {
     publishes: "#D69E2E", // yellow
     subscribes: "#00B5D8", // cyan
   };
-  return colors[type] || "#A0AEC0";
+  return (
+    display?.relationshipTypes[type]?.color ?? colors[type] ?? hashedColor(type)
+  );
 }
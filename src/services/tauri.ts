@@ -9,7 +9,43 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
-import type { Service, GraphData, Relationship } from "@/types";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type {
+  Service,
+  ServiceType,
+  ServiceStatus,
+  ServiceLifecycle,
+  ServiceSummary,
+  RecentService,
+  GraphData,
+  GraphExclusions,
+  GraphNodeExpansion,
+  GraphPreferences,
+  GroupBy,
+  GraphExportFormat,
+  GrafanaExportFormat,
+  ManifestFormat,
+  DisplayConfig,
+  GroupedGraphData,
+  Group,
+  NodeAnnotation,
+  AppliedView,
+  SavedView,
+  CustomTypeKind,
+  CustomTypeDefinition,
+  SnapshotInfo,
+  EnvironmentDiff,
+  ScheduledRun,
+  Relationship,
+  RelationshipType,
+  EffectiveDates,
+  RelationshipOrigin,
+  ReviewStatus,
+  TraversalDirection,
+  AppErrorPayload,
+  Settings,
+  WebhookConfig,
+} from "@/types";
 import type { ValidationIssue } from "@/store/editorStore";
 
 // ============================================================================
@@ -33,6 +69,27 @@ export async function getAllServices(environment: string): Promise<Service[]> {
   return invoke<Service[]>("get_all_services", { environment });
 }
 
+/**
+ * Retrieves lightweight summaries of all services in an environment, for
+ * list views: no description text or metadata, but the numbers of incoming
+ * and outgoing relationships.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to one summary per service
+ * @throws Error if the backend fails to load services
+ *
+ * @example
+ * ```typescript
+ * const summaries = await getServiceSummaries('dev');
+ * const unused = summaries.filter((s) => s.incomingCount === 0);
+ * ```
+ */
+export async function getServiceSummaries(
+  environment: string
+): Promise<ServiceSummary[]> {
+  return invoke<ServiceSummary[]>("get_service_summaries", { environment });
+}
+
 /**
  * Retrieves a single service by its unique identifier.
  *
@@ -54,14 +111,36 @@ export async function getServiceById(
   return invoke<Service>("get_service_by_id", { environment, serviceId });
 }
 
+/**
+ * Checks whether a service exists. Unlike getServiceById, a missing service
+ * is not an error.
+ *
+ * @param environment - The name of the environment to look in
+ * @param serviceId - The unique identifier of the service
+ * @returns Promise resolving to whether the service exists
+ * @throws Error if the environment name or service ID is not path-safe
+ *
+ * @example
+ * ```typescript
+ * const taken = await serviceExists('dev', 'new-api');
+ * ```
+ */
+export async function serviceExists(
+  environment: string,
+  serviceId: string
+): Promise<boolean> {
+  return invoke<boolean>("service_exists", { environment, serviceId });
+}
+
 /**
  * Searches for services matching a query string.
  *
  * Performs case-insensitive search across service name, ID, description,
- * owner, team, and tags.
+ * owner, team, tier (as "tier-1"), and tags.
  *
  * @param environment - The name of the environment to search within
  * @param query - The search query string
+ * @param tier - Only return services of this tier
  * @returns Promise resolving to an array of matching services (may be empty)
  * @throws Error if the backend fails to perform the search
  *
@@ -73,9 +152,78 @@ export async function getServiceById(
  */
 export async function searchServices(
   environment: string,
-  query: string
+  query: string,
+  tier?: number
 ): Promise<Service[]> {
-  return invoke<Service[]>("search_services", { environment, query });
+  return invoke<Service[]>("search_services", { environment, query, tier });
+}
+
+/** How a quickOpen result matches: best first. */
+export type MatchRank =
+  | "exact_id"
+  | "id_prefix"
+  | "name_prefix"
+  | "word_prefix";
+
+/**
+ * A service matching a quickOpen prefix.
+ *
+ * @property rank - Whether the prefix is the whole ID, or starts the ID, the
+ *   name or a later word of either
+ */
+export interface QuickOpenMatch extends ServiceSummary {
+  rank: MatchRank;
+}
+
+/**
+ * Finds services by the prefix of their ID, name or a word of them, using
+ * an index fast enough to call on every keystroke.
+ *
+ * @param environment - The name of the environment to search
+ * @param prefix - What was typed, compared ignoring case
+ * @param limit - The most services to return (default: 20)
+ * @returns Promise resolving to the matching services, best match first
+ * @throws Error if the services cannot be loaded
+ *
+ * @example
+ * ```typescript
+ * const matches = await quickOpen('prod', 'pay', 10);
+ * ```
+ */
+export async function quickOpen(
+  environment: string,
+  prefix: string,
+  limit?: number
+): Promise<QuickOpenMatch[]> {
+  return invoke<QuickOpenMatch[]>("quick_open", {
+    environment,
+    prefix,
+    limit,
+  });
+}
+
+/** Kinds of IDs `generateId` generates. */
+export type IdKind = "service" | "relationship";
+
+/**
+ * Generates a filename-safe ID for a new service or relationship.
+ *
+ * Service IDs are slugs of the hint ("Zürich Payments API" becomes
+ * "zurich-payments-api"), with "-2", "-3" and so on appended if the
+ * environment already has the ID, or "service" if the hint has nothing to
+ * keep. Relationship IDs are random UUIDs.
+ *
+ * @param environment - The environment the ID must be free in
+ * @param kind - "service" or "relationship"
+ * @param hint - The name to derive a service ID from
+ * @returns Promise resolving to the new ID
+ */
+export async function generateId(
+  environment: string,
+  kind: IdKind,
+  hint?: string
+): Promise<string> {
+  return invoke<string>("generate_id", { environment, kind, hint });
 }
 
 /**
@@ -85,9 +233,10 @@ export async function searchServices(
  * Otherwise, a new service will be created.
  *
  * @param environment - The name of the environment to save the service to
- * @param service - The complete service object to save
- * @returns Promise resolving when the save is complete
- * @throws Error if the backend fails to save the service
+ * @param service - The complete service object to save, with the revision it was read at
+ * @param force - Save even if the service was changed elsewhere since it was read
+ * @returns Promise resolving to the saved service with its new revision
+ * @throws Error if the service was changed elsewhere (conflict) or the save fails
  *
  * @example
  * ```typescript
@@ -102,9 +251,10 @@ export async function searchServices(
  */
 export async function saveService(
   environment: string,
-  service: Service
-): Promise<void> {
-  return invoke<void>("save_service", { environment, service });
+  service: Service,
+  force?: boolean
+): Promise<Service> {
+  return invoke<Service>("save_service", { environment, service, force });
 }
 
 /**
@@ -132,6 +282,66 @@ export async function deleteService(
   return invoke<void>("delete_service", { environment, serviceId });
 }
 
+// ============================================================================
+// Pinned and Recent Service Commands
+// ============================================================================
+
+/**
+ * Pins a service to the top of the quick-switcher. Pins belong to the
+ * active data path, so each workspace's environments have their own.
+ *
+ * @param environment - The name of the environment
+ * @param serviceId - The service to pin
+ * @throws Error if the service is not found
+ */
+export async function pinService(
+  environment: string,
+  serviceId: string
+): Promise<void> {
+  return invoke<void>("pin_service", { environment, serviceId });
+}
+
+/**
+ * Unpins a service. Does nothing if it isn't pinned.
+ *
+ * @param environment - The name of the environment
+ * @param serviceId - The service to unpin
+ */
+export async function unpinService(
+  environment: string,
+  serviceId: string
+): Promise<void> {
+  return invoke<void>("unpin_service", { environment, serviceId });
+}
+
+/**
+ * Retrieves the pinned services of an environment, in the order they were
+ * pinned. Deleted services are left out.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to the pinned services
+ */
+export async function getPinnedServices(
+  environment: string
+): Promise<ServiceSummary[]> {
+  return invoke<ServiceSummary[]>("get_pinned_services", { environment });
+}
+
+/**
+ * Retrieves the recently viewed services of an environment, most recent
+ * first. Services are recorded by getServiceById and getServiceGraph.
+ *
+ * @param environment - The name of the environment
+ * @param limit - Maximum number of services to return (default: all kept, up to 50)
+ * @returns Promise resolving to the services and when they were viewed
+ */
+export async function getRecentServices(
+  environment: string,
+  limit?: number
+): Promise<RecentService[]> {
+  return invoke<RecentService[]>("get_recent_services", { environment, limit });
+}
+
 // ============================================================================
 // Graph Commands
 // ============================================================================
@@ -145,7 +355,22 @@ export async function deleteService(
  *
  * @param environment - The name of the environment
  * @param centerServiceId - The ID of the service to center the graph on
- * @param depth - Optional maximum traversal depth (default: 1)
+ * @param depth - Optional maximum traversal depth (default: the preference)
+ * @param includeEffectiveStatus - Also return each node's effective status (default: false)
+ * @param includeCriticality - Also return each node's criticality score (default: false)
+ * @param includeGroups - Also return the groups each node belongs to (default: false)
+ * @param includeAnnotations - Also return each node's stored position and notes (default: false)
+ * @param maxNodes - Optional maximum number of services in the graph, the center included
+ * @param maxEdges - Optional maximum number of relationships in the graph
+ * @param exclude - Optional services to leave out and not traverse through, by
+ *   status, tag, ID or type; the center service is always included. Statuses
+ *   and types default to the preferred ones.
+ * @param aggregateEdges - Also return one aggregated edge per source and target
+ *   (default: the preference)
+ * @param effectiveDates - Which relationships to follow by their effective
+ *   dates (default: those in effect today)
+ * @param direction - Which way relationships are followed (default: the
+ *   preferred direction)
  * @returns Promise resolving to the graph data for visualization
  * @throws Error if the center service is not found or backend fails
  *
@@ -161,285 +386,4693 @@ export async function deleteService(
 export async function getServiceGraph(
   environment: string,
   centerServiceId: string,
-  depth?: number
+  depth?: number,
+  includeEffectiveStatus?: boolean,
+  includeCriticality?: boolean,
+  includeGroups?: boolean,
+  includeAnnotations?: boolean,
+  maxNodes?: number,
+  maxEdges?: number,
+  exclude?: GraphExclusions & { excludeServiceTypes?: ServiceType[] },
+  aggregateEdges?: boolean,
+  effectiveDates?: EffectiveDates,
+  direction?: TraversalDirection
 ): Promise<GraphData> {
   return invoke<GraphData>("get_service_graph", {
     environment,
     centerServiceId,
     depth,
+    includeEffectiveStatus,
+    includeCriticality,
+    includeGroups,
+    includeAnnotations,
+    maxNodes,
+    maxEdges,
+    ...exclude,
+    aggregateEdges,
+    ...effectiveDates,
+    direction,
   });
 }
 
-// ============================================================================
-// Environment Commands
-// ============================================================================
-
 /**
- * Lists all available environments in the data directory.
+ * Fetches the neighbors of one service omitted from a capped graph.
  *
- * Returns environments sorted by priority: dev, staging, prod, then
- * alphabetically for others.
- *
- * @returns Promise resolving to an array of environment names
- * @throws Error if the backend fails to read the data directory
+ * Pass the services already shown as excludeIds to get only the missing
+ * neighbors, e.g. when a "+N more" badge is expanded.
  *
- * @example
- * ```typescript
- * const environments = await listEnvironments();
- * // ['dev', 'staging', 'prod', 'feature-branch']
- * ```
+ * @param environment - The name of the environment
+ * @param serviceId - The service to expand
+ * @param excludeIds - Services to leave out, typically those already in the graph
+ * @param relationshipTypes - Relationship types to follow (default: all)
+ * @param direction - Which way relationships are followed (default: both)
+ * @param exclude - Optional statuses and tags of services to leave out
+ * @param effectiveDates - Which relationships to follow by their effective
+ *   dates (default: those in effect today)
+ * @returns Promise resolving to the neighbors and their relationships to the service
+ * @throws Error if the service is not found
  */
-export async function listEnvironments(): Promise<string[]> {
-  return invoke<string[]>("list_environments");
+export async function expandGraphNode(
+  environment: string,
+  serviceId: string,
+  excludeIds: string[],
+  relationshipTypes?: RelationshipType[],
+  direction?: TraversalDirection,
+  exclude?: Omit<GraphExclusions, "excludeServiceIds">,
+  effectiveDates?: EffectiveDates
+): Promise<GraphNodeExpansion> {
+  return invoke<GraphNodeExpansion>("expand_graph_node", {
+    environment,
+    serviceId,
+    excludeIds,
+    relationshipTypes,
+    direction,
+    ...exclude,
+    ...effectiveDates,
+  });
 }
 
 /**
- * Retrieves the currently active environment name.
- *
- * @returns Promise resolving to the current environment name
- * @throws Error if the backend fails to get the current environment
+ * A service's own status and the status its dependencies give it.
  *
- * @example
- * ```typescript
- * const current = await getCurrentEnvironment();
- * console.log(`Currently viewing: ${current}`);
- * ```
+ * @property serviceId - The service
+ * @property ownStatus - The status recorded on the service
+ * @property effectiveStatus - Degraded if a dependency is impaired, unless the own status is worse
+ * @property worstDependencyPath - Service IDs from this service to its worst dependency
  */
-export async function getCurrentEnvironment(): Promise<string> {
-  return invoke<string>("get_current_environment");
+export interface EffectiveStatus {
+  serviceId: string;
+  ownStatus: ServiceStatus;
+  effectiveStatus: ServiceStatus;
+  worstDependencyPath: string[];
 }
 
 /**
- * Switches the active environment to a different one.
- *
- * After switching, all subsequent service/relationship operations will
- * use the new environment.
+ * Computes the effective status of every service in an environment.
  *
- * @param environment - The name of the environment to switch to
- * @returns Promise resolving when the switch is complete
- * @throws Error if the environment doesn't exist
+ * @param environment - The name of the environment
+ * @param includeDataFlow - Also follow reads_from and writes_to relationships (default: false)
+ * @returns Promise resolving to one entry per service, sorted by ID
+ * @throws Error if the services or relationships cannot be loaded
  *
  * @example
  * ```typescript
- * await switchEnvironment('staging');
- * // All subsequent operations now use 'staging' environment
+ * const statuses = await getEffectiveStatus('prod');
+ * const impaired = statuses.filter((s) => s.effectiveStatus !== s.ownStatus);
  * ```
  */
-export async function switchEnvironment(environment: string): Promise<void> {
-  return invoke<void>("switch_environment", { environment });
+export async function getEffectiveStatus(
+  environment: string,
+  includeDataFlow?: boolean
+): Promise<EffectiveStatus[]> {
+  return invoke<EffectiveStatus[]>("get_effective_status", { environment, includeDataFlow });
 }
 
 /**
- * Creates a new environment with the required directory structure.
- *
- * Creates a new environment directory with an empty services folder and
- * an empty relationships.json file.
- *
- * @param environment - The name of the new environment to create
- * @returns Promise resolving when the environment is created
- * @throws Error if the environment already exists or creation fails
+ * A service and how critical it is to the rest of the environment.
  *
- * @example
- * ```typescript
- * await createEnvironment('staging');
- * ```
+ * @property serviceId - ID of the service
+ * @property serviceName - Name of the service
+ * @property score - Normalized PageRank; the most critical service scores 1
  */
-export async function createEnvironment(environment: string): Promise<void> {
-  return invoke<void>("create_environment", { environment });
+export interface CriticalityScore {
+  serviceId: string;
+  serviceName: string;
+  score: number;
 }
 
 /**
- * Sets the root data directory path for all environment data.
- *
- * Use this to point the application at a different data location.
- * This clears all cached data.
+ * Ranks the services of an environment by how much transitively depends on them.
  *
- * @param path - The absolute path to the new data directory
- * @returns Promise resolving when the path is updated
- * @throws Error if the path doesn't exist or isn't a directory
+ * @param environment - The name of the environment
+ * @param damping - PageRank damping factor between 0 and 1 (default: 0.85)
+ * @param iterations - Maximum number of iterations (default: 30)
+ * @returns Promise resolving to every service, most critical first
+ * @throws Error if the parameters are out of range or the data cannot be loaded
  *
  * @example
  * ```typescript
- * await setDataPath('/Users/user/projects/my-app/service-data');
+ * const [mostCritical] = await getCriticalityRanking('prod');
  * ```
  */
-export async function setDataPath(path: string): Promise<void> {
-  return invoke<void>("set_data_path", { path });
+export async function getCriticalityRanking(
+  environment: string,
+  damping?: number,
+  iterations?: number
+): Promise<CriticalityScore[]> {
+  return invoke<CriticalityScore[]>("get_criticality_ranking", {
+    environment,
+    damping,
+    iterations,
+  });
 }
 
-// ============================================================================
-// Relationship Commands
-// ============================================================================
+/**
+ * Services grouped into layers for a left-to-right view.
+ *
+ * @property layers - Service IDs per layer; layer 0 holds the services nothing depends on
+ * @property cycles - Groups of services depending on each other in a cycle
+ */
+export interface DependencyLayers {
+  layers: string[][];
+  cycles: string[][];
+}
 
 /**
- * Retrieves all relationships for a specified environment.
+ * Assigns the services of an environment to layers by their longest dependency path.
  *
  * @param environment - The name of the environment
- * @returns Promise resolving to an array of all relationships
- * @throws Error if the backend fails to load relationships
+ * @param seedByType - Force frontends first and databases last (default: false)
+ * @returns Promise resolving to the layers and cycle groups
+ * @throws Error if the services or relationships cannot be loaded
  *
  * @example
  * ```typescript
- * const relationships = await getAllRelationships('dev');
- * console.log(`Found ${relationships.length} relationships`);
+ * const { layers } = await getDependencyLayers('prod', true);
  * ```
  */
-export async function getAllRelationships(
-  environment: string
-): Promise<Relationship[]> {
-  return invoke<Relationship[]>("get_all_relationships", { environment });
+export async function getDependencyLayers(
+  environment: string,
+  seedByType?: boolean
+): Promise<DependencyLayers> {
+  return invoke<DependencyLayers>("get_dependency_layers", { environment, seedByType });
 }
 
 /**
- * Retrieves all relationships involving a specific service.
+ * How the related services of two compared services overlap.
  *
- * Returns relationships where the service is either the source or target.
+ * @property onlyA - Services related only to service A, with the relationship types
+ * @property onlyB - Services related only to service B, with the relationship types
+ * @property shared - Services related to both, with the types towards each
+ */
+export interface RelatedServicesComparison {
+  onlyA: { serviceId: string; relationshipTypes: RelationshipType[] }[];
+  onlyB: { serviceId: string; relationshipTypes: RelationshipType[] }[];
+  shared: {
+    serviceId: string;
+    relationshipTypesA: RelationshipType[];
+    relationshipTypesB: RelationshipType[];
+  }[];
+}
+
+/**
+ * The dependencies and dependents two services have in common and apart.
+ */
+export interface DependencyComparison {
+  serviceA: string;
+  serviceB: string;
+  dependencies: RelatedServicesComparison;
+  dependents: RelatedServicesComparison;
+  transitiveDependencies: RelatedServicesComparison | null;
+  transitiveDependents: RelatedServicesComparison | null;
+}
+
+/**
+ * Compares the direct (and optionally transitive) relations of two services.
  *
  * @param environment - The name of the environment
- * @param serviceId - The ID of the service to find relationships for
- * @returns Promise resolving to an array of relationships (may be empty)
- * @throws Error if the backend fails to load relationships
+ * @param serviceA - ID of the first service
+ * @param serviceB - ID of the second service
+ * @param includeTransitive - Also compare everything reachable (default: false)
+ * @returns Promise resolving to the comparison
+ * @throws Error if either service doesn't exist
  *
  * @example
  * ```typescript
- * const rels = await getRelationshipsForService('dev', 'api-gateway');
- * // Returns all relationships where api-gateway is source OR target
+ * const { dependencies } = await compareServiceDependencies('prod', 'user-api', 'user-service');
  * ```
  */
-export async function getRelationshipsForService(
+export async function compareServiceDependencies(
   environment: string,
-  serviceId: string
-): Promise<Relationship[]> {
-  return invoke<Relationship[]>("get_relationships_for_service", {
+  serviceA: string,
+  serviceB: string,
+  includeTransitive?: boolean
+): Promise<DependencyComparison> {
+  return invoke<DependencyComparison>("compare_service_dependencies", {
     environment,
-    serviceId,
+    serviceA,
+    serviceB,
+    includeTransitive,
   });
 }
 
 /**
- * Saves a relationship to the specified environment (create or update).
+ * Whether one service can reach another.
  *
- * If a relationship with the same ID exists, it will be updated.
- * For new relationships, validates that no duplicate exists with the
- * same source, target, and type.
+ * @property reachable - True if a path exists
+ * @property distance - Relationships on the shortest path (0 to itself), null if unreachable
+ */
+export interface Reachability {
+  reachable: boolean;
+  distance: number | null;
+}
+
+/**
+ * Checks whether one service can reach another along relationships.
  *
  * @param environment - The name of the environment
- * @param relationship - The complete relationship object to save
- * @returns Promise resolving when the save is complete
- * @throws Error if a duplicate relationship exists or save fails
- *
- * @example
- * ```typescript
- * await saveRelationship('dev', {
- *   id: 'rel-123',
- *   source: 'api-gateway',
- *   target: 'user-service',
- *   relationshipType: 'depends_on',
- *   description: 'API Gateway routes to User Service'
- * });
- * ```
+ * @param fromId - ID of the service to start from
+ * @param toId - ID of the service to reach
+ * @param relationshipTypes - Relationship types to follow (default: depends_on only)
+ * @param directed - If false, relationships are followed both ways (default: true)
+ * @returns Promise resolving to whether and how far the target is reachable
+ * @throws Error if either service doesn't exist
  */
-export async function saveRelationship(
+export async function isReachable(
   environment: string,
-  relationship: Relationship
-): Promise<void> {
-  return invoke<void>("save_relationship", { environment, relationship });
+  fromId: string,
+  toId: string,
+  relationshipTypes?: RelationshipType[],
+  directed?: boolean
+): Promise<Reachability> {
+  return invoke<Reachability>("is_reachable", {
+    environment,
+    fromId,
+    toId,
+    relationshipTypes,
+    directed,
+  });
 }
 
 /**
- * Deletes a single relationship by its unique identifier.
+ * Checks reachability for many pairs of services, building the graph once.
  *
  * @param environment - The name of the environment
- * @param relationshipId - The unique identifier of the relationship to delete
- * @returns Promise resolving when the deletion is complete
- * @throws Error if the relationship is not found or deletion fails
+ * @param pairs - The services to check
+ * @param relationshipTypes - Relationship types to follow (default: depends_on only)
+ * @param directed - If false, relationships are followed both ways (default: true)
+ * @returns Promise resolving to one result per pair, in order
+ * @throws Error if a service of the pairs doesn't exist
  *
  * @example
  * ```typescript
- * await deleteRelationship('dev', 'rel-123');
+ * const [result] = await areReachable('prod', [{ fromId: 'web', toId: 'users-db' }]);
  * ```
  */
-export async function deleteRelationship(
+export async function areReachable(
   environment: string,
-  relationshipId: string
-): Promise<void> {
-  return invoke<void>("delete_relationship", {
+  pairs: { fromId: string; toId: string }[],
+  relationshipTypes?: RelationshipType[],
+  directed?: boolean
+): Promise<Reachability[]> {
+  return invoke<Reachability[]>("are_reachable", {
     environment,
-    relationshipId,
+    pairs,
+    relationshipTypes,
+    directed,
   });
 }
 
 /**
- * Deletes all relationships involving a specific service.
+ * A relationship whose removal breaks dependency cycles.
  *
- * Removes relationships where the service is either source or target.
- * Typically called before deleting a service to clean up orphaned relationships.
+ * @property relationshipIds - The relationships between the two services to remove
+ * @property cycle - The shortest cycle this breaks, starting and ending at sourceId
+ * @property cyclesBroken - How many of the remaining cycles this breaks
+ * @property justification - Why this relationship was picked
+ */
+export interface CycleBreakSuggestion {
+  sourceId: string;
+  targetId: string;
+  relationshipIds: string[];
+  relationshipTypes: RelationshipType[];
+  cycle: string[];
+  cyclesBroken: number;
+  justification: string;
+}
+
+/**
+ * Suggests relationships to remove to break the dependency cycles of an environment.
+ *
+ * Read-only; delete the suggested relationships with `deleteRelationship`, or
+ * all at once with the `execute_batch` command.
  *
  * @param environment - The name of the environment
- * @param serviceId - The ID of the service whose relationships to delete
- * @returns Promise resolving to the number of relationships deleted
- * @throws Error if the backend fails to delete relationships
+ * @returns Promise resolving to the cyclic groups and the suggested removals
+ * @throws Error if the services or relationships cannot be loaded
+ */
+export async function suggestCycleBreaks(
+  environment: string
+): Promise<{ cyclicGroups: string[][]; suggestions: CycleBreakSuggestion[] }> {
+  return invoke("suggest_cycle_breaks", { environment });
+}
+
+/**
+ * Collapses the graph of an environment into one node per group of services.
+ *
+ * @param environment - The name of the environment
+ * @param groupBy - "team", "serviceType" or { tagPrefix }
+ * @param effectiveDates - Which relationships to count by their effective
+ *   dates (default: those in effect today)
+ * @returns Promise resolving to the group nodes and aggregated relationships
+ * @throws Error if the services or relationships cannot be loaded
  *
  * @example
  * ```typescript
- * const count = await deleteRelationshipsForService('dev', 'deprecated-service');
- * console.log(`Deleted ${count} relationships`);
+ * const teams = await getGroupedGraph('prod', 'team');
+ * const domains = await getGroupedGraph('prod', { tagPrefix: 'domain:' });
  * ```
  */
-export async function deleteRelationshipsForService(
+export async function getGroupedGraph(
   environment: string,
-  serviceId: string
-): Promise<number> {
-  return invoke<number>("delete_relationships_for_service", {
+  groupBy: GroupBy,
+  effectiveDates?: EffectiveDates
+): Promise<GroupedGraphData> {
+  return invoke<GroupedGraphData>("get_grouped_graph", {
     environment,
-    serviceId,
+    groupBy,
+    ...effectiveDates,
   });
 }
 
-// ============================================================================
-// Validation Commands
-// ============================================================================
+/**
+ * A hypothetical change for simulateGraph, tagged like batch operations.
+ */
+export type HypotheticalChange =
+  | { op: "add_service"; service: Service }
+  | { op: "remove_service"; serviceId: string }
+  | { op: "add_relationship"; relationship: Relationship }
+  | { op: "remove_relationship"; relationshipId: string };
+
+/**
+ * An environment's data as hypothetical changes would leave it.
+ *
+ * @property services - Every service after the changes
+ * @property relationships - Every relationship after the changes
+ * @property graph - The graph around the center service, if one was given
+ * @property validation - Orphaned relationship and cycle checks of the new data
+ * @property newIssues - The issues the current data doesn't have
+ */
+export interface GraphSimulation {
+  services: Service[];
+  relationships: Relationship[];
+  graph: GraphData | null;
+  validation: ValidationResult;
+  newIssues: ValidationIssue[];
+}
+
+/**
+ * Previews the graph after hypothetical changes, without writing anything.
+ *
+ * @param environment - The name of the environment
+ * @param changes - The hypothetical changes, applied in order
+ * @param centerServiceId - Optional service to center the graph on
+ * @param depth - Traversal depth around the center (default: 1)
+ * @returns Promise resolving to the changed data, its graph and new issues
+ * @throws Error if a change can't be applied or the center doesn't exist
+ *
+ * @example
+ * ```typescript
+ * const simulation = await simulateGraph('prod', [
+ *   { op: 'remove_service', serviceId: 'legacy-auth' },
+ * ]);
+ * console.log(`${simulation.newIssues.length} new problems`);
+ * ```
+ */
+export async function simulateGraph(
+  environment: string,
+  changes: HypotheticalChange[],
+  centerServiceId?: string,
+  depth?: number
+): Promise<GraphSimulation> {
+  return invoke<GraphSimulation>("simulate_graph", {
+    environment,
+    changes,
+    centerServiceId,
+    depth,
+  });
+}
+
+/**
+ * An exported graph.
+ *
+ * @property content - The rendered graph
+ * @property redactedValues - How many values were masked or stripped
+ */
+export interface GraphExport {
+  content: string;
+  redactedValues: number;
+}
+
+/**
+ * Exports an environment's whole graph, sorted by ID.
+ *
+ * @param environment - The name of the environment
+ * @param format - "json", "dot" (Graphviz) or "mermaid"
+ * @param outputPath - File to also write the graph to; nothing is written if omitted
+ * @param operationId - ID for progress events and `cancelOperation`
+ * @param aggregateEdges - Draw the relationships between two services as one DOT or
+ *   Mermaid edge labeled with their types and count (default: false)
+ * @param effectiveDates - Which relationships to export by their effective
+ *   dates (default: those in effect today)
+ * @param redact - Hide the values the redaction config of the settings lists
+ *   (default: false); only JSON exports contain any
+ * @returns Promise resolving to the rendered graph
+ * @throws Error if the data cannot be loaded, the file cannot be written or a
+ *   redaction pattern is invalid
+ */
+export async function exportGraph(
+  environment: string,
+  format: GraphExportFormat,
+  outputPath?: string,
+  operationId?: string,
+  aggregateEdges?: boolean,
+  effectiveDates?: EffectiveDates,
+  redact?: boolean
+): Promise<GraphExport> {
+  return invoke<GraphExport>("export_graph", {
+    environment,
+    format,
+    outputPath,
+    operationId,
+    aggregateEdges,
+    ...effectiveDates,
+    redact,
+  });
+}
+
+/**
+ * Exports an environment's graph for Grafana's node graph panel.
+ *
+ * @param environment - The name of the environment
+ * @param outputDir - Directory to write the files to; created if needed
+ * @param format - "csv" for nodes.csv and edges.csv, "json" for nodegraph.json
+ * @param effectiveDates - Which relationships to export by their effective
+ *   dates (default: those in effect today)
+ * @returns Promise resolving to the paths of the written files
+ * @throws Error if the data cannot be loaded or the files cannot be written
+ */
+export async function exportGrafanaNodegraph(
+  environment: string,
+  outputDir: string,
+  format: GrafanaExportFormat = "csv",
+  effectiveDates?: EffectiveDates
+): Promise<string[]> {
+  return invoke<string[]>("export_grafana_nodegraph", {
+    environment,
+    outputDir,
+    format,
+    ...effectiveDates,
+  });
+}
+
+/**
+ * Which part of an environment's graph a D2 or draw.io export draws.
+ *
+ * @property centerServiceId - Service to draw the neighborhood of; the whole
+ *   environment if omitted
+ * @property depth - How far from the center to traverse (default: 1)
+ * @property serviceTypes - Service types to draw; all of them if omitted
+ * @property relationshipTypes - Relationship types to draw and follow
+ * @property excludeStatuses - Statuses of services to leave out
+ * @property excludeTags - Tags of services to leave out
+ * @property excludeServiceIds - Services to leave out
+ */
+export interface GraphSelection extends EffectiveDates {
+  centerServiceId?: string;
+  depth?: number;
+  serviceTypes?: ServiceType[];
+  relationshipTypes?: RelationshipType[];
+  excludeStatuses?: ServiceStatus[];
+  excludeTags?: string[];
+  excludeServiceIds?: string[];
+}
+
+/**
+ * What a D2 export draws.
+ *
+ * @property groupByTeam - Draw the services of each team in a container
+ * @property outputPath - File to also write the diagram to
+ */
+export interface D2ExportOptions extends GraphSelection {
+  groupByTeam?: boolean;
+  outputPath?: string;
+}
+
+/**
+ * Exports an environment's graph, or the neighborhood of a service, as a D2
+ * diagram: databases as cylinders, queues as queues, edges labeled with
+ * their relationship type and styled with the display config.
+ *
+ * @param environment - The name of the environment
+ * @param options - What to draw and where to write it
+ * @returns Promise resolving to the D2 source
+ * @throws Error if the center doesn't exist, the data cannot be loaded or
+ *   the file cannot be written
+ */
+export async function exportGraphD2(
+  environment: string,
+  options?: D2ExportOptions
+): Promise<string> {
+  return invoke<string>("export_graph_d2", {
+    environment,
+    options,
+  });
+}
+
+/**
+ * Exports an environment's graph, or the neighborhood of a service, as a
+ * draw.io diagram laid out on a grid. Every cell carries the ID and type of
+ * its service or relationship, so `importDrawio` reads it back unchanged.
+ *
+ * @param environment - The name of the environment
+ * @param outputPath - The .drawio file to write
+ * @param options - The part of the graph to draw
+ * @returns Promise resolving to the written diagram
+ * @throws Error if the center doesn't exist, the data cannot be loaded or
+ *   the file cannot be written
+ */
+export async function exportGraphDrawio(
+  environment: string,
+  outputPath: string,
+  options?: GraphSelection
+): Promise<string> {
+  return invoke<string>("export_graph_drawio", {
+    environment,
+    outputPath,
+    options,
+  });
+}
+
+/**
+ * Exports the deployment manifest of a service: its transitive
+ * prerequisites, each after its own, and its relationships to its direct
+ * prerequisites with their metadata. Only relationships in effect today
+ * are followed.
+ *
+ * @param environment - The name of the environment
+ * @param serviceId - The service to deploy
+ * @param outputPath - Optional file to write the manifest to
+ * @param format - "yaml" or "json"
+ * @param includeDataFlow - Also follow reads_from and writes_to relationships
+ * @returns Promise resolving to the manifest
+ * @throws Error if the service doesn't exist or its prerequisites depend on
+ *   each other in a cycle (the message names it)
+ */
+export async function exportDeploymentManifest(
+  environment: string,
+  serviceId: string,
+  outputPath?: string,
+  format: ManifestFormat = "yaml",
+  includeDataFlow?: boolean
+): Promise<string> {
+  return invoke<string>("export_deployment_manifest", {
+    environment,
+    serviceId,
+    outputPath,
+    format,
+    includeDataFlow,
+  });
+}
+
+/**
+ * Exports the deployment manifest of every service, one
+ * `<service-id>.yaml` (or `.json`) each.
+ *
+ * @param environment - The name of the environment
+ * @param outputDir - Directory to write the files to; created if needed
+ * @param format - "yaml" or "json"
+ * @param includeDataFlow - Also follow reads_from and writes_to relationships
+ * @returns Promise resolving to the paths of the written files
+ * @throws Error if the prerequisites of a service depend on each other in a
+ *   cycle; nothing is written then
+ */
+export async function exportDeploymentManifests(
+  environment: string,
+  outputDir: string,
+  format: ManifestFormat = "yaml",
+  includeDataFlow?: boolean
+): Promise<string[]> {
+  return invoke<string[]>("export_deployment_manifests", {
+    environment,
+    outputDir,
+    format,
+    includeDataFlow,
+  });
+}
+
+/**
+ * Turns read-only mode on or off. While it is on, commands that change data
+ * reject with a `read_only_mode` error.
+ *
+ * @param enabled - Whether read-only mode is on
+ */
+export async function setReadOnly(enabled: boolean): Promise<void> {
+  return invoke<void>("set_read_only", { enabled });
+}
+
+/**
+ * Retrieves the palette the graph view and the exports draw with.
+ *
+ * @returns Promise resolving to the saved display config, or the built-in one
+ */
+export async function getDisplayConfig(): Promise<DisplayConfig> {
+  return invoke<DisplayConfig>("get_display_config");
+}
+
+/**
+ * Replaces the palette the graph view and the exports draw with.
+ *
+ * @param config - The complete new display config
+ * @throws Error if a color isn't a hex color or a color name
+ */
+export async function saveDisplayConfig(config: DisplayConfig): Promise<void> {
+  return invoke<void>("save_display_config", { config });
+}
+
+/**
+ * Retrieves the defaults getServiceGraph uses for omitted parameters.
+ *
+ * @returns Promise resolving to the saved preferences, or the built-in defaults
+ */
+export async function getGraphPreferences(): Promise<GraphPreferences> {
+  return invoke<GraphPreferences>("get_graph_preferences");
+}
+
+/**
+ * Replaces the defaults getServiceGraph uses for omitted parameters.
+ *
+ * Invalid values, like a depth of 0, are replaced by the built-in defaults.
+ *
+ * @param preferences - The complete new graph preferences
+ * @returns Promise resolving to the preferences as saved
+ */
+export async function saveGraphPreferences(
+  preferences: GraphPreferences
+): Promise<GraphPreferences> {
+  return invoke<GraphPreferences>("save_graph_preferences", { preferences });
+}
+
+/**
+ * Retrieves the stored graph annotations of an environment.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to the annotations by service ID
+ */
+export async function getGraphAnnotations(
+  environment: string
+): Promise<Record<string, NodeAnnotation>> {
+  return invoke<Record<string, NodeAnnotation>>("get_graph_annotations", {
+    environment,
+  });
+}
+
+/**
+ * Merges annotations into the stored ones, node by node. A null annotation
+ * deletes the node's annotation; nodes that aren't given are kept.
+ *
+ * @param environment - The name of the environment
+ * @param annotations - Annotations (or null to delete) by service ID
+ * @returns Promise resolving to all annotations after the merge
+ *
+ * @example
+ * ```typescript
+ * await saveGraphAnnotations('prod', {
+ *   'billing-api': { x: 120, y: 40, pinnedPosition: true },
+ * });
+ * ```
+ */
+export async function saveGraphAnnotations(
+  environment: string,
+  annotations: Record<string, NodeAnnotation | null>
+): Promise<Record<string, NodeAnnotation>> {
+  return invoke<Record<string, NodeAnnotation>>("save_graph_annotations", {
+    environment,
+    annotations,
+  });
+}
+
+// ============================================================================
+// Group Commands
+// ============================================================================
+
+/**
+ * Lists the groups of an environment, sorted by name.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to the groups
+ */
+export async function listGroups(environment: string): Promise<Group[]> {
+  return invoke<Group[]>("list_groups", { environment });
+}
+
+/**
+ * Creates or replaces a group. All members must be existing services.
+ *
+ * @param environment - The name of the environment
+ * @param group - The group to save; replaces the group with the same ID
+ * @returns Promise resolving to the saved group, without duplicate members
+ * @throws Error if the ID or name is empty or a member doesn't exist
+ */
+export async function saveGroup(
+  environment: string,
+  group: Group
+): Promise<Group> {
+  return invoke<Group>("save_group", { environment, group });
+}
+
+/**
+ * Deletes a group. Its member services are not affected.
+ *
+ * @param environment - The name of the environment
+ * @param groupId - The ID of the group to delete
+ * @throws Error if the group is not found
+ */
+export async function deleteGroup(
+  environment: string,
+  groupId: string
+): Promise<void> {
+  return invoke<void>("delete_group", { environment, groupId });
+}
+
+/**
+ * Adds services to a group, skipping those already in it.
+ *
+ * @param environment - The name of the environment
+ * @param groupId - The ID of the group
+ * @param serviceIds - The services to add
+ * @returns Promise resolving to the updated group
+ * @throws Error if the group or a service is not found
+ */
+export async function addGroupMembers(
+  environment: string,
+  groupId: string,
+  serviceIds: string[]
+): Promise<Group> {
+  return invoke<Group>("add_group_members", {
+    environment,
+    groupId,
+    serviceIds,
+  });
+}
+
+/**
+ * Removes services from a group, ignoring those not in it.
+ *
+ * @param environment - The name of the environment
+ * @param groupId - The ID of the group
+ * @param serviceIds - The services to remove
+ * @returns Promise resolving to the updated group
+ * @throws Error if the group is not found
+ */
+export async function removeGroupMembers(
+  environment: string,
+  groupId: string,
+  serviceIds: string[]
+): Promise<Group> {
+  return invoke<Group>("remove_group_members", {
+    environment,
+    groupId,
+    serviceIds,
+  });
+}
+
+// ============================================================================
+// Saved View Commands
+// ============================================================================
+
+/**
+ * Saves a graph view, replacing the view with the same name.
+ *
+ * @param view - The view to save
+ * @returns Promise resolving to the saved view
+ * @throws Error if the name or environment can't be used as a filename
+ */
+export async function saveView(view: SavedView): Promise<SavedView> {
+  return invoke<SavedView>("save_view", { view });
+}
+
+/**
+ * Lists the saved views, sorted by name.
+ *
+ * @param environment - Only list the views of this environment; all if omitted
+ * @returns Promise resolving to the views
+ */
+export async function listViews(environment?: string): Promise<SavedView[]> {
+  return invoke<SavedView[]>("list_views", { environment });
+}
+
+/**
+ * Retrieves a saved view.
+ *
+ * @param viewName - The name of the view
+ * @returns Promise resolving to the view
+ * @throws Error if the view is not found
+ */
+export async function getView(viewName: string): Promise<SavedView> {
+  return invoke<SavedView>("get_view", { viewName });
+}
+
+/**
+ * Deletes a saved view.
+ *
+ * @param viewName - The name of the view
+ * @throws Error if the view is not found
+ */
+export async function deleteView(viewName: string): Promise<void> {
+  return invoke<void>("delete_view", { viewName });
+}
+
+/**
+ * Rebuilds the graph of a saved view from the current data.
+ *
+ * Services and groups the view refers to that no longer exist are left out
+ * and reported instead of failing the view.
+ *
+ * @param viewName - The name of the view
+ * @param maxNodes - Optional maximum number of services in the graph
+ * @param maxEdges - Optional maximum number of relationships in the graph
+ * @param exclude - Optional services to leave out and not traverse through
+ * @param aggregateEdges - Also return one aggregated edge per source and target (default: false)
+ * @returns Promise resolving to the view, its graph and what is missing
+ * @throws Error if the view is not found
+ *
+ * @example
+ * ```typescript
+ * const { graph, missingServiceIds } = await applyView('payments-morning');
+ * ```
+ */
+export async function applyView(
+  viewName: string,
+  maxNodes?: number,
+  maxEdges?: number,
+  exclude?: GraphExclusions,
+  aggregateEdges?: boolean
+): Promise<AppliedView> {
+  return invoke<AppliedView>("apply_view", {
+    viewName,
+    maxNodes,
+    maxEdges,
+    ...exclude,
+    aggregateEdges,
+  });
+}
+
+// ============================================================================
+// Custom Type Commands
+// ============================================================================
+
+/**
+ * Lists the registered custom types of one kind, sorted by name.
+ *
+ * @param kind - `service` or `relationship`
+ * @returns Promise resolving to the registered types
+ */
+export async function listCustomTypes(
+  kind: CustomTypeKind
+): Promise<CustomTypeDefinition[]> {
+  return invoke<CustomTypeDefinition[]>("list_custom_types", { kind });
+}
+
+/**
+ * Registers a custom type, replacing the entry with the same name.
+ *
+ * @param kind - `service` or `relationship`
+ * @param definition - The type to register; a blank label defaults to the name
+ * @returns Promise resolving to the registered type
+ * @throws Error if the name is empty, built in, or differs only in case from
+ *   a registered type
+ */
+export async function addCustomType(
+  kind: CustomTypeKind,
+  definition: CustomTypeDefinition
+): Promise<CustomTypeDefinition> {
+  return invoke<CustomTypeDefinition>("add_custom_type", { kind, definition });
+}
+
+/**
+ * Renames a registered custom type. Only the registry changes; use
+ * `normalizeCustomTypes` to move the data.
+ *
+ * @param kind - `service` or `relationship`
+ * @param name - The registered name
+ * @param newName - The name to register it under instead
+ * @returns Promise resolving to the renamed type
+ * @throws Error if the type is not registered or the new name is taken
+ */
+export async function renameCustomType(
+  kind: CustomTypeKind,
+  name: string,
+  newName: string
+): Promise<CustomTypeDefinition> {
+  return invoke<CustomTypeDefinition>("rename_custom_type", {
+    kind,
+    name,
+    newName,
+  });
+}
+
+/**
+ * Removes a custom type from the registry.
+ *
+ * @param kind - `service` or `relationship`
+ * @param name - The registered name
+ * @throws Error if the type is not registered
+ */
+export async function deleteCustomType(
+  kind: CustomTypeKind,
+  name: string
+): Promise<void> {
+  return invoke<void>("delete_custom_type", { kind, name });
+}
+
+/**
+ * A custom type found in an environment's data.
+ *
+ * @property name - The type as the data spells it
+ * @property count - Number of services or relationships using it
+ * @property registeredAs - The registered type matching it regardless of case
+ * @property newlyRegistered - Whether the discovery registered it just now
+ */
+export interface DiscoveredCustomType {
+  name: string;
+  count: number;
+  registeredAs: string | null;
+  newlyRegistered: boolean;
+}
+
+/**
+ * The custom types used in an environment, by kind.
+ */
+export interface CustomTypeDiscovery {
+  serviceTypes: DiscoveredCustomType[];
+  relationshipTypes: DiscoveredCustomType[];
+}
+
+/**
+ * Lists the custom types an environment uses.
+ *
+ * @param environment - The environment to scan
+ * @param register - Register the types that aren't yet, under their most
+ *   used spelling (default: false)
+ * @returns Promise resolving to the custom types in use
+ */
+export async function discoverCustomTypes(
+  environment: string,
+  register?: boolean
+): Promise<CustomTypeDiscovery> {
+  return invoke<CustomTypeDiscovery>("discover_custom_types", {
+    environment,
+    register,
+  });
+}
+
+/**
+ * Rewrites custom types to their registered spelling, as one batch.
+ *
+ * @param environment - The environment to rewrite
+ * @param mapping - Optional type names and the names to rewrite them to,
+ *   for both kinds; by default types matching a registered one regardless of
+ *   case are rewritten to it
+ * @param dryRun - Only compute the changes (default: false)
+ * @returns Promise resolving to the rewritten services and relationships
+ *
+ * @example
+ * ```typescript
+ * const preview = await normalizeCustomTypes('prod', { fn: 'lambda' }, true);
+ * ```
+ */
+export async function normalizeCustomTypes(
+  environment: string,
+  mapping?: Record<string, string>,
+  dryRun?: boolean
+): Promise<BatchResult> {
+  return invoke<BatchResult>("normalize_custom_types", {
+    environment,
+    mapping,
+    dryRun,
+  });
+}
+
+// ============================================================================
+// Metadata Key Commands
+// ============================================================================
+
+/** The JSON type of a metadata value. */
+export type MetadataValueType =
+  | "string"
+  | "number"
+  | "bool"
+  | "object"
+  | "array"
+  | "null";
+
+/**
+ * How one metadata key is used.
+ *
+ * @property key - The key
+ * @property count - Number of items that have it
+ * @property valueTypes - Number of items per type of their value; more than
+ *   one type means the key is used inconsistently
+ * @property examples - Up to three distinct values, the most used first
+ * @property similarKeys - Other keys that differ only in case or separators
+ */
+export interface MetadataKeyUsage {
+  key: string;
+  count: number;
+  valueTypes: Partial<Record<MetadataValueType, number>>;
+  examples: unknown[];
+  similarKeys: string[];
+}
+
+/**
+ * The metadata keys an environment's services or relationships use.
+ *
+ * @property itemCount - Number of services or relationships
+ * @property itemsWithMetadata - Number of them with at least one key
+ * @property keys - Every key used, the most used first
+ */
+export interface MetadataReport {
+  itemCount: number;
+  itemsWithMetadata: number;
+  keys: MetadataKeyUsage[];
+}
+
+/**
+ * What a rename does with an item that already has the new key:
+ * `keep_existing` keeps its value, `overwrite` replaces it with the old key's.
+ */
+export type KeyMergePolicy = "keep_existing" | "overwrite";
+
+/**
+ * The result of renaming a metadata key.
+ *
+ * @property renamed - IDs of the items whose key was renamed
+ * @property merged - IDs of those that had both keys
+ * @property result - What was saved (or, in a dry run, would be)
+ */
+export interface MetadataKeyRename {
+  renamed: string[];
+  merged: string[];
+  result: BatchResult;
+}
+
+/**
+ * Lists the top-level metadata keys an environment's relationships use.
+ *
+ * @param environment - The environment to scan
+ * @returns Promise resolving to every key with its counts, value types and
+ *   examples
+ */
+export async function getRelationshipMetadataReport(
+  environment: string
+): Promise<MetadataReport> {
+  return invoke<MetadataReport>("get_relationship_metadata_report", {
+    environment,
+  });
+}
+
+/**
+ * Lists the top-level metadata keys an environment's services use.
+ *
+ * @param environment - The environment to scan
+ * @returns Promise resolving to every key with its counts, value types and
+ *   examples
+ */
+export async function getServiceMetadataReport(
+  environment: string
+): Promise<MetadataReport> {
+  return invoke<MetadataReport>("get_service_metadata_report", {
+    environment,
+  });
+}
+
+/**
+ * Renames a metadata key on every relationship that has it, as one batch.
+ *
+ * @param environment - The environment to rewrite
+ * @param oldKey - The key to rename
+ * @param newKey - The key to rename it to
+ * @param merge - What to do with relationships that have both keys; without
+ *   it they are refused
+ * @param dryRun - Only compute the renames (default: false)
+ * @returns Promise resolving to the renamed relationships
+ * @throws Error if relationships have both keys and no merge policy is given
+ *
+ * @example
+ * ```typescript
+ * await renameRelationshipMetadataKey('prod', 'proto', 'protocol');
+ * ```
+ */
+export async function renameRelationshipMetadataKey(
+  environment: string,
+  oldKey: string,
+  newKey: string,
+  merge?: KeyMergePolicy,
+  dryRun?: boolean
+): Promise<MetadataKeyRename> {
+  return invoke<MetadataKeyRename>("rename_relationship_metadata_key", {
+    environment,
+    oldKey,
+    newKey,
+    merge,
+    dryRun,
+  });
+}
+
+/**
+ * Renames a metadata key on every service that has it, as one batch.
+ *
+ * @param environment - The environment to rewrite
+ * @param oldKey - The key to rename
+ * @param newKey - The key to rename it to
+ * @param merge - What to do with services that have both keys; without it
+ *   they are refused
+ * @param dryRun - Only compute the renames (default: false)
+ * @returns Promise resolving to the renamed services
+ * @throws Error if services have both keys and no merge policy is given
+ */
+export async function renameServiceMetadataKey(
+  environment: string,
+  oldKey: string,
+  newKey: string,
+  merge?: KeyMergePolicy,
+  dryRun?: boolean
+): Promise<MetadataKeyRename> {
+  return invoke<MetadataKeyRename>("rename_service_metadata_key", {
+    environment,
+    oldKey,
+    newKey,
+    merge,
+    dryRun,
+  });
+}
+
+// ============================================================================
+// Snapshot Commands
+// ============================================================================
+
+/**
+ * Takes a snapshot of an environment's current services and relationships.
+ *
+ * @param environment - The environment to snapshot
+ * @param label - A human-readable label, e.g. "Before Q4 review"
+ * @returns Promise resolving to the new snapshot's ID, label and counts
+ * @throws Error if the label is empty
+ */
+export async function createSnapshot(
+  environment: string,
+  label: string
+): Promise<SnapshotInfo> {
+  return invoke<SnapshotInfo>("create_snapshot", { environment, label });
+}
+
+/**
+ * Lists the snapshots of an environment, newest first.
+ *
+ * @param environment - The environment whose snapshots to list
+ * @returns Promise resolving to the snapshots
+ */
+export async function listSnapshots(
+  environment: string
+): Promise<SnapshotInfo[]> {
+  return invoke<SnapshotInfo[]>("list_snapshots", { environment });
+}
+
+/**
+ * Compares a snapshot with the environment's current data.
+ *
+ * @param environment - The environment the snapshot was taken of
+ * @param snapshotId - The ID of the snapshot
+ * @returns Promise resolving to what was added, removed and changed since
+ * @throws Error if the snapshot is not found
+ *
+ * @example
+ * ```typescript
+ * const diff = await diffSnapshot('prod', snapshot.id);
+ * console.log(`${diff.services.added.length} services added`);
+ * ```
+ */
+export async function diffSnapshot(
+  environment: string,
+  snapshotId: string
+): Promise<EnvironmentDiff> {
+  return invoke<EnvironmentDiff>("diff_snapshot", { environment, snapshotId });
+}
+
+/**
+ * Deletes a snapshot.
+ *
+ * @param environment - The environment the snapshot was taken of
+ * @param snapshotId - The ID of the snapshot
+ * @throws Error if the snapshot is not found
+ */
+export async function deleteSnapshot(
+  environment: string,
+  snapshotId: string
+): Promise<void> {
+  return invoke<void>("delete_snapshot", { environment, snapshotId });
+}
+
+// ============================================================================
+// Scheduled Export Commands
+// ============================================================================
+
+/**
+ * Runs the configured scheduled export now, even if it is disabled.
+ *
+ * @returns Promise resolving to one run per configured environment
+ * @throws Error if no environment is configured or the settings are invalid
+ */
+export async function runScheduledExportNow(): Promise<ScheduledRun[]> {
+  return invoke<ScheduledRun[]>("run_scheduled_export_now");
+}
+
+/**
+ * Gets the recent scheduled and manual export runs since the app started.
+ *
+ * @returns Promise resolving to the runs, most recent first
+ */
+export async function getScheduledTaskHistory(): Promise<ScheduledRun[]> {
+  return invoke<ScheduledRun[]>("get_scheduled_task_history");
+}
+
+// ============================================================================
+// Environment Commands
+// ============================================================================
+
+/**
+ * Lists all available environments in the data directory.
+ *
+ * Returns environments sorted by priority: dev, staging, prod, then
+ * alphabetically for others.
+ *
+ * @returns Promise resolving to an array of environment names
+ * @throws Error if the backend fails to read the data directory
+ *
+ * @example
+ * ```typescript
+ * const environments = await listEnvironments();
+ * // ['dev', 'staging', 'prod', 'feature-branch']
+ * ```
+ */
+export async function listEnvironments(): Promise<string[]> {
+  return invoke<string[]>("list_environments");
+}
+
+/**
+ * Retrieves the currently active environment name.
+ *
+ * @returns Promise resolving to the current environment name
+ * @throws Error if the backend fails to get the current environment
+ *
+ * @example
+ * ```typescript
+ * const current = await getCurrentEnvironment();
+ * console.log(`Currently viewing: ${current}`);
+ * ```
+ */
+export async function getCurrentEnvironment(): Promise<string> {
+  return invoke<string>("get_current_environment");
+}
+
+/**
+ * Switches the active environment to a different one.
+ *
+ * After switching, all subsequent service/relationship operations will
+ * use the new environment.
+ *
+ * @param environment - The name of the environment to switch to
+ * @param preload - If true, load the environment into the caches in the
+ *   background so the first query is served from memory
+ * @returns Promise resolving when the switch is complete
+ * @throws Error if the environment doesn't exist
+ *
+ * @example
+ * ```typescript
+ * await switchEnvironment('staging', true);
+ * // All subsequent operations now use 'staging' environment
+ * ```
+ */
+export async function switchEnvironment(
+  environment: string,
+  preload = false
+): Promise<void> {
+  return invoke<void>("switch_environment", { environment, preload });
+}
+
+/**
+ * The numbers of services and relationships in an environment.
+ *
+ * @property serviceCount - Number of services
+ * @property relationshipCount - Number of relationships
+ * @property statusCounts - Number of services per status; statuses without
+ *   services are omitted
+ */
+export interface EnvironmentCounts {
+  serviceCount: number;
+  relationshipCount: number;
+  statusCounts: Partial<Record<ServiceStatus, number>>;
+}
+
+/**
+ * Counts the services and relationships in an environment, for badges and
+ * summaries that don't need the data itself.
+ *
+ * @param environment - The name of the environment to count
+ * @returns Promise resolving to the counts
+ * @throws Error if the environment's data cannot be read
+ *
+ * @example
+ * ```typescript
+ * const counts = await getCounts('dev');
+ * console.log(`${counts.statusCounts.unhealthy ?? 0} unhealthy`);
+ * ```
+ */
+export async function getCounts(environment: string): Promise<EnvironmentCounts> {
+  return invoke<EnvironmentCounts>("get_counts", { environment });
+}
+
+/**
+ * What a generated sample environment contains. Every field is optional.
+ *
+ * @property serviceCount - Number of services to generate (default: 50)
+ * @property seed - The same seed and spec give the same data (default: 42)
+ * @property typeWeights - Relative weights per service type; all built-in
+ *   types equally if empty
+ * @property statusWeights - Relative weights per status; mostly healthy if empty
+ * @property teamCount - Number of teams services are spread over (default: 5)
+ * @property averageFanOut - Average dependencies per service (default: 2.0)
+ * @property includeCycles - Add a few circular dependencies
+ * @property includeOrphans - Add a few relationships to missing services
+ */
+export interface SampleSpec {
+  serviceCount?: number;
+  seed?: number;
+  typeWeights?: [ServiceType, number][];
+  statusWeights?: [ServiceStatus, number][];
+  teamCount?: number;
+  averageFanOut?: number;
+  includeCycles?: boolean;
+  includeOrphans?: boolean;
+}
+
+/**
+ * The result of generating a sample environment.
+ *
+ * @property environment - The environment that was written
+ * @property services - Number of services generated
+ * @property relationships - Number of relationships generated
+ * @property replacedServices - Number of existing services that were removed
+ * @property dryRun - Whether nothing was written
+ */
+export interface SampleEnvironmentReport {
+  environment: string;
+  services: number;
+  relationships: number;
+  replacedServices: number;
+  dryRun: boolean;
+}
+
+/**
+ * Fills an environment with reproducible generated data, for demos and
+ * stress-testing the UI. The whole generation is one undo entry.
+ *
+ * @param environment - The environment to fill; created if it doesn't exist
+ * @param spec - What to generate
+ * @param overwrite - Replace the data of an environment that isn't empty
+ * @param dryRun - Only report what would be generated and replaced
+ * @returns Promise resolving to what was (or would be) generated
+ * @throws Error if the spec asks for too many services, or the environment
+ *   isn't empty and overwrite isn't set
+ *
+ * @example
+ * ```typescript
+ * const report = await generateSampleEnvironment(
+ *   'demo',
+ *   { serviceCount: 5000, seed: 7, includeCycles: true },
+ *   true
+ * );
+ * ```
+ */
+export async function generateSampleEnvironment(
+  environment: string,
+  spec?: SampleSpec,
+  overwrite?: boolean,
+  dryRun?: boolean
+): Promise<SampleEnvironmentReport> {
+  return invoke<SampleEnvironmentReport>("generate_sample_environment", {
+    environment,
+    spec,
+    overwrite,
+    dryRun,
+  });
+}
+
+/**
+ * A data file that was (or would be) rewritten at the current schema version.
+ *
+ * @property path - Path of the migrated file
+ * @property fromVersion - The schema version the file was stored at
+ * @property toVersion - The schema version the file was upgraded to
+ * @property applied - Descriptions of the migrations applied, in order
+ */
+export interface FileMigration {
+  path: string;
+  fromVersion: number;
+  toVersion: number;
+  applied: string[];
+}
+
+/**
+ * The result of migrating an environment's files to the current schema.
+ *
+ * @property environment - The environment that was migrated
+ * @property targetVersion - The schema version files were upgraded to
+ * @property files - Each file that was (or would be) rewritten
+ * @property dryRun - Whether nothing was written
+ */
+export interface MigrationReport {
+  environment: string;
+  targetVersion: number;
+  files: FileMigration[];
+  dryRun: boolean;
+}
+
+/**
+ * Rewrites an environment's outdated data files at the latest schema version.
+ *
+ * Loading already migrates in memory; this persists the upgrade. With a
+ * SQLite data path the report is always empty.
+ *
+ * @param environment - The name of the environment to migrate
+ * @param dryRun - When true, only report what would change
+ * @returns Promise resolving to the files that were (or would be) migrated
+ * @throws Error if a file cannot be read, migrated or written
+ *
+ * @example
+ * ```typescript
+ * const report = await migrateEnvironment('dev', true);
+ * console.log(`${report.files.length} files need migration`);
+ * ```
+ */
+export async function migrateEnvironment(
+  environment: string,
+  dryRun: boolean
+): Promise<MigrationReport> {
+  return invoke<MigrationReport>("migrate_environment", {
+    environment,
+    dryRun,
+  });
+}
+
+/**
+ * How an environment's relationships are stored on disk: one
+ * `relationships.json`, or one `relationships/{sourceServiceId}.json` per
+ * source service.
+ */
+export type RelationshipLayout = "single" | "sharded";
+
+/**
+ * The result of switching an environment's relationship storage layout.
+ *
+ * @property environment - The environment that was migrated
+ * @property layout - The layout the environment uses now
+ * @property files - The files that were written or removed (empty if the
+ *   environment already used the layout)
+ */
+export interface RelationshipStorageMigration {
+  environment: string;
+  layout: RelationshipLayout;
+  files: string[];
+}
+
+/**
+ * Switches an environment between single-file and sharded relationship
+ * storage. Sharding makes saving a relationship rewrite only its source
+ * service's file; every command works the same with both layouts.
+ *
+ * @param environment - The name of the environment to migrate
+ * @param sharded - True for one file per source service
+ * @returns Promise resolving to the layout now in use and the changed files
+ * @throws Error if the data path is a SQLite database and `sharded` is true
+ */
+export async function migrateRelationshipStorage(
+  environment: string,
+  sharded: boolean
+): Promise<RelationshipStorageMigration> {
+  return invoke<RelationshipStorageMigration>("migrate_relationship_storage", {
+    environment,
+    sharded,
+  });
+}
+
+/**
+ * The result of normalizing an environment's data files.
+ *
+ * @property environment - The environment that was normalized
+ * @property files - The files that were (or would be) rewritten
+ * @property dryRun - Whether nothing was written
+ */
+export interface NormalizationReport {
+  environment: string;
+  files: string[];
+  dryRun: boolean;
+}
+
+/**
+ * Rewrites an environment's service and relationships files in the form
+ * every save writes them in (sorted tags, metadata keys and relationships,
+ * two-space indentation, a trailing newline), so git diffs of the data
+ * directory only show real changes.
+ *
+ * @param environment - The name of the environment to normalize
+ * @param dryRun - Only report which files would change
+ * @returns Promise resolving to the files that were (or would be) rewritten
+ * @throws Error if a file cannot be read or parsed
+ */
+export async function normalizeEnvironmentFiles(
+  environment: string,
+  dryRun: boolean
+): Promise<NormalizationReport> {
+  return invoke<NormalizationReport>("normalize_environment_files", {
+    environment,
+    dryRun,
+  });
+}
+
+/**
+ * What is cached for one environment.
+ *
+ * @property environment - The environment name
+ * @property serviceCount - Number of cached services, if cached
+ * @property relationshipCount - Number of cached relationships, if cached
+ * @property servicesLoadedAt - When services were loaded (ms since epoch)
+ * @property relationshipsLoadedAt - When relationships were loaded (ms)
+ */
+export interface CachedEnvironmentInfo {
+  environment: string;
+  serviceCount: number | null;
+  relationshipCount: number | null;
+  servicesLoadedAt: number | null;
+  relationshipsLoadedAt: number | null;
+}
+
+/**
+ * A snapshot of the caches.
+ *
+ * @property generation - Incremented on every write and invalidation
+ * @property environments - The cached environments, sorted by name
+ */
+export interface CacheInfo {
+  generation: number;
+  environments: CachedEnvironmentInfo[];
+}
+
+/**
+ * Reloads an environment's services and relationships from storage.
+ *
+ * Use this after editing data files outside the app when the automatic
+ * freshness checks can't see the change.
+ *
+ * @param environment - The name of the environment to reload
+ * @returns Promise resolving to what is cached for the environment afterwards
+ * @throws Error if the environment's data cannot be read
+ *
+ * @example
+ * ```typescript
+ * const info = await refreshEnvironment('dev');
+ * console.log(`Reloaded ${info.serviceCount} services`);
+ * ```
+ */
+export async function refreshEnvironment(
+  environment: string
+): Promise<CachedEnvironmentInfo> {
+  return invoke<CachedEnvironmentInfo>("refresh_environment", { environment });
+}
+
+/**
+ * Loads an environment into the caches ahead of its first query.
+ *
+ * @param environment - The name of the environment to load
+ * @returns Promise resolving to what is cached for the environment
+ * @throws Error if the environment's data cannot be read
+ */
+export async function preloadEnvironment(
+  environment: string
+): Promise<CachedEnvironmentInfo> {
+  return invoke<CachedEnvironmentInfo>("preload_environment", { environment });
+}
+
+/**
+ * Drops cached data, for troubleshooting.
+ *
+ * @param environment - The environment to clear, or null for all of them
+ * @returns Promise resolving to what is still cached
+ */
+export async function clearCache(
+  environment: string | null = null
+): Promise<CacheInfo> {
+  return invoke<CacheInfo>("clear_cache", { environment });
+}
+
+/**
+ * Describes the current contents of the caches.
+ *
+ * @returns Promise resolving to the cached environments
+ */
+export async function getCacheInfo(): Promise<CacheInfo> {
+  return invoke<CacheInfo>("get_cache_info");
+}
+
+/**
+ * Call statistics of one command.
+ *
+ * @property calls - How often the command was called
+ * @property lastDurationMs - How long the last call took
+ * @property maxDurationMs - How long the slowest call took
+ * @property totalDurationMs - How long all calls took together
+ */
+export interface CommandStats {
+  calls: number;
+  lastDurationMs: number;
+  maxDurationMs: number;
+  totalDurationMs: number;
+}
+
+/**
+ * What the application is doing and how fast, for troubleshooting.
+ *
+ * @property appVersion - The application version
+ * @property schemaVersion - The data file schema version the app writes
+ * @property dataPath - The data directory or SQLite database file
+ * @property workspace - The active workspace, if any
+ * @property currentEnvironment - The currently selected environment
+ * @property cache - What is cached, per environment
+ * @property commands - Call statistics per instrumented command
+ * @property runningOperations - The long-running commands in progress
+ */
+export interface Diagnostics {
+  appVersion: string;
+  schemaVersion: number;
+  dataPath: string;
+  workspace: string | null;
+  currentEnvironment: string;
+  cache: CacheInfo;
+  commands: Record<string, CommandStats>;
+  runningOperations: RunningOperation[];
+}
+
+/**
+ * Collects diagnostics about the running application.
+ *
+ * @returns Promise resolving to the diagnostics
+ */
+export async function getDiagnostics(): Promise<Diagnostics> {
+  return invoke<Diagnostics>("get_diagnostics");
+}
+
+/**
+ * Resets the command call statistics of `getDiagnostics`.
+ *
+ * @returns Promise resolving when the statistics are reset
+ */
+export async function resetDiagnostics(): Promise<void> {
+  return invoke<void>("reset_diagnostics");
+}
+
+/**
+ * How a long-running operation ended.
+ */
+export type OperationOutcome = "succeeded" | "failed" | "cancelled";
+
+/**
+ * Progress of a long-running operation, from an `operation:progress` event.
+ *
+ * @property operationId - The ID the operation was started with
+ * @property phase - What the operation is doing, e.g. "Writing files"
+ * @property done - How many items of the phase are done
+ * @property total - How many items the phase has
+ * @property outcome - How the operation ended; only set on the final event
+ * @property error - The error of a failed operation
+ */
+export interface OperationProgress {
+  operationId: string;
+  phase: string;
+  done: number;
+  total: number;
+  outcome: OperationOutcome | null;
+  error: AppErrorPayload | null;
+}
+
+/**
+ * Listens for the progress events of one operation.
+ *
+ * Start listening before invoking the command with the same `operationId`.
+ *
+ * @param operationId - The ID passed to the command
+ * @param onProgress - Called with each progress event of the operation
+ * @returns Promise resolving to a function that stops listening
+ *
+ * @example
+ * ```typescript
+ * const operationId = crypto.randomUUID();
+ * const unlisten = await onOperationProgress(operationId, (p) =>
+ *   setProgress(p.phase, p.done, p.total)
+ * );
+ * try {
+ *   await validateEnvironment('prod', operationId);
+ * } finally {
+ *   unlisten();
+ * }
+ * ```
+ */
+export async function onOperationProgress(
+  operationId: string,
+  onProgress: (progress: OperationProgress) => void
+): Promise<UnlistenFn> {
+  return listen<OperationProgress>("operation:progress", (event) => {
+    if (event.payload.operationId === operationId) {
+      onProgress(event.payload);
+    }
+  });
+}
+
+/**
+ * A long-running operation in progress.
+ *
+ * @property operationId - The ID the operation was started with
+ * @property command - The command running it, e.g. "execute_batch"
+ * @property startedAt - When it started, in milliseconds since the epoch
+ * @property cancelRequested - Whether `cancelOperation` was called for it
+ */
+export interface RunningOperation {
+  operationId: string;
+  command: string;
+  startedAt: number;
+  cancelRequested: boolean;
+}
+
+/**
+ * Lists the long-running operations in progress.
+ *
+ * @returns Promise resolving to the running operations, oldest first
+ */
+export async function listRunningOperations(): Promise<RunningOperation[]> {
+  return invoke<RunningOperation[]>("list_running_operations");
+}
+
+/**
+ * Cancels a running operation.
+ *
+ * The command stops before it writes anything and rejects with an
+ * `operation_cancelled` error whose details hold the `phase` it was in and
+ * how many of its items were `done` out of `total`; once it is writing
+ * files it finishes.
+ *
+ * @param operationId - The ID the operation was started with
+ * @returns Promise resolving to false if no such operation is running
+ */
+export async function cancelOperation(operationId: string): Promise<boolean> {
+  return invoke<boolean>("cancel_operation", { operationId });
+}
+
+/**
+ * Creates a new environment with the required directory structure.
+ *
+ * Creates a new environment directory with an empty services folder and
+ * an empty relationships.json file.
+ *
+ * @param environment - The name of the new environment to create
+ * @returns Promise resolving when the environment is created
+ * @throws Error if the environment already exists or creation fails
+ *
+ * @example
+ * ```typescript
+ * await createEnvironment('staging');
+ * ```
+ */
+export async function createEnvironment(environment: string): Promise<void> {
+  return invoke<void>("create_environment", { environment });
+}
+
+/**
+ * The services an extraction takes: those listed in `serviceIds`, and those
+ * matching every given criterion out of `tags`, `teams` and `serviceTypes`
+ * (any of the values of each).
+ */
+export interface ServiceSelector {
+  serviceIds?: string[];
+  tags?: string[];
+  teams?: string[];
+  serviceTypes?: ServiceType[];
+}
+
+/**
+ * What `extractToEnvironment` copied or moved.
+ *
+ * @property services - IDs of the services taken
+ * @property relationships - IDs of the relationships between them
+ * @property boundaryRelationships - Relationships to services left behind;
+ *   not copied, and orphaned in the source after a move
+ * @property moved - Whether they were removed from the source
+ * @property dryRun - Whether nothing was written
+ */
+export interface ExtractionReport {
+  sourceEnvironment: string;
+  targetEnvironment: string;
+  services: string[];
+  relationships: string[];
+  boundaryRelationships: Relationship[];
+  moved: boolean;
+  dryRun: boolean;
+}
+
+/**
+ * Copies or moves the selected services, and the relationships between
+ * them, into a new environment.
+ *
+ * @param sourceEnvironment - The environment to take the services from
+ * @param targetEnvironment - The environment to create; must not exist
+ * @param selector - Which services to take
+ * @param moveServices - Delete them from the source (default: false)
+ * @param dryRun - Only report what would be taken (default: false)
+ * @returns Promise resolving to what was taken and the boundary
+ *   relationships
+ * @throws Error if the target exists or the selector matches nothing
+ *
+ * @example
+ * ```typescript
+ * await extractToEnvironment('prod', 'payments', { teams: ['Payments'] });
+ * ```
+ */
+export async function extractToEnvironment(
+  sourceEnvironment: string,
+  targetEnvironment: string,
+  selector: ServiceSelector,
+  moveServices?: boolean,
+  dryRun?: boolean
+): Promise<ExtractionReport> {
+  return invoke<ExtractionReport>("extract_to_environment", {
+    sourceEnvironment,
+    targetEnvironment,
+    selector,
+    moveServices,
+    dryRun,
+  });
+}
+
+/**
+ * Sets the root data directory path for all environment data.
+ *
+ * Use this to point the application at a different data location.
+ * This clears all cached data.
+ *
+ * @param path - The absolute path to the new data directory
+ * @returns Promise resolving when the path is updated
+ * @throws Error if the path doesn't exist or isn't a directory
+ *
+ * @example
+ * ```typescript
+ * await setDataPath('/Users/user/projects/my-app/service-data');
+ * ```
+ */
+export async function setDataPath(path: string): Promise<void> {
+  return invoke<void>("set_data_path", { path });
+}
+
+/**
+ * The result of importing a data directory into a SQLite database.
+ *
+ * @property dbPath - The database file that was written
+ * @property environments - Names of the environments that were imported
+ * @property servicesImported - Total number of services imported
+ * @property relationshipsImported - Total number of relationships imported
+ */
+export interface SqliteMigrationReport {
+  dbPath: string;
+  environments: string[];
+  servicesImported: number;
+  relationshipsImported: number;
+}
+
+/**
+ * Imports a JSON data directory into a SQLite database file.
+ *
+ * Data the database already holds for the same environments is replaced;
+ * the directory is left untouched. Call `setDataPath` with the database
+ * path afterwards to switch to it.
+ *
+ * @param dataPath - The data directory to import
+ * @param dbPath - The database file to write (defaults to `{dataPath}.db`)
+ * @returns Promise resolving to what was imported and where
+ * @throws Error if the paths are invalid or the data cannot be imported
+ *
+ * @example
+ * ```typescript
+ * const report = await migrateToSqlite('/Users/user/projects/my-app/service-data');
+ * await setDataPath(report.dbPath);
+ * ```
+ */
+export async function migrateToSqlite(
+  dataPath: string,
+  dbPath?: string
+): Promise<SqliteMigrationReport> {
+  return invoke<SqliteMigrationReport>("migrate_to_sqlite", {
+    dataPath,
+    dbPath,
+  });
+}
+
+/**
+ * A subdirectory of a candidate data directory that looks like an environment.
+ *
+ * @property name - The directory name
+ * @property hasServicesDir - Whether it has a `services/` directory
+ * @property hasRelationshipsFile - Whether it has a `relationships.json`
+ * @property serviceFileCount - Number of service files in `services/`
+ */
+export interface EnvironmentPreflight {
+  name: string;
+  hasServicesDir: boolean;
+  hasRelationshipsFile: boolean;
+  serviceFileCount: number;
+}
+
+/**
+ * What a candidate data path contains.
+ *
+ * @property path - The candidate path
+ * @property usable - Whether `setDataPath` would accept the path
+ * @property sqlite - Whether the path is a SQLite database file
+ * @property environments - Subdirectories that look like environments
+ * @property otherDirectories - Subdirectories that don't look like environments
+ * @property serviceFileCount - Number of service files across all environments
+ * @property warnings - Why the path may not be the intended data directory
+ */
+export interface DataPathPreflight {
+  path: string;
+  usable: boolean;
+  sqlite: boolean;
+  environments: EnvironmentPreflight[];
+  otherDirectories: string[];
+  serviceFileCount: number;
+  warnings: string[];
+}
+
+/**
+ * Inspects a candidate data directory without switching to it.
+ *
+ * @param path - The candidate data directory or SQLite database file
+ * @returns Promise resolving to what the path contains
+ *
+ * @example
+ * ```typescript
+ * const preflight = await validateDataPath(selectedFolder);
+ * if (preflight.usable && preflight.warnings.length === 0) {
+ *   await setDataPath(selectedFolder);
+ * }
+ * ```
+ */
+export async function validateDataPath(
+  path: string
+): Promise<DataPathPreflight> {
+  return invoke<DataPathPreflight>("validate_data_path", { path });
+}
+
+/**
+ * Lists the recently used data paths that still exist.
+ *
+ * @returns Promise resolving to up to 10 paths, most recently set first
+ */
+export async function getRecentDataPaths(): Promise<string[]> {
+  return invoke<string[]>("get_recent_data_paths");
+}
+
+/**
+ * A named data path, as listed in the workspace switcher.
+ *
+ * @property name - The workspace name
+ * @property path - The data directory or SQLite database file
+ * @property active - Whether this is the workspace currently open
+ */
+export interface Workspace {
+  name: string;
+  path: string;
+  active: boolean;
+}
+
+/**
+ * Lists the configured workspaces.
+ *
+ * @returns Promise resolving to the workspaces, in the order they were added
+ */
+export async function listWorkspaces(): Promise<Workspace[]> {
+  return invoke<Workspace[]>("list_workspaces");
+}
+
+/**
+ * Adds a workspace without switching to it.
+ *
+ * @param name - The workspace name; must not be empty or already taken
+ * @param path - The data directory or SQLite database file
+ * @returns Promise resolving when the workspace is saved
+ * @throws Error if the name is taken or the path can't be used
+ */
+export async function addWorkspace(name: string, path: string): Promise<void> {
+  return invoke<void>("add_workspace", { name, path });
+}
+
+/**
+ * Removes a workspace. Its data is left untouched.
+ *
+ * @param name - The workspace to remove; can't be the active one
+ * @returns Promise resolving when the workspace is removed
+ */
+export async function removeWorkspace(name: string): Promise<void> {
+  return invoke<void>("remove_workspace", { name });
+}
+
+/**
+ * Switches to another workspace.
+ *
+ * The caches of the workspace switched away from are kept, so switching
+ * back is instant. `setDataPath` changes the active workspace's path.
+ *
+ * @param name - The workspace to switch to
+ * @returns Promise resolving when the workspace is open
+ * @throws Error if the workspace doesn't exist or its path can't be used
+ *
+ * @example
+ * ```typescript
+ * await switchWorkspace('Acme');
+ * const environments = await listEnvironments();
+ * ```
+ */
+export async function switchWorkspace(name: string): Promise<void> {
+  return invoke<void>("switch_workspace", { name });
+}
+
+// ============================================================================
+// Relationship Commands
+// ============================================================================
+
+/**
+ * Retrieves all relationships for a specified environment.
+ *
+ * @param environment - The name of the environment
+ * @param effectiveDates - Which relationships to return by their effective
+ *   dates (default: those in effect today)
+ * @param origin - Only return relationships with this origin
+ * @param reviewStatus - Only return relationships with this review status
+ * @returns Promise resolving to an array of all relationships
+ * @throws Error if the backend fails to load relationships
+ *
+ * @example
+ * ```typescript
+ * const relationships = await getAllRelationships('dev');
+ * console.log(`Found ${relationships.length} relationships`);
+ *
+ * const manual = await getAllRelationships('prod', undefined, 'manual');
+ * const flagged = await getAllRelationships('prod', {}, undefined, 'flagged');
+ * ```
+ */
+export async function getAllRelationships(
+  environment: string,
+  effectiveDates?: EffectiveDates,
+  origin?: RelationshipOrigin,
+  reviewStatus?: ReviewStatus
+): Promise<Relationship[]> {
+  return invoke<Relationship[]>("get_all_relationships", {
+    environment,
+    ...effectiveDates,
+    origin,
+    reviewStatus,
+  });
+}
+
+/**
+ * Retrieves all relationships involving a specific service.
+ *
+ * Returns relationships where the service is either the source or target.
+ *
+ * @param environment - The name of the environment
+ * @param serviceId - The ID of the service to find relationships for
+ * @returns Promise resolving to an array of relationships (may be empty)
+ * @throws Error if the backend fails to load relationships
+ *
+ * @example
+ * ```typescript
+ * const rels = await getRelationshipsForService('dev', 'api-gateway');
+ * // Returns all relationships where api-gateway is source OR target
+ * ```
+ */
+export async function getRelationshipsForService(
+  environment: string,
+  serviceId: string
+): Promise<Relationship[]> {
+  return invoke<Relationship[]>("get_relationships_for_service", {
+    environment,
+    serviceId,
+  });
+}
+
+/**
+ * Relationships a service is the target and the source of.
+ *
+ * @property incoming - Relationships targeting the service
+ * @property outgoing - Relationships from the service
+ */
+export interface EdgeCounts {
+  incoming: number;
+  outgoing: number;
+}
+
+/**
+ * Relationship counts of a service, for list row badges.
+ *
+ * @property incoming - Relationships targeting the service
+ * @property outgoing - Relationships from the service
+ * @property byType - The same counts per relationship type
+ */
+export interface RelationshipCounts extends EdgeCounts {
+  byType: Record<string, EdgeCounts>;
+}
+
+/**
+ * Counts the incoming and outgoing relationships of every service.
+ *
+ * Relationships whose other end doesn't exist are counted too.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to counts by service ID; services without
+ *   relationships are left out
+ * @throws Error if the backend fails to load relationships
+ *
+ * @example
+ * ```typescript
+ * const counts = await getRelationshipCounts('prod');
+ * const badge = `${counts['api-gateway']?.incoming ?? 0} in`;
+ * ```
+ */
+export async function getRelationshipCounts(
+  environment: string
+): Promise<Record<string, RelationshipCounts>> {
+  return invoke<Record<string, RelationshipCounts>>(
+    "get_relationship_counts",
+    { environment }
+  );
+}
+
+/**
+ * Saves a relationship to the specified environment (create or update).
+ *
+ * If a relationship with the same ID exists, it will be updated.
+ * For new relationships, validates that no duplicate exists with the
+ * same source, target, and type.
+ *
+ * @param environment - The name of the environment
+ * @param relationship - The complete relationship object to save, with the
+ *   revision it was read at
+ * @param force - Save even if the relationship was changed elsewhere since it was read
+ * @returns Promise resolving to the saved relationship with its new revision
+ * @throws Error if a duplicate relationship exists, the relationship was changed
+ *   elsewhere (conflict), or the save fails
+ *
+ * @example
+ * ```typescript
+ * await saveRelationship('dev', {
+ *   id: 'rel-123',
+ *   source: 'api-gateway',
+ *   target: 'user-service',
+ *   relationshipType: 'depends_on',
+ *   description: 'API Gateway routes to User Service'
+ * });
+ * ```
+ */
+export async function saveRelationship(
+  environment: string,
+  relationship: Relationship,
+  force?: boolean
+): Promise<Relationship> {
+  return invoke<Relationship>("save_relationship", {
+    environment,
+    relationship,
+    force,
+  });
+}
+
+/**
+ * Records the architecture review of a relationship.
+ *
+ * Only the review fields change; `reviewedAt` becomes now, or is cleared
+ * when the status goes back to unreviewed.
+ *
+ * @param environment - The name of the environment
+ * @param relationshipId - The ID of the relationship reviewed
+ * @param status - "unreviewed", "approved" or "flagged"
+ * @param note - Optional note, e.g. why the dependency was flagged
+ * @param reviewer - Optional name or email of the reviewer
+ * @returns Promise resolving to the relationship with its new revision
+ * @throws Error if the relationship is not found or cannot be saved
+ *
+ * @example
+ * ```typescript
+ * await setRelationshipReview('prod', 'rel-123', 'approved', undefined, 'kim');
+ * ```
+ */
+export async function setRelationshipReview(
+  environment: string,
+  relationshipId: string,
+  status: ReviewStatus,
+  note?: string,
+  reviewer?: string
+): Promise<Relationship> {
+  return invoke<Relationship>("set_relationship_review", {
+    environment,
+    relationshipId,
+    status,
+    note,
+    reviewer,
+  });
+}
+
+/**
+ * A relationship a rewrite left alone because it would duplicate another.
+ *
+ * @property relationshipId - The relationship left alone
+ * @property duplicateOf - The relationship it would have duplicated
+ */
+export interface SkippedDuplicate {
+  relationshipId: string;
+  duplicateOf: string;
+}
+
+/**
+ * What retargetRelationships or resourceRelationships rewrote.
+ *
+ * @property relationships - The rewritten relationships (as they would be
+ *   saved in a dry run)
+ * @property skippedDuplicates - Relationships left alone
+ * @property dryRun - True if nothing was written
+ */
+export interface RewriteResult {
+  relationships: Relationship[];
+  skippedDuplicates: SkippedDuplicate[];
+  dryRun: boolean;
+}
+
+/**
+ * Points every matching relationship to another target, as one batch.
+ *
+ * Relationships whose rewrite would duplicate another relationship are left
+ * alone and reported.
+ *
+ * @param environment - The name of the environment
+ * @param oldTargetId - The service the relationships target now
+ * @param newTargetId - The service they should target; must exist
+ * @param relationshipTypes - Optional types to rewrite (default: all)
+ * @param sourceIds - Optional sources whose relationships to rewrite
+ * @param dryRun - If true, only compute the rewrites
+ * @returns Promise resolving to the rewritten and skipped relationships
+ * @throws Error if the new target doesn't exist or the rewrite can't be saved
+ *
+ * @example
+ * ```typescript
+ * const preview = await retargetRelationships(
+ *   'prod', 'postgres-9', 'postgres-14', undefined, undefined, true
+ * );
+ * ```
+ */
+export async function retargetRelationships(
+  environment: string,
+  oldTargetId: string,
+  newTargetId: string,
+  relationshipTypes?: RelationshipType[],
+  sourceIds?: string[],
+  dryRun?: boolean
+): Promise<RewriteResult> {
+  return invoke<RewriteResult>("retarget_relationships", {
+    environment,
+    oldTargetId,
+    newTargetId,
+    relationshipTypes,
+    sourceIds,
+    dryRun,
+  });
+}
+
+/**
+ * Moves every matching relationship to another source, as one batch.
+ *
+ * The counterpart of retargetRelationships.
+ *
+ * @param environment - The name of the environment
+ * @param oldSourceId - The service the relationships start at now
+ * @param newSourceId - The service they should start at; must exist
+ * @param relationshipTypes - Optional types to rewrite (default: all)
+ * @param targetIds - Optional targets whose relationships to rewrite
+ * @param dryRun - If true, only compute the rewrites
+ * @returns Promise resolving to the rewritten and skipped relationships
+ * @throws Error if the new source doesn't exist or the rewrite can't be saved
+ *
+ * @example
+ * ```typescript
+ * await resourceRelationships('prod', 'billing-v1', 'billing-v2');
+ * ```
+ */
+export async function resourceRelationships(
+  environment: string,
+  oldSourceId: string,
+  newSourceId: string,
+  relationshipTypes?: RelationshipType[],
+  targetIds?: string[],
+  dryRun?: boolean
+): Promise<RewriteResult> {
+  return invoke<RewriteResult>("resource_relationships", {
+    environment,
+    oldSourceId,
+    newSourceId,
+    relationshipTypes,
+    targetIds,
+    dryRun,
+  });
+}
+
+/**
+ * Generates the missing and previously generated descriptions of the
+ * relationships of an environment, as one batch.
+ *
+ * Descriptions are rendered from the relationshipDescriptions template of
+ * the settings and marked with the generatedDescription metadata flag.
+ *
+ * @param environment - The name of the environment
+ * @param overwriteExisting - Also replace descriptions people wrote
+ * @param dryRun - If true, only compute the changes
+ * @returns Promise resolving to the relationships with new descriptions
+ * @throws Error if the relationships cannot be read or written
+ *
+ * @example
+ * ```typescript
+ * const preview = await backfillRelationshipDescriptions('prod', false, true);
+ * ```
+ */
+export async function backfillRelationshipDescriptions(
+  environment: string,
+  overwriteExisting?: boolean,
+  dryRun?: boolean
+): Promise<BatchResult> {
+  return invoke<BatchResult>("backfill_relationship_descriptions", {
+    environment,
+    overwriteExisting,
+    dryRun,
+  });
+}
+
+/**
+ * Deletes a single relationship by its unique identifier.
+ *
+ * @param environment - The name of the environment
+ * @param relationshipId - The unique identifier of the relationship to delete
+ * @returns Promise resolving when the deletion is complete
+ * @throws Error if the relationship is not found or deletion fails
+ *
+ * @example
+ * ```typescript
+ * await deleteRelationship('dev', 'rel-123');
+ * ```
+ */
+export async function deleteRelationship(
+  environment: string,
+  relationshipId: string
+): Promise<void> {
+  return invoke<void>("delete_relationship", {
+    environment,
+    relationshipId,
+  });
+}
+
+/**
+ * Deletes all relationships involving a specific service.
+ *
+ * Removes relationships where the service is either source or target.
+ * Typically called before deleting a service to clean up orphaned relationships.
+ *
+ * @param environment - The name of the environment
+ * @param serviceId - The ID of the service whose relationships to delete
+ * @returns Promise resolving to the number of relationships deleted
+ * @throws Error if the backend fails to delete relationships
+ *
+ * @example
+ * ```typescript
+ * const count = await deleteRelationshipsForService('dev', 'deprecated-service');
+ * console.log(`Deleted ${count} relationships`);
+ * ```
+ */
+export async function deleteRelationshipsForService(
+  environment: string,
+  serviceId: string
+): Promise<number> {
+  return invoke<number>("delete_relationships_for_service", {
+    environment,
+    serviceId,
+  });
+}
+
+/**
+ * What a destructive command changed, or would change when run as a dry run.
+ */
+export interface BatchResult {
+  savedServices: Service[];
+  deletedServices: string[];
+  savedRelationships: Relationship[];
+  deletedRelationships: string[];
+  dryRun: boolean;
+}
+
+/**
+ * Previews which relationships deleteRelationshipsForService would delete.
+ *
+ * Nothing is written.
+ *
+ * @param environment - The name of the environment
+ * @param serviceId - The ID of the service whose relationships would be deleted
+ * @returns Promise resolving to the planned changes
+ *
+ * @example
+ * ```typescript
+ * const preview = await previewDeleteRelationshipsForService('dev', 'old-api');
+ * console.log(`Would delete ${preview.deletedRelationships.length} relationships`);
+ * ```
+ */
+export async function previewDeleteRelationshipsForService(
+  environment: string,
+  serviceId: string
+): Promise<BatchResult> {
+  return invoke<BatchResult>("delete_relationships_for_service_preview", {
+    environment,
+    serviceId,
+  });
+}
+
+// ============================================================================
+// Batch Commands
+// ============================================================================
+
+/**
+ * One operation of a batch; each behaves like the command of the same name.
+ */
+export type BatchOperation =
+  | { op: "save_service"; service: Service; force?: boolean }
+  | { op: "delete_service"; serviceId: string }
+  | { op: "save_relationship"; relationship: Relationship; force?: boolean }
+  | { op: "delete_relationship"; relationshipId: string }
+  | { op: "delete_relationships_for_service"; serviceId: string };
+
+/**
+ * Applies a list of operations to an environment as a single unit.
+ *
+ * Each operation sees the effects of the ones before it. Files are only
+ * written once every operation succeeded, so a failing operation leaves
+ * the data untouched; the whole batch is one undo entry.
+ *
+ * @param environment - The name of the environment to change
+ * @param operations - The operations to apply, in order
+ * @param dryRun - Only compute what the batch would change
+ * @param operationId - ID for progress events and `cancelOperation`
+ * @returns Promise resolving to what the batch saved and deleted
+ * @throws Error with the index of the failing operation and the error its
+ *   single command would have returned
+ *
+ * @example
+ * ```typescript
+ * // Rename a service and move its relationship
+ * await executeBatch('dev', [
+ *   { op: 'save_service', service: { ...oldService, id: 'user-api', revision: 0 } },
+ *   { op: 'delete_service', serviceId: 'users' },
+ *   { op: 'save_relationship', relationship: { ...rel, source: 'user-api' } },
+ * ]);
+ * ```
+ */
+export async function executeBatch(
+  environment: string,
+  operations: BatchOperation[],
+  dryRun?: boolean,
+  operationId?: string
+): Promise<BatchResult> {
+  return invoke<BatchResult>("execute_batch", {
+    environment,
+    operations,
+    dryRun,
+    operationId,
+  });
+}
+
+// ============================================================================
+// Validation Commands
+// ============================================================================
+
+/**
+ * Result of validating an environment's data integrity.
+ *
+ * @property issues - Array of validation issues found
+ * @property errorCount - Number of critical errors
+ * @property warningCount - Number of warnings
+ * @property infoCount - Number of informational notices
+ * @property suppressedIssues - Issues matching an active suppression (not counted)
+ * @property issuesByTeam - The issues grouped by team, if asked for
+ */
+export interface ValidationResult {
+  issues: ValidationIssue[];
+  errorCount: number;
+  warningCount: number;
+  infoCount: number;
+  suppressedIssues: ValidationIssue[];
+  issuesByTeam?: TeamIssues[];
+}
+
+/**
+ * Validates the entire environment for data integrity issues.
+ *
+ * Checks for:
+ * - Duplicate service IDs (error)
+ * - Missing required fields (error)
+ * - Orphaned relationships (error)
+ * - Invalid relationship types (warning)
+ * - Circular dependencies (warning)
+ * - Unreachable services (info)
+ *
+ * @param environment - The name of the environment to validate
+ * @param operationId - ID for progress events and `cancelOperation`
+ * @param groupIssuesByTeam - Also group the issues by team in `issuesByTeam`
+ * @returns Promise resolving to the validation results
+ * @throws Error if the backend fails to validate
+ *
+ * @example
+ * ```typescript
+ * const result = await validateEnvironment('dev');
+ * if (result.errorCount > 0) {
+ *   console.log('Environment has errors:', result.issues);
+ * }
+ * ```
+ */
+export async function validateEnvironment(
+  environment: string,
+  operationId?: string,
+  groupIssuesByTeam?: boolean
+): Promise<ValidationResult> {
+  return invoke<ValidationResult>("validate_environment", {
+    environment,
+    operationId,
+    groupIssuesByTeam,
+  });
+}
+
+/**
+ * Checks a relationship draft without saving it: missing endpoints and
+ * duplicates (errors), self references, the direction heuristics and a
+ * dependency cycle the draft would close (warnings).
+ *
+ * @param environment - The environment the relationship is for
+ * @param relationship - The relationship as filled in so far; an existing
+ *   relationship's ID makes it an edit
+ * @returns Promise resolving to the draft's issues; no errors means
+ *   saveRelationship accepts it
+ *
+ * @example
+ * ```typescript
+ * const check = await validateRelationshipDraft('prod', draft);
+ * setCanSave(check.errorCount === 0);
+ * ```
+ */
+export async function validateRelationshipDraft(
+  environment: string,
+  relationship: Relationship
+): Promise<ValidationResult> {
+  return invoke<ValidationResult>("validate_relationship_draft", {
+    environment,
+    relationship,
+  });
+}
+
+/**
+ * File formats of an exported validation report: the full result as JSON
+ * for scripts, or the issues grouped by severity as Markdown.
+ */
+export type ReportFormat = "json" | "markdown";
+
+/**
+ * Limits above which an exported report counts as failed.
+ *
+ * @property maxErrors - Most errors allowed (no limit if absent)
+ * @property maxWarnings - Most warnings allowed (no limit if absent)
+ */
+export interface FailThreshold {
+  maxErrors?: number;
+  maxWarnings?: number;
+}
+
+/**
+ * Summary of an exported validation report.
+ *
+ * @property outputPath - Where the report was written
+ * @property errorCount - Number of counted errors
+ * @property warningCount - Number of counted warnings
+ * @property infoCount - Number of counted informational notices
+ * @property suppressedCount - Issues left out of the counts by a suppression
+ * @property passed - Whether the counts are within the fail threshold (null without one)
+ * @property redactedValues - How many values were masked (0 without redact)
+ */
+export interface ValidationReportSummary {
+  outputPath: string;
+  errorCount: number;
+  warningCount: number;
+  infoCount: number;
+  suppressedCount: number;
+  passed: boolean | null;
+  redactedValues: number;
+}
+
+/**
+ * Validates an environment like validateEnvironment and writes the results
+ * to a report file, with the flagged dependencies and their review notes.
+ *
+ * @param environment - The name of the environment to validate
+ * @param outputPath - The file to write; replaced if it exists
+ * @param format - The report format
+ * @param failThreshold - Limits the result must stay within to pass
+ * @param redact - Mask the values the redaction config of the settings lists
+ * @returns Promise resolving to the counts of the written report
+ * @throws Error if the data cannot be read or the report cannot be written
+ *
+ * @example
+ * ```typescript
+ * const summary = await exportValidationReport(
+ *   'prod',
+ *   '/tmp/prod-validation.json',
+ *   'json',
+ *   { maxErrors: 0 }
+ * );
+ * if (!summary.passed) {
+ *   console.log(`${summary.errorCount} errors`);
+ * }
+ * ```
+ */
+export async function exportValidationReport(
+  environment: string,
+  outputPath: string,
+  format: ReportFormat,
+  failThreshold?: FailThreshold,
+  redact?: boolean
+): Promise<ValidationReportSummary> {
+  return invoke<ValidationReportSummary>("export_validation_report", {
+    environment,
+    outputPath,
+    format,
+    failThreshold,
+    redact,
+  });
+}
+
+/**
+ * Two services that may be the same service entered twice.
+ *
+ * @property firstId - ID of one of the services
+ * @property firstName - Name of that service
+ * @property secondId - ID of the other service
+ * @property secondName - Name of the other service
+ * @property distance - Edit distance between the normalized names (0 if equal)
+ */
+export interface PossibleDuplicate {
+  firstId: string;
+  firstName: string;
+  secondId: string;
+  secondName: string;
+  distance: number;
+}
+
+/**
+ * Result of a possible duplicates scan.
+ *
+ * @property pairs - The possible duplicates, closest first
+ * @property truncated - True if more pairs exist than were returned
+ */
+export interface PossibleDuplicatesResult {
+  pairs: PossibleDuplicate[];
+  truncated: boolean;
+}
+
+/**
+ * Finds services whose names are so similar they are probably duplicates.
+ *
+ * @param environment - The name of the environment to scan
+ * @param threshold - Largest edit distance between normalized names that counts as similar
+ * @returns Promise resolving to at most 200 pairs of possible duplicates
+ * @throws Error if the services cannot be loaded
+ *
+ * @example
+ * ```typescript
+ * const { pairs } = await findPossibleDuplicates('prod');
+ * pairs.forEach((p) => console.log(`${p.firstId} ~ ${p.secondId}`));
+ * ```
+ */
+export async function findPossibleDuplicates(
+  environment: string,
+  threshold?: number
+): Promise<PossibleDuplicatesResult> {
+  return invoke<PossibleDuplicatesResult>("find_possible_duplicates", {
+    environment,
+    threshold,
+  });
+}
+
+/**
+ * A depends_on relationship to a service of a less critical tier.
+ *
+ * @property relationshipId - ID of the relationship
+ * @property sourceId - The depending service
+ * @property sourceTier - Its tier
+ * @property targetId - The dependency
+ * @property targetTier - Its (higher numbered) tier
+ */
+export interface TierViolation {
+  relationshipId: string;
+  sourceId: string;
+  sourceTier: number;
+  targetId: string;
+  targetTier: number;
+}
+
+/**
+ * Tier violations of the services of one team.
+ *
+ * @property team - The team of the depending services (null for services without one)
+ * @property violations - Their tier violations
+ */
+export interface TeamTierViolations {
+  team: string | null;
+  violations: TierViolation[];
+}
+
+/**
+ * Tier classification health of an environment.
+ *
+ * @property teams - Violations by the team of the depending service, most first
+ * @property violationCount - Total number of tier violations
+ * @property unclassified - IDs of the services without a tier, sorted
+ */
+export interface TierReport {
+  teams: TeamTierViolations[];
+  violationCount: number;
+  unclassified: string[];
+}
+
+/**
+ * Reports the tier violations of an environment, grouped by the team that
+ * has to remediate them, and the services without a tier.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to the violations and the unclassified services
+ * @throws Error if the services or relationships cannot be loaded
+ *
+ * @example
+ * ```typescript
+ * const report = await getTierReport('prod');
+ * for (const { team, violations } of report.teams) {
+ *   console.log(`${team ?? 'No team'}: ${violations.length} violations`);
+ * }
+ * ```
+ */
+export async function getTierReport(environment: string): Promise<TierReport> {
+  return invoke<TierReport>("get_tier_report", { environment });
+}
+
+/**
+ * A deprecated service and the services still using it.
+ *
+ * @property serviceId - ID of the deprecated service
+ * @property serviceName - Name of the deprecated service
+ * @property dependents - IDs of the services pointing at it, sorted
+ */
+export interface DeprecatedUsage {
+  serviceId: string;
+  serviceName: string;
+  dependents: string[];
+}
+
+/**
+ * Lists the deprecated services of an environment and who still uses them.
+ * Deprecated services nothing points at are included with no dependents,
+ * as they can be removed.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to one entry per deprecated service, most used first
+ * @throws Error if the services or relationships cannot be loaded
+ *
+ * @example
+ * ```typescript
+ * const usage = await getDeprecatedUsage('prod');
+ * for (const { serviceId, dependents } of usage) {
+ *   console.log(`${serviceId}: ${dependents.length} services to migrate`);
+ * }
+ * ```
+ */
+export async function getDeprecatedUsage(
+  environment: string
+): Promise<DeprecatedUsage[]> {
+  return invoke<DeprecatedUsage[]>("get_deprecated_usage", { environment });
+}
+
+/**
+ * A service being retired and the services still using it.
+ *
+ * @property serviceId - ID of the service
+ * @property serviceName - Name of the service
+ * @property status - The service's status
+ * @property lifecycle - The service's lifecycle stage, if set
+ * @property decommissionDate - The planned decommission date, if set
+ * @property successorId - ID of the service replacing it, if set
+ * @property dependents - IDs of the services pointing at it, sorted
+ * @property overdue - True if the decommission date has passed without the
+ *   service being decommissioned
+ */
+export interface DecommissionPlanEntry {
+  serviceId: string;
+  serviceName: string;
+  status: ServiceStatus;
+  lifecycle: ServiceLifecycle | null;
+  decommissionDate: string | null;
+  successorId: string | null;
+  dependents: string[];
+  overdue: boolean;
+}
+
+/**
+ * Lists the deprecated, decommissioned and decommission-dated services and
+ * what still depends on them.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to the entries, earliest decommission date first
+ * @throws Error if the services or relationships cannot be loaded
+ *
+ * @example
+ * ```typescript
+ * const plan = await getDecommissionPlan('prod');
+ * const overdue = plan.filter((entry) => entry.overdue);
+ * ```
+ */
+export async function getDecommissionPlan(
+  environment: string
+): Promise<DecommissionPlanEntry[]> {
+  return invoke<DecommissionPlanEntry[]>("get_decommission_plan", {
+    environment,
+  });
+}
+
+/**
+ * A service file renamed to match the ID it contains.
+ *
+ * @property serviceId - The ID contained in the file
+ * @property fromPath - The file's previous path
+ * @property toPath - The path matching the service ID
+ */
+export interface FilenameRepair {
+  serviceId: string;
+  fromPath: string;
+  toPath: string;
+}
+
+/**
+ * A mismatched service file that could not be renamed automatically.
+ *
+ * @property serviceId - The ID contained in the file
+ * @property path - The file's current path
+ * @property reason - Why the file needs manual resolution
+ */
+export interface FilenameConflict {
+  serviceId: string;
+  path: string;
+  reason: string;
+}
+
+/**
+ * Result of a filename repair run.
+ *
+ * @property renamed - Files that were renamed (or would be, in a dry run)
+ * @property conflicts - Files that need manual resolution
+ * @property dryRun - Whether the run only reported changes without applying them
+ */
+export interface RepairFilenamesResult {
+  renamed: FilenameRepair[];
+  conflicts: FilenameConflict[];
+  dryRun: boolean;
+}
+
+/**
+ * Renames service files so their filenames match the IDs they contain,
+ * fixing the filename mismatch issues of `validateEnvironment`.
+ *
+ * A file whose target name is taken is reported as a conflict instead.
+ *
+ * @param environment - The name of the environment to repair
+ * @param dryRun - When true, only report what would be renamed
+ * @returns Promise resolving to the renamed files and the conflicts
+ * @throws Error if the data path is a SQLite database or files cannot be renamed
+ *
+ * @example
+ * ```typescript
+ * const preview = await repairFilenames('dev', true);
+ * console.log(`${preview.renamed.length} files would be renamed`);
+ * ```
+ */
+export async function repairFilenames(
+  environment: string,
+  dryRun: boolean
+): Promise<RepairFilenamesResult> {
+  return invoke<RepairFilenamesResult>("repair_filenames", {
+    environment,
+    dryRun,
+  });
+}
+
+/**
+ * The issue categories fixValidationIssues should remediate. All default to false.
+ *
+ * @property deleteOrphanedRelationships - Delete relationships whose source
+ *   or target service doesn't exist
+ * @property removeDuplicateRelationships - Delete relationships identical to
+ *   an earlier one
+ * @property stripDeprecatedRelationships - Delete relationships from or to
+ *   deprecated services
+ * @property createPlaceholderServices - Create a placeholder service for every
+ *   missing endpoint instead of deleting orphaned relationships
+ * @property pruneOrphanedAnnotations - Delete graph annotations of services
+ *   that don't exist
+ */
+export interface ValidationFixes {
+  deleteOrphanedRelationships?: boolean;
+  removeDuplicateRelationships?: boolean;
+  stripDeprecatedRelationships?: boolean;
+  createPlaceholderServices?: boolean;
+  pruneOrphanedAnnotations?: boolean;
+}
+
+/**
+ * Issue counts of a validation run.
+ */
+export interface IssueCounts {
+  errorCount: number;
+  warningCount: number;
+  infoCount: number;
+}
+
+/**
+ * Result of a fixValidationIssues run.
+ *
+ * @property deletedOrphanedRelationships - IDs of deleted orphaned relationships
+ * @property removedDuplicateRelationships - IDs of deleted duplicates
+ * @property strippedDeprecatedRelationships - IDs of deleted relationships of
+ *   deprecated services
+ * @property placeholderServices - The placeholder services created
+ * @property prunedAnnotations - Service IDs whose orphaned annotations were deleted
+ * @property before - Issue counts before the fixes
+ * @property after - Issue counts after the fixes (as they would be, in a dry run)
+ * @property dryRun - Whether nothing was written
+ */
+export interface FixValidationIssuesResult {
+  deletedOrphanedRelationships: string[];
+  removedDuplicateRelationships: string[];
+  strippedDeprecatedRelationships: string[];
+  placeholderServices: Service[];
+  prunedAnnotations: string[];
+  before: IssueCounts;
+  after: IssueCounts;
+  dryRun: boolean;
+}
+
+/**
+ * Automatically remediates selected categories of validation issues, as a
+ * single undo entry.
+ *
+ * @param environment - The name of the environment to fix
+ * @param fixes - Which issue categories to remediate
+ * @param dryRun - Only report what would change
+ * @returns Promise resolving to the changed items and the counts before and after
+ * @throws Error if both deleting orphaned relationships and creating
+ *   placeholder services are selected
+ *
+ * @example
+ * ```typescript
+ * const result = await fixValidationIssues(
+ *   'dev',
+ *   { deleteOrphanedRelationships: true, removeDuplicateRelationships: true },
+ *   true
+ * );
+ * console.log(`Errors: ${result.before.errorCount} -> ${result.after.errorCount}`);
+ * ```
+ */
+export async function fixValidationIssues(
+  environment: string,
+  fixes: ValidationFixes,
+  dryRun?: boolean
+): Promise<FixValidationIssuesResult> {
+  return invoke<FixValidationIssuesResult>("fix_validation_issues", {
+    environment,
+    fixes,
+    dryRun,
+  });
+}
+
+/**
+ * Naming and metadata conventions for the services of a data path.
+ * Patterns must match the whole value; unset patterns check nothing.
+ *
+ * @property serviceIdPattern - Pattern every service ID must match
+ * @property namePattern - Pattern every service name must match
+ * @property tagPattern - Pattern every tag must match
+ * @property requiredTags - Tags each service of a type must have, by type
+ * @property requiredMetadata - Metadata keys each service of a type must have, by type
+ * @property strict - Reject saves that break the conventions instead of
+ *   only reporting them
+ */
+export interface NamingRules {
+  serviceIdPattern?: string | null;
+  namePattern?: string | null;
+  tagPattern?: string | null;
+  requiredTags?: Record<string, string[]>;
+  requiredMetadata?: Record<string, string[]>;
+  strict?: boolean;
+}
+
+/**
+ * Where the port conflict check finds the ports and hosts of services.
+ *
+ * @property portKeys - Metadata keys holding a port or an array of ports
+ * @property hostKeys - Metadata keys holding the host; the first one set is used
+ * @property colocationTagPrefix - Prefix of the tags marking services that
+ *   run on the same hosts, e.g. `host-group:`
+ */
+export interface PortRules {
+  portKeys?: string[];
+  hostKeys?: string[];
+  colocationTagPrefix?: string | null;
+}
+
+/**
+ * When an import source counts as out of sync.
+ *
+ * @property sources - Import sources expected to sync regularly, e.g. `consul`
+ * @property staleAfterDays - Days without a sync after which a source is
+ *   reported (default: 30)
+ */
+export interface ImportRules {
+  sources?: string[];
+  staleAfterDays?: number;
+}
+
+/**
+ * Per-data-path configuration of the validation rules. Every field has a
+ * default, so an empty config keeps the built-in behavior.
+ *
+ * @property disabledRules - Issue types that are not checked at all
+ * @property severityOverrides - Severity to report an issue type with instead
+ *   of its default
+ * @property naming - Naming conventions services are checked against
+ * @property strictMetadataSchemas - Reject saves of services whose metadata
+ *   fails their type's schema instead of only reporting them
+ * @property departedOwners - Owners who have left, matched case-insensitively
+ * @property ports - Where the port conflict check finds ports and hosts
+ * @property imports - When import sources count as out of sync
+ */
+export interface ValidationConfig {
+  disabledRules?: ValidationIssue["issueType"][];
+  severityOverrides?: Partial<
+    Record<ValidationIssue["issueType"], ValidationIssue["severity"]>
+  >;
+  naming?: NamingRules;
+  strictMetadataSchemas?: boolean;
+  departedOwners?: string[];
+  ports?: PortRules;
+  imports?: ImportRules;
+}
+
+/**
+ * Retrieves the validation rule configuration of the active data path.
+ *
+ * @returns Promise resolving to the config (defaults if none was saved)
+ * @throws Error if the config file isn't valid JSON
+ */
+export async function getValidationConfig(): Promise<ValidationConfig> {
+  return invoke<ValidationConfig>("get_validation_config");
+}
+
+/**
+ * Replaces the validation rule configuration of the active data path.
+ *
+ * @param config - The complete new config
+ * @returns Promise resolving when the config is saved
+ * @throws Error if a naming pattern isn't a valid regular expression; nothing
+ *   is saved then
+ *
+ * @example
+ * ```typescript
+ * // Treat cycles as errors and hide isolated services
+ * await saveValidationConfig({
+ *   disabledRules: ['unreachable_service'],
+ *   severityOverrides: { circular_dependency: 'error' },
+ * });
+ * ```
+ */
+export async function saveValidationConfig(
+  config: ValidationConfig
+): Promise<void> {
+  return invoke<void>("save_validation_config", { config });
+}
+
+/**
+ * Retrieves the JSON Schema the metadata of a service type must satisfy.
+ *
+ * @param serviceType - The service type as serialized (e.g. "database", or a
+ *   custom type's name)
+ * @returns Promise resolving to the schema, or null if the type has none
+ * @throws Error if the type can't be used as a filename or the schema isn't valid JSON
+ */
+export async function getMetadataSchema(
+  serviceType: ServiceType
+): Promise<Record<string, unknown> | null> {
+  return invoke<Record<string, unknown> | null>("get_metadata_schema", {
+    serviceType,
+  });
+}
+
+/**
+ * Sets the JSON Schema the metadata of a service type must satisfy.
+ *
+ * validateEnvironment then checks every service of the type against it,
+ * and saveService rejects non-conforming metadata when the validation
+ * config's strictMetadataSchemas is set.
+ *
+ * @param serviceType - The service type as serialized
+ * @param schema - The JSON Schema the services' metadata must satisfy
+ * @returns Promise resolving when the schema is saved
+ * @throws Error if the schema isn't a valid JSON Schema; nothing is saved then
+ *
+ * @example
+ * ```typescript
+ * await saveMetadataSchema('database', {
+ *   type: 'object',
+ *   required: ['engine', 'version'],
+ *   properties: { engine: { type: 'string' }, version: { type: 'string' } },
+ * });
+ * ```
+ */
+export async function saveMetadataSchema(
+  serviceType: ServiceType,
+  schema: Record<string, unknown>
+): Promise<void> {
+  return invoke<void>("save_metadata_schema", { serviceType, schema });
+}
+
+/**
+ * An accepted validation issue that is no longer counted.
+ *
+ * @property fingerprint - The fingerprint of the suppressed issue
+ * @property reason - Why the issue was accepted
+ * @property user - The OS username of whoever suppressed it
+ * @property createdAt - When it was suppressed (ms since epoch)
+ * @property expiresAt - When the suppression stops applying (ms since epoch);
+ *   never if absent
+ */
+export interface Suppression {
+  fingerprint: string;
+  reason: string;
+  user: string;
+  createdAt: number;
+  expiresAt?: number;
+}
+
+/**
+ * Suppresses a validation issue, so validateEnvironment reports it as
+ * suppressed instead of counting it. Suppressing it again replaces the
+ * earlier suppression.
+ *
+ * @param environment - The name of the environment
+ * @param issueFingerprint - The `fingerprint` of the issue to suppress
+ * @param reason - Why the issue is accepted
+ * @param expires - When the suppression stops applying (ms since epoch); never if omitted
+ * @returns Promise resolving to the stored suppression
+ * @throws Error if the fingerprint or reason is empty, or expires is in the past
+ *
+ * @example
+ * ```typescript
+ * await suppressValidationIssue(
+ *   'prod',
+ *   issue.fingerprint,
+ *   'Known cycle between auth and session, tracked in OPS-42',
+ *   Date.parse('2027-01-01')
+ * );
+ * ```
+ */
+export async function suppressValidationIssue(
+  environment: string,
+  issueFingerprint: string,
+  reason: string,
+  expires?: number
+): Promise<Suppression> {
+  return invoke<Suppression>("suppress_validation_issue", {
+    environment,
+    issueFingerprint,
+    reason,
+    expires,
+  });
+}
+
+/**
+ * Lists the suppressions of an environment, expired ones included.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to the suppressions
+ * @throws Error if the suppressions file cannot be parsed
+ */
+export async function listSuppressions(
+  environment: string
+): Promise<Suppression[]> {
+  return invoke<Suppression[]>("list_suppressions", { environment });
+}
+
+/**
+ * Removes the suppression of an issue, so it is counted again.
+ *
+ * @param environment - The name of the environment
+ * @param issueFingerprint - The fingerprint of the suppressed issue
+ * @returns Promise resolving to true if a suppression was removed, false if
+ *   the issue wasn't suppressed
+ * @throws Error if the suppressions file cannot be written
+ */
+export async function removeSuppression(
+  environment: string,
+  issueFingerprint: string
+): Promise<boolean> {
+  return invoke<boolean>("remove_suppression", {
+    environment,
+    issueFingerprint,
+  });
+}
+
+// ============================================================================
+// Ownership Commands
+// ============================================================================
+
+/**
+ * Ownership coverage of an environment.
+ *
+ * @property serviceCount - Number of services in the environment
+ * @property unownedCount - Number of services with neither an owner nor a team
+ * @property unowned - The unowned services, grouped by type
+ * @property teams - Every team with its number of services
+ * @property crossTeamOwners - Owners whose services belong to several teams
+ * @property departedOwnerServices - Services owned by someone on the departed list
+ */
+export interface OwnershipReport {
+  serviceCount: number;
+  unownedCount: number;
+  unowned: { serviceType: string; serviceIds: string[] }[];
+  teams: { team: string; serviceCount: number }[];
+  crossTeamOwners: { owner: string; teams: string[]; serviceIds: string[] }[];
+  departedOwnerServices: {
+    serviceId: string;
+    serviceName: string;
+    owner: string;
+    team?: string;
+  }[];
+}
+
+/**
+ * Reports who owns the services of an environment.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to the ownership coverage
+ * @throws Error if the services or the validation config cannot be loaded
+ *
+ * @example
+ * ```typescript
+ * const report = await getOwnershipReport('prod');
+ * console.log(`${report.unownedCount} services have no owner`);
+ * ```
+ */
+export async function getOwnershipReport(environment: string): Promise<OwnershipReport> {
+  return invoke<OwnershipReport>("get_ownership_report", { environment });
+}
+
+/**
+ * An exported ownership report.
+ *
+ * @property report - The exported report, unredacted
+ * @property redactedValues - How many values were masked in the file
+ */
+export interface OwnershipExport {
+  report: OwnershipReport;
+  redactedValues: number;
+}
+
+/**
+ * Writes the ownership report of an environment as CSV.
+ *
+ * @param environment - The name of the environment
+ * @param outputPath - The file to write
+ * @param redact - Mask the values the redaction config of the settings lists
+ *   in the owner, team and detail columns (default: false)
+ * @returns Promise resolving to the exported report
+ * @throws Error if the report cannot be written or a redaction pattern is
+ *   invalid
+ */
+export async function exportOwnershipReport(
+  environment: string,
+  outputPath: string,
+  redact?: boolean
+): Promise<OwnershipExport> {
+  return invoke<OwnershipExport>("export_ownership_report", {
+    environment,
+    outputPath,
+    redact,
+  });
+}
+
+// ============================================================================
+// External Dependency Commands
+// ============================================================================
+
+/** How external dependencies are ordered: by ID, or most dependents first. */
+export type ExternalDependencyOrder = "id" | "dependent_count";
+
+/**
+ * An external service and the services that use it.
+ *
+ * @property serviceId - The external service's ID
+ * @property serviceName - The external service's display name
+ * @property dependentCount - Number of distinct services using it
+ * @property dependents - The services, grouped by relationship type and team
+ */
+export interface ExternalDependency {
+  serviceId: string;
+  serviceName: string;
+  dependentCount: number;
+  dependents: {
+    relationshipType: RelationshipType;
+    team?: string;
+    serviceIds: string[];
+  }[];
+}
+
+/**
+ * Lists the external services of an environment and who uses them,
+ * through relationships in effect today.
+ *
+ * @param environment - The name of the environment
+ * @param order - "id" or "dependent_count" (default: "id")
+ * @returns Promise resolving to every external service with its dependents
+ * @throws Error if the data cannot be loaded
+ */
+export async function getExternalDependencies(
+  environment: string,
+  order?: ExternalDependencyOrder
+): Promise<ExternalDependency[]> {
+  return invoke<ExternalDependency[]>("get_external_dependencies", {
+    environment,
+    order,
+  });
+}
+
+/**
+ * Writes the external dependency inventory of an environment as CSV, one
+ * row per dependent service and relationship type.
+ *
+ * @param environment - The name of the environment
+ * @param outputPath - The file to write
+ * @param order - "id" or "dependent_count" (default: "id")
+ * @returns Promise resolving to the exported inventory
+ * @throws Error if the inventory cannot be written
+ */
+export async function exportExternalDependencies(
+  environment: string,
+  outputPath: string,
+  order?: ExternalDependencyOrder
+): Promise<ExternalDependency[]> {
+  return invoke<ExternalDependency[]>("export_external_dependencies", {
+    environment,
+    outputPath,
+    order,
+  });
+}
+
+// ============================================================================
+// Team Commands
+// ============================================================================
+
+/**
+ * The validation issues concerning one team.
+ *
+ * @property team - The team ("unassigned" for services without one)
+ * @property issues - The issues about its services and their relationships
+ * @property errorCount - Number of errors among them
+ * @property warningCount - Number of warnings among them
+ * @property infoCount - Number of informational notices among them
+ */
+export interface TeamIssues {
+  team: string;
+  issues: ValidationIssue[];
+  errorCount: number;
+  warningCount: number;
+  infoCount: number;
+}
+
+/**
+ * The relationships between a team and one other team.
+ *
+ * @property team - The other team
+ * @property relationships - The relationships
+ */
+export interface TeamEdges {
+  team: string;
+  relationships: Relationship[];
+}
+
+/**
+ * One team's slice of an environment.
+ *
+ * @property team - The team
+ * @property services - The team's services
+ * @property relationships - The relationships among them
+ * @property outbound - Relationships to other teams, by the other team
+ * @property inbound - Relationships from other teams, by the other team
+ * @property issues - The validation issues concerning the team
+ */
+export interface TeamOverview {
+  team: string;
+  services: Service[];
+  relationships: Relationship[];
+  outbound: TeamEdges[];
+  inbound: TeamEdges[];
+  issues: TeamIssues;
+}
+
+/**
+ * Returns one team's services, relationships in effect today and
+ * validation issues. Teams are matched ignoring case.
+ *
+ * @param environment - The name of the environment
+ * @param team - The team, or "unassigned" for the services without one
+ * @returns Promise resolving to the team's slice of the environment
+ * @throws Error if no service belongs to the team
+ *
+ * @example
+ * ```typescript
+ * const overview = await getTeamOverview('prod', 'Payments');
+ * ```
+ */
+export async function getTeamOverview(
+  environment: string,
+  team: string
+): Promise<TeamOverview> {
+  return invoke<TeamOverview>("get_team_overview", {
+    environment,
+    team,
+  });
+}
+
+/**
+ * What transferOwnership moved.
+ *
+ * @property transferredServiceIds - The services moved to the new team
+ * @property crossTeamRelationships - Relationships in effect today between a
+ *   moved service and a service of another team
+ * @property dryRun - True if nothing was written
+ */
+export interface OwnershipTransfer {
+  transferredServiceIds: string[];
+  crossTeamRelationships: Relationship[];
+  dryRun: boolean;
+}
+
+/**
+ * Moves every service of one team to another, as one batch.
+ *
+ * Each moved service gets a note with the date and its previous team and
+ * owner appended to its ownershipTransfers metadata.
+ *
+ * @param environment - The name of the environment
+ * @param fromTeam - The team whose services move (matched ignoring case)
+ * @param toTeam - The team they move to
+ * @param newOwner - Optional new owner of every moved service
+ * @param dryRun - If true, only compute the transfer
+ * @returns Promise resolving to the moved services and cross-team edges
+ * @throws Error if the teams are the same or the services cannot be saved
+ *
+ * @example
+ * ```typescript
+ * const preview = await transferOwnership(
+ *   'prod', 'legacy-platform', 'core-platform', undefined, true
+ * );
+ * ```
+ */
+export async function transferOwnership(
+  environment: string,
+  fromTeam: string,
+  toTeam: string,
+  newOwner?: string,
+  dryRun?: boolean
+): Promise<OwnershipTransfer> {
+  return invoke<OwnershipTransfer>("transfer_ownership", {
+    environment,
+    fromTeam,
+    toTeam,
+    newOwner,
+    dryRun,
+  });
+}
+
+// ============================================================================
+// Placeholder Services
+// ============================================================================
+
+/**
+ * What placeholder services are created with.
+ *
+ * @property serviceType - The type of every placeholder; inferred from the
+ *   relationships pointing at it if omitted
+ * @property team - The team of every placeholder
+ * @property owner - The owner of every placeholder
+ */
+export interface PlaceholderDefaults {
+  serviceType?: ServiceType;
+  team?: string;
+  owner?: string;
+}
+
+/**
+ * The result of creating placeholder services.
+ *
+ * @property created - IDs of the placeholders created
+ * @property alreadyExisted - Requested IDs that already were services
+ * @property skippedRelationshipIds - Requested IDs of relationships, skipped
+ * @property result - What was saved
+ */
+export interface PlaceholderResult {
+  created: string[];
+  alreadyExisted: string[];
+  skippedRelationshipIds: string[];
+  result: BatchResult;
+}
+
+/**
+ * Creates placeholder services, tagged "placeholder", for IDs that
+ * relationships point at. Existing services are never overwritten, and IDs
+ * no relationship refers to are rejected.
+ *
+ * @param environment - The name of the environment
+ * @param serviceIds - The IDs to create; an orphan issue's `affectedIds` can
+ *   be passed as they are
+ * @param defaults - The type, team and owner to create them with
+ * @returns Promise resolving to which IDs were created and which existed
+ * @throws Error if an ID isn't referenced by any relationship or a
+ *   placeholder can't be created; nothing is written then
+ *
+ * @example
+ * ```typescript
+ * const result = await createPlaceholderServices('prod', issue.affectedIds);
+ * console.log(`Created ${result.created.join(', ')}`);
+ * ```
+ */
+export async function createPlaceholderServices(
+  environment: string,
+  serviceIds: string[],
+  defaults?: PlaceholderDefaults
+): Promise<PlaceholderResult> {
+  return invoke<PlaceholderResult>("create_placeholder_services", {
+    environment,
+    serviceIds,
+    defaults,
+  });
+}
+
+// ============================================================================
+// Import Merge Conflicts
+// ============================================================================
+
+/**
+ * A field where an imported and a stored service or relationship disagreed.
+ *
+ * @property itemType - Whether the item is a service or a relationship
+ * @property itemId - The ID of the stored item
+ * @property field - The field, e.g. "status"; metadata is named by its path,
+ *   e.g. "metadata.kubernetes.namespace"
+ * @property existing - The stored value
+ * @property incoming - The imported value
+ * @property winner - Which side was saved; "merged" if both were combined
+ */
+export interface MergeConflict {
+  itemType: "service" | "relationship";
+  itemId: string;
+  field: string;
+  existing: unknown;
+  incoming: unknown;
+  winner: "existing" | "incoming" | "merged";
+}
+
+// ============================================================================
+// Kubernetes Import Commands
+// ============================================================================
+
+/**
+ * Result of importing services from a Kubernetes cluster.
+ *
+ * @property servicesFound - Number of services mapped from the cluster
+ * @property edgesFound - Number of communicates_with edges inferred from the cluster
+ * @property missingFromCluster - IDs of previously imported services that are gone;
+ *   they are tagged "missing-from-cluster", not deleted
+ * @property conflicts - Fields where the cluster and a stored service disagreed
+ * @property result - What the import saved, or would save for a dry run
+ */
+export interface KubernetesImportReport {
+  servicesFound: number;
+  edgesFound: number;
+  missingFromCluster: string[];
+  conflicts: MergeConflict[];
+  result: BatchResult;
+}
+
+/**
+ * Imports Deployments, StatefulSets, Services and Ingresses from a cluster.
+ *
+ * @param environment - The environment to import into
+ * @param kubeconfigPath - The kubeconfig to use, or null for the default one
+ * @param namespaces - The namespaces to import
+ * @param dryRun - If true, only compute what the import would change
+ * @param operationId - ID for progress events and `cancelOperation`
+ * @returns Promise resolving to what was found and changed
+ * @throws Error if the cluster rejects the credentials or can't be reached
+ *
+ * @example
+ * ```typescript
+ * const preview = await importFromKubernetesCluster('prod', null, ['payments'], true);
+ * console.log(`Would save ${preview.result.savedServices.length} services`);
+ * ```
+ */
+export async function importFromKubernetesCluster(
+  environment: string,
+  kubeconfigPath: string | null,
+  namespaces: string[],
+  dryRun = false,
+  operationId?: string
+): Promise<KubernetesImportReport> {
+  return invoke<KubernetesImportReport>("import_from_kubernetes_cluster", {
+    environment,
+    kubeconfigPath,
+    namespaces,
+    dryRun,
+    operationId,
+  });
+}
+
+// ============================================================================
+// Consul Import Commands
+// ============================================================================
+
+/**
+ * Result of importing services from Consul.
+ *
+ * @property servicesFound - Number of services registered in Consul
+ * @property unmatched - Consul names without a service in the environment;
+ *   only filled when syncing statuses
+ * @property conflicts - Fields where Consul and a stored service disagreed
+ * @property result - What the import saved
+ */
+export interface ConsulImportReport {
+  servicesFound: number;
+  unmatched: string[];
+  conflicts: MergeConflict[];
+  result: BatchResult;
+}
+
+/**
+ * Imports the services registered in Consul, or refreshes their statuses.
+ *
+ * @param environment - The environment to import into
+ * @param baseUrl - The Consul HTTP address, e.g. "http://consul.internal:8500"
+ * @param token - The ACL token, or null; it is never stored
+ * @param syncStatusesOnly - If true, only update the status of existing services
+ * @param operationId - ID for progress events and `cancelOperation`
+ * @returns Promise resolving to what was found and changed
+ * @throws Error if Consul rejects the token, times out or can't be reached
+ *
+ * @example
+ * ```typescript
+ * const report = await importFromConsul('prod', consulUrl, token, true);
+ * console.log(`${report.result.savedServices.length} statuses changed`);
+ * ```
+ */
+export async function importFromConsul(
+  environment: string,
+  baseUrl: string,
+  token: string | null,
+  syncStatusesOnly = false,
+  operationId?: string
+): Promise<ConsulImportReport> {
+  return invoke<ConsulImportReport>("import_from_consul", {
+    environment,
+    baseUrl,
+    token,
+    syncStatusesOnly,
+    operationId,
+  });
+}
+
+// ============================================================================
+// Health Probe Commands
+// ============================================================================
+
+/**
+ * The outcome of probing one service's health endpoint.
+ *
+ * @property serviceId - The probed service
+ * @property url - The health endpoint; null if the service has none
+ * @property previousStatus - The status before the probe; null if the service
+ *   doesn't exist
+ * @property status - The status the answer maps to; null if not probed
+ * @property httpStatus - The status code of the answer, if there was one
+ * @property latencyMs - How long the request took
+ * @property error - Why the service wasn't probed or isn't healthy
+ * @property changed - Whether the probe changes the status
+ */
+export interface ProbeOutcome {
+  serviceId: string;
+  url: string | null;
+  previousStatus: ServiceStatus | null;
+  status: ServiceStatus | null;
+  httpStatus: number | null;
+  latencyMs: number | null;
+  error: string | null;
+  changed: boolean;
+}
+
+/**
+ * Probes the health endpoints in the services' metadata (the healthProbe.urlKey
+ * setting, `healthUrl` by default): 2xx is healthy, 4xx degraded, 5xx and
+ * timeouts unhealthy. Without `apply` nothing is written.
+ *
+ * @param environment - The name of the environment
+ * @param serviceIds - The services to probe; every service with an endpoint
+ *   if omitted
+ * @param apply - Save the statuses and the probe metadata (default: false)
+ * @param operationId - ID for progress events and `cancelOperation`
+ * @returns Promise resolving to one outcome per service
+ * @throws Error if the statuses can't be saved
+ */
+export async function probeServiceHealth(
+  environment: string,
+  serviceIds?: string[],
+  apply = false,
+  operationId?: string
+): Promise<ProbeOutcome[]> {
+  return invoke<ProbeOutcome[]>("probe_service_health", {
+    environment,
+    serviceIds,
+    apply,
+    operationId,
+  });
+}
+
+/**
+ * Subscribes to the outcome of each probe of `probeServiceHealth` as it
+ * arrives.
+ *
+ * @param onOutcome - Called with each outcome
+ * @returns Promise resolving to a function that unsubscribes
+ */
+export async function onHealthProbeResult(
+  onOutcome: (outcome: ProbeOutcome) => void
+): Promise<UnlistenFn> {
+  return listen<ProbeOutcome>("health-probe-result", (event) =>
+    onOutcome(event.payload)
+  );
+}
+
+// ============================================================================
+// Drift Check Commands
+// ============================================================================
+
+/**
+ * An exported edge without a relationship in the environment.
+ *
+ * @property source - ID of the source service
+ * @property target - ID of the target service
+ */
+export interface DriftEdge {
+  source: string;
+  target: string;
+}
+
+/**
+ * How an environment differs from an export of an import source.
+ *
+ * @property source - The import source, "kubernetes" or "consul"
+ * @property lastImportedAt - When the source last synced the environment, in
+ *   milliseconds since the epoch; null if no sync was recorded
+ * @property snapshotServices - Number of services mapped from the export
+ * @property snapshotRelationships - Number of edges mapped from the export
+ * @property missingServices - Exported services the environment doesn't have
+ * @property extraServices - Services imported from the source that are no
+ *   longer in the export
+ * @property missingRelationships - Exported edges the environment doesn't have
+ * @property extraRelationships - IDs of imported relationships the export no
+ *   longer implies
+ */
+export interface DriftReport {
+  source: string;
+  lastImportedAt: number | null;
+  snapshotServices: number;
+  snapshotRelationships: number;
+  missingServices: string[];
+  extraServices: string[];
+  missingRelationships: DriftEdge[];
+  extraRelationships: string[];
+}
+
+/**
+ * Compares an environment with an export of Kubernetes or Consul, without
+ * changing anything.
+ *
+ * @param environment - The name of the environment
+ * @param sourceSnapshotPath - A `kubectl get -o json` List or a Consul
+ *   catalog (`/v1/catalog/services`) saved as JSON
+ * @returns Promise resolving to what the environment lacks and has in excess
+ * @throws Error if the export can't be read or has neither shape
+ *
+ * @example
+ * ```typescript
+ * const drift = await checkDrift('prod', '/tmp/prod-cluster.json');
+ * console.log(`${drift.missingServices.length} services are missing`);
+ * ```
+ */
+export async function checkDrift(
+  environment: string,
+  sourceSnapshotPath: string
+): Promise<DriftReport> {
+  return invoke<DriftReport>("check_drift", {
+    environment,
+    sourceSnapshotPath,
+  });
+}
+
+// ============================================================================
+// GitHub Enrichment Commands
+// ============================================================================
+
+/**
+ * Enrichment of one service from its GitHub repository.
+ *
+ * @property serviceId - The service
+ * @property repository - The repository as "owner/name", if recognized
+ * @property outcome - What happened to the service
+ * @property message - Why the enrichment failed, for failed services
+ * @property deprecated - Whether the service was marked deprecated because
+ *   its repository is archived
+ */
+export interface ServiceEnrichment {
+  serviceId: string;
+  repository: string | null;
+  outcome:
+    | "enriched"
+    | "unchanged"
+    | "noRepository"
+    | "failed"
+    | "notAttempted";
+  message: string | null;
+  deprecated: boolean;
+}
+
+/**
+ * Result of enriching services from GitHub.
+ *
+ * @property services - One entry per requested service
+ * @property rateLimited - Why the enrichment stopped early, if the rate
+ *   limit ran out
+ * @property result - What the enrichment saved
+ */
+export interface GitHubEnrichmentReport {
+  services: ServiceEnrichment[];
+  rateLimited: string | null;
+  result: BatchResult;
+}
+
+/**
+ * Enriches services with facts about their GitHub repositories.
+ *
+ * @param environment - The environment of the services
+ * @param serviceIds - The services to enrich; empty enriches every service
+ * @param token - A GitHub token, or null; it is never stored
+ * @param deprecateArchived - If true, mark services with an archived
+ *   repository as deprecated
+ * @returns Promise resolving to what happened to each service
+ *
+ * @example
+ * ```typescript
+ * const report = await enrichFromGitHub('prod', [], token, true);
+ * if (report.rateLimited) console.warn(report.rateLimited);
+ * ```
+ */
+export async function enrichFromGitHub(
+  environment: string,
+  serviceIds: string[],
+  token: string | null,
+  deprecateArchived = false
+): Promise<GitHubEnrichmentReport> {
+  return invoke<GitHubEnrichmentReport>("enrich_from_github", {
+    environment,
+    serviceIds,
+    token,
+    deprecateArchived,
+  });
+}
+
+// ============================================================================
+// nginx Import Commands
+// ============================================================================
+
+/**
+ * A directive skipped while reading an nginx config.
+ *
+ * @property file - The config file of the directive
+ * @property line - The line the directive starts on
+ * @property directive - The directive, as written
+ * @property reason - Why it was skipped
+ */
+export interface SkippedDirective {
+  file: string;
+  line: number;
+  directive: string;
+  reason: string;
+}
+
+/**
+ * Result of importing a gateway's routes from its nginx config.
+ *
+ * @property routesFound - Number of proxy_pass routes read from the config
+ * @property createdServices - IDs of the external services created for
+ *   unknown upstreams
+ * @property skipped - Directives that were skipped, with the reason
+ * @property conflicts - Fields where the config and a stored service or edge
+ *   disagreed
+ * @property result - What the import saved
+ */
+export interface NginxImportReport {
+  routesFound: number;
+  createdServices: string[];
+  skipped: SkippedDirective[];
+  conflicts: MergeConflict[];
+  result: BatchResult;
+}
+
+/**
+ * Imports a gateway's communicates_with edges from its nginx config.
+ *
+ * @param environment - The environment to import into
+ * @param configPath - The main nginx config file
+ * @param gatewayServiceId - The service the config belongs to
+ * @param operationId - ID for progress events and `cancelOperation`
+ * @returns Promise resolving to what was found, skipped and changed
+ * @throws Error if the config can't be read or the gateway doesn't exist
+ *
+ * @example
+ * ```typescript
+ * const report = await importNginxConfig('prod', '/etc/nginx/nginx.conf', 'edge');
+ * console.log(`${report.routesFound} routes, ${report.skipped.length} skipped`);
+ * ```
+ */
+export async function importNginxConfig(
+  environment: string,
+  configPath: string,
+  gatewayServiceId: string,
+  operationId?: string
+): Promise<NginxImportReport> {
+  return invoke<NginxImportReport>("import_nginx_config", {
+    environment,
+    configPath,
+    gatewayServiceId,
+    operationId,
+  });
+}
+
+/**
+ * A cell of a draw.io diagram that wasn't imported.
+ *
+ * @property cellId - The ID of the cell in the diagram
+ * @property label - The text of the cell
+ * @property reason - Why it was skipped
+ */
+export interface SkippedCell {
+  cellId: string;
+  label: string;
+  reason: string;
+}
+
+/**
+ * Result of importing a draw.io diagram.
+ *
+ * @property servicesFound - Number of vertices read as services
+ * @property relationshipsFound - Number of edges read as relationships
+ * @property createdServices - IDs of the services the diagram added
+ * @property skipped - Vertices and edges that weren't imported, with the reason
+ * @property conflicts - Fields where the diagram and a stored service or
+ *   relationship disagreed
+ * @property result - What the import saved
+ */
+export interface DrawioImportReport {
+  servicesFound: number;
+  relationshipsFound: number;
+  createdServices: string[];
+  skipped: SkippedCell[];
+  conflicts: MergeConflict[];
+  result: BatchResult;
+}
+
+/**
+ * Imports the services and relationships of an uncompressed draw.io
+ * diagram. Vertices and edges are matched by their serviceId and
+ * relationshipId attributes, or else by name and by endpoints and type;
+ * nothing missing from the diagram is deleted.
+ *
+ * @param environment - The environment to import into
+ * @param filePath - The .drawio file
+ * @returns Promise resolving to what was found, skipped and changed
+ * @throws Error if the file can't be read or is compressed
+ */
+export async function importDrawio(
+  environment: string,
+  filePath: string
+): Promise<DrawioImportReport> {
+  return invoke<DrawioImportReport>("import_drawio", {
+    environment,
+    filePath,
+  });
+}
+
+// ============================================================================
+// Settings Commands
+// ============================================================================
+
+/**
+ * Retrieves the application settings.
+ *
+ * @returns Promise resolving to the current settings
+ *
+ * @example
+ * ```typescript
+ * const settings = await getSettings();
+ * console.log(settings.gitAutoCommit);
+ * ```
+ */
+export async function getSettings(): Promise<Settings> {
+  return invoke<Settings>("get_settings");
+}
+
+/**
+ * Replaces the application settings and persists them.
+ *
+ * The HTTP API, scheduled exports and redaction patterns are checked (and
+ * the HTTP API restarted) before anything is saved, so settings the app
+ * can't run with are never stored.
+ *
+ * @param settings - The complete new settings
+ * @returns Promise resolving when the settings are saved
+ * @throws Error if the settings are invalid or cannot be written
+ *
+ * @example
+ * ```typescript
+ * const settings = await getSettings();
+ * await updateSettings({ ...settings, gitAutoCommit: true });
+ * ```
+ */
+export async function updateSettings(settings: Settings): Promise<void> {
+  return invoke<void>("update_settings", { settings });
+}
+
+// ============================================================================
+// Git Commands
+// ============================================================================
 
 /**
- * Result of validating an environment's data integrity.
+ * Git state of one environment's files.
  *
- * @property issues - Array of validation issues found
- * @property errorCount - Number of critical errors
- * @property warningCount - Number of warnings
- * @property infoCount - Number of informational notices
+ * @property isRepository - Whether the data directory is inside a git repository
+ * @property branch - The checked-out branch, if any
+ * @property dirty - Whether the environment has uncommitted changes
+ * @property changedFiles - Repository-relative paths of changed or untracked files
  */
-export interface ValidationResult {
-  issues: ValidationIssue[];
-  errorCount: number;
-  warningCount: number;
-  infoCount: number;
+export interface GitStatus {
+  isRepository: boolean;
+  branch: string | null;
+  dirty: boolean;
+  changedFiles: string[];
 }
 
 /**
- * Validates the entire environment for data integrity issues.
+ * Retrieves whether an environment's files have uncommitted changes.
  *
- * Checks for:
- * - Duplicate service IDs (error)
- * - Missing required fields (error)
- * - Orphaned relationships (error)
- * - Invalid relationship types (warning)
- * - Circular dependencies (warning)
- * - Unreachable services (info)
+ * Reports `isRepository: false` when the data directory isn't in a git
+ * repository or the data path is a SQLite database.
  *
- * @param environment - The name of the environment to validate
- * @returns Promise resolving to the validation results
- * @throws Error if the backend fails to validate
+ * @param environment - The name of the environment to inspect
+ * @returns Promise resolving to the environment's git status
+ * @throws Error if the repository exists but cannot be read
  *
  * @example
  * ```typescript
- * const result = await validateEnvironment('dev');
- * if (result.errorCount > 0) {
- *   console.log('Environment has errors:', result.issues);
+ * const status = await getGitStatus('dev');
+ * if (status.dirty) {
+ *   console.log(`${status.changedFiles.length} uncommitted files`);
  * }
  * ```
  */
-export async function validateEnvironment(
+export async function getGitStatus(environment: string): Promise<GitStatus> {
+  return invoke<GitStatus>("get_git_status", { environment });
+}
+
+// ============================================================================
+// Encryption Commands
+// ============================================================================
+
+/**
+ * Sets the passphrase the data directory is encrypted and decrypted with,
+ * for the rest of the session. The first passphrase set for a data
+ * directory becomes its passphrase; afterwards only that one is accepted.
+ *
+ * While a passphrase is set every file the app writes is encrypted;
+ * existing files are converted with `migrateEncryption`.
+ *
+ * @param passphrase - The passphrase to derive the key from
+ * @returns Promise resolving when the data directory is unlocked
+ * @throws Error if the passphrase is empty or wrong, or the data path is a
+ *   SQLite database
+ *
+ * @example
+ * ```typescript
+ * await setEncryptionPassphrase('correct horse battery staple');
+ * ```
+ */
+export async function setEncryptionPassphrase(passphrase: string): Promise<void> {
+  return invoke<void>("set_encryption_passphrase", { passphrase });
+}
+
+/**
+ * Forgets the encryption passphrase for the rest of the session.
+ *
+ * New writes are plaintext again; encrypted files can't be read until the
+ * passphrase is set again. Decrypt them with `migrateEncryption` first to
+ * turn encryption off for good.
+ *
+ * @returns Promise resolving when the passphrase is forgotten
+ */
+export async function clearEncryptionPassphrase(): Promise<void> {
+  return invoke<void>("clear_encryption_passphrase");
+}
+
+/**
+ * The result of encrypting or decrypting an environment's files.
+ *
+ * @property environment - The environment that was converted
+ * @property enabled - True if files were encrypted, false if decrypted
+ * @property files - Paths of the files that were converted
+ */
+export interface EncryptionMigrationReport {
+  environment: string;
+  enabled: boolean;
+  files: string[];
+}
+
+/**
+ * Encrypts or decrypts an environment's existing files in place.
+ *
+ * Requires the passphrase in both directions. Files already in the
+ * requested form are skipped, so the command can be re-run after an
+ * interruption.
+ *
+ * @param environment - The name of the environment to convert
+ * @param enable - True to encrypt plaintext files, false to decrypt
+ * @returns Promise resolving to the files that were converted
+ * @throws Error if no passphrase is set, a file was encrypted with another
+ *   one, or files cannot be read or written
+ *
+ * @example
+ * ```typescript
+ * await setEncryptionPassphrase(passphrase);
+ * const report = await migrateEncryption('prod', true);
+ * console.log(`Encrypted ${report.files.length} files`);
+ * ```
+ */
+export async function migrateEncryption(
+  environment: string,
+  enable: boolean
+): Promise<EncryptionMigrationReport> {
+  return invoke<EncryptionMigrationReport>("migrate_encryption", {
+    environment,
+    enable,
+  });
+}
+
+// ============================================================================
+// Storage Report Commands
+// ============================================================================
+
+/**
+ * What an unexpected file in an environment directory appears to be.
+ */
+export type ArtifactKind = "temporary" | "backup" | "other";
+
+/**
+ * Size and age of a single file.
+ *
+ * @property path - Path of the file
+ * @property bytes - File size in bytes
+ * @property daysSinceModified - Whole days since the last modification, if known
+ */
+export interface FileStat {
+  path: string;
+  bytes: number;
+  daysSinceModified: number | null;
+}
+
+/**
+ * A file in an environment directory that isn't part of the data layout.
+ *
+ * @property file - Size and age of the file
+ * @property kind - What the file appears to be
+ */
+export interface OrphanedArtifact {
+  file: FileStat;
+  kind: ArtifactKind;
+}
+
+/**
+ * Health of an environment's relationships file (or shards, together).
+ *
+ * @property exists - Whether the file exists
+ * @property bytes - Its size in bytes (0 if missing)
+ * @property parses - Whether it loads (a missing file counts as loading)
+ * @property error - Why it failed to load, if it did
+ */
+export interface RelationshipsFileHealth {
+  exists: boolean;
+  bytes: number;
+  parses: boolean;
+  error: string | null;
+}
+
+/**
+ * Disk usage and file health of one environment.
+ *
+ * @property environment - The environment name
+ * @property serviceFileCount - Number of service files
+ * @property totalBytes - Size of every file in the environment directory
+ * @property largestFiles - The largest files, biggest first
+ * @property staleFiles - Data files not modified within the threshold, oldest first
+ * @property orphanedArtifacts - Files that aren't part of the data layout
+ * @property orphanedBytes - Combined size of the orphaned artifacts
+ * @property relationships - Health of the relationships file
+ */
+export interface EnvironmentStorageReport {
+  environment: string;
+  serviceFileCount: number;
+  totalBytes: number;
+  largestFiles: FileStat[];
+  staleFiles: FileStat[];
+  orphanedArtifacts: OrphanedArtifact[];
+  orphanedBytes: number;
+  relationships: RelationshipsFileHealth;
+}
+
+/**
+ * Disk usage and file health across one or more environments.
+ *
+ * @property dataPath - The data directory that was scanned
+ * @property staleAfterDays - The staleness threshold that was applied
+ * @property serviceFileCount - Total service files of the reported environments
+ * @property totalBytes - Total size of the reported environments
+ * @property orphanedBytes - Total size of the orphaned artifacts
+ * @property environments - The per-environment reports
+ */
+export interface StorageReport {
+  dataPath: string;
+  staleAfterDays: number;
+  serviceFileCount: number;
+  totalBytes: number;
+  orphanedBytes: number;
+  environments: EnvironmentStorageReport[];
+}
+
+/**
+ * Reports disk usage and file health of the data directory: file counts
+ * and sizes, the largest and stale files, leftover artifacts and whether
+ * the relationships load.
+ *
+ * @param environment - The environment to report on; all when omitted
+ * @param staleDays - Age in days after which data files count as stale (default 180)
+ * @returns Promise resolving to the report
+ * @throws Error if the data path is a SQLite database or cannot be listed
+ *
+ * @example
+ * ```typescript
+ * const report = await getStorageReport(undefined, 365);
+ * for (const env of report.environments) {
+ *   console.log(`${env.environment}: ${env.staleFiles.length} stale files`);
+ * }
+ * ```
+ */
+export async function getStorageReport(
+  environment?: string,
+  staleDays?: number
+): Promise<StorageReport> {
+  return invoke<StorageReport>("get_storage_report", {
+    environment,
+    staleDays,
+  });
+}
+
+// ============================================================================
+// Undo/Redo Commands
+// ============================================================================
+
+/**
+ * A recorded change, as shown in the undo and redo menus.
+ *
+ * @property description - What the command did
+ * @property recordedAt - When it ran (ms since epoch)
+ * @property changeCount - How many services and relationships it touched
+ */
+export interface JournalEntryInfo {
+  description: string;
+  recordedAt: number;
+  changeCount: number;
+}
+
+/**
+ * The undo and redo stacks of an environment.
+ *
+ * @property undo - Entries that can be undone, most recent first
+ * @property redo - Entries that can be redone, next redo first
+ */
+export interface UndoHistory {
+  undo: JournalEntryInfo[];
+  redo: JournalEntryInfo[];
+}
+
+/**
+ * Undoes the most recent change in an environment. Changes made by one
+ * command, bulk operations included, are undone together.
+ *
+ * @param environment - The name of the environment to undo in
+ * @returns Promise resolving to the change that was undone, or null if
+ *   there was nothing to undo
+ * @throws Error if the files cannot be written; the change can be undone again
+ *
+ * @example
+ * ```typescript
+ * const undone = await undo('dev');
+ * if (undone) {
+ *   showToast(`Undid: ${undone.description}`);
+ * }
+ * ```
+ */
+export async function undo(
   environment: string
-): Promise<ValidationResult> {
-  return invoke<ValidationResult>("validate_environment", { environment });
+): Promise<JournalEntryInfo | null> {
+  return invoke<JournalEntryInfo | null>("undo", { environment });
+}
+
+/**
+ * Redoes the most recently undone change in an environment. Any new change
+ * clears what can be redone.
+ *
+ * @param environment - The name of the environment to redo in
+ * @returns Promise resolving to the change that was redone, or null if
+ *   there was nothing to redo
+ * @throws Error if the files cannot be written; the change can be redone again
+ */
+export async function redo(
+  environment: string
+): Promise<JournalEntryInfo | null> {
+  return invoke<JournalEntryInfo | null>("redo", { environment });
+}
+
+/**
+ * Retrieves the undo and redo stacks of an environment. The journal is kept
+ * in memory, so it only covers changes made this session.
+ *
+ * @param environment - The name of the environment
+ * @returns Promise resolving to both stacks
+ *
+ * @example
+ * ```typescript
+ * const history = await getUndoHistory('dev');
+ * undoButton.title = history.undo[0]?.description ?? 'Nothing to undo';
+ * ```
+ */
+export async function getUndoHistory(environment: string): Promise<UndoHistory> {
+  return invoke<UndoHistory>("get_undo_history", { environment });
+}
+
+// ============================================================================
+// Audit Log Commands
+// ============================================================================
+
+/**
+ * One change recorded in an environment's audit log.
+ *
+ * @property timestamp - When the change was made (ms since epoch)
+ * @property user - The OS username of whoever ran the app
+ * @property command - The command that made the change (e.g. "save_service")
+ * @property affectedIds - IDs of the changed services and relationships;
+ *   relationship changes also list their source and target services
+ * @property before - Summary of the data before the change, if it existed
+ * @property after - Summary of the data after the change, if it still exists
+ */
+export interface AuditRecord {
+  timestamp: number;
+  user: string;
+  command: string;
+  affectedIds: string[];
+  before?: string;
+  after?: string;
+}
+
+/**
+ * A page of audit records, newest first.
+ *
+ * @property entries - The records on this page
+ * @property total - The number of matching records across all pages
+ */
+export interface AuditLogPage {
+  entries: AuditRecord[];
+  total: number;
+}
+
+/**
+ * Retrieves a page of an environment's audit log, newest first.
+ *
+ * @param environment - The name of the environment whose log to read
+ * @param limit - Maximum number of records to return (default: 100)
+ * @param offset - Number of matching records to skip from the newest (default: 0)
+ * @param filterByService - Only return records that touched this service,
+ *   changes to its relationships included
+ * @returns Promise resolving to the records and the number matching the filter
+ * @throws Error if the audit log exists but cannot be read
+ *
+ * @example
+ * ```typescript
+ * const page = await getAuditLog('prod', 50, 0, 'user-api');
+ * for (const record of page.entries) {
+ *   console.log(new Date(record.timestamp), record.user, record.command);
+ * }
+ * ```
+ */
+export async function getAuditLog(
+  environment: string,
+  limit?: number,
+  offset?: number,
+  filterByService?: string
+): Promise<AuditLogPage> {
+  return invoke<AuditLogPage>("get_audit_log", {
+    environment,
+    limit,
+    offset,
+    filterByService,
+  });
+}
+
+// ============================================================================
+// Declared Dependency Commands
+// ============================================================================
+
+/**
+ * A declared dependency without a matching depends_on relationship.
+ *
+ * @property sourceId - The service declaring the dependency
+ * @property targetId - The service it declares, resolved to an ID
+ * @property declaredAs - The entry as written in the metadata (an ID or a name)
+ */
+export interface MissingDependency {
+  sourceId: string;
+  targetId: string;
+  declaredAs: string;
+}
+
+/**
+ * A depends_on relationship its source service doesn't declare.
+ *
+ * @property relationshipId - ID of the relationship
+ * @property sourceId - The depending service
+ * @property targetId - The undeclared dependency
+ */
+export interface UndeclaredDependency {
+  relationshipId: string;
+  sourceId: string;
+  targetId: string;
+}
+
+/**
+ * A declared dependency that doesn't resolve to exactly one service.
+ *
+ * @property sourceId - The service declaring the dependency
+ * @property declaredAs - The entry as written in the metadata
+ * @property reason - Why it couldn't be resolved
+ */
+export interface UnresolvedDependency {
+  sourceId: string;
+  declaredAs: string;
+  reason: string;
+}
+
+/**
+ * The result of comparing declared with actual dependencies.
+ *
+ * @property servicesChecked - Number of services with declared dependencies
+ * @property missing - Declared dependencies without a relationship; pass the
+ *   accepted ones to applyReconciliation to create them
+ * @property undeclared - Relationships of checked services that aren't declared
+ * @property unresolved - Declared entries that match no service or several
+ */
+export interface ReconciliationReport {
+  servicesChecked: number;
+  missing: MissingDependency[];
+  undeclared: UndeclaredDependency[];
+  unresolved: UnresolvedDependency[];
+}
+
+/**
+ * Compares the dependencies services declare in their
+ * `declaredDependencies` metadata with their depends_on relationships.
+ * Entries are matched against service IDs, then case-insensitively against
+ * names.
+ *
+ * @param environment - The name of the environment to reconcile
+ * @returns Promise resolving to the differences, sorted by source service
+ * @throws Error if the services or relationships cannot be loaded
+ *
+ * @example
+ * ```typescript
+ * const report = await reconcileDeclaredDependencies('prod');
+ * for (const m of report.missing) {
+ *   console.log(`${m.sourceId} declares ${m.targetId} but doesn't depend on it`);
+ * }
+ * ```
+ */
+export async function reconcileDeclaredDependencies(
+  environment: string
+): Promise<ReconciliationReport> {
+  return invoke<ReconciliationReport>("reconcile_declared_dependencies", {
+    environment,
+  });
+}
+
+/**
+ * Creates the accepted missing dependencies as depends_on relationships,
+ * all or none, as a single undo entry.
+ *
+ * @param environment - The name of the environment
+ * @param accepted - Missing dependencies from reconcileDeclaredDependencies to create
+ * @param dryRun - Only compute the relationships that would be created
+ * @returns Promise resolving to the created relationships in `savedRelationships`
+ * @throws Error if a service doesn't exist or a relationship can't be
+ *   created; nothing is written then
+ *
+ * @example
+ * ```typescript
+ * const report = await reconcileDeclaredDependencies('prod');
+ * await applyReconciliation('prod', report.missing);
+ * ```
+ */
+export async function applyReconciliation(
+  environment: string,
+  accepted: MissingDependency[],
+  dryRun?: boolean
+): Promise<BatchResult> {
+  return invoke<BatchResult>("apply_reconciliation", {
+    environment,
+    accepted,
+    dryRun,
+  });
+}
+
+// ============================================================================
+// Webhook Commands
+// ============================================================================
+
+/**
+ * Retrieves the webhook configuration.
+ *
+ * @returns Promise resolving to the configured webhook, or null if none is
+ *
+ * @example
+ * ```typescript
+ * const webhook = await getWebhookConfig();
+ * console.log(webhook?.events ?? 'notifications are off');
+ * ```
+ */
+export async function getWebhookConfig(): Promise<WebhookConfig | null> {
+  return invoke<WebhookConfig | null>("get_webhook_config");
+}
+
+/**
+ * Configures where webhook notifications are sent, or turns them off.
+ *
+ * Notifications are sent in the background and retried; failures are only
+ * logged. The current validation error counts become the baseline for
+ * `validationErrorsIncreased`.
+ *
+ * @param config - The webhook to send to; null removes it
+ * @returns Promise resolving when the configuration is saved
+ * @throws Error if the URL isn't an http(s) URL or the settings cannot be written
+ *
+ * @example
+ * ```typescript
+ * await setWebhookConfig({
+ *   url: 'https://hooks.slack.com/services/T000/B000/XXXX',
+ *   secret: 'shared-secret',
+ *   events: ['validationErrorsIncreased', 'serviceDeleted'],
+ * });
+ * ```
+ */
+export async function setWebhookConfig(config: WebhookConfig | null): Promise<void> {
+  return invoke<void>("set_webhook_config", { config });
+}
+
+/**
+ * Sends a `test` notification to the configured webhook, right away and
+ * once, so the URL and receiver can be checked.
+ *
+ * @returns Promise resolving when the receiver accepted the notification
+ * @throws Error if no webhook is configured, or the request failed or got an
+ *   error status
+ *
+ * @example
+ * ```typescript
+ * try {
+ *   await testWebhook();
+ * } catch (e) {
+ *   console.error('Webhook unreachable:', e);
+ * }
+ * ```
+ */
+export async function testWebhook(): Promise<void> {
+  return invoke<void>("test_webhook");
 }
@@ -34,10 +34,40 @@ export interface ValidationIssue {
     | "missing_required_field"
     | "invalid_relationship_type"
     | "circular_dependency"
-    | "unreachable_service";
+    | "unreachable_service"
+    | "filename_mismatch"
+    | "legacy_file_format"
+    | "naming_convention"
+    | "missing_type_requirement"
+    | "possible_duplicate"
+    | "reversed_data_flow"
+    | "reversed_dependency"
+    | "unlikely_auth_provider"
+    | "missing_queue"
+    | "deprecated_dependency"
+    | "unhealthy_dependency"
+    | "metadata_schema_violation"
+    | "tier_violation"
+    | "unclassified_tier"
+    | "missing_owner"
+    | "orphaned_group_member"
+    | "orphaned_annotation"
+    | "unregistered_custom_type"
+    | "past_decommission_date"
+    | "decommissioned_dependency"
+    | "expired_relationship"
+    | "stale_import"
+    | "unreviewed_dependency"
+    | "port_conflict"
+    | "invalid_port"
+    | "mistyped_third_party"
+    | "external_depends_on_internal"
+    | "duplicate_relationship"
+    | "self_reference";
   message: string;
   affectedIds: string[];
   suggestion?: string;
+  fingerprint: string;
 }
 
 /**
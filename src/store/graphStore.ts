@@ -11,6 +11,7 @@ import { create } from "zustand";
 import type { GraphElements } from "@/types/graph";
 import type { Service } from "@/types/service";
 import { getServiceGraph } from "@/services/tauri";
+import { errorMessage } from "@/utils/errors";
 import { transformToGraphElements } from "@/services/graphTransforms";
 
 /**
@@ -68,7 +69,7 @@ export const useGraphStore = create<GraphState>((set, get) => ({
 
     set({ isLoading: true });
     try {
-      const graphData = await getServiceGraph(environment, centerNodeId, 1);
+      const graphData = await getServiceGraph(environment, centerNodeId);
       const elements = transformToGraphElements(graphData);
       set({
         elements,
@@ -77,7 +78,7 @@ export const useGraphStore = create<GraphState>((set, get) => ({
         isLoading: false,
       });
     } catch (error) {
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
   reset: () =>
@@ -11,6 +11,7 @@
 import { create } from "zustand";
 import type { Service } from "@/types/service";
 import { getAllServices } from "@/services/tauri";
+import { errorMessage } from "@/utils/errors";
 
 /**
  * State interface for the services store.
@@ -108,7 +109,7 @@ export const useServicesStore = create<ServicesState>((set, get) => ({
         isLoading: false,
       });
     } catch (error) {
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
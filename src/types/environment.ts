@@ -7,6 +7,9 @@
  * @module types/environment
  */
 
+import type { Service } from "./service";
+import type { Relationship } from "./relationship";
+
 /**
  * Common environment name values.
  *
@@ -41,3 +44,69 @@ export const DEFAULT_ENVIRONMENTS: Environment[] = [
   { name: "staging", displayName: "Staging" },
   { name: "prod", displayName: "Production" },
 ];
+
+/**
+ * A snapshot of an environment without its data, as listed.
+ *
+ * @property id - Unique ID within the environment: `{createdAt}-{label slug}`
+ * @property label - Label given when the snapshot was taken
+ * @property createdAt - When the snapshot was taken, in milliseconds since the epoch
+ * @property serviceCount - Number of services in the snapshot
+ * @property relationshipCount - Number of relationships in the snapshot
+ */
+export interface SnapshotInfo {
+  id: string;
+  label: string;
+  createdAt: number;
+  serviceCount: number;
+  relationshipCount: number;
+}
+
+/**
+ * An entity that exists in both versions of an environment but differs.
+ *
+ * @property changedFields - Names of the fields that differ, ignoring revisions
+ */
+export interface ChangedEntity<T> {
+  id: string;
+  before: T;
+  after: T;
+  changedFields: string[];
+}
+
+/**
+ * Added, removed and changed entities of one kind, each sorted by ID.
+ */
+export interface EntityDiff<T> {
+  added: T[];
+  removed: T[];
+  changed: ChangedEntity<T>[];
+}
+
+/**
+ * The differences between two versions of an environment.
+ */
+export interface EnvironmentDiff {
+  services: EntityDiff<Service>;
+  relationships: EntityDiff<Relationship>;
+}
+
+/**
+ * One environment's scheduled or manual export.
+ *
+ * @property trigger - Whether the schedule or `runScheduledExportNow` started it
+ * @property startedAt - When the export started, in milliseconds since the epoch
+ * @property outcome - `skipped` if the environment was being written
+ * @property output - The snapshot ID or file path written, if any
+ * @property pruned - Number of older scheduled outputs deleted afterwards
+ * @property message - Why the export was skipped or failed
+ */
+export interface ScheduledRun {
+  environment: string;
+  trigger: "schedule" | "manual";
+  startedAt: number;
+  outcome: "succeeded" | "skipped" | "failed";
+  output: string | null;
+  pruned: number;
+  message: string | null;
+}
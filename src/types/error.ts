@@ -0,0 +1,59 @@
+/**
+ * @fileoverview Type definitions for errors returned by backend commands.
+ *
+ * Failed commands reject with an {@link AppErrorPayload}. Decide what to
+ * show by its `code`; the `message` is for display and its wording may
+ * change.
+ *
+ * @module types/error
+ */
+
+/**
+ * Stable codes of backend errors, one per error variant.
+ */
+export type AppErrorCode =
+  | "io_error"
+  | "json_error"
+  | "file_load_failed"
+  | "database_error"
+  | "git_error"
+  | "webhook_failed"
+  | "cluster_auth_failed"
+  | "cluster_request_failed"
+  | "registry_auth_failed"
+  | "registry_timeout"
+  | "registry_request_failed"
+  | "github_rate_limited"
+  | "github_request_failed"
+  | "encryption_required"
+  | "wrong_passphrase"
+  | "service_not_found"
+  | "environment_not_found"
+  | "environment_exists"
+  | "workspace_not_found"
+  | "invalid_path"
+  | "relationship_not_found"
+  | "duplicate_relationship"
+  | "group_not_found"
+  | "view_not_found"
+  | "custom_type_not_found"
+  | "snapshot_not_found"
+  | "conflict"
+  | "batch_operation_failed"
+  | "operation_cancelled"
+  | "read_only_mode"
+  | "validation_error";
+
+/**
+ * An error returned by a backend command.
+ *
+ * @property code - The stable code of the error
+ * @property message - Human-readable description for display
+ * @property details - The error's data, e.g. `{ serviceId }` for
+ *   `service_not_found` or `{ index, cause }` for `batch_operation_failed`
+ */
+export interface AppErrorPayload {
+  code: AppErrorCode;
+  message: string;
+  details: Record<string, unknown>;
+}
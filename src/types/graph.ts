@@ -8,8 +8,8 @@
  */
 
 import type { ElementDefinition } from "cytoscape";
-import type { Service } from "./service";
-import type { Relationship } from "./relationship";
+import type { Service, ServiceStatus, ServiceType } from "./service";
+import type { Relationship, RelationshipType } from "./relationship";
 
 /**
  * Data properties for a graph node representing a service.
@@ -69,6 +69,16 @@ export type GraphElements = (GraphNode | GraphEdge)[];
  * @property centerService - The service at the center of the graph
  * @property connectedServices - All services connected within the depth
  * @property relationships - All relationships between the services
+ * @property effectiveStatuses - Effective status per service ID, only when requested
+ * @property criticalityScores - Criticality score (0-1) per service ID, only when requested
+ * @property groupMemberships - Group IDs per service ID, only when requested
+ * @property annotations - Stored position and notes per service ID, only when requested
+ * @property aggregatedEdges - One edge per source and target, only when requested
+ * @property centerExcluded - Whether the center service matches an exclusion; it is
+ *   returned anyway
+ * @property truncated - Whether maxNodes or maxEdges left services or relationships out
+ * @property omittedNeighborCounts - Neighbors left out by the caps per service ID, for
+ *   "+N more" badges; only services with omitted neighbors are listed
  *
  * @example
  * ```typescript
@@ -80,7 +90,9 @@ export type GraphElements = (GraphNode | GraphEdge)[];
  *   ],
  *   relationships: [
  *     { id: "rel-1", source: "api-gateway", target: "user-service", ... }
- *   ]
+ *   ],
+ *   centerExcluded: false,
+ *   truncated: false
  * };
  * ```
  */
@@ -88,4 +100,245 @@ export interface GraphData {
   centerService: Service;
   connectedServices: Service[];
   relationships: Relationship[];
+  effectiveStatuses?: Record<string, ServiceStatus>;
+  criticalityScores?: Record<string, number>;
+  groupMemberships?: Record<string, string[]>;
+  annotations?: Record<string, NodeAnnotation>;
+  aggregatedEdges?: AggregatedEdge[];
+  centerExcluded: boolean;
+  truncated: boolean;
+  omittedNeighborCounts?: Record<string, number>;
+}
+
+/**
+ * The relationships from one service to another, drawn as one edge.
+ *
+ * A to B and B to A are separate edges.
+ *
+ * @property id - `{source}->{target}`
+ * @property count - Number of relationships collapsed into this edge
+ * @property relationshipTypes - The types among them, sorted
+ * @property relationshipIds - IDs of the relationships, to expand the edge
+ */
+export interface AggregatedEdge {
+  id: string;
+  source: string;
+  target: string;
+  count: number;
+  relationshipTypes: RelationshipType[];
+  relationshipIds: string[];
+}
+
+/**
+ * Services a graph query leaves out and doesn't traverse through.
+ *
+ * @property excludeStatuses - Statuses of services to leave out, e.g. deprecated
+ * @property excludeTags - Tags of services to leave out (any of them excludes)
+ * @property excludeServiceIds - IDs of services to leave out
+ */
+export interface GraphExclusions {
+  excludeStatuses?: ServiceStatus[];
+  excludeTags?: string[];
+  excludeServiceIds?: string[];
+}
+
+/**
+ * The neighbors of one service that a capped graph left out.
+ *
+ * @property serviceId - The expanded service
+ * @property services - Its neighbors that were not excluded, sorted by ID
+ * @property relationships - The relationships between it and those neighbors
+ */
+export interface GraphNodeExpansion {
+  serviceId: string;
+  services: Service[];
+  relationships: Relationship[];
+}
+
+/**
+ * The stored position and notes of a node in the graph view.
+ *
+ * @property pinnedPosition - Whether layouts should keep the node at its position
+ * @property note - Optional sticky-note comment
+ * @property color - Optional highlight color, as a CSS color
+ */
+export interface NodeAnnotation {
+  x: number;
+  y: number;
+  pinnedPosition: boolean;
+  note?: string | null;
+  color?: string | null;
+}
+
+/**
+ * What to collapse services into groups by: their team, their type, or the
+ * rest of their first tag starting with a prefix.
+ */
+export type GroupBy = "team" | "serviceType" | { tagPrefix: string };
+
+/**
+ * Formats an environment's whole graph can be exported in.
+ *
+ * - `json`: `{ services, relationships }`
+ * - `dot`: A Graphviz digraph
+ * - `mermaid`: A Mermaid flowchart
+ */
+export type GraphExportFormat = "json" | "dot" | "mermaid";
+
+/**
+ * Formats a Grafana node graph can be exported in.
+ *
+ * - `csv`: `nodes.csv` and `edges.csv`
+ * - `json`: One `nodegraph.json` with `{ nodes, edges }`
+ */
+export type GrafanaExportFormat = "csv" | "json";
+
+/** Formats a deployment manifest can be exported in. */
+export type ManifestFormat = "yaml" | "json";
+
+/** How an edge's line is drawn. */
+export type LineStyle = "solid" | "dashed" | "dotted";
+
+/**
+ * How the nodes of a service type or status are drawn.
+ *
+ * @property color - A CSS color, e.g. `#3182CE`
+ * @property icon - Optional icon name
+ */
+export interface NodeStyle {
+  color: string;
+  icon?: string;
+}
+
+/**
+ * How the edges of a relationship type are drawn.
+ *
+ * @property color - A CSS color, e.g. `#E53E3E`
+ * @property lineStyle - Solid, dashed or dotted
+ */
+export interface EdgeStyle {
+  color: string;
+  lineStyle: LineStyle;
+}
+
+/**
+ * The palette the graph view and the exports draw with, keyed by the type
+ * or status (e.g. `database`, `depends_on`). Types it doesn't list use the
+ * built-in colors.
+ */
+export interface DisplayConfig {
+  serviceTypes: Record<string, NodeStyle>;
+  statuses: Record<string, NodeStyle>;
+  relationshipTypes: Record<string, EdgeStyle>;
+}
+
+/**
+ * A group of services shown as one node.
+ *
+ * @property id - `group:{name}`, or `ungrouped` for services without the attribute
+ * @property status - The worst status among the members
+ */
+export interface GroupNode {
+  id: string;
+  name: string;
+  status: ServiceStatus;
+  memberCount: number;
+  memberIds: string[];
+}
+
+/**
+ * The relationships between members of two groups, collapsed into one edge.
+ *
+ * @property count - Number of relationships collapsed into this edge
+ * @property relationshipTypes - The types among them
+ */
+export interface GroupRelationship {
+  id: string;
+  source: string;
+  target: string;
+  count: number;
+  relationshipTypes: RelationshipType[];
+}
+
+/**
+ * Response data from the backend grouped graph query, shaped like GraphData.
+ *
+ * @property intraGroupRelationshipCount - Relationships within a group, which are dropped
+ */
+export interface GroupedGraphData {
+  groups: GroupNode[];
+  relationships: GroupRelationship[];
+  intraGroupRelationshipCount: number;
+}
+
+/**
+ * Which way a graph traversal follows relationships: both ways, from source
+ * to target (towards dependencies), or from target to source.
+ */
+export type TraversalDirection = "both" | "downstream" | "upstream";
+
+/**
+ * The defaults getServiceGraph uses for the parameters a call omits. They
+ * apply to every environment.
+ *
+ * @property depth - Maximum traversal depth (default: 1)
+ * @property direction - Which way relationships are followed (default: "both")
+ * @property excludeStatuses - Statuses of services to leave out
+ * @property excludeServiceTypes - Types of services to leave out
+ * @property aggregateEdges - Also return the aggregated edges (default: false)
+ */
+export interface GraphPreferences {
+  depth: number;
+  direction: TraversalDirection;
+  excludeStatuses: ServiceStatus[];
+  excludeServiceTypes: ServiceType[];
+  aggregateEdges: boolean;
+}
+
+/**
+ * The services a saved view starts from: the members of a group, or the
+ * services of a team or with a tag.
+ */
+export type ViewSelector =
+  | { group: string }
+  | { team: string }
+  | { tag: string };
+
+/**
+ * The stored parameters of a graph view.
+ *
+ * @property name - Unique name of the view
+ * @property environment - The environment the view is of
+ * @property centerServiceId - The service the graph is centered on
+ * @property selector - Services to center the graph on as well
+ * @property depth - Maximum traversal depth (default: 1)
+ * @property relationshipTypes - Relationship types to follow; all if absent
+ * @property direction - Which way relationships are followed (default: "both")
+ * @property groupBy - How the graph is collapsed into groups, if it is
+ * @property nodePositions - Node positions by service ID, stored as sent
+ */
+export interface SavedView {
+  name: string;
+  environment: string;
+  centerServiceId?: string;
+  selector?: ViewSelector;
+  depth?: number;
+  relationshipTypes?: RelationshipType[];
+  direction?: TraversalDirection;
+  groupBy?: GroupBy;
+  nodePositions?: Record<string, unknown>;
+}
+
+/**
+ * A saved view with its graph rebuilt from the current data.
+ *
+ * @property graph - The resolved graph, or null if none of its center services exist
+ * @property missingServiceIds - Services the view mentions that no longer exist
+ * @property missingGroupId - The selector's group, if it no longer exists
+ */
+export interface AppliedView {
+  view: SavedView;
+  graph: GraphData | null;
+  missingServiceIds: string[];
+  missingGroupId: string | null;
 }
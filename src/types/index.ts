@@ -13,3 +13,5 @@ export * from "./service";
 export * from "./relationship";
 export * from "./graph";
 export * from "./environment";
+export * from "./error";
+export * from "./settings";
@@ -31,6 +31,21 @@ export type RelationshipType =
   | "subscribes"
   | string;
 
+/**
+ * Where a relationship came from: created by hand (`"manual"`, the default)
+ * or by an importer (`{ imported: "kubernetes" }`).
+ *
+ * Re-running an import only changes the relationships imported from its
+ * own source.
+ */
+export type RelationshipOrigin = "manual" | { imported: string };
+
+/**
+ * Where a relationship stands in the architecture review: not reviewed yet
+ * (`"unreviewed"`, the default), signed off, or questioned.
+ */
+export type ReviewStatus = "unreviewed" | "approved" | "flagged";
+
 /**
  * Represents a directed relationship between two services.
  *
@@ -43,6 +58,14 @@ export type RelationshipType =
  * @property relationshipType - Type of relationship/dependency
  * @property description - Optional description of the relationship
  * @property metadata - Optional key-value pairs for custom properties
+ * @property effectiveFrom - Optional first day in effect (`YYYY-MM-DD`)
+ * @property effectiveUntil - Optional last day in effect (`YYYY-MM-DD`)
+ * @property origin - Created by hand or imported (default: manual)
+ * @property reviewStatus - Architecture review status (default: unreviewed)
+ * @property reviewNote - Optional note of the last review
+ * @property reviewedBy - Optional reviewer of the last review
+ * @property reviewedAt - Time of the last review (milliseconds since the epoch)
+ * @property revision - Save counter used to detect concurrent edits (0 for new relationships)
  *
  * @example
  * ```typescript
@@ -63,4 +86,25 @@ export interface Relationship {
   relationshipType: RelationshipType;
   description?: string;
   metadata?: Record<string, unknown>;
+  effectiveFrom?: string;
+  effectiveUntil?: string;
+  origin?: RelationshipOrigin;
+  reviewStatus?: ReviewStatus;
+  reviewNote?: string;
+  reviewedBy?: string;
+  reviewedAt?: number;
+  revision?: number;
+}
+
+/**
+ * Which relationships a query counts by their effective dates.
+ *
+ * @property asOf - Date (`YYYY-MM-DD`) relationships must be in effect on
+ *   (default: today)
+ * @property ignoreEffectiveDates - Count every relationship whatever its
+ *   effective dates
+ */
+export interface EffectiveDates {
+  asOf?: string;
+  ignoreEffectiveDates?: boolean;
 }
@@ -50,6 +50,20 @@ export type ServiceStatus =
   | "unknown"
   | "deprecated";
 
+/**
+ * Lifecycle stages of a service, independent of its health.
+ *
+ * - `planned`: Not built or not in production yet
+ * - `active`: In use
+ * - `deprecated`: Being replaced; nothing new should depend on it
+ * - `decommissioned`: Shut down; nothing should depend on it any more
+ */
+export type ServiceLifecycle =
+  | "planned"
+  | "active"
+  | "deprecated"
+  | "decommissioned";
+
 /**
  * Represents a service/microservice in the dependency graph.
  *
@@ -64,8 +78,13 @@ export type ServiceStatus =
  * @property version - Optional version string (e.g., "1.0.0")
  * @property owner - Optional individual owner identifier
  * @property team - Optional team responsible for the service
+ * @property tier - Optional criticality tier, from 1 (critical) to 4 (best-effort)
+ * @property lifecycle - Optional lifecycle stage
+ * @property decommissionDate - Optional planned decommission date (`YYYY-MM-DD`)
+ * @property successorId - Optional ID of the service replacing this one
  * @property tags - Array of searchable tags for categorization
  * @property metadata - Key-value pairs for custom properties
+ * @property revision - Save counter used to detect concurrent edits (0 for new services)
  *
  * @example
  * ```typescript
@@ -78,6 +97,7 @@ export type ServiceStatus =
  *   version: "2.1.0",
  *   owner: "john.doe",
  *   team: "Platform",
+ *   tier: 1,
  *   tags: ["auth", "critical", "public-facing"],
  *   metadata: { port: 8080, replicas: 3 }
  * };
@@ -92,6 +112,81 @@ export interface Service {
   version?: string;
   owner?: string;
   team?: string;
+  tier?: number;
+  lifecycle?: ServiceLifecycle;
+  decommissionDate?: string;
+  successorId?: string;
   tags: string[];
   metadata: Record<string, unknown>;
+  revision?: number;
+}
+
+/**
+ * An explicit group (domain) of services, such as "payments" or "identity".
+ * A service can belong to any number of groups.
+ *
+ * @property id - Unique identifier within the environment
+ * @property name - Human-readable display name
+ * @property description - Optional description of what the group covers
+ * @property owner - Optional owner email or identifier
+ * @property memberIds - IDs of the services in the group
+ */
+export interface Group {
+  id: string;
+  name: string;
+  description?: string;
+  owner?: string;
+  memberIds: string[];
+}
+
+/**
+ * A lightweight view of a service for lists such as the quick-switcher.
+ *
+ * @property tagCount - Number of tags
+ * @property hasDescription - Whether the service has a non-empty description
+ * @property incomingCount - Relationships targeting the service; only set by
+ *   `get_service_summaries`
+ * @property outgoingCount - Relationships from the service; only set by
+ *   `get_service_summaries`
+ */
+export interface ServiceSummary {
+  id: string;
+  name: string;
+  serviceType: ServiceType;
+  status: ServiceStatus;
+  team: string | null;
+  tier: number | null;
+  tagCount: number;
+  hasDescription: boolean;
+  incomingCount?: number;
+  outgoingCount?: number;
+}
+
+/**
+ * A service the user recently viewed.
+ *
+ * @property serviceId - The service that was viewed
+ * @property viewedAt - When it was last viewed, in milliseconds since the Unix epoch
+ */
+export interface RecentService {
+  serviceId: string;
+  viewedAt: number;
+}
+
+/** Which registry a custom type belongs to. */
+export type CustomTypeKind = "service" | "relationship";
+
+/**
+ * A registered custom service or relationship type.
+ *
+ * @property name - The type as services or relationships store it
+ * @property label - Human-readable display name
+ * @property description - Optional description of what the type is for
+ * @property color - Optional CSS color to draw the type with
+ */
+export interface CustomTypeDefinition {
+  name: string;
+  label: string;
+  description?: string;
+  color?: string;
 }
@@ -0,0 +1,166 @@
+/**
+ * @fileoverview Type definitions for the application settings.
+ *
+ * Mirrors the backend settings model. Every field has a default on the
+ * backend, so settings written by older versions of the app load without
+ * error.
+ *
+ * @module types/settings
+ */
+
+import type { DisplayConfig, GraphPreferences } from "./graph";
+
+/**
+ * Settings of the embedded read-only HTTP API. The server only listens on
+ * localhost and requests must send `Authorization: Bearer {token}`.
+ *
+ * @property enabled - Whether the server runs
+ * @property port - The localhost port to listen on (default: 7878)
+ * @property token - The bearer token; the server won't start without one
+ */
+export interface HttpApiSettings {
+  enabled: boolean;
+  port: number;
+  token: string;
+}
+
+/**
+ * Events a webhook can subscribe to. `test` is only sent by `testWebhook`,
+ * whatever the subscriptions.
+ */
+export type WebhookEvent =
+  | "dataChanged"
+  | "validationErrorsIncreased"
+  | "serviceDeleted"
+  | "test";
+
+/**
+ * Where and when to send webhook notifications.
+ *
+ * @property url - The http(s) URL notifications are POSTed to
+ * @property secret - Key of the HMAC-SHA256 `X-Webhook-Signature` header;
+ *   requests are unsigned without one
+ * @property events - The events to send
+ */
+export interface WebhookConfig {
+  url: string;
+  secret?: string | null;
+  events: WebhookEvent[];
+}
+
+/**
+ * When scheduled exports run: every `minutes` minutes, or once a day at
+ * `time` (`"HH:MM"`, UTC).
+ */
+export type ExportSchedule =
+  | { type: "interval"; minutes: number }
+  | { type: "daily"; time: string };
+
+/**
+ * Where scheduled exports go: an environment snapshot, or a snapshot bundle
+ * file in a directory.
+ */
+export type ExportTarget =
+  | { type: "snapshot" }
+  | { type: "directory"; path: string };
+
+/**
+ * Settings of the scheduled exports.
+ *
+ * @property enabled - Whether scheduled exports run
+ * @property schedule - When they run
+ * @property environments - The environments to export on each run
+ * @property target - Where the exports go
+ * @property retention - How many scheduled outputs to keep per environment
+ */
+export interface ScheduledExportSettings {
+  enabled: boolean;
+  schedule: ExportSchedule;
+  environments: string[];
+  target: ExportTarget;
+  retention: number;
+}
+
+/**
+ * What happens to a redacted value: replaced with `"***"`, or left out.
+ */
+export type RedactionAction = "mask" | "strip";
+
+/**
+ * Service fields that can be redacted.
+ */
+export type RedactedField = "description" | "version" | "owner" | "team";
+
+/**
+ * Which values exports made with `redact` hide.
+ *
+ * @property metadataKeys - Metadata keys to redact, by a pattern matching the
+ *   whole key, ignoring case; the first matching pattern decides
+ * @property serviceFields - Service fields to redact
+ */
+export interface RedactionConfig {
+  metadataKeys: { pattern: string; action?: RedactionAction }[];
+  serviceFields: { field: RedactedField; action?: RedactionAction }[];
+}
+
+/**
+ * Settings of generated relationship descriptions.
+ *
+ * @property autoGenerate - Describe relationships saved without a description
+ * @property template - What descriptions are rendered from, with placeholders
+ *   like `{sourceName}`
+ */
+export interface RelationshipDescriptionSettings {
+  autoGenerate: boolean;
+  template: string;
+}
+
+/**
+ * Settings of the health probes.
+ *
+ * @property urlKey - The metadata key holding a service's health endpoint URL
+ * @property timeoutMs - How long a probe may take before the service counts
+ *   as unhealthy
+ * @property concurrency - How many services are probed at the same time
+ */
+export interface HealthProbeSettings {
+  urlKey: string;
+  timeoutMs: number;
+  concurrency: number;
+}
+
+/**
+ * User-configurable application settings.
+ *
+ * @property gitAutoCommit - Commit every data change to the data directory's
+ *   git repository
+ * @property pinnedServices - Pinned service IDs by data path and environment
+ * @property httpApi - The embedded read-only HTTP API
+ * @property webhook - Where to send notifications about data changes, if anywhere
+ * @property scheduledExport - Periodic snapshots or exports while the app runs
+ * @property workspaces - Named data paths to switch between
+ * @property activeWorkspace - The workspace opened at startup, if any
+ * @property recentDataPaths - Data paths set via `setDataPath`, most recent first
+ * @property display - Colors, icons and line styles of the graph and exports
+ * @property readOnly - Reject every command that changes data
+ * @property redaction - Values exports made with `redact` hide
+ * @property relationshipDescriptions - Descriptions generated for relationships
+ * @property healthProbe - How health endpoints are found and called
+ * @property graphPreferences - What getServiceGraph does when a parameter is omitted
+ */
+export interface Settings {
+  gitAutoCommit: boolean;
+  pinnedServices: Record<string, Record<string, string[]>>;
+  httpApi: HttpApiSettings;
+  webhook: WebhookConfig | null;
+  scheduledExport: ScheduledExportSettings;
+  workspaces: { name: string; path: string }[];
+  activeWorkspace: string | null;
+  recentDataPaths: string[];
+  display: DisplayConfig;
+  readOnly: boolean;
+  redaction: RedactionConfig;
+  relationshipDescriptions: RelationshipDescriptionSettings;
+  healthProbe: HealthProbeSettings;
+  graphPreferences: GraphPreferences;
+}
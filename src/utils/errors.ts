@@ -0,0 +1,40 @@
+import type { AppErrorCode, AppErrorPayload } from "@/types";
+
+/**
+ * Returns true if a rejection is a backend error, optionally with a code.
+ *
+ * @param error - The value a command rejected with
+ * @param code - The code to check for, if any
+ */
+export function isAppError(
+  error: unknown,
+  code?: AppErrorCode
+): error is AppErrorPayload {
+  if (typeof error !== "object" || error === null) {
+    return false;
+  }
+  const payload = error as Partial<AppErrorPayload>;
+  return (
+    typeof payload.code === "string" &&
+    typeof payload.message === "string" &&
+    (code === undefined || payload.code === code)
+  );
+}
+
+/**
+ * Returns the message to display for a rejection.
+ *
+ * Handles backend errors as well as plain strings and `Error`s, which
+ * Tauri itself rejects with (e.g. for invalid command arguments).
+ *
+ * @param error - The value a command rejected with
+ */
+export function errorMessage(error: unknown): string {
+  if (isAppError(error)) {
+    return error.message;
+  }
+  if (error instanceof Error) {
+    return error.message;
+  }
+  return String(error);
+}